@@ -0,0 +1,28 @@
+"""The `conformance` adapter for pycryptodome.
+
+Usage: pycryptodome_hex.py ALGORITHM OUTPUT_LEN, with the message as hex
+on stdin; prints the output as hex. Exits 3 for unknown algorithms.
+"""
+
+import sys
+
+from Crypto.Hash import SHAKE128, SHAKE256, keccak
+
+
+def main(argv):
+    algorithm, output_len = argv[1], int(argv[2])
+    message = bytes.fromhex(sys.stdin.read().strip())
+    if algorithm == "keccak256":
+        out = keccak.new(digest_bits=256, data=message).digest()
+    elif algorithm == "shake128":
+        out = SHAKE128.new(message).read(output_len)
+    elif algorithm == "shake256":
+        out = SHAKE256.new(message).read(output_len)
+    else:
+        return 3
+    print(out.hex())
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main(sys.argv))
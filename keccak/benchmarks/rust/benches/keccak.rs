@@ -0,0 +1,387 @@
+//! Criterion microbenchmarks for Rust-vs-Rust regression tracking.
+//!
+//! Save a baseline with `cargo bench --bench keccak -- --save-baseline main`
+//! and compare a later build against it with `-- --baseline main`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use keccak256_rust_baseline::merkle::{verify_proof, Accumulator};
+use keccak256_rust_baseline::multiscalar::{pippenger, straus};
+use keccak256_rust_baseline::schnorr::{verify_schnorr, verify_schnorr_batch, SchnorrInput};
+use keccak256_rust_baseline::secp256k1::{AffinePoint, Scalar, G};
+use keccak256_rust_baseline::{keccak256, keccak256_batch, native, parallel, to_hex_string};
+use tiny_keccak::{Hasher, Keccak};
+
+/// Message sizes around the 136-byte Keccak-256 rate plus a few larger inputs.
+const SIZES: &[usize] = &[0, 32, 110, 135, 136, 137, 512, 4096, 65536];
+
+/// Chunk sizes fed to the streaming hasher.
+const CHUNK_SIZES: &[usize] = &[1, 32, 136, 1024];
+
+const STREAMING_LENGTH: usize = 16 * 1024;
+
+/// Messages per batch in the multi-message groups.
+const BATCH: usize = 64;
+
+/// One of the batch entry points, all with `keccak256_batch`'s shape.
+type BatchFn = fn(&[Vec<u8>], &mut [[u8; 32]]);
+
+/// One `native::keccak256` call per message.
+fn scalar_batch(messages: &[Vec<u8>], digests: &mut [[u8; 32]]) {
+    for (message, digest) in messages.iter().zip(digests) {
+        *digest = native::keccak256(message);
+    }
+}
+
+fn message(length: usize) -> Vec<u8> {
+    (0..length).map(|offset| (offset % 256) as u8).collect()
+}
+
+fn bench_one_shot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keccak256/one_shot");
+    for &size in SIZES {
+        let input = message(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| keccak256(black_box(input)))
+        });
+    }
+    group.finish();
+}
+
+/// Ethereum-sized messages through `native::keccak256`, whose one-block
+/// path pads straight into the state, and through the streaming hasher.
+fn bench_small(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keccak256/small");
+    for &size in &[0, 32, 64, 110, 135] {
+        let input = message(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("one-shot", size), &input, |b, input| {
+            b.iter(|| native::keccak256(black_box(input)))
+        });
+        group.bench_with_input(BenchmarkId::new("hasher", size), &input, |b, input| {
+            b.iter(|| {
+                let mut hasher = native::Keccak256::new();
+                hasher.update(black_box(input));
+                hasher.finalize()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_streaming(c: &mut Criterion) {
+    let input = message(STREAMING_LENGTH);
+    let mut group = c.benchmark_group("keccak256/streaming");
+    group.throughput(Throughput::Bytes(STREAMING_LENGTH as u64));
+    for &chunk in CHUNK_SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(chunk), &chunk, |b, &chunk| {
+            b.iter(|| {
+                let mut hasher = Keccak::v256();
+                for piece in black_box(&input).chunks(chunk) {
+                    hasher.update(piece);
+                }
+                let mut output = [0u8; 32];
+                hasher.finalize(&mut output);
+                output
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Batches through the scalar, two-, four- and eight-way paths, so the
+/// per-message gain of each wider build reads straight off the report, and
+/// through `keccak256_batch` with its grouping and dispatch.
+fn bench_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keccak256/batch");
+    for &size in &[32, 136, 512] {
+        let messages = vec![message(size); BATCH];
+        let mut digests = vec![[0u8; 32]; BATCH];
+        group.throughput(Throughput::Bytes((size * BATCH) as u64));
+        let paths: [(&str, BatchFn); 5] = [
+            ("scalar", scalar_batch),
+            ("x2", parallel::keccak256_batch_x2),
+            ("x4", parallel::keccak256_batch),
+            ("x8", parallel::keccak256_batch_x8),
+            ("dispatched", keccak256_batch),
+        ];
+        for (name, batch) in paths {
+            group.bench_function(BenchmarkId::new(name, size), |b| {
+                b.iter(|| batch(black_box(&messages), &mut digests))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Scalar hashing timed right after untimed work on each path. On CPUs
+/// that lower their clock for 512-bit instructions the `after-x8` case is
+/// slower than `after-scalar` by the downclocking penalty; the two match
+/// where there is none. Skipped without AVX-512.
+fn bench_after_avx512(c: &mut Criterion) {
+    if !parallel::avx512_supported() {
+        return;
+    }
+    let messages = vec![message(136); BATCH];
+    let mut digests = vec![[0u8; 32]; BATCH];
+    let mut group = c.benchmark_group("keccak256/scalar_after");
+    group.throughput(Throughput::Bytes((136 * BATCH) as u64));
+    let paths: [(&str, BatchFn); 2] = [
+        ("after-scalar", scalar_batch),
+        ("after-x8", parallel::keccak256_batch_x8),
+    ];
+    for (name, warm) in paths {
+        group.bench_function(name, |b| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    warm(&messages, &mut digests);
+                    let start = Instant::now();
+                    for message in &messages {
+                        black_box(native::keccak256(black_box(message)));
+                    }
+                    total += start.elapsed();
+                }
+                total
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Small messages through a new sponge per message versus one hasher that
+/// is reset between them, and the thread-local hasher.
+fn bench_reuse(c: &mut Criterion) {
+    let input = message(32);
+    let mut group = c.benchmark_group("keccak256/reuse");
+    group.throughput(Throughput::Bytes(32));
+    group.bench_function("fresh", |b| {
+        b.iter(|| {
+            let mut hasher = native::Keccak256::new();
+            hasher.update(black_box(&input));
+            hasher.finalize()
+        })
+    });
+    let mut hasher = native::Keccak256::new();
+    group.bench_function("reset", |b| {
+        b.iter(|| {
+            hasher.update(black_box(&input));
+            hasher.finalize_reset()
+        })
+    });
+    group.bench_function("thread-local", |b| {
+        b.iter(|| {
+            native::with_thread_hasher(|hasher| {
+                hasher.update(black_box(&input));
+                hasher.finalize_reset()
+            })
+        })
+    });
+    group.finish();
+}
+
+/// Four 1 KiB streams fed 32 bytes at a time, round robin, through one
+/// lockstep `MultiHasher` and through four separate hashers.
+fn bench_multi(c: &mut Criterion) {
+    let input = message(1024);
+    let mut group = c.benchmark_group("keccak256/multi");
+    group.throughput(Throughput::Bytes(4 * 1024));
+    group.bench_function("separate", |b| {
+        b.iter(|| {
+            let mut hashers: [native::Keccak256; 4] = Default::default();
+            for piece in input.chunks(32) {
+                for hasher in &mut hashers {
+                    hasher.update(black_box(piece));
+                }
+            }
+            hashers.map(native::Keccak256::finalize)
+        })
+    });
+    group.bench_function("lockstep", |b| {
+        b.iter(|| {
+            let mut hasher = parallel::MultiHasher::<4>::new();
+            for piece in input.chunks(32) {
+                for stream in 0..4 {
+                    hasher.update(stream, black_box(piece));
+                }
+            }
+            hasher.finalize()
+        })
+    });
+    group.finish();
+}
+
+/// The unrolled permutation against the round loop it replaced and the
+/// 32-bit interleaved form, alone and inside a one-block native hash, with
+/// the AVX2 build where there is one.
+fn bench_permutation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keccak_f1600");
+    let mut state = [0x5555_aaaa_5555_aaaau64; 25];
+    group.bench_function("compact", |b| {
+        b.iter(|| native::keccak_f1600_compact(black_box(&mut state)))
+    });
+    group.bench_function("unrolled", |b| {
+        b.iter(|| native::keccak_f1600(black_box(&mut state)))
+    });
+    group.bench_function("interleaved", |b| {
+        b.iter(|| native::keccak_f1600_interleaved(black_box(&mut state)))
+    });
+    group.finish();
+
+    let input = message(100);
+    let mut group = c.benchmark_group("keccak256/permutation");
+    group.throughput(Throughput::Bytes(100));
+    let permutations = [
+        ("compact", native::Permutation::Compact),
+        ("unrolled", native::Permutation::Generic),
+        ("unrolled-avx2", native::Permutation::Avx2),
+        ("interleaved", native::Permutation::Interleaved),
+        ("asm", native::Permutation::Asm),
+    ];
+    for (name, permutation) in permutations {
+        if permutation.is_supported() {
+            group.bench_function(name, |b| {
+                b.iter(|| native::keccak256_with(permutation, black_box(&input)))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// A deposit-depth accumulator: 1024 insertions from empty, whose hashes
+/// each depend on the last, then the 32-hash root, a proof of a leaf on
+/// the partly filled right edge, and its check.
+fn bench_accumulator(c: &mut Criterion) {
+    let leaves: Vec<[u8; 32]> = (0..1024u32).map(|i| keccak256(&i.to_be_bytes())).collect();
+    let mut accumulator = Accumulator::new(Accumulator::DEPOSIT_DEPTH);
+    for leaf in &leaves[..1000] {
+        accumulator.insert(*leaf).unwrap();
+    }
+    let root = accumulator.root();
+    let proof = accumulator.proof(999).unwrap();
+
+    let mut group = c.benchmark_group("merkle/accumulator");
+    group.throughput(Throughput::Elements(leaves.len() as u64));
+    group.bench_function("insert", |b| {
+        b.iter(|| {
+            let mut accumulator = Accumulator::new(Accumulator::DEPOSIT_DEPTH);
+            for leaf in &leaves {
+                accumulator.insert(black_box(*leaf)).unwrap();
+            }
+            accumulator
+        })
+    });
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("root", |b| b.iter(|| black_box(&accumulator).root()));
+    group.bench_function("proof", |b| {
+        b.iter(|| black_box(&accumulator).proof(black_box(999)))
+    });
+    group.bench_function("verify", |b| {
+        b.iter(|| verify_proof(&root, black_box(&leaves[999]), 999, &proof))
+    });
+    group.finish();
+}
+
+fn bench_multiscalar(c: &mut Criterion) {
+    let scalar = |i: u32| Scalar::reduce(&keccak256(&i.to_be_bytes()));
+    let scalars: Vec<Scalar> = (0..256).map(scalar).collect();
+    let points: Vec<AffinePoint> = (256..512).map(|i| G.mul(&scalar(i)).unwrap()).collect();
+
+    let mut group = c.benchmark_group("secp256k1/multiscalar");
+    group.sample_size(10);
+    for n in [16, 256] {
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("straus", n), &n, |b, &n| {
+            b.iter(|| straus(black_box(&scalars[..n]), &points[..n]))
+        });
+        group.bench_with_input(BenchmarkId::new("pippenger", n), &n, |b, &n| {
+            b.iter(|| pippenger(black_box(&scalars[..n]), &points[..n]))
+        });
+    }
+    group.finish();
+}
+
+/// BIP-340 test vectors 1 and 2 as key, message and signature, which the
+/// Schnorr group cycles through.
+const SCHNORR_VECTORS: [(&str, &str, &str); 2] = [
+    (
+        "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+        "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+        "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de3341\
+         8906d11ac976abccb20b091292bff4ea897efcb639ea871cfa95f6de339e4b0a",
+    ),
+    (
+        "dd308afec5777e13121fa72b9cc1b7cc0139715309b086c960e18fd969774eb8",
+        "7e2d58d8b3bcdf1abadec7829054f90dda9805aab56c77333024b9d0a508b75c",
+        "5831aaeed7b44bb74e5eab94ba9d4294c49bcf2a60728d8b4c200f50dd313c1b\
+         ab745879a5ad954a72c45a91c3a51d3c7adea98d82f8481e0e1e03674a6f3fb7",
+    ),
+];
+
+/// 16 signatures checked one by one and as one batch; with elements as
+/// the throughput, criterion reports signatures per second for each.
+fn bench_schnorr(c: &mut Criterion) {
+    type Vector = ([u8; 32], Vec<u8>, [u8; 64]);
+    let vectors: Vec<Vector> = SCHNORR_VECTORS
+        .iter()
+        .cycle()
+        .take(16)
+        .map(|(key, message, signature)| {
+            (
+                hex::decode(key).unwrap().try_into().unwrap(),
+                hex::decode(message).unwrap(),
+                hex::decode(signature).unwrap().try_into().unwrap(),
+            )
+        })
+        .collect();
+    let inputs: Vec<SchnorrInput> = vectors
+        .iter()
+        .map(|(public_key, message, signature)| SchnorrInput {
+            public_key,
+            message,
+            signature,
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("secp256k1/schnorr");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(inputs.len() as u64));
+    group.bench_function("individual/16", |b| {
+        b.iter(|| {
+            black_box(&inputs)
+                .iter()
+                .all(|input| verify_schnorr(input.public_key, input.message, input.signature))
+        })
+    });
+    group.bench_function("batch/16", |b| {
+        b.iter(|| verify_schnorr_batch(black_box(&inputs)))
+    });
+    group.finish();
+}
+
+fn bench_hex(c: &mut Criterion) {
+    let digest = keccak256(b"abc");
+    c.bench_function("to_hex_string/32", |b| {
+        b.iter(|| to_hex_string(black_box(&digest)))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_one_shot,
+    bench_small,
+    bench_streaming,
+    bench_batch,
+    bench_after_avx512,
+    bench_reuse,
+    bench_multi,
+    bench_permutation,
+    bench_accumulator,
+    bench_multiscalar,
+    bench_schnorr,
+    bench_hex
+);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+//! Record build metadata for the bench binary's result output, regenerate
+//! the C header from the FFI exports under `--features cbindgen`, and set
+//! up linking for the `node` addon.
+
+use std::env;
+#[cfg(feature = "cbindgen")]
+use std::path::Path;
+use std::process::Command;
+
+/// Write `$OUT_DIR/keccak256_rust.h` from `src/ffi.rs`, for the test that
+/// compares it with the checked-in `include/keccak256_rust.h`. The source
+/// tree is never written.
+#[cfg(feature = "cbindgen")]
+fn generate_header(crate_dir: &Path, out_dir: &Path) {
+    let config = match cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")) {
+        Ok(config) => config,
+        Err(err) => {
+            println!("cargo:warning=C header not regenerated: {}", err);
+            return;
+        }
+    };
+    match cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/ffi.rs"))
+        .generate()
+    {
+        Ok(bindings) => {
+            bindings.write_to_file(out_dir.join("keccak256_rust.h"));
+        }
+        Err(err) => println!("cargo:warning=C header not regenerated: {}", err),
+    }
+}
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|text| text.trim().to_string())
+        .unwrap_or_else(|| String::from("unknown"));
+    println!("cargo:rustc-env=BENCH_RUSTC_VERSION={}", version);
+    println!(
+        "cargo:rustc-env=BENCH_BUILD_PROFILE={}",
+        env::var("PROFILE").unwrap_or_else(|_| String::from("unknown"))
+    );
+    #[cfg(feature = "cbindgen")]
+    {
+        let crate_dir = env::var("CARGO_MANIFEST_DIR").expect("cargo sets CARGO_MANIFEST_DIR");
+        let out_dir = env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+        generate_header(Path::new(&crate_dir), Path::new(&out_dir));
+    }
+    // Node addons resolve N-API symbols from the host process.
+    #[cfg(feature = "node")]
+    napi_build::setup();
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+}
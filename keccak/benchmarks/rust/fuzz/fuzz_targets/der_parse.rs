@@ -0,0 +1,23 @@
+//! Signature DER parsing: whatever strict parsing accepts, lenient parsing
+//! accepts as the same signature and re-encoding reproduces byte for byte,
+//! and whatever lenient parsing accepts re-encodes to strict DER.
+
+#![no_main]
+
+use keccak256_rust_baseline::signature::{Signature, Strictness};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let strict = Signature::from_der(data, Strictness::Consensus);
+    let lenient = Signature::from_der(data, Strictness::Lenient);
+    if let Ok(signature) = strict {
+        assert_eq!(lenient, Ok(signature));
+        assert_eq!(signature.to_der(), data);
+    }
+    if let Ok(signature) = lenient {
+        assert_eq!(
+            Signature::from_der(&signature.to_der(), Strictness::Consensus),
+            Ok(signature)
+        );
+    }
+});
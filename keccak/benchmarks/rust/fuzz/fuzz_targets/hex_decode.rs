@@ -0,0 +1,23 @@
+//! Hex decoding accepts exactly the even-length strings of hex digits,
+//! and whatever it accepts renders back as the same string, lowercased.
+
+#![no_main]
+
+use keccak256_rust_baseline::to_hex_string;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let valid = data.len().is_multiple_of(2) && data.iter().all(u8::is_ascii_hexdigit);
+    match hex::decode(data) {
+        Ok(bytes) => {
+            assert!(valid);
+            assert_eq!(bytes.len(), data.len() / 2);
+            assert_eq!(
+                to_hex_string(&bytes).as_bytes(),
+                data.to_ascii_lowercase().as_slice()
+            );
+        }
+        Err(_) => assert!(!valid),
+    }
+    assert_eq!(hex::decode(to_hex_string(data)).unwrap(), data);
+});
@@ -0,0 +1,18 @@
+//! The known-answer file parser accepts any text without panicking, and
+//! every record it returns points at a real line holding one of its
+//! fields.
+
+#![no_main]
+
+use keccak256_rust_baseline::kat;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let lines: Vec<&str> = text.lines().collect();
+    for record in kat::parse(text) {
+        assert!(!record.fields.is_empty());
+        let line = lines[record.line - 1];
+        let (key, _) = line.split_once('=').expect("a record starts at a field");
+        assert!(record.fields.contains_key(key.trim()));
+    }
+});
@@ -0,0 +1,40 @@
+//! Streaming equals one-shot: however a message is split across
+//! `update` calls, the native Keccak-256 and SHAKE hashers agree with
+//! their own one-shot digest and with tiny-keccak.
+
+#![no_main]
+
+use keccak256_rust_baseline::native::{Keccak256, Shake};
+use libfuzzer_sys::fuzz_target;
+use tiny_keccak::{Hasher, Shake as TinyShake};
+
+fuzz_target!(|input: (Vec<u8>, Vec<u16>)| {
+    let (data, cuts) = input;
+    let mut points: Vec<usize> = cuts
+        .iter()
+        .map(|&cut| cut as usize % (data.len() + 1))
+        .collect();
+    points.extend([0, data.len()]);
+    points.sort_unstable();
+    let pieces: Vec<&[u8]> = points.windows(2).map(|w| &data[w[0]..w[1]]).collect();
+
+    let mut hasher = Keccak256::new();
+    for piece in &pieces {
+        hasher.update(piece);
+    }
+    let digest = hasher.finalize();
+    assert_eq!(digest, keccak256_rust_baseline::native::keccak256(&data));
+    assert_eq!(digest, keccak256_rust_baseline::keccak256(&data));
+
+    let mut shake = Shake::shake128();
+    for piece in &pieces {
+        shake.update(piece);
+    }
+    let mut streamed = [0u8; 400];
+    shake.finalize_xof().squeeze(&mut streamed);
+    let mut reference = TinyShake::v128();
+    reference.update(&data);
+    let mut expected = [0u8; 400];
+    reference.finalize(&mut expected);
+    assert_eq!(streamed, expected);
+});
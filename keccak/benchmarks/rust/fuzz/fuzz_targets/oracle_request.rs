@@ -0,0 +1,34 @@
+//! The oracle's request decoder never panics on a hostile stream, any
+//! request it decodes re-encodes to exactly the bytes it consumed, and
+//! answers have the promised length.
+
+#![no_main]
+
+use std::io::Cursor;
+
+use keccak256_rust_baseline::oracle;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut reader = Cursor::new(data);
+    let mut start = 0;
+    while let Ok(Some(request)) = oracle::read_request(&mut reader) {
+        let end = reader.position() as usize;
+        let consumed = &data[start..end];
+        start = end;
+        // Names that are not UTF-8 decode lossily and cannot round-trip.
+        let name = &consumed[1..1 + consumed[0] as usize];
+        if std::str::from_utf8(name).is_ok() {
+            let mut encoded = Vec::new();
+            oracle::write_request(&mut encoded, &request).unwrap();
+            assert_eq!(encoded, consumed);
+        }
+        if let Ok(output) = oracle::answer(&request) {
+            let expected = match request.algorithm.as_str() {
+                "keccak256" => 32,
+                _ => request.output_len as usize,
+            };
+            assert_eq!(output.len(), expected);
+        }
+    }
+});
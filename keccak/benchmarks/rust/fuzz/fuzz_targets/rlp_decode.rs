@@ -0,0 +1,41 @@
+//! Streaming RLP decoding: whatever the decoder accepts is canonical, so
+//! rebuilding it from the events and encoding it again reproduces the
+//! input byte for byte, and each list's start carries its own encoding.
+
+#![no_main]
+
+use keccak256_rust_baseline::ethereum::rlp::{Decoder, Event};
+use keccak256_rust_baseline::ethereum::Rlp;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(events) = Decoder::new(data).collect::<Result<Vec<Event>, _>>() else {
+        return;
+    };
+    let mut open: Vec<(&[u8], Vec<Rlp>)> = Vec::new();
+    let mut done = None;
+    for event in events {
+        let item = match event {
+            Event::Bytes(bytes) => Rlp::Bytes(bytes),
+            Event::ListStart { encoded } => {
+                open.push((encoded, Vec::new()));
+                continue;
+            }
+            Event::ListEnd => {
+                let (encoded, items) = open.pop().expect("balanced lists");
+                let list = Rlp::List(items);
+                let mut out = Vec::new();
+                list.encode(&mut out);
+                assert_eq!(out, encoded);
+                list
+            }
+        };
+        match open.last_mut() {
+            Some((_, items)) => items.push(item),
+            None => done = Some(item),
+        }
+    }
+    let mut out = Vec::new();
+    done.expect("one item").encode(&mut out);
+    assert_eq!(out, data);
+});
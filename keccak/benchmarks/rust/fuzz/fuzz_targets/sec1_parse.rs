@@ -0,0 +1,24 @@
+//! SEC1 point parsing: nothing panics, whatever parses is on the curve,
+//! re-encoding in the input's own form reproduces it byte for byte, and
+//! the other form parses back to the same point.
+
+#![no_main]
+
+use keccak256_rust_baseline::secp256k1::AffinePoint;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(point) = AffinePoint::from_sec1(data) else {
+        return;
+    };
+    assert!(point.is_on_curve());
+    let compressed = point.to_compressed();
+    let uncompressed = point.to_uncompressed();
+    match data.len() {
+        33 => assert_eq!(compressed[..], *data),
+        65 => assert_eq!(uncompressed[..], *data),
+        len => panic!("accepted {} bytes", len),
+    }
+    assert_eq!(AffinePoint::from_sec1(&compressed), Ok(point));
+    assert_eq!(AffinePoint::from_sec1(&uncompressed), Ok(point));
+});
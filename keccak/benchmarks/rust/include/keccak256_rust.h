@@ -0,0 +1,196 @@
+/* C ABI of the Rust Keccak-256 baseline (src/ffi.rs).
+ *
+ * Build the shared library with `cargo build --release --lib`; it lands in
+ * target/release as libkeccak256_rust_baseline.{so,dylib}. Every function
+ * returns KECCAK256_FFI_OK on success or a negative KECCAK256_FFI_* status.
+ */
+
+#ifndef KECCAK256_RUST_H
+#define KECCAK256_RUST_H
+
+/* Generated by cbindgen from src/ffi.rs (--features cbindgen); do not edit. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Success.
+ */
+#define KECCAK256_FFI_OK 0
+
+/**
+ * A pointer that must not be null was.
+ */
+#define KECCAK256_FFI_NULL_POINTER -1
+
+/**
+ * The benchmark config was rejected.
+ */
+#define KECCAK256_FFI_INVALID_CONFIG -2
+
+/**
+ * The benchmark failed or panicked while running.
+ */
+#define KECCAK256_FFI_BENCH_FAILED -3
+
+/**
+ * Bytes in [`FfiError::message`], including the terminating NUL.
+ */
+#define KECCAK256_FFI_ERROR_LEN 256
+
+/**
+ * Hash implementations a benchmark can time; the values of
+ * [`FfiBenchConfig::backend`].
+ */
+enum keccak256_backend
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint32_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  KECCAK256_BACKEND_TINY_KECCAK = 0,
+  KECCAK256_BACKEND_NATIVE = 1,
+  KECCAK256_BACKEND_NATIVE_SIMD = 2,
+  KECCAK256_BACKEND_SHA3 = 3,
+  KECCAK256_BACKEND_BLAKE3 = 4,
+  KECCAK256_BACKEND_NATIVE_X4 = 5,
+  KECCAK256_BACKEND_NATIVE_X8 = 6,
+  KECCAK256_BACKEND_NATIVE_X2 = 7,
+  KECCAK256_BACKEND_NATIVE_AUTO = 8,
+  KECCAK256_BACKEND_NATIVE_ASM = 9,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum keccak256_backend keccak256_backend;
+#else
+typedef uint32_t keccak256_backend;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+/**
+ * One message for [`keccak256_batch_ffi`]: `len` bytes at `ptr`.
+ */
+typedef struct {
+  const uint8_t *ptr;
+  size_t len;
+} keccak256_ffi_message;
+
+/**
+ * The benchmark settings a C caller can choose; see
+ * [`keccak256_bench_config_default`].
+ */
+typedef struct {
+  /**
+   * An [`FfiBackend`] value.
+   */
+  uint32_t backend;
+  size_t messages;
+  size_t rounds;
+  size_t min_len;
+  size_t max_len;
+  size_t stride;
+  size_t trials;
+  size_t threads;
+  /**
+   * Untimed rounds before the first trial, unless `adaptive_warmup`.
+   */
+  size_t warmup_rounds;
+  /**
+   * Nonzero to warm up until throughput settles instead of
+   * `warmup_rounds`. A byte rather than `bool`, since C may store any
+   * value in it.
+   */
+  uint8_t adaptive_warmup;
+  /**
+   * Nonzero for pseudorandom message bytes from `seed`, instead of the
+   * legacy counter pattern.
+   */
+  uint8_t use_seed;
+  uint64_t seed;
+} keccak256_bench_config;
+
+/**
+ * The median trial of a benchmark run.
+ */
+typedef struct {
+  double seconds;
+  double hashes_per_second;
+  double mb_per_second;
+  /**
+   * Input bytes hashed per trial.
+   */
+  uint64_t bytes;
+  uint64_t checksum;
+  /**
+   * Set when the trials varied more than the noise threshold.
+   */
+  bool noisy;
+} keccak256_bench_result;
+
+/**
+ * Why a call failed: its status and a NUL-terminated UTF-8 message,
+ * truncated to fit.
+ */
+typedef struct {
+  int32_t code;
+  char message[KECCAK256_FFI_ERROR_LEN];
+} keccak256_ffi_error;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Write the Keccak-256 digest of `len` bytes at `data` to the 32 bytes
+ * at `out`. `data` may be null when `len` is 0.
+ *
+ * # Safety
+ *
+ * `data` must be valid for reads of `len` bytes and `out` for writes of
+ * 32 bytes, and the two must not overlap.
+ */
+int32_t keccak256_ffi(const uint8_t *data, size_t len, uint8_t *out);
+
+/**
+ * Hash `count` messages in one call, so callers pay for crossing the
+ * boundary once per batch: the digest of `messages[i]` goes to the 32
+ * bytes at `out + 32 * i`. Nothing is written unless every message is
+ * valid.
+ *
+ * # Safety
+ *
+ * `messages` must be valid for reads of `count` entries, each as for
+ * [`keccak256_ffi`], and `out` valid for writes of `32 * count` bytes
+ * that overlap none of the messages.
+ */
+int32_t keccak256_batch_ffi(const keccak256_ffi_message *messages, size_t count, uint8_t *out);
+
+/**
+ * Fill `out` with the harness's default benchmark settings.
+ *
+ * # Safety
+ *
+ * `out` must be valid for writes of one [`FfiBenchConfig`].
+ */
+int32_t keccak256_bench_config_default(keccak256_bench_config *out);
+
+/**
+ * Benchmark `config` and write its median trial to `result`. On failure
+ * the status is also written to `error`, with a message, unless `error`
+ * is null; `result` is then left untouched.
+ *
+ * # Safety
+ *
+ * `config` must be valid for reads of one [`FfiBenchConfig`], `result`
+ * for writes of one [`FfiBenchResult`], and `error`, when not null, for
+ * writes of one [`FfiError`].
+ */
+int32_t keccak256_bench_ffi(const keccak256_bench_config *config,
+                            keccak256_bench_result *result,
+                            keccak256_ffi_error *error);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* KECCAK256_RUST_H */
@@ -0,0 +1,156 @@
+//! Base58 and Base58Check, the encodings of Bitcoin P2PKH addresses, WIF
+//! keys and BIP-32 `xprv`/`xpub` strings.
+//!
+//! Base58 writes a big-endian number in the 58 characters that remain
+//! after dropping `0`, `O`, `I` and `l`, with one `1` for each leading zero
+//! byte, which the number alone would lose. Base58Check appends the first
+//! four bytes of the payload's double SHA-256 before encoding, and
+//! [`decode_check`] refuses strings whose checksum does not match.
+
+use crate::sha256::sha256d;
+use crate::Error;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// `bytes` in Base58.
+pub fn encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    // Little-endian base-58 digits of the number after the leading zeros.
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &byte in &bytes[zeros..] {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut out = "1".repeat(zeros);
+    out.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&digit| char::from(ALPHABET[usize::from(digit)])),
+    );
+    out
+}
+
+/// The bytes Base58 `text` spells.
+pub fn decode(text: &str) -> Result<Vec<u8>, Error> {
+    let zeros = text.bytes().take_while(|&c| c == b'1').count();
+    // Little-endian bytes of the number after the leading ones.
+    let mut bytes: Vec<u8> = Vec::with_capacity(text.len() * 733 / 1000 + 1);
+    for (position, character) in text.chars().enumerate().skip(zeros) {
+        let mut carry = ALPHABET
+            .iter()
+            .position(|&c| char::from(c) == character)
+            .ok_or(Error::InvalidCharacter {
+                character,
+                position,
+            })? as u32;
+        for byte in bytes.iter_mut() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes.iter().rev());
+    Ok(out)
+}
+
+/// `payload` and its four checksum bytes in Base58.
+pub fn encode_check(payload: &[u8]) -> String {
+    let mut bytes = payload.to_vec();
+    bytes.extend_from_slice(&sha256d(payload)[..4]);
+    encode(&bytes)
+}
+
+/// The payload of Base58Check `text`, with its checksum verified and
+/// removed.
+pub fn decode_check(text: &str) -> Result<Vec<u8>, Error> {
+    let mut bytes = decode(text)?;
+    if bytes.len() < 4 {
+        return Err(Error::InvalidChecksum);
+    }
+    let checksum = bytes.split_off(bytes.len() - 4);
+    if sha256d(&bytes)[..4] != checksum[..] {
+        return Err(Error::InvalidChecksum);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bitcoin Core's `base58_encode_decode.json` cases encode and decode
+    /// both ways, leading zero bytes included.
+    #[test]
+    fn core_vectors_round_trip() {
+        let cases = [
+            ("", ""),
+            ("61", "2g"),
+            ("626262", "a3gV"),
+            ("636363", "aPEr"),
+            (
+                "73696d706c792061206c6f6e6720737472696e67",
+                "2cFupjhnEsSn59qHXstmK2ffpLv2",
+            ),
+            (
+                "00eb15231dfceb60925886b67d065299925915aeb172c06647",
+                "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L",
+            ),
+            ("516b6fcd0f", "ABnLTmg"),
+            ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+            ("572e4794", "3EFU7m"),
+            ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+            ("10c8511e", "Rt5zm"),
+            ("00000000000000000000", "1111111111"),
+        ];
+        for (bytes, text) in cases {
+            let bytes = hex::decode(bytes).unwrap();
+            assert_eq!(encode(&bytes), text);
+            assert_eq!(decode(text), Ok(bytes));
+        }
+        assert_eq!(
+            decode("3SEo3LWL0PntC"),
+            Err(Error::InvalidCharacter {
+                character: '0',
+                position: 8
+            })
+        );
+        assert!(decode("l").is_err() && decode("I").is_err() && decode(" ").is_err());
+    }
+
+    /// A WIF key and a P2PKH address carry their payloads, and a changed
+    /// character or a truncated string fails the checksum.
+    #[test]
+    fn checked_strings_verify() {
+        let wif = "5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ";
+        let key = hex::decode("800c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d")
+            .unwrap();
+        assert_eq!(decode_check(wif), Ok(key.clone()));
+        assert_eq!(encode_check(&key), wif);
+
+        let address = "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJED9L";
+        let payload = hex::decode("00eb15231dfceb60925886b67d065299925915aeb1").unwrap();
+        assert_eq!(decode_check(address), Ok(payload));
+        // Core's plain Base58 vector above: the same payload, wrong checksum.
+        assert_eq!(
+            decode_check("1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L"),
+            Err(Error::InvalidChecksum)
+        );
+        assert_eq!(decode_check("2g"), Err(Error::InvalidChecksum));
+        assert_eq!(decode_check(""), Err(Error::InvalidChecksum));
+        assert_eq!(decode_check(&encode_check(b"")), Ok(vec![]));
+    }
+}
@@ -0,0 +1,288 @@
+//! Bech32 (BIP-173) and Bech32m (BIP-350) strings.
+//!
+//! A string is a human-readable part (HRP), the separator `1`, then data
+//! in a 32-character alphabet, each character carrying five bits, ending
+//! in a six-character BCH checksum over the HRP and data. The two variants
+//! differ only in the constant the checksum is XORed with: segwit v0
+//! addresses use Bech32 and v1 (Taproot) and later use Bech32m. Strings
+//! are all lower or all upper case; [`encode`] writes lower case.
+//!
+//! The data here is 5-bit groups, as the checksum sees it; [`to_base32`]
+//! and [`from_base32`] regroup bytes. [`decode`] enforces BIP-173's
+//! 90-character limit, which Lightning invoices exceed, so
+//! [`decode_with_max_len`] takes the limit as an argument.
+
+use crate::Error;
+
+/// The data alphabet, indexed by 5-bit value.
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// The longest string BIP-173 allows.
+pub const MAX_LEN: usize = 90;
+
+/// Which checksum constant a string uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc8_30a3,
+        }
+    }
+}
+
+fn polymod(values: impl IntoIterator<Item = u8>) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = (checksum & 0x01ff_ffff) << 5 ^ u32::from(value);
+        for (bit, generator) in GENERATOR.iter().enumerate() {
+            if (top >> bit) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+/// The HRP as the checksum sees it: high bits of each character, a zero,
+/// then the low bits.
+fn expand_hrp(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    let bytes = hrp.bytes();
+    bytes
+        .clone()
+        .map(|c| c >> 5)
+        .chain([0])
+        .chain(bytes.map(|c| c & 31))
+}
+
+/// An HRP must be 1 to 83 characters from `!` to `~`.
+fn check_hrp(hrp: &str) -> Result<(), Error> {
+    if hrp.is_empty() || hrp.len() > 83 || !hrp.bytes().all(|c| (33..=126).contains(&c)) {
+        return Err(Error::InvalidHrp {
+            hrp: hrp.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// `hrp` and the 5-bit groups `data` with their `variant` checksum, in
+/// lower case.
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> Result<String, Error> {
+    check_hrp(hrp)?;
+    if let Some(&value) = data.iter().find(|&&value| value >= 32) {
+        return Err(Error::ValueOutOfRange {
+            value: hex::encode([value]),
+            type_name: "a 5-bit group".to_string(),
+        });
+    }
+    let hrp = hrp.to_ascii_lowercase();
+    let checksum =
+        polymod(expand_hrp(&hrp).chain(data.iter().copied()).chain([0; 6])) ^ variant.constant();
+    let mut out = hrp;
+    out.push('1');
+    out.extend(
+        data.iter()
+            .map(|&value| char::from(CHARSET[usize::from(value)])),
+    );
+    out.extend((0..6).map(|i| char::from(CHARSET[(checksum >> (5 * (5 - i))) as usize & 31])));
+    Ok(out)
+}
+
+/// The HRP (lower-cased), 5-bit data and variant of `text`, checking the
+/// checksum and the 90-character limit.
+pub fn decode(text: &str) -> Result<(String, Vec<u8>, Variant), Error> {
+    decode_with_max_len(text, MAX_LEN)
+}
+
+/// [`decode`] allowing strings up to `max_len` characters.
+pub fn decode_with_max_len(
+    text: &str,
+    max_len: usize,
+) -> Result<(String, Vec<u8>, Variant), Error> {
+    if text.len() > max_len {
+        return Err(Error::TooLong {
+            what: "a bech32 string",
+            max: max_len,
+            actual: text.len(),
+        });
+    }
+    if text.bytes().any(|c| c.is_ascii_lowercase()) && text.bytes().any(|c| c.is_ascii_uppercase())
+    {
+        return Err(Error::MixedCase);
+    }
+    let text = text.to_ascii_lowercase();
+    let separator = text.rfind('1').ok_or(Error::MissingSeparator)?;
+    let (hrp, rest) = (&text[..separator], &text[separator + 1..]);
+    check_hrp(hrp)?;
+    let data = rest
+        .chars()
+        .enumerate()
+        .map(|(index, character)| {
+            CHARSET
+                .iter()
+                .position(|&c| char::from(c) == character)
+                .map(|value| value as u8)
+                .ok_or(Error::InvalidCharacter {
+                    character,
+                    position: separator + 1 + index,
+                })
+        })
+        .collect::<Result<Vec<u8>, Error>>()?;
+    if data.len() < 6 {
+        return Err(Error::InvalidChecksum);
+    }
+    let variant = match polymod(expand_hrp(hrp).chain(data.iter().copied())) {
+        1 => Variant::Bech32,
+        0x2bc8_30a3 => Variant::Bech32m,
+        _ => return Err(Error::InvalidChecksum),
+    };
+    let payload = data[..data.len() - 6].to_vec();
+    Ok((hrp.to_string(), payload, variant))
+}
+
+/// `bytes` as 5-bit groups, the last one zero-padded.
+pub fn to_base32(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut acc, mut bits) = (0u32, 0);
+    for &byte in bytes {
+        acc = acc << 8 | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push((acc >> bits) as u8 & 31);
+        }
+    }
+    if bits > 0 {
+        out.push((acc << (5 - bits)) as u8 & 31);
+    }
+    out
+}
+
+/// The bytes 5-bit `groups` spell. Fewer than five bits may be left over,
+/// and they must be zero, as [`to_base32`] writes them.
+pub fn from_base32(groups: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(groups.len() * 5 / 8);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &group in groups {
+        if group >= 32 {
+            return Err(Error::ValueOutOfRange {
+                value: hex::encode([group]),
+                type_name: "a 5-bit group".to_string(),
+            });
+        }
+        acc = (acc << 5 | u32::from(group)) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+        return Err(Error::InvalidPadding);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The BIP-173 and BIP-350 valid strings decode as their variant and
+    /// re-encode to themselves, lower-cased.
+    #[test]
+    fn bip_valid_strings_round_trip() {
+        let cases = [
+            (Variant::Bech32, "A12UEL5L"),
+            (Variant::Bech32, "a12uel5l"),
+            (
+                Variant::Bech32,
+                "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+            ),
+            (Variant::Bech32, "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw"),
+            (
+                Variant::Bech32,
+                "11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j",
+            ),
+            (Variant::Bech32, "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w"),
+            (Variant::Bech32, "?1ezyfcl"),
+            (Variant::Bech32m, "A1LQFN3A"),
+            (Variant::Bech32m, "a1lqfn3a"),
+            (
+                Variant::Bech32m,
+                "an83characterlonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11sg7hg6",
+            ),
+            (Variant::Bech32m, "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx"),
+            (
+                Variant::Bech32m,
+                "11llllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllludsr8",
+            ),
+            (Variant::Bech32m, "split1checkupstagehandshakeupstreamerranterredcaperredlc445v"),
+            (Variant::Bech32m, "?1v759aa"),
+        ];
+        for (variant, text) in cases {
+            let (hrp, data, got) = decode(text).unwrap_or_else(|err| panic!("{}: {}", text, err));
+            assert_eq!(got, variant, "{}", text);
+            assert_eq!(encode(&hrp, &data, variant), Ok(text.to_ascii_lowercase()));
+        }
+    }
+
+    /// The BIP-173 and BIP-350 invalid strings are refused for the reason
+    /// the BIPs give.
+    #[test]
+    fn bip_invalid_strings_are_refused() {
+        let long = "an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx";
+        assert!(matches!(decode(long), Err(Error::TooLong { .. })));
+        assert_eq!(decode("pzry9x0s0muk"), Err(Error::MissingSeparator));
+        for empty_hrp in ["1pzry9x0s0muk", "10a06t8", "1qzzfhee", "16plkw9"] {
+            assert!(matches!(decode(empty_hrp), Err(Error::InvalidHrp { .. })));
+        }
+        assert!(matches!(
+            decode("\x201nwldj5"),
+            Err(Error::InvalidHrp { .. })
+        ));
+        assert_eq!(
+            decode("x1b4n0q5v"),
+            Err(Error::InvalidCharacter {
+                character: 'b',
+                position: 2
+            })
+        );
+        assert_eq!(decode("li1dgmt3"), Err(Error::InvalidChecksum));
+        assert_eq!(decode("A1G7SGD8"), Err(Error::InvalidChecksum));
+        assert_eq!(decode("a12UEL5L"), Err(Error::MixedCase));
+        // A Bech32 string with one character changed.
+        assert_eq!(decode("a12uel5m"), Err(Error::InvalidChecksum));
+        assert!(encode("bc", &[32], Variant::Bech32).is_err());
+        assert!(encode("", &[], Variant::Bech32).is_err());
+        assert!(decode_with_max_len("a12uel5l", 7).is_err());
+    }
+
+    /// Bytes regroup into 5-bit values and back, and leftover bits must be
+    /// zero padding.
+    #[test]
+    fn base32_regrouping_round_trips() {
+        for len in 0..=21usize {
+            let bytes: Vec<u8> = (0..len).map(|i| (i * 37 + 11) as u8).collect();
+            let groups = to_base32(&bytes);
+            assert_eq!(groups.len(), (len * 8).div_ceil(5));
+            assert_eq!(from_base32(&groups), Ok(bytes));
+        }
+        assert_eq!(to_base32(&[0xff]), [31, 28]);
+        assert_eq!(from_base32(&[31, 29]), Err(Error::InvalidPadding));
+        assert_eq!(from_base32(&[0]), Err(Error::InvalidPadding));
+        assert!(from_base32(&[32, 0]).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! CPU pinning and scheduling priority for `--pin-core` and `--realtime`.
+//!
+//! Only Linux builds without `safe-only` are supported; elsewhere both
+//! requests fail with an error rather than silently measuring an unpinned
+//! run.
+
+/// Pin the calling thread to logical CPU `core`.
+#[cfg(all(target_os = "linux", not(feature = "safe-only")))]
+pub fn pin_current_thread(core: usize) -> Result<(), String> {
+    // SAFETY: `cpu_set_t` is plain data, so all zeroes is a valid empty set,
+    // and the libc macros only touch the set they are given.
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "cannot pin to core {}: {}",
+            core,
+            std::io::Error::last_os_error()
+        ))
+    }
+}
+
+/// Move the process to `SCHED_FIFO` at its lowest real-time priority.
+#[cfg(all(target_os = "linux", not(feature = "safe-only")))]
+pub fn set_realtime() -> Result<(), String> {
+    // SAFETY: `sched_param` is plain data and outlives the call.
+    let result = unsafe {
+        let param = libc::sched_param {
+            sched_priority: libc::sched_get_priority_min(libc::SCHED_FIFO),
+        };
+        libc::sched_setscheduler(0, libc::SCHED_FIFO, &param)
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "cannot switch to SCHED_FIFO: {} (needs CAP_SYS_NICE or root)",
+            std::io::Error::last_os_error()
+        ))
+    }
+}
+
+#[cfg(not(all(target_os = "linux", not(feature = "safe-only"))))]
+pub fn pin_current_thread(_core: usize) -> Result<(), String> {
+    Err(String::from(
+        "--pin-core needs Linux and a build without safe-only",
+    ))
+}
+
+#[cfg(not(all(target_os = "linux", not(feature = "safe-only"))))]
+pub fn set_realtime() -> Result<(), String> {
+    Err(String::from(
+        "--realtime needs Linux and a build without safe-only",
+    ))
+}
@@ -0,0 +1,263 @@
+//! Fleet-wide statistics for `bench aggregate`.
+//!
+//! Records from many hosts are grouped by metadata: a `--tag` of that
+//! name, or failing that `implementation`, `operation`, `os`, `arch` or
+//! `cpu` (the environment's CPU model). Each group summarizes the median
+//! hashes/s of its runs. When `implementation` is one of the keys, each
+//! group is also normalized against the baseline implementation's group
+//! with the same other keys, so a row of the comparison table reads as
+//! "on this CPU and OS, Mojo runs at 1.4x Rust".
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::bench::compare::Record;
+use crate::bench::stats::Summary;
+
+/// Keys grouped by when none are given.
+pub const DEFAULT_KEYS: &[&str] = &["cpu", "os", "implementation"];
+
+/// The runs sharing one value of every grouping key.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Group {
+    pub key: BTreeMap<String, String>,
+    pub runs: usize,
+    pub hashes_per_second: Summary,
+    /// Median hashes/s over the baseline implementation's with the same
+    /// other keys; `None` without an `implementation` key or a baseline
+    /// group to compare with.
+    pub relative: Option<f64>,
+}
+
+/// The value `record` has for grouping key `key`, `-` when it has none.
+pub fn field(record: &Record, key: &str) -> String {
+    if let Some(tag) = record.tags.get(key) {
+        return tag.clone();
+    }
+    let environment = |name: &str| {
+        record
+            .environment
+            .as_ref()
+            .and_then(|env| env.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    match key {
+        "implementation" => Some(record.implementation.clone()),
+        "operation" => record.operation.clone(),
+        "os" | "arch" => environment(key),
+        "cpu" => environment("cpu_model"),
+        _ => None,
+    }
+    .unwrap_or_else(|| String::from("-"))
+}
+
+/// Group `records` by `keys`, in order of first appearance, normalizing
+/// against `baseline` or the first record's implementation.
+pub fn aggregate(records: &[Record], keys: &[&str], baseline: Option<&str>) -> Vec<Group> {
+    let mut order: Vec<Vec<String>> = Vec::new();
+    let mut samples: BTreeMap<Vec<String>, Vec<f64>> = BTreeMap::new();
+    for record in records {
+        let values: Vec<String> = keys.iter().map(|key| field(record, key)).collect();
+        let entry = samples.entry(values.clone()).or_default();
+        if entry.is_empty() {
+            order.push(values);
+        }
+        entry.push(record.hashes_per_second);
+    }
+    let summaries: BTreeMap<&Vec<String>, Summary> = samples
+        .iter()
+        .map(|(values, runs)| {
+            (
+                values,
+                Summary::from_samples(runs).expect("groups have runs"),
+            )
+        })
+        .collect();
+
+    let implementation = keys.iter().position(|&key| key == "implementation");
+    let baseline = baseline
+        .map(str::to_string)
+        .or_else(|| records.first().map(|record| record.implementation.clone()));
+    order
+        .iter()
+        .map(|values| {
+            let summary = &summaries[values];
+            let relative = implementation.zip(baseline.as_ref()).and_then(|(i, name)| {
+                let mut base = values.clone();
+                base[i] = name.clone();
+                let base = summaries.get(&base)?;
+                (base.median > 0.0).then(|| summary.median / base.median)
+            });
+            Group {
+                key: keys
+                    .iter()
+                    .map(|key| key.to_string())
+                    .zip(values.iter().cloned())
+                    .collect(),
+                runs: samples[values].len(),
+                hashes_per_second: summary.clone(),
+                relative,
+            }
+        })
+        .collect()
+}
+
+/// The per-group statistics and, when grouped by `implementation`, a
+/// comparison table with one row per value of the other keys and one
+/// column per implementation.
+pub fn render(groups: &[Group], keys: &[&str]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{} | runs | median hashes/s | mean | stddev | min | max | relative",
+        keys.join(" | ")
+    )
+    .expect("writing to a String");
+    writeln!(out, "{}", vec!["---"; keys.len() + 7].join(" | ")).expect("writing to a String");
+    for group in groups {
+        let values: Vec<&str> = keys.iter().map(|key| group.key[*key].as_str()).collect();
+        let summary = &group.hashes_per_second;
+        writeln!(
+            out,
+            "{} | {} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {}",
+            values.join(" | "),
+            group.runs,
+            summary.median,
+            summary.mean,
+            summary.stddev,
+            summary.min,
+            summary.max,
+            relative(group.relative)
+        )
+        .expect("writing to a String");
+    }
+
+    if !keys.contains(&"implementation") {
+        return out;
+    }
+    let others: Vec<&str> = keys
+        .iter()
+        .copied()
+        .filter(|&key| key != "implementation")
+        .collect();
+    let mut columns: Vec<&str> = Vec::new();
+    // Each row's values of the other keys and ratio per implementation.
+    type Row<'a> = (Vec<&'a str>, BTreeMap<&'a str, Option<f64>>);
+    let mut rows: Vec<Row> = Vec::new();
+    for group in groups {
+        let implementation = group.key["implementation"].as_str();
+        if !columns.contains(&implementation) {
+            columns.push(implementation);
+        }
+        let row: Vec<&str> = others.iter().map(|key| group.key[*key].as_str()).collect();
+        match rows.iter_mut().find(|(values, _)| *values == row) {
+            Some((_, cells)) => {
+                cells.insert(implementation, group.relative);
+            }
+            None => rows.push((row, BTreeMap::from([(implementation, group.relative)]))),
+        }
+    }
+    writeln!(out).expect("writing to a String");
+    let mut header = others.clone();
+    header.extend(&columns);
+    writeln!(out, "{}", header.join(" | ")).expect("writing to a String");
+    writeln!(out, "{}", vec!["---"; header.len()].join(" | ")).expect("writing to a String");
+    for (values, cells) in &rows {
+        let mut line = values.clone();
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| relative(cells.get(column).copied().flatten()))
+            .collect();
+        line.extend(cells.iter().map(String::as_str));
+        writeln!(out, "{}", line.join(" | ")).expect("writing to a String");
+    }
+    out
+}
+
+fn relative(value: Option<f64>) -> String {
+    value
+        .map(|ratio| format!("{:.2}x", ratio))
+        .unwrap_or_else(|| String::from("-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(implementation: &str, cpu: &str, host: &str, hashes_per_second: f64) -> Record {
+        serde_json::from_value(serde_json::json!({
+            "implementation": implementation,
+            "seconds": 1.0,
+            "hashes_per_second": hashes_per_second,
+            "tags": {"host": host},
+            "environment": {"os": "linux", "arch": "x86_64", "cpu_model": cpu},
+        }))
+        .unwrap()
+    }
+
+    /// Runs group by environment and tags, and each group is normalized
+    /// against the baseline on the same CPU; a CPU without a baseline run
+    /// has no ratio.
+    #[test]
+    fn groups_normalize_per_cpu() {
+        let records = [
+            record("rust", "epyc", "a", 100.0),
+            record("mojo", "epyc", "a", 150.0),
+            record("rust", "epyc", "b", 110.0),
+            record("mojo", "epyc", "b", 130.0),
+            record("rust", "m2", "c", 80.0),
+            record("mojo", "m2", "c", 40.0),
+            record("mojo", "xeon", "d", 90.0),
+        ];
+        let groups = aggregate(&records, DEFAULT_KEYS, None);
+        assert_eq!(groups.len(), 5);
+        assert_eq!(groups[0].key["cpu"], "epyc");
+        assert_eq!(groups[0].runs, 2);
+        assert_eq!(groups[0].hashes_per_second.median, 105.0);
+        assert_eq!(groups[0].relative, Some(1.0));
+        assert_eq!(groups[1].hashes_per_second.median, 140.0);
+        assert_eq!(groups[1].relative, Some(140.0 / 105.0));
+        assert_eq!(groups[3].relative, Some(0.5));
+        assert_eq!(groups[4].relative, None);
+
+        let by_host = aggregate(&records, &["host", "implementation"], Some("mojo"));
+        assert_eq!(by_host[0].key["host"], "a");
+        assert_eq!(by_host[0].relative, Some(100.0 / 150.0));
+        assert_eq!(aggregate(&records, &["os"], None)[0].relative, None);
+        assert_eq!(field(&records[0], "branch"), "-");
+    }
+
+    /// The comparison table has a row per CPU and OS and a column per
+    /// implementation.
+    #[test]
+    fn comparison_table_pivots_implementations() {
+        let records = [
+            record("rust", "epyc", "a", 100.0),
+            record("mojo", "epyc", "a", 150.0),
+            record("mojo", "xeon", "d", 90.0),
+        ];
+        let text = render(&aggregate(&records, DEFAULT_KEYS, None), DEFAULT_KEYS);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "cpu | os | implementation | runs | median hashes/s | mean | stddev | min | max | relative"
+        );
+        assert_eq!(
+            lines[3],
+            "epyc | linux | mojo | 1 | 150.00 | 150.00 | 0.00 | 150.00 | 150.00 | 1.50x"
+        );
+        assert_eq!(
+            &lines[6..],
+            [
+                "cpu | os | rust | mojo",
+                "--- | --- | --- | ---",
+                "epyc | linux | 1.00x | 1.50x",
+                "xeon | linux | - | -"
+            ]
+        );
+    }
+}
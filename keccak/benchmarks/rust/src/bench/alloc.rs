@@ -0,0 +1,161 @@
+//! Allocation counting for the `alloc-stats` feature.
+//!
+//! The feature installs a counting wrapper around the system allocator, which
+//! adds two relaxed atomic increments to every allocation. Without it
+//! [`snapshot`] returns `None` and allocation is untouched.
+
+#[cfg(feature = "alloc-stats")]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Forwards to the system allocator, counting allocations and requested
+/// bytes. Reallocations count as one allocation of the new size.
+#[cfg(feature = "alloc-stats")]
+pub struct CountingAllocator;
+
+#[cfg(feature = "alloc-stats")]
+fn record(bytes: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+// SAFETY: every method forwards to `System` with the caller's arguments and
+// only adds counter updates, which do not allocate.
+#[cfg(feature = "alloc-stats")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        // SAFETY: forwarded unchanged from our caller.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        // SAFETY: forwarded unchanged from our caller.
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: forwarded unchanged from our caller.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size);
+        // SAFETY: forwarded unchanged from our caller.
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Allocations made over some interval.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct AllocCounts {
+    pub count: u64,
+    pub bytes: u64,
+}
+
+impl AllocCounts {
+    /// Counts accumulated since `earlier`.
+    pub fn since(self, earlier: AllocCounts) -> AllocCounts {
+        AllocCounts {
+            count: self.count.wrapping_sub(earlier.count),
+            bytes: self.bytes.wrapping_sub(earlier.bytes),
+        }
+    }
+}
+
+/// Process-wide totals so far, or `None` when counting is not compiled in.
+pub fn snapshot() -> Option<AllocCounts> {
+    cfg!(feature = "alloc-stats").then(|| AllocCounts {
+        count: ALLOCATIONS.load(Ordering::Relaxed),
+        bytes: BYTES.load(Ordering::Relaxed),
+    })
+}
+
+/// Allocations made while `f` runs.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocCounts>) {
+    let before = snapshot();
+    let value = f();
+    let counts = before
+        .zip(snapshot())
+        .map(|(before, after)| after.since(before));
+    (value, counts)
+}
+
+/// Allocations by benchmark phase for one backend.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AllocationReport {
+    pub warmup: AllocCounts,
+    /// Totals over every measured trial.
+    pub trials: AllocCounts,
+    /// Allocations per hash in the measured trials.
+    pub per_hash: f64,
+    /// Bytes allocated per hash in the measured trials.
+    pub bytes_per_hash: f64,
+}
+
+impl AllocationReport {
+    pub fn new(warmup: AllocCounts, trials: AllocCounts, hashes: u64) -> AllocationReport {
+        let per_hash = |value: u64| {
+            if hashes > 0 {
+                value as f64 / hashes as f64
+            } else {
+                0.0
+            }
+        };
+        AllocationReport {
+            warmup,
+            trials,
+            per_hash: per_hash(trials.count),
+            bytes_per_hash: per_hash(trials.bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deltas subtract component-wise and per-hash ratios use the trials.
+    #[test]
+    fn report_divides_trial_counts() {
+        let before = AllocCounts {
+            count: 5,
+            bytes: 100,
+        };
+        let after = AllocCounts {
+            count: 25,
+            bytes: 900,
+        };
+        let trials = after.since(before);
+        assert_eq!(
+            trials,
+            AllocCounts {
+                count: 20,
+                bytes: 800
+            }
+        );
+        let report = AllocationReport::new(AllocCounts::default(), trials, 10);
+        assert_eq!(report.per_hash, 2.0);
+        assert_eq!(report.bytes_per_hash, 80.0);
+    }
+
+    /// Counting reflects real allocations when the feature is enabled.
+    #[cfg(feature = "alloc-stats")]
+    #[test]
+    fn measure_counts_allocations() {
+        let (_, counts) = measure(|| std::hint::black_box(vec![0u8; 64]));
+        let counts = counts.unwrap();
+        assert!(counts.count >= 1);
+        assert!(counts.bytes >= 64);
+    }
+}
@@ -0,0 +1,384 @@
+//! Hash implementations the bench can time.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::bench::xof::{self, Xof};
+use crate::native::{self, Permutation};
+use crate::parallel;
+
+/// A hash backend. Every backend except `blake3` computes Keccak-256, so
+/// their checksums must agree; BLAKE3 is only a speed reference.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    #[default]
+    TinyKeccak,
+    /// The crate's portable implementation in `crate::native`.
+    Native,
+    /// The same implementation with its permutation built for AVX2 and
+    /// picked at runtime; needs a CPU with AVX2.
+    NativeSimd,
+    /// The same implementation calling the hand-written permutation
+    /// kernel; needs the `asm` feature and x86-64 with BMI1 or aarch64.
+    NativeAsm,
+    /// Batches hashed four messages per permutation pass by
+    /// `crate::parallel`, on AVX2 where the CPU has it; single messages
+    /// take the portable path, so it only differs from `native` with
+    /// `--batch`.
+    NativeX4,
+    /// As `native-x4`, eight messages per pass on AVX-512F.
+    NativeX8,
+    /// As `native-x4`, two messages per pass on aarch64 NEON.
+    NativeX2,
+    /// Batches on whichever of the above `crate::parallel::dispatch`
+    /// selects: the widest the CPU supports, unless forced.
+    NativeAuto,
+    /// RustCrypto `sha3::Keccak256`; needs the `sha3` feature.
+    Sha3,
+    /// BLAKE3-256; needs the `blake3` feature.
+    Blake3,
+}
+
+impl Backend {
+    /// Every backend compiled into this binary.
+    pub fn available() -> Vec<Backend> {
+        [
+            Backend::TinyKeccak,
+            Backend::Native,
+            Backend::NativeSimd,
+            Backend::NativeAsm,
+            Backend::NativeX2,
+            Backend::NativeX4,
+            Backend::NativeX8,
+            Backend::NativeAuto,
+            Backend::Sha3,
+            Backend::Blake3,
+        ]
+        .into_iter()
+        .filter(|backend| backend.is_available())
+        .collect()
+    }
+
+    pub fn is_available(self) -> bool {
+        match self {
+            Backend::TinyKeccak
+            | Backend::Native
+            | Backend::NativeX2
+            | Backend::NativeX4
+            | Backend::NativeX8
+            | Backend::NativeAuto => true,
+            Backend::NativeSimd => Permutation::Avx2.is_supported(),
+            Backend::NativeAsm => Permutation::Asm.is_supported(),
+            Backend::Sha3 => cfg!(feature = "sha3"),
+            Backend::Blake3 => cfg!(feature = "blake3"),
+        }
+    }
+
+    pub fn hash(self, message: &[u8]) -> [u8; 32] {
+        match self {
+            Backend::TinyKeccak => crate::keccak256(message),
+            Backend::Native
+            | Backend::NativeX2
+            | Backend::NativeX4
+            | Backend::NativeX8
+            | Backend::NativeAuto => native::keccak256(message),
+            Backend::NativeSimd => native::keccak256_with(Permutation::Avx2, message),
+            Backend::NativeAsm => native::keccak256_with(Permutation::Asm, message),
+            #[cfg(feature = "sha3")]
+            Backend::Sha3 => {
+                use sha3::Digest;
+                sha3::Keccak256::digest(message).into()
+            }
+            #[cfg(feature = "blake3")]
+            Backend::Blake3 => blake3::hash(message).into(),
+            #[allow(unreachable_patterns)]
+            unavailable => panic!("backend {} is not compiled in", unavailable),
+        }
+    }
+
+    /// Hash `message` incrementally, `chunk` bytes per update call.
+    pub fn hash_chunked(self, message: &[u8], chunk: usize) -> [u8; 32] {
+        match self {
+            Backend::TinyKeccak => {
+                use tiny_keccak::Hasher;
+                let mut hasher = tiny_keccak::Keccak::v256();
+                for piece in message.chunks(chunk) {
+                    hasher.update(piece);
+                }
+                let mut output = [0u8; 32];
+                hasher.finalize(&mut output);
+                output
+            }
+            Backend::Native
+            | Backend::NativeSimd
+            | Backend::NativeAsm
+            | Backend::NativeX2
+            | Backend::NativeX4
+            | Backend::NativeX8
+            | Backend::NativeAuto => {
+                let mut hasher = native::Keccak256::with_permutation(self.permutation());
+                for piece in message.chunks(chunk) {
+                    hasher.update(piece);
+                }
+                hasher.finalize()
+            }
+            #[cfg(feature = "sha3")]
+            Backend::Sha3 => {
+                use sha3::Digest;
+                let mut hasher = sha3::Keccak256::new();
+                for piece in message.chunks(chunk) {
+                    hasher.update(piece);
+                }
+                hasher.finalize().into()
+            }
+            #[cfg(feature = "blake3")]
+            Backend::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                for piece in message.chunks(chunk) {
+                    hasher.update(piece);
+                }
+                hasher.finalize().into()
+            }
+            #[allow(unreachable_patterns)]
+            unavailable => panic!("backend {} is not compiled in", unavailable),
+        }
+    }
+
+    /// Hash a batch of messages with one call where the backend has a batch
+    /// API, and one call per message otherwise.
+    pub fn hash_batch<M: AsRef<[u8]>>(self, messages: &[M], digests: &mut [[u8; 32]]) {
+        match self {
+            Backend::NativeX2 => parallel::keccak256_batch_x2(messages, digests),
+            Backend::NativeX4 => parallel::keccak256_batch(messages, digests),
+            Backend::NativeX8 => parallel::keccak256_batch_x8(messages, digests),
+            Backend::NativeAuto => parallel::dispatch::keccak256_batch(messages, digests),
+            _ => {
+                for (message, digest) in messages.iter().zip(digests) {
+                    *digest = self.hash(message.as_ref());
+                }
+            }
+        }
+    }
+
+    /// Whether [`Backend::hash_xof`] supports this backend.
+    pub fn has_xof(self) -> bool {
+        match self {
+            Backend::Native => true,
+            Backend::Sha3 => cfg!(feature = "sha3"),
+            Backend::TinyKeccak
+            | Backend::NativeSimd
+            | Backend::NativeAsm
+            | Backend::NativeX2
+            | Backend::NativeX4
+            | Backend::NativeX8
+            | Backend::NativeAuto
+            | Backend::Blake3 => false,
+        }
+    }
+
+    /// Absorb `message`, squeeze `length` bytes, and fold them into a
+    /// digest-sized value with [`xof::fold`].
+    pub fn hash_xof(self, function: Xof, message: &[u8], length: usize) -> [u8; 32] {
+        match self {
+            Backend::Native => {
+                let mut shake = match function {
+                    Xof::Shake128 => native::Shake::shake128(),
+                    Xof::Shake256 => native::Shake::shake256(),
+                };
+                shake.update(message);
+                let mut reader = shake.finalize_xof();
+                xof::fold(length, |block| reader.squeeze(block))
+            }
+            #[cfg(feature = "sha3")]
+            Backend::Sha3 => {
+                use sha3::digest::{ExtendableOutput, Update, XofReader};
+                match function {
+                    Xof::Shake128 => {
+                        let mut shake = sha3::Shake128::default();
+                        shake.update(message);
+                        let mut reader = shake.finalize_xof();
+                        xof::fold(length, |block| reader.read(block))
+                    }
+                    Xof::Shake256 => {
+                        let mut shake = sha3::Shake256::default();
+                        shake.update(message);
+                        let mut reader = shake.finalize_xof();
+                        xof::fold(length, |block| reader.read(block))
+                    }
+                }
+            }
+            unsupported => panic!("backend {} has no XOF", unsupported),
+        }
+    }
+
+    /// The permutation a native backend's hashers call.
+    fn permutation(self) -> Permutation {
+        match self {
+            Backend::NativeSimd => Permutation::Avx2,
+            Backend::NativeAsm => Permutation::Asm,
+            _ => Permutation::Generic,
+        }
+    }
+
+    /// Why this backend is not available, for error messages.
+    pub fn requirement(self) -> String {
+        match self {
+            Backend::NativeSimd => format!("backend {} needs a CPU with AVX2", self),
+            Backend::NativeAsm => format!(
+                "backend {} requires building with --features asm, on x86-64 with BMI1 or aarch64",
+                self
+            ),
+            _ => format!(
+                "backend {} requires building with --features {}",
+                self, self
+            ),
+        }
+    }
+
+    /// The default `--label` for results from this backend.
+    pub fn label(self) -> String {
+        format!("rust ({})", self)
+    }
+}
+
+impl FromStr for Backend {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "tiny-keccak" => Ok(Backend::TinyKeccak),
+            "native" => Ok(Backend::Native),
+            "native-simd" => Ok(Backend::NativeSimd),
+            "native-asm" => Ok(Backend::NativeAsm),
+            "native-x2" => Ok(Backend::NativeX2),
+            "native-x4" => Ok(Backend::NativeX4),
+            "native-x8" => Ok(Backend::NativeX8),
+            "native-auto" => Ok(Backend::NativeAuto),
+            "sha3" => Ok(Backend::Sha3),
+            "blake3" => Ok(Backend::Blake3),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Backend::TinyKeccak => "tiny-keccak",
+            Backend::Native => "native",
+            Backend::NativeSimd => "native-simd",
+            Backend::NativeAsm => "native-asm",
+            Backend::NativeX2 => "native-x2",
+            Backend::NativeX4 => "native-x4",
+            Backend::NativeX8 => "native-x8",
+            Backend::NativeAuto => "native-auto",
+            Backend::Sha3 => "sha3",
+            Backend::Blake3 => "blake3",
+        })
+    }
+}
+
+/// Parse a `--backend` value: a comma-separated list, or `all` for every
+/// available backend.
+pub fn parse_list(value: &str) -> Result<Vec<Backend>, String> {
+    if value == "all" {
+        return Ok(Backend::available());
+    }
+    let mut backends = Vec::new();
+    for name in value.split(',').map(str::trim) {
+        let backend: Backend = name
+            .parse()
+            .map_err(|_| format!("unknown backend {:?}", name))?;
+        if !backend.is_available() {
+            return Err(backend.requirement());
+        }
+        if !backends.contains(&backend) {
+            backends.push(backend);
+        }
+    }
+    Ok(backends)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All Keccak backends agree on the digest.
+    #[test]
+    fn keccak_backends_agree() {
+        let message = b"interleaved backends";
+        let expected = Backend::TinyKeccak.hash(message);
+        for backend in Backend::available() {
+            if backend != Backend::Blake3 {
+                assert_eq!(backend.hash(message), expected, "{}", backend);
+            }
+        }
+    }
+
+    /// Batches agree with one-shot hashing for every backend, including
+    /// sizes that leave a partial group of four.
+    #[test]
+    fn batches_match_one_shot() {
+        let messages: Vec<Vec<u8>> = (0..7u8).map(|i| vec![i; i as usize * 50]).collect();
+        for backend in Backend::available() {
+            let mut digests = vec![[0u8; 32]; messages.len()];
+            backend.hash_batch(&messages, &mut digests);
+            for (message, digest) in messages.iter().zip(&digests) {
+                assert_eq!(*digest, backend.hash(message), "{}", backend);
+            }
+        }
+    }
+
+    /// Chunked absorption produces the one-shot digest for every backend.
+    #[test]
+    fn chunked_hashing_matches_one_shot() {
+        let message: Vec<u8> = (0..700u32).map(|i| (i * 13) as u8).collect();
+        for backend in Backend::available() {
+            for chunk in [1, 7, 136, 137, 700, 4096] {
+                assert_eq!(
+                    backend.hash_chunked(&message, chunk),
+                    backend.hash(&message),
+                    "{} chunk {}",
+                    backend,
+                    chunk
+                );
+            }
+        }
+    }
+
+    /// Lists are de-duplicated and unknown names rejected.
+    #[test]
+    fn backend_lists_parse() {
+        assert_eq!(
+            parse_list("native,tiny-keccak,native").unwrap(),
+            vec![Backend::Native, Backend::TinyKeccak]
+        );
+        assert_eq!(parse_list("all").unwrap(), Backend::available());
+        assert!(parse_list("md5").is_err());
+    }
+
+    /// Every XOF backend squeezes the same stream, regardless of how the
+    /// output length falls against the rate.
+    #[test]
+    fn xof_backends_agree() {
+        let message = [0x5au8; 300];
+        for function in [Xof::Shake128, Xof::Shake256] {
+            let expected = Backend::Native.hash_xof(function, &message, 1000);
+            for backend in Backend::available() {
+                if backend.has_xof() {
+                    assert_eq!(
+                        backend.hash_xof(function, &message, 1000),
+                        expected,
+                        "{} {}",
+                        backend,
+                        function
+                    );
+                }
+            }
+            assert_ne!(expected, Backend::Native.hash_xof(function, &message, 999));
+        }
+    }
+}
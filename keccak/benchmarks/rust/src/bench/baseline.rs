@@ -0,0 +1,113 @@
+//! Named result baselines for regression gating.
+//!
+//! `--save-baseline NAME` stores the run as `<dir>/NAME.json`;
+//! `--check-against NAME` compares a run with that file and fails when
+//! throughput dropped by more than the regression threshold.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::bench::compare::{self, Record};
+use crate::bench::output::BenchResult;
+
+pub const DEFAULT_DIR: &str = ".bench-baselines";
+
+/// How a run compares with its baseline.
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    /// Throughput changed by `change` (a fraction; negative is slower)
+    /// without exceeding the threshold.
+    Pass { change: f64 },
+    /// Throughput dropped by more than the threshold.
+    Regression { change: f64 },
+}
+
+fn baseline_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("invalid baseline name {:?}", name));
+    }
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+pub fn save(dir: &Path, name: &str, result: &BenchResult) -> Result<PathBuf, String> {
+    let path = baseline_path(dir, name)?;
+    fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+    let json = serde_json::to_string_pretty(result).expect("bench results serialize to JSON");
+    fs::write(&path, json + "\n").map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(path)
+}
+
+pub fn load(dir: &Path, name: &str) -> Result<Record, String> {
+    let path = baseline_path(dir, name)?;
+    let mut records = compare::load_records(&path.display().to_string())?;
+    match records.len() {
+        1 => Ok(records.remove(0)),
+        count => Err(format!(
+            "{}: expected one result, found {}",
+            path.display(),
+            count
+        )),
+    }
+}
+
+/// Judge `current` against `baseline`. Errors when the two runs did not
+/// hash the same workload, since their throughput is then incomparable.
+pub fn check(baseline: &Record, current: &Record, threshold: f64) -> Result<Verdict, String> {
+    let problems = compare::validate(&[baseline.clone(), current.clone()]);
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+    if baseline.hashes_per_second <= 0.0 {
+        return Err(String::from("baseline reports zero throughput"));
+    }
+    let change = current.hashes_per_second / baseline.hashes_per_second - 1.0;
+    if change < -threshold {
+        Ok(Verdict::Regression { change })
+    } else {
+        Ok(Verdict::Pass { change })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(hashes_per_second: f64, messages: u64) -> Record {
+        serde_json::from_value(serde_json::json!({
+            "implementation": "rust",
+            "seconds": 1.0,
+            "hashes_per_second": hashes_per_second,
+            "checksum": 67,
+            "config": {"messages": messages},
+        }))
+        .unwrap()
+    }
+
+    /// Drops within the threshold pass; larger drops are regressions.
+    #[test]
+    fn check_applies_threshold() {
+        let baseline = record(100.0, 512);
+        assert!(matches!(
+            check(&baseline, &record(96.0, 512), 0.05),
+            Ok(Verdict::Pass { .. })
+        ));
+        assert!(matches!(
+            check(&baseline, &record(120.0, 512), 0.05),
+            Ok(Verdict::Pass { .. })
+        ));
+        assert!(matches!(
+            check(&baseline, &record(90.0, 512), 0.05),
+            Ok(Verdict::Regression { .. })
+        ));
+        assert!(check(&baseline, &record(100.0, 256), 0.05).is_err());
+    }
+
+    /// Names that would escape the baseline directory are rejected.
+    #[test]
+    fn baseline_names_stay_in_directory() {
+        let dir = Path::new("baselines");
+        assert_eq!(baseline_path(dir, "main").unwrap(), dir.join("main.json"));
+        assert!(baseline_path(dir, "../main").is_err());
+        assert!(baseline_path(dir, "").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Throughput by message-length bucket for `--by-length`.
+//!
+//! Messages are grouped into power-of-two length ranges (`0`, `1`, `2-3`,
+//! `4-7`, ...) and each group is timed on its own for the config's rounds,
+//! after the trials. A mixed workload then shows how each size range
+//! performs instead of one average over all of them.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::bench::workload::Workload;
+use crate::bench::BenchConfig;
+
+/// One length range and how fast its messages hashed.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LengthBucket {
+    /// Inclusive bounds of the range, not of the lengths seen in it.
+    pub min_len: usize,
+    pub max_len: usize,
+    /// Messages per round that fall in the range.
+    pub messages: usize,
+    /// Bytes hashed over all rounds.
+    pub bytes: u64,
+    pub seconds: f64,
+    pub hashes_per_second: f64,
+    pub mb_per_second: f64,
+}
+
+/// The power-of-two range holding `length`.
+pub fn bucket_of(length: usize) -> (usize, usize) {
+    if length == 0 {
+        return (0, 0);
+    }
+    let min = 1 << length.ilog2();
+    (min, min.saturating_mul(2) - 1)
+}
+
+/// Time each bucket's messages for `config.rounds` rounds, single
+/// threaded, shortest range first.
+pub fn measure(config: &BenchConfig, workload: &Workload) -> Vec<LengthBucket> {
+    let mut groups: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for index in 0..workload.count(config) {
+        let length = workload.message(config, index).len();
+        groups.entry(bucket_of(length)).or_default().push(index);
+    }
+    let batch = config.batch.unwrap_or(1);
+    let mut digests = vec![[0u8; 32]; batch];
+    groups
+        .into_iter()
+        .map(|((min_len, max_len), indices)| {
+            let mut checksum = 0;
+            let mut bytes = 0u64;
+            let start = config.timer.now();
+            for _ in 0..config.rounds {
+                for group in indices.chunks(batch) {
+                    let messages: Vec<_> = group
+                        .iter()
+                        .map(|&index| workload.message(config, index))
+                        .collect();
+                    let messages: Vec<&[u8]> = messages.iter().map(|m| m.as_ref()).collect();
+                    let digests = &mut digests[..group.len()];
+                    config.target.run_batch(config, &messages, digests);
+                    for (message, digest) in messages.iter().zip(digests.iter()) {
+                        checksum = config.target.checksum(config, checksum, digest);
+                        bytes += message.len() as u64;
+                    }
+                }
+            }
+            let seconds = start.elapsed_secs();
+            std::hint::black_box(checksum);
+            let hashes = (indices.len() * config.rounds) as f64;
+            let (hashes_per_second, mb_per_second) = if seconds > 0.0 {
+                (hashes / seconds, bytes as f64 / seconds / 1e6)
+            } else {
+                (0.0, 0.0)
+            };
+            LengthBucket {
+                min_len,
+                max_len,
+                messages: indices.len(),
+                bytes,
+                seconds,
+                hashes_per_second,
+                mb_per_second,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::backend::Backend;
+
+    /// Lengths land in power-of-two ranges, and every message of the
+    /// workload is counted in exactly one bucket.
+    #[test]
+    fn buckets_cover_the_workload() {
+        assert_eq!(bucket_of(0), (0, 0));
+        assert_eq!(bucket_of(1), (1, 1));
+        assert_eq!(bucket_of(64), (64, 127));
+        assert_eq!(bucket_of(127), (64, 127));
+        let config = BenchConfig {
+            backend: Backend::Native,
+            messages: 40,
+            rounds: 2,
+            min_len: 10,
+            max_len: 300,
+            ..BenchConfig::default()
+        };
+        let buckets = measure(&config, &Workload::Synthetic);
+        let ranges: Vec<(usize, usize)> = buckets.iter().map(|b| (b.min_len, b.max_len)).collect();
+        assert_eq!(
+            ranges,
+            [
+                (8, 15),
+                (16, 31),
+                (32, 63),
+                (64, 127),
+                (128, 255),
+                (256, 511)
+            ]
+        );
+        assert_eq!(buckets.iter().map(|b| b.messages).sum::<usize>(), 40);
+        assert_eq!(
+            buckets.iter().map(|b| b.bytes).sum::<u64>(),
+            Workload::Synthetic.bytes_per_round(&config) * 2
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Time budgets for `--duration`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+/// A wall-clock budget per trial, parsed from `10s`, `500ms`, `2m`, or a
+/// bare number of seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Budget {
+    pub seconds: f64,
+}
+
+impl FromStr for Budget {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+            (ms, 1e-3)
+        } else if let Some(s) = value.strip_suffix('s') {
+            (s, 1.0)
+        } else if let Some(m) = value.strip_suffix('m') {
+            (m, 60.0)
+        } else {
+            (value, 1.0)
+        };
+        let seconds = number.trim().parse::<f64>().map_err(|_| ())? * scale;
+        if seconds.is_finite() && seconds > 0.0 {
+            Ok(Budget { seconds })
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}s", self.seconds)
+    }
+}
+
+/// Serializes as a number of seconds.
+impl Serialize for Budget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.seconds)
+    }
+}
+
+impl Budget {
+    /// How many rounds of `round_seconds` fit in the budget; at least one.
+    pub fn rounds(self, round_seconds: f64) -> usize {
+        if round_seconds > 0.0 {
+            ((self.seconds / round_seconds) as usize).max(1)
+        } else {
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Suffixes scale to seconds; zero and garbage are rejected.
+    #[test]
+    fn budgets_parse_with_units() {
+        assert_eq!("10s".parse(), Ok(Budget { seconds: 10.0 }));
+        assert_eq!("250ms".parse(), Ok(Budget { seconds: 0.25 }));
+        assert_eq!("2m".parse(), Ok(Budget { seconds: 120.0 }));
+        assert_eq!("1.5".parse(), Ok(Budget { seconds: 1.5 }));
+        assert!("0s".parse::<Budget>().is_err());
+        assert!("soon".parse::<Budget>().is_err());
+    }
+
+    /// Budgets shorter than a round still measure one round.
+    #[test]
+    fn rounds_fit_within_budget() {
+        let budget = Budget { seconds: 1.0 };
+        assert_eq!(budget.rounds(0.3), 3);
+        assert_eq!(budget.rounds(5.0), 1);
+    }
+}
@@ -0,0 +1,293 @@
+//! Throughput-versus-message-size charts for `--chart`.
+//!
+//! Each result contributes one point per corpus file, or a single point at
+//! its mean message size. Results sharing a label form one series, so a
+//! sweep over sizes draws a curve per implementation. Sizes are plotted on
+//! a log2 axis, throughput in MB/s on a linear one.
+
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use crate::bench::output::BenchResult;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChartKind {
+    Ascii,
+    Svg,
+}
+
+impl FromStr for ChartKind {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "ascii" => Ok(ChartKind::Ascii),
+            "svg" => Ok(ChartKind::Svg),
+            _ => Err(()),
+        }
+    }
+}
+
+/// One implementation's `(message bytes, MB/s)` points, sorted by size.
+#[derive(Debug, PartialEq)]
+pub struct Series {
+    pub label: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// Group `results` into one series per implementation label.
+pub fn series(results: &[BenchResult]) -> Vec<Series> {
+    let mut all: Vec<Series> = Vec::new();
+    for result in results {
+        let points: Vec<(f64, f64)> = match &result.files {
+            Some(files) => files
+                .iter()
+                .map(|file| (file.bytes as f64, file.mb_per_second))
+                .collect(),
+            None => {
+                let hashes = (result.config.messages * result.config.rounds).max(1);
+                vec![(result.bytes as f64 / hashes as f64, result.mb_per_second)]
+            }
+        };
+        match all.iter_mut().find(|s| s.label == result.implementation) {
+            Some(existing) => existing.points.extend(points),
+            None => all.push(Series {
+                label: result.implementation.clone(),
+                points,
+            }),
+        }
+    }
+    for s in &mut all {
+        s.points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+    all
+}
+
+pub fn render(kind: ChartKind, series: &[Series]) -> String {
+    match kind {
+        ChartKind::Ascii => render_ascii(series),
+        ChartKind::Svg => render_svg(series),
+    }
+}
+
+/// Axis ranges: log2 sizes and MB/s from zero, padded so a single point or
+/// a flat series still gets a usable span.
+fn bounds(series: &[Series]) -> (f64, f64, f64) {
+    let points = series.iter().flat_map(|s| s.points.iter());
+    let (mut lo, mut hi, mut top) = (f64::INFINITY, f64::NEG_INFINITY, 0.0f64);
+    for &(size, mbps) in points {
+        let x = size.max(1.0).log2();
+        lo = lo.min(x);
+        hi = hi.max(x);
+        top = top.max(mbps);
+    }
+    if !lo.is_finite() {
+        return (0.0, 1.0, 1.0);
+    }
+    if hi - lo < 1.0 {
+        lo -= 0.5;
+        hi += 0.5;
+    }
+    (lo, hi, if top > 0.0 { top * 1.1 } else { 1.0 })
+}
+
+const MARKERS: &[char] = &['*', 'o', 'x', '#', '@', '%'];
+const COLORS: &[&str] = &[
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b",
+];
+
+fn render_ascii(series: &[Series]) -> String {
+    const WIDTH: usize = 60;
+    const HEIGHT: usize = 16;
+    let (lo, hi, top) = bounds(series);
+    let mut grid = vec![vec![' '; WIDTH]; HEIGHT];
+    for (index, s) in series.iter().enumerate() {
+        let marker = MARKERS[index % MARKERS.len()];
+        for &(size, mbps) in &s.points {
+            let x = (size.max(1.0).log2() - lo) / (hi - lo) * (WIDTH - 1) as f64;
+            let y = mbps / top * (HEIGHT - 1) as f64;
+            grid[HEIGHT - 1 - y.round() as usize][x.round() as usize] = marker;
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(out, "MB/s").unwrap();
+    for (row, cells) in grid.iter().enumerate() {
+        let label = match row {
+            0 => format!("{:>9.1}", top),
+            _ if row == HEIGHT - 1 => format!("{:>9.1}", 0.0),
+            _ => " ".repeat(9),
+        };
+        let line: String = cells.iter().collect();
+        writeln!(out, "{} |{}", label, line.trim_end()).unwrap();
+    }
+    writeln!(out, "{} +{}", " ".repeat(9), "-".repeat(WIDTH)).unwrap();
+    let left = format!("{:.0} B", lo.exp2());
+    let right = format!("{:.0} B", hi.exp2());
+    writeln!(
+        out,
+        "{}  {}{:>width$}  (log2 message size)",
+        " ".repeat(9),
+        left,
+        right,
+        width = WIDTH.saturating_sub(left.len())
+    )
+    .unwrap();
+    for (index, s) in series.iter().enumerate() {
+        writeln!(out, "  {} {}", MARKERS[index % MARKERS.len()], s.label).unwrap();
+    }
+    out
+}
+
+/// Escape text for an SVG text node or attribute.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_svg(series: &[Series]) -> String {
+    const WIDTH: f64 = 640.0;
+    const HEIGHT: f64 = 400.0;
+    const LEFT: f64 = 70.0;
+    const RIGHT: f64 = 20.0;
+    const TOP: f64 = 20.0;
+    const BOTTOM: f64 = 50.0;
+    let (lo, hi, top) = bounds(series);
+    let plot_w = WIDTH - LEFT - RIGHT;
+    let plot_h = HEIGHT - TOP - BOTTOM;
+    let px = |size: f64| LEFT + (size.max(1.0).log2() - lo) / (hi - lo) * plot_w;
+    let py = |mbps: f64| TOP + plot_h - mbps / top * plot_h;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">"#,
+        w = WIDTH,
+        h = HEIGHT
+    )
+    .unwrap();
+    writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+    writeln!(
+        out,
+        r#"<path d="M{l} {t} V{b} H{r}" fill="none" stroke="black"/>"#,
+        l = LEFT,
+        t = TOP,
+        b = TOP + plot_h,
+        r = LEFT + plot_w
+    )
+    .unwrap();
+    for exponent in lo.ceil() as i32..=hi.floor() as i32 {
+        let x = px(2f64.powi(exponent));
+        writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            x,
+            TOP + plot_h + 16.0,
+            1u64 << exponent.max(0)
+        )
+        .unwrap();
+    }
+    for step in 0..=4 {
+        let mbps = top * step as f64 / 4.0;
+        writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{:.0}</text>"#,
+            LEFT - 6.0,
+            py(mbps) + 4.0,
+            mbps
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">message size (bytes, log2)</text>"#,
+        LEFT + plot_w / 2.0,
+        HEIGHT - 12.0
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"<text transform="translate(16 {:.1}) rotate(-90)" text-anchor="middle">MB/s</text>"#,
+        TOP + plot_h / 2.0
+    )
+    .unwrap();
+    for (index, s) in series.iter().enumerate() {
+        let color = COLORS[index % COLORS.len()];
+        let points: Vec<String> = s
+            .points
+            .iter()
+            .map(|&(size, mbps)| format!("{:.1},{:.1}", px(size), py(mbps)))
+            .collect();
+        writeln!(
+            out,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+            points.join(" "),
+            color
+        )
+        .unwrap();
+        for &(size, mbps) in &s.points {
+            writeln!(
+                out,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="3" fill="{}"/>"#,
+                px(size),
+                py(mbps),
+                color
+            )
+            .unwrap();
+        }
+        let legend_y = TOP + 14.0 + index as f64 * 16.0;
+        writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" fill="{}">{}</text>"#,
+            LEFT + 10.0,
+            legend_y,
+            color,
+            escape(&s.label)
+        )
+        .unwrap();
+    }
+    writeln!(out, "</svg>").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Series> {
+        vec![
+            Series {
+                label: String::from("rust <native>"),
+                points: vec![(32.0, 100.0), (1024.0, 400.0)],
+            },
+            Series {
+                label: String::from("mojo"),
+                points: vec![(32.0, 50.0), (1024.0, 500.0)],
+            },
+        ]
+    }
+
+    /// Every point lands on the grid with its series' marker.
+    #[test]
+    fn ascii_plots_each_series() {
+        let chart = render(ChartKind::Ascii, &sample());
+        let grid: String = chart.lines().filter(|line| line.contains(" |")).collect();
+        assert_eq!(grid.matches('*').count(), 2, "{}", chart);
+        assert_eq!(grid.matches('o').count(), 2, "{}", chart);
+        assert!(chart.contains("  o mojo"));
+        assert!(chart.contains("32 B"));
+        assert!(chart.contains("1024 B"));
+    }
+
+    /// SVG output draws a line per series and escapes labels.
+    #[test]
+    fn svg_draws_polylines() {
+        let chart = render(ChartKind::Svg, &sample());
+        assert!(chart.starts_with("<svg"));
+        assert_eq!(chart.matches("<polyline").count(), 2);
+        assert!(chart.contains("rust &lt;native&gt;"));
+        assert!(chart.trim_end().ends_with("</svg>"));
+    }
+}
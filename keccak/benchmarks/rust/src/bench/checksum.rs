@@ -0,0 +1,103 @@
+//! Checksums folded over every digest a trial produces.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// How digests are folded into the reported checksum. Both modes are
+/// order-independent, so sharded trials combine to the single-threaded value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChecksumMode {
+    /// XOR of `digest[0]`, matching the Mojo and C harnesses and results
+    /// from before `fnv64` became the default. It only sees one byte per
+    /// digest, which lets the optimizer skip most of the finalization on
+    /// some backends, and cancels out over an even number of rounds.
+    Xor,
+    /// Wrapping sum of the 64-bit FNV-1a hash of each full 32-byte digest,
+    /// so every output byte must be computed.
+    #[default]
+    Fnv64,
+}
+
+impl FromStr for ChecksumMode {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "xor" => Ok(ChecksumMode::Xor),
+            "fnv64" => Ok(ChecksumMode::Fnv64),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ChecksumMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChecksumMode::Xor => "xor",
+            ChecksumMode::Fnv64 => "fnv64",
+        })
+    }
+}
+
+impl ChecksumMode {
+    /// Fold one digest into `acc`.
+    pub fn update(self, acc: u64, digest: &[u8; 32]) -> u64 {
+        match self {
+            ChecksumMode::Xor => acc ^ u64::from(digest[0]),
+            ChecksumMode::Fnv64 => acc.wrapping_add(fnv1a64(digest)),
+        }
+    }
+
+    /// Merge checksums accumulated over disjoint sets of digests.
+    pub fn combine(self, a: u64, b: u64) -> u64 {
+        match self {
+            ChecksumMode::Xor => a ^ b,
+            ChecksumMode::Fnv64 => a.wrapping_add(b),
+        }
+    }
+}
+
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// FNV-1a reference vectors.
+    #[test]
+    fn fnv1a64_matches_reference_values() {
+        assert_eq!(fnv1a64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a64(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    /// Unlike XOR of the first byte, fnv64 sees every byte and survives
+    /// repeated digests.
+    #[test]
+    fn fnv64_detects_what_xor_misses() {
+        let digest = [7u8; 32];
+        let mut altered = digest;
+        altered[31] = 8;
+        for mode in [ChecksumMode::Xor, ChecksumMode::Fnv64] {
+            let twice = mode.update(mode.update(0, &digest), &digest);
+            let split = mode.combine(mode.update(0, &digest), mode.update(0, &digest));
+            assert_eq!(twice, split);
+        }
+        let xor = ChecksumMode::Xor;
+        assert_eq!(xor.update(0, &digest), xor.update(0, &altered));
+        assert_eq!(xor.update(xor.update(0, &digest), &digest), 0);
+        let fnv = ChecksumMode::Fnv64;
+        assert_ne!(fnv.update(0, &digest), fnv.update(0, &altered));
+        assert_ne!(fnv.update(fnv.update(0, &digest), &digest), 0);
+    }
+}
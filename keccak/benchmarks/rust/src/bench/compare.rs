@@ -0,0 +1,293 @@
+//! Loading and validation of result files from several harnesses.
+//!
+//! Accepts any mix of files holding one result object, a JSON array of
+//! them, or one object per line, as written by this binary, `mojo_benchmark.mojo --json`, the C
+//! baseline, or `run_full_benchmarks.py --json`.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::bench::output::{BenchResult, KECCAK256};
+use crate::bench::schema;
+
+/// Config keys that change what gets hashed. Knobs such as `trials` or
+/// `threads` only affect how it is measured, so they may differ.
+const WORKLOAD_KEYS: &[&str] = &[
+    "profile",
+    "corpus",
+    "length_dist",
+    "seed",
+    "messages",
+    "rounds",
+    "min_len",
+    "max_len",
+    "stride",
+];
+
+/// The subset of a result record that comparison needs.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Record {
+    pub implementation: String,
+    pub seconds: f64,
+    pub hashes_per_second: f64,
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    #[serde(default)]
+    pub operation: Option<String>,
+    #[serde(default)]
+    pub checksum: Option<u64>,
+    #[serde(default)]
+    pub mb_per_second: Option<f64>,
+    #[serde(default)]
+    pub config: Option<Value>,
+    #[serde(default)]
+    pub environment: Option<Value>,
+    #[serde(default)]
+    pub noisy: bool,
+    #[serde(default)]
+    pub rounds: Option<Value>,
+}
+
+impl Record {
+    /// View a result produced by this binary as a comparison record.
+    pub fn from_result(result: &BenchResult) -> Record {
+        let value = serde_json::to_value(result).expect("bench results serialize to JSON");
+        serde_json::from_value(value).expect("bench results parse as comparison records")
+    }
+
+    /// Why the run that produced this record should not be trusted, if its
+    /// harness flagged it as noisy, throttled, or having outlier rounds.
+    pub fn dirty(&self) -> Option<String> {
+        let mut reasons = Vec::new();
+        if self.noisy {
+            reasons.push(String::from("noisy"));
+        }
+        if let Some(rounds) = &self.rounds {
+            if rounds.get("throttling").and_then(Value::as_bool) == Some(true) {
+                reasons.push(String::from("throttling"));
+            }
+            match rounds.get("outliers").and_then(Value::as_array) {
+                Some(outliers) if !outliers.is_empty() => {
+                    reasons.push(format!("{} outlier rounds", outliers.len()))
+                }
+                _ => {}
+            }
+        }
+        (!reasons.is_empty()).then(|| reasons.join(", "))
+    }
+}
+
+/// Split harness output into result objects; `source` names it in errors.
+pub fn parse_values(text: &str, source: &str) -> Result<Vec<Value>, String> {
+    match serde_json::from_str(text) {
+        Ok(Value::Array(items)) => Ok(items),
+        Ok(other) => Ok(vec![other]),
+        // Files written with `--output --append` hold one object per line.
+        Err(err) => text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<Value>, _>>()
+            .map_err(|_| format!("{}: {}", source, err)),
+    }
+}
+
+/// Read every record from a result file.
+pub fn load_records(path: &str) -> Result<Vec<Record>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    parse_values(&text, path)?
+        .into_iter()
+        .map(|item| {
+            schema::check_version(&item).map_err(|err| format!("{}: {}", path, err))?;
+            serde_json::from_value(item).map_err(|err| format!("{}: {}", path, err))
+        })
+        .collect()
+}
+
+/// Project a config onto the keys that define the workload.
+fn workload_of(config: &Value) -> Vec<(&'static str, Value)> {
+    WORKLOAD_KEYS
+        .iter()
+        .map(|&key| (key, config.get(key).cloned().unwrap_or(Value::Null)))
+        .collect()
+}
+
+/// What a record timed. Harnesses that predate `operation` only hash.
+fn operation(record: &Record) -> &str {
+    record.operation.as_deref().unwrap_or(KECCAK256)
+}
+
+/// The checksum mode a record was produced with. Harnesses that predate
+/// `checksum_mode` only implement the `xor` fold.
+fn checksum_mode(record: &Record) -> &str {
+    record
+        .config
+        .as_ref()
+        .and_then(|config| config.get("checksum_mode"))
+        .and_then(Value::as_str)
+        .unwrap_or("xor")
+}
+
+/// Problems that make the records incomparable.
+pub fn validate(records: &[Record]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if let Some(first) = records.first() {
+        for record in &records[1..] {
+            if operation(record) != operation(first) {
+                problems.push(format!(
+                    "operation mismatch: {} timed {} but {} timed {}",
+                    first.implementation,
+                    operation(first),
+                    record.implementation,
+                    operation(record)
+                ));
+            }
+        }
+    }
+
+    let mut configs = records
+        .iter()
+        .filter_map(|record| record.config.as_ref().map(|config| (record, config)));
+    if let Some((first, reference)) = configs.next() {
+        let expected = workload_of(reference);
+        for (record, config) in configs {
+            for ((key, want), (_, got)) in expected.iter().zip(workload_of(config)) {
+                if *want != got {
+                    problems.push(format!(
+                        "config mismatch: {} has {}={} but {} has {}={}",
+                        first.implementation, key, want, record.implementation, key, got
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut checksums = records
+        .iter()
+        .filter_map(|record| record.checksum.map(|checksum| (record, checksum)));
+    if let Some((first, expected)) = checksums.next() {
+        let mode = checksum_mode(first);
+        for (record, checksum) in checksums {
+            if checksum_mode(record) != mode {
+                problems.push(format!(
+                    "checksum mode mismatch: {} used {} but {} used {}",
+                    first.implementation,
+                    mode,
+                    record.implementation,
+                    checksum_mode(record)
+                ));
+            } else if checksum != expected {
+                problems.push(format!(
+                    "checksum mismatch: {} reported {} but {} reported {}",
+                    first.implementation, expected, record.implementation, checksum
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(json: &str) -> Record {
+        serde_json::from_str(json).unwrap()
+    }
+
+    /// Records without a config (e.g. from the Mojo harness) still compare.
+    #[test]
+    fn matching_results_validate() {
+        let records = vec![
+            record(
+                r#"{"implementation": "mojo", "seconds": 2.0, "hashes_per_second": 5.0, "checksum": 67}"#,
+            ),
+            record(
+                r#"{"implementation": "rust", "seconds": 1.0, "hashes_per_second": 10.0, "checksum": 67,
+                    "config": {"messages": 512, "rounds": 200, "trials": 5}}"#,
+            ),
+            record(
+                r#"{"implementation": "rust-mt", "seconds": 0.5, "hashes_per_second": 20.0, "checksum": 67,
+                    "config": {"messages": 512, "rounds": 200, "trials": 1}}"#,
+            ),
+        ];
+        assert!(validate(&records).is_empty());
+    }
+
+    /// Workload and checksum disagreements are both reported.
+    #[test]
+    fn mismatches_are_reported() {
+        let records = vec![
+            record(
+                r#"{"implementation": "a", "seconds": 1.0, "hashes_per_second": 1.0, "checksum": 1,
+                    "config": {"messages": 512}}"#,
+            ),
+            record(
+                r#"{"implementation": "b", "seconds": 1.0, "hashes_per_second": 1.0, "checksum": 2,
+                    "config": {"messages": 256}}"#,
+            ),
+        ];
+        let problems = validate(&records);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("messages"));
+        assert!(problems[1].contains("checksum"));
+    }
+
+    /// Checksums folded differently are flagged rather than compared.
+    #[test]
+    fn checksum_modes_must_agree() {
+        let records = vec![
+            record(
+                r#"{"implementation": "mojo", "seconds": 1.0, "hashes_per_second": 1.0, "checksum": 0}"#,
+            ),
+            record(
+                r#"{"implementation": "rust", "seconds": 1.0, "hashes_per_second": 1.0, "checksum": 9,
+                    "config": {"checksum_mode": "fnv64"}}"#,
+            ),
+        ];
+        let problems = validate(&records);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("checksum mode mismatch"));
+    }
+
+    /// Records without an operation are Keccak-256 results.
+    #[test]
+    fn operations_must_agree() {
+        let records = vec![
+            record(r#"{"implementation": "mojo", "seconds": 1.0, "hashes_per_second": 1.0}"#),
+            record(
+                r#"{"implementation": "rust", "seconds": 1.0, "hashes_per_second": 1.0,
+                    "operation": "keccak256"}"#,
+            ),
+            record(
+                r#"{"implementation": "mojo sign", "seconds": 1.0, "hashes_per_second": 1.0,
+                    "operation": "ecdsa-sign"}"#,
+            ),
+        ];
+        let problems = validate(&records);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("operation mismatch: mojo timed keccak256"));
+    }
+
+    /// Noisy, throttled, and outlier-laden runs are dirty; records from
+    /// harnesses that do not flag rounds are clean.
+    #[test]
+    fn dirty_runs_are_named() {
+        let clean =
+            record(r#"{"implementation": "mojo", "seconds": 1.0, "hashes_per_second": 1.0}"#);
+        assert_eq!(clean.dirty(), None);
+        let dirty = record(
+            r#"{"implementation": "rust", "seconds": 1.0, "hashes_per_second": 1.0, "noisy": true,
+                "rounds": {"outliers": [3, 9], "throttling": true}}"#,
+        );
+        assert_eq!(
+            dirty.dirty().as_deref(),
+            Some("noisy, throttling, 2 outlier rounds")
+        );
+    }
+}
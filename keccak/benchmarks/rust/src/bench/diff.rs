@@ -0,0 +1,320 @@
+//! Metric-by-metric changes between two result files for `bench diff`.
+//!
+//! Results are paired by implementation name, in file order, so the points
+//! of a sweep pair up with the same points of the other run. Metrics that
+//! come from the trial times are also tested for significance: a
+//! Mann-Whitney U test over both runs' `trial_seconds` decides whether a
+//! change beyond its threshold is real or noise.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::bench::stats;
+
+/// A metric `bench diff` compares: its name, where it lives in a result
+/// record, and which direction is better.
+struct Metric {
+    name: &'static str,
+    pointer: &'static str,
+    higher_is_better: bool,
+    /// Whether the metric is derived from the per-trial seconds.
+    per_trial: bool,
+}
+
+const METRICS: &[Metric] = &[
+    Metric {
+        name: "seconds",
+        pointer: "/seconds",
+        higher_is_better: false,
+        per_trial: true,
+    },
+    Metric {
+        name: "hashes_per_second",
+        pointer: "/hashes_per_second",
+        higher_is_better: true,
+        per_trial: true,
+    },
+    Metric {
+        name: "mb_per_second",
+        pointer: "/mb_per_second",
+        higher_is_better: true,
+        per_trial: true,
+    },
+    Metric {
+        name: "cycles_per_byte",
+        pointer: "/cycles_per_byte",
+        higher_is_better: false,
+        per_trial: false,
+    },
+    Metric {
+        name: "hashes_per_joule",
+        pointer: "/energy/hashes_per_joule",
+        higher_is_better: true,
+        per_trial: false,
+    },
+    Metric {
+        name: "latency_p50_ns",
+        pointer: "/latency/p50_ns",
+        higher_is_better: false,
+        per_trial: false,
+    },
+    Metric {
+        name: "latency_p99_ns",
+        pointer: "/latency/p99_ns",
+        higher_is_better: false,
+        per_trial: false,
+    },
+];
+
+/// Relative change below which a metric counts as unchanged, and the
+/// significance level a change beyond it must reach.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Thresholds {
+    pub default: f64,
+    pub per_metric: Vec<(String, f64)>,
+    /// Largest p-value that counts as significant.
+    pub alpha: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            default: 0.05,
+            per_metric: Vec::new(),
+            alpha: 0.05,
+        }
+    }
+}
+
+impl Thresholds {
+    /// Apply a `--threshold` value: `F` sets the default, `METRIC=F`
+    /// overrides one metric.
+    pub fn set(&mut self, value: &str) -> Result<(), String> {
+        let fraction = |raw: &str| match raw.parse::<f64>() {
+            Ok(fraction) if fraction >= 0.0 => Ok(fraction),
+            _ => Err(format!("{:?} is not a non-negative fraction", raw)),
+        };
+        match value.split_once('=') {
+            Some((metric, raw)) => {
+                if !METRICS.iter().any(|known| known.name == metric) {
+                    let names: Vec<&str> = METRICS.iter().map(|known| known.name).collect();
+                    return Err(format!(
+                        "unknown metric {:?}; expected one of {}",
+                        metric,
+                        names.join(", ")
+                    ));
+                }
+                let fraction = fraction(raw)?;
+                self.per_metric.retain(|(name, _)| name != metric);
+                self.per_metric.push((metric.to_string(), fraction));
+            }
+            None => self.default = fraction(value)?,
+        }
+        Ok(())
+    }
+
+    /// Apply an `--alpha` value.
+    pub fn set_alpha(&mut self, value: &str) -> Result<(), String> {
+        match value.parse::<f64>() {
+            Ok(alpha) if alpha > 0.0 && alpha < 1.0 => {
+                self.alpha = alpha;
+                Ok(())
+            }
+            _ => Err(format!("{:?} is not a level between 0 and 1", value)),
+        }
+    }
+
+    fn of(&self, metric: &str) -> f64 {
+        self.per_metric
+            .iter()
+            .find(|(name, _)| name == metric)
+            .map_or(self.default, |&(_, fraction)| fraction)
+    }
+}
+
+/// Direction of a change, judged against its threshold and, with several
+/// trials on each side, its significance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Improved,
+    Regressed,
+    Unchanged,
+    /// Beyond the threshold, but the trials do not show a significant
+    /// difference.
+    Noise,
+}
+
+/// One metric of one paired result.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Change {
+    pub implementation: String,
+    pub metric: &'static str,
+    pub before: f64,
+    pub after: f64,
+    /// `after / before - 1`.
+    pub relative: f64,
+    pub threshold: f64,
+    /// Mann-Whitney p-value over the trial seconds, when both runs have at
+    /// least two trials and the metric comes from them.
+    pub p_value: Option<f64>,
+    pub verdict: Verdict,
+}
+
+/// What [`diff`] found.
+#[derive(Debug, Default, Serialize)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+    /// Results present in only one of the files.
+    pub unmatched: Vec<String>,
+}
+
+impl Diff {
+    pub fn count(&self, verdict: Verdict) -> usize {
+        self.changes
+            .iter()
+            .filter(|change| change.verdict == verdict)
+            .count()
+    }
+}
+
+fn implementation(record: &Value) -> String {
+    record
+        .get("implementation")
+        .and_then(Value::as_str)
+        .unwrap_or("?")
+        .to_string()
+}
+
+/// Compare `after` against `before`. Metrics missing from either side, or
+/// zero before, are skipped.
+pub fn diff(before: &[Value], after: &[Value], thresholds: &Thresholds) -> Diff {
+    let mut result = Diff::default();
+    let mut used = vec![false; after.len()];
+    for old in before {
+        let name = implementation(old);
+        let Some(index) = (0..after.len()).find(|&i| !used[i] && implementation(&after[i]) == name)
+        else {
+            result.unmatched.push(format!("{} (only before)", name));
+            continue;
+        };
+        used[index] = true;
+        let trials = |record: &Value| -> Option<Vec<f64>> {
+            record
+                .get("trial_seconds")?
+                .as_array()?
+                .iter()
+                .map(Value::as_f64)
+                .collect()
+        };
+        let p_value = trials(old)
+            .zip(trials(&after[index]))
+            .and_then(|(from, to)| stats::mann_whitney(&from, &to));
+        for metric in METRICS {
+            let value = |record: &Value| record.pointer(metric.pointer).and_then(Value::as_f64);
+            let (Some(from), Some(to)) = (value(old), value(&after[index])) else {
+                continue;
+            };
+            if from == 0.0 {
+                continue;
+            }
+            let relative = to / from - 1.0;
+            let threshold = thresholds.of(metric.name);
+            let p_value = p_value.filter(|_| metric.per_trial);
+            let verdict = if relative.abs() <= threshold {
+                Verdict::Unchanged
+            } else if p_value.is_some_and(|p| p > thresholds.alpha) {
+                Verdict::Noise
+            } else if (relative > 0.0) == metric.higher_is_better {
+                Verdict::Improved
+            } else {
+                Verdict::Regressed
+            };
+            result.changes.push(Change {
+                implementation: name.clone(),
+                metric: metric.name,
+                before: from,
+                after: to,
+                relative,
+                threshold,
+                p_value,
+                verdict,
+            });
+        }
+    }
+    for (record, used) in after.iter().zip(used) {
+        if !used {
+            result
+                .unmatched
+                .push(format!("{} (only after)", implementation(record)));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(json: &str) -> Vec<Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    /// Direction decides improved versus regressed, thresholds decide
+    /// unchanged, and unpaired results are listed.
+    #[test]
+    fn judges_changes_by_direction_and_threshold() {
+        let before = records(
+            r#"[{"implementation": "rust", "seconds": 1.0, "hashes_per_second": 100.0,
+                 "latency": {"p50_ns": 50.0}},
+                {"implementation": "mojo", "seconds": 1.0, "hashes_per_second": 100.0}]"#,
+        );
+        let after = records(
+            r#"[{"implementation": "rust", "seconds": 0.9, "hashes_per_second": 111.0,
+                 "latency": {"p50_ns": 60.0}},
+                {"implementation": "c", "seconds": 1.0, "hashes_per_second": 1.0}]"#,
+        );
+        let mut thresholds = Thresholds::default();
+        thresholds.set("seconds=0.2").unwrap();
+        let diff = diff(&before, &after, &thresholds);
+        let verdicts: Vec<(&str, Verdict)> = diff
+            .changes
+            .iter()
+            .map(|change| (change.metric, change.verdict))
+            .collect();
+        assert_eq!(
+            verdicts,
+            [
+                ("seconds", Verdict::Unchanged),
+                ("hashes_per_second", Verdict::Improved),
+                ("latency_p50_ns", Verdict::Regressed),
+            ]
+        );
+        assert_eq!(diff.unmatched, ["mojo (only before)", "c (only after)"]);
+        assert!(thresholds.set("speed=0.1").is_err());
+        assert!(thresholds.set("-1").is_err());
+    }
+
+    /// With trials on both sides, overlapping trial times make a change
+    /// noise while separated ones keep their verdict.
+    #[test]
+    fn insignificant_changes_are_noise() {
+        let before = records(
+            r#"[{"implementation": "a", "seconds": 1.0, "trial_seconds": [1.0, 0.9, 1.1, 1.2]},
+                {"implementation": "b", "seconds": 1.0, "trial_seconds": [1.0, 1.01, 0.99, 1.02]}]"#,
+        );
+        let after = records(
+            r#"[{"implementation": "a", "seconds": 1.1, "trial_seconds": [1.1, 0.95, 1.15, 1.05]},
+                {"implementation": "b", "seconds": 1.1, "trial_seconds": [1.1, 1.11, 1.09, 1.12]}]"#,
+        );
+        let diff = diff(&before, &after, &Thresholds::default());
+        let verdicts: Vec<(&str, Verdict)> = diff
+            .changes
+            .iter()
+            .map(|change| (change.implementation.as_str(), change.verdict))
+            .collect();
+        assert_eq!(verdicts, [("a", Verdict::Noise), ("b", Verdict::Regressed)]);
+        assert!((diff.changes[1].p_value.unwrap() - 2.0 / 70.0).abs() < 1e-12);
+        assert!(Thresholds::default().set_alpha("1.5").is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! Binary dumps of a workload for `bench dump-corpus`, so other harnesses
+//! can hash the exact bytes instead of re-implementing the generator.
+//!
+//! `<prefix>.bin` holds every message back to back in index order.
+//! `<prefix>.manifest` is text: `#` comment lines naming the data file and
+//! the config as JSON, then one `<offset> <length> <keccak256 hex>` line per
+//! message, so a reader can slice the data and check its digests.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::bench::workload::Workload;
+use crate::bench::BenchConfig;
+use crate::{keccak256, to_hex_string};
+
+/// What [`write`] produced.
+pub struct Dump {
+    pub data: String,
+    pub manifest: String,
+    pub messages: usize,
+    pub bytes: u64,
+}
+
+/// The data file and manifest contents for `workload`.
+pub fn render(config: &BenchConfig, workload: &Workload, data_name: &str) -> (Vec<u8>, String) {
+    let mut data = Vec::new();
+    let mut manifest = String::new();
+    writeln!(manifest, "# keccak256 benchmark corpus").unwrap();
+    writeln!(manifest, "# data {}", data_name).unwrap();
+    writeln!(
+        manifest,
+        "# config {}",
+        serde_json::to_string(config).expect("configs serialize to JSON")
+    )
+    .unwrap();
+    writeln!(manifest, "# offset length keccak256").unwrap();
+    for index in 0..workload.count(config) {
+        let message = workload.message(config, index);
+        writeln!(
+            manifest,
+            "{} {} {}",
+            data.len(),
+            message.len(),
+            to_hex_string(&keccak256(&message))
+        )
+        .unwrap();
+        data.extend_from_slice(&message);
+    }
+    (data, manifest)
+}
+
+/// Write `<prefix>.bin` and `<prefix>.manifest`.
+pub fn write(config: &BenchConfig, workload: &Workload, prefix: &str) -> Result<Dump, String> {
+    let data_path = format!("{}.bin", prefix);
+    let manifest_path = format!("{}.manifest", prefix);
+    let data_name = Path::new(&data_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("{}: not a file path", prefix))?;
+    let (data, manifest) = render(config, workload, &data_name);
+    fs::write(&data_path, &data).map_err(|err| format!("{}: {}", data_path, err))?;
+    fs::write(&manifest_path, manifest).map_err(|err| format!("{}: {}", manifest_path, err))?;
+    Ok(Dump {
+        data: data_path,
+        manifest: manifest_path,
+        messages: workload.count(config),
+        bytes: data.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::workload::generate_message;
+
+    /// Manifest lines slice the data file back into the generated messages.
+    #[test]
+    fn manifest_slices_data_into_messages() {
+        let config = BenchConfig {
+            messages: 5,
+            seed: Some(3),
+            ..BenchConfig::default()
+        };
+        let (data, manifest) = render(&config, &Workload::Synthetic, "corpus.bin");
+        assert!(manifest.contains("# data corpus.bin\n"));
+        let entries: Vec<&str> = manifest.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(entries.len(), 5);
+        for (index, line) in entries.iter().enumerate() {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let offset: usize = fields[0].parse().unwrap();
+            let length: usize = fields[1].parse().unwrap();
+            let message = &data[offset..offset + length];
+            assert_eq!(message, generate_message(&config, index).as_slice());
+            assert_eq!(fields[2], to_hex_string(&keccak256(message)));
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! secp256k1 benchmark targets, so signature throughput is timed, checked
+//! and reported by the same harness as hashing.
+//!
+//! Each message's Keccak-256, through the config's backend, is the hash
+//! that is signed or checked, as Ethereum signs transaction hashes. The
+//! curve arithmetic costs thousands of times more than that hash, so
+//! `hashes_per_second` reads as operations per second, and a run wants a
+//! small workload such as `--messages 256 --rounds 2`.
+//!
+//! [`Recover`] recovers the key behind one fixed signature over each
+//! message's hash; any hash gives some key, for which the signature is
+//! valid. [`Verify`] checks the same signature against one of a pool of
+//! such hashes and keys, recovered during setup and picked by the
+//! message's hash, so every verification succeeds. `Keygen` and `Sign`
+//! use the variable-time fixture signer and so need the `test-utils`
+//! feature; `Sign` signs as the `alice` test key.
+
+use std::sync::OnceLock;
+
+use crate::bench::target::BenchTarget;
+use crate::bench::BenchConfig;
+use crate::keccak256;
+use crate::secp256k1::{AffinePoint, Scalar};
+use crate::signature::Signature;
+
+/// A signature from the `single-signer` scenario, with an odd nonce `y`.
+const SIGNATURE: Signature = Signature {
+    r: Scalar::from_reduced([
+        0x19, 0x2d, 0x4e, 0x05, 0xce, 0x9d, 0xda, 0x59, 0xfd, 0xe7, 0x7b, 0x1a, 0x85, 0x8f, 0xa6,
+        0x9f, 0x53, 0xd8, 0xfa, 0xc3, 0x4b, 0x9d, 0xe9, 0xf1, 0x6e, 0x03, 0x7a, 0x0e, 0x9a, 0x58,
+        0x26, 0x7c,
+    ]),
+    s: Scalar::from_reduced([
+        0x5f, 0x15, 0xd4, 0xc0, 0x63, 0x79, 0x9c, 0x07, 0x84, 0x09, 0x60, 0xd9, 0xc5, 0xe1, 0x9f,
+        0x2d, 0xda, 0xf9, 0x51, 0x0f, 0x06, 0x79, 0x58, 0x76, 0xaf, 0x3b, 0x1b, 0x82, 0x25, 0xd5,
+        0x8c, 0x94,
+    ]),
+};
+
+/// How many hash and key pairs [`Verify`] cycles through.
+pub const POOL_SIZE: usize = 16;
+
+/// The Keccak-256 of a point's 64 coordinate bytes, whose last 20 bytes
+/// are its Ethereum address.
+fn key_digest(point: &AffinePoint) -> [u8; 32] {
+    keccak256(&point.to_uncompressed()[1..])
+}
+
+fn recover(hash: &[u8; 32]) -> AffinePoint {
+    SIGNATURE
+        .recover(hash, true)
+        .expect("a fixed r recovers a key for all but a negligible set of hashes")
+}
+
+/// `(hash, key)` pairs [`SIGNATURE`] is valid for.
+fn pool() -> &'static [([u8; 32], AffinePoint)] {
+    static POOL: OnceLock<Vec<([u8; 32], AffinePoint)>> = OnceLock::new();
+    POOL.get_or_init(|| {
+        (0..POOL_SIZE as u8)
+            .map(|index| {
+                let hash = keccak256(&[index]);
+                (hash, recover(&hash))
+            })
+            .collect()
+    })
+}
+
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+fn alice() -> &'static crate::test_keys::TestKey {
+    crate::test_keys::get("alice").expect("a named test key")
+}
+
+/// The checks every curve target shares.
+fn one_at_a_time(name: &str, config: &BenchConfig) -> Result<(), String> {
+    if config.batch.is_some() || config.chunk_size.is_some() {
+        return Err(format!(
+            "{} takes one message at a time; drop batch and chunk_size",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Public-key recovery, as `ecrecover` runs it.
+pub struct Recover;
+
+impl BenchTarget for Recover {
+    fn name(&self) -> &'static str {
+        "ecdsa-recover"
+    }
+
+    fn description(&self) -> &'static str {
+        "secp256k1 public-key recovery from a signature over each message's hash"
+    }
+
+    fn setup(&self, config: &BenchConfig) -> Result<(), String> {
+        one_at_a_time(self.name(), config)
+    }
+
+    fn run_batch(&self, config: &BenchConfig, messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+        for (message, digest) in messages.iter().zip(digests) {
+            *digest = key_digest(&recover(&config.backend.hash(message)));
+        }
+    }
+}
+
+/// ECDSA verification against a known key. The digest is the pool hash
+/// when the signature verifies and zero when it does not.
+pub struct Verify;
+
+impl BenchTarget for Verify {
+    fn name(&self) -> &'static str {
+        "ecdsa-verify"
+    }
+
+    fn description(&self) -> &'static str {
+        "secp256k1 ECDSA verification against a pool of known keys"
+    }
+
+    fn setup(&self, config: &BenchConfig) -> Result<(), String> {
+        one_at_a_time(self.name(), config)?;
+        // Recover the pool now rather than in the first timed trial.
+        pool();
+        Ok(())
+    }
+
+    fn run_batch(&self, config: &BenchConfig, messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+        let pool = pool();
+        for (message, digest) in messages.iter().zip(digests) {
+            let (hash, key) = &pool[usize::from(config.backend.hash(message)[0]) % POOL_SIZE];
+            *digest = if SIGNATURE.verify(hash, key) {
+                *hash
+            } else {
+                [0; 32]
+            };
+        }
+    }
+}
+
+/// Public keys from each message's hash taken as the private key.
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+pub struct Keygen;
+
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+impl BenchTarget for Keygen {
+    fn name(&self) -> &'static str {
+        "secp256k1-keygen"
+    }
+
+    fn description(&self) -> &'static str {
+        "secp256k1 public keys from fixture private keys (test-utils)"
+    }
+
+    fn setup(&self, config: &BenchConfig) -> Result<(), String> {
+        one_at_a_time(self.name(), config)
+    }
+
+    fn run_batch(&self, config: &BenchConfig, messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+        for (message, digest) in messages.iter().zip(digests) {
+            let key = Scalar::reduce(&config.backend.hash(message));
+            *digest = crate::secp256k1::G
+                .mul(&key)
+                .map_or([0; 32], |point| key_digest(&point));
+        }
+    }
+}
+
+/// RFC 6979 signing with the fixture signer as `alice` from
+/// `test_keys`; the digest is the Keccak-256 of `r || s || v`.
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+pub struct Sign;
+
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+impl BenchTarget for Sign {
+    fn name(&self) -> &'static str {
+        "ecdsa-sign"
+    }
+
+    fn description(&self) -> &'static str {
+        "secp256k1 RFC 6979 signing with the variable-time fixture signer (test-utils)"
+    }
+
+    fn setup(&self, config: &BenchConfig) -> Result<(), String> {
+        one_at_a_time(self.name(), config)?;
+        // Derive the key now rather than in the first timed trial.
+        alice();
+        Ok(())
+    }
+
+    fn run_batch(&self, config: &BenchConfig, messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+        let key = alice().key;
+        for (message, digest) in messages.iter().zip(digests) {
+            let (signature, odd) = crate::scenarios::sign(&key, &config.backend.hash(message));
+            let mut signed = [0u8; 65];
+            signed[..64].copy_from_slice(&signature.to_compact());
+            signed[64] = u8::from(odd);
+            *digest = keccak256(&signed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Recovered keys are the ones the signature verifies for, and the
+    /// verify target reports success for every message.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn targets_agree_with_each_other() {
+        let config = BenchConfig::default();
+        let messages: [&[u8]; 2] = [b"abc", b""];
+        let mut digests = [[0u8; 32]; 2];
+        Recover.run_batch(&config, &messages, &mut digests);
+        for (message, digest) in messages.iter().zip(&digests) {
+            let hash = config.backend.hash(message);
+            let key = recover(&hash);
+            assert!(SIGNATURE.verify(&hash, &key));
+            assert_eq!(*digest, key_digest(&key));
+        }
+        Verify.run_batch(&config, &messages, &mut digests);
+        for digest in &digests {
+            assert!(pool().iter().any(|(hash, _)| hash == digest));
+        }
+        let batched = BenchConfig {
+            batch: Some(4),
+            ..BenchConfig::default()
+        };
+        assert!(Recover.setup(&batched).is_err());
+    }
+
+    /// The sign target signs with its fixed key, and a fixture signature by
+    /// the keygen target's key recovers to the key it reports.
+    #[test]
+    #[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn sign_and_keygen_match_the_fixture_signer() {
+        let config = BenchConfig::default();
+        let hash = config.backend.hash(b"abc");
+        let (signature, odd) = crate::scenarios::sign(&alice().key, &hash);
+        let mut digest = [[0u8; 32]];
+        Sign.run_batch(&config, &[b"abc"], &mut digest);
+        let mut signed = [0u8; 65];
+        signed[..64].copy_from_slice(&signature.to_compact());
+        signed[64] = u8::from(odd);
+        assert_eq!(digest[0], keccak256(&signed));
+
+        Keygen.run_batch(&config, &[b"abc"], &mut digest);
+        let (signature, odd) = crate::scenarios::sign(&Scalar::reduce(&hash), &hash);
+        let public = signature.recover(&hash, odd).unwrap();
+        assert!(signature.verify(&hash, &public));
+        assert_eq!(digest[0], key_digest(&public));
+    }
+}
@@ -0,0 +1,177 @@
+//! Package energy via the powercap RAPL interface (Linux, `energy`
+//! feature).
+//!
+//! Energy is read from every top-level `intel-rapl:N` zone under
+//! `/sys/class/powercap` (one per CPU package; AMD exposes the same zones)
+//! before and after each trial. The counters update about once a
+//! millisecond and cover the whole package, so short trials and busy
+//! machines give rough figures. Recent kernels make `energy_uj` readable
+//! by root only.
+
+use serde::Serialize;
+
+/// Energy of the median trial, as reported.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EnergySummary {
+    /// RAPL zones summed, e.g. `package-0`.
+    pub domains: Vec<String>,
+    pub joules: f64,
+    pub watts: f64,
+    pub hashes_per_joule: f64,
+}
+
+impl EnergySummary {
+    pub fn new(domains: Vec<String>, joules: f64, seconds: f64, hashes: u64) -> EnergySummary {
+        EnergySummary {
+            domains,
+            joules,
+            watts: if seconds > 0.0 { joules / seconds } else { 0.0 },
+            hashes_per_joule: if joules > 0.0 {
+                hashes as f64 / joules
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Microjoules between two readings of a counter that wraps at `range`.
+#[cfg_attr(not(all(feature = "energy", target_os = "linux")), allow(dead_code))]
+fn elapsed_uj(start: u64, end: u64, range: u64) -> u64 {
+    if end >= start {
+        end - start
+    } else {
+        range.saturating_sub(start) + end
+    }
+}
+
+#[cfg(all(feature = "energy", target_os = "linux"))]
+mod imp {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::elapsed_uj;
+
+    const POWERCAP: &str = "/sys/class/powercap";
+
+    struct Zone {
+        energy: PathBuf,
+        range: u64,
+        start: u64,
+    }
+
+    fn read_u64(path: &Path) -> Option<u64> {
+        fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+
+    /// Top-level package zones; `intel-rapl:0:0` and the like are
+    /// subdomains already counted in their package.
+    fn package_zones() -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(POWERCAP) else {
+            return Vec::new();
+        };
+        let mut zones: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_prefix("intel-rapl:"))
+                    .is_some_and(|index| index.parse::<u32>().is_ok())
+            })
+            .map(|entry| entry.path())
+            .collect();
+        zones.sort();
+        zones
+    }
+
+    /// Names of the zones a session reads, or empty when none is readable.
+    pub fn domains() -> Vec<String> {
+        package_zones()
+            .into_iter()
+            .filter(|zone| read_u64(&zone.join("energy_uj")).is_some())
+            .map(|zone| {
+                fs::read_to_string(zone.join("name"))
+                    .map(|name| name.trim().to_string())
+                    .unwrap_or_else(|_| zone.display().to_string())
+            })
+            .collect()
+    }
+
+    pub struct Session {
+        zones: Vec<Zone>,
+    }
+
+    impl Session {
+        pub fn start() -> Option<Session> {
+            let zones: Vec<Zone> = package_zones()
+                .into_iter()
+                .filter_map(|zone| {
+                    let energy = zone.join("energy_uj");
+                    Some(Zone {
+                        range: read_u64(&zone.join("max_energy_range_uj")).unwrap_or(u64::MAX),
+                        start: read_u64(&energy)?,
+                        energy,
+                    })
+                })
+                .collect();
+            (!zones.is_empty()).then_some(Session { zones })
+        }
+
+        /// Joules used by all packages since `start`.
+        pub fn stop(self) -> Option<f64> {
+            let mut microjoules = 0;
+            for zone in &self.zones {
+                let end = read_u64(&zone.energy)?;
+                microjoules += elapsed_uj(zone.start, end, zone.range);
+            }
+            Some(microjoules as f64 / 1e6)
+        }
+    }
+}
+
+#[cfg(not(all(feature = "energy", target_os = "linux")))]
+mod imp {
+    pub fn domains() -> Vec<String> {
+        Vec::new()
+    }
+
+    pub struct Session;
+
+    impl Session {
+        pub fn start() -> Option<Session> {
+            None
+        }
+
+        pub fn stop(self) -> Option<f64> {
+            None
+        }
+    }
+}
+
+/// Package energy over one measured region; see [`Session::start`].
+pub use imp::{domains, Session};
+
+/// Whether this binary was built with energy support.
+pub fn supported() -> bool {
+    cfg!(all(feature = "energy", target_os = "linux"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Readings that wrapped past the zone's range still count forward,
+    /// and the summary guards against zero time and energy.
+    #[test]
+    fn energy_handles_wraparound() {
+        assert_eq!(elapsed_uj(100, 350, 1_000), 250);
+        assert_eq!(elapsed_uj(900, 50, 1_000), 150);
+        let summary = EnergySummary::new(vec![String::from("package-0")], 2.0, 0.5, 1_000);
+        assert_eq!((summary.watts, summary.hashes_per_joule), (4.0, 500.0));
+        assert_eq!(
+            EnergySummary::new(Vec::new(), 0.0, 0.0, 5).hashes_per_joule,
+            0.0
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Machine and build metadata recorded with every result.
+
+use std::fs;
+use std::process::Command;
+use std::thread;
+
+use serde::Serialize;
+
+use crate::parallel::dispatch::{self, Simd};
+
+/// Where and how the benchmark ran. Cross-machine numbers are only
+/// comparable with this context.
+#[derive(Debug, Serialize)]
+pub struct Environment {
+    pub os: &'static str,
+    pub arch: &'static str,
+    /// CPU brand string, when the platform exposes one.
+    pub cpu_model: Option<String>,
+    /// Logical CPUs available to this process.
+    pub logical_cores: Option<usize>,
+    pub rustc_version: &'static str,
+    pub crate_version: &'static str,
+    /// Cargo profile the binary was built with, e.g. `release`.
+    pub build_profile: &'static str,
+    /// SIMD-relevant features the binary was compiled to assume.
+    pub target_features: Vec<&'static str>,
+    /// The same features as detected on this CPU at run time.
+    pub cpu_features: Vec<&'static str>,
+    /// The multi-message path `native-auto` batches take.
+    pub simd_dispatch: Simd,
+}
+
+impl Environment {
+    pub fn current() -> Environment {
+        Environment {
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            cpu_model: cpu_model(),
+            logical_cores: thread::available_parallelism().ok().map(usize::from),
+            rustc_version: env!("BENCH_RUSTC_VERSION"),
+            crate_version: env!("CARGO_PKG_VERSION"),
+            build_profile: env!("BENCH_BUILD_PROFILE"),
+            target_features: target_features(),
+            cpu_features: cpu_features(),
+            simd_dispatch: dispatch::selected(),
+        }
+    }
+}
+
+fn cpu_model() -> Option<String> {
+    if cfg!(target_os = "linux") {
+        let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+        cpuinfo.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            matches!(key.trim(), "model name" | "Model" | "cpu model")
+                .then(|| value.trim().to_string())
+        })
+    } else if cfg!(target_os = "macos") {
+        let output = Command::new("sysctl")
+            .args(["-n", "machdep.cpu.brand_string"])
+            .output()
+            .ok()?;
+        let model = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        (!model.is_empty()).then_some(model)
+    } else {
+        None
+    }
+}
+
+macro_rules! enabled_features {
+    ($($feature:tt),*) => {{
+        let mut features = Vec::new();
+        $(
+            if cfg!(target_feature = $feature) {
+                features.push($feature);
+            }
+        )*
+        features
+    }};
+}
+
+fn target_features() -> Vec<&'static str> {
+    enabled_features!("sse2", "ssse3", "sse4.1", "avx", "avx2", "avx512f", "bmi2", "neon", "sha3")
+}
+
+#[cfg(target_arch = "x86_64")]
+fn cpu_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    macro_rules! detect {
+        ($($feature:tt),*) => {
+            $(
+                if std::is_x86_feature_detected!($feature) {
+                    features.push($feature);
+                }
+            )*
+        };
+    }
+    detect!("sse2", "ssse3", "sse4.1", "avx", "avx2", "avx512f", "bmi2");
+    features
+}
+
+#[cfg(target_arch = "aarch64")]
+fn cpu_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    macro_rules! detect {
+        ($($feature:tt),*) => {
+            $(
+                if std::arch::is_aarch64_feature_detected!($feature) {
+                    features.push($feature);
+                }
+            )*
+        };
+    }
+    detect!("neon", "sha3");
+    features
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn cpu_features() -> Vec<&'static str> {
+    Vec::new()
+}
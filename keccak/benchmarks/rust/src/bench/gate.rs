@@ -0,0 +1,96 @@
+//! `--min-throughput`: fail a run whose throughput falls below a floor.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::bench::output::BenchResult;
+
+/// A throughput floor in hashes per second or megabytes per second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MinThroughput {
+    HashesPerSecond(f64),
+    MegabytesPerSecond(f64),
+}
+
+impl FromStr for MinThroughput {
+    type Err = ();
+
+    /// Accepts a bare number or one suffixed with `hashes/s` or `h/s` for
+    /// hashes per second, or `MB/s` for megabytes per second.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let lower = value.to_ascii_lowercase();
+        let (number, megabytes) = if let Some(number) = lower.strip_suffix("mb/s") {
+            (number, true)
+        } else if let Some(number) = lower
+            .strip_suffix("hashes/s")
+            .or_else(|| lower.strip_suffix("h/s"))
+        {
+            (number, false)
+        } else {
+            (lower.as_str(), false)
+        };
+        let number: f64 = number.trim().parse().map_err(|_| ())?;
+        if !number.is_finite() || number < 0.0 {
+            return Err(());
+        }
+        Ok(if megabytes {
+            MinThroughput::MegabytesPerSecond(number)
+        } else {
+            MinThroughput::HashesPerSecond(number)
+        })
+    }
+}
+
+impl fmt::Display for MinThroughput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MinThroughput::HashesPerSecond(floor) => write!(f, "{:.2} hashes/s", floor),
+            MinThroughput::MegabytesPerSecond(floor) => write!(f, "{:.2} MB/s", floor),
+        }
+    }
+}
+
+impl MinThroughput {
+    /// Describe how `result` misses the floor, or `None` when it clears it.
+    pub fn check(&self, result: &BenchResult) -> Option<String> {
+        let (measured, floor, unit) = match *self {
+            MinThroughput::HashesPerSecond(floor) => (result.hashes_per_second, floor, "hashes/s"),
+            MinThroughput::MegabytesPerSecond(floor) => (result.mb_per_second, floor, "MB/s"),
+        };
+        (measured < floor).then(|| {
+            format!(
+                "{}: throughput {:.2} {} is below the minimum of {}",
+                result.implementation, measured, unit, self
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Units are optional and case-insensitive; negatives are rejected.
+    #[test]
+    fn parses_units() {
+        assert_eq!(
+            "1e6".parse::<MinThroughput>(),
+            Ok(MinThroughput::HashesPerSecond(1e6))
+        );
+        assert_eq!(
+            "250000 hashes/s".parse::<MinThroughput>(),
+            Ok(MinThroughput::HashesPerSecond(250_000.0))
+        );
+        assert_eq!(
+            "50MB/s".parse::<MinThroughput>(),
+            Ok(MinThroughput::MegabytesPerSecond(50.0))
+        );
+        assert_eq!(
+            "12.5 mb/s".parse::<MinThroughput>(),
+            Ok(MinThroughput::MegabytesPerSecond(12.5))
+        );
+        assert!("-1".parse::<MinThroughput>().is_err());
+        assert!("fast".parse::<MinThroughput>().is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! The fixed workload behind `--golden`.
+//!
+//! A golden run hashes [`config`]'s messages once, untimed, and compares
+//! every digest with a committed [`verify`] file, so a correctness check
+//! never depends on how fast or quiet the machine is. The workload is
+//! seeded and covers lengths from 0 to 272 bytes in steps of 17 modulo
+//! 273, straddling the 136-byte rate. `keccak/vectors/golden.txt` is its
+//! digest file, computed with a Python Keccak independent of this crate.
+
+use crate::bench::verify::{self, Entry};
+use crate::bench::workload::Workload;
+use crate::bench::BenchConfig;
+
+/// `"golden"` in ASCII.
+pub const SEED: u64 = 0x676f_6c64_656e;
+
+/// The golden workload; only its message parameters matter.
+pub fn config() -> BenchConfig {
+    BenchConfig {
+        seed: Some(SEED),
+        messages: 64,
+        min_len: 0,
+        max_len: 272,
+        stride: 17,
+        ..BenchConfig::default()
+    }
+}
+
+/// The golden workload's digests.
+pub fn compute() -> Vec<Entry> {
+    verify::compute(&config(), &Workload::Synthetic)
+}
+
+/// Every way the golden workload's digests differ from `expected`, as
+/// read from a digest file; empty when they agree.
+pub fn check(expected: &[Entry]) -> Vec<String> {
+    verify::diff(expected, &compute())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The committed golden file matches, and a changed digest does not.
+    #[test]
+    fn committed_file_matches() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../vectors/golden.txt");
+        let mut entries = verify::read(path).unwrap();
+        assert_eq!(entries.len(), 64);
+        assert!(check(&entries).is_empty());
+        assert!(entries.iter().any(|entry| entry.length == 136));
+        entries[5].digest[0] ^= 1;
+        assert_eq!(check(&entries).len(), 1);
+    }
+}
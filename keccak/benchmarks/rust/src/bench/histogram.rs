@@ -0,0 +1,163 @@
+//! Full latency distributions for `--histogram` (the `hdr` feature).
+//!
+//! Every hash latency is recorded into an HDR histogram with three
+//! significant digits. The file written is a standard HdrHistogram interval
+//! log (base64, V2 + deflate), one interval per backend tagged with its
+//! label, so Mojo and Rust distributions can be overlaid with the usual
+//! HdrHistogram tooling. Values are nanoseconds.
+
+use serde::Serialize;
+
+/// Quantiles read back from the histogram, in nanoseconds.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HistogramSummary {
+    pub samples: u64,
+    pub min_ns: u64,
+    pub mean_ns: f64,
+    pub p50_ns: u64,
+    pub p90_ns: u64,
+    pub p99_ns: u64,
+    pub p999_ns: u64,
+    pub p9999_ns: u64,
+    pub max_ns: u64,
+}
+
+#[cfg(feature = "hdr")]
+mod imp {
+    use std::fs::File;
+    use std::io::{BufWriter, Write};
+    use std::time::Duration;
+
+    use hdrhistogram::serialization::interval_log::{IntervalLogWriterBuilder, Tag};
+    use hdrhistogram::serialization::V2DeflateSerializer;
+    use hdrhistogram::Histogram;
+
+    use super::HistogramSummary;
+
+    /// Latencies above a minute are clamped; no single hash takes that long.
+    const HIGHEST_NS: u64 = 60_000_000_000;
+
+    pub struct LatencyHistogram(Histogram<u64>);
+
+    impl LatencyHistogram {
+        pub fn record(latencies_ns: &[f64]) -> Option<LatencyHistogram> {
+            let mut histogram =
+                Histogram::new_with_bounds(1, HIGHEST_NS, 3).expect("histogram bounds are valid");
+            for &latency in latencies_ns {
+                histogram.saturating_record((latency as u64).max(1));
+            }
+            Some(LatencyHistogram(histogram))
+        }
+
+        pub fn summary(&self) -> HistogramSummary {
+            let histogram = &self.0;
+            HistogramSummary {
+                samples: histogram.len(),
+                min_ns: histogram.min(),
+                mean_ns: histogram.mean(),
+                p50_ns: histogram.value_at_quantile(0.5),
+                p90_ns: histogram.value_at_quantile(0.9),
+                p99_ns: histogram.value_at_quantile(0.99),
+                p999_ns: histogram.value_at_quantile(0.999),
+                p9999_ns: histogram.value_at_quantile(0.9999),
+                max_ns: histogram.max(),
+            }
+        }
+    }
+
+    pub fn write_log(
+        path: &str,
+        intervals: &[(&str, &LatencyHistogram, f64)],
+    ) -> Result<(), String> {
+        let describe = |err: &dyn std::fmt::Display| format!("{}: {}", path, err);
+        let file = File::create(path).map_err(|err| describe(&err))?;
+        let mut writer = BufWriter::new(file);
+        let mut serializer = V2DeflateSerializer::new();
+        let mut log = IntervalLogWriterBuilder::new()
+            .add_comment("keccak256 bench per-hash latencies in nanoseconds")
+            .begin_log_with(&mut writer, &mut serializer)
+            .map_err(|err| describe(&err))?;
+        for (label, histogram, seconds) in intervals {
+            // Tags may not contain commas, spaces, or equals signs.
+            let tag: String = label
+                .chars()
+                .map(|c| if matches!(c, ',' | ' ' | '=') { '_' } else { c })
+                .collect();
+            log.write_histogram(
+                &histogram.0,
+                Duration::ZERO,
+                Duration::from_secs_f64(*seconds),
+                Tag::new(&tag),
+            )
+            .map_err(|err| describe(&format!("{:?}", err)))?;
+        }
+        drop(log);
+        writer.flush().map_err(|err| describe(&err))
+    }
+}
+
+#[cfg(not(feature = "hdr"))]
+mod imp {
+    use super::HistogramSummary;
+
+    pub struct LatencyHistogram;
+
+    impl LatencyHistogram {
+        pub fn record(_latencies_ns: &[f64]) -> Option<LatencyHistogram> {
+            None
+        }
+
+        pub fn summary(&self) -> HistogramSummary {
+            unreachable!("histograms are never recorded without the hdr feature")
+        }
+    }
+
+    pub fn write_log(
+        _path: &str,
+        _intervals: &[(&str, &LatencyHistogram, f64)],
+    ) -> Result<(), String> {
+        Err(String::from("built without the hdr feature"))
+    }
+}
+
+pub use imp::{write_log, LatencyHistogram};
+
+/// Whether this binary was built with histogram support.
+pub fn supported() -> bool {
+    cfg!(feature = "hdr")
+}
+
+#[cfg(all(test, feature = "hdr"))]
+mod tests {
+    use super::*;
+
+    /// Quantiles come back within the histogram's precision, and the log
+    /// parses as an interval log.
+    #[test]
+    fn histogram_round_trips_through_log() {
+        let latencies: Vec<f64> = (1..=10_000).map(f64::from).collect();
+        let histogram = LatencyHistogram::record(&latencies).unwrap();
+        let summary = histogram.summary();
+        assert_eq!(summary.samples, 10_000);
+        assert_eq!(summary.min_ns, 1);
+        assert!((summary.p50_ns as f64 - 5_000.0).abs() <= 5.0);
+        assert!((summary.p99_ns as f64 - 9_900.0).abs() <= 10.0);
+
+        let path =
+            std::env::temp_dir().join(format!("keccak-bench-hdr-{}.hlog", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_log(path, &[("rust (tiny-keccak)", &histogram, 1.5)]).unwrap();
+        let text = std::fs::read(path).unwrap();
+        let entries: Vec<_> =
+            hdrhistogram::serialization::interval_log::IntervalLogIterator::new(&text)
+                .filter_map(|entry| match entry.unwrap() {
+                    hdrhistogram::serialization::interval_log::LogEntry::Interval(interval) => {
+                        Some(interval.tag().map(|tag| tag.as_str().to_string()))
+                    }
+                    _ => None,
+                })
+                .collect();
+        assert_eq!(entries, [Some(String::from("rust_(tiny-keccak)"))]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
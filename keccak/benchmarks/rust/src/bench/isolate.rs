@@ -0,0 +1,153 @@
+//! Trials in child processes for `--isolate`.
+//!
+//! Each trial of each backend runs in a fresh process: the parent writes
+//! the config to the child's stdin as JSON, and the child loads the
+//! workload, warms up, runs one trial, and answers on stdout with the
+//! measurement. Allocator state, code layout, and frequency history from
+//! one backend therefore never carry over into another's trial. Children
+//! re-run the current executable's [`SUBCOMMAND`].
+
+use std::env;
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::bench::replay::{self, field};
+use crate::bench::warmup::WarmupResult;
+use crate::bench::workload::Workload;
+use crate::bench::{affinity, run_trial, warm_up, BenchConfig, Measurement};
+
+/// The hidden `bench` subcommand a child runs.
+pub const SUBCOMMAND: &str = "isolated-trial";
+
+/// What a child reports back.
+#[derive(Serialize, Deserialize)]
+pub struct ChildTrial {
+    pub warmup: WarmupResult,
+    pub measurement: Measurement,
+}
+
+/// The config a child was sent: the workload fields [`replay::config`]
+/// reads plus those deciding how the trial is run.
+pub fn config(value: &Value) -> Result<BenchConfig, String> {
+    let record = serde_json::json!({ "config": value });
+    let mut config = replay::config(&record)?;
+    macro_rules! set {
+        ($($key:ident),*) => {
+            $(if let Some(value) = field(value, stringify!($key))? {
+                config.$key = value;
+            })*
+        };
+    }
+    set!(
+        warmup,
+        warmup_tolerance,
+        warmup_timeout,
+        latency_sample,
+        threads,
+        realtime,
+        timer,
+        exclude_generation,
+        reuse_buffer
+    );
+    config.pin_core = field(value, "pin_core")?;
+    config.async_mode = field(value, "async_mode")?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// The child side: run the trial `input` describes and render the reply.
+pub fn child(input: &str) -> Result<String, String> {
+    let value: Value = serde_json::from_str(input).map_err(|err| err.to_string())?;
+    let mut config = config(&value)?;
+    let workload = Workload::load(&mut config)?;
+    if let Some(core) = config.pin_core {
+        affinity::pin_current_thread(core)?;
+    }
+    config.timer.check()?;
+    let generated = match workload {
+        Workload::Synthetic if config.exclude_generation => Some(Workload::generate(&config)),
+        _ => None,
+    };
+    let workload = generated.as_ref().unwrap_or(&workload);
+    if config.realtime {
+        affinity::set_realtime()?;
+    }
+    let warmup = warm_up(&config, workload);
+    let measurement = run_trial(&config, workload, config.threads);
+    serde_json::to_string(&ChildTrial {
+        warmup,
+        measurement,
+    })
+    .map_err(|err| err.to_string())
+}
+
+/// The parent side: run one trial of `config` in a child process.
+pub fn run(config: &BenchConfig) -> Result<ChildTrial, String> {
+    let program =
+        env::current_exe().map_err(|err| format!("cannot find own executable: {}", err))?;
+    let mut child = Command::new(&program)
+        .arg(SUBCOMMAND)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to start {}: {}", program.display(), err))?;
+    let input = serde_json::to_string(config).map_err(|err| err.to_string())?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())
+        .map_err(|err| format!("failed to send the config to the child: {}", err))?;
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("isolated trial failed: {}", err))?;
+    if !output.status.success() {
+        return Err(format!("isolated trial exited with {}", output.status));
+    }
+    let mut reply: ChildTrial = serde_json::from_slice(&output.stdout)
+        .map_err(|err| format!("unreadable reply from isolated trial: {}", err))?;
+    // The child's clock is not ours; place the trial as just finished.
+    reply.measurement.started = Instant::now()
+        .checked_sub(Duration::from_secs_f64(reply.measurement.seconds))
+        .unwrap_or_else(Instant::now);
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::backend::Backend;
+    use crate::bench::timer::Timer;
+    use crate::bench::warmup::Warmup;
+
+    /// A child rebuilds the config it was sent and its reply parses back
+    /// into the same measurement.
+    #[test]
+    fn child_round_trips_config_and_measurement() {
+        let config = BenchConfig {
+            backend: Backend::Native,
+            seed: Some(4),
+            messages: 8,
+            rounds: 3,
+            warmup: Warmup::Fixed(1),
+            latency_sample: 2,
+            timer: Timer::Instant,
+            ..BenchConfig::default()
+        };
+        let sent = serde_json::to_value(&config).unwrap();
+        let received = super::config(&sent).unwrap();
+        assert_eq!(serde_json::to_value(&received).unwrap(), sent);
+
+        let reply: ChildTrial = serde_json::from_str(&child(&sent.to_string()).unwrap()).unwrap();
+        assert_eq!(reply.warmup.rounds, 1);
+        assert_eq!(
+            reply.measurement.checksum,
+            run_trial(&config, &Workload::Synthetic, 1).checksum
+        );
+        assert_eq!(reply.measurement.round_seconds.len(), 3);
+    }
+}
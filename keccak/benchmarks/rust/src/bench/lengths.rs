@@ -0,0 +1,158 @@
+//! Empirical message-length distributions for `--length-dist`.
+
+use std::fs;
+
+use serde::{Serialize, Serializer};
+
+use crate::bench::workload::{SplitMix64, GOLDEN_GAMMA};
+
+/// A weighted set of length buckets, sampled once per message index.
+///
+/// The file holds one bucket per line: a length or an inclusive `min-max`
+/// range, then a non-negative weight (a count or a fraction), separated by
+/// whitespace or a comma. Blank lines and `#` comments are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LengthDist {
+    /// Where the distribution was loaded from; this is what gets reported.
+    pub path: String,
+    /// `(min, max, cumulative weight)` for each bucket with nonzero weight.
+    buckets: Vec<(usize, usize, f64)>,
+}
+
+impl Serialize for LengthDist {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.path)
+    }
+}
+
+impl LengthDist {
+    pub fn load(path: &str) -> Result<LengthDist, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        LengthDist::parse(path, &text)
+    }
+
+    pub fn parse(path: &str, text: &str) -> Result<LengthDist, String> {
+        let mut buckets = Vec::new();
+        let mut total = 0.0;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let bad = || {
+                format!(
+                    "{}:{}: expected `<length>[-<max>] <weight>`",
+                    path,
+                    number + 1
+                )
+            };
+            let mut fields = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|field| !field.is_empty());
+            let (Some(lengths), Some(weight), None) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(bad());
+            };
+            let (min, max) = match lengths.split_once('-') {
+                Some((min, max)) => (min.parse(), max.parse()),
+                None => (lengths.parse(), lengths.parse()),
+            };
+            let (Ok(min), Ok(max), Ok(weight)) = (min, max, weight.parse::<f64>()) else {
+                return Err(bad());
+            };
+            if min > max || !weight.is_finite() || weight < 0.0 {
+                return Err(bad());
+            }
+            if weight > 0.0 {
+                total += weight;
+                buckets.push((min, max, total));
+            }
+        }
+        if buckets.is_empty() {
+            return Err(format!("{}: no lengths with nonzero weight", path));
+        }
+        Ok(LengthDist {
+            path: path.to_string(),
+            buckets,
+        })
+    }
+
+    pub fn min_len(&self) -> usize {
+        self.buckets
+            .iter()
+            .map(|&(min, _, _)| min)
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn max_len(&self) -> usize {
+        self.buckets
+            .iter()
+            .map(|&(_, max, _)| max)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Length of message `index`: a bucket drawn by weight, then a length
+    /// drawn uniformly inside it, both from a [`SplitMix64`] seeded with
+    /// `!(seed ^ index * 0x9e3779b97f4a7c15)` so the draw is independent
+    /// of the message bytes.
+    pub fn sample(&self, seed: u64, index: usize) -> usize {
+        let mut rng = SplitMix64::new(!(seed ^ (index as u64).wrapping_mul(GOLDEN_GAMMA)));
+        let total = self.buckets.last().map_or(0.0, |&(_, _, total)| total);
+        // 53 random bits give a uniform fraction in [0, 1).
+        let target = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64 * total;
+        let bucket = self
+            .buckets
+            .iter()
+            .position(|&(_, _, cumulative)| target < cumulative)
+            .unwrap_or(self.buckets.len() - 1);
+        let (min, max, _) = self.buckets[bucket];
+        min + (rng.next_u64() % (max - min + 1) as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ranges, single lengths, comments, and zero weights all parse.
+    #[test]
+    fn parses_buckets() {
+        let dist = LengthDist::parse(
+            "calldata.txt",
+            "# length weight\n68 30\n100-200, 0.5\n\n4096 0 # unused\n",
+        )
+        .unwrap();
+        assert_eq!(dist.min_len(), 68);
+        assert_eq!(dist.max_len(), 200);
+        assert!(LengthDist::parse("x", "12").is_err());
+        assert!(LengthDist::parse("x", "9-3 1").is_err());
+        assert!(LengthDist::parse("x", "5 -1").is_err());
+        assert!(LengthDist::parse("x", "5 0").is_err());
+    }
+
+    /// Draws are reproducible, stay in their buckets, and follow the weights.
+    #[test]
+    fn samples_follow_weights() {
+        let dist = LengthDist::parse("x", "32 3\n1000-1999 1\n").unwrap();
+        let samples: Vec<usize> = (0..4000).map(|index| dist.sample(7, index)).collect();
+        assert_eq!(
+            samples[..100],
+            (0..100)
+                .map(|index| dist.sample(7, index))
+                .collect::<Vec<_>>()
+        );
+        assert!(samples
+            .iter()
+            .all(|&len| len == 32 || (1000..=1999).contains(&len)));
+        let small = samples.iter().filter(|&&len| len == 32).count();
+        assert!((2800..3200).contains(&small), "{} of 4000", small);
+        assert_ne!(
+            samples,
+            (0..4000)
+                .map(|index| dist.sample(8, index))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Memory-mapped inputs for `--mmap` (Linux).
+//!
+//! The whole file is one message, absorbed `chunk_size` bytes at a time,
+//! so large inputs exercise the streaming path. Before each trial the
+//! mapping's pages are dropped from the process (the page cache keeps
+//! them), so every trial takes the same page faults as it reads the file.
+
+use serde::{Deserialize, Serialize};
+
+/// Page faults taken by the process, from `getrusage`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageFaults {
+    /// Served from memory, e.g. the page cache.
+    pub minor: u64,
+    /// Needed I/O.
+    pub major: u64,
+}
+
+impl PageFaults {
+    /// Faults between `self` and the later reading `end`.
+    pub fn until(self, end: PageFaults) -> PageFaults {
+        PageFaults {
+            minor: end.minor.saturating_sub(self.minor),
+            major: end.major.saturating_sub(self.major),
+        }
+    }
+}
+
+/// What a mapped-file run reports about its input.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MappedResult {
+    pub path: String,
+    pub bytes: u64,
+    /// Bytes absorbed per update call.
+    pub window: usize,
+    /// Faults during the median trial.
+    pub faults: Option<PageFaults>,
+}
+
+#[cfg(all(target_os = "linux", not(feature = "safe-only")))]
+mod imp {
+    use std::fs::File;
+    use std::os::fd::AsRawFd;
+    use std::ptr;
+    use std::slice;
+
+    use super::PageFaults;
+
+    /// A read-only private mapping of a whole file.
+    pub struct MappedFile {
+        pub path: String,
+        data: *mut libc::c_void,
+        len: usize,
+    }
+
+    // The mapping is read-only and lives until drop, so shared references
+    // to it are as safe as a `&[u8]`.
+    unsafe impl Send for MappedFile {}
+    unsafe impl Sync for MappedFile {}
+
+    impl MappedFile {
+        pub fn open(path: &str) -> Result<MappedFile, String> {
+            let file = File::open(path).map_err(|err| format!("{}: {}", path, err))?;
+            let len = file
+                .metadata()
+                .map_err(|err| format!("{}: {}", path, err))?
+                .len() as usize;
+            if len == 0 {
+                return Err(format!("{}: cannot map an empty file", path));
+            }
+            // SAFETY: a fresh read-only mapping of an open file; the fd may
+            // be closed once the mapping exists.
+            let data = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ,
+                    libc::MAP_PRIVATE,
+                    file.as_raw_fd(),
+                    0,
+                )
+            };
+            if data == libc::MAP_FAILED {
+                return Err(format!(
+                    "{}: mmap failed: {}",
+                    path,
+                    std::io::Error::last_os_error()
+                ));
+            }
+            Ok(MappedFile {
+                path: path.to_string(),
+                data,
+                len,
+            })
+        }
+
+        pub fn as_slice(&self) -> &[u8] {
+            // SAFETY: `data` maps `len` readable bytes until drop.
+            unsafe { slice::from_raw_parts(self.data as *const u8, self.len) }
+        }
+
+        /// Unmap the pages from this process so the next pass faults them
+        /// back in.
+        pub fn evict(&self) {
+            // SAFETY: MADV_DONTNEED on our own private read-only mapping
+            // only drops page table entries; reads refault the file data.
+            unsafe {
+                libc::madvise(self.data, self.len, libc::MADV_DONTNEED);
+            }
+        }
+    }
+
+    impl Drop for MappedFile {
+        fn drop(&mut self) {
+            // SAFETY: unmapping the region mapped in `open`.
+            unsafe {
+                libc::munmap(self.data, self.len);
+            }
+        }
+    }
+
+    pub fn faults() -> Option<PageFaults> {
+        // SAFETY: getrusage fills the zeroed struct it is given.
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+            return None;
+        }
+        Some(PageFaults {
+            minor: usage.ru_minflt as u64,
+            major: usage.ru_majflt as u64,
+        })
+    }
+}
+
+#[cfg(not(all(target_os = "linux", not(feature = "safe-only"))))]
+mod imp {
+    use super::PageFaults;
+
+    pub struct MappedFile {
+        pub path: String,
+    }
+
+    impl MappedFile {
+        pub fn open(_path: &str) -> Result<MappedFile, String> {
+            Err(String::from(
+                "memory-mapped inputs require Linux and a build without safe-only",
+            ))
+        }
+
+        pub fn as_slice(&self) -> &[u8] {
+            unreachable!("mappings are never opened in this build")
+        }
+
+        pub fn evict(&self) {}
+    }
+
+    pub fn faults() -> Option<PageFaults> {
+        None
+    }
+}
+
+/// A whole file mapped read-only; see [`MappedFile::open`].
+pub use imp::{faults, MappedFile};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fault deltas subtract readings and never go negative.
+    #[test]
+    fn fault_deltas_subtract() {
+        let start = PageFaults {
+            minor: 10,
+            major: 2,
+        };
+        let end = PageFaults {
+            minor: 25,
+            major: 2,
+        };
+        assert_eq!(
+            start.until(end),
+            PageFaults {
+                minor: 15,
+                major: 0
+            }
+        );
+        assert_eq!(end.until(start), PageFaults::default());
+    }
+
+    /// A mapping reads back the file it maps, before and after eviction.
+    #[cfg(all(target_os = "linux", not(feature = "safe-only")))]
+    #[test]
+    fn mapping_reads_file_contents() {
+        let path = std::env::temp_dir().join(format!("keccak-bench-mmap-{}", std::process::id()));
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+        let mapped = MappedFile::open(path.to_str().unwrap()).unwrap();
+        assert_eq!(mapped.as_slice(), data.as_slice());
+        mapped.evict();
+        assert_eq!(mapped.as_slice(), data.as_slice());
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+//! TOML and YAML renderings of result records for `--format toml|yaml`.
+//!
+//! Both are emitted from the record's JSON value, so they carry exactly the
+//! fields the JSON format does, with keys in sorted order. TOML has no null,
+//! so null fields are left out there; YAML keeps them.
+
+use std::fmt::Write as _;
+
+use serde_json::{Map, Value};
+
+/// One result as a `[[results]]` table, so appended results extend the
+/// same array.
+pub fn toml(record: &Value) -> String {
+    let mut out = String::new();
+    match record {
+        Value::Object(map) => write_toml_table(&mut out, "results", map, true),
+        other => writeln!(out, "results = {}", toml_inline(other)).unwrap(),
+    }
+    out
+}
+
+/// One result as its own YAML document, so appended results form a stream.
+pub fn yaml(record: &Value) -> String {
+    let mut out = String::from("---\n");
+    match record {
+        Value::Object(map) if !map.is_empty() => write_yaml_map(&mut out, map, 0),
+        Value::Array(items) if !items.is_empty() => write_yaml_seq(&mut out, items, 0),
+        scalar => writeln!(out, "{}", yaml_scalar(scalar)).unwrap(),
+    }
+    out
+}
+
+fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_string()
+    } else {
+        toml_string(key)
+    }
+}
+
+/// A TOML basic string. JSON escapes are valid TOML escapes except that
+/// TOML has no `\/`, which serde_json never emits.
+fn toml_string(text: &str) -> String {
+    Value::String(text.to_string()).to_string()
+}
+
+fn is_table_array(value: &Value) -> bool {
+    matches!(value, Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object))
+}
+
+fn toml_inline(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("\"\""),
+        Value::String(text) => toml_string(text),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .filter(|item| !item.is_null())
+                .map(toml_inline)
+                .collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(map) => {
+            let fields: Vec<String> = map
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| format!("{} = {}", toml_key(key), toml_inline(value)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+    }
+}
+
+/// Write `map` under `path`: plain keys first, since TOML ends a table at
+/// the next header, then nested tables and arrays of tables.
+fn write_toml_table(out: &mut String, path: &str, map: &Map<String, Value>, array: bool) {
+    if array {
+        writeln!(out, "[[{}]]", path).unwrap();
+    } else {
+        writeln!(out, "[{}]", path).unwrap();
+    }
+    for (key, value) in map {
+        if !value.is_null() && !value.is_object() && !is_table_array(value) {
+            writeln!(out, "{} = {}", toml_key(key), toml_inline(value)).unwrap();
+        }
+    }
+    for (key, value) in map {
+        let child = format!("{}.{}", path, toml_key(key));
+        match value {
+            Value::Object(inner) => {
+                writeln!(out).unwrap();
+                write_toml_table(out, &child, inner, false);
+            }
+            Value::Array(items) if is_table_array(value) => {
+                for item in items {
+                    writeln!(out).unwrap();
+                    write_toml_table(out, &child, item.as_object().unwrap(), true);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn yaml_key(key: &str) -> String {
+    let plain = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
+/// Scalars and empty collections in flow style. Strings are always
+/// double-quoted: a JSON string literal is a valid YAML one, and quoting
+/// keeps values such as `no` or `1e3` from changing type.
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::Array(_) => String::from("[]"),
+        Value::Object(_) => String::from("{}"),
+        other => other.to_string(),
+    }
+}
+
+fn is_block(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+        _ => false,
+    }
+}
+
+fn write_yaml_map(out: &mut String, map: &Map<String, Value>, indent: usize) {
+    let pad = " ".repeat(indent);
+    for (key, value) in map {
+        if !is_block(value) {
+            writeln!(out, "{}{}: {}", pad, yaml_key(key), yaml_scalar(value)).unwrap();
+            continue;
+        }
+        writeln!(out, "{}{}:", pad, yaml_key(key)).unwrap();
+        match value {
+            Value::Object(inner) => write_yaml_map(out, inner, indent + 2),
+            Value::Array(items) => write_yaml_seq(out, items, indent + 2),
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn write_yaml_seq(out: &mut String, items: &[Value], indent: usize) {
+    let pad = " ".repeat(indent);
+    for item in items {
+        if !is_block(item) {
+            writeln!(out, "{}- {}", pad, yaml_scalar(item)).unwrap();
+            continue;
+        }
+        // Render the item one level deeper, then put the dash in place of
+        // the first line's indentation.
+        let mut nested = String::new();
+        match item {
+            Value::Object(inner) => write_yaml_map(&mut nested, inner, indent + 2),
+            Value::Array(inner) => write_yaml_seq(&mut nested, inner, indent + 2),
+            _ => unreachable!(),
+        }
+        write!(out, "{}- {}", pad, &nested[indent + 2..]).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Value {
+        serde_json::from_str(
+            r#"{"implementation": "rust \"tiny\"", "seconds": 1.5, "latency": null,
+                "trial_seconds": [1.0, 2.0], "config": {"seed": null, "messages": 8},
+                "files": [{"path": "a.bin", "bytes": 3}, {"path": "b.bin", "bytes": 4}],
+                "stats": {}}"#,
+        )
+        .unwrap()
+    }
+
+    /// Plain keys precede subtables, arrays of objects become arrays of
+    /// tables, and null fields are dropped.
+    #[test]
+    fn toml_orders_tables_after_keys() {
+        let out = toml(&sample());
+        assert_eq!(
+            out,
+            "[[results]]\n\
+             implementation = \"rust \\\"tiny\\\"\"\n\
+             seconds = 1.5\n\
+             trial_seconds = [1.0, 2.0]\n\
+             \n[results.config]\nmessages = 8\n\
+             \n[[results.files]]\nbytes = 3\npath = \"a.bin\"\n\
+             \n[[results.files]]\nbytes = 4\npath = \"b.bin\"\n\
+             \n[results.stats]\n"
+        );
+    }
+
+    /// YAML nests blocks by indentation and keeps nulls.
+    #[test]
+    fn yaml_nests_blocks() {
+        let out = yaml(&sample());
+        assert_eq!(
+            out,
+            "---\n\
+             config:\n  messages: 8\n  seed: null\n\
+             files:\n  - bytes: 3\n    path: \"a.bin\"\n  - bytes: 4\n    path: \"b.bin\"\n\
+             implementation: \"rust \\\"tiny\\\"\"\n\
+             latency: null\n\
+             seconds: 1.5\n\
+             stats: {}\n\
+             trial_seconds:\n  - 1.0\n  - 2.0\n"
+        );
+    }
+}
@@ -0,0 +1,1381 @@
+//! The benchmark harness behind the `bench` binary: workload generation,
+//! warmup, timed trials, and result reporting.
+//!
+//! [`run`] measures one config end to end; [`run_with`] also takes several
+//! backends, a trial callback, and latency histograms. The lower-level
+//! [`run_benchmark`] and [`summarize`] are public for callers that need the
+//! raw trials.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::trace;
+
+pub mod affinity;
+pub mod aggregate;
+pub mod alloc;
+pub mod backend;
+pub mod baseline;
+pub mod buckets;
+pub mod budget;
+pub mod chart;
+pub mod checksum;
+pub mod compare;
+pub mod diff;
+pub mod dump;
+pub mod ecdsa;
+pub mod energy;
+pub mod environment;
+pub mod gate;
+pub mod golden;
+pub mod histogram;
+pub mod isolate;
+pub mod lengths;
+pub mod mapped;
+pub mod markup;
+pub mod output;
+pub mod perf;
+pub mod profile;
+pub mod replay;
+pub mod report;
+pub mod rounds;
+pub mod runtime;
+pub mod schema;
+pub mod settings;
+pub mod soak;
+pub mod stats;
+pub mod summary;
+pub mod sweep;
+pub mod target;
+pub mod timer;
+pub mod tsc;
+pub mod verify;
+pub mod warmup;
+pub mod workload;
+pub mod xof;
+
+use alloc::{AllocCounts, AllocationReport};
+use backend::Backend;
+use budget::Budget;
+use checksum::ChecksumMode;
+use energy::EnergySummary;
+use environment::Environment;
+use histogram::LatencyHistogram;
+use lengths::LengthDist;
+use mapped::{MappedResult, PageFaults};
+use output::{BenchResult, FileResult, ThreadingResult, TrialEvent};
+use perf::{CounterSummary, PerfCounts};
+use profile::Profile;
+use rounds::RoundReport;
+use runtime::{AsyncMode, RuntimeResult};
+use soak::{Soak, SoakReport};
+use stats::{LatencySummary, Summary};
+use sweep::Sweep;
+use target::Target;
+use timer::Timer;
+use warmup::{Warmup, WarmupResult};
+use workload::Workload;
+
+/// Workload parameters. The defaults mirror the constants in
+/// `benchmarks/mojo_benchmark.mojo` and `benchmarks/run_benchmarks.py`.
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchConfig {
+    /// Preset the workload fields were seeded from, if any.
+    pub profile: Option<Profile>,
+    /// Corpus directory or manifest replacing the synthetic messages.
+    pub corpus: Option<String>,
+    /// File memory-mapped and hashed as one streamed message.
+    pub mmap: Option<String>,
+    /// Seed for pseudorandom message bytes; `None` keeps the legacy
+    /// `(index + offset) % 256` pattern.
+    pub seed: Option<u64>,
+    pub messages: usize,
+    pub rounds: usize,
+    pub min_len: usize,
+    pub max_len: usize,
+    pub stride: usize,
+    /// Untimed rounds before the first trial, or adaptive calibration.
+    pub warmup: Warmup,
+    /// Adaptive warmup stops once consecutive rounds agree within this
+    /// fraction of their mean throughput.
+    pub warmup_tolerance: f64,
+    /// Adaptive warmup gives up after this many seconds.
+    pub warmup_timeout: f64,
+    /// Number of times the timed rounds are repeated.
+    pub trials: usize,
+    /// Coefficient of variation above which a run is flagged as noisy; also
+    /// the slowdown above which a steady climb in round times is flagged as
+    /// throttling.
+    pub noise_threshold: f64,
+    /// Rounds further than this many median absolute deviations from the
+    /// median round are flagged as outliers.
+    pub outlier_mad: f64,
+    /// Time every Nth hash individually for latency percentiles; 0 disables.
+    pub latency_sample: usize,
+    /// Time each power-of-two message-length range separately after the
+    /// trials.
+    pub by_length: bool,
+    /// Worker threads sharing each round's messages.
+    pub threads: usize,
+    /// Hash behind an async hop from tasks on a tokio runtime.
+    pub async_mode: Option<AsyncMode>,
+    /// Run every trial, with its own warmup, in a fresh child process.
+    pub isolate: bool,
+    /// How digests are folded into the reported checksum.
+    pub checksum_mode: ChecksumMode,
+    /// Hash implementation being timed.
+    pub backend: Backend,
+    /// Size `rounds` so each trial takes about this long; `rounds` then
+    /// records the calibrated count.
+    pub duration: Option<Budget>,
+    /// Repeat trials of `soak_interval` each for this long instead of
+    /// running `trials` of them; `trials` then records how many ran.
+    pub soak: Option<Soak>,
+    /// Length of each soak trial.
+    pub soak_interval: Budget,
+    /// Logical CPU the measurement thread is pinned to; worker `i` of a
+    /// threaded run uses `pin_core + i`.
+    pub pin_core: Option<usize>,
+    /// Whether the run used `SCHED_FIFO` scheduling.
+    pub realtime: bool,
+    /// Clock for trial and per-hash timing.
+    pub timer: Timer,
+    /// Feed each message to the hasher this many bytes at a time instead
+    /// of hashing it in one call.
+    pub chunk_size: Option<usize>,
+    /// Hash this many messages per call through the backend's batch API
+    /// instead of one call per message.
+    pub batch: Option<usize>,
+    /// Generate synthetic messages before warmup instead of inside the
+    /// timed loop, so trials do not time allocation and byte filling.
+    pub exclude_generation: bool,
+    /// Generate each message inside the timed loop into one reused buffer,
+    /// so generation is timed but allocation is not.
+    pub reuse_buffer: bool,
+    /// Empirical length distribution replacing `min_len`/`max_len`/`stride`.
+    pub length_dist: Option<LengthDist>,
+    /// The sweep this run is one size of; `min_len` and `max_len` are then
+    /// both that size.
+    pub sweep: Option<Sweep>,
+    /// What the timed loop runs on each message.
+    pub target: Target,
+    /// Bytes squeezed per message by the SHAKE targets.
+    pub xof_output: usize,
+}
+
+impl BenchConfig {
+    /// Hash one message the way this config measures it.
+    pub fn hash(&self, message: &[u8]) -> [u8; 32] {
+        self.target.hash(self, message)
+    }
+
+    /// Reject settings the harness cannot run.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_len > self.max_len {
+            return Err(format!(
+                "min_len ({}) must not exceed max_len ({})",
+                self.min_len, self.max_len
+            ));
+        }
+        if self.trials == 0 {
+            return Err(String::from("trials must be at least 1"));
+        }
+        if self.chunk_size == Some(0) {
+            return Err(String::from("chunk_size must be at least 1"));
+        }
+        if self.batch == Some(0) {
+            return Err(String::from("batch must be at least 1"));
+        }
+        if self.batch.is_some() && self.chunk_size.is_some() {
+            return Err(String::from("batch and chunk_size cannot be combined"));
+        }
+        if self.batch.is_some() && self.latency_sample > 0 {
+            return Err(String::from(
+                "batched hashing has no per-hash latencies; drop latency sampling",
+            ));
+        }
+        if self.reuse_buffer {
+            if self.exclude_generation {
+                return Err(String::from(
+                    "a reused buffer is filled inside the timed loop; drop exclude_generation",
+                ));
+            }
+            if self.corpus.is_some() || self.batch.is_some() {
+                return Err(String::from(
+                    "a reused buffer holds one generated message; drop the corpus and batch",
+                ));
+            }
+        }
+        if self.mmap.is_some()
+            && (self.corpus.is_some()
+                || self.length_dist.is_some()
+                || self.sweep.is_some()
+                || self.batch.is_some()
+                || self.reuse_buffer)
+        {
+            return Err(String::from(
+                "a mapped file is the whole workload; drop the corpus, length distribution, \
+                 sweep, batch, and reuse_buffer",
+            ));
+        }
+        if self.length_dist.is_some() && self.corpus.is_some() {
+            return Err(String::from(
+                "a corpus fixes its own lengths; drop the length distribution",
+            ));
+        }
+        self.target.setup(self)?;
+        if self.sweep.is_some() && (self.corpus.is_some() || self.length_dist.is_some()) {
+            return Err(String::from(
+                "a sweep sets every message length; drop the corpus and length distribution",
+            ));
+        }
+        if self.soak.is_some() && (self.duration.is_some() || self.sweep.is_some()) {
+            return Err(String::from(
+                "a soak sizes its trials by soak_interval over one workload; drop duration and sweep",
+            ));
+        }
+        if self.threads == 0 {
+            return Err(String::from("threads must be at least 1"));
+        }
+        if self.async_mode.is_some() {
+            if !runtime::supported() {
+                return Err(String::from(
+                    "async trials need a build with the async feature",
+                ));
+            }
+            if self.batch.is_some() || self.reuse_buffer || self.mmap.is_some() {
+                return Err(String::from(
+                    "async trials hand over one generated message at a time; drop batch, \
+                     reuse_buffer, and mmap",
+                ));
+            }
+        }
+        if self.outlier_mad.is_nan() || self.outlier_mad <= 0.0 {
+            return Err(String::from("outlier_mad must be positive"));
+        }
+        if self.warmup_tolerance.is_nan() || self.warmup_tolerance <= 0.0 {
+            return Err(String::from("warmup_tolerance must be a positive fraction"));
+        }
+        Ok(())
+    }
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        BenchConfig {
+            profile: None,
+            corpus: None,
+            mmap: None,
+            seed: None,
+            messages: 512,
+            rounds: 200,
+            min_len: 32,
+            max_len: 512,
+            stride: 31,
+            warmup: Warmup::Adaptive,
+            warmup_tolerance: 0.02,
+            warmup_timeout: 10.0,
+            trials: 1,
+            noise_threshold: 0.05,
+            outlier_mad: 5.0,
+            latency_sample: 0,
+            by_length: false,
+            threads: 1,
+            async_mode: None,
+            isolate: false,
+            checksum_mode: ChecksumMode::default(),
+            backend: Backend::TinyKeccak,
+            chunk_size: None,
+            batch: None,
+            exclude_generation: true,
+            reuse_buffer: false,
+            length_dist: None,
+            sweep: None,
+            target: Target::default(),
+            xof_output: 32,
+            duration: None,
+            soak: None,
+            soak_interval: Budget { seconds: 60.0 },
+            pin_core: None,
+            realtime: false,
+            timer: Timer::Instant,
+        }
+    }
+}
+
+/// Hash every message once, untimed.
+fn warm_up_round(config: &BenchConfig, workload: &Workload) {
+    let count = workload.count(config);
+    match config.batch {
+        Some(size) => {
+            let mut digests = vec![[0u8; 32]; size];
+            for first in (0..count).step_by(size) {
+                let last = (first + size).min(count);
+                let messages: Vec<_> = (first..last)
+                    .map(|idx| workload.message(config, idx))
+                    .collect();
+                let messages: Vec<&[u8]> = messages.iter().map(|m| m.as_ref()).collect();
+                let digests = &mut digests[..messages.len()];
+                config.target.run_batch(config, &messages, digests);
+                std::hint::black_box(digests[0][0]);
+            }
+        }
+        None => {
+            for idx in 0..count {
+                let message = workload.message(config, idx);
+                let digest = config.hash(&message);
+                std::hint::black_box(digest[0]);
+            }
+        }
+    }
+}
+
+fn warm_up(config: &BenchConfig, workload: &Workload) -> WarmupResult {
+    trace::span!(DEBUG, "warmup", backend = %config.backend, warmup = %config.warmup);
+    let start = Instant::now();
+    match config.warmup {
+        Warmup::Fixed(rounds) => {
+            for _ in 0..rounds {
+                warm_up_round(config, workload);
+            }
+            WarmupResult {
+                rounds,
+                seconds: start.elapsed().as_secs_f64(),
+                converged: None,
+            }
+        }
+        Warmup::Adaptive => {
+            let mut throughputs = Vec::new();
+            let converged = loop {
+                let round_start = Instant::now();
+                warm_up_round(config, workload);
+                throughputs.push(1.0 / round_start.elapsed().as_secs_f64().max(1e-12));
+                if warmup::settled(&throughputs, config.warmup_tolerance) {
+                    break true;
+                }
+                if start.elapsed().as_secs_f64() >= config.warmup_timeout {
+                    break false;
+                }
+            };
+            trace::event!(
+                debug,
+                rounds = throughputs.len(),
+                converged,
+                "warmup finished"
+            );
+            WarmupResult {
+                rounds: throughputs.len(),
+                seconds: start.elapsed().as_secs_f64(),
+                converged: Some(converged),
+            }
+        }
+    }
+}
+
+/// What one timed trial measured.
+#[derive(Serialize, Deserialize)]
+pub struct Measurement {
+    pub seconds: f64,
+    pub checksum: u64,
+    /// Sampled per-hash latencies in nanoseconds.
+    pub latencies_ns: Vec<f64>,
+    /// TSC ticks spent in the timed loop, when a TSC is available.
+    pub cycles: Option<u64>,
+    /// Hardware counters over the timed loop, with the `perf` feature.
+    pub counters: Option<PerfCounts>,
+    /// Package joules over the timed loop, with the `energy` feature.
+    pub energy_joules: Option<f64>,
+    /// Page faults over the timed loop, for mapped files.
+    pub faults: Option<PageFaults>,
+    /// Seconds spent on each corpus file across all rounds; empty for
+    /// synthetic workloads.
+    pub file_seconds: Vec<f64>,
+    /// One entry per worker thread, in shard order.
+    pub shards: Vec<ShardMeasurement>,
+    /// Seconds each round took on the first worker.
+    pub round_seconds: Vec<f64>,
+    /// When the trial started, so soak samples can be placed in time.
+    #[serde(skip, default = "Instant::now")]
+    pub started: Instant,
+}
+
+/// What one worker thread measured for its share of the messages.
+#[derive(Serialize, Deserialize)]
+pub struct ShardMeasurement {
+    pub messages: usize,
+    pub seconds: f64,
+    pub checksum: u64,
+    pub latencies_ns: Vec<f64>,
+    pub file_seconds: Vec<f64>,
+    pub round_seconds: Vec<f64>,
+}
+
+/// Hash every message whose index is congruent to `shard` modulo `shards`.
+fn run_shard(
+    config: &BenchConfig,
+    workload: &Workload,
+    shard: usize,
+    shards: usize,
+) -> ShardMeasurement {
+    if let (Some(base), true) = (config.pin_core, shards > 1) {
+        // Best effort: run_with() already checked pinning works on `base`.
+        let _ = affinity::pin_current_thread(base + shard);
+    }
+    let count = workload.count(config);
+    let per_file = matches!(workload, Workload::Corpus(_));
+    let mut file_seconds = vec![0.0; if per_file { count } else { 0 }];
+    let mut checksum: u64 = 0;
+    let mut latencies_ns = Vec::new();
+    let mut hash_index: usize = 0;
+    let indices: Vec<usize> = match config.batch {
+        Some(_) => (shard..count).step_by(shards).collect(),
+        None => Vec::new(),
+    };
+    let mut digests = vec![[0u8; 32]; config.batch.unwrap_or(0)];
+    let mut buffer = Vec::new();
+    // Per-hash and per-batch times exclude the cost of reading the clock.
+    let overhead = if config.latency_sample > 0 || per_file {
+        config.timer.overhead_ns() / 1e9
+    } else {
+        0.0
+    };
+    let mut round_ends = Vec::with_capacity(config.rounds);
+    let start = config.timer.now();
+
+    for round in 0..config.rounds {
+        if round > 0 {
+            round_ends.push(start.elapsed_secs());
+        }
+        if let Some(size) = config.batch {
+            for group in indices.chunks(size) {
+                let messages: Vec<_> = group
+                    .iter()
+                    .map(|&idx| workload.message(config, idx))
+                    .collect();
+                let messages: Vec<&[u8]> = messages.iter().map(|m| m.as_ref()).collect();
+                let digests = &mut digests[..group.len()];
+                let batch_start = per_file.then(|| config.timer.now());
+                config.target.run_batch(config, &messages, digests);
+                if let Some(batch_start) = batch_start {
+                    // One call covers the whole batch, so its files share
+                    // the time evenly.
+                    let share =
+                        (batch_start.elapsed_secs() - overhead).max(0.0) / group.len() as f64;
+                    for &idx in group {
+                        file_seconds[idx] += share;
+                    }
+                }
+                for digest in digests.iter() {
+                    checksum = config.target.checksum(config, checksum, digest);
+                }
+            }
+            continue;
+        }
+        for idx in (shard..count).step_by(shards) {
+            let message = if config.reuse_buffer {
+                Cow::Borrowed(workload.message_into(config, idx, &mut buffer))
+            } else {
+                workload.message(config, idx)
+            };
+            let sampled =
+                config.latency_sample > 0 && hash_index.is_multiple_of(config.latency_sample);
+            let digest = if sampled || per_file {
+                let hash_start = config.timer.now();
+                let digest = config.hash(&message);
+                let elapsed = (hash_start.elapsed_secs() - overhead).max(0.0);
+                if sampled {
+                    latencies_ns.push(elapsed * 1e9);
+                }
+                if let Some(total) = file_seconds.get_mut(idx) {
+                    *total += elapsed;
+                }
+                digest
+            } else {
+                config.hash(&message)
+            };
+            checksum = config.target.checksum(config, checksum, &digest);
+            hash_index += 1;
+        }
+    }
+
+    let seconds = start.elapsed_secs();
+    if config.rounds > 0 {
+        round_ends.push(seconds);
+    }
+    let round_seconds = round_ends
+        .iter()
+        .scan(0.0, |previous, &end| {
+            let round = end - *previous;
+            *previous = end;
+            Some(round)
+        })
+        .collect();
+
+    ShardMeasurement {
+        messages: (shard..count).step_by(shards).count(),
+        seconds,
+        checksum,
+        latencies_ns,
+        file_seconds,
+        round_seconds,
+    }
+}
+
+/// Run one timed trial, sharded across `threads` workers.
+pub fn run_trial(config: &BenchConfig, workload: &Workload, threads: usize) -> Measurement {
+    trace::span!(DEBUG, "trial", backend = %config.backend, threads);
+    let mapped = match workload {
+        Workload::Mapped(mapped) => {
+            mapped.evict();
+            mapped::faults()
+        }
+        _ => None,
+    };
+    let hop = config
+        .async_mode
+        .map(|mode| runtime::Trial::prepare(config, workload, threads, mode));
+    let started = Instant::now();
+    let session = perf::Session::start();
+    let meter = energy::Session::start();
+    let start_cycles = tsc::read();
+    let start = config.timer.now();
+    let shards: Vec<ShardMeasurement> = if let Some(hop) = hop {
+        hop.run()
+    } else if threads == 1 {
+        vec![run_shard(config, workload, 0, 1)]
+    } else {
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|shard| scope.spawn(move || run_shard(config, workload, shard, threads)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("benchmark worker panicked"))
+                .collect()
+        })
+    };
+    let elapsed = start.elapsed_secs();
+    let cycles = match (start_cycles, tsc::read()) {
+        (Some(begin), Some(end)) => Some(end.wrapping_sub(begin)),
+        _ => None,
+    };
+    let energy_joules = meter.and_then(energy::Session::stop);
+    let faults = mapped
+        .zip(mapped::faults())
+        .map(|(start, end)| start.until(end));
+    let counters = session.and_then(perf::Session::stop);
+    trace::event!(debug, seconds = elapsed, "trial finished");
+
+    // Shards cover disjoint indices, so combining their checksums and summing
+    // their per-file totals reproduces the single-threaded values.
+    let mut file_seconds = vec![0.0; shards[0].file_seconds.len()];
+    for shard in &shards {
+        for (total, seconds) in file_seconds.iter_mut().zip(&shard.file_seconds) {
+            *total += seconds;
+        }
+    }
+    Measurement {
+        seconds: elapsed,
+        checksum: shards.iter().fold(0, |acc, shard| {
+            config.checksum_mode.combine(acc, shard.checksum)
+        }),
+        latencies_ns: shards
+            .iter()
+            .flat_map(|shard| shard.latencies_ns.iter().copied())
+            .collect(),
+        cycles,
+        counters,
+        energy_joules,
+        faults,
+        file_seconds,
+        round_seconds: shards[0].round_seconds.clone(),
+        shards,
+        started,
+    }
+}
+
+/// Everything measured for one backend.
+pub struct Run {
+    pub warmup: WarmupResult,
+    /// Allocation totals, with the `alloc-stats` feature.
+    pub warmup_allocations: Option<AllocCounts>,
+    pub trial_allocations: Option<AllocCounts>,
+    pub measurements: Vec<Measurement>,
+}
+
+/// Warm up every backend, then run `trials` rounds of timed trials with the
+/// backends interleaved, so thermal and frequency drift affects them alike.
+/// Each trial is handed to `on_trial` with its config and backend index as
+/// soon as it completes. Isolated configs warm up and run each trial in a
+/// child process instead, and report the first child's warmup; the only
+/// error is a child that failed.
+pub fn run_benchmark(
+    configs: &mut [BenchConfig],
+    workload: &Workload,
+    mut on_trial: impl FnMut(&BenchConfig, usize, usize, &Measurement),
+) -> Result<Vec<Run>, String> {
+    let mut runs: Vec<Run> = configs
+        .iter()
+        .map(|config| {
+            if config.isolate {
+                return Run {
+                    warmup: WarmupResult {
+                        rounds: 0,
+                        seconds: 0.0,
+                        converged: None,
+                    },
+                    warmup_allocations: None,
+                    trial_allocations: None,
+                    measurements: Vec::new(),
+                };
+            }
+            let (warmup, warmup_allocations) = alloc::measure(|| warm_up(config, workload));
+            Run {
+                warmup,
+                warmup_allocations,
+                trial_allocations: warmup_allocations.map(|_| AllocCounts::default()),
+                measurements: Vec::new(),
+            }
+        })
+        .collect();
+    let soak = configs.first().and_then(|config| config.soak);
+    let budget = configs.first().and_then(|config| match soak {
+        Some(_) => Some(config.soak_interval),
+        None => config.duration,
+    });
+    if let Some(budget) = budget {
+        // Give every backend the same rounds, sized for the slowest one, so
+        // their workloads and checksums stay comparable.
+        let round_seconds = configs
+            .iter()
+            .map(|config| seconds_per_round(config, workload))
+            .fold(0.0, f64::max);
+        let rounds = budget.rounds(round_seconds);
+        for config in configs.iter_mut() {
+            config.rounds = rounds;
+        }
+    }
+    if let Some(soak) = soak {
+        // An estimate for progress reports; corrected once the soak ends.
+        let trials = soak.trials(budget.unwrap(), configs.len());
+        for config in configs.iter_mut() {
+            config.trials = trials;
+        }
+    }
+    let trials = configs.first().map_or(0, |config| config.trials);
+    let start = Instant::now();
+    let mut trial = 0;
+    while match soak {
+        Some(soak) => trial == 0 || start.elapsed().as_secs_f64() < soak.seconds(),
+        None => trial < trials,
+    } {
+        for (backend, config) in configs.iter().enumerate() {
+            trace::span!(INFO, "backend", backend = %config.backend, trial);
+            let run = &mut runs[backend];
+            let (measurement, allocations) = if config.isolate {
+                let child = isolate::run(config)?;
+                if trial == 0 {
+                    run.warmup = child.warmup;
+                }
+                (child.measurement, None)
+            } else {
+                alloc::measure(|| run_trial(config, workload, config.threads))
+            };
+            on_trial(config, backend, trial, &measurement);
+            if let (Some(total), Some(allocations)) = (&mut run.trial_allocations, allocations) {
+                total.count += allocations.count;
+                total.bytes += allocations.bytes;
+            }
+            run.measurements.push(measurement);
+        }
+        trial += 1;
+    }
+    if soak.is_some() {
+        for config in configs.iter_mut() {
+            config.trials = trial;
+        }
+    }
+    Ok(runs)
+}
+
+/// Estimate the cost of one round by timing single-round trials for at
+/// least 50 ms.
+fn seconds_per_round(config: &BenchConfig, workload: &Workload) -> f64 {
+    let single = BenchConfig {
+        rounds: 1,
+        latency_sample: 0,
+        ..config.clone()
+    };
+    let start = Instant::now();
+    let mut rounds = 0;
+    while rounds == 0 || start.elapsed().as_secs_f64() < 0.05 {
+        std::hint::black_box(run_trial(&single, workload, single.threads).checksum);
+        rounds += 1;
+    }
+    start.elapsed().as_secs_f64() / rounds as f64
+}
+
+/// Index of the sample closest to the median.
+fn median_index(samples: &[f64]) -> usize {
+    let mut order: Vec<usize> = (0..samples.len()).collect();
+    order.sort_by(|&a, &b| samples[a].total_cmp(&samples[b]));
+    order[order.len() / 2]
+}
+
+/// Hashes per second for a trial of `config` that took `seconds`.
+pub fn throughput(config: &BenchConfig, seconds: f64) -> f64 {
+    let total_hashes = (config.messages * config.rounds) as f64;
+    if seconds > 0.0 {
+        total_hashes / seconds
+    } else {
+        0.0
+    }
+}
+
+/// Reduce one backend's trials to its reported result.
+pub fn summarize(config: BenchConfig, workload: &Workload, label: String, run: Run) -> BenchResult {
+    let Run {
+        warmup,
+        warmup_allocations,
+        trial_allocations,
+        measurements,
+    } = run;
+    let checksum = measurements[0].checksum;
+    assert!(
+        measurements.iter().all(|m| m.checksum == checksum),
+        "checksum changed between trials"
+    );
+    if warmup.converged == Some(false) {
+        eprintln!(
+            "warning: {}: adaptive warmup did not settle within {:.1} s ({} rounds)",
+            label, config.warmup_timeout, warmup.rounds
+        );
+        trace::event!(warn, implementation = %label, rounds = warmup.rounds, "warmup did not settle");
+    }
+    let trial_seconds: Vec<f64> = measurements.iter().map(|m| m.seconds).collect();
+    let stats = Summary::from_samples(&trial_seconds).expect("at least one trial");
+    let noisy = trial_seconds.len() > 1 && stats.cv > config.noise_threshold;
+    if noisy {
+        eprintln!(
+            "warning: {}: noisy run, coefficient of variation {:.2}% exceeds {:.2}%",
+            label,
+            stats.cv * 100.0,
+            config.noise_threshold * 100.0
+        );
+        trace::event!(warn, implementation = %label, cv = stats.cv, "noisy run");
+    }
+
+    let round_seconds: Vec<f64> = measurements
+        .iter()
+        .flat_map(|m| m.round_seconds.iter().copied())
+        .collect();
+    let rounds = RoundReport::analyze(&round_seconds, config.outlier_mad, config.noise_threshold);
+    if let Some(report) = rounds.as_ref().filter(|report| !report.outliers.is_empty()) {
+        eprintln!(
+            "warning: {}: {} of {} rounds are more than {} MADs from the median round",
+            label,
+            report.outliers.len(),
+            report.rounds,
+            config.outlier_mad
+        );
+        trace::event!(
+            warn,
+            implementation = %label,
+            outliers = ?report.outliers,
+            rounds = report.rounds,
+            median_seconds = report.median_seconds,
+            "outlier rounds"
+        );
+    }
+    if let Some(report) = rounds.as_ref().filter(|report| report.throttling) {
+        eprintln!(
+            "warning: {}: rounds slowed down steadily by {:.2}% over the run; the CPU may be throttling",
+            label,
+            report.slowdown * 100.0
+        );
+        trace::event!(warn, implementation = %label, slowdown = report.slowdown, "throttling");
+    }
+
+    let soak = config.soak.and_then(|_| {
+        let origin = measurements[0].started;
+        let samples: Vec<(f64, f64)> = measurements
+            .iter()
+            .map(|m| {
+                let end = m.started.duration_since(origin).as_secs_f64() + m.seconds;
+                (end, throughput(&config, m.seconds))
+            })
+            .collect();
+        SoakReport::analyze(&samples)
+    });
+    if let Some(report) = soak
+        .as_ref()
+        .filter(|r| r.drift.abs() > config.noise_threshold)
+    {
+        eprintln!(
+            "warning: {}: throughput drifted {:+.2}% over the {:.2} h soak",
+            label,
+            report.drift * 100.0,
+            report.hours
+        );
+        trace::event!(warn, implementation = %label, drift = report.drift, "soak drift");
+    }
+
+    let latencies: Vec<f64> = measurements
+        .iter()
+        .flat_map(|m| m.latencies_ns.iter().copied())
+        .collect();
+    let latency = LatencySummary::from_samples(&latencies);
+
+    let bytes = workload.bytes_per_round(&config) * config.rounds as u64;
+    let mb_per_second = if stats.median > 0.0 {
+        bytes as f64 / stats.median / 1e6
+    } else {
+        0.0
+    };
+    let output_mb_per_second = config.target.xof().map(|_| {
+        let squeezed = (config.messages * config.rounds * config.xof_output) as f64;
+        if stats.median > 0.0 {
+            squeezed / stats.median / 1e6
+        } else {
+            0.0
+        }
+    });
+    // A run that hashes no bytes has no per-byte cost, so the field is
+    // left out rather than reporting cycles per "one" byte.
+    let cycles_per_byte: Option<Vec<f64>> = measurements
+        .iter()
+        .map(|m| {
+            m.cycles
+                .filter(|_| bytes > 0)
+                .map(|cycles| cycles as f64 / bytes as f64)
+        })
+        .collect();
+    let cycles_per_byte = cycles_per_byte
+        .and_then(|samples| Summary::from_samples(&samples))
+        .map(|summary| summary.median);
+
+    let files = match workload {
+        Workload::Corpus(files) => Some(
+            files
+                .iter()
+                .enumerate()
+                .map(|(idx, file)| {
+                    let samples: Vec<f64> =
+                        measurements.iter().map(|m| m.file_seconds[idx]).collect();
+                    let seconds = Summary::from_samples(&samples)
+                        .expect("at least one trial")
+                        .median;
+                    FileResult::new(&file.path, file.data.len() as u64, config.rounds, seconds)
+                })
+                .collect(),
+        ),
+        Workload::Synthetic | Workload::Generated(_) | Workload::Mapped(_) => None,
+    };
+
+    let runtime = config.async_mode.map(|mode| {
+        // The hop is judged against a synchronous trial of the same
+        // (already warm) workload.
+        let sync = BenchConfig {
+            async_mode: None,
+            ..config.clone()
+        };
+        let reference = run_trial(&sync, workload, config.threads);
+        RuntimeResult::new(
+            mode,
+            config.threads,
+            (config.messages * config.rounds) as u64,
+            stats.median,
+            reference.seconds,
+        )
+    });
+
+    let by_length = config
+        .by_length
+        .then(|| buckets::measure(&config, workload));
+
+    let threading = (config.threads > 1).then(|| {
+        // Scaling is judged against a single-threaded trial on the same
+        // (already warm) workload.
+        let reference = run_trial(&config, workload, 1);
+        let median = &measurements[median_index(&trial_seconds)];
+        ThreadingResult::new(
+            config.rounds,
+            throughput(&config, stats.median),
+            throughput(&config, reference.seconds),
+            median
+                .shards
+                .iter()
+                .map(|shard| (shard.messages, shard.seconds))
+                .collect(),
+        )
+    });
+
+    let median = &measurements[median_index(&trial_seconds)];
+    let counters = median
+        .counters
+        .clone()
+        .map(|counts| CounterSummary::new(counts, (config.messages * config.rounds) as u64));
+    if perf::supported() && counters.is_none() {
+        eprintln!(
+            "warning: {}: hardware counters unavailable (perf_event_open failed)",
+            label
+        );
+    }
+
+    let mapped = match workload {
+        Workload::Mapped(file) => Some(MappedResult {
+            path: file.path.clone(),
+            bytes: workload.bytes_per_round(&config),
+            window: config.chunk_size.unwrap_or(0),
+            faults: median.faults,
+        }),
+        _ => None,
+    };
+
+    let energy = median.energy_joules.map(|joules| {
+        EnergySummary::new(
+            energy::domains(),
+            joules,
+            median.seconds,
+            (config.messages * config.rounds) as u64,
+        )
+    });
+    if energy::supported() && energy.is_none() {
+        eprintln!(
+            "warning: {}: package energy unavailable (no readable RAPL zone under /sys/class/powercap)",
+            label
+        );
+    }
+
+    let allocations = warmup_allocations
+        .zip(trial_allocations)
+        .map(|(warmup, trials)| {
+            let hashes = (config.messages * config.rounds * config.trials) as u64;
+            AllocationReport::new(warmup, trials, hashes)
+        });
+
+    let timer_overhead_ns = config.timer.overhead_ns();
+    let tsc_hz = match config.timer {
+        Timer::Tsc => tsc::frequency(),
+        Timer::Instant => None,
+    };
+
+    BenchResult {
+        schema_version: schema::SCHEMA_VERSION,
+        implementation: label,
+        tags: BTreeMap::new(),
+        operation: config.target.name(),
+        seconds: stats.median,
+        hashes_per_second: throughput(&config, stats.median),
+        mb_per_second,
+        output_mb_per_second,
+        cycles_per_byte,
+        bytes,
+        checksum,
+        config,
+        warmup,
+        trial_seconds,
+        stats,
+        noisy,
+        rounds,
+        soak,
+        latency,
+        files,
+        by_length,
+        mapped,
+        threading,
+        runtime,
+        counters,
+        energy,
+        allocations,
+        histogram: None,
+        tsc_hz,
+        timer_overhead_ns,
+        environment: Environment::current(),
+    }
+}
+
+/// Receives each trial as it completes; see [`RunOptions::on_trial`].
+pub type TrialCallback<'a> = Box<dyn FnMut(&TrialEvent) + 'a>;
+
+/// Extras for [`run_with`]; the default times `config.backend` alone.
+#[derive(Default)]
+pub struct RunOptions<'a> {
+    /// Backends timed with interleaved trials; empty means `config.backend`.
+    pub backends: Vec<Backend>,
+    /// Result label. Defaults to the backend's; with several backends each
+    /// result is labelled `"{label} ({backend})"`.
+    pub label: Option<String>,
+    /// Metadata copied into every result.
+    pub tags: BTreeMap<String, String>,
+    /// Record every hash's latency into an HDR histogram (`hdr` feature).
+    pub histograms: bool,
+    /// Called with each trial as soon as it completes.
+    pub on_trial: Option<TrialCallback<'a>>,
+}
+
+/// Results of [`run`] or [`run_with`], one per backend in the order given.
+pub struct BenchReport {
+    pub results: Vec<BenchResult>,
+    /// Each result's latency histogram and the trial seconds it covers,
+    /// when histograms were requested.
+    pub histograms: Vec<Option<(LatencyHistogram, f64)>>,
+}
+
+impl BenchReport {
+    /// The first two Keccak results whose checksums disagree. BLAKE3 hashes
+    /// something else and is never compared.
+    pub fn checksum_mismatch(&self) -> Option<(&BenchResult, &BenchResult)> {
+        let mut keccak = self
+            .results
+            .iter()
+            .filter(|result| result.config.backend != Backend::Blake3);
+        let first = keccak.next()?;
+        keccak
+            .find(|other| other.checksum != first.checksum)
+            .map(|other| (first, other))
+    }
+
+    /// Write the recorded histograms to `path` as an HdrHistogram interval
+    /// log, one tagged interval per result.
+    pub fn write_histograms(&self, path: &str) -> Result<(), String> {
+        let intervals: Vec<(&str, &LatencyHistogram, f64)> = self
+            .results
+            .iter()
+            .zip(&self.histograms)
+            .filter_map(|(result, recorded)| {
+                let (histogram, seconds) = recorded.as_ref()?;
+                Some((result.implementation.as_str(), histogram, *seconds))
+            })
+            .collect();
+        histogram::write_log(path, &intervals)
+    }
+}
+
+/// Benchmark `config` with its own backend and default options.
+pub fn run(mut config: BenchConfig) -> Result<BenchReport, String> {
+    let workload = Workload::load(&mut config)?;
+    run_with(config, &workload, RunOptions::default())
+}
+
+/// Benchmark `config` over `workload`, as loaded by [`Workload::load`].
+///
+/// Applies the config's CPU pinning and scheduling to the calling thread
+/// before warming up, then runs every backend's trials interleaved.
+pub fn run_with(
+    mut config: BenchConfig,
+    workload: &Workload,
+    mut options: RunOptions,
+) -> Result<BenchReport, String> {
+    if options.histograms {
+        if !histogram::supported() {
+            return Err(String::from(
+                "latency histograms require building with --features hdr",
+            ));
+        }
+        if config.latency_sample == 0 {
+            config.latency_sample = 1;
+        }
+    }
+
+    let backends = if options.backends.is_empty() {
+        vec![config.backend]
+    } else {
+        options.backends.clone()
+    };
+    trace::span!(INFO, "run", backends = ?backends, trials = config.trials);
+    let mut configs: Vec<BenchConfig> = backends
+        .iter()
+        .map(|&backend| BenchConfig {
+            backend,
+            ..config.clone()
+        })
+        .collect();
+    for config in &configs {
+        config.validate()?;
+    }
+    // Pin before warmup, so warmup and measurement share the core's caches.
+    if let Some(core) = config.pin_core {
+        affinity::pin_current_thread(core)?;
+    }
+    config.timer.check()?;
+    config.timer.overhead_ns();
+
+    let generated = match workload {
+        Workload::Synthetic if config.exclude_generation => Some(Workload::generate(&config)),
+        _ => None,
+    };
+    let workload = generated.as_ref().unwrap_or(workload);
+    if config.realtime {
+        affinity::set_realtime()?;
+    }
+
+    let labels: Vec<String> = backends
+        .iter()
+        .map(|&backend| match (&options.label, backends.len()) {
+            (None, _) => backend.label(),
+            (Some(label), 1) => label.clone(),
+            (Some(label), _) => format!("{} ({})", label, backend),
+        })
+        .collect();
+    let mut soak_origin: Option<Instant> = None;
+    let runs = run_benchmark(
+        &mut configs,
+        workload,
+        |config, backend, trial, measurement| {
+            if let Some(on_trial) = &mut options.on_trial {
+                on_trial(&TrialEvent {
+                    event: "trial",
+                    implementation: &labels[backend],
+                    trial,
+                    trials: config.trials,
+                    seconds: measurement.seconds,
+                    hashes_per_second: throughput(config, measurement.seconds),
+                    checksum: measurement.checksum,
+                    elapsed_seconds: config.soak.map(|_| {
+                        let origin = *soak_origin.get_or_insert(measurement.started);
+                        measurement.started.duration_since(origin).as_secs_f64()
+                            + measurement.seconds
+                    }),
+                });
+            }
+        },
+    )?;
+    let histograms: Vec<Option<(LatencyHistogram, f64)>> = runs
+        .iter()
+        .map(|run| {
+            if !options.histograms {
+                return None;
+            }
+            let latencies: Vec<f64> = run
+                .measurements
+                .iter()
+                .flat_map(|m| m.latencies_ns.iter().copied())
+                .collect();
+            let seconds = run.measurements.iter().map(|m| m.seconds).sum();
+            LatencyHistogram::record(&latencies).map(|histogram| (histogram, seconds))
+        })
+        .collect();
+    let results = configs
+        .into_iter()
+        .zip(labels)
+        .zip(runs)
+        .zip(&histograms)
+        .map(|(((config, label), run), recorded)| BenchResult {
+            tags: options.tags.clone(),
+            histogram: recorded.as_ref().map(|(histogram, _)| histogram.summary()),
+            ..summarize(config, workload, label, run)
+        })
+        .collect();
+    Ok(BenchReport {
+        results,
+        histograms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sharding must not change what gets hashed.
+    #[test]
+    fn threaded_trials_match_single_threaded_checksum() {
+        let config = BenchConfig {
+            messages: 37,
+            rounds: 3,
+            seed: Some(7),
+            ..BenchConfig::default()
+        };
+        let single = run_trial(&config, &Workload::Synthetic, 1);
+        let threaded = run_trial(&config, &Workload::Synthetic, 4);
+        assert_eq!(single.checksum, threaded.checksum);
+        let fnv = BenchConfig {
+            checksum_mode: ChecksumMode::Fnv64,
+            ..config.clone()
+        };
+        let single = run_trial(&fnv, &Workload::Synthetic, 1);
+        let threaded = run_trial(&fnv, &Workload::Synthetic, 4);
+        assert_eq!(single.checksum, threaded.checksum);
+        assert_eq!(threaded.shards.len(), 4);
+        let messages: usize = threaded.shards.iter().map(|shard| shard.messages).sum();
+        assert_eq!(messages, 37);
+    }
+
+    /// Messages generated up front are the ones the loop would generate.
+    #[test]
+    fn pregenerated_messages_match_on_the_fly() {
+        let config = BenchConfig {
+            messages: 23,
+            rounds: 2,
+            seed: Some(11),
+            ..BenchConfig::default()
+        };
+        let generated = Workload::generate(&config);
+        assert_eq!(
+            generated.bytes_per_round(&config),
+            Workload::Synthetic.bytes_per_round(&config)
+        );
+        assert_eq!(
+            run_trial(&config, &generated, 1).checksum,
+            run_trial(&config, &Workload::Synthetic, 1).checksum
+        );
+    }
+
+    /// Filling one reused buffer hashes the same bytes as fresh messages.
+    #[test]
+    fn reused_buffer_matches_fresh_messages() {
+        let fresh = BenchConfig {
+            messages: 19,
+            rounds: 2,
+            seed: Some(5),
+            exclude_generation: false,
+            ..BenchConfig::default()
+        };
+        let reused = BenchConfig {
+            reuse_buffer: true,
+            ..fresh.clone()
+        };
+        assert!(reused.validate().is_ok());
+        assert_eq!(
+            run_trial(&reused, &Workload::Synthetic, 1).checksum,
+            run_trial(&fresh, &Workload::Synthetic, 1).checksum
+        );
+        let pregenerated = BenchConfig {
+            exclude_generation: true,
+            ..reused
+        };
+        assert!(pregenerated.validate().is_err());
+    }
+
+    /// The library entry point reports what a bare trial computes.
+    #[test]
+    fn run_reports_each_backend() {
+        let config = BenchConfig {
+            messages: 8,
+            rounds: 2,
+            warmup: Warmup::Fixed(0),
+            ..BenchConfig::default()
+        };
+        let expected = run_trial(&config, &Workload::Synthetic, 1).checksum;
+        let report = run(config.clone()).unwrap();
+        assert_eq!(report.results.len(), 1);
+        assert_eq!(report.results[0].checksum, expected);
+        assert_eq!(report.results[0].implementation, "rust (tiny-keccak)");
+
+        let options = RunOptions {
+            backends: vec![Backend::TinyKeccak, Backend::Native],
+            label: Some(String::from("ci")),
+            ..RunOptions::default()
+        };
+        let report = run_with(config, &Workload::Synthetic, options).unwrap();
+        assert_eq!(report.results[1].implementation, "ci (native)");
+        assert!(report.checksum_mismatch().is_none());
+    }
+
+    /// Hashing only empty messages reports no cycles per byte.
+    #[test]
+    fn empty_messages_have_no_cycles_per_byte() {
+        let config = BenchConfig {
+            messages: 4,
+            rounds: 2,
+            min_len: 0,
+            max_len: 0,
+            warmup: Warmup::Fixed(0),
+            ..BenchConfig::default()
+        };
+        let report = run(config).unwrap();
+        assert_eq!(report.results[0].bytes, 0);
+        assert_eq!(report.results[0].cycles_per_byte, None);
+    }
+
+    /// One call per batch hashes exactly what one call per message does.
+    #[test]
+    fn batched_trials_match_per_message_checksum() {
+        let config = BenchConfig {
+            messages: 37,
+            rounds: 2,
+            seed: Some(3),
+            ..BenchConfig::default()
+        };
+        let expected = run_trial(&config, &Workload::Synthetic, 1).checksum;
+        for backend in [Backend::TinyKeccak, Backend::Native] {
+            let batched = BenchConfig {
+                backend,
+                batch: Some(8),
+                ..config.clone()
+            };
+            assert_eq!(
+                run_trial(&batched, &Workload::Synthetic, 1).checksum,
+                expected
+            );
+            assert_eq!(
+                run_trial(&batched, &Workload::Synthetic, 3).checksum,
+                expected
+            );
+        }
+    }
+
+    /// A duration budget replaces the fixed rounds for every backend alike.
+    #[test]
+    fn duration_calibrates_shared_rounds() {
+        let config = BenchConfig {
+            messages: 16,
+            rounds: 1_000_000,
+            warmup: Warmup::Fixed(0),
+            duration: Some("20ms".parse().unwrap()),
+            ..BenchConfig::default()
+        };
+        let mut configs = [
+            config.clone(),
+            BenchConfig {
+                backend: Backend::Native,
+                ..config
+            },
+        ];
+        run_benchmark(&mut configs, &Workload::Synthetic, |_, _, _, _| {}).unwrap();
+        assert!(configs[0].rounds < 1_000_000);
+        assert_eq!(configs[0].rounds, configs[1].rounds);
+    }
+
+    /// Trials alternate between backends and are reported as they complete.
+    #[test]
+    fn run_benchmark_interleaves_backends() {
+        let config = BenchConfig {
+            messages: 8,
+            rounds: 2,
+            warmup: Warmup::Fixed(0),
+            trials: 3,
+            ..BenchConfig::default()
+        };
+        let mut configs = [
+            config.clone(),
+            BenchConfig {
+                backend: Backend::Native,
+                ..config
+            },
+        ];
+        let mut seen = Vec::new();
+        let runs = run_benchmark(
+            &mut configs,
+            &Workload::Synthetic,
+            |_, backend, trial, _| {
+                seen.push((trial, backend));
+            },
+        )
+        .unwrap();
+        assert_eq!(seen, [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)]);
+        assert_eq!(runs.len(), 2);
+        for run in &runs {
+            assert_eq!(run.warmup.rounds, 0);
+            assert_eq!(run.measurements.len(), 3);
+            assert_eq!(
+                run.measurements[0].checksum,
+                runs[0].measurements[0].checksum
+            );
+        }
+    }
+}
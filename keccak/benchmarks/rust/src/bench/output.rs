@@ -0,0 +1,879 @@
+//! Result rendering for the table, JSON, CSV, markdown, TOML, and YAML
+//! formats.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::bench::alloc::AllocationReport;
+use crate::bench::buckets::LengthBucket;
+use crate::bench::compare::Record;
+use crate::bench::energy::EnergySummary;
+use crate::bench::environment::Environment;
+use crate::bench::histogram::HistogramSummary;
+use crate::bench::mapped::MappedResult;
+use crate::bench::markup;
+use crate::bench::perf::CounterSummary;
+use crate::bench::report;
+use crate::bench::rounds::RoundReport;
+use crate::bench::runtime::RuntimeResult;
+use crate::bench::soak::SoakReport;
+use crate::bench::stats::{LatencySummary, Summary};
+use crate::bench::warmup::WarmupResult;
+use crate::bench::BenchConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+    Markdown,
+    Toml,
+    Yaml,
+}
+
+impl FromStr for OutputFormat {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "toml" => Ok(OutputFormat::Toml),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The `operation` of Keccak-256 hashing results.
+pub const KECCAK256: &str = "keccak256";
+
+/// One benchmark run as emitted by the JSON and CSV formats.
+///
+/// `seconds`, `hashes_per_second`, `mb_per_second`, and `cycles_per_byte`
+/// describe the median trial.
+#[derive(Debug, Serialize)]
+pub struct BenchResult {
+    /// Always [`SCHEMA_VERSION`](crate::bench::schema::SCHEMA_VERSION).
+    pub schema_version: u64,
+    pub implementation: String,
+    /// `--tag` metadata, e.g. the machine or branch the run came from.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    /// What was timed: the config's target, [`KECCAK256`] by default.
+    pub operation: &'static str,
+    pub seconds: f64,
+    pub hashes_per_second: f64,
+    /// Throughput in megabytes (10^6 bytes) per second.
+    pub mb_per_second: f64,
+    /// Squeezed output in megabytes per second, present with `--xof`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_mb_per_second: Option<f64>,
+    /// TSC ticks per input byte; `null` on targets without a TSC.
+    pub cycles_per_byte: Option<f64>,
+    /// Input bytes hashed per trial.
+    pub bytes: u64,
+    pub checksum: u64,
+    pub config: BenchConfig,
+    /// Rounds and seconds spent warming up before the first trial.
+    pub warmup: WarmupResult,
+    /// Wall-clock seconds of every measured trial, in run order.
+    pub trial_seconds: Vec<f64>,
+    /// Statistics over `trial_seconds`.
+    pub stats: Summary,
+    /// Set when the coefficient of variation exceeds the noise threshold.
+    pub noisy: bool,
+    /// Outlier rounds and throttling, judged from every timed round.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounds: Option<RoundReport>,
+    /// Throughput drift over the run, present for `--soak`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub soak: Option<SoakReport>,
+    /// Per-hash latency quantiles, present when `--latency-sample` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencySummary>,
+    /// Per-file breakdown, present for `--corpus` runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<FileResult>>,
+    /// Throughput per message-length range, present with `--by-length`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_length: Option<Vec<LengthBucket>>,
+    /// The mapped input and its page faults, present for `--mmap` runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mapped: Option<MappedResult>,
+    /// Per-thread breakdown and scaling, present when `--threads` > 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threading: Option<ThreadingResult>,
+    /// Hardware counters for the median trial, with the `perf` feature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counters: Option<CounterSummary>,
+    /// Async hop cost against a synchronous trial, present with `--async`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<RuntimeResult>,
+    /// Package energy for the median trial, with the `energy` feature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub energy: Option<EnergySummary>,
+    /// Allocations per phase, with the `alloc-stats` feature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allocations: Option<AllocationReport>,
+    /// Quantiles of the full latency histogram written by `--histogram`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<HistogramSummary>,
+    /// Calibrated TSC frequency, present with `--timer tsc`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tsc_hz: Option<f64>,
+    /// Cost of one clock-read pair, subtracted from every per-hash time.
+    pub timer_overhead_ns: f64,
+    /// Machine and build metadata.
+    pub environment: Environment,
+}
+
+/// One completed trial, emitted as a JSON line by `--stream`.
+#[derive(Debug, Serialize)]
+pub struct TrialEvent<'a> {
+    /// Always `"trial"`, so the final result line can be told apart.
+    pub event: &'static str,
+    pub implementation: &'a str,
+    /// Zero-based trial number.
+    pub trial: usize,
+    pub trials: usize,
+    pub seconds: f64,
+    pub hashes_per_second: f64,
+    pub checksum: u64,
+    /// Seconds since the first soak trial started, for `--soak` samples.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_seconds: Option<f64>,
+}
+
+/// Multi-threaded throughput breakdown for `--threads` > 1.
+#[derive(Debug, Serialize)]
+pub struct ThreadingResult {
+    /// Aggregate hashes/s of a single-threaded reference trial.
+    pub single_thread_hashes_per_second: f64,
+    /// Aggregate speedup over the reference, divided by the thread count.
+    pub scaling_efficiency: f64,
+    /// Per-worker numbers from the median trial.
+    pub per_thread: Vec<ThreadResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThreadResult {
+    pub thread: usize,
+    pub messages: usize,
+    pub seconds: f64,
+    pub hashes_per_second: f64,
+}
+
+impl ThreadingResult {
+    /// `shards` holds `(messages, seconds)` for each worker.
+    pub fn new(
+        rounds: usize,
+        hashes_per_second: f64,
+        single_thread_hashes_per_second: f64,
+        shards: Vec<(usize, f64)>,
+    ) -> ThreadingResult {
+        let threads = shards.len() as f64;
+        let scaling_efficiency = if single_thread_hashes_per_second > 0.0 {
+            hashes_per_second / (single_thread_hashes_per_second * threads)
+        } else {
+            0.0
+        };
+        let per_thread = shards
+            .into_iter()
+            .enumerate()
+            .map(|(thread, (messages, seconds))| ThreadResult {
+                thread,
+                messages,
+                seconds,
+                hashes_per_second: if seconds > 0.0 {
+                    (messages * rounds) as f64 / seconds
+                } else {
+                    0.0
+                },
+            })
+            .collect();
+        ThreadingResult {
+            single_thread_hashes_per_second,
+            scaling_efficiency,
+            per_thread,
+        }
+    }
+}
+
+/// Throughput for one corpus file over all rounds of the median trial.
+#[derive(Debug, Serialize)]
+pub struct FileResult {
+    pub path: String,
+    pub bytes: u64,
+    pub seconds: f64,
+    pub hashes_per_second: f64,
+    pub mb_per_second: f64,
+}
+
+impl FileResult {
+    pub fn new(path: &str, bytes: u64, rounds: usize, seconds: f64) -> FileResult {
+        let (hashes_per_second, mb_per_second) = if seconds > 0.0 {
+            (
+                rounds as f64 / seconds,
+                (bytes * rounds as u64) as f64 / seconds / 1e6,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        FileResult {
+            path: path.to_string(),
+            bytes,
+            seconds,
+            hashes_per_second,
+            mb_per_second,
+        }
+    }
+}
+
+pub fn print(format: OutputFormat, results: &[BenchResult]) {
+    print!("{}", render(format, results, true));
+}
+
+/// Write `result` to `path` through a temporary file and a rename, so readers
+/// and concurrent runs never observe a half-written file. With `append` the
+/// existing contents are kept and the result follows them: one more line for
+/// JSON (making the file JSON Lines) and one more row for CSV.
+pub fn write_file(
+    path: &Path,
+    format: OutputFormat,
+    results: &[BenchResult],
+    append: bool,
+) -> Result<(), String> {
+    let describe = |err: io::Error| format!("{}: {}", path.display(), err);
+    let mut contents = if append {
+        match fs::read_to_string(path) {
+            Ok(existing) => existing,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(describe(err)),
+        }
+    } else {
+        String::new()
+    };
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(&render(format, results, contents.is_empty()));
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("{}: not a file path", path.display()))?;
+    let mut temp_name = file_name.to_os_string();
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp = path.with_file_name(temp_name);
+    fs::write(&temp, contents).map_err(describe)?;
+    fs::rename(&temp, path).map_err(|err| {
+        let _ = fs::remove_file(&temp);
+        describe(err)
+    })
+}
+
+/// Render `results` in `format`: one JSON object per line, one CSV row per
+/// result, consecutive tables, a single markdown table, one `[[results]]`
+/// TOML table per result, or one YAML document per result. `header` controls
+/// whether CSV output starts with its header row, so appended rows can share
+/// one.
+pub fn render(format: OutputFormat, results: &[BenchResult], header: bool) -> String {
+    match format {
+        OutputFormat::Table => results
+            .iter()
+            .map(render_table)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Json => results.iter().map(render_json).collect(),
+        OutputFormat::Csv => results
+            .iter()
+            .enumerate()
+            .map(|(idx, result)| render_csv(result, header && idx == 0))
+            .collect(),
+        OutputFormat::Markdown => {
+            let records: Vec<Record> = results.iter().map(Record::from_result).collect();
+            report::render(&records, None).expect("results are renderable")
+        }
+        OutputFormat::Toml => results
+            .iter()
+            .map(|result| markup::toml(&to_value(result)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Yaml => results
+            .iter()
+            .map(|result| markup::yaml(&to_value(result)))
+            .collect(),
+    }
+}
+
+/// Write one `--stream` line to stdout. Stdout is line-buffered, so each
+/// event is visible to a reader as soon as it is printed.
+pub fn print_trial(event: &TrialEvent) {
+    let json = serde_json::to_string(event).expect("trial events serialize to JSON");
+    println!("{}", json);
+}
+
+fn render_table(result: &BenchResult) -> String {
+    let mut out = String::new();
+    let cycles_per_byte = match result.cycles_per_byte {
+        Some(value) => format!("{:.2}", value),
+        None => String::from("-"),
+    };
+    writeln!(
+        out,
+        "implementation | seconds | hashes/s | MB/s | cycles/byte | checksum"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "-------------- | ------- | -------- | ---- | ----------- | --------"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "{} | {:.9} | {:.2} | {:.2} | {} | {}",
+        result.implementation,
+        result.seconds,
+        result.hashes_per_second,
+        result.mb_per_second,
+        cycles_per_byte,
+        result.checksum
+    )
+    .unwrap();
+    if result.config.sweep.is_some() {
+        writeln!(out, "message size: {} bytes", result.config.max_len).unwrap();
+    }
+    if result.trial_seconds.len() > 1 {
+        let stats = &result.stats;
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "trials: {} | mean {:.9} s | median {:.9} s | stddev {:.9} s | min {:.9} s | max {:.9} s | cv {:.2}%{}",
+            result.trial_seconds.len(),
+            stats.mean,
+            stats.median,
+            stats.stddev,
+            stats.min,
+            stats.max,
+            stats.cv * 100.0,
+            if result.noisy { " (noisy)" } else { "" }
+        )
+        .unwrap();
+    }
+    if let Some(latency) = &result.latency {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "latency ({} samples): p50 {:.0} ns | p90 {:.0} ns | p99 {:.0} ns | p99.9 {:.0} ns | max {:.0} ns | clock overhead {:.0} ns subtracted",
+            latency.samples,
+            latency.p50_ns,
+            latency.p90_ns,
+            latency.p99_ns,
+            latency.p999_ns,
+            latency.max_ns,
+            result.timer_overhead_ns
+        )
+        .unwrap();
+    }
+    if let Some(counters) = &result.counters {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "counters: {} instructions | {} cycles | IPC {:.2} | {:.1} instructions/hash | {} branch misses | {} LLC misses",
+            counters.counts.instructions,
+            counters.counts.cycles,
+            counters.ipc,
+            counters.instructions_per_hash,
+            counters.counts.branch_misses,
+            counters.counts.llc_misses
+        )
+        .unwrap();
+    }
+    if let Some(runtime) = &result.runtime {
+        writeln!(
+            out,
+            "async ({}, threads {}): {:.2} hashes/s vs {:.2} synchronous | {:+.1} ns per hash",
+            runtime.mode,
+            runtime.workers,
+            runtime.hashes_per_second,
+            runtime.sync_hashes_per_second,
+            runtime.overhead_ns_per_hash
+        )
+        .unwrap();
+    }
+    if let Some(energy) = &result.energy {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "energy: {:.3} J | {:.2} W | {:.2} hashes/J ({})",
+            energy.joules,
+            energy.watts,
+            energy.hashes_per_joule,
+            energy.domains.join(", ")
+        )
+        .unwrap();
+    }
+    if let Some(allocations) = &result.allocations {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "allocations: warmup {} ({} bytes) | trials {} ({} bytes) | {:.2} per hash | {:.1} bytes/hash",
+            allocations.warmup.count,
+            allocations.warmup.bytes,
+            allocations.trials.count,
+            allocations.trials.bytes,
+            allocations.per_hash,
+            allocations.bytes_per_hash
+        )
+        .unwrap();
+    }
+    if let Some(threading) = &result.threading {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "threads: {} | single-thread {:.2} hashes/s | scaling efficiency {:.1}%",
+            threading.per_thread.len(),
+            threading.single_thread_hashes_per_second,
+            threading.scaling_efficiency * 100.0
+        )
+        .unwrap();
+        writeln!(out, "thread | messages | seconds | hashes/s").unwrap();
+        writeln!(out, "------ | -------- | ------- | --------").unwrap();
+        for thread in &threading.per_thread {
+            writeln!(
+                out,
+                "{} | {} | {:.9} | {:.2}",
+                thread.thread, thread.messages, thread.seconds, thread.hashes_per_second
+            )
+            .unwrap();
+        }
+    }
+    if let Some(files) = &result.files {
+        writeln!(out).unwrap();
+        writeln!(out, "file | bytes | seconds | hashes/s | MB/s").unwrap();
+        writeln!(out, "---- | ----- | ------- | -------- | ----").unwrap();
+        for file in files {
+            writeln!(
+                out,
+                "{} | {} | {:.9} | {:.2} | {:.2}",
+                file.path, file.bytes, file.seconds, file.hashes_per_second, file.mb_per_second
+            )
+            .unwrap();
+        }
+    }
+    if let Some(buckets) = &result.by_length {
+        writeln!(out).unwrap();
+        writeln!(out, "lengths | messages | seconds | hashes/s | MB/s").unwrap();
+        writeln!(out, "------- | -------- | ------- | -------- | ----").unwrap();
+        for bucket in buckets {
+            writeln!(
+                out,
+                "{}-{} | {} | {:.9} | {:.2} | {:.2}",
+                bucket.min_len,
+                bucket.max_len,
+                bucket.messages,
+                bucket.seconds,
+                bucket.hashes_per_second,
+                bucket.mb_per_second
+            )
+            .unwrap();
+        }
+    }
+    if let Some(mapped) = &result.mapped {
+        writeln!(out).unwrap();
+        write!(
+            out,
+            "mapped: {} ({} bytes in {}-byte windows)",
+            mapped.path, mapped.bytes, mapped.window
+        )
+        .unwrap();
+        if let Some(faults) = mapped.faults {
+            write!(
+                out,
+                " | {} minor / {} major page faults per trial",
+                faults.minor, faults.major
+            )
+            .unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+    out
+}
+
+fn to_value(result: &BenchResult) -> serde_json::Value {
+    serde_json::to_value(result).expect("bench results serialize to JSON")
+}
+
+fn render_json(result: &BenchResult) -> String {
+    let json = serde_json::to_string(result).expect("bench results serialize to JSON");
+    format!("{}\n", json)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_csv(result: &BenchResult, header: bool) -> String {
+    let config = &result.config;
+    let stats = &result.stats;
+    let latency = result.latency.as_ref();
+    let counters = result.counters.as_ref();
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    let columns: Vec<(&str, String)> = vec![
+        ("implementation", csv_field(&result.implementation)),
+        ("seconds", format!("{:.12}", result.seconds)),
+        (
+            "hashes_per_second",
+            format!("{:.2}", result.hashes_per_second),
+        ),
+        ("checksum", result.checksum.to_string()),
+        ("checksum_mode", config.checksum_mode.to_string()),
+        ("messages", config.messages.to_string()),
+        ("rounds", config.rounds.to_string()),
+        ("min_len", config.min_len.to_string()),
+        ("max_len", config.max_len.to_string()),
+        ("stride", config.stride.to_string()),
+        ("warmup", config.warmup.to_string()),
+        ("trials", config.trials.to_string()),
+        ("threads", config.threads.to_string()),
+        ("mean_seconds", format!("{:.12}", stats.mean)),
+        ("median_seconds", format!("{:.12}", stats.median)),
+        ("stddev_seconds", format!("{:.12}", stats.stddev)),
+        ("min_seconds", format!("{:.12}", stats.min)),
+        ("max_seconds", format!("{:.12}", stats.max)),
+        ("cv", format!("{:.6}", stats.cv)),
+        ("noisy", result.noisy.to_string()),
+        (
+            "latency_samples",
+            optional(latency.map(|l| l.samples.to_string())),
+        ),
+        (
+            "p50_ns",
+            optional(latency.map(|l| format!("{:.0}", l.p50_ns))),
+        ),
+        (
+            "p90_ns",
+            optional(latency.map(|l| format!("{:.0}", l.p90_ns))),
+        ),
+        (
+            "p99_ns",
+            optional(latency.map(|l| format!("{:.0}", l.p99_ns))),
+        ),
+        (
+            "p999_ns",
+            optional(latency.map(|l| format!("{:.0}", l.p999_ns))),
+        ),
+        (
+            "max_ns",
+            optional(latency.map(|l| format!("{:.0}", l.max_ns))),
+        ),
+        ("bytes", result.bytes.to_string()),
+        ("mb_per_second", format!("{:.2}", result.mb_per_second)),
+        (
+            "cycles_per_byte",
+            optional(result.cycles_per_byte.map(|value| format!("{:.4}", value))),
+        ),
+        ("backend", config.backend.to_string()),
+        (
+            "duration_seconds",
+            optional(config.duration.map(|budget| budget.seconds.to_string())),
+        ),
+        (
+            "pin_core",
+            optional(config.pin_core.map(|core| core.to_string())),
+        ),
+        ("realtime", config.realtime.to_string()),
+        ("timer", config.timer.to_string()),
+        (
+            "chunk_size",
+            optional(config.chunk_size.map(|chunk| chunk.to_string())),
+        ),
+        (
+            "batch",
+            optional(config.batch.map(|batch| batch.to_string())),
+        ),
+        ("exclude_generation", config.exclude_generation.to_string()),
+        ("reuse_buffer", config.reuse_buffer.to_string()),
+        (
+            "length_dist",
+            optional(config.length_dist.as_ref().map(|dist| dist.path.clone())),
+        ),
+        (
+            "sweep",
+            optional(config.sweep.map(|sweep| sweep.to_string())),
+        ),
+        (
+            "xof",
+            optional(config.target.xof().map(|xof| xof.to_string())),
+        ),
+        (
+            "xof_output",
+            optional(config.target.xof().map(|_| config.xof_output.to_string())),
+        ),
+        (
+            "allocations_per_hash",
+            optional(
+                result
+                    .allocations
+                    .as_ref()
+                    .map(|a| format!("{:.4}", a.per_hash)),
+            ),
+        ),
+        (
+            "instructions",
+            optional(counters.map(|c| c.counts.instructions.to_string())),
+        ),
+        (
+            "core_cycles",
+            optional(counters.map(|c| c.counts.cycles.to_string())),
+        ),
+        (
+            "branch_misses",
+            optional(counters.map(|c| c.counts.branch_misses.to_string())),
+        ),
+        (
+            "llc_misses",
+            optional(counters.map(|c| c.counts.llc_misses.to_string())),
+        ),
+        ("warmup_rounds", result.warmup.rounds.to_string()),
+        ("warmup_seconds", format!("{:.6}", result.warmup.seconds)),
+        (
+            "scaling_efficiency",
+            optional(
+                result
+                    .threading
+                    .as_ref()
+                    .map(|t| format!("{:.4}", t.scaling_efficiency)),
+            ),
+        ),
+        ("operation", result.operation.to_string()),
+        (
+            "output_mb_per_second",
+            optional(result.output_mb_per_second.map(|v| format!("{:.2}", v))),
+        ),
+        (
+            "timer_overhead_ns",
+            format!("{:.1}", result.timer_overhead_ns),
+        ),
+        (
+            "outlier_rounds",
+            optional(result.rounds.as_ref().map(|r| r.outliers.len().to_string())),
+        ),
+        (
+            "throttling",
+            optional(result.rounds.as_ref().map(|r| r.throttling.to_string())),
+        ),
+        (
+            "soak_drift",
+            optional(result.soak.as_ref().map(|s| format!("{:.6}", s.drift))),
+        ),
+        (
+            "hashes_per_joule",
+            optional(
+                result
+                    .energy
+                    .as_ref()
+                    .map(|e| format!("{:.2}", e.hashes_per_joule)),
+            ),
+        ),
+        (
+            "tags",
+            csv_field(
+                &result
+                    .tags
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(";"),
+            ),
+        ),
+    ];
+    let names: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+    let row: Vec<&str> = columns.iter().map(|(_, value)| value.as_str()).collect();
+    if header {
+        format!("{}\n{}\n", names.join(","), row.join(","))
+    } else {
+        format!("{}\n", row.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::schema;
+
+    fn sample_result() -> BenchResult {
+        BenchResult {
+            schema_version: schema::SCHEMA_VERSION,
+            implementation: String::from("rust \"tiny\" \\ keccak"),
+            tags: BTreeMap::from([(String::from("machine"), String::from("m1-max"))]),
+            operation: KECCAK256,
+            seconds: 1.5,
+            hashes_per_second: 2.0,
+            mb_per_second: 3.0,
+            output_mb_per_second: None,
+            cycles_per_byte: None,
+            bytes: 4,
+            checksum: 7,
+            config: BenchConfig::default(),
+            warmup: WarmupResult {
+                rounds: 3,
+                seconds: 0.5,
+                converged: None,
+            },
+            trial_seconds: vec![1.5],
+            stats: Summary::from_samples(&[1.5]).unwrap(),
+            noisy: false,
+            latency: None,
+            files: None,
+            by_length: None,
+            mapped: None,
+            runtime: None,
+            threading: None,
+            counters: None,
+            energy: None,
+            allocations: None,
+            histogram: None,
+            tsc_hz: None,
+            timer_overhead_ns: 20.0,
+            rounds: None,
+            soak: None,
+            environment: Environment::current(),
+        }
+    }
+
+    /// Labels with JSON metacharacters must still produce valid JSON.
+    #[test]
+    fn json_output_escapes_labels() {
+        let result = sample_result();
+        let json = serde_json::to_string(&result).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["implementation"], "rust \"tiny\" \\ keccak");
+        assert_eq!(parsed["config"]["messages"], 512);
+        assert_eq!(parsed["tags"]["machine"], "m1-max");
+    }
+
+    /// What this build writes passes its own schema check.
+    #[test]
+    fn json_output_matches_schema() {
+        let value = serde_json::to_value(sample_result()).unwrap();
+        assert_eq!(schema::check(&value), Vec::<String>::new());
+    }
+
+    /// TOML and YAML emit one table or document per result.
+    #[test]
+    fn toml_and_yaml_repeat_per_result() {
+        let results = [sample_result(), sample_result()];
+        let toml = render(OutputFormat::Toml, &results, true);
+        assert_eq!(toml.matches("[[results]]\n").count(), 2);
+        assert_eq!(toml.matches("[results.config]\n").count(), 2);
+        assert!(toml.contains("schema_version = 1\n"));
+        let yaml = render(OutputFormat::Yaml, &results, true);
+        assert_eq!(yaml.matches("---\n").count(), 2);
+        assert!(yaml.contains("\nconfig:\n  async_mode: null\n  backend: \"tiny-keccak\"\n"));
+    }
+
+    /// A real three-trial run with latency sampling, with every measured
+    /// time replaced by a fixed value and the environment by a fixed
+    /// machine, so only a format change alters its rendering.
+    fn normalized_results() -> Vec<BenchResult> {
+        let config = BenchConfig {
+            backend: crate::bench::backend::Backend::Native,
+            seed: Some(7),
+            messages: 8,
+            rounds: 2,
+            trials: 3,
+            warmup: crate::bench::warmup::Warmup::Fixed(1),
+            latency_sample: 4,
+            ..BenchConfig::default()
+        };
+        let mut results = crate::bench::run(config).unwrap().results;
+        for result in &mut results {
+            result.seconds = 0.25;
+            result.hashes_per_second = 64.0;
+            result.mb_per_second = 0.5;
+            // Present only where there is a TSC.
+            result.cycles_per_byte = Some(12.5);
+            result.warmup.seconds = 0.125;
+            result.trial_seconds = vec![0.25, 0.5, 0.125];
+            result.stats = Summary::from_samples(&result.trial_seconds).unwrap();
+            result.noisy = false;
+            result.timer_overhead_ns = 20.0;
+            // Filled in only with the perf, energy and alloc-stats features.
+            result.counters = None;
+            result.energy = None;
+            result.allocations = None;
+            if let Some(rounds) = &mut result.rounds {
+                rounds.median_seconds = 0.125;
+                rounds.mad_seconds = 0.0;
+                rounds.outliers.clear();
+                rounds.slowdown = 0.0;
+                rounds.throttling = false;
+            }
+            if let Some(latency) = &mut result.latency {
+                latency.p50_ns = 100.0;
+                latency.p90_ns = 200.0;
+                latency.p99_ns = 300.0;
+                latency.p999_ns = 400.0;
+                latency.max_ns = 500.0;
+            }
+            result.environment = Environment {
+                os: "linux",
+                arch: "x86_64",
+                cpu_model: Some(String::from("Example CPU")),
+                logical_cores: Some(8),
+                rustc_version: "rustc 1.0.0",
+                crate_version: "0.0.0",
+                build_profile: "test",
+                target_features: vec!["sse2"],
+                cpu_features: vec!["sse2", "avx2"],
+                simd_dispatch: crate::parallel::dispatch::Simd::Avx2,
+            };
+        }
+        results
+    }
+
+    /// Table, JSON, CSV and markdown renderings match their reviewed
+    /// snapshots; run `cargo insta review` after an intended change.
+    #[test]
+    #[cfg_attr(miri, ignore = "insta runs cargo to find the workspace")]
+    fn output_formats_match_snapshots() {
+        let results = normalized_results();
+        insta::assert_snapshot!("table", render(OutputFormat::Table, &results, true));
+        let json = render(OutputFormat::Json, &results, true);
+        assert_eq!(json.lines().count(), 1);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        insta::assert_snapshot!("json", serde_json::to_string_pretty(&value).unwrap());
+        insta::assert_snapshot!("csv", render(OutputFormat::Csv, &results, true));
+        insta::assert_snapshot!("markdown", render(OutputFormat::Markdown, &results, true));
+    }
+
+    /// Appending keeps earlier results and writes the CSV header once.
+    #[test]
+    fn write_file_appends_without_repeating_header() {
+        let path = std::env::temp_dir().join(format!("keccak-bench-output-{}.csv", process::id()));
+        let _ = fs::remove_file(&path);
+        let results = [sample_result()];
+        write_file(&path, OutputFormat::Csv, &results, true).unwrap();
+        write_file(&path, OutputFormat::Csv, &results, true).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 3);
+        assert_eq!(text.matches("implementation,").count(), 1);
+        write_file(&path, OutputFormat::Csv, &results, false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+}
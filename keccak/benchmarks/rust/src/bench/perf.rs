@@ -0,0 +1,234 @@
+//! Hardware performance counters via `perf_event_open` (Linux, `perf`
+//! feature).
+//!
+//! Counters exclude the kernel and hypervisor, so they open under the
+//! default `perf_event_paranoid` level of 2. Each counter inherits into
+//! threads spawned while it is enabled, which covers `--threads` workers.
+
+use serde::{Deserialize, Serialize};
+
+/// Counts for one measured trial.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PerfCounts {
+    pub instructions: u64,
+    pub cycles: u64,
+    pub branch_misses: u64,
+    /// Last-level cache misses (`PERF_COUNT_HW_CACHE_MISSES`).
+    pub llc_misses: u64,
+}
+
+impl PerfCounts {
+    /// Instructions retired per core cycle.
+    pub fn ipc(&self) -> f64 {
+        if self.cycles > 0 {
+            self.instructions as f64 / self.cycles as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Counts from the median trial with per-hash ratios, as reported.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CounterSummary {
+    #[serde(flatten)]
+    pub counts: PerfCounts,
+    pub ipc: f64,
+    pub instructions_per_hash: f64,
+    pub branch_misses_per_hash: f64,
+}
+
+impl CounterSummary {
+    pub fn new(counts: PerfCounts, hashes: u64) -> CounterSummary {
+        let per_hash = |count: u64| {
+            if hashes > 0 {
+                count as f64 / hashes as f64
+            } else {
+                0.0
+            }
+        };
+        CounterSummary {
+            ipc: counts.ipc(),
+            instructions_per_hash: per_hash(counts.instructions),
+            branch_misses_per_hash: per_hash(counts.branch_misses),
+            counts,
+        }
+    }
+}
+
+#[cfg(all(feature = "perf", target_os = "linux", not(feature = "safe-only")))]
+mod imp {
+    use std::fs::File;
+    use std::io::Read;
+    use std::os::fd::FromRawFd;
+
+    use super::PerfCounts;
+
+    const PERF_TYPE_HARDWARE: u32 = 0;
+    const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+    const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+    const PERF_COUNT_HW_CACHE_MISSES: u64 = 3;
+    const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+
+    const FLAG_DISABLED: u64 = 1 << 0;
+    const FLAG_INHERIT: u64 = 1 << 1;
+    const FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+    const FLAG_EXCLUDE_HV: u64 = 1 << 6;
+
+    const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+    const PERF_EVENT_IOC_DISABLE: libc::c_ulong = 0x2401;
+    const PERF_EVENT_IOC_RESET: libc::c_ulong = 0x2403;
+
+    /// `struct perf_event_attr` up to `PERF_ATTR_SIZE_VER0`; the kernel
+    /// zero-fills the newer fields.
+    #[repr(C)]
+    struct PerfEventAttr {
+        kind: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+    }
+
+    struct Counter(File);
+
+    impl Counter {
+        fn open(config: u64) -> Option<Counter> {
+            let attr = PerfEventAttr {
+                kind: PERF_TYPE_HARDWARE,
+                size: std::mem::size_of::<PerfEventAttr>() as u32,
+                config,
+                sample_period: 0,
+                sample_type: 0,
+                read_format: 0,
+                flags: FLAG_DISABLED | FLAG_INHERIT | FLAG_EXCLUDE_KERNEL | FLAG_EXCLUDE_HV,
+                wakeup_events: 0,
+                bp_type: 0,
+                config1: 0,
+            };
+            // SAFETY: `attr` is a valid, initialized `perf_event_attr` prefix
+            // whose `size` matches its layout; pid 0 / cpu -1 measures this
+            // process on any CPU.
+            let fd = unsafe {
+                libc::syscall(
+                    libc::SYS_perf_event_open,
+                    &attr as *const PerfEventAttr,
+                    0 as libc::pid_t,
+                    -1 as libc::c_int,
+                    -1 as libc::c_int,
+                    0 as libc::c_ulong,
+                )
+            };
+            if fd < 0 {
+                return None;
+            }
+            // SAFETY: the syscall returned a fresh descriptor we now own.
+            Some(Counter(unsafe { File::from_raw_fd(fd as libc::c_int) }))
+        }
+
+        fn ioctl(&self, request: libc::c_ulong) {
+            use std::os::fd::AsRawFd;
+            // SAFETY: the descriptor is an open perf event and these
+            // requests take no argument.
+            unsafe {
+                libc::ioctl(self.0.as_raw_fd(), request, 0);
+            }
+        }
+
+        fn read(&mut self) -> Option<u64> {
+            let mut buf = [0u8; 8];
+            self.0.read_exact(&mut buf).ok()?;
+            Some(u64::from_ne_bytes(buf))
+        }
+    }
+
+    pub struct Session {
+        counters: [Counter; 4],
+    }
+
+    impl Session {
+        pub fn start() -> Option<Session> {
+            let mut session = Session {
+                counters: [
+                    Counter::open(PERF_COUNT_HW_INSTRUCTIONS)?,
+                    Counter::open(PERF_COUNT_HW_CPU_CYCLES)?,
+                    Counter::open(PERF_COUNT_HW_BRANCH_MISSES)?,
+                    Counter::open(PERF_COUNT_HW_CACHE_MISSES)?,
+                ],
+            };
+            for counter in &mut session.counters {
+                counter.ioctl(PERF_EVENT_IOC_RESET);
+                counter.ioctl(PERF_EVENT_IOC_ENABLE);
+            }
+            Some(session)
+        }
+
+        pub fn stop(mut self) -> Option<PerfCounts> {
+            for counter in &self.counters {
+                counter.ioctl(PERF_EVENT_IOC_DISABLE);
+            }
+            let [instructions, cycles, branch_misses, llc_misses] = &mut self.counters;
+            Some(PerfCounts {
+                instructions: instructions.read()?,
+                cycles: cycles.read()?,
+                branch_misses: branch_misses.read()?,
+                llc_misses: llc_misses.read()?,
+            })
+        }
+    }
+}
+
+#[cfg(not(all(feature = "perf", target_os = "linux", not(feature = "safe-only"))))]
+mod imp {
+    use super::PerfCounts;
+
+    pub struct Session;
+
+    impl Session {
+        pub fn start() -> Option<Session> {
+            None
+        }
+
+        pub fn stop(self) -> Option<PerfCounts> {
+            None
+        }
+    }
+}
+
+/// Counters running over one measured region; see [`Session::start`].
+pub use imp::Session;
+
+/// Whether this binary was built with counter support.
+pub fn supported() -> bool {
+    cfg!(all(
+        feature = "perf",
+        target_os = "linux",
+        not(feature = "safe-only")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ratios are derived from the raw counts and guard against zero.
+    #[test]
+    fn summary_derives_ratios() {
+        let counts = PerfCounts {
+            instructions: 3_000,
+            cycles: 1_000,
+            branch_misses: 10,
+            llc_misses: 1,
+        };
+        let summary = CounterSummary::new(counts.clone(), 100);
+        assert_eq!(summary.ipc, 3.0);
+        assert_eq!(summary.instructions_per_hash, 30.0);
+        assert_eq!(summary.branch_misses_per_hash, 0.1);
+        assert_eq!(CounterSummary::new(counts, 0).instructions_per_hash, 0.0);
+    }
+}
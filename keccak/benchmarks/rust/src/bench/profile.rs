@@ -0,0 +1,96 @@
+//! Named workload presets.
+
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::bench::BenchConfig;
+
+/// Preset message counts and length distributions modelled on real traffic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Profile {
+    /// 32-byte inputs, e.g. hashing digests or storage keys.
+    Small,
+    /// 96-128 byte inputs around a typical signed transaction.
+    EthTx,
+    /// 0.5-4 KiB contract calldata.
+    Calldata,
+    /// 1 MiB blobs.
+    Large,
+    /// An interleaving of the other profiles; see [`mixed_length`].
+    Mixed,
+}
+
+impl FromStr for Profile {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "small" => Ok(Profile::Small),
+            "eth-tx" => Ok(Profile::EthTx),
+            "calldata" => Ok(Profile::Calldata),
+            "large" => Ok(Profile::Large),
+            "mixed" => Ok(Profile::Mixed),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Profile {
+    /// Overwrite the workload fields of `config` with this preset. Warmup is
+    /// left to the adaptive calibration.
+    pub fn apply(self, config: &mut BenchConfig) {
+        let (messages, rounds, min_len, max_len, stride) = match self {
+            Profile::Small => (1024, 500, 32, 32, 1),
+            Profile::EthTx => (512, 300, 96, 128, 7),
+            Profile::Calldata => (256, 50, 512, 4096, 131),
+            Profile::Large => (4, 20, 1 << 20, 1 << 20, 1),
+            Profile::Mixed => (512, 20, 32, 128 << 10, 1),
+        };
+        config.profile = Some(self);
+        config.messages = messages;
+        config.rounds = rounds;
+        config.min_len = min_len;
+        config.max_len = max_len;
+        config.stride = stride;
+    }
+}
+
+/// Message length for the mixed profile. Every block of 16 consecutive
+/// indices holds 8 small inputs, 5 transactions, 2 calldata payloads, and
+/// one 128 KiB blob.
+pub fn mixed_length(index: usize) -> usize {
+    match index % 16 {
+        0..=7 => 32,
+        8..=12 => 96 + (index * 7) % 33,
+        13 | 14 => 512 + (index * 131) % 3585,
+        _ => 128 << 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every profile name parses back to the variant it serializes as.
+    #[test]
+    fn profile_names_round_trip() {
+        for name in ["small", "eth-tx", "calldata", "large", "mixed"] {
+            let profile: Profile = name.parse().unwrap();
+            assert_eq!(serde_json::to_value(profile).unwrap(), name);
+        }
+        assert!("huge".parse::<Profile>().is_err());
+    }
+
+    /// Mixed lengths stay within the bounds the preset advertises.
+    #[test]
+    fn mixed_lengths_stay_within_bounds() {
+        let mut config = BenchConfig::default();
+        Profile::Mixed.apply(&mut config);
+        for index in 0..config.messages {
+            let length = mixed_length(index);
+            assert!((config.min_len..=config.max_len).contains(&length));
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Re-running a recorded workload for `bench replay`.
+//!
+//! A result's `config` names everything that decides which bytes get
+//! hashed and how digests are folded, so the checksum can be recomputed
+//! without timing anything. A mismatch means hashing behaviour changed,
+//! not just speed. Each message is hashed once and its digests folded in
+//! once per recorded round, so replaying costs one round however many
+//! were timed.
+
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::bench::checksum::ChecksumMode;
+use crate::bench::lengths::LengthDist;
+use crate::bench::target::Target;
+use crate::bench::workload::Workload;
+use crate::bench::BenchConfig;
+
+/// What replaying one result record found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    pub implementation: String,
+    pub recorded: u64,
+    pub replayed: u64,
+}
+
+impl Replay {
+    pub fn matches(&self) -> bool {
+        self.recorded == self.replayed
+    }
+}
+
+/// A config field parsed with its `FromStr`, or `None` when absent or
+/// null. Numbers parse from their JSON text.
+pub(crate) fn field<T: FromStr>(config: &Value, key: &str) -> Result<Option<T>, String> {
+    let text = match config.get(key) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    };
+    text.parse()
+        .map(Some)
+        .map_err(|_| format!("config.{}: unrecognised value {:?}", key, text))
+}
+
+/// The config a recorded result ran with, as far as the workload and
+/// checksum are concerned. Calibrated runs recorded the rounds they
+/// settled on and sweep points their own size, so neither needs redoing.
+pub fn config(record: &Value) -> Result<BenchConfig, String> {
+    let recorded = record
+        .get("config")
+        .filter(|config| config.is_object())
+        .ok_or("no recorded config; only this harness's results can be replayed")?;
+    let mut config = BenchConfig::default();
+    macro_rules! set {
+        ($($key:ident),*) => {
+            $(if let Some(value) = field(recorded, stringify!($key))? {
+                config.$key = value;
+            })*
+        };
+    }
+    set!(messages, rounds, min_len, max_len, stride, backend, xof_output);
+    // The mixed profile draws its own message lengths.
+    config.profile = field(recorded, "profile")?;
+    // Configs without a mode predate `--checksum`, when only XOR existed.
+    config.checksum_mode = field(recorded, "checksum_mode")?.unwrap_or(ChecksumMode::Xor);
+    config.seed = field(recorded, "seed")?;
+    config.corpus = field(recorded, "corpus")?;
+    config.mmap = field(recorded, "mmap")?;
+    config.chunk_size = field(recorded, "chunk_size")?;
+    config.batch = field(recorded, "batch")?;
+    if let Some(path) = field::<String>(recorded, "length_dist")? {
+        config.length_dist = Some(LengthDist::load(&path)?);
+    }
+    // Results from before targets were pluggable name an XOF instead.
+    config.target = match field(recorded, "target")? {
+        Some(target) => target,
+        None => field(recorded, "xof")?.map_or_else(Target::default, Target::xof),
+    };
+    config.validate()?;
+    Ok(config)
+}
+
+/// The checksum `config` produces over `workload`, computed untimed.
+pub fn checksum(config: &BenchConfig, workload: &Workload) -> u64 {
+    let count = workload.count(config);
+    let batch = config.batch.unwrap_or(1);
+    let mut digests = vec![[0u8; 32]; batch];
+    let mut round = 0;
+    for start in (0..count).step_by(batch) {
+        let end = (start + batch).min(count);
+        let messages: Vec<_> = (start..end)
+            .map(|index| workload.message(config, index))
+            .collect();
+        let messages: Vec<&[u8]> = messages.iter().map(|m| m.as_ref()).collect();
+        let digests = &mut digests[..messages.len()];
+        config.target.run_batch(config, &messages, digests);
+        for digest in digests.iter() {
+            round = config.target.checksum(config, round, digest);
+        }
+    }
+    (0..config.rounds).fold(0, |acc, _| config.checksum_mode.combine(acc, round))
+}
+
+/// Rebuild the workload of `record` and recompute its checksum.
+pub fn replay(record: &Value) -> Result<Replay, String> {
+    let implementation = record
+        .get("implementation")
+        .and_then(Value::as_str)
+        .unwrap_or("?")
+        .to_string();
+    let recorded = record
+        .get("checksum")
+        .and_then(Value::as_u64)
+        .ok_or("no recorded checksum")?;
+    let mut config = config(record)?;
+    let workload = Workload::load(&mut config)?;
+    Ok(Replay {
+        implementation,
+        recorded,
+        replayed: checksum(&config, &workload),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::backend::Backend;
+    use crate::bench::run_trial;
+    use serde_json::json;
+
+    /// A recorded config replays to the checksum its trial produced, batched
+    /// or not, and a tampered checksum is caught.
+    #[test]
+    fn replays_recorded_checksums() {
+        for batch in [None, Some(4)] {
+            let config = BenchConfig {
+                backend: Backend::Native,
+                checksum_mode: ChecksumMode::Fnv64,
+                seed: Some(9),
+                messages: 10,
+                rounds: 3,
+                batch,
+                ..BenchConfig::default()
+            };
+            let expected = run_trial(&config, &Workload::Synthetic, 1).checksum;
+            let mut record = json!({
+                "implementation": "rust (native)",
+                "checksum": expected,
+                "config": config,
+            });
+            let replay = replay(&record).unwrap();
+            assert!(replay.matches(), "{:?}", replay);
+            record["checksum"] = json!(expected ^ 1);
+            assert!(!super::replay(&record).unwrap().matches());
+        }
+        let foreign = json!({"implementation": "mojo", "checksum": 3});
+        assert!(replay(&foreign).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Markdown rendering for `--format markdown` and `bench report`.
+
+use std::fmt::Write as _;
+
+use serde_json::Value;
+
+use crate::bench::compare::{self, Record};
+
+/// Config keys shown in the workload line, in display order.
+const WORKLOAD_KEYS: &[&str] = &[
+    "profile",
+    "corpus",
+    "length_dist",
+    "seed",
+    "messages",
+    "rounds",
+    "min_len",
+    "max_len",
+    "stride",
+    "warmup",
+    "trials",
+    "threads",
+];
+
+fn escape(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+fn config_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("-"),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn environment(record: &Record) -> String {
+    let Some(env) = &record.environment else {
+        return String::from("-");
+    };
+    let field = |key: &str| {
+        env.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or("?")
+            .to_string()
+    };
+    let platform = format!("{}/{}", field("os"), field("arch"));
+    match env.get("cpu_model").and_then(Value::as_str) {
+        Some(cpu) => format!("{}, {}", cpu, platform),
+        None => platform,
+    }
+}
+
+/// Render `records` as a ready-to-paste markdown section. Relative speed is
+/// measured against `baseline`, or the first record when it is `None`.
+pub fn render(records: &[Record], baseline: Option<&str>) -> Result<String, String> {
+    let base = match baseline {
+        Some(name) => records
+            .iter()
+            .find(|record| record.implementation == name)
+            .ok_or_else(|| format!("baseline {:?} not found among the results", name))?,
+        None => records.first().ok_or("no results to report")?,
+    };
+
+    let mut out = String::new();
+    writeln!(out, "### Keccak-256 benchmark").unwrap();
+    writeln!(out).unwrap();
+    if let Some(config) = records.iter().find_map(|record| record.config.as_ref()) {
+        let fields: Vec<String> = WORKLOAD_KEYS
+            .iter()
+            .filter_map(|&key| {
+                config
+                    .get(key)
+                    .map(|v| format!("{}={}", key, config_value(v)))
+            })
+            .collect();
+        writeln!(out, "**Workload:** {}", fields.join(", ")).unwrap();
+        writeln!(out).unwrap();
+    }
+    for problem in compare::validate(records) {
+        writeln!(out, "> **Warning:** {}", escape(&problem)).unwrap();
+        writeln!(out).unwrap();
+    }
+
+    writeln!(
+        out,
+        "| implementation | hashes/s | MB/s | relative | seconds | checksum | environment |"
+    )
+    .unwrap();
+    writeln!(out, "| --- | ---: | ---: | ---: | ---: | ---: | --- |").unwrap();
+    for record in records {
+        let relative = if base.hashes_per_second > 0.0 {
+            record.hashes_per_second / base.hashes_per_second
+        } else {
+            0.0
+        };
+        writeln!(
+            out,
+            "| {} | {:.2} | {} | {:.2}x | {:.6} | {} | {} |",
+            escape(&record.implementation),
+            record.hashes_per_second,
+            record
+                .mb_per_second
+                .map(|value| format!("{:.2}", value))
+                .unwrap_or_else(|| String::from("-")),
+            relative,
+            record.seconds,
+            record
+                .checksum
+                .map(|value| value.to_string())
+                .unwrap_or_else(|| String::from("-")),
+            escape(&environment(record))
+        )
+        .unwrap();
+    }
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "_Relative speed is against {}._",
+        escape(&base.implementation)
+    )
+    .unwrap();
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows carry relative speed and escaped labels.
+    #[test]
+    fn render_includes_relative_speed() {
+        let records: Vec<Record> = serde_json::from_str(
+            r#"[
+                {"implementation": "mojo | jit", "seconds": 2.0, "hashes_per_second": 50.0, "checksum": 67},
+                {"implementation": "rust", "seconds": 1.0, "hashes_per_second": 100.0, "checksum": 67,
+                 "mb_per_second": 3.5, "config": {"messages": 512, "seed": null},
+                 "environment": {"os": "linux", "arch": "x86_64"}}
+            ]"#,
+        )
+        .unwrap();
+        let markdown = render(&records, Some("mojo | jit")).unwrap();
+        assert!(markdown.contains("**Workload:** seed=-, messages=512"));
+        assert!(markdown.contains("| mojo \\| jit | 50.00 | - | 1.00x |"));
+        assert!(markdown.contains("| rust | 100.00 | 3.50 | 2.00x |"));
+        assert!(markdown.contains("linux/x86_64"));
+        assert!(render(&records, Some("c")).is_err());
+    }
+}
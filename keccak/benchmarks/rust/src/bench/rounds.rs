@@ -0,0 +1,123 @@
+//! Per-round outlier and thermal-throttling detection.
+//!
+//! Every timed round is recorded, so a run can be judged on more than its
+//! trial totals: single rounds far from the median point at interference,
+//! and a steady climb in round times points at the CPU slowing down.
+
+use serde::Serialize;
+
+use crate::bench::stats::Summary;
+
+/// Fewest rounds the trend check looks at; shorter runs cannot show one.
+const TREND_MIN_ROUNDS: usize = 8;
+
+/// Windows the rounds are split into for the trend check.
+const TREND_WINDOWS: usize = 4;
+
+/// What the round times say about a run's cleanliness.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RoundReport {
+    /// Rounds analysed, across all trials in run order.
+    pub rounds: usize,
+    pub median_seconds: f64,
+    /// Median absolute deviation from `median_seconds`.
+    pub mad_seconds: f64,
+    /// Indices into the run-ordered rounds further than `k` MADs from the
+    /// median.
+    pub outliers: Vec<usize>,
+    /// Median round time of the last quarter of the run relative to the
+    /// first, minus one; positive when the run slowed down.
+    pub slowdown: f64,
+    /// Set when every quarter was slower than the one before and the
+    /// slowdown exceeds the noise threshold.
+    pub throttling: bool,
+}
+
+impl RoundReport {
+    /// Analyse `seconds`, flagging rounds beyond `k` MADs and a monotonic
+    /// slowdown of more than `threshold`. Returns `None` without rounds.
+    pub fn analyze(seconds: &[f64], k: f64, threshold: f64) -> Option<RoundReport> {
+        let median = Summary::from_samples(seconds)?.median;
+        let deviations: Vec<f64> = seconds.iter().map(|s| (s - median).abs()).collect();
+        let mad = Summary::from_samples(&deviations)?.median;
+        // A MAD of zero (identical rounds, or a coarse clock) would flag
+        // every round that differs at all.
+        let outliers = if mad > 0.0 {
+            deviations
+                .iter()
+                .enumerate()
+                .filter(|&(_, &deviation)| deviation > k * mad)
+                .map(|(index, _)| index)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let (slowdown, monotonic) = if seconds.len() >= TREND_MIN_ROUNDS {
+            let size = seconds.len() / TREND_WINDOWS;
+            let medians: Vec<f64> = (0..TREND_WINDOWS)
+                .map(|window| {
+                    let end = if window == TREND_WINDOWS - 1 {
+                        seconds.len()
+                    } else {
+                        (window + 1) * size
+                    };
+                    Summary::from_samples(&seconds[window * size..end])
+                        .map_or(0.0, |summary| summary.median)
+                })
+                .collect();
+            let slowdown = if medians[0] > 0.0 {
+                medians[TREND_WINDOWS - 1] / medians[0] - 1.0
+            } else {
+                0.0
+            };
+            (slowdown, medians.windows(2).all(|pair| pair[1] > pair[0]))
+        } else {
+            (0.0, false)
+        };
+
+        Some(RoundReport {
+            rounds: seconds.len(),
+            median_seconds: median,
+            mad_seconds: mad,
+            outliers,
+            slowdown,
+            throttling: monotonic && slowdown > threshold,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A lone slow round is an outlier; steady rounds are not.
+    #[test]
+    fn flags_rounds_beyond_k_mads() {
+        let mut seconds: Vec<f64> = (0..20).map(|i| 1.0 + (i % 3) as f64 * 0.01).collect();
+        seconds[7] = 1.5;
+        let report = RoundReport::analyze(&seconds, 5.0, 0.05).unwrap();
+        assert_eq!(report.outliers, [7]);
+        assert!(!report.throttling);
+        assert!(RoundReport::analyze(&[1.0; 10], 5.0, 0.05)
+            .unwrap()
+            .outliers
+            .is_empty());
+        assert!(RoundReport::analyze(&[], 5.0, 0.05).is_none());
+    }
+
+    /// A steady climb is throttling; the same slowdown with the middle
+    /// quarters swapped is not monotonic, so it is not.
+    #[test]
+    fn detects_monotonic_slowdown() {
+        let climbing: Vec<f64> = (0..40).map(|i| 1.0 + i as f64 * 0.01).collect();
+        let report = RoundReport::analyze(&climbing, 5.0, 0.05).unwrap();
+        assert!(report.throttling, "{:?}", report);
+        assert!(report.slowdown > 0.25);
+        let mut swapped = climbing.clone();
+        swapped[10..30].rotate_left(10);
+        let report = RoundReport::analyze(&swapped, 5.0, 0.05).unwrap();
+        assert!(report.slowdown > 0.25);
+        assert!(!report.throttling, "{:?}", report);
+    }
+}
@@ -0,0 +1,372 @@
+//! Hashing behind an async hop for `--async` (`async` feature).
+//!
+//! Each shard of a trial runs as a task on a multi-threaded tokio runtime
+//! with one worker per `--threads`, the way a request handler would, and
+//! hands every message to blocking code: through `spawn_blocking`, or over
+//! a channel to a dedicated worker thread per shard that replies on a
+//! oneshot. Timed hashes therefore include the hop there and back. The
+//! runtime and workers are started, and the messages generated, before the
+//! clock starts; the handler owns its messages, so none are copied.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+/// How a handler task reaches the blocking hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AsyncMode {
+    /// `tokio::task::spawn_blocking` per message.
+    SpawnBlocking,
+    /// A dedicated thread per shard, fed over an mpsc channel.
+    Worker,
+}
+
+impl FromStr for AsyncMode {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "spawn-blocking" => Ok(AsyncMode::SpawnBlocking),
+            "worker" => Ok(AsyncMode::Worker),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for AsyncMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AsyncMode::SpawnBlocking => "spawn-blocking",
+            AsyncMode::Worker => "worker",
+        })
+    }
+}
+
+/// Async throughput next to a synchronous trial of the same workload.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RuntimeResult {
+    pub mode: AsyncMode,
+    /// Runtime worker threads, one per shard.
+    pub workers: usize,
+    pub hashes_per_second: f64,
+    pub sync_hashes_per_second: f64,
+    /// Extra time each worker spent per hash compared with the
+    /// synchronous trial.
+    pub overhead_ns_per_hash: f64,
+}
+
+impl RuntimeResult {
+    pub fn new(
+        mode: AsyncMode,
+        workers: usize,
+        hashes: u64,
+        seconds: f64,
+        sync_seconds: f64,
+    ) -> RuntimeResult {
+        let rate = |seconds: f64| {
+            if seconds > 0.0 {
+                hashes as f64 / seconds
+            } else {
+                0.0
+            }
+        };
+        RuntimeResult {
+            mode,
+            workers,
+            hashes_per_second: rate(seconds),
+            sync_hashes_per_second: rate(sync_seconds),
+            overhead_ns_per_hash: if hashes > 0 {
+                (seconds - sync_seconds) * workers as f64 / hashes as f64 * 1e9
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+mod imp {
+    use std::sync::Arc;
+    use std::thread::{self, JoinHandle};
+
+    use tokio::runtime::{Builder, Runtime};
+    use tokio::sync::{mpsc, oneshot};
+
+    use super::AsyncMode;
+    use crate::bench::workload::Workload;
+    use crate::bench::{BenchConfig, ShardMeasurement};
+
+    type Job = (usize, oneshot::Sender<[u8; 32]>);
+
+    /// A runtime, its messages, and any dedicated workers, ready to time.
+    pub struct Trial {
+        runtime: Runtime,
+        config: Arc<BenchConfig>,
+        messages: Arc<Vec<Vec<u8>>>,
+        /// One per shard; `None` hops through `spawn_blocking`.
+        senders: Vec<Option<mpsc::UnboundedSender<Job>>>,
+        workers: Vec<JoinHandle<()>>,
+        per_file: bool,
+    }
+
+    impl Trial {
+        pub fn prepare(
+            config: &BenchConfig,
+            workload: &Workload,
+            shards: usize,
+            mode: AsyncMode,
+        ) -> Trial {
+            let runtime = Builder::new_multi_thread()
+                .worker_threads(shards)
+                .build()
+                .expect("failed to start the tokio runtime");
+            let config = Arc::new(config.clone());
+            let messages: Arc<Vec<Vec<u8>>> = Arc::new(
+                (0..workload.count(&config))
+                    .map(|index| workload.message(&config, index).into_owned())
+                    .collect(),
+            );
+            let (senders, workers) = match mode {
+                AsyncMode::SpawnBlocking => (vec![None; shards], Vec::new()),
+                AsyncMode::Worker => (0..shards)
+                    .map(|_| {
+                        let (sender, worker) = spawn_worker(&config, &messages);
+                        (Some(sender), worker)
+                    })
+                    .unzip(),
+            };
+            Trial {
+                runtime,
+                config,
+                messages,
+                senders,
+                workers,
+                per_file: matches!(workload, Workload::Corpus(_)),
+            }
+        }
+
+        /// Run every shard as a runtime task and wait for all of them.
+        pub fn run(self) -> Vec<ShardMeasurement> {
+            let shards = self.senders.len();
+            let measurements = self.runtime.block_on(async {
+                let tasks: Vec<_> = self
+                    .senders
+                    .into_iter()
+                    .enumerate()
+                    .map(|(shard, sender)| {
+                        tokio::spawn(run_shard(
+                            self.config.clone(),
+                            self.messages.clone(),
+                            sender,
+                            shard,
+                            shards,
+                            self.per_file,
+                        ))
+                    })
+                    .collect();
+                let mut measurements = Vec::with_capacity(tasks.len());
+                for task in tasks {
+                    measurements.push(task.await.expect("async benchmark task panicked"));
+                }
+                measurements
+            });
+            // The senders went with the tasks, so the workers have exited.
+            for worker in self.workers {
+                worker.join().expect("async benchmark worker panicked");
+            }
+            measurements
+        }
+    }
+
+    fn spawn_worker(
+        config: &Arc<BenchConfig>,
+        messages: &Arc<Vec<Vec<u8>>>,
+    ) -> (mpsc::UnboundedSender<Job>, JoinHandle<()>) {
+        let (sender, mut jobs) = mpsc::unbounded_channel::<Job>();
+        let config = config.clone();
+        let messages = messages.clone();
+        let worker = thread::spawn(move || {
+            while let Some((index, reply)) = jobs.blocking_recv() {
+                let _ = reply.send(config.hash(&messages[index]));
+            }
+        });
+        (sender, worker)
+    }
+
+    /// Hash message `index` on the far side of the hop.
+    async fn hop(
+        config: &Arc<BenchConfig>,
+        messages: &Arc<Vec<Vec<u8>>>,
+        worker: Option<&mpsc::UnboundedSender<Job>>,
+        index: usize,
+    ) -> [u8; 32] {
+        match worker {
+            Some(worker) => {
+                let (reply, digest) = oneshot::channel();
+                worker
+                    .send((index, reply))
+                    .expect("async benchmark worker exited");
+                digest.await.expect("async benchmark worker exited")
+            }
+            None => {
+                let config = config.clone();
+                let messages = messages.clone();
+                tokio::task::spawn_blocking(move || config.hash(&messages[index]))
+                    .await
+                    .expect("blocking hash panicked")
+            }
+        }
+    }
+
+    /// The async counterpart of the synchronous shard loop.
+    async fn run_shard(
+        config: Arc<BenchConfig>,
+        messages: Arc<Vec<Vec<u8>>>,
+        worker: Option<mpsc::UnboundedSender<Job>>,
+        shard: usize,
+        shards: usize,
+        per_file: bool,
+    ) -> ShardMeasurement {
+        let count = messages.len();
+        let mut file_seconds = vec![0.0; if per_file { count } else { 0 }];
+        let mut checksum = 0;
+        let mut latencies_ns = Vec::new();
+        let mut hash_index: usize = 0;
+        let overhead = if config.latency_sample > 0 || per_file {
+            config.timer.overhead_ns() / 1e9
+        } else {
+            0.0
+        };
+        let mut round_ends = Vec::with_capacity(config.rounds);
+        let start = config.timer.now();
+
+        for round in 0..config.rounds {
+            if round > 0 {
+                round_ends.push(start.elapsed_secs());
+            }
+            for index in (shard..count).step_by(shards) {
+                let sampled =
+                    config.latency_sample > 0 && hash_index.is_multiple_of(config.latency_sample);
+                let digest = if sampled || per_file {
+                    let hash_start = config.timer.now();
+                    let digest = hop(&config, &messages, worker.as_ref(), index).await;
+                    let elapsed = (hash_start.elapsed_secs() - overhead).max(0.0);
+                    if sampled {
+                        latencies_ns.push(elapsed * 1e9);
+                    }
+                    if let Some(total) = file_seconds.get_mut(index) {
+                        *total += elapsed;
+                    }
+                    digest
+                } else {
+                    hop(&config, &messages, worker.as_ref(), index).await
+                };
+                checksum = config.target.checksum(&config, checksum, &digest);
+                hash_index += 1;
+            }
+        }
+
+        let seconds = start.elapsed_secs();
+        if config.rounds > 0 {
+            round_ends.push(seconds);
+        }
+        let mut previous = 0.0;
+        let round_seconds = round_ends
+            .iter()
+            .map(|&end| {
+                let round = end - previous;
+                previous = end;
+                round
+            })
+            .collect();
+
+        ShardMeasurement {
+            messages: (shard..count).step_by(shards).count(),
+            seconds,
+            checksum,
+            latencies_ns,
+            file_seconds,
+            round_seconds,
+        }
+    }
+}
+
+#[cfg(not(feature = "async"))]
+mod imp {
+    use super::AsyncMode;
+    use crate::bench::workload::Workload;
+    use crate::bench::{BenchConfig, ShardMeasurement};
+
+    pub struct Trial;
+
+    impl Trial {
+        pub fn prepare(
+            _config: &BenchConfig,
+            _workload: &Workload,
+            _shards: usize,
+            _mode: AsyncMode,
+        ) -> Trial {
+            unreachable!("validation rejects --async without the async feature")
+        }
+
+        pub fn run(self) -> Vec<ShardMeasurement> {
+            unreachable!("validation rejects --async without the async feature")
+        }
+    }
+}
+
+/// One trial behind an async hop; see [`Trial::prepare`].
+pub use imp::Trial;
+
+/// Whether this binary was built with async support.
+pub fn supported() -> bool {
+    cfg!(feature = "async")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Overhead is the extra wall time per hash on each worker.
+    #[test]
+    fn overhead_scales_with_workers() {
+        let result = RuntimeResult::new(AsyncMode::Worker, 2, 1_000, 0.003, 0.001);
+        assert_eq!(result.sync_hashes_per_second, 1_000_000.0);
+        assert!((result.overhead_ns_per_hash - 4_000.0).abs() < 1e-6);
+        assert_eq!("spawn-blocking".parse(), Ok(AsyncMode::SpawnBlocking));
+        assert!("inline".parse::<AsyncMode>().is_err());
+    }
+
+    /// Both hops hash every message once per round, sharded or not.
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_trials_match_synchronous_checksum() {
+        use crate::bench::backend::Backend;
+        use crate::bench::workload::Workload;
+        use crate::bench::{run_trial, BenchConfig};
+
+        let config = BenchConfig {
+            backend: Backend::Native,
+            messages: 12,
+            rounds: 3,
+            latency_sample: 5,
+            ..BenchConfig::default()
+        };
+        let expected = run_trial(&config, &Workload::Synthetic, 1).checksum;
+        for mode in [AsyncMode::SpawnBlocking, AsyncMode::Worker] {
+            for threads in [1, 3] {
+                let config = BenchConfig {
+                    async_mode: Some(mode),
+                    threads,
+                    ..config.clone()
+                };
+                let trial = run_trial(&config, &Workload::Synthetic, threads);
+                assert_eq!(trial.checksum, expected, "{} x{}", mode, threads);
+                assert_eq!(trial.round_seconds.len(), 3);
+                assert!(!trial.latencies_ns.is_empty());
+            }
+        }
+    }
+}
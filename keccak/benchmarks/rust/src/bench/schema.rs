@@ -0,0 +1,155 @@
+//! The result schema: which fields a record must carry, by version.
+//!
+//! Records from this harness carry `schema_version`. Records without it come
+//! from the Mojo, C, and Python harnesses and only promise the fields
+//! `bench compare` needs.
+
+use serde_json::Value;
+
+/// Version written by this build. Bump it when a field is renamed, removed,
+/// or changes meaning; adding an optional field does not need a bump.
+pub const SCHEMA_VERSION: u64 = 1;
+
+#[derive(Clone, Copy)]
+enum Kind {
+    String,
+    Number,
+    Bool,
+    Object,
+    Array,
+}
+
+impl Kind {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Kind::String => value.is_string(),
+            Kind::Number => value.is_number(),
+            Kind::Bool => value.is_boolean(),
+            Kind::Object => value.is_object(),
+            Kind::Array => value.is_array(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::String => "a string",
+            Kind::Number => "a number",
+            Kind::Bool => "a boolean",
+            Kind::Object => "an object",
+            Kind::Array => "an array",
+        }
+    }
+}
+
+/// Fields every record needs, versioned or not.
+const LEGACY_FIELDS: &[(&str, Kind)] = &[
+    ("implementation", Kind::String),
+    ("seconds", Kind::Number),
+    ("hashes_per_second", Kind::Number),
+];
+
+/// Fields a version 1 record needs on top of [`LEGACY_FIELDS`].
+const V1_FIELDS: &[(&str, Kind)] = &[
+    ("operation", Kind::String),
+    ("mb_per_second", Kind::Number),
+    ("bytes", Kind::Number),
+    ("checksum", Kind::Number),
+    ("config", Kind::Object),
+    ("warmup", Kind::Object),
+    ("trial_seconds", Kind::Array),
+    ("stats", Kind::Object),
+    ("noisy", Kind::Bool),
+    ("environment", Kind::Object),
+];
+
+/// Fail if `record` was written by a newer schema than this build knows.
+pub fn check_version(record: &Value) -> Result<(), String> {
+    match record.get("schema_version") {
+        None => Ok(()),
+        Some(Value::Number(number)) => match number.as_u64() {
+            Some(version) if (1..=SCHEMA_VERSION).contains(&version) => Ok(()),
+            Some(version) if version > SCHEMA_VERSION => Err(format!(
+                "schema_version {} is newer than this build understands ({}); update the tooling",
+                version, SCHEMA_VERSION
+            )),
+            _ => Err(format!("invalid schema_version {}", number)),
+        },
+        Some(other) => Err(format!("invalid schema_version {}", other)),
+    }
+}
+
+/// Everything wrong with `record`; empty when it is valid.
+pub fn check(record: &Value) -> Vec<String> {
+    if !record.is_object() {
+        return vec![String::from("not a JSON object")];
+    }
+    if let Err(err) = check_version(record) {
+        return vec![err];
+    }
+    let mut fields: Vec<(&str, Kind)> = LEGACY_FIELDS.to_vec();
+    if record.get("schema_version").is_some() {
+        fields.extend_from_slice(V1_FIELDS);
+    }
+    let mut problems = Vec::new();
+    for (key, kind) in fields {
+        match record.get(key) {
+            None => problems.push(format!("missing field {:?}", key)),
+            Some(value) if !kind.matches(value) => {
+                problems.push(format!("field {:?} must be {}", key, kind.name()))
+            }
+            Some(_) => {}
+        }
+    }
+    // Other harnesses may omit the checksum or report null, but not a
+    // string or a negative number.
+    if let Some(checksum) = record.get("checksum") {
+        if !checksum.is_null() && checksum.as_u64().is_none() {
+            problems.push(String::from(
+                "field \"checksum\" must be a non-negative integer",
+            ));
+        }
+    }
+    if let Some(tags) = record.get("tags") {
+        let strings = tags
+            .as_object()
+            .is_some_and(|tags| tags.values().all(Value::is_string));
+        if !strings {
+            problems.push(String::from("field \"tags\" must be an object of strings"));
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unversioned records only need the comparison fields.
+    #[test]
+    fn legacy_records_need_core_fields() {
+        let mojo: Value = serde_json::from_str(
+            r#"{"implementation": "mojo", "seconds": 1.5, "hashes_per_second": 9.0, "checksum": 67}"#,
+        )
+        .unwrap();
+        assert!(check(&mojo).is_empty());
+        let broken: Value =
+            serde_json::from_str(r#"{"implementation": 3, "seconds": 1.5, "checksum": "x"}"#)
+                .unwrap();
+        let problems = check(&broken);
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+    }
+
+    /// Versioned records need the full field set, and newer versions are
+    /// rejected outright.
+    #[test]
+    fn versioned_records_are_checked() {
+        let partial: Value = serde_json::from_str(
+            r#"{"schema_version": 1, "implementation": "rust", "seconds": 1.0, "hashes_per_second": 2.0}"#,
+        )
+        .unwrap();
+        assert_eq!(check(&partial).len(), V1_FIELDS.len());
+        let future: Value = serde_json::from_str(r#"{"schema_version": 99}"#).unwrap();
+        assert!(check_version(&future).unwrap_err().contains("newer"));
+        assert_eq!(check(&future).len(), 1);
+    }
+}
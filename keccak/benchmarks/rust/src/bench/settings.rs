@@ -0,0 +1,388 @@
+//! Run settings files for `--config`.
+//!
+//! A settings file is a small TOML document whose tables group the command
+//! line flags:
+//!
+//! ```toml
+//! [workload]
+//! messages = 512
+//! sweep = "32..4096"
+//!
+//! [backends]
+//! backend = ["tiny-keccak", "native"]
+//!
+//! [output]
+//! format = "csv"
+//!
+//! [environment]
+//! pin-core = 2
+//!
+//! [tags]
+//! machine = "m1-max"
+//! ```
+//!
+//! Each key is a flag name without the leading dashes (`_` and `-` are
+//! interchangeable). Strings and numbers become the flag's value, `true`
+//! passes a switch and `false` leaves it out, and arrays are joined with
+//! commas. Each `[tags]` entry becomes a `--tag key=value`. [`to_args`] turns the file into flags to put ahead of the real
+//! command line, so flags given there win.
+//!
+//! Only the TOML the settings need is understood: tables, bare or quoted
+//! keys, strings, integers, floats, booleans, and (possibly multi-line)
+//! arrays of those.
+
+use serde_json::Value;
+
+/// Flags each table accepts.
+const TABLES: &[(&str, &[&str])] = &[
+    (
+        "workload",
+        &[
+            "profile",
+            "messages",
+            "rounds",
+            "min-len",
+            "max-len",
+            "stride",
+            "seed",
+            "corpus",
+            "mmap",
+            "length-dist",
+            "sweep",
+            "target",
+            "xof",
+            "xof-output",
+            "checksum",
+            "exclude-generation",
+            "include-generation",
+            "reuse-buffer",
+            "batch",
+            "chunk-size",
+            "threads",
+            "async",
+            "warmup",
+            "trials",
+            "duration",
+            "soak",
+            "soak-interval",
+            "warmup-tolerance",
+            "warmup-timeout",
+            "noise-threshold",
+            "outlier-mad",
+            "latency-sample",
+            "by-length",
+        ],
+    ),
+    ("backends", &["backend", "simd-compare"]),
+    (
+        "output",
+        &[
+            "label",
+            "json",
+            "format",
+            "stream",
+            "output",
+            "append",
+            "quiet",
+            "verbose",
+            "histogram",
+            "chart",
+            "chart-out",
+            "verify-out",
+            "verify-in",
+            "min-throughput",
+            "baseline-dir",
+            "save-baseline",
+            "check-against",
+            "regression-threshold",
+        ],
+    ),
+    ("environment", &["pin-core", "realtime", "timer", "isolate"]),
+];
+
+/// Parse `text` into `(table, key, value)` entries in file order.
+pub fn parse(text: &str) -> Result<Vec<(String, String, Value)>, String> {
+    let mut entries = Vec::new();
+    let mut table = String::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let at = |err: String| format!("line {}: {}", index + 1, err);
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .filter(|name| !name.starts_with('['))
+                .ok_or_else(|| at(String::from("only plain [table] headers are supported")))?;
+            table = unquote_key(name.trim()).map_err(at)?;
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| at(String::from("expected key = value")))?;
+        let key = unquote_key(key.trim()).map_err(at)?;
+        let mut value = value.trim().to_string();
+        // An array may continue over the following lines until its
+        // brackets balance.
+        while value.starts_with('[') && !balanced(&value) {
+            let (_, next) = lines
+                .next()
+                .ok_or_else(|| at(String::from("unterminated array")))?;
+            value.push(' ');
+            value.push_str(strip_comment(next).trim());
+        }
+        let (parsed, rest) = parse_value(&value).map_err(at)?;
+        if !rest.trim().is_empty() {
+            return Err(at(format!("unexpected {:?} after value", rest.trim())));
+        }
+        entries.push((table.clone(), key, parsed));
+    }
+    Ok(entries)
+}
+
+/// The command line flags `text` stands for, in file order.
+pub fn to_args(text: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for (table, key, value) in parse(text)? {
+        if table == "tags" {
+            let value = match value {
+                Value::String(text) => text,
+                Value::Number(_) | Value::Bool(_) => value.to_string(),
+                _ => return Err(format!("[tags] {}: tags must be strings", key)),
+            };
+            args.push(String::from("--tag"));
+            args.push(format!("{}={}", key, value));
+            continue;
+        }
+        let flag = key.replace('_', "-");
+        let Some(&(_, flags)) = TABLES.iter().find(|&&(name, _)| name == table) else {
+            return Err(if table.is_empty() {
+                format!("{}: put settings under a table, e.g. [workload]", key)
+            } else {
+                format!(
+                    "[{}]: unknown table; expected workload, backends, output, environment, or tags",
+                    table
+                )
+            });
+        };
+        if !flags.contains(&flag.as_str()) {
+            return Err(format!("[{}] {}: unknown setting", table, key));
+        }
+        match value {
+            Value::Bool(true) => args.push(format!("--{}", flag)),
+            Value::Bool(false) => {}
+            value => {
+                args.push(format!("--{}", flag));
+                args.push(
+                    flag_value(&value).map_err(|err| format!("[{}] {}: {}", table, key, err))?,
+                );
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn flag_value(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Array(items) => {
+            let items = items
+                .iter()
+                .map(|item| match item {
+                    Value::Array(_) => Err(String::from("nested arrays are not supported")),
+                    item => flag_value(item),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(items.join(","))
+        }
+        _ => Err(String::from("booleans are only allowed for switches")),
+    }
+}
+
+/// `line` without a trailing `#` comment, ignoring `#` inside strings.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &line[..index],
+            None => {}
+        }
+    }
+    line
+}
+
+/// Whether every `[` in `value` outside strings has been closed.
+fn balanced(value: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in value.chars() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '[' => depth += 1,
+            None if c == ']' => depth -= 1,
+            None => {}
+        }
+    }
+    depth <= 0
+}
+
+fn unquote_key(key: &str) -> Result<String, String> {
+    if key.starts_with('"') || key.starts_with('\'') {
+        match parse_value(key)? {
+            (Value::String(text), rest) if rest.trim().is_empty() => Ok(text),
+            _ => Err(format!("invalid key {:?}", key)),
+        }
+    } else if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        Ok(key.to_string())
+    } else {
+        Err(format!("invalid key {:?}", key))
+    }
+}
+
+/// Parse one value from the start of `text`, returning it and the rest.
+fn parse_value(text: &str) -> Result<(Value, &str), String> {
+    let text = text.trim_start();
+    if let Some(rest) = text.strip_prefix('[') {
+        let mut items = Vec::new();
+        let mut rest = rest.trim_start();
+        loop {
+            if let Some(after) = rest.strip_prefix(']') {
+                return Ok((Value::Array(items), after));
+            }
+            let (item, after) = parse_value(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after.trim_start();
+            } else if !rest.starts_with(']') {
+                return Err(String::from("expected , or ] in array"));
+            }
+        }
+    }
+    if let Some(rest) = text.strip_prefix('\'') {
+        let end = rest
+            .find('\'')
+            .ok_or_else(|| String::from("unterminated string"))?;
+        return Ok((Value::String(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    if let Some(body) = text.strip_prefix('"') {
+        // TOML basic strings use JSON's escapes, plus \U for long code
+        // points, which settings never need.
+        let mut escaped = false;
+        let end = body
+            .char_indices()
+            .find(|&(_, c)| {
+                let closes = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                closes
+            })
+            .map(|(index, _)| index + 2)
+            .ok_or_else(|| String::from("unterminated string"))?;
+        let parsed = serde_json::from_str(&text[..end])
+            .map_err(|err| format!("invalid string {}: {}", &text[..end], err))?;
+        return Ok((parsed, &text[end..]));
+    }
+    let end = text
+        .find(|c: char| c == ',' || c == ']' || c.is_whitespace())
+        .unwrap_or(text.len());
+    let (token, rest) = text.split_at(end);
+    let value = match token {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => {
+            let digits = token.replace('_', "");
+            if let Ok(integer) = digits.parse::<i64>() {
+                Value::from(integer)
+            } else if let Some(float) = digits.parse::<f64>().ok().filter(|f| f.is_finite()) {
+                Value::from(float)
+            } else {
+                return Err(format!("invalid value {:?}", token));
+            }
+        }
+    };
+    Ok((value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tables, comments, quoting, and multi-line arrays all turn into the
+    /// flags they stand for.
+    #[test]
+    fn settings_become_flags() {
+        let text = r#"
+            # comparison matrix
+            [workload]
+            messages = 1_024     # per round
+            noise_threshold = 0.1
+            sweep = "32..4096:x2"
+            corpus = 'data/#1'
+            reuse-buffer = false
+
+            [backends]
+            backend = [
+                "tiny-keccak",  # reference
+                "native",
+            ]
+
+            [output]
+            json = true
+
+            [tags]
+            branch = "simd-experiment"
+        "#;
+        assert_eq!(
+            to_args(text).unwrap(),
+            [
+                "--messages",
+                "1024",
+                "--noise-threshold",
+                "0.1",
+                "--sweep",
+                "32..4096:x2",
+                "--corpus",
+                "data/#1",
+                "--backend",
+                "tiny-keccak,native",
+                "--json",
+                "--tag",
+                "branch=simd-experiment",
+            ]
+        );
+    }
+
+    /// Unknown tables and keys, stray keys, and malformed values are errors.
+    #[test]
+    fn rejects_unknown_settings() {
+        for (text, expected) in [
+            ("messages = 3", "put settings under a table"),
+            ("[workloads]\nmessages = 3", "unknown table"),
+            ("[workload]\nmesages = 3", "unknown setting"),
+            ("[backends]\nbackend = [\"native\"", "unterminated array"),
+            ("[workload]\nrounds = ten", "line 2: invalid value"),
+            ("[workload]\ntrials = 3 4", "after value"),
+        ] {
+            let err = to_args(text).unwrap_err();
+            assert!(err.contains(expected), "{:?}: {}", text, err);
+        }
+    }
+}
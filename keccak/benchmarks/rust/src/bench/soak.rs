@@ -0,0 +1,151 @@
+//! Long-running stability runs for `--soak`.
+//!
+//! A soak repeats trials of `--soak-interval` each until the soak time is
+//! up, so slow degradations (frequency scaling, heap fragmentation) show up
+//! as drift between the first and last samples.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+use crate::bench::budget::Budget;
+use crate::bench::stats::Summary;
+
+/// Share of the samples at each end whose median is compared for drift.
+const END_FRACTION: f64 = 0.1;
+
+/// How long to soak, parsed from a number of hours.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Soak {
+    pub hours: f64,
+}
+
+impl FromStr for Soak {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let hours = value.trim().parse::<f64>().map_err(|_| ())?;
+        if hours.is_finite() && hours > 0.0 {
+            Ok(Soak { hours })
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl fmt::Display for Soak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}h", self.hours)
+    }
+}
+
+/// Serializes as a number of hours.
+impl Serialize for Soak {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.hours)
+    }
+}
+
+impl Soak {
+    pub fn seconds(self) -> f64 {
+        self.hours * 3600.0
+    }
+
+    /// Trials per backend expected to fit when each takes `interval` and
+    /// `backends` take turns; at least one.
+    pub fn trials(self, interval: Budget, backends: usize) -> usize {
+        ((self.seconds() / (interval.seconds * backends.max(1) as f64)).ceil() as usize).max(1)
+    }
+}
+
+/// How throughput moved over a soak.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SoakReport {
+    /// Time from the start of the first sample to the end of the last.
+    pub hours: f64,
+    pub samples: usize,
+    /// Median throughput of the first and last tenth of the samples.
+    pub first_hashes_per_second: f64,
+    pub last_hashes_per_second: f64,
+    /// `last / first - 1`; negative when the run slowed down.
+    pub drift: f64,
+    /// Least-squares slope of throughput against time, as a fraction of
+    /// the mean throughput per hour.
+    pub drift_per_hour: f64,
+    pub min_hashes_per_second: f64,
+    pub max_hashes_per_second: f64,
+}
+
+impl SoakReport {
+    /// Summarize `(end_seconds, hashes_per_second)` samples in run order,
+    /// where `end_seconds` counts from the start of the first sample.
+    pub fn analyze(samples: &[(f64, f64)]) -> Option<SoakReport> {
+        let &(end, _) = samples.last()?;
+        let rates: Vec<f64> = samples.iter().map(|&(_, rate)| rate).collect();
+        let edge = ((samples.len() as f64 * END_FRACTION).ceil() as usize).max(1);
+        let first = Summary::from_samples(&rates[..edge])?.median;
+        let last = Summary::from_samples(&rates[rates.len() - edge..])?.median;
+        let drift = if first > 0.0 { last / first - 1.0 } else { 0.0 };
+
+        let count = samples.len() as f64;
+        let mean_hours = samples.iter().map(|&(t, _)| t / 3600.0).sum::<f64>() / count;
+        let mean_rate = rates.iter().sum::<f64>() / count;
+        let (covariance, variance) =
+            samples
+                .iter()
+                .fold((0.0, 0.0), |(covariance, variance), &(t, rate)| {
+                    let dt = t / 3600.0 - mean_hours;
+                    (covariance + dt * (rate - mean_rate), variance + dt * dt)
+                });
+        let drift_per_hour = if variance > 0.0 && mean_rate > 0.0 {
+            covariance / variance / mean_rate
+        } else {
+            0.0
+        };
+
+        Some(SoakReport {
+            hours: end / 3600.0,
+            samples: samples.len(),
+            first_hashes_per_second: first,
+            last_hashes_per_second: last,
+            drift,
+            drift_per_hour,
+            min_hashes_per_second: rates.iter().copied().fold(f64::INFINITY, f64::min),
+            max_hashes_per_second: rates.iter().copied().fold(0.0, f64::max),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A steady decline shows as negative drift in both measures; a flat
+    /// run shows none.
+    #[test]
+    fn measures_drift_between_ends() {
+        // One sample a minute for two hours, losing 10% per hour.
+        let declining: Vec<(f64, f64)> = (1..=120)
+            .map(|minute| (minute as f64 * 60.0, 100.0 * (1.0 - minute as f64 / 600.0)))
+            .collect();
+        let report = SoakReport::analyze(&declining).unwrap();
+        assert_eq!(report.samples, 120);
+        assert_eq!(report.hours, 2.0);
+        assert!((report.drift + 0.18).abs() < 0.01, "{:?}", report);
+        assert!((report.drift_per_hour + 0.112).abs() < 0.01, "{:?}", report);
+
+        let flat = SoakReport::analyze(&[(60.0, 5.0), (120.0, 5.0)]).unwrap();
+        assert_eq!((flat.drift, flat.drift_per_hour), (0.0, 0.0));
+        assert!(SoakReport::analyze(&[]).is_none());
+    }
+
+    /// Hours parse as positive numbers and divide into interval trials.
+    #[test]
+    fn soak_splits_into_trials() {
+        let soak: Soak = "0.5".parse().unwrap();
+        assert_eq!(soak.trials(Budget { seconds: 60.0 }, 2), 15);
+        assert!("0".parse::<Soak>().is_err());
+        assert!("8h".parse::<Soak>().is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! Summary statistics over repeated benchmark trials.
+
+use serde::Serialize;
+
+/// Aggregate statistics for a set of samples.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub mean: f64,
+    pub median: f64,
+    /// Sample standard deviation (Bessel-corrected); zero for one sample.
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Coefficient of variation, `stddev / mean`.
+    pub cv: f64,
+}
+
+impl Summary {
+    /// Summarize `samples`. Returns `None` when there are no samples.
+    pub fn from_samples(samples: &[f64]) -> Option<Summary> {
+        if samples.is_empty() {
+            return None;
+        }
+        let count = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / count;
+        let stddev = if samples.len() > 1 {
+            let squares: f64 = samples.iter().map(|value| (value - mean).powi(2)).sum();
+            (squares / (count - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let middle = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        };
+        let cv = if mean > 0.0 { stddev / mean } else { 0.0 };
+        Some(Summary {
+            mean,
+            median,
+            stddev,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            cv,
+        })
+    }
+}
+
+/// Per-hash latency quantiles in nanoseconds.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LatencySummary {
+    pub samples: usize,
+    pub p50_ns: f64,
+    pub p90_ns: f64,
+    pub p99_ns: f64,
+    pub p999_ns: f64,
+    pub max_ns: f64,
+}
+
+impl LatencySummary {
+    /// Summarize latency samples. Returns `None` when there are no samples.
+    pub fn from_samples(samples: &[f64]) -> Option<LatencySummary> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        Some(LatencySummary {
+            samples: sorted.len(),
+            p50_ns: percentile(&sorted, 50.0),
+            p90_ns: percentile(&sorted, 90.0),
+            p99_ns: percentile(&sorted, 99.0),
+            p999_ns: percentile(&sorted, 99.9),
+            max_ns: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice.
+pub fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    // The epsilon keeps values like 99.9% of 1000 from rounding up a rank.
+    let rank = (pct / 100.0 * sorted.len() as f64 - 1e-9).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Largest sample sizes whose [`mann_whitney`] p-value is computed
+/// exactly; larger or tied samples use the normal approximation.
+const EXACT_MANN_WHITNEY: usize = 30;
+
+/// Two-sided p-value of the Mann-Whitney U test that `a` and `b` come from
+/// the same distribution. Returns `None` unless both have at least two
+/// samples.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> Option<f64> {
+    let (m, n) = (a.len(), b.len());
+    if m < 2 || n < 2 {
+        return None;
+    }
+    let mut pooled: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&x| (x, true))
+        .chain(b.iter().map(|&x| (x, false)))
+        .collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+    // Average ranks over ties, and collect the tie sizes for the variance.
+    let mut rank_sum = 0.0;
+    let mut ties = 0.0;
+    let mut start = 0;
+    while start < pooled.len() {
+        let mut end = start + 1;
+        while end < pooled.len() && pooled[end].0 == pooled[start].0 {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        let from_a = pooled[start..end].iter().filter(|(_, in_a)| *in_a).count();
+        rank_sum += rank * from_a as f64;
+        let size = (end - start) as f64;
+        ties += size * size * size - size;
+        start = end;
+    }
+    let u = rank_sum - (m * (m + 1)) as f64 / 2.0;
+
+    if ties == 0.0 && m <= EXACT_MANN_WHITNEY && n <= EXACT_MANN_WHITNEY {
+        let counts = u_distribution(m, n);
+        let total: f64 = counts.iter().sum();
+        let u = u.round() as usize;
+        let below: f64 = counts[..=u].iter().sum();
+        let above: f64 = counts[u..].iter().sum();
+        return Some((2.0 * below.min(above) / total).min(1.0));
+    }
+    let (m, n) = (m as f64, n as f64);
+    let count = m + n;
+    let variance = m * n / 12.0 * ((count + 1.0) - ties / (count * (count - 1.0)));
+    if variance <= 0.0 {
+        return Some(1.0);
+    }
+    let z = (((u - m * n / 2.0).abs() - 0.5).max(0.0)) / variance.sqrt();
+    Some(erfc(z / std::f64::consts::SQRT_2).min(1.0))
+}
+
+/// How many orderings of `m` and `n` distinct samples give each U.
+fn u_distribution(m: usize, n: usize) -> Vec<f64> {
+    // counts[i][j][u] for i samples of one side and j of the other, built
+    // up one sample at a time: the largest sample comes from either side.
+    let mut previous: Vec<Vec<f64>> = (0..=n).map(|_| vec![1.0]).collect();
+    for i in 1..=m {
+        let mut current: Vec<Vec<f64>> = vec![vec![1.0]];
+        for j in 1..=n {
+            let mut counts = vec![0.0; i * j + 1];
+            for (u, count) in previous[j].iter().enumerate() {
+                counts[u + j] += count;
+            }
+            for (u, count) in current[j - 1].iter().enumerate() {
+                counts[u] += count;
+            }
+            current.push(counts);
+        }
+        previous = current;
+    }
+    previous.pop().expect("n + 1 rows")
+}
+
+/// Complementary error function (Abramowitz and Stegun 7.1.26, absolute
+/// error below 1.5e-7).
+fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        return 2.0 - erfc(-x);
+    }
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    poly * (-x * x).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check every field against hand-computed values.
+    #[test]
+    fn summary_matches_expected_values() {
+        let summary = Summary::from_samples(&[4.0, 1.0, 3.0, 2.0]).unwrap();
+        assert_eq!(summary.mean, 2.5);
+        assert_eq!(summary.median, 2.5);
+        assert_eq!(summary.min, 1.0);
+        assert_eq!(summary.max, 4.0);
+        assert!((summary.stddev - 1.290_994_448_7).abs() < 1e-9);
+        assert!((summary.cv - summary.stddev / 2.5).abs() < 1e-12);
+    }
+
+    /// A single sample has no spread, and no samples have no summary.
+    #[test]
+    fn summary_handles_degenerate_inputs() {
+        let summary = Summary::from_samples(&[3.0]).unwrap();
+        assert_eq!(summary.median, 3.0);
+        assert_eq!(summary.stddev, 0.0);
+        assert!(Summary::from_samples(&[]).is_none());
+    }
+
+    /// Nearest-rank percentiles over 1..=1000.
+    #[test]
+    fn latency_percentiles_use_nearest_rank() {
+        let samples: Vec<f64> = (1..=1000).rev().map(f64::from).collect();
+        let latency = LatencySummary::from_samples(&samples).unwrap();
+        assert_eq!(latency.samples, 1000);
+        assert_eq!(latency.p50_ns, 500.0);
+        assert_eq!(latency.p90_ns, 900.0);
+        assert_eq!(latency.p99_ns, 990.0);
+        assert_eq!(latency.p999_ns, 999.0);
+        assert_eq!(latency.max_ns, 1000.0);
+        assert_eq!(percentile(&[7.0], 0.0), 7.0);
+    }
+
+    /// Exact p-values match tables, the approximation agrees with them for
+    /// larger samples, and overlapping samples are not significant.
+    #[test]
+    fn mann_whitney_separates_shifted_samples() {
+        // Completely separated samples of 4 and 4: p = 2 / C(8, 4).
+        let p = mann_whitney(&[1.0, 2.0, 3.0, 4.0], &[5.0, 6.0, 7.0, 8.0]).unwrap();
+        assert!((p - 2.0 / 70.0).abs() < 1e-12);
+        let mixed = mann_whitney(&[1.0, 3.0, 5.0, 7.0], &[2.0, 4.0, 6.0, 8.0]).unwrap();
+        assert!(mixed > 0.5, "{}", mixed);
+        // Ties fall back to the normal approximation.
+        let tied = mann_whitney(&[1.0, 1.0, 2.0], &[1.0, 2.0, 2.0]).unwrap();
+        assert!(tied > 0.3 && tied <= 1.0, "{}", tied);
+        let low: Vec<f64> = (0..40).map(f64::from).collect();
+        let high: Vec<f64> = (0..40).map(|x| f64::from(x) + 20.0).collect();
+        assert!(mann_whitney(&low, &high).unwrap() < 1e-4);
+        assert!(mann_whitney(&[1.0], &[2.0, 3.0]).is_none());
+        assert!((erfc(0.0) - 1.0).abs() < 1e-6);
+        assert!((erfc(1.0) - 0.157_299_207).abs() < 1e-6);
+    }
+}
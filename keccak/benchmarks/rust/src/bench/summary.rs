@@ -0,0 +1,140 @@
+//! The headline numbers for `bench export-summary`: the fastest Rust
+//! result, the fastest Mojo result, and how they compare, small enough
+//! to embed in a dashboard or a README badge.
+//!
+//! Records are sorted by implementation name: `rust...`, as every
+//! backend's default label is, or `mojo...`. Anything else, such as the
+//! C baseline, is left out. Each side is represented by its highest
+//! hashes/s.
+
+use std::fmt::Write as _;
+
+use serde::Serialize;
+
+use crate::bench::chart::escape;
+use crate::bench::compare::Record;
+
+/// One side of the comparison.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Entry {
+    pub implementation: String,
+    pub hashes_per_second: f64,
+    pub mb_per_second: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Headline {
+    /// The best Rust backend.
+    pub rust: Entry,
+    pub mojo: Option<Entry>,
+    /// Mojo hashes/s over Rust's; above 1 means Mojo is faster.
+    pub ratio: Option<f64>,
+}
+
+fn best(records: &[Record], prefix: &str) -> Option<Entry> {
+    records
+        .iter()
+        .filter(|record| record.implementation.starts_with(prefix))
+        .max_by(|a, b| a.hashes_per_second.total_cmp(&b.hashes_per_second))
+        .map(|record| Entry {
+            implementation: record.implementation.clone(),
+            hashes_per_second: record.hashes_per_second,
+            mb_per_second: record.mb_per_second,
+        })
+}
+
+/// The headline of `records`, which need at least one Rust result.
+pub fn summarize(records: &[Record]) -> Result<Headline, String> {
+    let rust = best(records, "rust").ok_or("no Rust results (implementations named rust...)")?;
+    let mojo = best(records, "mojo");
+    let ratio = mojo
+        .as_ref()
+        .filter(|_| rust.hashes_per_second > 0.0)
+        .map(|mojo| mojo.hashes_per_second / rust.hashes_per_second);
+    Ok(Headline { rust, mojo, ratio })
+}
+
+/// A shields-style badge reading `mojo / rust | 1.42x`, green when Mojo
+/// is at least as fast, orange when slower, grey without a Mojo result.
+pub fn badge(headline: &Headline) -> String {
+    const LABEL: &str = "mojo / rust";
+    let (message, color) = match headline.ratio {
+        Some(ratio) => (
+            format!("{:.2}x", ratio),
+            if ratio >= 1.0 { "#4c1" } else { "#fe7d37" },
+        ),
+        None => (String::from("no mojo result"), "#9f9f9f"),
+    };
+    // Verdana at 11px averages about 7px a character.
+    let width = |text: &str| 10 + 7 * text.chars().count();
+    let (left, right) = (width(LABEL), width(&message));
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="20" role="img" aria-label="{label}: {message}">"#,
+        w = left + right,
+        label = LABEL,
+        message = escape(&message)
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r##"<rect width="{}" height="20" fill="#555"/><rect x="{}" width="{}" height="20" fill="{}"/>"##,
+        left, left, right, color
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r##"<g fill="#fff" text-anchor="middle" font-family="Verdana,sans-serif" font-size="11">"##
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"<text x="{}" y="14">{}</text><text x="{}" y="14">{}</text>"#,
+        left / 2,
+        LABEL,
+        left + right / 2,
+        escape(&message)
+    )
+    .unwrap();
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(implementation: &str, hashes_per_second: f64) -> Record {
+        serde_json::from_value(serde_json::json!({
+            "implementation": implementation,
+            "seconds": 1.0,
+            "hashes_per_second": hashes_per_second,
+        }))
+        .unwrap()
+    }
+
+    /// The fastest Rust backend and Mojo run are compared, and other
+    /// harnesses are ignored.
+    #[test]
+    fn picks_the_best_of_each_side() {
+        let records = [
+            record("rust (tiny-keccak)", 100.0),
+            record("rust (native-avx2)", 160.0),
+            record("mojo", 120.0),
+            record("mojo | jit", 200.0),
+            record("c", 500.0),
+        ];
+        let headline = summarize(&records).unwrap();
+        assert_eq!(headline.rust.implementation, "rust (native-avx2)");
+        assert_eq!(headline.mojo.as_ref().unwrap().implementation, "mojo | jit");
+        assert_eq!(headline.ratio, Some(1.25));
+        let svg = badge(&headline);
+        assert!(svg.contains(">1.25x</text>") && svg.contains("#4c1"));
+
+        let rust_only = summarize(&records[..2]).unwrap();
+        assert_eq!((rust_only.mojo, rust_only.ratio), (None, None));
+        assert!(badge(&summarize(&records[..2]).unwrap()).contains("no mojo result"));
+        assert!(summarize(&records[2..]).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Message-size sweeps for `--sweep`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+use crate::bench::BenchConfig;
+
+/// How a sweep moves from one size to the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Add this many bytes.
+    Add(usize),
+    /// Multiply by this factor.
+    Scale(usize),
+}
+
+/// Sizes `min`, `min + step`, ... up to and including `max`, written
+/// `MIN..MAX[:STEP]` where `STEP` is a byte count or `xFACTOR` (default
+/// `x2`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sweep {
+    pub min: usize,
+    pub max: usize,
+    pub step: Step,
+}
+
+impl FromStr for Sweep {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (range, step) = match value.split_once(':') {
+            Some((range, step)) => (range, step),
+            None => (value, "x2"),
+        };
+        let (min, max) = range.split_once("..").ok_or(())?;
+        let (min, max): (usize, usize) =
+            (min.parse().map_err(|_| ())?, max.parse().map_err(|_| ())?);
+        let step = match step.strip_prefix('x') {
+            Some(factor) => Step::Scale(factor.parse().map_err(|_| ())?),
+            None => Step::Add(step.parse().map_err(|_| ())?),
+        };
+        let advances = match step {
+            Step::Add(bytes) => bytes > 0,
+            Step::Scale(factor) => factor > 1 && min > 0,
+        };
+        if min > max || !advances {
+            return Err(());
+        }
+        Ok(Sweep { min, max, step })
+    }
+}
+
+impl fmt::Display for Sweep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.step {
+            Step::Add(bytes) => write!(f, "{}..{}:{}", self.min, self.max, bytes),
+            Step::Scale(factor) => write!(f, "{}..{}:x{}", self.min, self.max, factor),
+        }
+    }
+}
+
+impl Serialize for Sweep {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Sweep {
+    /// Every message size in the sweep, smallest first.
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = Vec::new();
+        let mut size = self.min;
+        while size <= self.max {
+            sizes.push(size);
+            size = match self.step {
+                Step::Add(bytes) => size.saturating_add(bytes),
+                Step::Scale(factor) => size.saturating_mul(factor),
+            };
+            if sizes.last() == Some(&size) {
+                break;
+            }
+        }
+        sizes
+    }
+
+    /// One config per size, each hashing `base.messages` messages of
+    /// exactly that length.
+    pub fn points(&self, base: &BenchConfig) -> Vec<BenchConfig> {
+        self.sizes()
+            .into_iter()
+            .map(|size| BenchConfig {
+                min_len: size,
+                max_len: size,
+                sweep: Some(*self),
+                ..base.clone()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both step kinds parse, round-trip, and stop at the upper bound.
+    #[test]
+    fn parses_and_expands_sizes() {
+        let doubling: Sweep = "32..4096".parse().unwrap();
+        assert_eq!(doubling.to_string(), "32..4096:x2");
+        assert_eq!(doubling.sizes(), [32, 64, 128, 256, 512, 1024, 2048, 4096]);
+        let linear: Sweep = "100..400:150".parse().unwrap();
+        assert_eq!(linear.sizes(), [100, 250, 400]);
+        assert_eq!(linear.to_string().parse::<Sweep>(), Ok(linear));
+        for bad in [
+            "64..32",
+            "32..64:0",
+            "0..64:x2",
+            "32..64:x1",
+            "32-64",
+            "a..b",
+        ] {
+            assert!(bad.parse::<Sweep>().is_err(), "{}", bad);
+        }
+    }
+
+    /// Each point fixes the message length and keeps the rest of the config.
+    #[test]
+    fn points_fix_message_length() {
+        let base = BenchConfig {
+            messages: 7,
+            ..BenchConfig::default()
+        };
+        let points = "16..48:16".parse::<Sweep>().unwrap().points(&base);
+        assert_eq!(points.len(), 3);
+        assert!(points
+            .iter()
+            .all(|config| config.min_len == config.max_len && config.messages == 7));
+        assert_eq!(points[2].max_len, 48);
+    }
+}
@@ -0,0 +1,262 @@
+//! Benchmark targets: the primitive the timed loop runs on each message.
+//!
+//! A target reduces every message to a 32-byte digest that feeds the
+//! checksum, so warmup, threading, batching, and every output format work
+//! unchanged for any primitive. New primitives implement [`BenchTarget`]
+//! and are added to [`TARGETS`]; they then appear in `--list-targets`,
+//! are selectable with `--target`, and are recorded as the result's
+//! `operation`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+use crate::bench::ecdsa;
+use crate::bench::output::KECCAK256;
+use crate::bench::xof::Xof;
+use crate::bench::BenchConfig;
+
+/// A primitive the harness can time.
+pub trait BenchTarget: Sync {
+    /// The `--target` name, recorded as the result's `operation`.
+    fn name(&self) -> &'static str;
+
+    /// One line for `--list-targets`.
+    fn description(&self) -> &'static str;
+
+    /// Reject configs this target cannot run. Called by
+    /// [`BenchConfig::validate`], before any warmup.
+    fn setup(&self, config: &BenchConfig) -> Result<(), String> {
+        let _ = config;
+        Ok(())
+    }
+
+    /// Process `messages`, writing one digest per message into `digests`.
+    /// Called with a single message unless `config.batch` is set.
+    fn run_batch(&self, config: &BenchConfig, messages: &[&[u8]], digests: &mut [[u8; 32]]);
+
+    /// Fold one digest into the running checksum.
+    fn checksum(&self, config: &BenchConfig, checksum: u64, digest: &[u8; 32]) -> u64 {
+        config.checksum_mode.update(checksum, digest)
+    }
+
+    /// The extendable-output function this target squeezes, if any; its
+    /// results also report output throughput.
+    fn xof(&self) -> Option<Xof> {
+        None
+    }
+}
+
+/// Keccak-256 through the config's backend, honouring `chunk_size` and
+/// `batch`.
+pub struct Keccak256;
+
+impl BenchTarget for Keccak256 {
+    fn name(&self) -> &'static str {
+        KECCAK256
+    }
+
+    fn description(&self) -> &'static str {
+        "Keccak-256 digests (the default)"
+    }
+
+    fn run_batch(&self, config: &BenchConfig, messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+        if config.batch.is_some() {
+            return config.backend.hash_batch(messages, digests);
+        }
+        for (message, digest) in messages.iter().zip(digests) {
+            *digest = match config.chunk_size {
+                Some(chunk) => config.backend.hash_chunked(message, chunk),
+                None => config.backend.hash(message),
+            };
+        }
+    }
+}
+
+/// A SHAKE function squeezing `xof_output` bytes per message.
+pub struct Shake(pub Xof);
+
+impl BenchTarget for Shake {
+    fn name(&self) -> &'static str {
+        self.0.operation()
+    }
+
+    fn description(&self) -> &'static str {
+        match self.0 {
+            Xof::Shake128 => "SHAKE128, squeezing --xof-output bytes per message",
+            Xof::Shake256 => "SHAKE256, squeezing --xof-output bytes per message",
+        }
+    }
+
+    fn setup(&self, config: &BenchConfig) -> Result<(), String> {
+        if config.xof_output == 0 {
+            return Err(String::from("xof_output must be at least 1"));
+        }
+        if !config.backend.has_xof() {
+            return Err(format!(
+                "backend {} has no {}; use the native or sha3 backend",
+                config.backend, self.0
+            ));
+        }
+        if config.batch.is_some() || config.chunk_size.is_some() {
+            return Err(format!(
+                "{} absorbs each message in one call; drop batch and chunk_size",
+                self.0
+            ));
+        }
+        Ok(())
+    }
+
+    fn run_batch(&self, config: &BenchConfig, messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+        for (message, digest) in messages.iter().zip(digests) {
+            *digest = config.backend.hash_xof(self.0, message, config.xof_output);
+        }
+    }
+
+    fn xof(&self) -> Option<Xof> {
+        Some(self.0)
+    }
+}
+
+/// Every registered target, in `--list-targets` order.
+#[cfg(not(all(feature = "test-utils", not(feature = "verify-only"))))]
+pub static TARGETS: &[&dyn BenchTarget] = &[
+    &Keccak256,
+    &Shake(Xof::Shake128),
+    &Shake(Xof::Shake256),
+    &ecdsa::Recover,
+    &ecdsa::Verify,
+];
+
+/// Every registered target, in `--list-targets` order, with the ones
+/// that need the fixture signer.
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+pub static TARGETS: &[&dyn BenchTarget] = &[
+    &Keccak256,
+    &Shake(Xof::Shake128),
+    &Shake(Xof::Shake256),
+    &ecdsa::Recover,
+    &ecdsa::Verify,
+    &ecdsa::Keygen,
+    &ecdsa::Sign,
+];
+
+/// A registered target, as stored in a config. Serializes as its name.
+#[derive(Clone, Copy)]
+pub struct Target(&'static dyn BenchTarget);
+
+impl Target {
+    /// The target for `function`.
+    pub fn xof(function: Xof) -> Target {
+        function
+            .operation()
+            .parse()
+            .expect("every XOF is a registered target")
+    }
+
+    /// Run this target on one message.
+    pub fn hash(self, config: &BenchConfig, message: &[u8]) -> [u8; 32] {
+        let mut digest = [[0u8; 32]];
+        self.0.run_batch(config, &[message], &mut digest);
+        digest[0]
+    }
+}
+
+impl std::ops::Deref for Target {
+    type Target = dyn BenchTarget;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Target(&Keccak256)
+    }
+}
+
+impl PartialEq for Target {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.name() == other.0.name()
+    }
+}
+
+impl FromStr for Target {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        TARGETS
+            .iter()
+            .find(|target| target.name() == value)
+            .map(|&target| Target(target))
+            .ok_or(())
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.name())
+    }
+}
+
+impl fmt::Debug for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Target({})", self.0.name())
+    }
+}
+
+impl Serialize for Target {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::backend::Backend;
+
+    /// Names are unique and round-trip through parsing.
+    #[test]
+    fn targets_parse_by_name() {
+        for target in TARGETS {
+            let parsed: Target = target.name().parse().unwrap();
+            assert_eq!(parsed.name(), target.name());
+        }
+        let mut names: Vec<&str> = TARGETS.iter().map(|target| target.name()).collect();
+        names.dedup();
+        assert_eq!(names.len(), TARGETS.len());
+        assert_eq!(Target::default().name(), KECCAK256);
+        assert!("sha256".parse::<Target>().is_err());
+    }
+
+    /// Batches and single messages give the same digests, and SHAKE
+    /// targets squeeze through the backend.
+    #[test]
+    fn run_batch_matches_single_messages() {
+        let config = BenchConfig {
+            backend: Backend::Native,
+            ..BenchConfig::default()
+        };
+        let messages: [&[u8]; 2] = [b"abc", b""];
+        // The curve targets are covered in `bench::ecdsa`, not under Miri.
+        let fast = TARGETS
+            .iter()
+            .filter(|target| !cfg!(miri) || target.name() == KECCAK256 || target.xof().is_some());
+        for target in fast {
+            let target = Target(*target);
+            let mut digests = [[0u8; 32]; 2];
+            target.run_batch(&config, &messages, &mut digests);
+            assert_eq!(digests[0], target.hash(&config, b"abc"));
+            assert_eq!(digests[1], target.hash(&config, b""));
+        }
+        let shake = Target::xof(Xof::Shake128);
+        assert_eq!(
+            shake.hash(&config, b"abc"),
+            Backend::Native.hash_xof(Xof::Shake128, b"abc", 32)
+        );
+        assert_eq!(Target::default().hash(&config, b""), crate::keccak256(b""));
+    }
+}
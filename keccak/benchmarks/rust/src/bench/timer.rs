@@ -0,0 +1,143 @@
+//! Clock used for trial and per-hash timing (`--timer`).
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::bench::tsc;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Timer {
+    /// `std::time::Instant`, i.e. `clock_gettime` through the vDSO on
+    /// Linux.
+    #[default]
+    Instant,
+    /// Serialized `rdtscp` reads converted with a calibrated frequency. Its
+    /// overhead is a few dozen cycles, versus tens of nanoseconds for
+    /// `Instant`, which matters for per-hash latency of small messages.
+    Tsc,
+}
+
+impl FromStr for Timer {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "instant" => Ok(Timer::Instant),
+            "tsc" => Ok(Timer::Tsc),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Timer::Instant => "instant",
+            Timer::Tsc => "tsc",
+        })
+    }
+}
+
+/// A point in time taken with some [`Timer`].
+#[derive(Clone, Copy)]
+pub enum Stamp {
+    Instant(Instant),
+    Tsc(u64),
+}
+
+impl Timer {
+    /// Check that the timer works here, calibrating the TSC if needed.
+    pub fn check(self) -> Result<(), String> {
+        match self {
+            Timer::Instant => Ok(()),
+            Timer::Tsc if !tsc::invariant() => Err(String::from(
+                "--timer tsc needs an x86-64 CPU with an invariant TSC and rdtscp",
+            )),
+            Timer::Tsc => tsc::frequency()
+                .map(|_| ())
+                .ok_or_else(|| String::from("TSC calibration failed")),
+        }
+    }
+
+    /// Nanoseconds one [`Timer::now`] plus [`Stamp::elapsed_secs`] pair
+    /// costs: the fastest of 1000 back-to-back pairs, measured once per
+    /// timer. Per-hash times subtract it; taking the minimum keeps the
+    /// correction from exceeding what the clock reads actually cost.
+    pub fn overhead_ns(self) -> f64 {
+        static INSTANT: OnceLock<f64> = OnceLock::new();
+        static TSC: OnceLock<f64> = OnceLock::new();
+        let cell = match self {
+            Timer::Instant => &INSTANT,
+            Timer::Tsc => &TSC,
+        };
+        *cell.get_or_init(|| {
+            (0..1000)
+                .map(|_| self.now().elapsed_secs() * 1e9)
+                .fold(f64::INFINITY, f64::min)
+        })
+    }
+
+    pub fn now(self) -> Stamp {
+        match self {
+            Timer::Instant => Stamp::Instant(Instant::now()),
+            Timer::Tsc => Stamp::Tsc(tsc::read_serialized().expect("checked by Timer::check")),
+        }
+    }
+}
+
+impl Stamp {
+    pub fn elapsed_secs(&self) -> f64 {
+        match self {
+            Stamp::Instant(start) => start.elapsed().as_secs_f64(),
+            Stamp::Tsc(start) => {
+                let now = tsc::read_serialized().expect("checked by Timer::check");
+                let hz = tsc::frequency().expect("checked by Timer::check");
+                now.wrapping_sub(*start) as f64 / hz
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both clocks measure a sleep to within a generous tolerance.
+    #[test]
+    fn timers_agree_on_a_sleep() {
+        for timer in [Timer::Instant, Timer::Tsc] {
+            if timer.check().is_err() {
+                continue;
+            }
+            let start = timer.now();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            let elapsed = start.elapsed_secs();
+            assert!((0.019..0.5).contains(&elapsed), "{:?}: {}", timer, elapsed);
+        }
+    }
+
+    /// Clock-read overhead is measurable and far below a microsecond on
+    /// anything that can run the benchmark.
+    #[test]
+    #[cfg_attr(miri, ignore = "interpreted clock reads take far longer")]
+    fn overhead_is_small_and_stable() {
+        for timer in [Timer::Instant, Timer::Tsc] {
+            if timer.check().is_err() {
+                continue;
+            }
+            let overhead = timer.overhead_ns();
+            assert!(
+                (0.0..100_000.0).contains(&overhead),
+                "{:?}: {}",
+                timer,
+                overhead
+            );
+            assert_eq!(timer.overhead_ns(), overhead);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Time-stamp counter access for cycle-based metrics and `--timer tsc`.
+//!
+//! The TSC ticks at a constant reference rate on modern x86-64 parts, so
+//! cycles/byte derived from it is comparable across runs on one machine
+//! but not a direct count of core clock cycles under frequency scaling.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Read the time-stamp counter, or `None` when the target has no TSC.
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+pub fn read() -> Option<u64> {
+    // SAFETY: `rdtsc` is available on every x86-64 CPU and has no memory
+    // effects.
+    Some(unsafe { core::arch::x86_64::_rdtsc() })
+}
+
+/// Read the time-stamp counter, or `None` when the target has no TSC.
+#[cfg(not(all(target_arch = "x86_64", not(feature = "safe-only"))))]
+pub fn read() -> Option<u64> {
+    None
+}
+
+/// Read the TSC with `rdtscp` followed by `lfence`, so the read waits for
+/// earlier instructions to retire and later ones cannot start before it.
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+pub fn read_serialized() -> Option<u64> {
+    let mut aux = 0u32;
+    // SAFETY: `rdtscp` and `lfence` exist on every CPU `invariant()` accepts
+    // and only write `aux`.
+    unsafe {
+        let ticks = core::arch::x86_64::__rdtscp(&mut aux);
+        core::arch::x86_64::_mm_lfence();
+        Some(ticks)
+    }
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(feature = "safe-only"))))]
+pub fn read_serialized() -> Option<u64> {
+    None
+}
+
+/// Whether the CPU advertises an invariant TSC (constant rate across P-,
+/// C-, and T-states) and `rdtscp`, which `--timer tsc` relies on.
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+pub fn invariant() -> bool {
+    use core::arch::x86_64::__cpuid;
+    // Extended leaves are only queried when the maximum leaf reports them.
+    let max_extended = __cpuid(0x8000_0000).eax;
+    if max_extended < 0x8000_0007 {
+        return false;
+    }
+    let has_rdtscp = __cpuid(0x8000_0001).edx & (1 << 27) != 0;
+    let invariant = __cpuid(0x8000_0007).edx & (1 << 8) != 0;
+    has_rdtscp && invariant
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(feature = "safe-only"))))]
+pub fn invariant() -> bool {
+    false
+}
+
+/// TSC ticks per second, calibrated once against `Instant` over 50 ms.
+pub fn frequency() -> Option<f64> {
+    static FREQUENCY: OnceLock<Option<f64>> = OnceLock::new();
+    *FREQUENCY.get_or_init(|| {
+        let start_ticks = read_serialized()?;
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(50) {
+            std::hint::spin_loop();
+        }
+        let ticks = read_serialized()?.wrapping_sub(start_ticks);
+        Some(ticks as f64 / start.elapsed().as_secs_f64())
+    })
+}
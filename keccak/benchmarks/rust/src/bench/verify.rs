@@ -0,0 +1,161 @@
+//! Per-message digest files for `--verify-out` and `--verify-in`.
+//!
+//! Each non-comment line is `<index> <length> <hex digest>`, one per message
+//! in index order, so another harness can emit the same file for the same
+//! workload and have it checked here.
+
+use std::fmt::Write as _;
+use std::fs;
+
+use crate::{keccak256, to_hex_string};
+
+use crate::bench::workload::Workload;
+use crate::bench::BenchConfig;
+
+/// Diffs stop listing individual messages after this many.
+const MAX_REPORTED: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub index: usize,
+    pub length: usize,
+    pub digest: [u8; 32],
+}
+
+/// Hash every message of the workload once.
+pub fn compute(config: &BenchConfig, workload: &Workload) -> Vec<Entry> {
+    (0..workload.count(config))
+        .map(|index| {
+            let message = workload.message(config, index);
+            Entry {
+                index,
+                length: message.len(),
+                digest: keccak256(&message),
+            }
+        })
+        .collect()
+}
+
+pub fn render(entries: &[Entry]) -> String {
+    let mut out = String::from("# index length keccak256\n");
+    for entry in entries {
+        writeln!(
+            out,
+            "{} {} {}",
+            entry.index,
+            entry.length,
+            to_hex_string(&entry.digest)
+        )
+        .unwrap();
+    }
+    out
+}
+
+pub fn parse(text: &str) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || format!("line {}: expected `<index> <length> <digest>`", number + 1);
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [index, length, digest] = fields[..] else {
+            return Err(invalid());
+        };
+        let digest = hex::decode(digest.trim_start_matches("0x")).map_err(|_| invalid())?;
+        entries.push(Entry {
+            index: index.parse().map_err(|_| invalid())?,
+            length: length.parse().map_err(|_| invalid())?,
+            digest: digest.try_into().map_err(|_| invalid())?,
+        });
+    }
+    Ok(entries)
+}
+
+pub fn write(path: &str, entries: &[Entry]) -> Result<(), String> {
+    fs::write(path, render(entries)).map_err(|err| format!("{}: {}", path, err))
+}
+
+pub fn read(path: &str) -> Result<Vec<Entry>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    parse(&text).map_err(|err| format!("{}: {}", path, err))
+}
+
+/// Describe every way `actual` differs from `expected`; empty when they agree.
+pub fn diff(expected: &[Entry], actual: &[Entry]) -> Vec<String> {
+    let mut problems = Vec::new();
+    if expected.len() != actual.len() {
+        problems.push(format!(
+            "expected {} messages but computed {}",
+            expected.len(),
+            actual.len()
+        ));
+    }
+    let mismatches: Vec<String> = expected
+        .iter()
+        .zip(actual)
+        .filter(|(want, got)| want != got)
+        .map(|(want, got)| {
+            if want.index != got.index || want.length != got.length {
+                format!(
+                    "message {}: expected index {} length {}, computed index {} length {}",
+                    got.index, want.index, want.length, got.index, got.length
+                )
+            } else {
+                format!(
+                    "message {} ({} bytes): expected {}, computed {}",
+                    got.index,
+                    got.length,
+                    to_hex_string(&want.digest),
+                    to_hex_string(&got.digest)
+                )
+            }
+        })
+        .collect();
+    let total = mismatches.len();
+    problems.extend(mismatches.into_iter().take(MAX_REPORTED));
+    if total > MAX_REPORTED {
+        problems.push(format!(
+            "... and {} more mismatched messages",
+            total - MAX_REPORTED
+        ));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<Entry> {
+        let config = BenchConfig {
+            messages: 5,
+            ..BenchConfig::default()
+        };
+        compute(&config, &Workload::Synthetic)
+    }
+
+    /// What `render` writes, `parse` reads back.
+    #[test]
+    fn digest_files_round_trip() {
+        let entries = entries();
+        assert_eq!(parse(&render(&entries)).unwrap(), entries);
+        assert!(parse("0 32").is_err());
+        assert!(parse("0 32 abcd").is_err());
+    }
+
+    /// Wrong digests and missing messages are both reported.
+    #[test]
+    fn diff_reports_mismatches() {
+        let expected = entries();
+        assert!(diff(&expected, &expected).is_empty());
+        let mut actual = expected.clone();
+        actual[2].digest[0] ^= 1;
+        actual.pop();
+        let problems = diff(&expected, &actual);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("expected 5 messages but computed 4"));
+        assert!(problems[1].starts_with("message 2 ("));
+    }
+}
@@ -0,0 +1,95 @@
+//! Warmup policy: a fixed number of rounds or adaptive calibration.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+/// How many untimed rounds run before the first trial.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Warmup {
+    /// Exactly this many rounds.
+    Fixed(usize),
+    /// Rounds until throughput settles; see [`settled`].
+    Adaptive,
+}
+
+impl FromStr for Warmup {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(Warmup::Adaptive),
+            rounds => rounds.parse().map(Warmup::Fixed).map_err(|_| ()),
+        }
+    }
+}
+
+impl fmt::Display for Warmup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warmup::Fixed(rounds) => write!(f, "{}", rounds),
+            Warmup::Adaptive => f.write_str("auto"),
+        }
+    }
+}
+
+/// Serializes as the round count, or `"auto"` for adaptive warmup.
+impl Serialize for Warmup {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Warmup::Fixed(rounds) => serializer.serialize_u64(*rounds as u64),
+            Warmup::Adaptive => serializer.serialize_str("auto"),
+        }
+    }
+}
+
+/// What the warmup phase actually did.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WarmupResult {
+    pub rounds: usize,
+    pub seconds: f64,
+    /// Whether adaptive warmup settled before its timeout; `null` for a
+    /// fixed warmup.
+    pub converged: Option<bool>,
+}
+
+/// Consecutive windows compared by [`settled`].
+pub const WINDOWS: usize = 3;
+
+/// True once the last [`WINDOWS`] per-round throughputs spread by less than
+/// `tolerance`, as a fraction of their mean.
+pub fn settled(throughputs: &[f64], tolerance: f64) -> bool {
+    if throughputs.len() < WINDOWS {
+        return false;
+    }
+    let recent = &throughputs[throughputs.len() - WINDOWS..];
+    let mean = recent.iter().sum::<f64>() / WINDOWS as f64;
+    let min = recent.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = recent.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    mean > 0.0 && (max - min) / mean < tolerance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `auto` and round counts parse, and serialize back the same way.
+    #[test]
+    fn warmup_parses_and_serializes() {
+        assert_eq!("auto".parse(), Ok(Warmup::Adaptive));
+        assert_eq!("5".parse(), Ok(Warmup::Fixed(5)));
+        assert!("fast".parse::<Warmup>().is_err());
+        assert_eq!(serde_json::to_value(Warmup::Adaptive).unwrap(), "auto");
+        assert_eq!(serde_json::to_value(Warmup::Fixed(5)).unwrap(), 5);
+    }
+
+    /// Only the most recent windows count, and short histories never settle.
+    #[test]
+    fn settled_compares_recent_windows() {
+        assert!(!settled(&[100.0, 100.0], 0.02));
+        assert!(!settled(&[50.0, 100.0, 101.0, 90.0], 0.02));
+        assert!(settled(&[50.0, 100.0, 101.0, 100.5], 0.02));
+        assert!(!settled(&[0.0, 0.0, 0.0], 0.02));
+    }
+}
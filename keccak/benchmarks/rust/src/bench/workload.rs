@@ -0,0 +1,507 @@
+//! Message sources for the timed loop.
+//!
+//! A [`Generator`] says how many messages a workload has, how long each is
+//! and what bytes it holds. [`Synthetic`] builds messages from a length
+//! rule and a byte rule, [`Corpus`] serves files, and
+//! [`Workload::from_generator`] turns any generator into a workload the
+//! harness can time. Other harnesses that want the same inputs implement
+//! the synthetic rules below, which are the whole specification; all
+//! arithmetic is on unsigned integers, and 64-bit steps wrap.
+//!
+//! Lengths ([`Lengths`]), for message `i`:
+//!
+//! - stride: `min_len + (i * stride) % (max_len - min_len + 1)`.
+//! - mixed: by `i % 16`, 32 for 0 to 7, `96 + (i * 7) % 33` for 8 to 12,
+//!   `512 + (i * 131) % 3585` for 13 and 14, and 131072 for 15.
+//! - distribution (lengths measured from a trace, `--length-dist`): with
+//!   `r` the [`SplitMix64`] seeded with `!(seed ^ (i * 0x9e3779b97f4a7c15))`,
+//!   take `u = (r.next() >> 11) / 2^53` as a double, pick the first bucket
+//!   whose cumulative weight exceeds `u * total` (the last if none does),
+//!   then `min + r.next() % (max - min + 1)` within it.
+//!
+//! Bytes ([`Bytes`]), for message `i` of length `n`:
+//!
+//! - counter: byte `j` is `(i + j) % 256`.
+//! - seeded: the little-endian bytes of successive outputs of the
+//!   [`SplitMix64`] seeded with `seed ^ (i * 0x9e3779b97f4a7c15)`, eight
+//!   per output, the last output truncated to what `n` needs.
+//!
+//! [`SplitMix64`] itself adds `0x9e3779b97f4a7c15` to its state, then
+//! returns `z ^ (z >> 31)` where `z` is the state put through
+//! `z = (z ^ (z >> 30)) * 0xbf58476d1ce4e5b9` and
+//! `z = (z ^ (z >> 27)) * 0x94d049bb133111eb`.
+
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::bench::lengths::LengthDist;
+use crate::bench::mapped::MappedFile;
+use crate::bench::profile::{self, Profile};
+use crate::bench::BenchConfig;
+
+/// One input loaded from a corpus directory or manifest.
+pub struct CorpusFile {
+    pub path: String,
+    pub data: Vec<u8>,
+}
+
+/// A numbered set of messages.
+pub trait Generator {
+    /// Number of messages.
+    fn count(&self) -> usize;
+
+    /// Length of message `index`.
+    fn length(&self, index: usize) -> usize;
+
+    /// Overwrite `message` with message `index`.
+    fn fill(&self, index: usize, message: &mut Vec<u8>);
+
+    fn generate(&self, index: usize) -> Vec<u8> {
+        let mut message = Vec::with_capacity(self.length(index));
+        self.fill(index, &mut message);
+        message
+    }
+}
+
+/// How long each synthetic message is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lengths<'a> {
+    Stride {
+        min_len: usize,
+        max_len: usize,
+        stride: usize,
+    },
+    /// The mixed profile's 16-message cycle.
+    Mixed,
+    /// Drawn from an empirical distribution with `seed`.
+    Dist { dist: &'a LengthDist, seed: u64 },
+}
+
+impl Lengths<'_> {
+    pub fn length(&self, index: usize) -> usize {
+        match *self {
+            Lengths::Stride {
+                min_len,
+                max_len,
+                stride,
+            } => min_len + (index * stride) % (max_len - min_len + 1),
+            Lengths::Mixed => profile::mixed_length(index),
+            Lengths::Dist { dist, seed } => dist.sample(seed, index),
+        }
+    }
+}
+
+/// What bytes each synthetic message holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bytes {
+    /// Byte `offset` of message `index` is `(index + offset) % 256`,
+    /// matching the Mojo and Python harnesses.
+    Counter,
+    /// [`SplitMix64`] output seeded from the seed and the index.
+    Seeded(u64),
+}
+
+impl Bytes {
+    /// Overwrite `message` with the `length` bytes of message `index`.
+    pub fn fill(&self, index: usize, length: usize, message: &mut Vec<u8>) {
+        message.clear();
+        match *self {
+            Bytes::Counter => {
+                for offset in 0..length {
+                    message.push(((index + offset) % 256) as u8);
+                }
+            }
+            Bytes::Seeded(seed) => {
+                let mut rng = SplitMix64::new(seed ^ (index as u64).wrapping_mul(GOLDEN_GAMMA));
+                while message.len() < length {
+                    let bytes = rng.next_u64().to_le_bytes();
+                    let take = (length - message.len()).min(bytes.len());
+                    message.extend_from_slice(&bytes[..take]);
+                }
+            }
+        }
+    }
+}
+
+/// Messages built from a length rule and a byte rule.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Synthetic<'a> {
+    pub messages: usize,
+    pub lengths: Lengths<'a>,
+    pub bytes: Bytes,
+}
+
+impl<'a> Synthetic<'a> {
+    /// The synthetic messages `config` describes: its length distribution,
+    /// else the mixed profile's lengths, else its stride, with seeded bytes
+    /// when it has a seed.
+    pub fn from_config(config: &'a BenchConfig) -> Synthetic<'a> {
+        let lengths = match &config.length_dist {
+            Some(dist) => Lengths::Dist {
+                dist,
+                seed: config.seed.unwrap_or(0),
+            },
+            None if config.profile == Some(Profile::Mixed) => Lengths::Mixed,
+            None => Lengths::Stride {
+                min_len: config.min_len,
+                max_len: config.max_len,
+                stride: config.stride,
+            },
+        };
+        Synthetic {
+            messages: config.messages,
+            lengths,
+            bytes: config.seed.map_or(Bytes::Counter, Bytes::Seeded),
+        }
+    }
+}
+
+impl Generator for Synthetic<'_> {
+    fn count(&self) -> usize {
+        self.messages
+    }
+
+    fn length(&self, index: usize) -> usize {
+        self.lengths.length(index)
+    }
+
+    fn fill(&self, index: usize, message: &mut Vec<u8>) {
+        self.bytes.fill(index, self.length(index), message);
+    }
+}
+
+/// Loaded corpus files as messages, in order.
+pub struct Corpus<'a>(pub &'a [CorpusFile]);
+
+impl Generator for Corpus<'_> {
+    fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn length(&self, index: usize) -> usize {
+        self.0[index].data.len()
+    }
+
+    fn fill(&self, index: usize, message: &mut Vec<u8>) {
+        message.clear();
+        message.extend_from_slice(&self.0[index].data);
+    }
+}
+
+/// Where benchmark messages come from.
+pub enum Workload {
+    /// Messages generated on the fly from the config's length parameters.
+    Synthetic,
+    /// The synthetic messages, generated up front so the timed loop only
+    /// hashes.
+    Generated(Vec<Vec<u8>>),
+    /// Files loaded up front by `--corpus`, hashed in a stable order.
+    Corpus(Vec<CorpusFile>),
+    /// One memory-mapped file from `--mmap`, hashed as a single message.
+    Mapped(MappedFile),
+}
+
+impl Workload {
+    /// The workload `config` describes, loading its corpus if it names one.
+    /// A corpus overrides `messages`, `min_len`, and `max_len` to match its
+    /// files; a length distribution overrides the length bounds. A mapped
+    /// file is one message of its size, absorbed in 1 MiB windows unless
+    /// `chunk_size` says otherwise.
+    pub fn load(config: &mut BenchConfig) -> Result<Workload, String> {
+        if let Some(dist) = &config.length_dist {
+            config.min_len = dist.min_len();
+            config.max_len = dist.max_len();
+        }
+        if let Some(path) = &config.mmap {
+            let mapped = MappedFile::open(path)?;
+            config.messages = 1;
+            config.min_len = mapped.as_slice().len();
+            config.max_len = config.min_len;
+            config.chunk_size = config.chunk_size.or(Some(1 << 20));
+            return Ok(Workload::Mapped(mapped));
+        }
+        let Some(path) = &config.corpus else {
+            return Ok(Workload::Synthetic);
+        };
+        let files = load_corpus(Path::new(path))
+            .map_err(|err| format!("failed to load corpus {}: {}", path, err))?;
+        if files.is_empty() {
+            return Err(format!("corpus {} contains no files", path));
+        }
+        config.messages = files.len();
+        config.min_len = files.iter().map(|file| file.data.len()).min().unwrap_or(0);
+        config.max_len = files.iter().map(|file| file.data.len()).max().unwrap_or(0);
+        Ok(Workload::Corpus(files))
+    }
+
+    /// The synthetic messages for `config`, generated now.
+    pub fn generate(config: &BenchConfig) -> Workload {
+        Workload::from_generator(&Synthetic::from_config(config))
+    }
+
+    /// Every message of `generator`, generated now. Timing it needs a
+    /// config whose `messages`, `min_len` and `max_len` describe it.
+    pub fn from_generator(generator: &dyn Generator) -> Workload {
+        Workload::Generated(
+            (0..generator.count())
+                .map(|index| generator.generate(index))
+                .collect(),
+        )
+    }
+
+    /// Number of messages hashed per round.
+    pub fn count(&self, config: &BenchConfig) -> usize {
+        match self {
+            Workload::Synthetic => config.messages,
+            Workload::Generated(messages) => messages.len(),
+            Workload::Corpus(files) => files.len(),
+            Workload::Mapped(_) => 1,
+        }
+    }
+
+    /// The message at `index`, generating it when the workload is synthetic.
+    pub fn message(&self, config: &BenchConfig, index: usize) -> Cow<'_, [u8]> {
+        match self {
+            Workload::Synthetic => Cow::Owned(generate_message(config, index)),
+            Workload::Generated(messages) => Cow::Borrowed(&messages[index]),
+            Workload::Corpus(files) => Cow::Borrowed(&files[index].data),
+            Workload::Mapped(mapped) => Cow::Borrowed(mapped.as_slice()),
+        }
+    }
+
+    /// Like [`Workload::message`], but a synthetic message is written into
+    /// `buffer` instead of a fresh allocation.
+    pub fn message_into<'a>(
+        &'a self,
+        config: &BenchConfig,
+        index: usize,
+        buffer: &'a mut Vec<u8>,
+    ) -> &'a [u8] {
+        match self {
+            Workload::Synthetic => {
+                fill_message(config, index, buffer);
+                buffer
+            }
+            Workload::Generated(messages) => &messages[index],
+            Workload::Corpus(files) => &files[index].data,
+            Workload::Mapped(mapped) => mapped.as_slice(),
+        }
+    }
+
+    /// Bytes hashed by one round over the whole message set.
+    pub fn bytes_per_round(&self, config: &BenchConfig) -> u64 {
+        match self {
+            Workload::Synthetic => (0..config.messages)
+                .map(|idx| message_length(config, idx) as u64)
+                .sum(),
+            Workload::Generated(messages) => messages.iter().map(|m| m.len() as u64).sum(),
+            Workload::Corpus(files) => files.iter().map(|file| file.data.len() as u64).sum(),
+            Workload::Mapped(mapped) => mapped.as_slice().len() as u64,
+        }
+    }
+}
+
+/// Length of synthetic message `index` of `config`.
+pub fn message_length(config: &BenchConfig, index: usize) -> usize {
+    Synthetic::from_config(config).length(index)
+}
+
+/// Build synthetic message `index` of `config`, by the rules in the module
+/// docs.
+pub fn generate_message(config: &BenchConfig, index: usize) -> Vec<u8> {
+    Synthetic::from_config(config).generate(index)
+}
+
+/// Overwrite `message` with message `index`, reusing its allocation once it
+/// has grown to the longest message.
+pub fn fill_message(config: &BenchConfig, index: usize, message: &mut Vec<u8>) {
+    Synthetic::from_config(config).fill(index, message);
+}
+
+pub(crate) const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Steele, Lea, and Flood's SplitMix64, chosen because it is a few lines
+/// in any language and has no platform-dependent behaviour.
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Load a corpus from `path`.
+///
+/// A directory contributes every regular file directly inside it, sorted by
+/// name. Any other path is read as a manifest: one input path per line,
+/// relative to the manifest's directory, with blank lines and `#` comments
+/// ignored.
+pub fn load_corpus(path: &Path) -> io::Result<Vec<CorpusFile>> {
+    let paths = if path.is_dir() {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                paths.push(entry.path());
+            }
+        }
+        paths.sort();
+        paths
+    } else {
+        let base = path.parent().unwrap_or(Path::new("."));
+        fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| base.join(line))
+            .collect::<Vec<PathBuf>>()
+    };
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let data = fs::read(&path).map_err(|err| {
+                io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+            })?;
+            Ok(CorpusFile {
+                path: path.display().to_string(),
+                data,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("keccak-bench-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Reference outputs for seed 1234567 from the SplitMix64 paper's C code.
+    #[test]
+    fn splitmix64_matches_reference_outputs() {
+        let mut rng = SplitMix64::new(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+        assert_eq!(rng.next_u64(), 9817491932198370423);
+    }
+
+    /// Seeded messages are reproducible, seed-dependent, and sized correctly.
+    #[test]
+    fn seeded_messages_are_reproducible() {
+        let mut config = BenchConfig {
+            seed: Some(42),
+            ..BenchConfig::default()
+        };
+        let first = generate_message(&config, 3);
+        assert_eq!(first.len(), message_length(&config, 3));
+        assert_eq!(first, generate_message(&config, 3));
+        assert_ne!(first[..8], generate_message(&config, 4)[..8]);
+        config.seed = Some(43);
+        assert_ne!(first, generate_message(&config, 3));
+    }
+
+    /// Each rule gives what the module docs specify; the seeded bytes and
+    /// distribution draws were computed with a Python port of the spec.
+    #[test]
+    fn generators_follow_the_spec() {
+        let stride = Lengths::Stride {
+            min_len: 10,
+            max_len: 20,
+            stride: 4,
+        };
+        let lengths: Vec<usize> = (0..4).map(|index| stride.length(index)).collect();
+        assert_eq!(lengths, [10, 14, 18, 11]);
+        assert_eq!(Lengths::Mixed.length(15), 128 << 10);
+        let dist = LengthDist::parse("trace", "68 1\n100-200 1\n").unwrap();
+        let draws = Lengths::Dist {
+            dist: &dist,
+            seed: 7,
+        };
+        let lengths: Vec<usize> = (0..8).map(|index| draws.length(index)).collect();
+        assert_eq!(lengths, [120, 143, 135, 167, 68, 152, 161, 179]);
+
+        let mut message = vec![0xaa; 100];
+        Bytes::Counter.fill(254, 4, &mut message);
+        assert_eq!(message, [254, 255, 0, 1]);
+        Bytes::Seeded(42).fill(3, 13, &mut message);
+        assert_eq!(hex::encode(&message), "74c9058bb4d345658fc006b931");
+
+        let config = BenchConfig {
+            seed: Some(42),
+            ..BenchConfig::default()
+        };
+        let synthetic = Synthetic::from_config(&config);
+        assert_eq!(synthetic.count(), config.messages);
+        assert_eq!(synthetic.generate(9), generate_message(&config, 9));
+    }
+
+    /// Any generator becomes a workload; corpus files are served as they
+    /// are.
+    #[test]
+    fn generators_become_workloads() {
+        let files = vec![
+            CorpusFile {
+                path: String::from("a"),
+                data: b"alpha".to_vec(),
+            },
+            CorpusFile {
+                path: String::from("b"),
+                data: Vec::new(),
+            },
+        ];
+        let corpus = Corpus(&files);
+        assert_eq!((corpus.count(), corpus.length(0)), (2, 5));
+        let workload = Workload::from_generator(&corpus);
+        let config = BenchConfig::default();
+        assert_eq!(workload.count(&config), 2);
+        assert_eq!(&*workload.message(&config, 0), b"alpha");
+        assert_eq!(workload.bytes_per_round(&config), 5);
+    }
+
+    /// Directory corpora are sorted by name and skip subdirectories.
+    #[test]
+    fn directory_corpus_is_sorted() {
+        let dir = scratch_dir("dir");
+        fs::write(dir.join("b.bin"), b"bb").unwrap();
+        fs::write(dir.join("a.bin"), b"a").unwrap();
+        fs::create_dir(dir.join("nested")).unwrap();
+
+        let files = load_corpus(&dir).unwrap();
+        let names: Vec<&[u8]> = files.iter().map(|file| file.data.as_slice()).collect();
+        assert_eq!(names, vec![&b"a"[..], &b"bb"[..]]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Manifest entries resolve relative to the manifest and skip comments.
+    #[test]
+    fn manifest_corpus_resolves_relative_paths() {
+        let dir = scratch_dir("manifest");
+        fs::write(dir.join("tx.bin"), b"tx").unwrap();
+        fs::write(dir.join("inputs.txt"), "# captured calldata\n\ntx.bin\n").unwrap();
+
+        let files = load_corpus(&dir.join("inputs.txt")).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].data, b"tx");
+        assert!(load_corpus(&dir.join("missing.txt")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+//! Extendable-output functions for `--xof`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+/// Which SHAKE variant to time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Xof {
+    Shake128,
+    Shake256,
+}
+
+impl FromStr for Xof {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "shake128" => Ok(Xof::Shake128),
+            "shake256" => Ok(Xof::Shake256),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Xof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.operation())
+    }
+}
+
+impl Xof {
+    /// The result `operation` for runs of this function.
+    pub fn operation(self) -> &'static str {
+        match self {
+            Xof::Shake128 => "shake128",
+            Xof::Shake256 => "shake256",
+        }
+    }
+}
+
+/// Squeeze `length` bytes from `squeeze` in 32-byte blocks and XOR them
+/// together (the last block zero-padded), so arbitrarily long output folds
+/// into a digest-sized value without allocating.
+pub fn fold(length: usize, mut squeeze: impl FnMut(&mut [u8])) -> [u8; 32] {
+    let mut folded = [0u8; 32];
+    let mut block = [0u8; 32];
+    let mut left = length;
+    while left > 0 {
+        let take = left.min(block.len());
+        block[take..].fill(0);
+        squeeze(&mut block[..take]);
+        for (out, byte) in folded.iter_mut().zip(&block) {
+            *out ^= byte;
+        }
+        left -= take;
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Folding XORs whole blocks and zero-pads the tail.
+    #[test]
+    fn fold_xors_blocks() {
+        let mut next = 0u8;
+        let folded = fold(40, |block| {
+            for byte in block {
+                next += 1;
+                *byte = next;
+            }
+        });
+        assert_eq!(folded[0], 1 ^ 33);
+        assert_eq!(folded[7], 8 ^ 40);
+        assert_eq!(folded[8], 9);
+        assert_eq!(fold(0, |_| unreachable!()), [0; 32]);
+    }
+}
@@ -0,0 +1,85 @@
+//! `bench aggregate`: per-group statistics over result files from many
+//! hosts.
+
+use std::process;
+
+use keccak256_rust_baseline::bench::aggregate::{aggregate, render, DEFAULT_KEYS};
+use keccak256_rust_baseline::bench::compare::load_records;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: bench aggregate <result.json>... [--by <key>[,<key>...]] [--baseline <implementation>] \
+         [--json] [--exclude-dirty]"
+    );
+    process::exit(2);
+}
+
+/// Entry point for `bench aggregate`; `args` excludes the subcommand name.
+pub fn run(args: Vec<String>) -> i32 {
+    let mut paths = Vec::new();
+    let mut keys: Option<String> = None;
+    let mut baseline: Option<String> = None;
+    let mut emit_json = false;
+    let mut exclude_dirty = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--by" => keys = Some(args.next().unwrap_or_else(|| usage())),
+            "--baseline" => baseline = Some(args.next().unwrap_or_else(|| usage())),
+            "--json" => emit_json = true,
+            "--exclude-dirty" => exclude_dirty = true,
+            flag if flag.starts_with("--") => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        usage();
+    }
+    let keys: Vec<&str> = match &keys {
+        Some(list) => list.split(',').filter(|key| !key.is_empty()).collect(),
+        None => DEFAULT_KEYS.to_vec(),
+    };
+    if keys.is_empty() {
+        usage();
+    }
+
+    let mut records = Vec::new();
+    for path in &paths {
+        match load_records(path) {
+            Ok(mut loaded) => records.append(&mut loaded),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return 2;
+            }
+        }
+    }
+    if exclude_dirty {
+        records.retain(|record| match record.dirty() {
+            Some(reason) => {
+                eprintln!("note: excluding {}: {}", record.implementation, reason);
+                false
+            }
+            None => true,
+        });
+    }
+    if records.is_empty() {
+        eprintln!("error: no results found");
+        return 2;
+    }
+    if let Some(name) = &baseline {
+        if !records.iter().any(|record| &record.implementation == name) {
+            eprintln!("error: baseline {:?} not found among the results", name);
+            return 2;
+        }
+    }
+
+    let groups = aggregate(&records, &keys, baseline.as_deref());
+    if emit_json {
+        let json = serde_json::to_string(&groups).expect("aggregate groups serialize to JSON");
+        println!("{}", json);
+    } else {
+        print!("{}", render(&groups, &keys));
+    }
+    0
+}
@@ -0,0 +1,132 @@
+//! `bench compare`: validate and rank result files from several harnesses.
+
+use std::process;
+
+use keccak256_rust_baseline::bench::compare::{load_records, validate};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct Row {
+    implementation: String,
+    seconds: f64,
+    hashes_per_second: f64,
+    speedup: f64,
+    checksum: Option<u64>,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: bench compare <result.json>... [--baseline <implementation>] [--json] [--allow-mismatch] \
+         [--exclude-dirty]"
+    );
+    process::exit(2);
+}
+
+/// Entry point for `bench compare`; `args` excludes the subcommand name.
+pub fn run(args: Vec<String>) -> i32 {
+    let mut paths = Vec::new();
+    let mut baseline: Option<String> = None;
+    let mut emit_json = false;
+    let mut allow_mismatch = false;
+    let mut exclude_dirty = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baseline" => baseline = Some(args.next().unwrap_or_else(|| usage())),
+            "--json" => emit_json = true,
+            "--allow-mismatch" => allow_mismatch = true,
+            "--exclude-dirty" => exclude_dirty = true,
+            flag if flag.starts_with("--") => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        usage();
+    }
+
+    let mut records = Vec::new();
+    for path in &paths {
+        match load_records(path) {
+            Ok(mut loaded) => records.append(&mut loaded),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return 2;
+            }
+        }
+    }
+    if exclude_dirty {
+        records.retain(|record| match record.dirty() {
+            Some(reason) => {
+                eprintln!("note: excluding {}: {}", record.implementation, reason);
+                false
+            }
+            None => true,
+        });
+    }
+    if records.is_empty() {
+        eprintln!("error: no results found");
+        return 2;
+    }
+
+    let problems = validate(&records);
+    for problem in &problems {
+        eprintln!(
+            "{}: {}",
+            if allow_mismatch { "warning" } else { "error" },
+            problem
+        );
+    }
+    if !problems.is_empty() && !allow_mismatch {
+        return 1;
+    }
+
+    let base = match &baseline {
+        Some(name) => match records.iter().find(|record| &record.implementation == name) {
+            Some(record) => record.clone(),
+            None => {
+                eprintln!("error: baseline {:?} not found among the results", name);
+                return 2;
+            }
+        },
+        None => records[0].clone(),
+    };
+
+    let mut rows: Vec<Row> = records
+        .iter()
+        .map(|record| Row {
+            implementation: record.implementation.clone(),
+            seconds: record.seconds,
+            hashes_per_second: record.hashes_per_second,
+            speedup: if base.hashes_per_second > 0.0 {
+                record.hashes_per_second / base.hashes_per_second
+            } else {
+                0.0
+            },
+            checksum: record.checksum,
+        })
+        .collect();
+    rows.sort_by(|a, b| b.hashes_per_second.total_cmp(&a.hashes_per_second));
+
+    if emit_json {
+        let json = serde_json::to_string(&rows).expect("comparison rows serialize to JSON");
+        println!("{}", json);
+    } else {
+        println!("baseline: {}", base.implementation);
+        println!("implementation | seconds | hashes/s | speedup | checksum");
+        println!("-------------- | ------- | -------- | ------- | --------");
+        for row in &rows {
+            println!(
+                "{} | {:.9} | {:.2} | {:.2}x | {}",
+                row.implementation,
+                row.seconds,
+                row.hashes_per_second,
+                row.speedup,
+                row.checksum
+                    .map(|checksum| checksum.to_string())
+                    .unwrap_or_else(|| String::from("-"))
+            );
+        }
+    }
+    0
+}
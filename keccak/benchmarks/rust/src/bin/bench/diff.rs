@@ -0,0 +1,159 @@
+//! `bench diff`: per-metric changes between two result files.
+
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::process;
+
+use keccak256_rust_baseline::bench::compare::parse_values;
+use keccak256_rust_baseline::bench::diff::{diff, Thresholds, Verdict};
+use keccak256_rust_baseline::bench::schema;
+use serde_json::Value;
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: bench diff <before.json> <after.json> [--threshold [METRIC=]F]... \
+         [--alpha P] [--color auto|always|never] [--json]"
+    );
+    process::exit(2);
+}
+
+fn load(path: &str) -> Result<Vec<Value>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let values = parse_values(&text, path)?;
+    for (index, value) in values.iter().enumerate() {
+        if let Some(problem) = schema::check(value).into_iter().next() {
+            return Err(format!("{}: record {}: {}", path, index + 1, problem));
+        }
+    }
+    Ok(values)
+}
+
+/// Sub-unit values (seconds) keep nanosecond digits; the rest get two.
+fn number(value: f64) -> String {
+    if value.abs() < 1.0 {
+        format!("{:.9}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+/// Entry point for `bench diff`; `args` excludes the subcommand name.
+pub fn run(args: Vec<String>) -> i32 {
+    let mut paths = Vec::new();
+    let mut thresholds = Thresholds::default();
+    let mut color: Option<bool> = None;
+    let mut emit_json = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threshold" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                if let Err(err) = thresholds.set(&value) {
+                    eprintln!("error: invalid value for --threshold: {}", err);
+                    return 2;
+                }
+            }
+            "--alpha" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                if let Err(err) = thresholds.set_alpha(&value) {
+                    eprintln!("error: invalid value for --alpha: {}", err);
+                    return 2;
+                }
+            }
+            "--color" => {
+                color = match args.next().as_deref() {
+                    Some("always") => Some(true),
+                    Some("never") => Some(false),
+                    Some("auto") => None,
+                    _ => usage(),
+                }
+            }
+            "--json" => emit_json = true,
+            flag if flag.starts_with("--") => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    let [before, after] = paths.as_slice() else {
+        usage();
+    };
+    let color =
+        color.unwrap_or_else(|| io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none());
+
+    let (before, after) = match (load(before), load(after)) {
+        (Ok(before), Ok(after)) => (before, after),
+        (Err(err), _) | (_, Err(err)) => {
+            eprintln!("error: {}", err);
+            return 2;
+        }
+    };
+    let diff = diff(&before, &after, &thresholds);
+    for name in &diff.unmatched {
+        eprintln!("warning: {}: no result to compare against", name);
+    }
+
+    if emit_json {
+        let json = serde_json::to_string(&diff).expect("diffs serialize to JSON");
+        println!("{}", json);
+        return 0;
+    }
+    println!("implementation | metric | before | after | change");
+    println!("-------------- | ------ | ------ | ----- | ------");
+    for change in &diff.changes {
+        let mark = match change.verdict {
+            Verdict::Improved => "improved",
+            Verdict::Regressed => "regressed",
+            Verdict::Unchanged => "",
+            Verdict::Noise => "noise",
+        };
+        let mut cell = format!("{:+.2}% {}", change.relative * 100.0, mark);
+        if let Some(p) = change.p_value {
+            cell = format!("{} (p={:.3})", cell.trim_end(), p);
+        }
+        cell.truncate(cell.trim_end().len());
+        if color && matches!(change.verdict, Verdict::Improved | Verdict::Regressed) {
+            let paint = if change.verdict == Verdict::Improved {
+                GREEN
+            } else {
+                RED
+            };
+            cell = format!("{}{}{}", paint, cell, RESET);
+        }
+        println!(
+            "{} | {} | {} | {} | {}",
+            change.implementation,
+            change.metric,
+            number(change.before),
+            number(change.after),
+            cell
+        );
+    }
+    println!(
+        "{} improved, {} regressed, {} within threshold, {} not significant (alpha {})",
+        diff.count(Verdict::Improved),
+        diff.count(Verdict::Regressed),
+        diff.count(Verdict::Unchanged),
+        diff.count(Verdict::Noise),
+        thresholds.alpha
+    );
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_rejects_records_failing_the_schema() {
+        let path = env::temp_dir().join(format!("bench-diff-{}.json", process::id()));
+        fs::write(&path, "{}").unwrap();
+        let err = load(path.to_str().unwrap()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(err.contains("record 1: missing field"), "{}", err);
+    }
+}
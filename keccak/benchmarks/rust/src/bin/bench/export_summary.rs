@@ -0,0 +1,99 @@
+//! `bench export-summary`: reduce result files to the headline JSON or
+//! an SVG badge.
+
+use std::fs;
+use std::process;
+
+use keccak256_rust_baseline::bench::compare::{load_records, validate};
+use keccak256_rust_baseline::bench::summary::{badge, summarize};
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: bench export-summary <result.json>... [--svg] [--out <path>] [--allow-mismatch] \
+         [--exclude-dirty]"
+    );
+    process::exit(2);
+}
+
+/// Entry point for `bench export-summary`; `args` excludes the
+/// subcommand name.
+pub fn run(args: Vec<String>) -> i32 {
+    let mut paths = Vec::new();
+    let mut svg = false;
+    let mut out: Option<String> = None;
+    let mut allow_mismatch = false;
+    let mut exclude_dirty = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--svg" => svg = true,
+            "--out" => out = Some(args.next().unwrap_or_else(|| usage())),
+            "--allow-mismatch" => allow_mismatch = true,
+            "--exclude-dirty" => exclude_dirty = true,
+            flag if flag.starts_with("--") => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        usage();
+    }
+
+    let mut records = Vec::new();
+    for path in &paths {
+        match load_records(path) {
+            Ok(mut loaded) => records.append(&mut loaded),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return 2;
+            }
+        }
+    }
+    if exclude_dirty {
+        records.retain(|record| match record.dirty() {
+            Some(reason) => {
+                eprintln!("note: excluding {}: {}", record.implementation, reason);
+                false
+            }
+            None => true,
+        });
+    }
+
+    // A ratio between different workloads is not worth publishing.
+    let problems = validate(&records);
+    for problem in &problems {
+        eprintln!(
+            "{}: {}",
+            if allow_mismatch { "warning" } else { "error" },
+            problem
+        );
+    }
+    if !problems.is_empty() && !allow_mismatch {
+        return 1;
+    }
+
+    let headline = match summarize(&records) {
+        Ok(headline) => headline,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return 2;
+        }
+    };
+    let text = if svg {
+        badge(&headline)
+    } else {
+        let mut json = serde_json::to_string(&headline).expect("headlines serialize to JSON");
+        json.push('\n');
+        json
+    };
+    match out {
+        Some(path) => {
+            if let Err(err) = fs::write(&path, text) {
+                eprintln!("error: cannot write {}: {}", path, err);
+                return 2;
+            }
+        }
+        None => print!("{}", text),
+    }
+    0
+}
@@ -0,0 +1,93 @@
+//! `bench kat`: run SHA-3 known-answer files in the NIST or XKCP layout
+//! against each backend.
+
+use std::path::Path;
+use std::process;
+
+use keccak256_rust_baseline::bench::backend::Backend;
+use keccak256_rust_baseline::kat::{self, RspFile};
+
+fn usage() -> ! {
+    eprintln!("usage: bench kat [--backend NAME] <file.rsp|KAT.txt>...");
+    process::exit(2);
+}
+
+/// Entry point for `bench kat`; `args` excludes the subcommand name.
+pub fn run(args: Vec<String>) -> i32 {
+    let mut backends: Vec<Backend> = kat::BACKENDS
+        .into_iter()
+        .filter(|backend| backend.is_available())
+        .collect();
+    let mut paths = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                let Some(backend) = args.next().and_then(|name| name.parse().ok()) else {
+                    eprintln!("error: --backend needs one of native, sha3");
+                    return 2;
+                };
+                backends = vec![backend];
+            }
+            flag if flag.starts_with("--") => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        usage();
+    }
+
+    let mut checked = 0;
+    let mut failed = 0;
+    for path in &paths {
+        let file = match RspFile::load(Path::new(path)) {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return 2;
+            }
+        };
+        for &backend in &backends {
+            let outcome = match file.check(backend) {
+                None => {
+                    eprintln!(
+                        "warning: {}: backend {} has no {}; skipped",
+                        path, backend, file.algorithm
+                    );
+                    continue;
+                }
+                Some(Err(err)) => {
+                    eprintln!("error: {}: {}", path, err);
+                    return 2;
+                }
+                Some(Ok(outcome)) => outcome,
+            };
+            checked += 1;
+            for failure in &outcome.failures {
+                println!("{} ({}): FAIL {}", path, backend, failure);
+            }
+            println!(
+                "{} ({} {}, {}): {} passed, {} failed, {} skipped",
+                path,
+                file.algorithm,
+                file.test,
+                backend,
+                outcome.passed,
+                outcome.failures.len(),
+                outcome.skipped
+            );
+            if !outcome.failures.is_empty() {
+                failed += 1;
+            }
+        }
+    }
+    if checked == 0 {
+        eprintln!("error: no file could be checked by the selected backends");
+        return 2;
+    }
+    if failed > 0 {
+        eprintln!("{} of {} file checks failed", failed, checked);
+        return 1;
+    }
+    0
+}
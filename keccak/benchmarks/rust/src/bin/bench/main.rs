@@ -0,0 +1,607 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
+use keccak256_rust_baseline::bench::backend::{self, Backend};
+use keccak256_rust_baseline::bench::chart::{self, ChartKind};
+use keccak256_rust_baseline::bench::gate::MinThroughput;
+use keccak256_rust_baseline::bench::lengths::LengthDist;
+use keccak256_rust_baseline::bench::output::{self, OutputFormat, TrialEvent};
+use keccak256_rust_baseline::bench::profile::Profile;
+use keccak256_rust_baseline::bench::target::{Target, TARGETS};
+use keccak256_rust_baseline::bench::workload::Workload;
+use keccak256_rust_baseline::bench::{
+    self, baseline, dump, golden, isolate, settings, verify, BenchConfig, RunOptions,
+};
+use keccak256_rust_baseline::parallel::dispatch::{self, Simd};
+use keccak256_rust_baseline::trace;
+
+mod aggregate;
+mod compare;
+mod diff;
+mod export_summary;
+mod kat;
+mod progress;
+mod replay;
+mod report;
+mod shm;
+mod validate;
+
+use progress::{Progress, Verbosity};
+
+const USAGE: &str = "\
+usage: bench [FLAGS]
+       bench <aggregate|compare|diff|export-summary|kat|replay|report|shm|validate> ...
+       bench dump-corpus <prefix> [workload flags]
+
+workload: --messages N --rounds N --min-len N --max-len N --stride N --warmup N
+          --trials N --seed N --corpus PATH --mmap PATH --length-dist PATH --by-length
+          --target NAME --xof N --xof-output N --sweep SPEC --batch N --chunk-size N
+          --threads N --duration S --soak S --soak-interval S --async MODE
+          --exclude-generation --include-generation --reuse-buffer --profile NAME
+          --config PATH --list-targets
+timing:   --warmup-tolerance F --warmup-timeout S --noise-threshold F --outlier-mad F
+          --latency-sample N --isolate --pin-core N --realtime --timer NAME
+backends: --backend LIST --simd NAME|auto --simd-compare
+output:   --json --format NAME --stream --output PATH --append --quiet --verbose
+          --label TEXT --tag KEY=VALUE --histogram PATH --chart KIND --chart-out PATH
+checks:   --checksum MODE --verify-out PATH --verify-in PATH --golden PATH
+          --min-throughput SPEC --baseline-dir DIR --save-baseline NAME
+          --check-against NAME --regression-threshold F
+          --help";
+
+/// Parse the value following `flag`, exiting with a usage error when it is
+/// missing or malformed.
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> T {
+    let Some(raw) = value else {
+        eprintln!("error: {} requires a value", flag);
+        process::exit(2);
+    };
+    match raw.parse() {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            eprintln!("error: invalid value for {}: {:?}", flag, raw);
+            process::exit(2);
+        }
+    }
+}
+
+/// Serve one `--isolate` trial: config JSON on stdin, measurement on stdout.
+fn isolated_trial() -> i32 {
+    let mut input = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut input) {
+        eprintln!("error: failed to read the trial config: {}", err);
+        return 2;
+    }
+    match isolate::child(&input) {
+        Ok(reply) => {
+            println!("{}", reply);
+            0
+        }
+        Err(err) => {
+            eprintln!("error: isolated trial: {}", err);
+            2
+        }
+    }
+}
+
+/// Run `--golden`: hash the fixed workload untimed and compare it with the
+/// digest file at `path`.
+fn golden_run(path: &str) -> i32 {
+    let expected = match verify::read(path) {
+        Ok(expected) => expected,
+        Err(err) => {
+            eprintln!("error: failed to read golden digests: {}", err);
+            return 2;
+        }
+    };
+    let problems = golden::check(&expected);
+    for problem in &problems {
+        eprintln!("error: {}", problem);
+    }
+    if !problems.is_empty() {
+        return 1;
+    }
+    println!("golden: {} digests match {}", expected.len(), path);
+    0
+}
+
+/// Parse a `--tag` value of the form `key=value`.
+fn parse_tag(value: Option<String>) -> (String, String) {
+    let raw: String = parse_value("--tag", value);
+    match raw.split_once('=') {
+        Some((key, value)) if !key.is_empty() => (key.to_string(), value.to_string()),
+        _ => {
+            eprintln!(
+                "error: invalid value for --tag: {:?} (expected key=value)",
+                raw
+            );
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    trace::init();
+    let mut label: Option<String> = None;
+    let mut tags = BTreeMap::new();
+    let mut backends = vec![Backend::TinyKeccak];
+    let mut format = OutputFormat::Table;
+    let mut config = BenchConfig::default();
+    let mut baseline_dir = String::from(baseline::DEFAULT_DIR);
+    let mut save_baseline: Option<String> = None;
+    let mut check_against: Option<String> = None;
+    let mut regression_threshold: f64 = 0.05;
+    let mut stream = false;
+    let mut output_path: Option<String> = None;
+    let mut append = false;
+    let mut verify_out: Option<String> = None;
+    let mut verify_in: Option<String> = None;
+    let mut histogram_path: Option<String> = None;
+    let mut verbosity = Verbosity::Normal;
+    let mut min_throughput: Option<MinThroughput> = None;
+    let mut chart_kind: Option<ChartKind> = None;
+    let mut chart_out: Option<String> = None;
+    let mut simd_compare = false;
+
+    let mut argv: Vec<String> = env::args().skip(1).collect();
+    let mut dump_prefix: Option<String> = None;
+    match argv.first().map(String::as_str) {
+        Some("aggregate") => process::exit(aggregate::run(argv[1..].to_vec())),
+        Some("compare") => process::exit(compare::run(argv[1..].to_vec())),
+        Some("diff") => process::exit(diff::run(argv[1..].to_vec())),
+        Some("export-summary") => process::exit(export_summary::run(argv[1..].to_vec())),
+        Some("kat") => process::exit(kat::run(argv[1..].to_vec())),
+        Some("replay") => process::exit(replay::run(argv[1..].to_vec())),
+        Some("report") => process::exit(report::run(argv[1..].to_vec())),
+        Some("shm") => process::exit(shm::run(argv[1..].to_vec())),
+        Some("validate") => process::exit(validate::run(argv[1..].to_vec())),
+        Some(isolate::SUBCOMMAND) => process::exit(isolated_trial()),
+        // Takes the usual workload flags, so it is parsed like a run.
+        Some("dump-corpus") => match argv.get(1) {
+            Some(prefix) if !prefix.starts_with("--") => {
+                dump_prefix = Some(prefix.clone());
+                argv.drain(..2);
+            }
+            _ => {
+                eprintln!("usage: bench dump-corpus <prefix> [workload flags]");
+                process::exit(2);
+            }
+        },
+        _ => {}
+    }
+
+    // Settings from a file go ahead of the command line, so flags given
+    // there override them.
+    if let Some(position) = argv.iter().position(|arg| arg == "--config") {
+        let path: String = parse_value("--config", argv.get(position + 1).cloned());
+        argv.drain(position..position + 2);
+        let file_args = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| settings::to_args(&text))
+            .unwrap_or_else(|err| {
+                eprintln!("error: {}: {}", path, err);
+                process::exit(2);
+            });
+        argv.splice(0..0, file_args);
+    }
+
+    // Apply the preset first so explicit flags override it regardless of
+    // their position on the command line.
+    if let Some(position) = argv.iter().position(|arg| arg == "--profile") {
+        let profile: Profile = parse_value("--profile", argv.get(position + 1).cloned());
+        profile.apply(&mut config);
+    }
+
+    let mut args = argv.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => format = OutputFormat::Json,
+            "--format" => format = parse_value(&arg, args.next()),
+            "--stream" => stream = true,
+            "--quiet" => verbosity = Verbosity::Quiet,
+            "--verbose" => verbosity = Verbosity::Verbose,
+            "--output" => output_path = Some(parse_value(&arg, args.next())),
+            "--append" => append = true,
+            "--label" => {
+                if let Some(value) = args.next() {
+                    label = Some(value);
+                }
+            }
+            "--tag" => {
+                let (key, value) = parse_tag(args.next());
+                tags.insert(key, value);
+            }
+            "--messages" => config.messages = parse_value(&arg, args.next()),
+            "--rounds" => config.rounds = parse_value(&arg, args.next()),
+            "--min-len" => config.min_len = parse_value(&arg, args.next()),
+            "--max-len" => config.max_len = parse_value(&arg, args.next()),
+            "--stride" => config.stride = parse_value(&arg, args.next()),
+            "--warmup" => config.warmup = parse_value(&arg, args.next()),
+            "--trials" => config.trials = parse_value(&arg, args.next()),
+            "--warmup-tolerance" => config.warmup_tolerance = parse_value(&arg, args.next()),
+            "--warmup-timeout" => config.warmup_timeout = parse_value(&arg, args.next()),
+            "--noise-threshold" => config.noise_threshold = parse_value(&arg, args.next()),
+            "--outlier-mad" => config.outlier_mad = parse_value(&arg, args.next()),
+            "--latency-sample" => config.latency_sample = parse_value(&arg, args.next()),
+            "--by-length" => config.by_length = true,
+            "--isolate" => config.isolate = true,
+            "--async" => config.async_mode = Some(parse_value(&arg, args.next())),
+            "--profile" => {
+                args.next();
+            }
+            "--corpus" => config.corpus = Some(parse_value(&arg, args.next())),
+            "--mmap" => config.mmap = Some(parse_value(&arg, args.next())),
+            "--seed" => config.seed = Some(parse_value(&arg, args.next())),
+            "--checksum" => config.checksum_mode = parse_value(&arg, args.next()),
+            "--histogram" => histogram_path = Some(parse_value(&arg, args.next())),
+            "--verify-out" => verify_out = Some(parse_value(&arg, args.next())),
+            "--verify-in" => verify_in = Some(parse_value(&arg, args.next())),
+            "--simd-compare" => simd_compare = true,
+            "--simd" => {
+                let value: String = parse_value(&arg, args.next());
+                let simd = match value.as_str() {
+                    "auto" => None,
+                    name => Some(parse_value::<Simd>(&arg, Some(name.to_string()))),
+                };
+                if let Err(err) = dispatch::force(simd) {
+                    eprintln!("error: --simd: {}", err);
+                    process::exit(2);
+                }
+                // `--isolate` children read the choice from the environment.
+                env::set_var(dispatch::ENV_VAR, value);
+            }
+            "--backend" => {
+                let value: String = parse_value(&arg, args.next());
+                backends = backend::parse_list(&value).unwrap_or_else(|err| {
+                    eprintln!("error: invalid value for --backend: {}", err);
+                    process::exit(2);
+                });
+            }
+            "--duration" => config.duration = Some(parse_value(&arg, args.next())),
+            "--soak" => config.soak = Some(parse_value(&arg, args.next())),
+            "--soak-interval" => config.soak_interval = parse_value(&arg, args.next()),
+            "--pin-core" => config.pin_core = Some(parse_value(&arg, args.next())),
+            "--realtime" => config.realtime = true,
+            "--timer" => config.timer = parse_value(&arg, args.next()),
+            "--chart" => chart_kind = Some(parse_value(&arg, args.next())),
+            "--chart-out" => chart_out = Some(parse_value(&arg, args.next())),
+            "--min-throughput" => min_throughput = Some(parse_value(&arg, args.next())),
+            "--length-dist" => {
+                let path: String = parse_value(&arg, args.next());
+                config.length_dist = Some(LengthDist::load(&path).unwrap_or_else(|err| {
+                    eprintln!("error: invalid length distribution: {}", err);
+                    process::exit(2);
+                }));
+            }
+            "--xof" => config.target = Target::xof(parse_value(&arg, args.next())),
+            "--target" => {
+                let name: String = parse_value(&arg, args.next());
+                config.target = name.parse().unwrap_or_else(|_| {
+                    let names: Vec<&str> = TARGETS.iter().map(|target| target.name()).collect();
+                    eprintln!(
+                        "error: unknown target {:?}; expected one of {}",
+                        name,
+                        names.join(", ")
+                    );
+                    process::exit(2);
+                });
+            }
+            "--golden" => {
+                let path: String = parse_value(&arg, args.next());
+                process::exit(golden_run(&path));
+            }
+            "--list-targets" => {
+                for target in TARGETS {
+                    println!("{:<17} {}", target.name(), target.description());
+                }
+                return;
+            }
+            "--xof-output" => config.xof_output = parse_value(&arg, args.next()),
+            "--sweep" => config.sweep = Some(parse_value(&arg, args.next())),
+            "--exclude-generation" => config.exclude_generation = true,
+            "--include-generation" => config.exclude_generation = false,
+            "--reuse-buffer" => {
+                config.reuse_buffer = true;
+                config.exclude_generation = false;
+            }
+            "--batch" => config.batch = Some(parse_value(&arg, args.next())),
+            "--chunk-size" => config.chunk_size = Some(parse_value(&arg, args.next())),
+            "--threads" => config.threads = parse_value(&arg, args.next()),
+            "--baseline-dir" => baseline_dir = parse_value(&arg, args.next()),
+            "--save-baseline" => save_baseline = Some(parse_value(&arg, args.next())),
+            "--check-against" => check_against = Some(parse_value(&arg, args.next())),
+            "--regression-threshold" => regression_threshold = parse_value(&arg, args.next()),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => {
+                eprintln!("error: unrecognized argument {:?}\n\n{}", arg, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    if let Err(err) = Simd::from_env() {
+        eprintln!("error: {}", err);
+        process::exit(2);
+    }
+    if simd_compare {
+        if !Backend::NativeSimd.is_available() {
+            eprintln!(
+                "error: --simd-compare: {}",
+                Backend::NativeSimd.requirement()
+            );
+            process::exit(2);
+        }
+        backends = vec![Backend::Native, Backend::NativeSimd];
+    }
+    for &backend in &backends {
+        let checked = BenchConfig {
+            backend,
+            ..config.clone()
+        };
+        if let Err(err) = checked.validate() {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    }
+    if backends.len() > 1 && (save_baseline.is_some() || check_against.is_some()) {
+        eprintln!("error: baselines track a single backend; pass one --backend");
+        process::exit(2);
+    }
+    if append && output_path.is_none() {
+        eprintln!("error: --append requires --output");
+        process::exit(2);
+    }
+    if regression_threshold.is_nan() || regression_threshold < 0.0 {
+        eprintln!("error: --regression-threshold must be a non-negative fraction");
+        process::exit(2);
+    }
+
+    if config.sweep.is_some()
+        && (verify_out.is_some()
+            || verify_in.is_some()
+            || histogram_path.is_some()
+            || save_baseline.is_some()
+            || check_against.is_some())
+    {
+        eprintln!(
+            "error: --sweep produces one result per size; it cannot be combined with \
+             --verify-out, --verify-in, --histogram, or baselines"
+        );
+        process::exit(2);
+    }
+
+    if let Some(prefix) = &dump_prefix {
+        if config.sweep.is_some() {
+            eprintln!("error: dump-corpus writes one workload; drop --sweep");
+            process::exit(2);
+        }
+        let workload = Workload::load(&mut config).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        });
+        match dump::write(&config, &workload, prefix) {
+            Ok(written) => Progress::new(verbosity, 0).note(format_args!(
+                "wrote {} messages ({} bytes) to {} and {}",
+                written.messages, written.bytes, written.data, written.manifest
+            )),
+            Err(err) => {
+                eprintln!("error: failed to write corpus: {}", err);
+                process::exit(2);
+            }
+        }
+        return;
+    }
+
+    let points = match config.sweep {
+        Some(sweep) => sweep.points(&config),
+        None => vec![config],
+    };
+    let trials = match points[0].soak {
+        Some(soak) => soak.trials(points[0].soak_interval, backends.len()),
+        None => points[0].trials,
+    };
+    let mut progress = Progress::new(verbosity, points.len() * trials * backends.len());
+    let soak_hours = points[0].soak.map(|soak| soak.hours);
+    let mut results = Vec::new();
+    for mut config in points {
+        let workload = Workload::load(&mut config).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        });
+
+        if verify_out.is_some() || verify_in.is_some() {
+            let entries = verify::compute(&config, &workload);
+            if let Some(path) = &verify_out {
+                if let Err(err) = verify::write(path, &entries) {
+                    eprintln!("error: failed to write digests: {}", err);
+                    process::exit(2);
+                }
+                progress.note(format_args!("wrote {} digests to {}", entries.len(), path));
+            }
+            if let Some(path) = &verify_in {
+                let expected = match verify::read(path) {
+                    Ok(expected) => expected,
+                    Err(err) => {
+                        eprintln!("error: failed to read digests: {}", err);
+                        process::exit(2);
+                    }
+                };
+                let problems = verify::diff(&expected, &entries);
+                if !problems.is_empty() {
+                    for problem in &problems {
+                        eprintln!("error: {}", problem);
+                    }
+                    process::exit(1);
+                }
+                progress.note(format_args!(
+                    "verified {} digests against {}",
+                    entries.len(),
+                    path
+                ));
+            }
+        }
+
+        progress.detail(format_args!(
+            "warming up {} backend(s) on {} messages",
+            backends.len(),
+            config.messages
+        ));
+        let options = RunOptions {
+            backends: backends.clone(),
+            label: label.clone(),
+            tags: tags.clone(),
+            histograms: histogram_path.is_some(),
+            on_trial: Some(Box::new(|event: &TrialEvent| {
+                if stream {
+                    output::print_trial(event);
+                }
+                // Soak samples are spread out over hours, so each one gets
+                // its own line instead of a progress redraw.
+                match (event.elapsed_seconds, soak_hours) {
+                    (Some(elapsed), Some(hours)) => progress.note(format_args!(
+                        "soak {:.2}/{} h: {} {:.2} hashes/s",
+                        elapsed / 3600.0,
+                        hours,
+                        event.implementation,
+                        event.hashes_per_second
+                    )),
+                    _ => progress.trial(event),
+                }
+            })),
+        };
+        let report = bench::run_with(config, &workload, options).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        });
+        for result in &report.results {
+            progress.detail(format_args!(
+                "{}: warmup {} rounds in {:.3} s, {} timed rounds per trial",
+                result.implementation,
+                result.warmup.rounds,
+                result.warmup.seconds,
+                result.config.rounds
+            ));
+        }
+        if let Some(path) = &histogram_path {
+            if let Err(err) = report.write_histograms(path) {
+                eprintln!("error: failed to write histogram: {}", err);
+                process::exit(2);
+            }
+        }
+        if let Some((first, other)) = report.checksum_mismatch() {
+            eprintln!(
+                "error: checksum mismatch: {} reported {} but {} reported {}",
+                first.implementation, first.checksum, other.implementation, other.checksum
+            );
+            process::exit(1);
+        }
+        results.extend(report.results);
+    }
+
+    match &output_path {
+        Some(path) => {
+            if let Err(err) = output::write_file(Path::new(path), format, &results, append) {
+                eprintln!("error: failed to write results: {}", err);
+                process::exit(2);
+            }
+        }
+        None => output::print(format, &results),
+    }
+
+    if let Some(kind) = chart_kind {
+        let rendered = chart::render(kind, &chart::series(&results));
+        match (&chart_out, kind) {
+            (None, ChartKind::Ascii) => eprint!("{}", rendered),
+            (path, _) => {
+                let path = path.as_deref().unwrap_or("throughput.svg");
+                if let Err(err) = fs::write(path, rendered) {
+                    eprintln!("error: failed to write chart {}: {}", path, err);
+                    process::exit(2);
+                }
+                progress.note(format_args!("wrote chart to {}", path));
+            }
+        }
+    }
+
+    if simd_compare {
+        // One generic/vectorized pair per sweep size, in backend order.
+        for pair in results.chunks(2) {
+            let (generic, simd) = (&pair[0], &pair[1]);
+            let size = match simd.config.sweep {
+                Some(_) => format!(" at {} bytes", simd.config.max_len),
+                None => String::new(),
+            };
+            progress.note(format_args!(
+                "{} runs at {:.2}x the throughput of {}{}",
+                simd.implementation,
+                simd.hashes_per_second / generic.hashes_per_second,
+                generic.implementation,
+                size
+            ));
+        }
+    }
+
+    if let Some(floor) = min_throughput {
+        let failures: Vec<String> = results
+            .iter()
+            .filter_map(|result| floor.check(result))
+            .collect();
+        if !failures.is_empty() {
+            for failure in &failures {
+                eprintln!("error: {}", failure);
+            }
+            process::exit(1);
+        }
+    }
+
+    let result = &results[0];
+    let baseline_dir = Path::new(&baseline_dir);
+    if let Some(name) = &save_baseline {
+        match baseline::save(baseline_dir, name, result) {
+            Ok(path) => progress.note(format_args!(
+                "saved baseline {:?} to {}",
+                name,
+                path.display()
+            )),
+            Err(err) => {
+                eprintln!("error: failed to save baseline: {}", err);
+                process::exit(2);
+            }
+        }
+    }
+    if let Some(name) = &check_against {
+        let verdict = baseline::load(baseline_dir, name).and_then(|stored| {
+            let current = bench::compare::Record::from_result(result);
+            baseline::check(&stored, &current, regression_threshold)
+        });
+        match verdict {
+            Ok(baseline::Verdict::Pass { change }) => progress.note(format_args!(
+                "baseline {:?}: throughput {:+.2}% (threshold -{:.2}%)",
+                name,
+                change * 100.0,
+                regression_threshold * 100.0
+            )),
+            Ok(baseline::Verdict::Regression { change }) => {
+                eprintln!(
+                    "error: regression against baseline {:?}: throughput {:+.2}% exceeds -{:.2}%",
+                    name,
+                    change * 100.0,
+                    regression_threshold * 100.0
+                );
+                process::exit(1);
+            }
+            Err(err) => {
+                eprintln!("error: cannot check against baseline {:?}: {}", name, err);
+                process::exit(2);
+            }
+        }
+    }
+}
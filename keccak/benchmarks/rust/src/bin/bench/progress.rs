@@ -0,0 +1,110 @@
+//! Progress and status messages on stderr, so stdout stays machine-readable.
+
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+
+use keccak256_rust_baseline::bench::output::TrialEvent;
+
+/// How much the binary says on stderr. Errors and warnings are always shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Errors and warnings only.
+    Quiet,
+    /// Status notes, plus a live progress line when stderr is a terminal.
+    Normal,
+    /// A line per trial, whether or not stderr is a terminal.
+    Verbose,
+}
+
+/// Tracks completed trials across all backends to estimate time remaining.
+pub struct Progress {
+    verbosity: Verbosity,
+    /// Redraw one line in place instead of printing a line per trial.
+    live: bool,
+    total: usize,
+    done: usize,
+    /// Timed seconds of the finished trials.
+    seconds: f64,
+}
+
+impl Progress {
+    pub fn new(verbosity: Verbosity, total: usize) -> Progress {
+        Progress {
+            verbosity,
+            live: verbosity == Verbosity::Normal && io::stderr().is_terminal(),
+            total,
+            done: 0,
+            seconds: 0.0,
+        }
+    }
+
+    /// Print a status message unless quiet.
+    pub fn note(&self, message: impl fmt::Display) {
+        if self.verbosity != Verbosity::Quiet {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// Print a message only when verbose.
+    pub fn detail(&self, message: impl fmt::Display) {
+        if self.verbosity == Verbosity::Verbose {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// Report one finished trial. The estimate uses timed seconds only, so
+    /// warmup and calibration do not skew it.
+    pub fn trial(&mut self, event: &TrialEvent) {
+        self.done += 1;
+        self.seconds += event.seconds;
+        let line = format!(
+            "[{}/{}] {} trial {}/{}: {:.6} s, {:.2} hashes/s, ETA {:.1} s",
+            self.done,
+            self.total,
+            event.implementation,
+            event.trial + 1,
+            event.trials,
+            event.seconds,
+            event.hashes_per_second,
+            eta(
+                self.seconds,
+                self.done,
+                self.total.saturating_sub(self.done)
+            )
+        );
+        if self.live {
+            // Erase the line after the last trial so warnings that follow
+            // start on a clean line.
+            if self.done < self.total {
+                eprint!("\r\x1b[2K{}", line);
+            } else {
+                eprint!("\r\x1b[2K");
+            }
+            let _ = io::stderr().flush();
+        } else if self.verbosity == Verbosity::Verbose {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+/// Seconds left for `remaining` trials, extrapolated from `done` trials that
+/// took `elapsed` seconds in total.
+fn eta(elapsed: f64, done: usize, remaining: usize) -> f64 {
+    if done == 0 {
+        return 0.0;
+    }
+    elapsed / done as f64 * remaining as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The estimate scales the average trial time by the trials left.
+    #[test]
+    fn eta_extrapolates_average_trial_time() {
+        assert_eq!(eta(3.0, 3, 2), 2.0);
+        assert_eq!(eta(5.0, 2, 0), 0.0);
+        assert_eq!(eta(0.0, 0, 4), 0.0);
+    }
+}
@@ -0,0 +1,81 @@
+//! `bench replay`: recompute recorded checksums without timing anything.
+
+use std::fs;
+use std::process;
+
+use keccak256_rust_baseline::bench::compare;
+use keccak256_rust_baseline::bench::replay;
+
+fn usage() -> ! {
+    eprintln!("usage: bench replay <result.json>...");
+    process::exit(2);
+}
+
+/// Entry point for `bench replay`; `args` excludes the subcommand name.
+pub fn run(args: Vec<String>) -> i32 {
+    if args.is_empty() || args.iter().any(|arg| arg.starts_with("--")) {
+        usage();
+    }
+
+    let mut replayed = 0;
+    let mut mismatched = 0;
+    let mut skipped = 0;
+    for path in &args {
+        let values = match fs::read_to_string(path)
+            .map_err(|err| format!("{}: {}", path, err))
+            .and_then(|text| compare::parse_values(&text, path))
+        {
+            Ok(values) => values,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return 2;
+            }
+        };
+        for (index, value) in values.iter().enumerate() {
+            let replay = match replay::replay(value) {
+                Ok(replay) => replay,
+                Err(err) => {
+                    eprintln!("warning: {}: record {}: skipped: {}", path, index + 1, err);
+                    skipped += 1;
+                    continue;
+                }
+            };
+            replayed += 1;
+            if replay.matches() {
+                println!(
+                    "{}: record {} ({}): checksum {} matches",
+                    path,
+                    index + 1,
+                    replay.implementation,
+                    replay.recorded
+                );
+            } else {
+                mismatched += 1;
+                println!(
+                    "{}: record {} ({}): checksum MISMATCH: recorded {}, replayed {}",
+                    path,
+                    index + 1,
+                    replay.implementation,
+                    replay.recorded,
+                    replay.replayed
+                );
+            }
+        }
+    }
+    if replayed == 0 {
+        eprintln!("error: no record could be replayed");
+        return 2;
+    }
+    if mismatched > 0 {
+        eprintln!(
+            "{} of {} replayed records changed checksum; hashing behaviour differs from the recording",
+            mismatched, replayed
+        );
+        return 1;
+    }
+    eprintln!(
+        "{} records replayed, all checksums match ({} skipped)",
+        replayed, skipped
+    );
+    0
+}
@@ -0,0 +1,49 @@
+//! `bench report`: render result files as a markdown section.
+
+use std::process;
+
+use keccak256_rust_baseline::bench::compare;
+use keccak256_rust_baseline::bench::report::render;
+
+fn usage() -> ! {
+    eprintln!("usage: bench report <result.json>... [--baseline <implementation>]");
+    process::exit(2);
+}
+
+/// Entry point for `bench report`; `args` excludes the subcommand name.
+pub fn run(args: Vec<String>) -> i32 {
+    let mut paths = Vec::new();
+    let mut baseline: Option<String> = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baseline" => baseline = Some(args.next().unwrap_or_else(|| usage())),
+            flag if flag.starts_with("--") => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        usage();
+    }
+
+    let mut records = Vec::new();
+    for path in &paths {
+        match compare::load_records(path) {
+            Ok(mut loaded) => records.append(&mut loaded),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return 2;
+            }
+        }
+    }
+    match render(&records, baseline.as_deref()) {
+        Ok(markdown) => {
+            print!("{}", markdown);
+            0
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            2
+        }
+    }
+}
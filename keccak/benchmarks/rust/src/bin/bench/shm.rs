@@ -0,0 +1,53 @@
+//! `bench shm`: answer digest requests through a shared-memory region.
+
+use std::process;
+
+use keccak256_rust_baseline::bench::backend::Backend;
+use keccak256_rust_baseline::shm::{self, Region};
+
+fn usage() -> ! {
+    eprintln!("usage: bench shm --name NAME [--backend NAME]");
+    process::exit(2);
+}
+
+/// Entry point for `bench shm`; `args` excludes the subcommand name.
+pub fn run(args: Vec<String>) -> i32 {
+    let mut name = None;
+    let mut backend = Backend::Native;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" => name = Some(args.next().unwrap_or_else(|| usage())),
+            "--backend" => {
+                let Some(parsed) = args.next().and_then(|name| name.parse::<Backend>().ok()) else {
+                    eprintln!("error: --backend needs the name of a backend");
+                    return 2;
+                };
+                if !parsed.is_available() {
+                    eprintln!("error: {}", parsed.requirement());
+                    return 2;
+                }
+                backend = parsed;
+            }
+            _ => usage(),
+        }
+    }
+    let Some(name) = name else { usage() };
+    let region = match Region::open(&name) {
+        Ok(region) => region,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return 2;
+        }
+    };
+    match shm::serve(&region, backend) {
+        Ok(served) => {
+            eprintln!("served {} requests from {}", served, name);
+            0
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            1
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! `bench validate`: check result files against the result schema.
+
+use std::fs;
+use std::process;
+
+use keccak256_rust_baseline::bench::compare;
+use keccak256_rust_baseline::bench::schema;
+
+fn usage() -> ! {
+    eprintln!("usage: bench validate <result.json>...");
+    process::exit(2);
+}
+
+/// Entry point for `bench validate`; `args` excludes the subcommand name.
+pub fn run(args: Vec<String>) -> i32 {
+    if args.is_empty() || args.iter().any(|arg| arg.starts_with("--")) {
+        usage();
+    }
+
+    let mut records = 0;
+    let mut invalid = 0;
+    for path in &args {
+        let values = match fs::read_to_string(path)
+            .map_err(|err| format!("{}: {}", path, err))
+            .and_then(|text| compare::parse_values(&text, path))
+        {
+            Ok(values) => values,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return 2;
+            }
+        };
+        for (index, value) in values.iter().enumerate() {
+            records += 1;
+            let problems = schema::check(value);
+            if !problems.is_empty() {
+                invalid += 1;
+            }
+            for problem in problems {
+                eprintln!("error: {}: record {}: {}", path, index + 1, problem);
+            }
+        }
+    }
+    if invalid > 0 {
+        eprintln!("{} of {} records invalid", invalid, records);
+        return 1;
+    }
+    eprintln!(
+        "{} records valid (schema version {})",
+        records,
+        schema::SCHEMA_VERSION
+    );
+    0
+}
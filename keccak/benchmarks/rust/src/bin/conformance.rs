@@ -0,0 +1,289 @@
+//! Run every shared hash vector against external implementations and
+//! print a compatibility matrix.
+//!
+//! Each `--impl NAME=TEMPLATE` is a command template split on spaces, in
+//! which `{algorithm}` and `{output_len}` (bytes) are replaced per vector.
+//! The command reads the message as hex on stdin and prints the output as
+//! hex on stdout; exit status 3 means it does not implement the
+//! algorithm. This crate's own answers always form the first column.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+use serde::Serialize;
+
+use keccak256_rust_baseline::vectors::{self, VectorFile, VECTOR_DIR};
+
+/// The exit status an implementation uses for an algorithm it lacks.
+const UNSUPPORTED: i32 = 3;
+
+fn usage() -> ! {
+    eprintln!("usage: conformance [--impl NAME=TEMPLATE]... [--json] [FILE|DIR]...");
+    eprintln!();
+    eprintln!("  --impl NAME=TEMPLATE  an implementation to run, e.g.");
+    eprintln!("                        'py=python3 tools/hex.py {{algorithm}} {{output_len}}'");
+    eprintln!("  --json                print the matrix as JSON instead of a table");
+    eprintln!();
+    eprintln!("Without files, runs every file in {}.", VECTOR_DIR);
+    process::exit(2);
+}
+
+struct Implementation {
+    name: String,
+    /// `None` for this crate.
+    template: Option<Vec<String>>,
+}
+
+enum Answer {
+    Output(String),
+    Unsupported,
+    Error(String),
+}
+
+impl Implementation {
+    fn answer(&self, algorithm: &str, message: &[u8], output_len: usize) -> Answer {
+        let Some(template) = &self.template else {
+            return match vectors::digest(algorithm, message, output_len) {
+                Some(output) => Answer::Output(hex::encode(output)),
+                None => Answer::Unsupported,
+            };
+        };
+        let args: Vec<String> = template
+            .iter()
+            .map(|arg| {
+                arg.replace("{algorithm}", algorithm)
+                    .replace("{output_len}", &output_len.to_string())
+            })
+            .collect();
+        let mut child = match Command::new(&args[0])
+            .args(&args[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => return Answer::Error(format!("cannot run {}: {}", args[0], err)),
+        };
+        let input = format!("{}\n", hex::encode(message));
+        // A command that exits without reading is judged by its status.
+        match child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(input.as_bytes())
+        {
+            Err(err) if err.kind() != ErrorKind::BrokenPipe => {
+                return Answer::Error(format!("cannot write the message: {}", err))
+            }
+            _ => {}
+        }
+        let output = match child.wait_with_output() {
+            Ok(output) => output,
+            Err(err) => return Answer::Error(format!("{} failed: {}", args[0], err)),
+        };
+        match output.status.code() {
+            Some(0) => match String::from_utf8(output.stdout) {
+                Ok(text) => Answer::Output(text.trim().to_ascii_lowercase()),
+                Err(_) => Answer::Error(String::from("the output is not UTF-8")),
+            },
+            Some(UNSUPPORTED) => Answer::Unsupported,
+            _ => Answer::Error(format!("{} exited with {}", args[0], output.status)),
+        }
+    }
+}
+
+/// One implementation's results for one file and algorithm.
+#[derive(Default, Serialize)]
+struct Cell {
+    passed: usize,
+    failed: usize,
+    unsupported: usize,
+    failures: Vec<String>,
+}
+
+impl Cell {
+    fn label(&self) -> String {
+        let total = self.passed + self.failed + self.unsupported;
+        if self.unsupported == total {
+            String::from("unsupported")
+        } else if self.failed == 0 {
+            format!("ok {}/{}", self.passed, total)
+        } else {
+            format!("FAIL {}/{}", self.passed, total)
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Row {
+    file: String,
+    algorithm: String,
+    vectors: usize,
+    /// Keyed by implementation name.
+    results: BTreeMap<String, Cell>,
+}
+
+fn load(args: &[String]) -> Result<Vec<(PathBuf, VectorFile)>, String> {
+    if args.is_empty() {
+        return VectorFile::load_dir(Path::new(VECTOR_DIR));
+    }
+    let mut files = Vec::new();
+    for arg in args {
+        let path = Path::new(arg);
+        if path.is_dir() {
+            files.extend(VectorFile::load_dir(path)?);
+        } else {
+            files.push((path.to_path_buf(), VectorFile::load(path)?));
+        }
+    }
+    Ok(files)
+}
+
+fn parse_impl(value: &str) -> Implementation {
+    let Some((name, template)) = value.split_once('=') else {
+        eprintln!("error: --impl needs NAME=TEMPLATE, got {:?}", value);
+        process::exit(2);
+    };
+    let template: Vec<String> = template.split_whitespace().map(String::from).collect();
+    if name.is_empty() || name == "rust" || template.is_empty() {
+        eprintln!("error: --impl needs a name other than rust and a command");
+        process::exit(2);
+    }
+    Implementation {
+        name: name.to_string(),
+        template: Some(template),
+    }
+}
+
+fn main() {
+    let mut implementations = vec![Implementation {
+        name: String::from("rust"),
+        template: None,
+    }];
+    let mut json = false;
+    let mut inputs = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--impl" => implementations.push(parse_impl(&args.next().unwrap_or_else(|| usage()))),
+            "--json" => json = true,
+            "-h" | "--help" => usage(),
+            flag if flag.starts_with("--") => usage(),
+            _ => inputs.push(arg),
+        }
+    }
+    let files = match load(&inputs) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    };
+
+    let mut rows: Vec<Row> = Vec::new();
+    for (path, file) in &files {
+        let file_name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        let mut by_algorithm: BTreeMap<&str, Row> = BTreeMap::new();
+        for vector in &file.vectors {
+            let message = match vector.input.bytes() {
+                Ok(message) => message,
+                Err(err) => {
+                    eprintln!("error: {}:{}: {}", file_name, vector.name, err);
+                    process::exit(2);
+                }
+            };
+            for (algorithm, expected) in &vector.expected {
+                let row = by_algorithm.entry(algorithm).or_insert_with(|| Row {
+                    file: file_name.clone(),
+                    algorithm: algorithm.clone(),
+                    vectors: 0,
+                    results: BTreeMap::new(),
+                });
+                row.vectors += 1;
+                for implementation in &implementations {
+                    let cell = row.results.entry(implementation.name.clone()).or_default();
+                    match implementation.answer(algorithm, &message, expected.len() / 2) {
+                        Answer::Output(got) if got == *expected => cell.passed += 1,
+                        Answer::Output(got) => {
+                            cell.failed += 1;
+                            cell.failures.push(format!(
+                                "{}: expected {}, got {}",
+                                vector.name, expected, got
+                            ));
+                        }
+                        Answer::Unsupported => cell.unsupported += 1,
+                        Answer::Error(err) => {
+                            cell.failed += 1;
+                            cell.failures.push(format!("{}: {}", vector.name, err));
+                        }
+                    }
+                }
+            }
+        }
+        rows.extend(by_algorithm.into_values());
+    }
+
+    let failed = rows
+        .iter()
+        .flat_map(|row| row.results.values())
+        .any(|cell| cell.failed > 0);
+    if json {
+        let names: Vec<&str> = implementations.iter().map(|i| i.name.as_str()).collect();
+        let report = serde_json::json!({ "implementations": names, "rows": rows });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("reports serialize")
+        );
+    } else {
+        print_table(&implementations, &rows);
+    }
+    process::exit(if failed { 1 } else { 0 });
+}
+
+fn print_table(implementations: &[Implementation], rows: &[Row]) {
+    let mut header = vec![String::from("vectors"), String::from("count")];
+    header.extend(implementations.iter().map(|i| i.name.clone()));
+    let mut lines = vec![header];
+    for row in rows {
+        let mut line = vec![
+            format!("{}:{}", row.file, row.algorithm),
+            row.vectors.to_string(),
+        ];
+        line.extend(implementations.iter().map(|i| {
+            row.results
+                .get(&i.name)
+                .map_or_else(String::new, Cell::label)
+        }));
+        lines.push(line);
+    }
+    let widths: Vec<usize> = (0..lines[0].len())
+        .map(|column| {
+            lines
+                .iter()
+                .map(|line| line[column].len())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    for line in &lines {
+        let cells: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+    for row in rows {
+        for (name, cell) in &row.results {
+            for failure in &cell.failures {
+                println!("FAIL {} {}:{} {}", name, row.file, row.algorithm, failure);
+            }
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Run the byte-order known-answer checks and exit 1 if any fails.
+//!
+//! Built only with the `endian-check` feature, and without dev-dependencies,
+//! so it cross-compiles to a big-endian target and runs under emulation.
+//! See `keccak256_rust_baseline::byte_order`.
+
+use std::process;
+
+use keccak256_rust_baseline::byte_order;
+
+fn main() {
+    let failures = byte_order::check();
+    let order = if cfg!(target_endian = "big") {
+        "big"
+    } else {
+        "little"
+    };
+    for failure in &failures {
+        eprintln!("FAIL {}", failure);
+    }
+    if !failures.is_empty() {
+        eprintln!(
+            "{} check(s) failed on this {}-endian host",
+            failures.len(),
+            order
+        );
+        process::exit(1);
+    }
+    println!("all byte-order checks passed on this {}-endian host", order);
+}
@@ -0,0 +1,191 @@
+//! Run the Ethereum conformance vectors against this crate or an external
+//! implementation, and print a pass/fail JSON report.
+//!
+//! With `--exec`, the command is run once per vector with the file's kind
+//! (`address`, `eip55`, `eip191`, `eip155`) as its last argument and the
+//! vector's `input` object as JSON on stdin, and must print its answer on
+//! stdout. The vector format is documented in
+//! `keccak256_rust_baseline::ethereum`.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use keccak256_rust_baseline::ethereum::{EthVectorFile, Kind, ETHEREUM_DIR};
+
+fn usage() -> ! {
+    eprintln!("usage: eth-vectors [--exec COMMAND] [--report PATH] [FILE|DIR]...");
+    eprintln!();
+    eprintln!("  --exec COMMAND  answer with COMMAND (split on spaces) instead of this crate");
+    eprintln!("  --report PATH   write the JSON report to PATH instead of stdout");
+    eprintln!();
+    eprintln!("Without files, runs every file in {}.", ETHEREUM_DIR);
+    process::exit(2);
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Fail,
+    Error,
+}
+
+#[derive(Serialize)]
+struct Outcome {
+    file: String,
+    kind: Kind,
+    name: String,
+    status: Status,
+    expected: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    got: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    implementation: String,
+    passed: usize,
+    failed: usize,
+    errors: usize,
+    results: Vec<Outcome>,
+}
+
+/// The external implementation's answer for one vector.
+fn execute(command: &[String], kind: Kind, input: &Value) -> Result<String, String> {
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .arg(kind.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("cannot run {}: {}", command[0], err))?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.to_string().as_bytes())
+        .map_err(|err| format!("cannot write the input: {}", err))?;
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("{} failed: {}", command[0], err))?;
+    if !output.status.success() {
+        return Err(format!("{} exited with {}", command[0], output.status));
+    }
+    String::from_utf8(output.stdout)
+        .map(|text| text.trim().to_string())
+        .map_err(|_| String::from("the answer is not UTF-8"))
+}
+
+fn paths(args: &[String]) -> Result<Vec<(PathBuf, EthVectorFile)>, String> {
+    if args.is_empty() {
+        return EthVectorFile::load_dir(Path::new(ETHEREUM_DIR));
+    }
+    let mut files = Vec::new();
+    for arg in args {
+        let path = Path::new(arg);
+        if path.is_dir() {
+            files.extend(EthVectorFile::load_dir(path)?);
+        } else {
+            files.push((path.to_path_buf(), EthVectorFile::load(path)?));
+        }
+    }
+    Ok(files)
+}
+
+fn main() {
+    let mut exec = None;
+    let mut report_path = None;
+    let mut inputs = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--exec" => exec = Some(args.next().unwrap_or_else(|| usage())),
+            "--report" => report_path = Some(args.next().unwrap_or_else(|| usage())),
+            "-h" | "--help" => usage(),
+            flag if flag.starts_with("--") => usage(),
+            _ => inputs.push(arg),
+        }
+    }
+    let command: Option<Vec<String>> = exec
+        .as_deref()
+        .map(|exec| exec.split_whitespace().map(String::from).collect());
+    if command.as_ref().is_some_and(|command| command.is_empty()) {
+        usage();
+    }
+    let files = match paths(&inputs) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    };
+
+    let mut report = Report {
+        implementation: exec.unwrap_or_else(|| String::from("rust baseline")),
+        passed: 0,
+        failed: 0,
+        errors: 0,
+        results: Vec::new(),
+    };
+    for (path, file) in &files {
+        let file_name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        for vector in &file.vectors {
+            let answer = match &command {
+                Some(command) => execute(command, file.kind, &vector.input),
+                None => file.kind.compute(&vector.input),
+            };
+            let (status, got, error) = match answer {
+                Ok(got) if file.kind.matches(&vector.expected, &got) => {
+                    report.passed += 1;
+                    (Status::Pass, Some(got), None)
+                }
+                Ok(got) => {
+                    report.failed += 1;
+                    (Status::Fail, Some(got), None)
+                }
+                Err(err) => {
+                    report.errors += 1;
+                    (Status::Error, None, Some(err))
+                }
+            };
+            report.results.push(Outcome {
+                file: file_name.clone(),
+                kind: file.kind,
+                name: vector.name.clone(),
+                status,
+                expected: vector.expected.clone(),
+                got,
+                error,
+            });
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&report).expect("reports serialize");
+    match &report_path {
+        Some(path) => {
+            if let Err(err) = fs::write(path, format!("{}\n", json)) {
+                eprintln!("error: cannot write {}: {}", path, err);
+                process::exit(2);
+            }
+        }
+        None => println!("{}", json),
+    }
+    eprintln!(
+        "{}: {} passed, {} failed, {} errors",
+        report.implementation, report.passed, report.failed, report.errors
+    );
+    if report.failed > 0 || report.errors > 0 {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,59 @@
+//! Write the secp256k1 generator table to a file, for embedding with
+//! `include_bytes!` or mapping at startup instead of building it.
+//! See `keccak256_rust_baseline::fixed_base`.
+
+use std::env;
+use std::fs;
+use std::process;
+
+use keccak256_rust_baseline::fixed_base::{GeneratorTable, DEFAULT_WINDOW_BITS};
+
+const USAGE: &str = "usage: generator-table [--window BITS] OUT
+
+Writes the table of generator multiples for BITS-bit windows (1 to 8,
+default 4) to OUT.";
+
+fn main() {
+    let mut window_bits = DEFAULT_WINDOW_BITS;
+    let mut out = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--window" => {
+                let Some(bits) = args
+                    .next()
+                    .and_then(|bits| bits.parse().ok())
+                    .filter(|bits| (1..=8).contains(bits))
+                else {
+                    eprintln!("generator-table: --window needs 1 to 8");
+                    process::exit(2);
+                };
+                window_bits = bits;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            path if out.is_none() && !path.starts_with('-') => out = Some(path.to_string()),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    let Some(out) = out else {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    };
+    let bytes = GeneratorTable::compute(window_bits).to_bytes();
+    if let Err(err) = fs::write(&out, &bytes) {
+        eprintln!("generator-table: cannot write {}: {}", out, err);
+        process::exit(1);
+    }
+    println!(
+        "wrote {} bytes ({}-bit windows) to {}",
+        bytes.len(),
+        window_bits,
+        out
+    );
+}
@@ -0,0 +1,377 @@
+//! `keccak256sum`: print or check Keccak-256 digests, like `sha256sum`.
+//!
+//! Each file (or stdin, for `-` or no arguments) is hashed as a stream and
+//! printed as `<hex digest>  <name>`. Names containing a backslash or a
+//! newline are escaped and the line prefixed with `\`, as coreutils does,
+//! so every line can be parsed back. `-c` reads such lines back and
+//! checks each named file, exiting 1 if any did not match or could not be
+//! read.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::process;
+
+use keccak256_rust_baseline::digest::HashAlgorithm;
+use keccak256_rust_baseline::to_hex_string;
+
+const USAGE: &str = "usage: keccak256sum [--algorithm NAME] [FILE]...
+       keccak256sum -c [--quiet | --status] [--strict] [--algorithm NAME] [FILE]...
+
+With no FILE, or when FILE is -, read standard input.
+
+  -a, --algorithm NAME  keccak256 (default), sha3-256, sha256, shake128
+                        (32-byte output) or shake256 (64-byte output)
+  -c, --check           read digest lines from the FILEs and check them
+      --quiet           with -c, do not print OK for each matching file
+      --status          with -c, print nothing; the exit code tells
+      --strict          with -c, exit 1 on improperly formatted lines";
+
+/// The algorithm `--algorithm` names. SHAKE128 outputs 32 bytes and
+/// SHAKE256 64, their security levels doubled.
+fn parse_algorithm(name: &str) -> Option<HashAlgorithm> {
+    let len = if name == "shake256" { 64 } else { 32 };
+    HashAlgorithm::from_name(name, len)
+}
+
+/// Hash everything `reader` yields.
+fn digest(algorithm: HashAlgorithm, mut reader: impl Read) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut hasher = algorithm.hasher();
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(hasher.finalize().into_vec()),
+            Ok(read) => hasher.update(&buffer[..read]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Hash `name`, where `-` is stdin.
+fn digest_file(algorithm: HashAlgorithm, name: &str) -> io::Result<Vec<u8>> {
+    if name == "-" {
+        digest(algorithm, io::stdin().lock())
+    } else {
+        digest(algorithm, File::open(name)?)
+    }
+}
+
+/// A name as it appears in a digest line, and whether it needed escaping.
+fn escape(name: &str) -> (String, bool) {
+    if !name.contains(['\\', '\n']) {
+        return (name.to_string(), false);
+    }
+    (name.replace('\\', "\\\\").replace('\n', "\\n"), true)
+}
+
+/// Undo [`escape`], or `None` for an unknown escape.
+fn unescape(name: &str) -> Option<String> {
+    let mut out = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        out.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                'n' => '\n',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    Some(out)
+}
+
+/// The digest and file name of one `<digest>  <name>` line (`*` in place
+/// of the second space marks binary mode and is accepted).
+fn parse_line(line: &str, algorithm: HashAlgorithm) -> Option<(Vec<u8>, String)> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let hex_len = algorithm.output_len() * 2;
+    let digest = hex::decode(line.get(..hex_len)?).ok()?;
+    let name = line[hex_len..]
+        .strip_prefix("  ")
+        .or_else(|| line[hex_len..].strip_prefix(" *"))?;
+    if name.is_empty() {
+        return None;
+    }
+    let name = if escaped {
+        unescape(name)?
+    } else {
+        name.to_string()
+    };
+    Some((digest, name))
+}
+
+/// What checking one or more digest lists found.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Tally {
+    matched: usize,
+    mismatched: usize,
+    unreadable: usize,
+    malformed: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Report {
+    All,
+    /// `--quiet`: only failures.
+    Failures,
+    /// `--status`: nothing.
+    Nothing,
+}
+
+/// Check every line of `list`, printing a verdict per file.
+fn check_list(
+    algorithm: HashAlgorithm,
+    list: impl BufRead,
+    list_name: &str,
+    report: Report,
+    tally: &mut Tally,
+) -> io::Result<()> {
+    let before = (tally.matched, tally.mismatched, tally.unreadable);
+    for line in list.lines() {
+        let line = line?;
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((expected, name)) = parse_line(line, algorithm) else {
+            tally.malformed += 1;
+            continue;
+        };
+        let verdict = match digest_file(algorithm, &name) {
+            Ok(digest) if digest == expected => {
+                tally.matched += 1;
+                "OK"
+            }
+            Ok(_) => {
+                tally.mismatched += 1;
+                "FAILED"
+            }
+            Err(err) => {
+                if report != Report::Nothing {
+                    eprintln!("keccak256sum: {}: {}", name, err);
+                }
+                tally.unreadable += 1;
+                "FAILED open or read"
+            }
+        };
+        let (shown, escaped) = escape(&name);
+        let prefix = if escaped { "\\" } else { "" };
+        match report {
+            Report::All => println!("{}{}: {}", prefix, shown, verdict),
+            Report::Failures if verdict != "OK" => println!("{}{}: {}", prefix, shown, verdict),
+            _ => {}
+        }
+    }
+    if (tally.matched, tally.mismatched, tally.unreadable) == before {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: no properly formatted checksum lines found", list_name),
+        ));
+    }
+    Ok(())
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
+
+/// `-c`: check every list, summarize, and return the exit code.
+fn check(algorithm: HashAlgorithm, lists: &[String], report: Report, strict: bool) -> i32 {
+    let mut tally = Tally::default();
+    let mut broken = false;
+    for name in lists {
+        let result = if name == "-" {
+            check_list(algorithm, io::stdin().lock(), name, report, &mut tally)
+        } else {
+            File::open(name).and_then(|file| {
+                check_list(algorithm, BufReader::new(file), name, report, &mut tally)
+            })
+        };
+        if let Err(err) = result {
+            if report != Report::Nothing {
+                match err.kind() {
+                    io::ErrorKind::InvalidData => eprintln!("keccak256sum: {}", err),
+                    _ => eprintln!("keccak256sum: {}: {}", name, err),
+                }
+            }
+            broken = true;
+        }
+    }
+    if report != Report::Nothing {
+        if tally.malformed > 0 {
+            eprintln!(
+                "keccak256sum: WARNING: {} improperly formatted",
+                plural(tally.malformed, "line is", "lines are")
+            );
+        }
+        if tally.unreadable > 0 {
+            eprintln!(
+                "keccak256sum: WARNING: {} could not be read",
+                plural(tally.unreadable, "listed file", "listed files")
+            );
+        }
+        if tally.mismatched > 0 {
+            eprintln!(
+                "keccak256sum: WARNING: {} did NOT match",
+                plural(tally.mismatched, "computed checksum", "computed checksums")
+            );
+        }
+    }
+    let failed =
+        broken || tally.mismatched > 0 || tally.unreadable > 0 || (strict && tally.malformed > 0);
+    i32::from(failed)
+}
+
+fn main() {
+    let mut algorithm = HashAlgorithm::Keccak256;
+    let mut checking = false;
+    let mut report = Report::All;
+    let mut strict = false;
+    let mut names = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-a" | "--algorithm" => {
+                let Some(parsed) = args.next().as_deref().and_then(parse_algorithm) else {
+                    eprintln!(
+                        "keccak256sum: --algorithm needs one of {}",
+                        HashAlgorithm::NAMES.join(", ")
+                    );
+                    process::exit(2);
+                };
+                algorithm = parsed;
+            }
+            "-c" | "--check" => checking = true,
+            "--quiet" => report = Report::Failures,
+            "--status" => report = Report::Nothing,
+            "--strict" => strict = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--" => {
+                names.extend(args.by_ref());
+            }
+            flag if flag.starts_with('-') && flag != "-" => {
+                eprintln!("keccak256sum: unknown option {}\n{}", flag, USAGE);
+                process::exit(2);
+            }
+            _ => names.push(arg),
+        }
+    }
+    if names.is_empty() {
+        names.push(String::from("-"));
+    }
+    if checking {
+        process::exit(check(algorithm, &names, report, strict));
+    }
+    if report != Report::All || strict {
+        eprintln!("keccak256sum: --quiet, --status and --strict only apply with -c");
+        process::exit(2);
+    }
+
+    let mut failed = false;
+    for name in &names {
+        match digest_file(algorithm, name) {
+            Ok(digest) => {
+                let (shown, escaped) = escape(name);
+                let prefix = if escaped { "\\" } else { "" };
+                println!("{}{}  {}", prefix, to_hex_string(&digest), shown);
+            }
+            Err(err) => {
+                eprintln!("keccak256sum: {}: {}", name, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Streamed digests match the one-shot helpers, and only names that
+    /// would break the line format are escaped.
+    #[test]
+    fn streams_and_escapes() {
+        let data = vec![0x61u8; 200_000];
+        let streamed = digest(HashAlgorithm::Keccak256, &data[..]).unwrap();
+        assert_eq!(streamed, keccak256_rust_baseline::keccak256(&data));
+        assert_eq!(
+            digest(parse_algorithm("shake256").unwrap(), &b""[..])
+                .unwrap()
+                .len(),
+            64
+        );
+        assert_eq!(escape("plain name"), (String::from("plain name"), false));
+        assert_eq!(escape("a\\b\nc"), (String::from("a\\\\b\\nc"), true));
+    }
+
+    /// Printed lines parse back to their digest and name, and a list
+    /// reports matches, mismatches, unreadable files and bad lines.
+    #[test]
+    fn check_lists_tally_each_line() {
+        let digest = keccak256_rust_baseline::keccak256(b"");
+        let awkward = "dir\\odd\nname";
+        let (shown, _) = escape(awkward);
+        let line = format!("\\{}  {}", to_hex_string(&digest), shown);
+        assert_eq!(
+            parse_line(&line, HashAlgorithm::Keccak256),
+            Some((digest.to_vec(), awkward.to_string()))
+        );
+        assert_eq!(parse_line("abc  file", HashAlgorithm::Keccak256), None);
+        let star = format!("{} *file", to_hex_string(&digest));
+        assert!(parse_line(&star, HashAlgorithm::Keccak256).is_some());
+        assert_eq!(
+            parse_line(&star, parse_algorithm("shake256").unwrap()),
+            None
+        );
+
+        let dir = env::temp_dir().join(format!("keccak256sum-{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("abc");
+        std::fs::write(&file, b"abc").unwrap();
+        let abc = to_hex_string(&keccak256_rust_baseline::keccak256(b"abc"));
+        let list = format!(
+            "{abc}  {path}\n{zero}  {path}\n{abc}  {missing}\nnot a line\n",
+            path = file.display(),
+            zero = "0".repeat(64),
+            missing = dir.join("missing").display(),
+        );
+        let mut tally = Tally::default();
+        check_list(
+            HashAlgorithm::Keccak256,
+            list.as_bytes(),
+            "list",
+            Report::Nothing,
+            &mut tally,
+        )
+        .unwrap();
+        assert_eq!(
+            tally,
+            Tally {
+                matched: 1,
+                mismatched: 1,
+                unreadable: 1,
+                malformed: 1
+            }
+        );
+        let empty = check_list(
+            HashAlgorithm::Keccak256,
+            &b"junk\n"[..],
+            "list",
+            Report::Nothing,
+            &mut tally,
+        );
+        assert!(empty.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+//! Answer hash queries over a socket so fuzzers and the Mojo test harness
+//! can query the Rust implementation without a process per query.
+//!
+//! The wire format is documented in `keccak256_rust_baseline::oracle`.
+//! Each connection is served on its own thread until the peer closes it.
+
+use std::env;
+use std::net::TcpListener;
+use std::process;
+use std::thread;
+
+use keccak256_rust_baseline::oracle;
+
+fn usage() -> ! {
+    eprintln!("usage: oracle (--unix PATH | --tcp ADDR)");
+    eprintln!();
+    eprintln!("  --unix PATH   listen on a Unix socket, replacing a stale socket file");
+    eprintln!("  --tcp ADDR    listen on a TCP address, e.g. 127.0.0.1:7878");
+    process::exit(2);
+}
+
+/// Serve one connection, reporting anything but a clean close.
+fn handle<S: std::io::Read + std::io::Write>(reader: S, writer: S, peer: String) {
+    match oracle::serve(reader, writer) {
+        Ok(served) => eprintln!("{}: closed after {} requests", peer, served),
+        Err(err) => eprintln!("warning: {}: {}", peer, err),
+    }
+}
+
+#[cfg(unix)]
+fn serve_unix(path: &str) -> Result<(), String> {
+    use std::os::unix::net::UnixListener;
+
+    // A socket file left by an earlier run would make bind fail.
+    if std::fs::metadata(path).is_ok_and(|meta| {
+        use std::os::unix::fs::FileTypeExt;
+        meta.file_type().is_socket()
+    }) {
+        std::fs::remove_file(path).map_err(|err| format!("cannot remove {}: {}", path, err))?;
+    }
+    let listener =
+        UnixListener::bind(path).map_err(|err| format!("cannot listen on {}: {}", path, err))?;
+    eprintln!("oracle listening on {}", path);
+    for (index, stream) in listener.incoming().enumerate() {
+        let stream = stream.map_err(|err| format!("accept failed: {}", err))?;
+        let writer = stream
+            .try_clone()
+            .map_err(|err| format!("cannot clone the connection: {}", err))?;
+        thread::spawn(move || handle(stream, writer, format!("connection {}", index + 1)));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_path: &str) -> Result<(), String> {
+    Err(String::from(
+        "Unix sockets are not supported on this platform; use --tcp",
+    ))
+}
+
+fn serve_tcp(addr: &str) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(addr).map_err(|err| format!("cannot listen on {}: {}", addr, err))?;
+    let local = listener.local_addr().map_err(|err| err.to_string())?;
+    eprintln!("oracle listening on {}", local);
+    for stream in listener.incoming() {
+        let stream = stream.map_err(|err| format!("accept failed: {}", err))?;
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| String::from("?"), |addr| addr.to_string());
+        // Replies are small and latency-bound.
+        let _ = stream.set_nodelay(true);
+        let writer = stream
+            .try_clone()
+            .map_err(|err| format!("cannot clone the connection: {}", err))?;
+        thread::spawn(move || handle(stream, writer, peer));
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["--unix", path] => serve_unix(path),
+        ["--tcp", addr] => serve_tcp(addr),
+        _ => usage(),
+    };
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(2);
+    }
+}
@@ -0,0 +1,225 @@
+//! Run the Mojo and Rust benchmarks on the same workload and merge their
+//! results into one comparison report.
+//!
+//! `mojo_benchmark.mojo` hard-codes its workload, so the Rust harness is
+//! handed the same parameters explicitly. Arguments after `--` go to the
+//! Rust harness, except ones that would change the workload or its output.
+//! The Mojo loop builds each message inside its timer, so the Rust harness
+//! runs with `--include-generation` to time the same work.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+use keccak256_rust_baseline::bench::compare::{self, Record};
+use keccak256_rust_baseline::bench::report;
+use serde_json::Value;
+
+/// The workload `mojo_benchmark.mojo` runs, as `bench` flags.
+const MOJO_WORKLOAD: &[(&str, &str)] = &[
+    ("--messages", "512"),
+    ("--rounds", "200"),
+    ("--min-len", "32"),
+    ("--max-len", "512"),
+    ("--stride", "31"),
+    ("--warmup", "3"),
+    // The Mojo harness only implements the legacy byte-0 fold.
+    ("--checksum", "xor"),
+];
+
+/// `bench` flags the orchestrator controls itself.
+const RESERVED: &[&str] = &[
+    "--messages",
+    "--rounds",
+    "--min-len",
+    "--max-len",
+    "--stride",
+    "--warmup",
+    "--seed",
+    "--corpus",
+    "--mmap",
+    "--length-dist",
+    "--exclude-generation",
+    "--include-generation",
+    "--sweep",
+    "--profile",
+    "--target",
+    "--xof",
+    "--xof-output",
+    "--checksum",
+    "--duration",
+    "--json",
+    "--format",
+    "--output",
+    "--append",
+    "--stream",
+    "--config",
+];
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: orchestrate [--root <dir>] [--mojo <path>] [--bench <path>] [--mojo-label <label>] \
+         [--skip-mojo] [--skip-rust] [--baseline <implementation>] [--json] [--allow-mismatch] \
+         [-- <bench args>...]"
+    );
+    process::exit(2);
+}
+
+/// The `bench` command line for the shared workload plus `extra`.
+fn rust_args(extra: &[String]) -> Result<Vec<String>, String> {
+    if let Some(flag) = extra.iter().find(|arg| RESERVED.contains(&arg.as_str())) {
+        return Err(format!(
+            "{} is set by orchestrate so both harnesses run the same workload",
+            flag
+        ));
+    }
+    let mut args: Vec<String> = MOJO_WORKLOAD
+        .iter()
+        .flat_map(|&(flag, value)| [flag.to_string(), value.to_string()])
+        .collect();
+    args.push(String::from("--include-generation"));
+    args.extend(extra.iter().cloned());
+    args.push(String::from("--json"));
+    Ok(args)
+}
+
+/// Run one harness and parse the results it prints. Its stderr passes
+/// through, so progress and warnings stay visible.
+fn collect(name: &str, command: &mut Command) -> Result<Vec<Value>, String> {
+    let output = command
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| format!("failed to launch the {} benchmark: {}", name, err))?;
+    if !output.status.success() {
+        return Err(format!("the {} benchmark failed ({})", name, output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    compare::parse_values(&stdout, name)
+}
+
+/// The `bench` binary built alongside this one.
+fn sibling_bench() -> PathBuf {
+    let exe = env::current_exe().unwrap_or_else(|_| PathBuf::from("orchestrate"));
+    exe.with_file_name(format!("bench{}", env::consts::EXE_SUFFIX))
+}
+
+fn main() {
+    let mut root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let mut mojo = String::from("mojo");
+    let mut bench = sibling_bench();
+    let mut mojo_label = String::from("mojo (jit)");
+    let mut skip_mojo = false;
+    let mut skip_rust = false;
+    let mut baseline: Option<String> = None;
+    let mut emit_json = false;
+    let mut allow_mismatch = false;
+    let mut extra: Vec<String> = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(args.next().unwrap_or_else(|| usage())),
+            "--mojo" => mojo = args.next().unwrap_or_else(|| usage()),
+            "--bench" => bench = PathBuf::from(args.next().unwrap_or_else(|| usage())),
+            "--mojo-label" => mojo_label = args.next().unwrap_or_else(|| usage()),
+            "--skip-mojo" => skip_mojo = true,
+            "--skip-rust" => skip_rust = true,
+            "--baseline" => baseline = Some(args.next().unwrap_or_else(|| usage())),
+            "--json" => emit_json = true,
+            "--allow-mismatch" => allow_mismatch = true,
+            "--" => extra.extend(args.by_ref()),
+            _ => usage(),
+        }
+    }
+    if skip_mojo && skip_rust {
+        eprintln!("error: nothing to run with both --skip-mojo and --skip-rust");
+        process::exit(2);
+    }
+    let rust_args = rust_args(&extra).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(2);
+    });
+
+    let mut values = Vec::new();
+    if !skip_mojo {
+        let script = root.join("benchmarks").join("mojo_benchmark.mojo");
+        let mut command = Command::new(&mojo);
+        command
+            .arg("-I")
+            .arg(&root)
+            .arg(&script)
+            .args(["--label", &mojo_label, "--json"])
+            .current_dir(&root);
+        match collect("mojo", &mut command) {
+            Ok(mut collected) => values.append(&mut collected),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                process::exit(2);
+            }
+        }
+    }
+    if !skip_rust {
+        match collect("rust", Command::new(&bench).args(&rust_args)) {
+            Ok(mut collected) => values.append(&mut collected),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                process::exit(2);
+            }
+        }
+    }
+
+    let records: Vec<Record> = match values
+        .iter()
+        .map(|value| serde_json::from_value(value.clone()))
+        .collect()
+    {
+        Ok(records) => records,
+        Err(err) => {
+            eprintln!("error: unexpected benchmark output: {}", err);
+            process::exit(2);
+        }
+    };
+    let problems = compare::validate(&records);
+    for problem in &problems {
+        eprintln!(
+            "{}: {}",
+            if allow_mismatch { "warning" } else { "error" },
+            problem
+        );
+    }
+    if !problems.is_empty() && !allow_mismatch {
+        process::exit(1);
+    }
+
+    if emit_json {
+        let json = serde_json::to_string(&values).expect("merged results serialize to JSON");
+        println!("{}", json);
+        return;
+    }
+    match report::render(&records, baseline.as_deref()) {
+        Ok(markdown) => print!("{}", markdown),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The shared workload is always passed and cannot be overridden.
+    #[test]
+    fn rust_args_pin_the_workload() {
+        let args = rust_args(&[String::from("--backend"), String::from("all")]).unwrap();
+        assert_eq!(&args[..2], ["--messages", "512"]);
+        assert!(args.ends_with(&[
+            String::from("--backend"),
+            String::from("all"),
+            String::from("--json")
+        ]));
+        assert!(rust_args(&[String::from("--rounds"), String::from("5")]).is_err());
+        assert!(rust_args(&[String::from("--stream")]).is_err());
+    }
+}
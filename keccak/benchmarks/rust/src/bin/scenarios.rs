@@ -0,0 +1,76 @@
+//! Write the end-to-end signing scenarios, one JSON file each, and the
+//! named test keys, for the Mojo port to check. See
+//! `keccak256_rust_baseline::scenarios` and `test_keys`; the binary needs
+//! the `test-utils` feature.
+
+use std::env;
+use std::process;
+
+#[cfg(not(feature = "verify-only"))]
+use keccak256_rust_baseline::scenarios::SCENARIO_DIR;
+
+const USAGE: &str = "usage: scenarios [DIR]
+
+Writes every scenario to DIR/<name>.json and the named test keys to
+DIR/test-keys.json, by default in the shared keccak/vectors/scenarios
+directory.";
+
+fn main() {
+    let mut dir = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            path if dir.is_none() && !path.starts_with('-') => dir = Some(path.to_string()),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    #[cfg(feature = "verify-only")]
+    {
+        let _ = dir;
+        eprintln!("scenarios: this build has verify-only, which leaves out the signer");
+        process::exit(2);
+    }
+    #[cfg(not(feature = "verify-only"))]
+    write(&dir.unwrap_or_else(|| SCENARIO_DIR.to_string()));
+}
+
+#[cfg(not(feature = "verify-only"))]
+fn write(dir: &str) {
+    use std::fs;
+    use std::path::Path;
+
+    use keccak256_rust_baseline::scenarios::{generate, to_json, SPECS};
+    use keccak256_rust_baseline::test_keys;
+
+    let dir = Path::new(dir);
+    if let Err(err) = fs::create_dir_all(dir) {
+        eprintln!("scenarios: cannot create {}: {}", dir.display(), err);
+        process::exit(1);
+    }
+    for spec in SPECS {
+        let path = dir.join(format!("{}.json", spec.name));
+        let scenario = generate(spec);
+        if let Err(err) = fs::write(&path, to_json(&scenario)) {
+            eprintln!("scenarios: cannot write {}: {}", path.display(), err);
+            process::exit(1);
+        }
+        println!(
+            "wrote {} ({} signers, {} transactions)",
+            path.display(),
+            scenario.signers.len(),
+            scenario.transactions.len()
+        );
+    }
+    let path = dir.join(test_keys::FILE_NAME);
+    if let Err(err) = fs::write(&path, test_keys::to_json()) {
+        eprintln!("scenarios: cannot write {}: {}", path.display(), err);
+        process::exit(1);
+    }
+    println!("wrote {} ({} keys)", path.display(), test_keys::NAMES.len());
+}
@@ -0,0 +1,59 @@
+//! Serve the Rust baseline over HTTP for remote differential testing; the
+//! routes are documented in `keccak256_rust_baseline::service`. Build with
+//! `--features test-utils` for `/sign`.
+
+use std::env;
+use std::net::TcpListener;
+use std::process;
+use std::thread;
+
+use keccak256_rust_baseline::service;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let addr = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => DEFAULT_ADDR,
+        ["--listen", addr] => addr,
+        _ => {
+            eprintln!(
+                "usage: service [--listen ADDR]   (default {})",
+                DEFAULT_ADDR
+            );
+            process::exit(2);
+        }
+    };
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("error: cannot listen on {}: {}", addr, err);
+            process::exit(2);
+        }
+    };
+    match listener.local_addr() {
+        Ok(local) => eprintln!("service listening on http://{}", local),
+        Err(_) => eprintln!("service listening on http://{}", addr),
+    }
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("warning: accept failed: {}", err);
+                continue;
+            }
+        };
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map_or_else(|_| String::from("?"), |addr| addr.to_string());
+            let writer = match stream.try_clone() {
+                Ok(writer) => writer,
+                Err(err) => return eprintln!("warning: {}: {}", peer, err),
+            };
+            if let Err(err) = service::serve(stream, writer) {
+                eprintln!("warning: {}: {}", peer, err);
+            }
+        });
+    }
+}
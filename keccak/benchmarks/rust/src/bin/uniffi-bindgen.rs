@@ -0,0 +1,5 @@
+//! UniFFI's binding generator, built against this crate's UniFFI version.
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
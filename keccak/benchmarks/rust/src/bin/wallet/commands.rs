@@ -0,0 +1,260 @@
+//! The wallet's subcommands, each returning what it prints so the tests
+//! can check it.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+use keccak256_rust_baseline::ethereum::{
+    self, checksum_address, eip191_hash, parse_address, recover_address, Eip1559Transaction,
+};
+use keccak256_rust_baseline::scenarios::sign;
+use keccak256_rust_baseline::secret::SecretKey;
+use keccak256_rust_baseline::{keccak256, to_hex_string};
+
+/// The environment variable a key is read from without `--key-file`.
+pub const KEY_VAR: &str = "WALLET_PRIVATE_KEY";
+
+#[derive(Debug)]
+pub enum Outcome {
+    /// Success, with what to print.
+    Done(String),
+    /// A signature that did not check out, with what to print.
+    Invalid(String),
+}
+
+#[derive(Debug)]
+pub enum Failure {
+    /// Arguments that do not form a command; the usage follows.
+    Usage(String),
+    /// A key, address, signature or transaction that does not parse.
+    Input(String),
+    /// A file that could not be read.
+    Io(String),
+}
+
+fn usage(message: impl Into<String>) -> Failure {
+    Failure::Usage(message.into())
+}
+
+/// Runs the command in `args`, the program name left out.
+pub fn run(args: &[String]) -> Result<Outcome, Failure> {
+    let mut key_file = None;
+    let mut json_file = None;
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| usage(format!("{} needs a value", flag)))
+        };
+        match arg.as_str() {
+            "--key-file" => key_file = Some(value(arg)?),
+            "--json" => json_file = Some(value(arg)?),
+            flag if flag.starts_with("--") => {
+                return Err(usage(format!("unrecognized argument {:?}", flag)))
+            }
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let load = || load_key(key_file.as_deref());
+    let done = |output: String| Ok(Outcome::Done(output));
+    match (positional.as_slice(), json_file) {
+        (["address"], None) => done(format!("{}\n", address(&load()?))),
+        (["sign-message", message], None) => {
+            done(format!("{}\n", sign_message(&load()?, message.as_bytes())))
+        }
+        (["verify-message", address, message, signature], None) => {
+            verify_message(address, message.as_bytes(), signature)
+        }
+        (["sign-tx"], Some(path)) => {
+            let text = if path == "-" {
+                let mut text = String::new();
+                io::stdin()
+                    .read_to_string(&mut text)
+                    .map_err(|err| Failure::Io(format!("cannot read stdin: {}", err)))?;
+                text
+            } else {
+                fs::read_to_string(&path)
+                    .map_err(|err| Failure::Io(format!("cannot read {}: {}", path, err)))?
+            };
+            let fields: Value = serde_json::from_str(&text)
+                .map_err(|err| Failure::Input(format!("{}: {}", path, err)))?;
+            done(sign_tx(&load()?, &fields)?)
+        }
+        (["sign-tx"], None) => Err(usage("sign-tx takes the transaction with --json FILE")),
+        ([], _) => Err(usage("missing command")),
+        ([command, ..], _) => Err(usage(match *command {
+            "address" | "sign-message" | "verify-message" | "sign-tx" => {
+                format!("bad arguments for {}", command)
+            }
+            _ => format!("unknown command {:?}", command),
+        })),
+    }
+}
+
+/// The key from `path`, or else from [`KEY_VAR`]. The text is wiped
+/// after parsing, and parse errors do not quote it.
+fn load_key(path: Option<&str>) -> Result<SecretKey, Failure> {
+    let text = Zeroizing::new(match path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| Failure::Io(format!("cannot read {}: {}", path, err)))?,
+        None => env::var(KEY_VAR)
+            .map_err(|_| Failure::Input(format!("no key: pass --key-file or set {}", KEY_VAR)))?,
+    });
+    SecretKey::from_hex(&text).map_err(|err| Failure::Input(err.to_string()))
+}
+
+fn address(key: &SecretKey) -> String {
+    let public_key = key.public_key().to_uncompressed();
+    checksum_address(&ethereum::address(&public_key).expect("65 bytes"))
+}
+
+/// The `personal_sign` signature of `message`, `v` being 27 or 28.
+fn sign_message(key: &SecretKey, message: &[u8]) -> String {
+    let (signature, odd) = sign(key.expose(), &eip191_hash(message));
+    let mut signed = [0u8; 65];
+    signed[..64].copy_from_slice(&signature.to_compact());
+    signed[64] = 27 + u8::from(odd);
+    format!("0x{}", to_hex_string(&signed))
+}
+
+fn verify_message(address: &str, message: &[u8], signature: &str) -> Result<Outcome, Failure> {
+    let input = |err: keccak256_rust_baseline::Error| Failure::Input(err.to_string());
+    let expected = parse_address(address).map_err(input)?;
+    let bytes = keccak256_rust_baseline::from_hex(signature).map_err(input)?;
+    let signature: [u8; 65] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| Failure::Input(format!("a signature is 65 bytes, not {}", bytes.len())))?;
+    Ok(match recover_address(&eip191_hash(message), &signature) {
+        Ok(signer) if signer == expected => Outcome::Done(String::from("valid\n")),
+        Ok(signer) => Outcome::Invalid(format!(
+            "invalid: signed by {}\n",
+            checksum_address(&signer)
+        )),
+        Err(err) => Outcome::Invalid(format!("invalid: {}\n", err)),
+    })
+}
+
+/// The transaction `fields` describes, signed: its signing hash, the
+/// signature, the signed encoding and its hash, named as in the
+/// scenario files so the two can be compared.
+fn sign_tx(key: &SecretKey, fields: &Value) -> Result<String, Failure> {
+    let tx = Eip1559Transaction::from_json(fields).map_err(Failure::Input)?;
+    let signing_hash = tx.signing_hash();
+    let (signature, odd) = sign(key.expose(), &signing_hash);
+    let raw = tx.encode_signed(&signature, odd);
+    let prefixed = |bytes: &[u8]| format!("0x{}", to_hex_string(bytes));
+    let mut text = serde_json::to_string_pretty(&json!({
+        "from": address(key),
+        "signing_hash": prefixed(&signing_hash),
+        "signature": {
+            "r": prefixed(&signature.r.to_bytes()),
+            "s": prefixed(&signature.s.to_bytes()),
+            "y_parity": u8::from(odd),
+        },
+        "raw": prefixed(&raw),
+        "hash": prefixed(&keccak256(&raw)),
+    }))
+    .expect("JSON values serialize");
+    text.push('\n');
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keccak256_rust_baseline::scenarios::SCENARIO_DIR;
+
+    /// `m/44'/60'/0'/0/0` of the `single-signer` scenario.
+    const SIGNER: &str = "5d14bc98f6b22117d468c32b7f0d6a5e8acdebd95c4ee5e78b5ae28277597178";
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn output(result: Result<Outcome, Failure>) -> (bool, String) {
+        match result {
+            Ok(Outcome::Done(output)) => (true, output),
+            Ok(Outcome::Invalid(output)) => (false, output),
+            Err(Failure::Usage(message) | Failure::Input(message) | Failure::Io(message)) => {
+                panic!("{}", message)
+            }
+        }
+    }
+
+    /// The web3.js documentation key signs "Some data" as web3.js does,
+    /// and verify-message accepts that for its address only.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn messages_round_trip() {
+        let key =
+            SecretKey::from_hex("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+                .unwrap();
+        let signature = sign_message(&key, b"Some data");
+        assert_eq!(
+            signature,
+            "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd\
+             6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c"
+        );
+        let address = address(&key);
+        assert_eq!(address, "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23");
+        let verify = |address: &str, message: &str| {
+            output(run(&args(&[
+                "verify-message",
+                address,
+                message,
+                &signature,
+            ])))
+        };
+        assert_eq!(
+            verify(&address, "Some data"),
+            (true, String::from("valid\n"))
+        );
+        assert!(!verify(&address, "Other data").0);
+        assert!(!verify("0x05A4D2f180D02BDfB1ac52E4017f607617DbB245", "Some data").0);
+    }
+
+    /// Signing the first `single-signer` transaction's fields with its
+    /// signer's key reproduces the committed signature and encoding.
+    #[test]
+    #[cfg_attr(miri, ignore = "reads files and signs")]
+    fn sign_tx_matches_the_scenario() {
+        let path = std::path::Path::new(SCENARIO_DIR).join("single-signer.json");
+        let scenario: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let tx = &scenario["transactions"][0];
+        let key = SecretKey::from_hex(SIGNER).unwrap();
+        let signed: Value = serde_json::from_str(&sign_tx(&key, &tx["fields"]).unwrap()).unwrap();
+        for field in ["signing_hash", "signature", "raw", "hash"] {
+            assert_eq!(signed[field], tx[field], "{}", field);
+        }
+        assert_eq!(signed["from"], scenario["signers"][0]["address"]);
+    }
+
+    /// Flags and argument counts outside the usage are refused before any
+    /// key is read.
+    #[test]
+    fn bad_arguments_are_usage_errors() {
+        for bad in [
+            &["address", "--verbose"][..],
+            &["sign-message"],
+            &["sign-tx"],
+            &["sign-tx", "--json"],
+            &["keygen"],
+            &["address", "--out", "key.txt"],
+            &["frobnicate"],
+            &[],
+        ] {
+            assert!(
+                matches!(run(&args(bad)), Err(Failure::Usage(_))),
+                "{:?}",
+                bad
+            );
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! A fixture-only development tool in the shape of a wallet, to exercise
+//! the stack end to end and compare its output with the Mojo CLI.
+//!
+//! Signing goes through the fixture signer in `scenarios`, which runs in
+//! variable time and must never see a real key, so the binary needs the
+//! `test-utils` feature and takes test keys only, such as those in
+//! `test-keys.json`. It makes no keys of its own. Keys read in are held in
+//! `keccak256_rust_baseline::secret::SecretKey`, which wipes them when
+//! dropped.
+
+use std::env;
+use std::process;
+
+#[cfg(not(feature = "verify-only"))]
+mod commands;
+
+const USAGE: &str = "usage: wallet [--key-file PATH] COMMAND [ARGS]
+
+A fixture-only development tool: it signs with the variable-time test
+signer, so never give it a key that holds or will hold funds.
+
+commands:
+  address                   print the key's EIP-55 address
+  sign-message MESSAGE      sign MESSAGE as personal_sign does and print
+                            r || s || v in hex
+  verify-message ADDRESS MESSAGE SIGNATURE
+                            check a personal_sign signature; exit status 1
+                            means it is not ADDRESS's
+  sign-tx --json FILE       sign the EIP-1559 transaction in FILE (- for
+                            stdin), shaped like a scenario's fields, and
+                            print the signature and encoding as JSON
+
+The key is 64 hex digits, from --key-file or else the WALLET_PRIVATE_KEY
+environment variable: a test key, such as one from test-keys.json.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    #[cfg(feature = "verify-only")]
+    {
+        eprintln!("wallet: this build has verify-only, which leaves out the signer");
+        process::exit(2);
+    }
+    #[cfg(not(feature = "verify-only"))]
+    match commands::run(&args) {
+        Ok(commands::Outcome::Done(output)) => print!("{}", output),
+        Ok(commands::Outcome::Invalid(output)) => {
+            print!("{}", output);
+            process::exit(1);
+        }
+        Err(commands::Failure::Usage(message)) => {
+            eprintln!("wallet: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+        Err(commands::Failure::Input(message)) => {
+            eprintln!("wallet: {}", message);
+            process::exit(2);
+        }
+        Err(commands::Failure::Io(message)) => {
+            eprintln!("wallet: {}", message);
+            process::exit(1);
+        }
+    }
+}
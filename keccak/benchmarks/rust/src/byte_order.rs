@@ -0,0 +1,148 @@
+//! Known-answer checks that catch byte-order assumptions.
+//!
+//! Every place the crate turns bytes into lanes or lanes into bytes uses
+//! `from_le_bytes` / `to_le_bytes` (or `from_le` / `to_le` on shared
+//! memory), never the native order, so results are the same on big-endian
+//! hosts. The checks here compare each of those paths against digests and
+//! byte layouts fixed in the source: on a little-endian host a stray
+//! `from_ne_bytes` still passes, so they are only meaningful run on a
+//! big-endian one. The `endian-check` binary runs them without the test
+//! harness or dev-dependencies, for `qemu-s390x` or
+//! `cargo +nightly miri run --target s390x-unknown-linux-gnu`.
+
+use crate::native::{self, Keccak256, Permutation, Shake};
+use crate::parallel::{self, MultiHasher};
+use crate::{shm, to_hex_string};
+
+/// Lane 0 of keccak-f[1600] applied to the all-zero state.
+const ZERO_STATE_LANE0: u64 = 0xf125_8f79_40e1_dde7;
+
+const EMPTY: &str = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+const ABC: &str = "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45";
+/// Keccak-256 of the bytes `0, 1, ..., 255, 0, ..., 43`.
+const SEQ300: &str = "a679e749a6af300c36e7ff2255d220864eab27b382f9cfdc5aa4d13563ba36ff";
+const SHAKE128_EMPTY: &str = "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26";
+const SHAKE256_EMPTY: &str = "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f";
+
+const PERMUTATIONS: [Permutation; 5] = [
+    Permutation::Generic,
+    Permutation::Avx2,
+    Permutation::Compact,
+    Permutation::Interleaved,
+    Permutation::Asm,
+];
+
+/// Run every check, returning a description of each that failed.
+pub fn check() -> Vec<String> {
+    let mut failures = Vec::new();
+    let mut expect = |what: &str, got: String, expected: &str| {
+        if got != expected {
+            failures.push(format!("{}: got {}, expected {}", what, got, expected));
+        }
+    };
+    let seq300: Vec<u8> = (0..300u32).map(|i| i as u8).collect();
+    let vectors: [(&str, &[u8], &str); 3] = [
+        ("empty", b"", EMPTY),
+        ("abc", b"abc", ABC),
+        ("seq300", &seq300, SEQ300),
+    ];
+
+    for permutation in PERMUTATIONS {
+        if !permutation.is_supported() {
+            continue;
+        }
+        let mut state = [0u64; 25];
+        permutation.apply(&mut state);
+        expect(
+            &format!("{:?} zero state", permutation),
+            format!("{:016x}", state[0]),
+            &format!("{:016x}", ZERO_STATE_LANE0),
+        );
+        for (name, message, digest) in vectors {
+            let one_shot = native::keccak256_with(permutation, message);
+            expect(
+                &format!("{:?} {}", permutation, name),
+                to_hex_string(&one_shot),
+                digest,
+            );
+            // Seven-byte pieces put every lane boundary at a new offset.
+            let mut hasher = Keccak256::with_permutation(permutation);
+            for piece in message.chunks(7) {
+                hasher.update(piece);
+            }
+            expect(
+                &format!("{:?} {} streamed", permutation, name),
+                to_hex_string(&hasher.finalize()),
+                digest,
+            );
+        }
+    }
+
+    for (name, message, digest) in vectors {
+        expect(
+            &format!("tiny-keccak {}", name),
+            to_hex_string(&crate::keccak256(message)),
+            digest,
+        );
+    }
+
+    let mut output = [0u8; 32];
+    Shake::shake128().finalize_xof().squeeze(&mut output);
+    expect("shake128 empty", to_hex_string(&output), SHAKE128_EMPTY);
+    Shake::shake256().finalize_xof().squeeze(&mut output);
+    expect("shake256 empty", to_hex_string(&output), SHAKE256_EMPTY);
+
+    let messages: [&[u8]; 8] = std::array::from_fn(|i| vectors[i % 3].1);
+    let digests: [&str; 8] = std::array::from_fn(|i| vectors[i % 3].2);
+    let widths = [
+        parallel::keccak256_x2([messages[0], messages[1]]).to_vec(),
+        parallel::keccak256_x4([messages[0], messages[1], messages[2], messages[3]]).to_vec(),
+        parallel::keccak256_x8(messages).to_vec(),
+    ];
+    for lanes in widths {
+        for (lane, digest) in lanes.iter().enumerate() {
+            expect(
+                &format!("x{} lane {}", lanes.len(), lane),
+                to_hex_string(digest),
+                digests[lane],
+            );
+        }
+    }
+    let mut multi = MultiHasher::<3>::new();
+    multi.update_all([messages[0], messages[1], messages[2]]);
+    for (stream, digest) in multi.finalize().iter().enumerate() {
+        expect(
+            &format!("MultiHasher stream {}", stream),
+            to_hex_string(digest),
+            digests[stream],
+        );
+    }
+
+    expect(
+        "shared-memory magic",
+        to_hex_string(&shm::MAGIC.to_le_bytes()),
+        &to_hex_string(b"KSHM"),
+    );
+    let mut body = [0u8; 8 + 3 + 32];
+    match shm::write_request(&mut body, &[b"abc"]) {
+        Ok(_) => expect(
+            "shared-memory length prefix",
+            to_hex_string(&body[..8]),
+            "0300000000000000",
+        ),
+        Err(err) => expect("shared-memory request", err, "a written request"),
+    }
+
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    /// Every path gives the fixed answers on the host running the tests,
+    /// so the binary's checks are known to pass wherever byte order is
+    /// handled.
+    #[test]
+    fn known_answers_hold() {
+        assert_eq!(super::check(), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,103 @@
+//! Commit-reveal over Keccak-256 with one fixed encoding.
+//!
+//! A commitment is the Keccak-256 of
+//!
+//! ```text
+//! tag || be64(len(value)) || value || be64(len(salt)) || salt
+//! ```
+//!
+//! where `tag` is [`DOMAIN_TAG`] (or the caller's own, for
+//! [`commit_with_domain`]) and `be64` is an 8-byte big-endian length. The
+//! lengths keep `("ab", "c")` and `("a", "bc")` apart, and the tag keeps
+//! commitments from colliding with any other Keccak-256 use of the same
+//! bytes. A contract recomputes it as `keccak256(abi.encodePacked(tag,
+//! uint64(value.length), value, uint64(salt.length), salt))`. The salt
+//! must be secret and unpredictable until the reveal, and at least 32
+//! random bytes, or a low-entropy value can be found by guessing.
+
+use crate::keccak256;
+
+/// The tag [`commit`] starts every preimage with.
+pub const DOMAIN_TAG: &[u8] = b"keccak256-commit-v1";
+
+/// The commitment to `value` under `salt`.
+pub fn commit(value: &[u8], salt: &[u8]) -> [u8; 32] {
+    commit_with_domain(DOMAIN_TAG, value, salt)
+}
+
+/// [`commit`] with `domain` in place of [`DOMAIN_TAG`], so one
+/// application's commitments cannot be replayed as another's. The domain
+/// is not length-prefixed, so it should be a fixed constant.
+pub fn commit_with_domain(domain: &[u8], value: &[u8], salt: &[u8]) -> [u8; 32] {
+    let mut preimage = Vec::with_capacity(domain.len() + value.len() + salt.len() + 16);
+    preimage.extend_from_slice(domain);
+    for field in [value, salt] {
+        preimage.extend_from_slice(&(field.len() as u64).to_be_bytes());
+        preimage.extend_from_slice(field);
+    }
+    keccak256(&preimage)
+}
+
+/// Whether revealing `value` and `salt` opens `commitment`. The digests
+/// are compared without an early exit.
+pub fn verify_commitment(commitment: &[u8; 32], value: &[u8], salt: &[u8]) -> bool {
+    verify_commitment_with_domain(DOMAIN_TAG, commitment, value, salt)
+}
+
+/// [`verify_commitment`] for [`commit_with_domain`].
+pub fn verify_commitment_with_domain(
+    domain: &[u8],
+    commitment: &[u8; 32],
+    value: &[u8],
+    salt: &[u8],
+) -> bool {
+    let expected = commit_with_domain(domain, value, salt);
+    expected
+        .iter()
+        .zip(commitment)
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The encoding is pinned by a known answer, commitments open only with
+    /// the committed value, salt and domain, and shifting bytes between
+    /// value and salt changes the commitment.
+    #[test]
+    fn commitments_open_only_as_committed() {
+        let salt = [0x5a; 32];
+        let commitment = commit(b"bid:100", &salt);
+        let mut preimage = b"keccak256-commit-v1".to_vec();
+        preimage.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 7]);
+        preimage.extend_from_slice(b"bid:100");
+        preimage.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 32]);
+        preimage.extend_from_slice(&salt);
+        assert_eq!(commitment, keccak256(&preimage));
+        assert_eq!(
+            hex::encode(commitment),
+            "c078a00c1363a0fcfe892fee70d4ea45be2307fc8f7355e63daa068e7c2122a9"
+        );
+
+        assert!(verify_commitment(&commitment, b"bid:100", &salt));
+        assert!(!verify_commitment(&commitment, b"bid:101", &salt));
+        assert!(!verify_commitment(&commitment, b"bid:100", &[0x5b; 32]));
+        assert!(!verify_commitment_with_domain(
+            b"other-app",
+            &commitment,
+            b"bid:100",
+            &salt
+        ));
+        let other = commit_with_domain(b"other-app", b"bid:100", &salt);
+        assert!(verify_commitment_with_domain(
+            b"other-app",
+            &other,
+            b"bid:100",
+            &salt
+        ));
+        assert_ne!(commit(b"ab", b"c"), commit(b"a", b"bc"));
+        assert_ne!(commit(b"", b""), keccak256(DOMAIN_TAG));
+    }
+}
@@ -0,0 +1,138 @@
+//! Constant-time selection on the secp256k1 types, built on `subtle`.
+//!
+//! Protocol code that branches on a secret bit (which MuSig nonce to
+//! keep, whether to negate a blinded key for even `y`, which precomputed
+//! multiple a window of the scalar picks) must not branch or index on
+//! it. [`Scalar`], [`FieldElement`] and [`AffinePoint`] implement
+//! [`ConditionallySelectable`] and [`ConstantTimeEq`] here, their
+//! `conditional_negate` methods compute the negation and then choose,
+//! and [`lookup`] reads every table entry to select one. Only the choice
+//! is protected: the arithmetic in [`crate::secp256k1`] is still variable
+//! time in its operands.
+//!
+//! `subtle` is re-exported so callers build [`Choice`]s from the same
+//! version.
+
+pub use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::secp256k1::{AffinePoint, FieldElement, Scalar};
+
+fn select_bytes(a: &[u8; 32], b: &[u8; 32], choice: Choice) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (out, (a, b)) in out.iter_mut().zip(a.iter().zip(b)) {
+        *out = u8::conditional_select(a, b, choice);
+    }
+    out
+}
+
+impl ConditionallySelectable for Scalar {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Scalar::from_reduced(select_bytes(&a.to_bytes(), &b.to_bytes(), choice))
+    }
+}
+
+impl ConstantTimeEq for Scalar {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.to_bytes()[..].ct_eq(&other.to_bytes()[..])
+    }
+}
+
+impl ConditionallySelectable for FieldElement {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        FieldElement::from_reduced(select_bytes(&a.to_bytes(), &b.to_bytes(), choice))
+    }
+}
+
+impl ConstantTimeEq for FieldElement {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.to_bytes()[..].ct_eq(&other.to_bytes()[..])
+    }
+}
+
+impl ConditionallySelectable for AffinePoint {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        AffinePoint {
+            x: FieldElement::conditional_select(&a.x, &b.x, choice),
+            y: FieldElement::conditional_select(&a.y, &b.y, choice),
+        }
+    }
+}
+
+impl ConstantTimeEq for AffinePoint {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.x.ct_eq(&other.x) & self.y.ct_eq(&other.y)
+    }
+}
+
+impl Scalar {
+    /// Replace the scalar with `n - self` when `choice` is set.
+    pub fn conditional_negate(&mut self, choice: Choice) {
+        let negated = self.neg();
+        self.conditional_assign(&negated, choice);
+    }
+}
+
+impl AffinePoint {
+    /// Replace the point with `(x, -y)` when `choice` is set, as BIP-340
+    /// and MuSig2 do to reach an even `y`.
+    pub fn conditional_negate(&mut self, choice: Choice) {
+        let negated = self.neg();
+        self.conditional_assign(&negated, choice);
+    }
+}
+
+/// `table[index]`, found by reading every entry so the memory accesses do
+/// not depend on `index`.
+///
+/// # Panics
+///
+/// If `index` is not below `table.len()`, which is public.
+pub fn lookup<T: ConditionallySelectable>(table: &[T], index: usize) -> T {
+    assert!(
+        index < table.len(),
+        "index {} past a table of {}",
+        index,
+        table.len()
+    );
+    let mut out = table[0];
+    for (i, entry) in table.iter().enumerate() {
+        out.conditional_assign(entry, (i as u64).ct_eq(&(index as u64)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::G;
+
+    /// Selection, negation and lookup give what branching would, and
+    /// equality agrees with `==`.
+    #[test]
+    fn selection_matches_branching() {
+        let a = Scalar::from_bytes(&[0x11; 32]).unwrap();
+        let b = Scalar::from_bytes(&[0x22; 32]).unwrap();
+        assert_eq!(Scalar::conditional_select(&a, &b, Choice::from(0)), a);
+        assert_eq!(Scalar::conditional_select(&a, &b, Choice::from(1)), b);
+        assert!(bool::from(a.ct_eq(&a)) && !bool::from(a.ct_eq(&b)));
+
+        for bit in [0, 1] {
+            let mut scalar = a;
+            scalar.conditional_negate(Choice::from(bit));
+            assert_eq!(scalar, if bit == 1 { a.neg() } else { a });
+            let mut point = G;
+            point.conditional_negate(Choice::from(bit));
+            assert_eq!(point, if bit == 1 { G.neg() } else { G });
+            assert_eq!(bool::from(point.ct_eq(&G)), bit == 0);
+        }
+
+        let table: Vec<FieldElement> = (0..16u8)
+            .map(|i| FieldElement::from_bytes(&[i; 32]).unwrap())
+            .collect();
+        for index in 0..16 {
+            assert_eq!(lookup(&table, index), table[index]);
+        }
+        let points = [G, G.neg()];
+        assert_eq!(lookup(&points, 1), G.neg());
+    }
+}
@@ -0,0 +1,209 @@
+//! Hash functions chosen at run time.
+//!
+//! Protocol code parameterized over its hash, the oracle server and the
+//! command-line tools name an algorithm by string; [`HashAlgorithm`] turns
+//! that name into one value to pass around, so callers call [`hash`] or
+//! [`HashAlgorithm::hasher`] instead of matching on it themselves. The
+//! extendable-output functions carry their output length.
+
+use std::fmt;
+
+use crate::hex::impl_hex;
+use crate::native::{Keccak256, Sha3_256, Shake};
+use crate::sha256::Sha256;
+
+/// A hash function and, for SHAKE, how many bytes it outputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    /// Keccak-256 with the original `0x01` padding, as Ethereum uses.
+    Keccak256,
+    /// FIPS 202 SHA3-256.
+    Sha3_256,
+    Sha256,
+    Shake128 {
+        len: usize,
+    },
+    Shake256 {
+        len: usize,
+    },
+}
+
+impl HashAlgorithm {
+    /// Every algorithm's name, as [`HashAlgorithm::from_name`] takes it.
+    pub const NAMES: &'static [&'static str] =
+        &["keccak256", "sha3-256", "sha256", "shake128", "shake256"];
+
+    /// The algorithm called `name`, producing `len` bytes if it is an
+    /// extendable-output function; fixed-length algorithms ignore `len`.
+    pub fn from_name(name: &str, len: usize) -> Option<HashAlgorithm> {
+        Some(match name {
+            "keccak256" => HashAlgorithm::Keccak256,
+            "sha3-256" => HashAlgorithm::Sha3_256,
+            "sha256" => HashAlgorithm::Sha256,
+            "shake128" => HashAlgorithm::Shake128 { len },
+            "shake256" => HashAlgorithm::Shake256 { len },
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Keccak256 => "keccak256",
+            HashAlgorithm::Sha3_256 => "sha3-256",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Shake128 { .. } => "shake128",
+            HashAlgorithm::Shake256 { .. } => "shake256",
+        }
+    }
+
+    /// Digest length in bytes.
+    pub fn output_len(self) -> usize {
+        match self {
+            HashAlgorithm::Keccak256 | HashAlgorithm::Sha3_256 | HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Shake128 { len } | HashAlgorithm::Shake256 { len } => len,
+        }
+    }
+
+    /// A streaming hasher for this algorithm.
+    pub fn hasher(self) -> Hasher {
+        match self {
+            HashAlgorithm::Keccak256 => Hasher::Keccak256(Keccak256::new()),
+            HashAlgorithm::Sha3_256 => Hasher::Sha3_256(Sha3_256::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Shake128 { len } => Hasher::Shake(Shake::shake128(), len),
+            HashAlgorithm::Shake256 { len } => Hasher::Shake(Shake::shake256(), len),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The output of a [`HashAlgorithm`], whose length depends on which one.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct DigestBytes(Vec<u8>);
+
+impl_hex!(DigestBytes, self => self.0);
+
+impl DigestBytes {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for DigestBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for DigestBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DigestBytes({:#x})", self)
+    }
+}
+
+/// A hasher for any [`HashAlgorithm`], from [`HashAlgorithm::hasher`].
+#[derive(Clone)]
+pub enum Hasher {
+    Keccak256(Keccak256),
+    Sha3_256(Sha3_256),
+    Sha256(Sha256),
+    /// SHAKE128 or SHAKE256 and its output length.
+    Shake(Shake, usize),
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Keccak256(hasher) => hasher.update(data),
+            Hasher::Sha3_256(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Shake(shake, _) => shake.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> DigestBytes {
+        DigestBytes(match self {
+            Hasher::Keccak256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha3_256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Shake(shake, len) => {
+                let mut output = vec![0u8; len];
+                shake.finalize_xof().squeeze(&mut output);
+                output
+            }
+        })
+    }
+}
+
+/// `message` under `algorithm`.
+pub fn hash(algorithm: HashAlgorithm, message: &[u8]) -> DigestBytes {
+    let mut hasher = algorithm.hasher();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each algorithm matches Python's hashlib, and names round-trip.
+    #[test]
+    fn algorithms_match_hashlib() {
+        let cases = [
+            (
+                "keccak256",
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            ),
+            (
+                "sha3-256",
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            ),
+            (
+                "sha256",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            ("shake128", "5881092dd818bf5cf8a3"),
+            ("shake256", "483366601360a8771c68"),
+        ];
+        for (name, expected) in cases {
+            let algorithm = HashAlgorithm::from_name(name, 10).unwrap();
+            assert_eq!(algorithm.to_string(), name);
+            assert_eq!(algorithm.output_len(), expected.len() / 2);
+            assert_eq!(
+                format!("{:x}", hash(algorithm, b"abc")),
+                expected,
+                "{}",
+                name
+            );
+        }
+        assert_eq!(HashAlgorithm::NAMES, cases.map(|(name, _)| name));
+        assert_eq!(HashAlgorithm::from_name("sha3-512", 32), None);
+    }
+
+    /// Feeding a message in uneven pieces, across block boundaries, gives
+    /// the one-shot digest.
+    #[test]
+    fn streaming_matches_one_shot() {
+        let message: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        for name in HashAlgorithm::NAMES {
+            let algorithm = HashAlgorithm::from_name(name, 200).unwrap();
+            let expected = hash(algorithm, &message);
+            for piece in [1, 55, 64, 135, 137, 168, 999] {
+                let mut hasher = algorithm.hasher();
+                for chunk in message.chunks(piece) {
+                    hasher.update(chunk);
+                }
+                assert_eq!(hasher.finalize(), expected, "{} in {}s", name, piece);
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! The error type of the hashing and Ethereum APIs.
+//!
+//! Parsing and encoding functions a library user calls (hex, addresses,
+//! public keys, blooms, ABI values, ENS names, Bech32, Base58, signatures,
+//! control blocks, recovered signers, RLP, generator tables, Merkle
+//! accumulators, secret keys, curve points) return [`Error`], so callers
+//! can tell the causes apart with a `match` rather than by reading a
+//! message. The benchmark harness and vector tooling, whose errors end up
+//! in front of a person, keep reporting them as strings.
+
+use std::fmt;
+
+/// Why an input was rejected. New causes may be added, so matches need a
+/// wildcard arm.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// `text` is not hex: a bad digit, or an odd number of them.
+    InvalidHex {
+        text: String,
+        source: hex::FromHexError,
+    },
+    /// A value or buffer of the wrong size, such as a 19-byte address.
+    InvalidLength {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// A public key that is neither 64 bytes nor 65 with the SEC1 `0x04`
+    /// prefix.
+    InvalidPublicKey { len: usize },
+    /// A type Solidity does not have, such as `uint12` or `bytes33`.
+    UnknownSolidityType { name: String },
+    /// `value`, in hex, needs more bits than `type_name` has.
+    ValueOutOfRange { value: String, type_name: String },
+    /// A name the ENS normalizer refused.
+    InvalidName { name: String, reason: String },
+    /// A string longer than its encoding allows.
+    TooLong {
+        what: &'static str,
+        max: usize,
+        actual: usize,
+    },
+    /// A Bech32 string mixing upper and lower case.
+    MixedCase,
+    /// A Bech32 string with no `1` before its data.
+    MissingSeparator,
+    /// A Bech32 human-readable part that is empty, longer than 83
+    /// characters, or outside `!` to `~`.
+    InvalidHrp { hrp: String },
+    /// A character outside the encoding's alphabet, at a 0-based index.
+    InvalidCharacter { character: char, position: usize },
+    /// A checksum that is missing or does not match.
+    InvalidChecksum,
+    /// Bits left over from regrouping that are not zero padding.
+    InvalidPadding,
+    /// A signature encoding the requested strictness does not accept.
+    InvalidDer { reason: &'static str },
+    /// A signature's `r` or `s` that is zero or not below the group order.
+    ScalarOutOfRange { what: &'static str },
+    /// A Taproot control block whose length is not `33 + 32m` for `m` up
+    /// to 128.
+    InvalidControlBlock { len: usize },
+    /// A recoverable signature's `v` that is not 0, 1, 27 or 28.
+    InvalidRecoveryId { v: u8 },
+    /// A signature with `s` above `n / 2`, which EIP-2 forbids.
+    HighS,
+    /// A signature from which no public key can be recovered: its `r` is
+    /// not the x coordinate of a point, or the key would be infinity.
+    Unrecoverable,
+    /// RLP that is malformed, not canonical, or over a decoding limit, at
+    /// a byte offset into the input.
+    InvalidRlp { offset: usize, reason: &'static str },
+    /// A serialized generator table that is truncated, corrupt, or from
+    /// another format version or build.
+    InvalidTable { reason: &'static str },
+    /// An insertion into a Merkle accumulator whose `2^depth` leaves are
+    /// all filled.
+    AccumulatorFull { depth: usize },
+    /// A secret key that is not 32 bytes of hex or not in `[1, n)`. The
+    /// text is left out, as it may be most of a key.
+    InvalidSecretKey { reason: &'static str },
+    /// A SEC1 point encoding with the wrong length or prefix, or that
+    /// names no point on the curve.
+    InvalidPoint { reason: &'static str },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidHex { text, source } => write!(f, "invalid hex {:?}: {}", text, source),
+            Error::InvalidLength {
+                what,
+                expected,
+                actual,
+            } => write!(f, "{} is {} bytes, not {}", what, expected, actual),
+            Error::InvalidPublicKey { len } => write!(
+                f,
+                "a public key is 64 bytes, or 65 with a 0x04 prefix, not {}",
+                len
+            ),
+            Error::UnknownSolidityType { name } => write!(f, "{} is not a Solidity type", name),
+            Error::ValueOutOfRange { value, type_name } => {
+                write!(f, "0x{} does not fit in {}", value, type_name)
+            }
+            Error::InvalidName { name, reason } => write!(f, "{:?} {}", name, reason),
+            Error::TooLong { what, max, actual } => {
+                write!(f, "{} is at most {} characters, not {}", what, max, actual)
+            }
+            Error::MixedCase => f.write_str("mixed upper and lower case"),
+            Error::MissingSeparator => f.write_str("no '1' separator before the data"),
+            Error::InvalidHrp { hrp } => write!(f, "{:?} is not a valid human-readable part", hrp),
+            Error::InvalidCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "invalid character {:?} at position {}",
+                character, position
+            ),
+            Error::InvalidChecksum => f.write_str("checksum mismatch"),
+            Error::InvalidPadding => f.write_str("non-zero or excess padding bits"),
+            Error::InvalidDer { reason } => write!(f, "invalid DER signature: {}", reason),
+            Error::ScalarOutOfRange { what } => write!(f, "{} is not in [1, n)", what),
+            Error::InvalidControlBlock { len } => write!(
+                f,
+                "a control block is 33 + 32m bytes with m at most 128, not {}",
+                len
+            ),
+            Error::InvalidRecoveryId { v } => {
+                write!(f, "a recovery id is 0, 1, 27 or 28, not {}", v)
+            }
+            Error::HighS => f.write_str("s is above n / 2"),
+            Error::Unrecoverable => f.write_str("no public key produces this signature"),
+            Error::InvalidRlp { offset, reason } => {
+                write!(f, "invalid RLP at byte {}: {}", offset, reason)
+            }
+            Error::InvalidTable { reason } => write!(f, "invalid generator table: {}", reason),
+            Error::AccumulatorFull { depth } => {
+                write!(f, "a Merkle accumulator of depth {} is full", depth)
+            }
+            Error::InvalidSecretKey { reason } => write!(f, "invalid secret key: {}", reason),
+            Error::InvalidPoint { reason } => write!(f, "invalid curve point: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidHex { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Lets the string-reporting tooling use `?` on these APIs.
+impl From<Error> for String {
+    fn from(err: Error) -> String {
+        err.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{self, abi};
+
+    /// Each API reports its own cause, and hex errors keep the decoder's
+    /// as their source.
+    #[test]
+    fn causes_can_be_matched() {
+        let err = crate::from_hex("0xzz").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidHex { ref text, source: hex::FromHexError::InvalidHexCharacter { .. } }
+                if text == "0xzz"
+        ));
+        assert!(std::error::Error::source(&err).is_some());
+        assert_eq!(
+            ethereum::parse_address("0x1234"),
+            Err(Error::InvalidLength {
+                what: "an address",
+                expected: 20,
+                actual: 2
+            })
+        );
+        assert_eq!(
+            ethereum::parse_address("0x1234").unwrap_err().to_string(),
+            "an address is 20 bytes, not 2"
+        );
+        assert_eq!(
+            ethereum::address(&[4; 33]),
+            Err(Error::InvalidPublicKey { len: 33 })
+        );
+        assert_eq!(
+            abi::SolidityPack::new().uint(8, &[1, 0]).unwrap_err(),
+            Error::ValueOutOfRange {
+                value: "0100".into(),
+                type_name: "uint8".into()
+            }
+        );
+        assert!(matches!(
+            abi::encode(&[abi::Token::FixedBytes(vec![])]),
+            Err(Error::UnknownSolidityType { name }) if name == "bytes0"
+        ));
+        assert!(matches!(
+            ethereum::ens::normalize_ascii("a..eth"),
+            Err(Error::InvalidName { .. })
+        ));
+    }
+}
@@ -0,0 +1,516 @@
+//! Solidity ABI encodings for hashing values the way contracts do.
+//!
+//! [`SolidityPack`] builds `abi.encodePacked` output: every value at its
+//! natural width with no padding or length prefix, so `uint16(3)` is two
+//! bytes and a `string` is just its UTF-8. Because nothing separates the
+//! values, two adjacent dynamic ones (`bytes`, `string`) can collide, e.g.
+//! `("a", "bc")` and `("ab", "c")`; contracts that hash user input should
+//! put a fixed-width value between them.
+//!
+//! [`encode`] is `abi.encode`: every value takes one or more 32-byte words,
+//! and dynamic ones (`bytes`, `string`, `T[]`, and arrays or tuples
+//! containing them) are replaced in the head by the offset of their data in
+//! the tail. That is the layout EIP-712 `encodeData` and most on-chain
+//! commitments hash.
+
+use crate::{keccak256, Error};
+
+/// One argument to `abi.encodePacked`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Packed<'a> {
+    Address(&'a [u8; 20]),
+    /// `uintN` for `N` a multiple of 8 up to 256, from big-endian bytes
+    /// of any length whose value fits in `N` bits.
+    Uint(u16, &'a [u8]),
+    /// `bytesN`, from exactly `N` bytes, `N` from 1 to 32.
+    FixedBytes(&'a [u8]),
+    Bytes(&'a [u8]),
+    String(&'a str),
+}
+
+/// `abi.encodePacked` output, built one argument at a time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolidityPack {
+    out: Vec<u8>,
+}
+
+impl SolidityPack {
+    pub fn new() -> SolidityPack {
+        SolidityPack::default()
+    }
+
+    /// Append `value`; errors leave the encoding as it was.
+    pub fn push(&mut self, value: Packed) -> Result<&mut SolidityPack, Error> {
+        match value {
+            Packed::Address(address) => self.out.extend_from_slice(address),
+            Packed::Uint(bits, value) => {
+                if bits == 0 || bits > 256 || !bits.is_multiple_of(8) {
+                    return Err(Error::UnknownSolidityType {
+                        name: format!("uint{}", bits),
+                    });
+                }
+                let value = &value[value.iter().take_while(|&&b| b == 0).count()..];
+                let width = usize::from(bits / 8);
+                if value.len() > width {
+                    return Err(Error::ValueOutOfRange {
+                        value: hex::encode(value),
+                        type_name: format!("uint{}", bits),
+                    });
+                }
+                self.out.resize(self.out.len() + width - value.len(), 0);
+                self.out.extend_from_slice(value);
+            }
+            Packed::FixedBytes(bytes) => {
+                if bytes.is_empty() || bytes.len() > 32 {
+                    return Err(Error::UnknownSolidityType {
+                        name: format!("bytes{}", bytes.len()),
+                    });
+                }
+                self.out.extend_from_slice(bytes);
+            }
+            Packed::Bytes(bytes) => self.out.extend_from_slice(bytes),
+            Packed::String(text) => self.out.extend_from_slice(text.as_bytes()),
+        }
+        Ok(self)
+    }
+
+    pub fn address(&mut self, address: &[u8; 20]) -> &mut SolidityPack {
+        self.bytes(address)
+    }
+
+    /// A `uintN` from big-endian bytes; see [`Packed::Uint`].
+    pub fn uint(&mut self, bits: u16, value: &[u8]) -> Result<&mut SolidityPack, Error> {
+        self.push(Packed::Uint(bits, value))
+    }
+
+    /// A `bytesN` of `bytes.len()` bytes.
+    pub fn fixed_bytes(&mut self, bytes: &[u8]) -> Result<&mut SolidityPack, Error> {
+        self.push(Packed::FixedBytes(bytes))
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut SolidityPack {
+        self.out.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn string(&mut self, text: &str) -> &mut SolidityPack {
+        self.bytes(text.as_bytes())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.out
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.out
+    }
+
+    /// `keccak256(abi.encodePacked(...))` of everything pushed so far.
+    pub fn keccak256(&self) -> [u8; 32] {
+        keccak256(&self.out)
+    }
+}
+
+/// `keccak256(abi.encodePacked(values...))`.
+pub fn keccak256_packed(values: &[Packed]) -> Result<[u8; 32], Error> {
+    let mut pack = SolidityPack::new();
+    for &value in values {
+        pack.push(value)?;
+    }
+    Ok(pack.keccak256())
+}
+
+/// One value to [`encode`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    Address([u8; 20]),
+    /// A `uintN`, as its 32-byte big-endian word.
+    Uint([u8; 32]),
+    /// An `intN`, as its 32-byte two's-complement word.
+    Int([u8; 32]),
+    Bool(bool),
+    /// `bytesN`: 1 to 32 bytes, padded on the right.
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    /// `T[k]`, laid out like a tuple of its elements.
+    FixedArray(Vec<Token>),
+    /// `T[]`: its length, then its elements as a tuple. Elements are not
+    /// checked to share a type.
+    Array(Vec<Token>),
+    Tuple(Vec<Token>),
+}
+
+impl Token {
+    pub fn uint(value: u128) -> Token {
+        Token::Uint(word(value))
+    }
+
+    pub fn int(value: i128) -> Token {
+        let mut word = [if value < 0 { 0xff } else { 0 }; 32];
+        word[16..].copy_from_slice(&value.to_be_bytes());
+        Token::Int(word)
+    }
+
+    /// Whether the value sits in the tail, with only its offset in the
+    /// head.
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            Token::Bytes(_) | Token::String(_) | Token::Array(_) => true,
+            Token::FixedArray(tokens) | Token::Tuple(tokens) => {
+                tokens.iter().any(Token::is_dynamic)
+            }
+            _ => false,
+        }
+    }
+
+    /// Bytes the value takes in its enclosing head.
+    fn head_len(&self) -> usize {
+        match self {
+            Token::FixedArray(tokens) | Token::Tuple(tokens) if !self.is_dynamic() => {
+                tokens.iter().map(Token::head_len).sum()
+            }
+            _ => 32,
+        }
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), Error> {
+        match self {
+            Token::Address(address) => {
+                out.extend_from_slice(&[0; 12]);
+                out.extend_from_slice(address);
+            }
+            Token::Uint(word) | Token::Int(word) => out.extend_from_slice(word),
+            Token::Bool(value) => out.extend_from_slice(&word(u128::from(*value))),
+            Token::FixedBytes(bytes) => {
+                if bytes.is_empty() || bytes.len() > 32 {
+                    return Err(Error::UnknownSolidityType {
+                        name: format!("bytes{}", bytes.len()),
+                    });
+                }
+                padded(out, bytes);
+            }
+            Token::Bytes(bytes) => {
+                out.extend_from_slice(&usize_word(bytes.len()));
+                padded(out, bytes);
+            }
+            Token::String(text) => {
+                out.extend_from_slice(&usize_word(text.len()));
+                padded(out, text.as_bytes());
+            }
+            Token::Array(tokens) => {
+                out.extend_from_slice(&usize_word(tokens.len()));
+                encode_tuple(tokens, out)?;
+            }
+            Token::FixedArray(tokens) | Token::Tuple(tokens) => encode_tuple(tokens, out)?,
+        }
+        Ok(())
+    }
+}
+
+/// `value` as a 32-byte big-endian word.
+fn word(value: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+fn usize_word(len: usize) -> [u8; 32] {
+    word(len as u128)
+}
+
+/// `bytes` followed by zeros up to a whole number of words.
+fn padded(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(bytes);
+    out.resize(
+        out.len() + bytes.len().next_multiple_of(32) - bytes.len(),
+        0,
+    );
+}
+
+fn encode_tuple(tokens: &[Token], out: &mut Vec<u8>) -> Result<(), Error> {
+    let heads: usize = tokens.iter().map(Token::head_len).sum();
+    let mut head = Vec::with_capacity(heads);
+    let mut tail = Vec::new();
+    for token in tokens {
+        if token.is_dynamic() {
+            head.extend_from_slice(&usize_word(heads + tail.len()));
+            token.encode_into(&mut tail)?;
+        } else {
+            token.encode_into(&mut head)?;
+        }
+    }
+    out.extend_from_slice(&head);
+    out.extend_from_slice(&tail);
+    Ok(())
+}
+
+/// `abi.encode(tokens...)`: the tokens as one tuple, with no function
+/// selector.
+pub fn encode(tokens: &[Token]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    encode_tuple(tokens, &mut out)?;
+    Ok(out)
+}
+
+/// `keccak256(abi.encode(tokens...))`.
+pub fn keccak256_encoded(tokens: &[Token]) -> Result<[u8; 32], Error> {
+    encode(tokens).map(|encoded| keccak256(&encoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values pack at their natural widths, out-of-range ones are refused
+    /// without touching the encoding, and the shortcut hashes the same
+    /// bytes as the builder.
+    #[test]
+    fn packs_like_solidity() {
+        // From the Solidity documentation's encodePacked example, minus
+        // its int16: bytes1(0x42), uint16(0x03), string("Hello, world!").
+        let mut pack = SolidityPack::new();
+        pack.fixed_bytes(&[0x42])
+            .unwrap()
+            .uint(16, &[0x03])
+            .unwrap()
+            .string("Hello, world!");
+        assert_eq!(
+            hex::encode(pack.as_bytes()),
+            "42000348656c6c6f2c20776f726c6421"
+        );
+
+        let address = [0x11; 20];
+        let mut amount = [0u8; 32];
+        amount[31] = 1;
+        let mut pack = SolidityPack::new();
+        pack.address(&address)
+            .uint(256, &amount)
+            .unwrap()
+            .uint(8, &[0, 0, 0xff])
+            .unwrap()
+            .bytes(b"xy");
+        let mut expected = address.to_vec();
+        expected.extend_from_slice(&amount);
+        expected.extend_from_slice(&[0xff, b'x', b'y']);
+        assert_eq!(pack.as_bytes(), expected);
+        assert_eq!(
+            keccak256_packed(&[
+                Packed::Address(&address),
+                Packed::Uint(256, &amount),
+                Packed::Uint(8, &[0xff]),
+                Packed::String("xy"),
+            ]),
+            Ok(keccak256(&expected))
+        );
+
+        let before = pack.clone();
+        assert!(pack.uint(8, &[1, 0]).is_err());
+        assert!(pack.uint(12, &[1]).is_err());
+        assert!(pack.uint(0, &[]).is_err());
+        assert!(pack.uint(264, &[1]).is_err());
+        assert!(pack.fixed_bytes(&[]).is_err());
+        assert!(pack.fixed_bytes(&[0; 33]).is_err());
+        assert_eq!(pack, before);
+        assert_eq!(
+            SolidityPack::new().uint(32, &[]).unwrap().as_bytes(),
+            [0; 4]
+        );
+    }
+
+    fn words(hex: &str) -> Vec<u8> {
+        let hex: String = hex.split_whitespace().collect();
+        hex::decode(hex).unwrap()
+    }
+
+    /// The worked examples of the Solidity ABI specification, which solc
+    /// reproduces, less their function selectors.
+    #[test]
+    fn encodes_the_specification_examples() {
+        let uints = |values: &[u128]| values.iter().map(|&v| Token::uint(v)).collect();
+        let bytes = |text: &str| Token::Bytes(text.as_bytes().to_vec());
+        let string = |text: &str| Token::String(text.to_string());
+
+        // baz(uint32 69, bool true)
+        assert_eq!(
+            encode(&[Token::uint(69), Token::Bool(true)]).unwrap(),
+            words(
+                "0000000000000000000000000000000000000000000000000000000000000045
+                 0000000000000000000000000000000000000000000000000000000000000001"
+            )
+        );
+        // bar(bytes3[2] ["abc", "def"])
+        assert_eq!(
+            encode(&[Token::FixedArray(vec![
+                Token::FixedBytes(b"abc".to_vec()),
+                Token::FixedBytes(b"def".to_vec()),
+            ])])
+            .unwrap(),
+            words(
+                "6162630000000000000000000000000000000000000000000000000000000000
+                 6465660000000000000000000000000000000000000000000000000000000000"
+            )
+        );
+        // sam(bytes "dave", bool true, uint[] [1, 2, 3])
+        assert_eq!(
+            encode(&[
+                bytes("dave"),
+                Token::Bool(true),
+                Token::Array(uints(&[1, 2, 3]))
+            ])
+            .unwrap(),
+            words(
+                "0000000000000000000000000000000000000000000000000000000000000060
+                 0000000000000000000000000000000000000000000000000000000000000001
+                 00000000000000000000000000000000000000000000000000000000000000a0
+                 0000000000000000000000000000000000000000000000000000000000000004
+                 6461766500000000000000000000000000000000000000000000000000000000
+                 0000000000000000000000000000000000000000000000000000000000000003
+                 0000000000000000000000000000000000000000000000000000000000000001
+                 0000000000000000000000000000000000000000000000000000000000000002
+                 0000000000000000000000000000000000000000000000000000000000000003"
+            )
+        );
+        // f(uint256 0x123, uint32[] [0x456, 0x789], bytes10 "1234567890",
+        //   bytes "Hello, world!")
+        assert_eq!(
+            encode(&[
+                Token::uint(0x123),
+                Token::Array(uints(&[0x456, 0x789])),
+                Token::FixedBytes(b"1234567890".to_vec()),
+                bytes("Hello, world!"),
+            ])
+            .unwrap(),
+            words(
+                "0000000000000000000000000000000000000000000000000000000000000123
+                 0000000000000000000000000000000000000000000000000000000000000080
+                 3132333435363738393000000000000000000000000000000000000000000000
+                 00000000000000000000000000000000000000000000000000000000000000e0
+                 0000000000000000000000000000000000000000000000000000000000000002
+                 0000000000000000000000000000000000000000000000000000000000000456
+                 0000000000000000000000000000000000000000000000000000000000000789
+                 000000000000000000000000000000000000000000000000000000000000000d
+                 48656c6c6f2c20776f726c642100000000000000000000000000000000000000"
+            )
+        );
+        // g(uint256[][] [[1, 2], [3]], string[] ["one", "two", "three"])
+        assert_eq!(
+            encode(&[
+                Token::Array(vec![
+                    Token::Array(uints(&[1, 2])),
+                    Token::Array(uints(&[3]))
+                ]),
+                Token::Array(vec![string("one"), string("two"), string("three")]),
+            ])
+            .unwrap(),
+            words(
+                "0000000000000000000000000000000000000000000000000000000000000040
+                 0000000000000000000000000000000000000000000000000000000000000140
+                 0000000000000000000000000000000000000000000000000000000000000002
+                 0000000000000000000000000000000000000000000000000000000000000040
+                 00000000000000000000000000000000000000000000000000000000000000a0
+                 0000000000000000000000000000000000000000000000000000000000000002
+                 0000000000000000000000000000000000000000000000000000000000000001
+                 0000000000000000000000000000000000000000000000000000000000000002
+                 0000000000000000000000000000000000000000000000000000000000000001
+                 0000000000000000000000000000000000000000000000000000000000000003
+                 0000000000000000000000000000000000000000000000000000000000000003
+                 0000000000000000000000000000000000000000000000000000000000000060
+                 00000000000000000000000000000000000000000000000000000000000000a0
+                 00000000000000000000000000000000000000000000000000000000000000e0
+                 0000000000000000000000000000000000000000000000000000000000000003
+                 6f6e650000000000000000000000000000000000000000000000000000000000
+                 0000000000000000000000000000000000000000000000000000000000000003
+                 74776f0000000000000000000000000000000000000000000000000000000000
+                 0000000000000000000000000000000000000000000000000000000000000005
+                 7468726565000000000000000000000000000000000000000000000000000000"
+            )
+        );
+    }
+
+    /// EIP-712's Mail example, hashed the way `encodeData` does. The
+    /// expected hashes are the ones printed by the EIP's reference
+    /// implementation (`assets/eip-712/Example.js`, which encodes with
+    /// ethereumjs-abi's `rawEncode`).
+    #[test]
+    fn hashes_the_eip712_reference_example() {
+        let address = |text: &str| -> [u8; 20] { hex::decode(text).unwrap().try_into().unwrap() };
+        let hashed = |text: &str| Token::FixedBytes(keccak256(text.as_bytes()).to_vec());
+        let person = |name: &str, wallet: &str| {
+            let type_hash = keccak256(b"Person(string name,address wallet)");
+            keccak256_encoded(&[
+                Token::FixedBytes(type_hash.to_vec()),
+                hashed(name),
+                Token::Address(address(wallet)),
+            ])
+            .unwrap()
+        };
+
+        let mail_type = keccak256(
+            b"Mail(Person from,Person to,string contents)Person(string name,address wallet)",
+        );
+        let mail = keccak256_encoded(&[
+            Token::FixedBytes(mail_type.to_vec()),
+            Token::FixedBytes(person("Cow", "cd2a3d9f938e13cd947ec05abc7fe734df8dd826").to_vec()),
+            Token::FixedBytes(person("Bob", "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb").to_vec()),
+            hashed("Hello, Bob!"),
+        ])
+        .unwrap();
+        assert_eq!(
+            hex::encode(mail),
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        );
+
+        let domain_type = keccak256(
+            b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)",
+        );
+        let domain = keccak256_encoded(&[
+            Token::FixedBytes(domain_type.to_vec()),
+            hashed("Ether Mail"),
+            hashed("1"),
+            Token::uint(1),
+            Token::Address(address("cccccccccccccccccccccccccccccccccccccccc")),
+        ])
+        .unwrap();
+        assert_eq!(
+            hex::encode(domain),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        let digest = keccak256(&[&[0x19, 0x01][..], &domain, &mail].concat());
+        assert_eq!(
+            hex::encode(digest),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+
+    /// Static tuples are inlined, dynamic ones become offsets, negative
+    /// integers are sign-extended, and bad `bytesN` lengths are refused.
+    #[test]
+    fn encodes_tuples_and_signed_values() {
+        let address = [0xab; 20];
+        let encoded = encode(&[
+            Token::Tuple(vec![Token::Address(address), Token::int(-1)]),
+            Token::Tuple(vec![Token::String("x".to_string())]),
+        ])
+        .unwrap();
+        let mut expected = vec![0u8; 12];
+        expected.extend_from_slice(&address);
+        expected.extend_from_slice(&[0xff; 32]);
+        expected.extend_from_slice(&word(0x60));
+        expected.extend_from_slice(&word(0x20));
+        expected.extend_from_slice(&word(1));
+        expected.push(b'x');
+        expected.resize(expected.len() + 31, 0);
+        assert_eq!(encoded, expected);
+        assert_eq!(
+            encode(&[Token::Array(vec![])]).unwrap(),
+            words(
+                "0000000000000000000000000000000000000000000000000000000000000020
+             0000000000000000000000000000000000000000000000000000000000000000"
+            )
+        );
+        assert_eq!(keccak256_encoded(&[]), Ok(keccak256(b"")));
+        assert!(encode(&[Token::FixedBytes(vec![])]).is_err());
+        assert!(encode(&[Token::Array(vec![Token::FixedBytes(vec![0; 33])])]).is_err());
+    }
+}
@@ -0,0 +1,41 @@
+//! EIP-4844 blob versioned hashes.
+//!
+//! A blob transaction names each blob by the SHA-256 of its 48-byte KZG
+//! commitment with the first byte replaced by [`VERSIONED_HASH_VERSION_KZG`].
+
+use crate::sha256::sha256;
+
+/// The version byte of a KZG commitment hash.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Bytes in a KZG commitment, a compressed BLS12-381 G1 point.
+pub const KZG_COMMITMENT_LEN: usize = 48;
+
+/// The versioned hash a blob transaction's `blob_versioned_hashes` lists
+/// for `commitment`.
+pub fn kzg_versioned_hash(commitment: &[u8; KZG_COMMITMENT_LEN]) -> [u8; 32] {
+    let mut hash = sha256(commitment);
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The versioned hash is the commitment's SHA-256 with its first byte
+    /// swapped.
+    #[test]
+    fn versioned_hash_is_prefixed_sha256() {
+        // The commitment to the all-zero blob is the compressed point at
+        // infinity.
+        let mut commitment = [0u8; KZG_COMMITMENT_LEN];
+        commitment[0] = 0xc0;
+        let hash = kzg_versioned_hash(&commitment);
+        assert_eq!(hash[1..], sha256(&commitment)[1..]);
+        assert_eq!(
+            hex::encode(hash),
+            "010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014"
+        );
+    }
+}
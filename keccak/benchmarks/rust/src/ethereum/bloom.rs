@@ -0,0 +1,180 @@
+//! The 2048-bit logs bloom in block headers and receipts.
+//!
+//! Inserting a value sets three bits chosen by its Keccak-256: each of the
+//! first three byte pairs, big-endian, taken modulo 2048, counting bit 0
+//! as the lowest bit of the last byte. A log accrues its address and each
+//! topic. Membership can give false positives, never false negatives, and
+//! the bloom of a block is the union of its receipts'.
+
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+
+use crate::hex::impl_hex;
+use crate::{keccak256, Error};
+
+/// Bytes in a serialized bloom.
+pub const BLOOM_LEN: usize = 256;
+
+/// A logs bloom, as the 256 bytes a header stores.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bloom(pub [u8; BLOOM_LEN]);
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom([0; BLOOM_LEN])
+    }
+}
+
+impl Bloom {
+    pub fn new() -> Bloom {
+        Bloom::default()
+    }
+
+    /// A bloom from its serialized bytes, which must be exactly 256.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Bloom, Error> {
+        bytes
+            .try_into()
+            .map(Bloom)
+            .map_err(|_| Error::InvalidLength {
+                what: "a bloom",
+                expected: BLOOM_LEN,
+                actual: bytes.len(),
+            })
+    }
+
+    /// A bloom from its hex, with an optional `0x` prefix.
+    pub fn from_hex(text: &str) -> Result<Bloom, Error> {
+        Bloom::from_bytes(&crate::from_hex(text)?)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; BLOOM_LEN] {
+        &self.0
+    }
+
+    /// The byte index and mask of each bit `value` sets.
+    fn bits(value: &[u8]) -> [(usize, u8); 3] {
+        let digest = keccak256(value);
+        std::array::from_fn(|i| {
+            let bit = usize::from(u16::from_be_bytes([digest[2 * i], digest[2 * i + 1]])) % 2048;
+            (BLOOM_LEN - 1 - bit / 8, 1 << (bit % 8))
+        })
+    }
+
+    pub fn insert(&mut self, value: &[u8]) {
+        for (byte, mask) in Bloom::bits(value) {
+            self.0[byte] |= mask;
+        }
+    }
+
+    /// Insert a log's emitting `address` and its `topics`.
+    pub fn accrue_log(&mut self, address: &[u8; 20], topics: &[[u8; 32]]) {
+        self.insert(address);
+        for topic in topics {
+            self.insert(topic);
+        }
+    }
+
+    /// Whether `value` may have been inserted.
+    pub fn contains(&self, value: &[u8]) -> bool {
+        Bloom::bits(value)
+            .iter()
+            .all(|&(byte, mask)| self.0[byte] & mask != 0)
+    }
+
+    /// Whether every bit of `other` is set here, e.g. a filter's bloom
+    /// against a block's.
+    pub fn contains_bloom(&self, other: &Bloom) -> bool {
+        self.0.iter().zip(&other.0).all(|(a, b)| a & b == *b)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&byte| byte == 0)
+    }
+}
+
+impl BitOrAssign for Bloom {
+    fn bitor_assign(&mut self, other: Bloom) {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a |= b;
+        }
+    }
+}
+
+impl BitOr for Bloom {
+    type Output = Bloom;
+
+    fn bitor(mut self, other: Bloom) -> Bloom {
+        self |= other;
+        self
+    }
+}
+
+impl_hex!(Bloom, self => self.0);
+
+impl fmt::Display for Bloom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+impl fmt::Debug for Bloom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bloom({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// go-ethereum's bloom tests: members are found, near misses are not,
+    /// and a hundred insertions hash to its recorded bloom.
+    #[test]
+    fn matches_go_ethereum() {
+        let mut bloom = Bloom::new();
+        for value in ["testtest", "test", "hallo", "other"] {
+            bloom.insert(value.as_bytes());
+        }
+        for value in ["testtest", "test", "hallo", "other"] {
+            assert!(bloom.contains(value.as_bytes()), "{}", value);
+        }
+        for value in ["tes", "lo"] {
+            assert!(!bloom.contains(value.as_bytes()), "{}", value);
+        }
+
+        let mut bloom = Bloom::new();
+        for i in 0..100 {
+            bloom.insert(format!("xxxxxxxxxx data {} yyyyyyyyyyyyyy", i).as_bytes());
+        }
+        assert_eq!(
+            hex::encode(keccak256(bloom.as_bytes())),
+            "c8d3ca65cdb4874300a9e39475508f23ed6da09fdbc487f89a2dcf50b09eb263"
+        );
+    }
+
+    /// Logs set exactly the bits of their address and topics, unions keep
+    /// both sides, and blooms round-trip through bytes and hex.
+    #[test]
+    fn logs_merge_and_serialize() {
+        let address = [0x11; 20];
+        let topic = keccak256(b"Transfer(address,address,uint256)");
+        let mut log = Bloom::new();
+        log.accrue_log(&address, &[topic]);
+        let mut expected = Bloom::new();
+        expected.insert(&address);
+        expected.insert(&topic);
+        assert_eq!(log, expected);
+        assert!(log.contains(&address) && log.contains(&topic));
+        assert!((1..=6).contains(&log.0.iter().map(|b| b.count_ones()).sum::<u32>()));
+
+        let mut other = Bloom::new();
+        other.insert(b"other");
+        let union = log | other;
+        assert!(union.contains_bloom(&log) && union.contains_bloom(&other));
+        assert!(Bloom::new().is_empty() && !union.is_empty());
+
+        assert_eq!(Bloom::from_bytes(union.as_bytes()), Ok(union));
+        assert_eq!(Bloom::from_hex(&union.to_string()), Ok(union));
+        assert!(Bloom::from_bytes(&[0; 255]).is_err());
+    }
+}
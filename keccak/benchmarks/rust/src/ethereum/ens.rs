@@ -0,0 +1,102 @@
+//! ENS name hashing (EIP-137).
+//!
+//! `labelhash(label)` is the Keccak-256 of the label's UTF-8, and a name's
+//! node is `namehash("") = 0` and `namehash(label.rest) =
+//! keccak256(namehash(rest) || labelhash(label))`. Hashing does not
+//! normalize: ENS hashes the ENSIP-15 normalized form of a name, and two
+//! spellings of one name hash apart. [`namehash_with`] runs a normalizer
+//! first; [`normalize_ascii`] covers ASCII names, and Unicode ones need a
+//! full ENSIP-15 implementation plugged in.
+
+use crate::{keccak256, Error};
+
+/// The Keccak-256 of `label`, as the registrar keys names under a node.
+pub fn labelhash(label: &str) -> [u8; 32] {
+    keccak256(label.as_bytes())
+}
+
+/// The node of `name`, hashed exactly as given.
+pub fn namehash(name: &str) -> [u8; 32] {
+    let mut node = [0u8; 32];
+    if name.is_empty() {
+        return node;
+    }
+    let mut preimage = [0u8; 64];
+    for label in name.rsplit('.') {
+        preimage[..32].copy_from_slice(&node);
+        preimage[32..].copy_from_slice(&labelhash(label));
+        node = keccak256(&preimage);
+    }
+    node
+}
+
+/// [`namehash`] of `name` after `normalize`, which rejects names it cannot
+/// normalize.
+pub fn namehash_with(
+    name: &str,
+    normalize: impl FnOnce(&str) -> Result<String, Error>,
+) -> Result<[u8; 32], Error> {
+    normalize(name).map(|name| namehash(&name))
+}
+
+/// A normalizer for ASCII names: lower-cases letters and rejects empty
+/// labels and anything outside ASCII, which ENSIP-15 would have to map.
+pub fn normalize_ascii(name: &str) -> Result<String, Error> {
+    let reject = |reason: &str| {
+        Err(Error::InvalidName {
+            name: name.to_string(),
+            reason: reason.to_string(),
+        })
+    };
+    if !name.is_ascii() {
+        return reject("is not ASCII; use an ENSIP-15 normalizer");
+    }
+    if !name.is_empty() && name.split('.').any(str::is_empty) {
+        return reject("has an empty label");
+    }
+    Ok(name.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The EIP-137 and ENS documentation names hash to their published
+    /// nodes, and normalization only changes spellings it is sure of.
+    #[test]
+    fn standard_names_hash_to_their_nodes() {
+        let node = |name| hex::encode(namehash(name));
+        assert_eq!(node(""), "0".repeat(64));
+        assert_eq!(
+            node("eth"),
+            "93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+        );
+        assert_eq!(
+            node("foo.eth"),
+            "de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+        );
+        assert_eq!(
+            node("alice.eth"),
+            "787192fc5378cc32aa956ddfdedbf26b24e8d78e40109add0eea2c1a012c3dec"
+        );
+        assert_eq!(
+            node("vitalik.eth"),
+            "ee6c4522aab0003e8d14cd40a6af439055fd2577951148c14b6cea9a53475835"
+        );
+        assert_eq!(
+            hex::encode(labelhash("eth")),
+            "4f5b812789fc606be1b3b16908db13fc7a9adf7ca72641f84d75b47069d3d7f0"
+        );
+
+        assert_ne!(namehash("Foo.ETH"), namehash("foo.eth"));
+        assert_eq!(
+            namehash_with("Foo.ETH", normalize_ascii),
+            Ok(namehash("foo.eth"))
+        );
+        assert_eq!(namehash_with("", normalize_ascii), Ok([0; 32]));
+        assert!(namehash_with("foo..eth", normalize_ascii).is_err());
+        assert!(namehash_with("ünicode.eth", normalize_ascii).is_err());
+        let custom = namehash_with("x", |name| Ok(format!("{}.eth", name)));
+        assert_eq!(custom, Ok(namehash("x.eth")));
+    }
+}
@@ -0,0 +1,662 @@
+//! Ethereum flows built on Keccak-256, and the conformance vectors in
+//! `keccak/vectors/ethereum/` that check them.
+//!
+//! Each vector file covers one [`Kind`] of computation: public key to
+//! address, EIP-55 checksum casing, EIP-191 message hashes, or EIP-155
+//! signing hashes. Besides `schema_version`, `description`, and
+//! `vectors`, as in the shared hash vectors, a file names its `kind`, and
+//! every vector's `input` is an object of that kind's fields with a single
+//! `expected` string. The `eth-vectors` binary runs the files against this
+//! crate or an external implementation.
+//!
+//! Encodings contracts hash (`abi.encodePacked` and friends) are in
+//! [`abi`], ENS name hashing in [`ens`], contract storage slots in
+//! [`storage`], Merkle Patricia Trie roots in [`trie`], the logs bloom in
+//! [`bloom`], EIP-4844 blob versioned hashes in [`blob`], and streaming
+//! RLP decoding in [`rlp`]. [`Eip1559Transaction`] encodes type 2
+//! transactions for signing and sending.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::signature::Signature;
+use crate::vectors::{Input, SCHEMA_VERSION};
+use crate::{from_hex, keccak256, Error};
+
+pub mod abi;
+pub mod blob;
+pub mod bloom;
+pub mod ens;
+pub mod rlp;
+pub mod storage;
+pub mod trie;
+
+/// The Ethereum vector directory, relative to this crate.
+pub const ETHEREUM_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../vectors/ethereum");
+
+/// What a vector file's inputs are turned into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// `public_key` (64 bytes, or 65 with the SEC1 `0x04` prefix) to its
+    /// checksummed address.
+    Address,
+    /// `address` to its EIP-55 casing.
+    Eip55,
+    /// `message`, spelled as a shared vector input, to its EIP-191
+    /// `personal_sign` hash.
+    Eip191,
+    /// A legacy transaction's fields to its EIP-155 signing hash.
+    Eip155,
+}
+
+impl Kind {
+    /// The answer this crate gives for `input`.
+    pub fn compute(self, input: &Value) -> Result<String, String> {
+        match self {
+            Kind::Address => {
+                let key = from_hex(string_field(input, "public_key")?)?;
+                Ok(checksum_address(&address(&key)?))
+            }
+            Kind::Eip55 => Ok(checksum_address(&parse_address(string_field(
+                input, "address",
+            )?)?)),
+            Kind::Eip191 => {
+                let message: Input = serde_json::from_value(
+                    input
+                        .get("message")
+                        .cloned()
+                        .ok_or("missing field message")?,
+                )
+                .map_err(|err| format!("message: {}", err))?;
+                Ok(format!("0x{}", hex::encode(eip191_hash(&message.bytes()?))))
+            }
+            Kind::Eip155 => Ok(format!(
+                "0x{}",
+                hex::encode(LegacyTransaction::from_json(input)?.signing_hash())
+            )),
+        }
+    }
+
+    /// Whether `got` answers `expected`. Addresses must match exactly,
+    /// casing included; hashes ignore case.
+    pub fn matches(self, expected: &str, got: &str) -> bool {
+        match self {
+            Kind::Address | Kind::Eip55 => expected == got,
+            Kind::Eip191 | Kind::Eip155 => expected.eq_ignore_ascii_case(got),
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Address => "address",
+            Kind::Eip55 => "eip55",
+            Kind::Eip191 => "eip191",
+            Kind::Eip155 => "eip155",
+        })
+    }
+}
+
+fn string_field<'a>(input: &'a Value, key: &str) -> Result<&'a str, String> {
+    input
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("missing string field {}", key))
+}
+
+/// The address of an uncompressed secp256k1 public key: the last 20 bytes
+/// of the Keccak-256 of its 64 coordinate bytes.
+pub fn address(public_key: &[u8]) -> Result<[u8; 20], Error> {
+    let coordinates = match public_key {
+        [0x04, rest @ ..] if rest.len() == 64 => rest,
+        key if key.len() == 64 => key,
+        key => return Err(Error::InvalidPublicKey { len: key.len() }),
+    };
+    let digest = keccak256(coordinates);
+    Ok(digest[12..].try_into().expect("20-byte tail"))
+}
+
+/// The address that signed the 32-byte `hash` with the 65-byte `r || s
+/// || v` signature `personal_sign` and `eth_sign` return, where `v` is 27
+/// or 28 (or 0 or 1). EIP-2's low-s rule is enforced, as the `ecrecover`
+/// callers it protects expect; the result is the signer only if `hash`
+/// is what they meant to sign.
+pub fn recover_address(hash: &[u8; 32], signature: &[u8; 65]) -> Result<[u8; 20], Error> {
+    let odd = match signature[64] {
+        v @ (0 | 1) => v == 1,
+        v @ (27 | 28) => v == 28,
+        v => return Err(Error::InvalidRecoveryId { v }),
+    };
+    let compact = Signature::from_compact(signature[..64].try_into().expect("64 bytes"))?;
+    if !compact.is_low_s() {
+        return Err(Error::HighS);
+    }
+    address(&compact.recover(hash, odd)?.to_uncompressed())
+}
+
+/// Serde functions for a `[u8; 20]` address field as its EIP-55 spelling:
+/// `#[serde(with = "keccak256_rust_baseline::ethereum::eip55")]`. Reading
+/// takes only that exact spelling, `0x` and checksum casing included, so
+/// a mistyped address fails instead of decoding to someone else.
+#[cfg(feature = "serde")]
+pub mod eip55 {
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{checksum_address, parse_address};
+    use crate::Error;
+
+    pub fn serialize<S: Serializer>(address: &[u8; 20], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&checksum_address(address))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 20], D::Error> {
+        let text = String::deserialize(deserializer)?;
+        if !text.starts_with("0x") {
+            return Err(D::Error::custom("an EIP-55 address starts with 0x"));
+        }
+        let address = parse_address(&text).map_err(D::Error::custom)?;
+        if checksum_address(&address) != text {
+            return Err(D::Error::custom(Error::InvalidChecksum));
+        }
+        Ok(address)
+    }
+}
+
+/// A 20-byte address from hex in any casing.
+pub fn parse_address(text: &str) -> Result<[u8; 20], Error> {
+    let bytes = from_hex(text)?;
+    bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::InvalidLength {
+            what: "an address",
+            expected: 20,
+            actual: bytes.len(),
+        })
+}
+
+/// `address` in EIP-55 mixed case: a hex letter is upper case when the
+/// matching nibble of the Keccak-256 of the lowercase hex is 8 or more.
+pub fn checksum_address(address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let digest = keccak256(lower.as_bytes());
+    let mut out = String::from("0x");
+    for (index, c) in lower.chars().enumerate() {
+        let nibble = (digest[index / 2] >> if index % 2 == 0 { 4 } else { 0 }) & 0xf;
+        out.push(if nibble >= 8 {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        });
+    }
+    out
+}
+
+/// The EIP-191 version `0x45` hash `personal_sign` signs for `message`.
+pub fn eip191_hash(message: &[u8]) -> [u8; 32] {
+    let mut preimage = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    preimage.extend_from_slice(message);
+    keccak256(&preimage)
+}
+
+/// An RLP item: a byte string or a list of items.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rlp<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<Rlp<'a>>),
+    /// An item encoded already, such as a trie node embedded in its
+    /// parent, copied through as is.
+    Encoded(&'a [u8]),
+}
+
+impl Rlp<'_> {
+    /// Append this item's encoding to `out`.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Rlp::Bytes([byte]) if *byte < 0x80 => out.push(*byte),
+            Rlp::Bytes(bytes) => {
+                encode_length(bytes.len(), 0x80, out);
+                out.extend_from_slice(bytes);
+            }
+            Rlp::List(items) => {
+                let mut body = Vec::new();
+                for item in items {
+                    item.encode(&mut body);
+                }
+                encode_length(body.len(), 0xc0, out);
+                out.extend_from_slice(&body);
+            }
+            Rlp::Encoded(encoded) => out.extend_from_slice(encoded),
+        }
+    }
+}
+
+fn encode_length(length: usize, offset: u8, out: &mut Vec<u8>) {
+    if length < 56 {
+        out.push(offset + length as u8);
+        return;
+    }
+    let bytes = length.to_be_bytes();
+    let digits = &bytes[bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len())..];
+    out.push(offset + 55 + digits.len() as u8);
+    out.extend_from_slice(digits);
+}
+
+/// A pre-EIP-2718 transaction, with every quantity as minimal big-endian
+/// bytes (zero is empty).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LegacyTransaction {
+    pub nonce: Vec<u8>,
+    pub gas_price: Vec<u8>,
+    pub gas_limit: Vec<u8>,
+    /// Empty for contract creation.
+    pub to: Vec<u8>,
+    pub value: Vec<u8>,
+    pub data: Vec<u8>,
+    pub chain_id: Vec<u8>,
+}
+
+/// A `0x`-prefixed hex quantity as minimal big-endian bytes.
+fn quantity(text: &str) -> Result<Vec<u8>, String> {
+    let digits = text
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("quantity {:?} lacks the 0x prefix", text))?
+        .trim_start_matches('0');
+    let padded = if digits.len() % 2 == 1 {
+        format!("0{}", digits)
+    } else {
+        digits.to_string()
+    };
+    hex::decode(&padded).map_err(|err| format!("invalid quantity {:?}: {}", text, err))
+}
+
+impl LegacyTransaction {
+    /// The transaction a vector's `input` describes: quantities
+    /// `nonce`, `gas_price`, `gas_limit`, `value`, and `chain_id`, and hex
+    /// byte strings `to` and `data`.
+    pub fn from_json(input: &Value) -> Result<LegacyTransaction, String> {
+        let quantity = |key| string_field(input, key).and_then(quantity);
+        let bytes = |key| string_field(input, key).and_then(|text| Ok(from_hex(text)?));
+        let to = bytes("to")?;
+        if !to.is_empty() && to.len() != 20 {
+            return Err(format!("to is 20 bytes or empty, not {}", to.len()));
+        }
+        Ok(LegacyTransaction {
+            nonce: quantity("nonce")?,
+            gas_price: quantity("gas_price")?,
+            gas_limit: quantity("gas_limit")?,
+            to,
+            value: quantity("value")?,
+            data: bytes("data")?,
+            chain_id: quantity("chain_id")?,
+        })
+    }
+
+    /// `rlp([nonce, gas_price, gas_limit, to, value, data, chain_id, 0, 0])`.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let fields = [
+            &self.nonce,
+            &self.gas_price,
+            &self.gas_limit,
+            &self.to,
+            &self.value,
+            &self.data,
+            &self.chain_id,
+        ];
+        let mut items: Vec<Rlp> = fields.iter().map(|field| Rlp::Bytes(field)).collect();
+        items.extend([Rlp::Bytes(&[]), Rlp::Bytes(&[])]);
+        let mut out = Vec::new();
+        Rlp::List(items).encode(&mut out);
+        out
+    }
+
+    pub fn signing_hash(&self) -> [u8; 32] {
+        keccak256(&self.signing_payload())
+    }
+}
+
+/// An EIP-1559 (type 2) transaction, with quantities as in
+/// [`LegacyTransaction`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Eip1559Transaction {
+    pub chain_id: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub max_priority_fee_per_gas: Vec<u8>,
+    pub max_fee_per_gas: Vec<u8>,
+    pub gas_limit: Vec<u8>,
+    /// Empty for contract creation.
+    pub to: Vec<u8>,
+    pub value: Vec<u8>,
+    pub data: Vec<u8>,
+    /// EIP-2930 entries: an address and the storage keys under it.
+    pub access_list: Vec<([u8; 20], Vec<[u8; 32]>)>,
+}
+
+/// `bytes` without leading zeros, as RLP integers are encoded.
+fn minimal(bytes: &[u8]) -> &[u8] {
+    &bytes[bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len())..]
+}
+
+impl Eip1559Transaction {
+    /// The EIP-2718 type byte.
+    pub const TYPE: u8 = 0x02;
+
+    /// The transaction a JSON object describes, in the shape of a
+    /// scenario's `fields`: quantities `chain_id`, `nonce`,
+    /// `max_priority_fee_per_gas`, `max_fee_per_gas`, `gas_limit` and
+    /// `value`, hex byte strings `to` and `data`, and an optional
+    /// `access_list` of `{address, storage_keys}` entries.
+    pub fn from_json(input: &Value) -> Result<Eip1559Transaction, String> {
+        let quantity = |key| string_field(input, key).and_then(quantity);
+        let bytes = |key| string_field(input, key).and_then(|text| Ok(from_hex(text)?));
+        let to = bytes("to")?;
+        if !to.is_empty() && to.len() != 20 {
+            return Err(format!("to is 20 bytes or empty, not {}", to.len()));
+        }
+        let mut access_list = Vec::new();
+        if let Some(entries) = input.get("access_list") {
+            let entries = entries.as_array().ok_or("access_list is not an array")?;
+            for entry in entries {
+                let address = parse_address(string_field(entry, "address")?)?;
+                let keys = entry
+                    .get("storage_keys")
+                    .and_then(Value::as_array)
+                    .ok_or("missing array field storage_keys")?
+                    .iter()
+                    .map(|key| {
+                        let text = key.as_str().ok_or("a storage key is not a string")?;
+                        let key = from_hex(text)?;
+                        key.as_slice()
+                            .try_into()
+                            .map_err(|_| format!("storage key {:?} is not 32 bytes", text))
+                    })
+                    .collect::<Result<Vec<[u8; 32]>, String>>()?;
+                access_list.push((address, keys));
+            }
+        }
+        Ok(Eip1559Transaction {
+            chain_id: quantity("chain_id")?,
+            nonce: quantity("nonce")?,
+            max_priority_fee_per_gas: quantity("max_priority_fee_per_gas")?,
+            max_fee_per_gas: quantity("max_fee_per_gas")?,
+            gas_limit: quantity("gas_limit")?,
+            to,
+            value: quantity("value")?,
+            data: bytes("data")?,
+            access_list,
+        })
+    }
+
+    fn fields(&self) -> Vec<Rlp<'_>> {
+        let access_list = self
+            .access_list
+            .iter()
+            .map(|(address, keys)| {
+                Rlp::List(vec![
+                    Rlp::Bytes(address),
+                    Rlp::List(keys.iter().map(|key| Rlp::Bytes(key)).collect()),
+                ])
+            })
+            .collect();
+        vec![
+            Rlp::Bytes(&self.chain_id),
+            Rlp::Bytes(&self.nonce),
+            Rlp::Bytes(&self.max_priority_fee_per_gas),
+            Rlp::Bytes(&self.max_fee_per_gas),
+            Rlp::Bytes(&self.gas_limit),
+            Rlp::Bytes(&self.to),
+            Rlp::Bytes(&self.value),
+            Rlp::Bytes(&self.data),
+            Rlp::List(access_list),
+        ]
+    }
+
+    /// `0x02 || rlp([chain_id, nonce, max_priority_fee_per_gas,
+    /// max_fee_per_gas, gas_limit, to, value, data, access_list])`.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut out = vec![Self::TYPE];
+        Rlp::List(self.fields()).encode(&mut out);
+        out
+    }
+
+    pub fn signing_hash(&self) -> [u8; 32] {
+        keccak256(&self.signing_payload())
+    }
+
+    /// The transaction as sent to the network: the signing payload's
+    /// fields followed by `y_parity`, `r` and `s`. Its Keccak-256 is the
+    /// transaction hash.
+    pub fn encode_signed(&self, signature: &Signature, odd: bool) -> Vec<u8> {
+        let (r, s) = (signature.r.to_bytes(), signature.s.to_bytes());
+        let mut fields = self.fields();
+        fields.extend([
+            Rlp::Bytes(if odd { &[1] } else { &[] }),
+            Rlp::Bytes(minimal(&r)),
+            Rlp::Bytes(minimal(&s)),
+        ]);
+        let mut out = vec![Self::TYPE];
+        Rlp::List(fields).encode(&mut out);
+        out
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EthVectorFile {
+    pub schema_version: u64,
+    pub kind: Kind,
+    pub description: String,
+    pub vectors: Vec<EthVector>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EthVector {
+    pub name: String,
+    pub input: Value,
+    pub expected: String,
+    #[serde(default)]
+    pub metadata: Value,
+}
+
+impl EthVectorFile {
+    pub fn load(path: &Path) -> Result<EthVectorFile, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let file: EthVectorFile =
+            serde_json::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        if file.schema_version != SCHEMA_VERSION {
+            return Err(format!(
+                "{}: schema_version {} is not {}",
+                path.display(),
+                file.schema_version,
+                SCHEMA_VERSION
+            ));
+        }
+        Ok(file)
+    }
+
+    /// Every `*.json` file in `dir`, by file name.
+    pub fn load_dir(dir: &Path) -> Result<Vec<(PathBuf, EthVectorFile)>, String> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .map_err(|err| format!("cannot read {}: {}", dir.display(), err))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| EthVectorFile::load(&path).map(|file| (path, file)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every Ethereum vector file loads and this crate passes it.
+    #[test]
+    fn ethereum_vectors_pass() {
+        let files = EthVectorFile::load_dir(Path::new(ETHEREUM_DIR)).unwrap();
+        assert_eq!(files.len(), 4, "expected one file per kind");
+        for (path, file) in files {
+            for vector in &file.vectors {
+                let got = file.kind.compute(&vector.input).unwrap();
+                assert!(
+                    file.kind.matches(&vector.expected, &got),
+                    "{}:{}: expected {}, got {}",
+                    path.display(),
+                    vector.name,
+                    vector.expected,
+                    got
+                );
+            }
+        }
+    }
+
+    /// The `fields` of the second `single-signer` scenario transaction, a
+    /// token call with an access list, give its signing hash, and a
+    /// transfer may leave the access list out.
+    #[test]
+    fn eip1559_transactions_parse_scenario_fields() {
+        let address = "0x05a4d2f180d02bdfb1ac52e4017f607617dbb245";
+        let mut fields = serde_json::json!({
+            "chain_id": "0x100000001",
+            "nonce": "0x1",
+            "max_priority_fee_per_gas": "0x77359400",
+            "max_fee_per_gas": "0x737be7600",
+            "gas_limit": "0xea60",
+            "to": address,
+            "value": "0x0",
+            "data": format!(
+                "0xa9059cbb000000000000000000000000{}{:0>64}",
+                &address[2..],
+                "1bc16d674ec80000"
+            ),
+            "access_list": [{
+                "address": address,
+                "storage_keys": [format!("0x{:0>64}", 0), format!("0x{:0>64}", 1)]
+            }]
+        });
+        let tx = Eip1559Transaction::from_json(&fields).unwrap();
+        assert_eq!(
+            hex::encode(tx.signing_hash()),
+            "562dca64f519bc1e6bf08ac7bff4bd848a70c0984b056faf16348255e29a9ea7"
+        );
+        assert!(tx.value.is_empty());
+
+        fields.as_object_mut().unwrap().remove("access_list");
+        assert!(Eip1559Transaction::from_json(&fields)
+            .unwrap()
+            .access_list
+            .is_empty());
+        fields["to"] = "0x1234".into();
+        assert_eq!(
+            Eip1559Transaction::from_json(&fields).unwrap_err(),
+            "to is 20 bytes or empty, not 2"
+        );
+    }
+
+    /// A `personal_sign` signature of "hello", made in Python with the
+    /// web3.js documentation key, recovers that key's address; the wrong
+    /// parity gives another, and bad `v`, high `s` and an `r` off the curve
+    /// are refused.
+    #[test]
+    #[cfg_attr(miri, ignore = "thousands of field inversions")]
+    fn recover_address_finds_the_signer() {
+        let hash = eip191_hash(b"hello");
+        let mut signature = [0u8; 65];
+        signature[..64].copy_from_slice(
+            &from_hex(concat!(
+                "5225c4a2df3dd2a6f678b8e20b42e5d205d84ddadb9033ae220e94c1f0487377",
+                "413dcb705be6b9b42e2b3cf1c2af02176b1fb3989668ec2d91f6a482fb301ac0"
+            ))
+            .unwrap(),
+        );
+        signature[64] = 27;
+        let signer = parse_address("0x2c7536E3605D9C16a7a3D7b1898e529396a65c23").unwrap();
+        assert_eq!(recover_address(&hash, &signature), Ok(signer));
+        signature[64] = 0;
+        assert_eq!(recover_address(&hash, &signature), Ok(signer));
+        signature[64] = 28;
+        assert_ne!(recover_address(&hash, &signature), Ok(signer));
+        signature[64] = 29;
+        assert_eq!(
+            recover_address(&hash, &signature),
+            Err(Error::InvalidRecoveryId { v: 29 })
+        );
+
+        let parsed = Signature::from_compact(signature[..64].try_into().unwrap()).unwrap();
+        let mut high = signature;
+        high[32..64].copy_from_slice(&parsed.s.neg().to_bytes());
+        high[64] = 28;
+        assert_eq!(recover_address(&hash, &high), Err(Error::HighS));
+        let mut off_curve = signature;
+        off_curve[..32].copy_from_slice(&[0; 32]);
+        off_curve[31] = 5;
+        off_curve[64] = 27;
+        assert_eq!(
+            recover_address(&hash, &off_curve),
+            Err(Error::Unrecoverable)
+        );
+    }
+
+    /// RLP follows the short and long forms for strings and lists.
+    #[test]
+    fn rlp_encodes_strings_and_lists() {
+        let encode = |item: Rlp| {
+            let mut out = Vec::new();
+            item.encode(&mut out);
+            out
+        };
+        assert_eq!(encode(Rlp::Bytes(b"")), [0x80]);
+        assert_eq!(encode(Rlp::Bytes(&[0x7f])), [0x7f]);
+        assert_eq!(encode(Rlp::Bytes(&[0x80])), [0x81, 0x80]);
+        assert_eq!(encode(Rlp::Bytes(b"dog")), b"\x83dog");
+        let long = [b'a'; 56];
+        assert_eq!(encode(Rlp::Bytes(&long))[..2], [0xb8, 56]);
+        assert_eq!(encode(Rlp::List(vec![])), [0xc0]);
+        assert_eq!(
+            encode(Rlp::List(vec![Rlp::Bytes(b"cat"), Rlp::Bytes(b"dog")])),
+            b"\xc8\x83cat\x83dog"
+        );
+        assert_eq!(quantity("0x0").unwrap(), b"");
+        assert_eq!(quantity("0x400").unwrap(), [0x04, 0x00]);
+        assert!(quantity("12").is_err());
+    }
+
+    /// Addresses serialize as EIP-55 and read back only from that exact
+    /// spelling.
+    #[test]
+    #[cfg(feature = "serde")]
+    fn addresses_serialize_as_eip55() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Account {
+            #[serde(with = "eip55")]
+            address: [u8; 20],
+        }
+
+        let text = "0x05A4D2f180D02BDfB1ac52E4017f607617DbB245";
+        let account = Account {
+            address: parse_address(text).unwrap(),
+        };
+        let json = serde_json::to_value(&account).unwrap();
+        assert_eq!(json, serde_json::json!({ "address": text }));
+        assert_eq!(serde_json::from_value::<Account>(json).unwrap(), account);
+        for bad in [
+            text.to_lowercase(),
+            text.replace("0x05A4", "0x05a4"),
+            text[2..].to_string(),
+            text[..40].to_string(),
+        ] {
+            let json = serde_json::json!({ "address": bad });
+            assert!(serde_json::from_value::<Account>(json).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//! Decoding RLP as a stream of events, for payloads too large or too
+//! untrusted to turn into nested [`Rlp`](super::Rlp) values.
+//!
+//! A [`Decoder`] walks one encoded item and yields [`Event`]s borrowing
+//! from the input: each byte string's contents, and the start and end of
+//! each list. A list's start carries its whole encoding, so a block
+//! header can be hashed, or a transaction skipped with
+//! [`Decoder::skip_list`], without re-encoding or visiting its contents.
+//! Nothing is allocated per item; the open lists are tracked in a fixed
+//! stack of [`MAX_DEPTH`] entries.
+//!
+//! Only canonical encodings are accepted, as consensus requires: a single
+//! byte below `0x80` must be its own encoding, long forms are for lengths
+//! of 56 or more, and lengths have no leading zeros. Every declared
+//! length is checked against the enclosing list and the input before it
+//! is used, and [`Limits`] bound nesting and string sizes on top.
+
+use crate::Error;
+
+/// The deepest nesting any [`Decoder`] follows.
+pub const MAX_DEPTH: usize = 64;
+
+/// Bounds for untrusted input, beyond the input's own length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Lists open at once, at most [`MAX_DEPTH`].
+    pub max_depth: usize,
+    /// The longest byte string, in bytes.
+    pub max_string_len: usize,
+}
+
+impl Default for Limits {
+    /// Deep enough for blocks and receipts, and strings up to 16 MiB.
+    fn default() -> Self {
+        Limits {
+            max_depth: 16,
+            max_string_len: 16 << 20,
+        }
+    }
+}
+
+/// One step of a [`Decoder`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// A byte string's contents.
+    Bytes(&'a [u8]),
+    /// The start of a list, with its whole encoding, header included.
+    ListStart { encoded: &'a [u8] },
+    /// The end of the list most recently started.
+    ListEnd,
+}
+
+/// A pull parser over the encoding of one RLP item.
+#[derive(Clone, Debug)]
+pub struct Decoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+    limits: Limits,
+    /// Where each open list's payload ends.
+    ends: [usize; MAX_DEPTH],
+    depth: usize,
+    /// Whether the top-level item has been read.
+    started: bool,
+    failed: bool,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(input: &'a [u8]) -> Decoder<'a> {
+        Decoder::with_limits(input, Limits::default())
+    }
+
+    pub fn with_limits(input: &'a [u8], limits: Limits) -> Decoder<'a> {
+        Decoder {
+            input,
+            pos: 0,
+            limits: Limits {
+                max_depth: limits.max_depth.min(MAX_DEPTH),
+                ..limits
+            },
+            ends: [0; MAX_DEPTH],
+            depth: 0,
+            started: false,
+            failed: false,
+        }
+    }
+
+    /// How many lists are open.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Skip the rest of the innermost open list, as if its remaining
+    /// events and its [`Event::ListEnd`] had been read.
+    ///
+    /// # Panics
+    ///
+    /// If no list is open.
+    pub fn skip_list(&mut self) {
+        assert!(self.depth > 0, "no open list to skip");
+        self.depth -= 1;
+        self.pos = self.ends[self.depth];
+    }
+
+    fn error(&mut self, reason: &'static str) -> Error {
+        self.failed = true;
+        Error::InvalidRlp {
+            offset: self.pos,
+            reason,
+        }
+    }
+
+    fn read(&mut self) -> Result<Option<Event<'a>>, Error> {
+        let end = match self.depth {
+            0 if self.started => {
+                if self.pos < self.input.len() {
+                    return Err(self.error("bytes after the item"));
+                }
+                return Ok(None);
+            }
+            0 => self.input.len(),
+            depth => self.ends[depth - 1],
+        };
+        if self.depth > 0 && self.pos == end {
+            self.depth -= 1;
+            return Ok(Some(Event::ListEnd));
+        }
+        self.started = true;
+        let start = self.pos;
+        let prefix = *self
+            .input
+            .get(start)
+            .ok_or_else(|| self.error("empty input"))?;
+        let (header, len, list) = match prefix {
+            0x00..=0x7f => {
+                self.pos += 1;
+                return Ok(Some(Event::Bytes(&self.input[start..start + 1])));
+            }
+            0x80..=0xb7 => (1, usize::from(prefix - 0x80), false),
+            0xb8..=0xbf => {
+                let len = self.long_length(end, usize::from(prefix - 0xb7))?;
+                (1 + usize::from(prefix - 0xb7), len, false)
+            }
+            0xc0..=0xf7 => (1, usize::from(prefix - 0xc0), true),
+            0xf8..=0xff => {
+                let len = self.long_length(end, usize::from(prefix - 0xf7))?;
+                (1 + usize::from(prefix - 0xf7), len, true)
+            }
+        };
+        let payload = start + header;
+        let item_end = payload
+            .checked_add(len)
+            .filter(|&item_end| item_end <= end)
+            .ok_or_else(|| self.error("an item overruns its list or the input"))?;
+        if list {
+            if self.depth == self.limits.max_depth {
+                return Err(self.error("lists nested deeper than the limit"));
+            }
+            self.ends[self.depth] = item_end;
+            self.depth += 1;
+            self.pos = payload;
+            return Ok(Some(Event::ListStart {
+                encoded: &self.input[start..item_end],
+            }));
+        }
+        if len > self.limits.max_string_len {
+            return Err(self.error("a string longer than the limit"));
+        }
+        let bytes = &self.input[payload..item_end];
+        if let [byte] = bytes {
+            if *byte < 0x80 {
+                return Err(self.error("a single byte below 0x80 with a length prefix"));
+            }
+        }
+        self.pos = item_end;
+        Ok(Some(Event::Bytes(bytes)))
+    }
+
+    /// The `count`-byte length after the prefix at `pos`, which must be
+    /// minimal and need the long form.
+    fn long_length(&mut self, end: usize, count: usize) -> Result<usize, Error> {
+        let digits = self
+            .input
+            .get(self.pos + 1..self.pos + 1 + count)
+            .filter(|_| self.pos + 1 + count <= end)
+            .ok_or_else(|| self.error("a truncated length"))?;
+        if digits[0] == 0 {
+            return Err(self.error("a length with leading zeros"));
+        }
+        if count > std::mem::size_of::<usize>() {
+            return Err(self.error("a length beyond the address space"));
+        }
+        let len = digits
+            .iter()
+            .fold(0usize, |len, &digit| len << 8 | usize::from(digit));
+        if len < 56 {
+            return Err(self.error("a long-form length under 56"));
+        }
+        Ok(len)
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = Result<Event<'a>, Error>;
+
+    /// The next event; after an error, `None`.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        self.read().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::Rlp;
+
+    fn encode(item: &Rlp) -> Vec<u8> {
+        let mut out = Vec::new();
+        item.encode(&mut out);
+        out
+    }
+
+    fn events(input: &[u8]) -> Result<Vec<Event<'_>>, Error> {
+        Decoder::new(input).collect()
+    }
+
+    fn reason(result: Result<Vec<Event<'_>>, Error>) -> &'static str {
+        match result {
+            Err(Error::InvalidRlp { reason, .. }) => reason,
+            other => panic!("expected an RLP error, got {:?}", other),
+        }
+    }
+
+    /// Decoding what the encoder writes gives back its strings and list
+    /// boundaries, with each list's own encoding at its start.
+    #[test]
+    fn decodes_what_the_encoder_writes() {
+        let long = vec![0xab; 1000];
+        let inner = Rlp::List(vec![Rlp::Bytes(b"cat"), Rlp::Bytes(&[0x05])]);
+        let item = Rlp::List(vec![
+            inner.clone(),
+            Rlp::Bytes(b""),
+            Rlp::Bytes(&long),
+            Rlp::List(vec![]),
+        ]);
+        let encoded = encode(&item);
+        let inner_encoded = encode(&inner);
+        assert_eq!(
+            events(&encoded).unwrap(),
+            [
+                Event::ListStart { encoded: &encoded },
+                Event::ListStart {
+                    encoded: &inner_encoded
+                },
+                Event::Bytes(b"cat"),
+                Event::Bytes(&[0x05]),
+                Event::ListEnd,
+                Event::Bytes(b""),
+                Event::Bytes(&long),
+                Event::ListStart { encoded: &[0xc0] },
+                Event::ListEnd,
+                Event::ListEnd,
+            ]
+        );
+        assert_eq!(events(b"\x83dog").unwrap(), [Event::Bytes(b"dog")]);
+
+        let mut decoder = Decoder::new(&encoded);
+        assert!(matches!(decoder.next(), Some(Ok(Event::ListStart { .. }))));
+        assert!(matches!(decoder.next(), Some(Ok(Event::ListStart { .. }))));
+        assert_eq!(decoder.depth(), 2);
+        decoder.skip_list();
+        assert_eq!(decoder.next(), Some(Ok(Event::Bytes(b""))));
+        assert_eq!(decoder.count(), 4);
+    }
+
+    /// Malformed, non-canonical and over-limit input is refused with the
+    /// cause, and the decoder stops there.
+    #[test]
+    fn refuses_bad_and_oversized_input() {
+        assert_eq!(reason(events(b"")), "empty input");
+        assert_eq!(
+            reason(events(b"\x83do")),
+            "an item overruns its list or the input"
+        );
+        assert_eq!(
+            reason(events(b"\xc2\x83dog")),
+            "an item overruns its list or the input"
+        );
+        assert_eq!(reason(events(b"\x83dog\x00")), "bytes after the item");
+        assert_eq!(
+            reason(events(b"\x81\x05")),
+            "a single byte below 0x80 with a length prefix"
+        );
+        assert_eq!(
+            reason(events(b"\xb8\x05hello")),
+            "a long-form length under 56"
+        );
+        assert_eq!(
+            reason(events(b"\xb9\x00\x38")),
+            "a length with leading zeros"
+        );
+        assert_eq!(reason(events(b"\xb9\x01")), "a truncated length");
+
+        let nested = [0xc3, 0xc2, 0xc1, 0xc0];
+        let limits = Limits {
+            max_depth: 3,
+            ..Limits::default()
+        };
+        assert_eq!(
+            Decoder::with_limits(&nested, limits)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| err.to_string()),
+            Err(String::from(
+                "invalid RLP at byte 3: lists nested deeper than the limit"
+            ))
+        );
+        assert_eq!(Decoder::with_limits(&nested[1..], limits).count(), 6);
+        let long = encode(&Rlp::Bytes(&[0xab; 100]));
+        let limits = Limits {
+            max_string_len: 99,
+            ..Limits::default()
+        };
+        assert_eq!(
+            reason(Decoder::with_limits(&long, limits).collect()),
+            "a string longer than the limit"
+        );
+
+        let mut decoder = Decoder::new(b"\xc2\x81\x05");
+        assert!(decoder.next().unwrap().is_ok());
+        assert!(decoder.next().unwrap().is_err());
+        assert_eq!(decoder.next(), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Solidity storage layout: where a mapping entry, an array element or a
+//! long `bytes`/`string`'s data lives.
+//!
+//! A state variable at slot `p` keeps a mapping's value for `key` at
+//! `keccak256(pad32(key) || p)`, or `keccak256(key || p)` unpadded when the
+//! key is `bytes` or `string`. A dynamic array's length sits at `p` and its
+//! elements from `keccak256(p)` on, as does the data of a `bytes` or
+//! `string` 32 bytes or longer. Nested mappings and structs chain those:
+//! `balances[a][b].field` is `p.mapping(a).mapping(b).offset(field)`.
+
+use std::fmt;
+
+use crate::hex::impl_hex;
+use crate::keccak256;
+
+/// A 32-byte storage slot, big-endian as `eth_getStorageAt` takes it.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Slot(pub [u8; 32]);
+
+impl_hex!(Slot, self => self.0);
+
+impl Slot {
+    /// The slot numbered `index`, as the compiler assigns state variables.
+    pub fn new(index: u128) -> Slot {
+        let mut slot = [0u8; 32];
+        slot[16..].copy_from_slice(&index.to_be_bytes());
+        Slot(slot)
+    }
+
+    /// The value slot for `key` in a mapping declared here. Value-type keys
+    /// are passed as their ABI word: integers and addresses left-padded,
+    /// `bytesN` right-padded.
+    pub fn mapping(self, key: &[u8; 32]) -> Slot {
+        let mut preimage = [0u8; 64];
+        preimage[..32].copy_from_slice(key);
+        preimage[32..].copy_from_slice(&self.0);
+        Slot(keccak256(&preimage))
+    }
+
+    /// [`Slot::mapping`] for an `address` key.
+    pub fn mapping_address(self, address: &[u8; 20]) -> Slot {
+        let mut key = [0u8; 32];
+        key[12..].copy_from_slice(address);
+        self.mapping(&key)
+    }
+
+    /// [`Slot::mapping`] for an unsigned integer key.
+    pub fn mapping_uint(self, key: u128) -> Slot {
+        self.mapping(&Slot::new(key).0)
+    }
+
+    /// The value slot for a `bytes` or `string` key, which is hashed
+    /// without padding.
+    pub fn mapping_bytes(self, key: &[u8]) -> Slot {
+        let mut preimage = key.to_vec();
+        preimage.extend_from_slice(&self.0);
+        Slot(keccak256(&preimage))
+    }
+
+    /// Where the elements of a dynamic array declared here start, or the
+    /// data of a long `bytes` or `string`.
+    pub fn data(self) -> Slot {
+        Slot(keccak256(&self.0))
+    }
+
+    /// The first slot of element `index` of a dynamic array declared here
+    /// whose elements take `element_slots` slots each. Elements smaller
+    /// than a slot are packed several to a slot; locate those with
+    /// `index / per_slot` and an offset within the slot instead.
+    pub fn array_element(self, index: u128, element_slots: u128) -> Slot {
+        self.data().offset(index.wrapping_mul(element_slots))
+    }
+
+    /// The slot `count` after this one, e.g. a struct member's, wrapping
+    /// modulo 2^256 as the EVM does.
+    pub fn offset(self, count: u128) -> Slot {
+        let mut slot = self.0;
+        let mut carry = count;
+        for byte in slot.iter_mut().rev() {
+            if carry == 0 {
+                break;
+            }
+            let sum = u128::from(*byte) + (carry & 0xff);
+            *byte = sum as u8;
+            carry = (carry >> 8) + (sum >> 8);
+        }
+        Slot(slot)
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+impl fmt::Debug for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Slot({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Well-known slots come out right, keys are padded as Solidity pads
+    /// them, and offsets carry across bytes and wrap at 2^256.
+    #[test]
+    fn slots_follow_the_solidity_layout() {
+        assert_eq!(
+            Slot::new(0).data().to_string(),
+            "0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+        );
+        assert_eq!(
+            Slot::new(1).data().to_string(),
+            "0xb10e2d527612073b26eecdfd717e6a320cf44b4afac2b0732d9fcbe2b7fa0cf6"
+        );
+        assert_eq!(
+            Slot::new(0).mapping_uint(0).to_string(),
+            "0xad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+        );
+
+        let owner = [0x5a; 20];
+        let spender = [0xa5; 20];
+        let mut preimage = [0u8; 64];
+        preimage[12..32].copy_from_slice(&owner);
+        preimage[63] = 3;
+        let inner = keccak256(&preimage);
+        let mut preimage = [0u8; 64];
+        preimage[12..32].copy_from_slice(&spender);
+        preimage[32..].copy_from_slice(&inner);
+        assert_eq!(
+            Slot::new(3)
+                .mapping_address(&owner)
+                .mapping_address(&spender),
+            Slot(keccak256(&preimage))
+        );
+        assert_eq!(
+            Slot::new(2).mapping_bytes(b"key"),
+            Slot(keccak256(&[&b"key"[..], &Slot::new(2).0].concat()))
+        );
+
+        assert_eq!(Slot::new(0xff).offset(1), Slot::new(0x100));
+        assert_eq!(Slot::new(u128::MAX).offset(1).0[15], 1);
+        assert_eq!(Slot([0xff; 32]).offset(2), Slot::new(1));
+        assert_eq!(
+            Slot::new(7).array_element(5, 2),
+            Slot::new(7).data().offset(10)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Merkle Patricia Trie roots, as in the Yellow Paper's appendix D.
+//!
+//! Keys are split into nibbles and the trie is built bottom-up from the
+//! sorted pairs: one remaining pair becomes a leaf, pairs sharing more
+//! nibbles become an extension over the branch where they diverge, and a
+//! branch has sixteen children plus the value of a key ending there. A
+//! node is RLP-encoded, and its parent embeds that encoding when it is
+//! under 32 bytes and its Keccak-256 otherwise; the root is always hashed.
+//! Empty values mean "absent", as in the state trie, so those pairs are
+//! dropped, and a repeated key keeps its last value.
+
+use std::collections::BTreeMap;
+
+use super::Rlp;
+use crate::keccak256;
+
+/// The root of a trie with no entries, `keccak256(rlp(""))`.
+pub const EMPTY_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+/// The root of the trie mapping each key to its value.
+pub fn trie_root<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    pairs: impl IntoIterator<Item = (K, V)>,
+) -> [u8; 32] {
+    let mut entries = BTreeMap::new();
+    for (key, value) in pairs {
+        let value = value.as_ref();
+        let nibbles: Vec<u8> = key
+            .as_ref()
+            .iter()
+            .flat_map(|&b| [b >> 4, b & 0xf])
+            .collect();
+        if value.is_empty() {
+            entries.remove(&nibbles);
+        } else {
+            entries.insert(nibbles, value.to_vec());
+        }
+    }
+    if entries.is_empty() {
+        return EMPTY_ROOT;
+    }
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = entries.into_iter().collect();
+    keccak256(&node(&entries, 0))
+}
+
+/// The root of a secure trie, like the state and storage tries, whose
+/// keys are the Keccak-256 of the given ones.
+pub fn secure_trie_root<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    pairs: impl IntoIterator<Item = (K, V)>,
+) -> [u8; 32] {
+    trie_root(
+        pairs
+            .into_iter()
+            .map(|(key, value)| (keccak256(key.as_ref()), value)),
+    )
+}
+
+/// The root of a trie keyed by `rlp(index)`, like a block's transactions
+/// and receipts roots, with `values` already encoded.
+pub fn ordered_trie_root<V: AsRef<[u8]>>(values: impl IntoIterator<Item = V>) -> [u8; 32] {
+    trie_root(values.into_iter().enumerate().map(|(index, value)| {
+        let bytes = index.to_be_bytes();
+        let minimal = &bytes[bytes.iter().take_while(|&&b| b == 0).count()..];
+        let mut key = Vec::new();
+        Rlp::Bytes(minimal).encode(&mut key);
+        (key, value)
+    }))
+}
+
+/// The RLP encoding of the node over `entries`, sorted with distinct
+/// nibble keys, whose first `depth` nibbles are consumed by its parents.
+fn node(entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    if let [(key, value)] = entries {
+        let path = hex_prefix(&key[depth..], true);
+        Rlp::List(vec![Rlp::Bytes(&path), Rlp::Bytes(value)]).encode(&mut out);
+        return out;
+    }
+    // Sorted keys share a prefix exactly when the first and last do.
+    let (first, last) = (&entries[0].0, &entries[entries.len() - 1].0);
+    let shared = first[depth..]
+        .iter()
+        .zip(&last[depth..])
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shared > 0 {
+        let path = hex_prefix(&first[depth..depth + shared], false);
+        let child = reference(node(entries, depth + shared));
+        Rlp::List(vec![Rlp::Bytes(&path), child.as_rlp()]).encode(&mut out);
+        return out;
+    }
+
+    // A key ending here sorts first; the rest split on their next nibble.
+    let (value, rest) = match entries.split_first() {
+        Some(((key, value), rest)) if key.len() == depth => (value.as_slice(), rest),
+        _ => (&[][..], entries),
+    };
+    let mut children: Vec<Reference> = Vec::with_capacity(16);
+    let mut start = 0;
+    for nibble in 0..16u8 {
+        let end = start + rest[start..].partition_point(|(key, _)| key[depth] == nibble);
+        children.push(if start == end {
+            Reference::Empty
+        } else {
+            reference(node(&rest[start..end], depth + 1))
+        });
+        start = end;
+    }
+    let mut items: Vec<Rlp> = children.iter().map(Reference::as_rlp).collect();
+    items.push(Rlp::Bytes(value));
+    Rlp::List(items).encode(&mut out);
+    out
+}
+
+/// How a parent refers to a child node.
+enum Reference {
+    Empty,
+    Inline(Vec<u8>),
+    Hash([u8; 32]),
+}
+
+impl Reference {
+    fn as_rlp(&self) -> Rlp<'_> {
+        match self {
+            Reference::Empty => Rlp::Bytes(&[]),
+            Reference::Inline(encoded) => Rlp::Encoded(encoded),
+            Reference::Hash(hash) => Rlp::Bytes(hash),
+        }
+    }
+}
+
+fn reference(encoded: Vec<u8>) -> Reference {
+    if encoded.len() < 32 {
+        Reference::Inline(encoded)
+    } else {
+        Reference::Hash(keccak256(&encoded))
+    }
+}
+
+/// The hex-prefix encoding of a nibble path: a flag nibble (2 for a leaf,
+/// plus 1 for an odd length), a zero pad nibble when the length is even,
+/// then the path, packed two nibbles to a byte.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 } + (nibbles.len() % 2) as u8;
+    let mut padded = vec![flag];
+    if nibbles.len().is_multiple_of(2) {
+        padded.push(0);
+    }
+    padded.extend_from_slice(nibbles);
+    padded
+        .chunks_exact(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::path::Path;
+
+    /// A fixture string: `0x` hex, or else UTF-8.
+    fn bytes(text: &str) -> Vec<u8> {
+        match text.strip_prefix("0x") {
+            Some(digits) => hex::decode(digits).unwrap(),
+            None => text.as_bytes().to_vec(),
+        }
+    }
+
+    /// The `trieanyorder` fixtures from ethereum/tests give their roots
+    /// whichever order the pairs arrive in.
+    #[test]
+    fn fixture_roots_match() {
+        let path = Path::new(super::super::ETHEREUM_DIR).join("trie/trieanyorder.json");
+        let fixtures: Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        for (name, fixture) in fixtures.as_object().unwrap() {
+            let pairs: Vec<(Vec<u8>, Vec<u8>)> = fixture["in"]
+                .as_object()
+                .unwrap()
+                .iter()
+                .map(|(key, value)| (bytes(key), bytes(value.as_str().unwrap())))
+                .collect();
+            let root = format!("0x{}", hex::encode(trie_root(pairs.clone())));
+            assert_eq!(root, fixture["root"].as_str().unwrap(), "{}", name);
+            let mut reversed = pairs;
+            reversed.reverse();
+            assert_eq!(
+                format!("0x{}", hex::encode(trie_root(reversed))),
+                root,
+                "{}",
+                name
+            );
+        }
+    }
+
+    /// The empty root is `keccak256(rlp(""))`, empty values delete, and the
+    /// secure and ordered tries hash and RLP-encode their keys.
+    #[test]
+    fn variants_key_as_documented() {
+        assert_eq!(EMPTY_ROOT, keccak256(&[0x80]));
+        assert_eq!(trie_root(Vec::<(&[u8], &[u8])>::new()), EMPTY_ROOT);
+        assert_eq!(trie_root([(&b"a"[..], &b"x"[..]), (b"a", b"")]), EMPTY_ROOT);
+        assert_eq!(
+            trie_root([(&b"a"[..], &b"x"[..]), (b"b", b"y"), (b"a", b"")]),
+            trie_root([(b"b", b"y")])
+        );
+        assert_eq!(
+            secure_trie_root([(&b"dog"[..], &b"puppy"[..]), (b"doe", b"reindeer")]),
+            trie_root([
+                (keccak256(b"dog"), &b"puppy"[..]),
+                (keccak256(b"doe"), b"reindeer")
+            ])
+        );
+        let values: Vec<Vec<u8>> = (0..200u32).map(|i| vec![i as u8 + 1; 40]).collect();
+        let keyed = values.iter().enumerate().map(|(index, value)| {
+            let key = match index {
+                0 => vec![0x80],
+                1..=0x7f => vec![index as u8],
+                _ => vec![0x81, index as u8],
+            };
+            (key, value)
+        });
+        assert_eq!(ordered_trie_root(&values), trie_root(keyed));
+        assert_eq!(ordered_trie_root(Vec::<Vec<u8>>::new()), EMPTY_ROOT);
+    }
+}
@@ -0,0 +1,494 @@
+//! C ABI exports, so the Mojo side can call the baseline in-process.
+//!
+//! The library also builds as a `cdylib`. `include/keccak256_rust.h` is
+//! generated from this file with cbindgen (names as in `cbindgen.toml`).
+//! With `--features cbindgen` the build regenerates it into `OUT_DIR`, and
+//! a test fails if the checked-in copy has drifted from the exports. Every
+//! function returns [`FFI_OK`] on success or a negative status, and never
+//! unwinds across the boundary.
+
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use crate::bench::backend::Backend;
+use crate::bench::warmup::Warmup;
+use crate::bench::{self, BenchConfig};
+use crate::keccak256_into;
+
+/// Success.
+pub const FFI_OK: i32 = 0;
+/// A pointer that must not be null was.
+pub const FFI_NULL_POINTER: i32 = -1;
+/// The benchmark config was rejected.
+pub const FFI_INVALID_CONFIG: i32 = -2;
+/// The benchmark failed or panicked while running.
+pub const FFI_BENCH_FAILED: i32 = -3;
+
+/// Bytes in [`FfiError::message`], including the terminating NUL.
+pub const FFI_ERROR_LEN: usize = 256;
+
+/// Write the Keccak-256 digest of `len` bytes at `data` to the 32 bytes
+/// at `out`. `data` may be null when `len` is 0.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes and `out` for writes of
+/// 32 bytes, and the two must not overlap.
+#[no_mangle]
+pub unsafe extern "C" fn keccak256_ffi(data: *const u8, len: usize, out: *mut u8) -> i32 {
+    if out.is_null() || (data.is_null() && len > 0) {
+        return FFI_NULL_POINTER;
+    }
+    let message = if len == 0 {
+        &[][..]
+    } else {
+        // SAFETY: the caller guarantees `len` readable bytes at `data`.
+        unsafe { slice::from_raw_parts(data, len) }
+    };
+    // SAFETY: the caller guarantees 32 writable bytes at `out`, apart from
+    // the message, and `[u8; 32]` has alignment 1.
+    keccak256_into(message, unsafe { &mut *out.cast::<[u8; 32]>() });
+    FFI_OK
+}
+
+/// One message for [`keccak256_batch_ffi`]: `len` bytes at `ptr`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FfiMessage {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl FfiMessage {
+    /// # Safety
+    ///
+    /// As for the `data` and `len` of [`keccak256_ffi`].
+    unsafe fn bytes<'a>(self) -> Option<&'a [u8]> {
+        match (self.ptr.is_null(), self.len) {
+            (_, 0) => Some(&[]),
+            (true, _) => None,
+            // SAFETY: the caller guarantees `len` readable bytes at `ptr`.
+            (false, len) => Some(unsafe { slice::from_raw_parts(self.ptr, len) }),
+        }
+    }
+}
+
+/// Hash `count` messages in one call, so callers pay for crossing the
+/// boundary once per batch: the digest of `messages[i]` goes to the 32
+/// bytes at `out + 32 * i`. Nothing is written unless every message is
+/// valid.
+///
+/// # Safety
+///
+/// `messages` must be valid for reads of `count` entries, each as for
+/// [`keccak256_ffi`], and `out` valid for writes of `32 * count` bytes
+/// that overlap none of the messages.
+#[no_mangle]
+pub unsafe extern "C" fn keccak256_batch_ffi(
+    messages: *const FfiMessage,
+    count: usize,
+    out: *mut u8,
+) -> i32 {
+    if count == 0 {
+        return FFI_OK;
+    }
+    if messages.is_null() || out.is_null() {
+        return FFI_NULL_POINTER;
+    }
+    // SAFETY: the caller guarantees `count` entries at `messages`.
+    let messages = unsafe { slice::from_raw_parts(messages, count) };
+    // SAFETY: each message is valid as documented above.
+    if messages
+        .iter()
+        .any(|message| unsafe { message.bytes() }.is_none())
+    {
+        return FFI_NULL_POINTER;
+    }
+    // SAFETY: the caller guarantees `32 * count` writable bytes at `out`,
+    // and `[u8; 32]` has alignment 1.
+    let digests = unsafe { slice::from_raw_parts_mut(out.cast::<[u8; 32]>(), count) };
+    for (message, digest) in messages.iter().zip(digests) {
+        // SAFETY: checked non-null above; validity is the caller's.
+        let bytes = unsafe { message.bytes() }.expect("checked above");
+        keccak256_into(bytes, digest);
+    }
+    FFI_OK
+}
+
+/// Hash implementations a benchmark can time; the values of
+/// [`FfiBenchConfig::backend`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FfiBackend {
+    TinyKeccak = 0,
+    Native = 1,
+    NativeSimd = 2,
+    Sha3 = 3,
+    Blake3 = 4,
+    NativeX4 = 5,
+    NativeX8 = 6,
+    NativeX2 = 7,
+    NativeAuto = 8,
+    NativeAsm = 9,
+}
+
+impl FfiBackend {
+    const ALL: [(FfiBackend, Backend); 10] = [
+        (FfiBackend::TinyKeccak, Backend::TinyKeccak),
+        (FfiBackend::Native, Backend::Native),
+        (FfiBackend::NativeSimd, Backend::NativeSimd),
+        (FfiBackend::Sha3, Backend::Sha3),
+        (FfiBackend::Blake3, Backend::Blake3),
+        (FfiBackend::NativeX4, Backend::NativeX4),
+        (FfiBackend::NativeX8, Backend::NativeX8),
+        (FfiBackend::NativeX2, Backend::NativeX2),
+        (FfiBackend::NativeAuto, Backend::NativeAuto),
+        (FfiBackend::NativeAsm, Backend::NativeAsm),
+    ];
+
+    fn from_raw(value: u32) -> Option<Backend> {
+        Self::ALL
+            .iter()
+            .find(|(ffi, _)| *ffi as u32 == value)
+            .map(|&(_, backend)| backend)
+    }
+
+    fn raw(backend: Backend) -> u32 {
+        Self::ALL
+            .iter()
+            .find(|&&(_, other)| other == backend)
+            .map(|&(ffi, _)| ffi as u32)
+            .expect("every backend has an FFI value")
+    }
+}
+
+/// The benchmark settings a C caller can choose; see
+/// [`keccak256_bench_config_default`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FfiBenchConfig {
+    /// An [`FfiBackend`] value.
+    pub backend: u32,
+    pub messages: usize,
+    pub rounds: usize,
+    pub min_len: usize,
+    pub max_len: usize,
+    pub stride: usize,
+    pub trials: usize,
+    pub threads: usize,
+    /// Untimed rounds before the first trial, unless `adaptive_warmup`.
+    pub warmup_rounds: usize,
+    /// Nonzero to warm up until throughput settles instead of
+    /// `warmup_rounds`. A byte rather than `bool`, since C may store any
+    /// value in it.
+    pub adaptive_warmup: u8,
+    /// Nonzero for pseudorandom message bytes from `seed`, instead of the
+    /// legacy counter pattern.
+    pub use_seed: u8,
+    pub seed: u64,
+}
+
+impl From<&BenchConfig> for FfiBenchConfig {
+    fn from(config: &BenchConfig) -> Self {
+        FfiBenchConfig {
+            backend: FfiBackend::raw(config.backend),
+            messages: config.messages,
+            rounds: config.rounds,
+            min_len: config.min_len,
+            max_len: config.max_len,
+            stride: config.stride,
+            trials: config.trials,
+            threads: config.threads,
+            warmup_rounds: match config.warmup {
+                Warmup::Fixed(rounds) => rounds,
+                Warmup::Adaptive => 0,
+            },
+            adaptive_warmup: u8::from(config.warmup == Warmup::Adaptive),
+            use_seed: u8::from(config.seed.is_some()),
+            seed: config.seed.unwrap_or(0),
+        }
+    }
+}
+
+impl FfiBenchConfig {
+    fn to_config(self) -> Result<BenchConfig, String> {
+        let backend = FfiBackend::from_raw(self.backend)
+            .ok_or_else(|| format!("unknown backend {}", self.backend))?;
+        let config = BenchConfig {
+            backend,
+            messages: self.messages,
+            rounds: self.rounds,
+            min_len: self.min_len,
+            max_len: self.max_len,
+            stride: self.stride,
+            trials: self.trials,
+            threads: self.threads,
+            warmup: if self.adaptive_warmup != 0 {
+                Warmup::Adaptive
+            } else {
+                Warmup::Fixed(self.warmup_rounds)
+            },
+            seed: (self.use_seed != 0).then_some(self.seed),
+            ..BenchConfig::default()
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// The median trial of a benchmark run.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FfiBenchResult {
+    pub seconds: f64,
+    pub hashes_per_second: f64,
+    pub mb_per_second: f64,
+    /// Input bytes hashed per trial.
+    pub bytes: u64,
+    pub checksum: u64,
+    /// Set when the trials varied more than the noise threshold.
+    pub noisy: bool,
+}
+
+/// Why a call failed: its status and a NUL-terminated UTF-8 message,
+/// truncated to fit.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct FfiError {
+    pub code: i32,
+    pub message: [c_char; FFI_ERROR_LEN],
+}
+
+impl FfiError {
+    fn new(code: i32, message: &str) -> FfiError {
+        let mut error = FfiError {
+            code,
+            message: [0; FFI_ERROR_LEN],
+        };
+        let mut end = message.len().min(FFI_ERROR_LEN - 1);
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        for (slot, &byte) in error.message.iter_mut().zip(&message.as_bytes()[..end]) {
+            *slot = byte as c_char;
+        }
+        error
+    }
+
+    /// The message up to its NUL.
+    pub fn message(&self) -> String {
+        let bytes: Vec<u8> = self
+            .message
+            .iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as u8)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+/// Fill `out` with the harness's default benchmark settings.
+///
+/// # Safety
+///
+/// `out` must be valid for writes of one [`FfiBenchConfig`].
+#[no_mangle]
+pub unsafe extern "C" fn keccak256_bench_config_default(out: *mut FfiBenchConfig) -> i32 {
+    if out.is_null() {
+        return FFI_NULL_POINTER;
+    }
+    // SAFETY: the caller guarantees `out` is writable.
+    unsafe { out.write(FfiBenchConfig::from(&BenchConfig::default())) };
+    FFI_OK
+}
+
+/// Benchmark `config` and write its median trial to `result`. On failure
+/// the status is also written to `error`, with a message, unless `error`
+/// is null; `result` is then left untouched.
+///
+/// # Safety
+///
+/// `config` must be valid for reads of one [`FfiBenchConfig`], `result`
+/// for writes of one [`FfiBenchResult`], and `error`, when not null, for
+/// writes of one [`FfiError`].
+#[no_mangle]
+pub unsafe extern "C" fn keccak256_bench_ffi(
+    config: *const FfiBenchConfig,
+    result: *mut FfiBenchResult,
+    error: *mut FfiError,
+) -> i32 {
+    let outcome = if config.is_null() || result.is_null() {
+        Err((
+            FFI_NULL_POINTER,
+            String::from("config and result must not be null"),
+        ))
+    } else {
+        // SAFETY: the caller guarantees `config` is readable.
+        let config = unsafe { config.read() };
+        config
+            .to_config()
+            .map_err(|err| (FFI_INVALID_CONFIG, err))
+            .and_then(|config| {
+                panic::catch_unwind(AssertUnwindSafe(|| bench::run(config)))
+                    .unwrap_or_else(|_| Err(String::from("the benchmark panicked")))
+                    .map_err(|err| (FFI_BENCH_FAILED, err))
+            })
+    };
+    match outcome {
+        Ok(report) => {
+            let median = &report.results[0];
+            // SAFETY: the caller guarantees `result` is writable.
+            unsafe {
+                result.write(FfiBenchResult {
+                    seconds: median.seconds,
+                    hashes_per_second: median.hashes_per_second,
+                    mb_per_second: median.mb_per_second,
+                    bytes: median.bytes,
+                    checksum: median.checksum,
+                    noisy: median.noisy,
+                })
+            };
+            FFI_OK
+        }
+        Err((code, message)) => {
+            if !error.is_null() {
+                // SAFETY: the caller guarantees a non-null `error` is writable.
+                unsafe { error.write(FfiError::new(code, &message)) };
+            }
+            code
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keccak256;
+    use std::ptr;
+
+    /// The checked-in header is what cbindgen generates from this file;
+    /// copy `$OUT_DIR/keccak256_rust.h` over it after changing an export.
+    #[cfg(feature = "cbindgen")]
+    #[test]
+    fn header_matches_exports() {
+        let path = concat!(env!("OUT_DIR"), "/keccak256_rust.h");
+        let generated = std::fs::read_to_string(path).expect("build.rs wrote the header");
+        let committed = include_str!("../include/keccak256_rust.h");
+        assert!(
+            generated == committed,
+            "include/keccak256_rust.h is stale; copy {}",
+            path
+        );
+    }
+
+    /// The exports match the safe API and reject null pointers.
+    #[test]
+    fn exports_match_safe_api() {
+        let mut out = [0u8; 32];
+        let status = unsafe { keccak256_ffi(b"abc".as_ptr(), 3, out.as_mut_ptr()) };
+        assert_eq!((status, out), (FFI_OK, keccak256(b"abc")));
+        let status = unsafe { keccak256_ffi(ptr::null(), 0, out.as_mut_ptr()) };
+        assert_eq!((status, out), (FFI_OK, keccak256(b"")));
+        assert_eq!(
+            unsafe { keccak256_ffi(ptr::null(), 1, out.as_mut_ptr()) },
+            FFI_NULL_POINTER
+        );
+    }
+
+    /// A batch writes every digest back to back, and one bad entry fails
+    /// the whole call untouched.
+    #[test]
+    fn batch_export_hashes_every_message() {
+        let messages: [&[u8]; 3] = [b"", b"abc", &[7u8; 300]];
+        let mut entries: Vec<FfiMessage> = messages
+            .iter()
+            .map(|message| FfiMessage {
+                ptr: message.as_ptr(),
+                len: message.len(),
+            })
+            .collect();
+        let mut digests = [0u8; 96];
+        let status = unsafe { keccak256_batch_ffi(entries.as_ptr(), 3, digests.as_mut_ptr()) };
+        assert_eq!(status, FFI_OK);
+        for (message, digest) in messages.iter().zip(digests.chunks(32)) {
+            assert_eq!(digest, keccak256(message));
+        }
+
+        entries[2].ptr = ptr::null();
+        let mut untouched = [0u8; 96];
+        let status = unsafe { keccak256_batch_ffi(entries.as_ptr(), 3, untouched.as_mut_ptr()) };
+        assert_eq!((status, untouched), (FFI_NULL_POINTER, [0u8; 96]));
+        assert_eq!(
+            unsafe { keccak256_batch_ffi(ptr::null(), 0, ptr::null_mut()) },
+            FFI_OK
+        );
+    }
+
+    /// The defaults, shrunk to a quick fixed-warmup run.
+    fn small_config() -> FfiBenchConfig {
+        let mut config = FfiBenchConfig::from(&BenchConfig::default());
+        assert_eq!(
+            unsafe { keccak256_bench_config_default(&mut config) },
+            FFI_OK
+        );
+        FfiBenchConfig {
+            backend: FfiBackend::Native as u32,
+            messages: 16,
+            rounds: 2,
+            adaptive_warmup: 0,
+            warmup_rounds: 1,
+            ..config
+        }
+    }
+
+    /// A benchmark through the C structs reports the checksum of the same
+    /// run through the Rust API, and bad configs come back as errors.
+    #[test]
+    fn bench_export_runs_and_reports_errors() {
+        let mut config = small_config();
+        let mut result = FfiBenchResult::default();
+        let status = unsafe { keccak256_bench_ffi(&config, &mut result, ptr::null_mut()) };
+        assert_eq!(status, FFI_OK);
+        let expected = bench::run(config.to_config().unwrap()).unwrap();
+        assert_eq!(result.checksum, expected.results[0].checksum);
+        assert!(result.hashes_per_second > 0.0);
+
+        config.min_len = 600;
+        let mut error = FfiError::new(0, "");
+        let status = unsafe { keccak256_bench_ffi(&config, &mut result, &mut error) };
+        assert_eq!(
+            (status, error.code),
+            (FFI_INVALID_CONFIG, FFI_INVALID_CONFIG)
+        );
+        assert!(error.message().contains("min_len"), "{}", error.message());
+        config.backend = FfiBackend::ALL.len() as u32;
+        let status = unsafe { keccak256_bench_ffi(&config, &mut result, &mut error) };
+        assert_eq!(
+            (status, error.message()),
+            (
+                FFI_INVALID_CONFIG,
+                format!("unknown backend {}", config.backend)
+            )
+        );
+        assert_eq!(FfiError::new(1, &"é".repeat(200)).message().len(), 254);
+    }
+
+    /// Any nonzero flag byte from C means true.
+    #[test]
+    fn flag_bytes_treat_nonzero_as_true() {
+        let config = FfiBenchConfig {
+            adaptive_warmup: 2,
+            use_seed: 0xff,
+            seed: 7,
+            ..small_config()
+        };
+        let converted = config.to_config().unwrap();
+        assert_eq!(
+            (converted.warmup, converted.seed),
+            (Warmup::Adaptive, Some(7))
+        );
+        let round_trip = FfiBenchConfig::from(&converted);
+        assert_eq!((round_trip.adaptive_warmup, round_trip.use_seed), (1, 1));
+    }
+}
@@ -0,0 +1,296 @@
+//! Precomputed multiples of the generator, and their on-disk form.
+//!
+//! A [`GeneratorTable`] with `w`-bit windows holds `d * 2^(w j) * G` for
+//! every window `j` and digit `d` from 1 to `2^w - 1`, so `k * G` is one
+//! addition per nonzero window of `k` and no doublings.
+//! [`AffinePoint::mul`] sends every multiple of [`G`] through the one
+//! [`generator_table`], built on first use. Building it costs a few
+//! multiplications, which a short-lived CLI or a per-process FFI caller
+//! pays on every start; the `generator-table` binary writes it once, and
+//! [`GeneratorTable::from_bytes`] borrows it
+//! from the blob without copying, whether the blob was embedded with
+//! `include_bytes!` or memory-mapped. The GLV constants the tables are
+//! built alongside are stored too, so a blob from another build of the
+//! curve parameters is refused. All integers are big-endian.
+//!
+//! ```text
+//!    0 magic "secpGtab"   8 u8 version   9 u8 window bits
+//!   10 BETA (32 bytes)   42 LAMBDA (32 bytes)
+//!   74 points, x || y (64 bytes each), window by window, digit 1 first
+//!  end - 32  Keccak-256 of everything before it
+//! ```
+//!
+//! Like the rest of the Rust curve code this runs in variable time.
+
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use crate::keccak256;
+use crate::secp256k1::{AffinePoint, FieldElement, Jacobian, Scalar, BETA, G, LAMBDA};
+use crate::Error;
+
+pub const MAGIC: [u8; 8] = *b"secpGtab";
+pub const VERSION: u8 = 1;
+
+/// The window width `generator-table` writes by default: 64 windows of
+/// 15 points, 60 KiB.
+pub const DEFAULT_WINDOW_BITS: u8 = 4;
+
+const HEADER_LEN: usize = 74;
+const POINT_LEN: usize = 64;
+
+/// Multiples of [`G`] by every digit of every window, as the module
+/// describes, either built here or borrowed from a serialized blob.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratorTable<'a> {
+    window_bits: u8,
+    /// The points as serialized, `x || y` each.
+    points: Cow<'a, [u8]>,
+}
+
+/// The table [`AffinePoint::mul`] uses for multiples of [`G`], computed
+/// with [`DEFAULT_WINDOW_BITS`] the first time it is needed and shared by
+/// every thread after that.
+pub fn generator_table() -> &'static GeneratorTable<'static> {
+    static TABLE: OnceLock<GeneratorTable<'static>> = OnceLock::new();
+    TABLE.get_or_init(|| GeneratorTable::compute(DEFAULT_WINDOW_BITS))
+}
+
+fn windows(window_bits: u8) -> usize {
+    256_usize.div_ceil(usize::from(window_bits))
+}
+
+fn digits(window_bits: u8) -> usize {
+    (1 << window_bits) - 1
+}
+
+impl GeneratorTable<'static> {
+    /// Build the table for `window_bits`-bit windows, inverting every
+    /// point's `Z` with one field inversion between them.
+    ///
+    /// # Panics
+    ///
+    /// If `window_bits` is not from 1 to 8.
+    pub fn compute(window_bits: u8) -> GeneratorTable<'static> {
+        assert!(
+            (1..=8).contains(&window_bits),
+            "window bits must be 1 to 8, not {}",
+            window_bits
+        );
+        let mut points = Vec::with_capacity(windows(window_bits) * digits(window_bits));
+        let mut base = Jacobian::from(&G);
+        for _ in 0..windows(window_bits) {
+            let mut multiple = base;
+            for _ in 0..digits(window_bits) {
+                points.push(multiple);
+                multiple = multiple.add(&base);
+            }
+            base = multiple;
+        }
+
+        // prefix[i] is the product of the first i Zs.
+        let mut prefix = Vec::with_capacity(points.len() + 1);
+        prefix.push(FieldElement::ONE);
+        for point in &points {
+            let last = prefix[prefix.len() - 1];
+            prefix.push(last.mul(&point.z));
+        }
+        let mut inverse = prefix[points.len()]
+            .invert()
+            .expect("multiples below n are finite");
+        let mut bytes = vec![0u8; points.len() * POINT_LEN];
+        for (i, point) in points.iter().enumerate().rev() {
+            let z_inverse = inverse.mul(&prefix[i]);
+            inverse = inverse.mul(&point.z);
+            let z2 = z_inverse.square();
+            let entry = &mut bytes[i * POINT_LEN..(i + 1) * POINT_LEN];
+            entry[..32].copy_from_slice(&point.x.mul(&z2).to_bytes());
+            entry[32..].copy_from_slice(&point.y.mul(&z2).mul(&z_inverse).to_bytes());
+        }
+        GeneratorTable {
+            window_bits,
+            points: Cow::Owned(bytes),
+        }
+    }
+}
+
+impl<'a> GeneratorTable<'a> {
+    /// A table from its serialization, borrowing the points. The checksum
+    /// catches truncation and corruption, and every point must be a
+    /// curve point with coordinates below `p`. Neither proves the points
+    /// are the right multiples of `G`: the blob is trusted like the binary
+    /// that embeds or maps it.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<GeneratorTable<'a>, Error> {
+        let invalid = |reason| Err(Error::InvalidTable { reason });
+        if bytes.len() < HEADER_LEN + 32 || bytes[..8] != MAGIC {
+            return invalid("not a generator table");
+        }
+        if bytes[8] != VERSION {
+            return invalid("an unsupported version");
+        }
+        let window_bits = bytes[9];
+        if !(1..=8).contains(&window_bits) {
+            return invalid("window bits outside 1 to 8");
+        }
+        if bytes[10..42] != BETA.to_bytes() || bytes[42..74] != LAMBDA.to_bytes() {
+            return invalid("GLV constants from another build");
+        }
+        let expected_len = HEADER_LEN + windows(window_bits) * digits(window_bits) * POINT_LEN + 32;
+        if bytes.len() != expected_len {
+            return invalid("a length that does not match its window bits");
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - 32);
+        if keccak256(body)[..] != *checksum {
+            return invalid("a checksum mismatch");
+        }
+        for entry in body[HEADER_LEN..].chunks_exact(POINT_LEN) {
+            let coordinate =
+                |bytes: &[u8]| FieldElement::from_bytes(&bytes.try_into().expect("32 bytes"));
+            let (Some(x), Some(y)) = (coordinate(&entry[..32]), coordinate(&entry[32..])) else {
+                return invalid("a point off the curve");
+            };
+            if !(AffinePoint { x, y }).is_on_curve() {
+                return invalid("a point off the curve");
+            }
+        }
+        Ok(GeneratorTable {
+            window_bits,
+            points: Cow::Borrowed(&body[HEADER_LEN..]),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.points.len() + 32);
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.push(self.window_bits);
+        out.extend_from_slice(&BETA.to_bytes());
+        out.extend_from_slice(&LAMBDA.to_bytes());
+        out.extend_from_slice(&self.points);
+        let checksum = keccak256(&out);
+        out.extend_from_slice(&checksum);
+        out
+    }
+
+    pub fn window_bits(&self) -> u8 {
+        self.window_bits
+    }
+
+    /// `digit * 2^(w window) * G`, or `None` for digit zero, whose
+    /// multiple is the point at infinity.
+    ///
+    /// # Panics
+    ///
+    /// If `window` or `digit` is outside the table.
+    pub fn point(&self, window: usize, digit: usize) -> Option<AffinePoint> {
+        assert!(
+            window < windows(self.window_bits) && digit <= digits(self.window_bits),
+            "no digit {} in window {} of a {}-bit table",
+            digit,
+            window,
+            self.window_bits
+        );
+        let index = window * digits(self.window_bits) + digit.checked_sub(1)?;
+        let entry = &self.points[index * POINT_LEN..(index + 1) * POINT_LEN];
+        Some(AffinePoint {
+            x: FieldElement::from_reduced(entry[..32].try_into().expect("32 bytes")),
+            y: FieldElement::from_reduced(entry[32..].try_into().expect("32 bytes")),
+        })
+    }
+
+    /// `k * G`, or `None` for zero.
+    pub fn mul(&self, k: &Scalar) -> Option<AffinePoint> {
+        let w = usize::from(self.window_bits);
+        let bit = |i: usize| i < 256 && (k.to_bytes()[31 - i / 8] >> (i % 8)) & 1 == 1;
+        let mut acc = Jacobian::INFINITY;
+        for window in 0..windows(self.window_bits) {
+            let digit = (0..w)
+                .filter(|&b| bit(window * w + b))
+                .fold(0, |digit, b| digit | 1 << b);
+            if let Some(point) = self.point(window, digit) {
+                acc = acc.add(&Jacobian::from(&point));
+            }
+        }
+        acc.to_affine()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::{double_mul, P};
+
+    /// Table multiplication matches the double-and-add one across window
+    /// widths, including a width that does not divide 256, and
+    /// `AffinePoint::mul` by `G` agrees with both.
+    #[test]
+    #[cfg_attr(miri, ignore = "thousands of field multiplications")]
+    fn table_multiplication_matches_double_and_add() {
+        let scalars = [
+            Scalar::ONE,
+            LAMBDA,
+            Scalar::ONE.neg(),
+            Scalar::from_bytes(&[0x5a; 32]).unwrap(),
+        ];
+        for window_bits in [3, DEFAULT_WINDOW_BITS] {
+            let table = GeneratorTable::compute(window_bits);
+            assert_eq!(table.point(0, 1), Some(G));
+            assert_eq!(table.point(1, 0), None);
+            for k in &scalars {
+                let expected = double_mul(k, &G, &Scalar::ZERO, &G);
+                assert_eq!(table.mul(k), expected, "{} bits, {:?}", window_bits, k);
+                assert_eq!(G.mul(k), expected);
+            }
+            assert_eq!(table.mul(&Scalar::ZERO), None);
+        }
+        assert_eq!(
+            generator_table(),
+            &GeneratorTable::compute(DEFAULT_WINDOW_BITS)
+        );
+    }
+
+    /// A serialized table loads back borrowing the blob, and corrupted,
+    /// truncated or foreign blobs are refused.
+    #[test]
+    #[cfg_attr(miri, ignore = "thousands of field multiplications")]
+    fn blobs_round_trip_and_are_checked() {
+        let table = GeneratorTable::compute(2);
+        let bytes = table.to_bytes();
+        assert_eq!(bytes.len(), HEADER_LEN + 128 * 3 * POINT_LEN + 32);
+        let loaded = GeneratorTable::from_bytes(&bytes).unwrap();
+        assert!(matches!(loaded.points, Cow::Borrowed(_)));
+        assert_eq!(loaded, table);
+        assert_eq!(loaded.window_bits(), 2);
+
+        let reason = |bytes: &[u8]| match GeneratorTable::from_bytes(bytes) {
+            Err(Error::InvalidTable { reason }) => reason,
+            other => panic!("expected a table error, got {:?}", other.map(|_| ())),
+        };
+        let mut corrupt = bytes.clone();
+        corrupt[HEADER_LEN + 5] ^= 1;
+        assert_eq!(reason(&corrupt), "a checksum mismatch");
+        assert_eq!(
+            reason(&bytes[..bytes.len() - 1]),
+            "a length that does not match its window bits"
+        );
+        assert_eq!(reason(&bytes[..40]), "not a generator table");
+        let mut foreign = bytes.clone();
+        foreign[20] ^= 1;
+        assert_eq!(reason(&foreign), "GLV constants from another build");
+        let mut future = bytes.clone();
+        future[8] = 2;
+        assert_eq!(reason(&future), "an unsupported version");
+
+        // A consistent checksum over a point that is not on the curve.
+        let mut off_curve = bytes[..bytes.len() - 32].to_vec();
+        off_curve[HEADER_LEN + POINT_LEN - 1] ^= 1;
+        let checksum = keccak256(&off_curve);
+        off_curve.extend_from_slice(&checksum);
+        assert_eq!(reason(&off_curve), "a point off the curve");
+        let mut unreduced = bytes[..bytes.len() - 32].to_vec();
+        unreduced[HEADER_LEN..HEADER_LEN + 32].copy_from_slice(&P);
+        let checksum = keccak256(&unreduced);
+        unreduced.extend_from_slice(&checksum);
+        assert_eq!(reason(&unreduced), "a point off the curve");
+    }
+}
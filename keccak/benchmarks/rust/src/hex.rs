@@ -0,0 +1,186 @@
+//! Hex rendering shared by the crate's byte types.
+//!
+//! [`fmt_hex`] backs the `LowerHex` and `UpperHex` impls on [`Hex`],
+//! [`Scalar`](crate::secp256k1::Scalar), [`FieldElement`](crate::secp256k1::FieldElement),
+//! [`Signature`](crate::signature::Signature), [`Bloom`](crate::ethereum::bloom::Bloom)
+//! and [`Slot`](crate::ethereum::storage::Slot), so they all take the
+//! integer flags: `{:#x}` adds `0x`, `{:#066x}` zero-pads to 64 digits
+//! after it, and a precision, `{:.12x}`, keeps at most that many
+//! characters with `…` in the middle for logs. Digests and addresses are
+//! plain arrays; wrap them in [`Hex`] for the same formatting.
+//!
+//! With the `serde` feature, points and signatures serialize through
+//! `serialize_hex` and `deserialize_hex`: `0x`-prefixed lowercase hex
+//! out, and in, hex of exactly the expected length.
+
+use std::fmt;
+
+/// A byte slice that formats as hex: lower case for `{}` and `{:x}`,
+/// upper case for `{:X}`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hex<'a>(pub &'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_hex(self.0, false, f)
+    }
+}
+
+impl fmt::Debug for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hex({:#x})", self)
+    }
+}
+
+impl fmt::LowerHex for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_hex(self.0, false, f)
+    }
+}
+
+impl fmt::UpperHex for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_hex(self.0, true, f)
+    }
+}
+
+/// `LowerHex` and `UpperHex` for a type, over the bytes `$bytes` gives for
+/// `$self`.
+macro_rules! impl_hex {
+    ($type:ty, $self:ident => $bytes:expr) => {
+        impl std::fmt::LowerHex for $type {
+            fn fmt(&$self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                $crate::hex::fmt_hex(&$bytes, false, f)
+            }
+        }
+
+        impl std::fmt::UpperHex for $type {
+            fn fmt(&$self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                $crate::hex::fmt_hex(&$bytes, true, f)
+            }
+        }
+    };
+}
+pub(crate) use impl_hex;
+
+/// Write `bytes` as hex digits to `f`, honouring its `#`, `0`, width and
+/// precision flags as described in the module docs.
+pub fn fmt_hex(bytes: &[u8], upper: bool, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let digits = if upper {
+        ::hex::encode_upper(bytes)
+    } else {
+        ::hex::encode(bytes)
+    };
+    match f.precision() {
+        Some(max_len) => f.pad_integral(true, "0x", &shorten(&digits, max_len)),
+        None => f.pad_integral(true, "0x", &digits),
+    }
+}
+
+/// `bytes` as `0x`-prefixed lowercase hex, as the `serde` impls write
+/// them.
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_hex<S: serde::Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("0x{}", ::hex::encode(bytes)))
+}
+
+/// Exactly `N` bytes of hex, with or without `0x`, as the `serde` impls
+/// read them; `what` names the value in the error.
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_hex<'de, D: serde::Deserializer<'de>, const N: usize>(
+    deserializer: D,
+    what: &'static str,
+) -> Result<[u8; N], D::Error> {
+    use serde::de::Error as _;
+    use serde::Deserialize;
+
+    let text = String::deserialize(deserializer)?;
+    let bytes = crate::from_hex(&text).map_err(D::Error::custom)?;
+    bytes.as_slice().try_into().map_err(|_| {
+        D::Error::custom(crate::Error::InvalidLength {
+            what,
+            expected: N,
+            actual: bytes.len(),
+        })
+    })
+}
+
+/// `bytes` as lowercase hex, zero-padded on the left to at least `width`
+/// digits, as a big-endian number would be. Longer input is not cut.
+pub fn encode_padded(bytes: &[u8], width: usize) -> String {
+    format!("{:0>width$}", ::hex::encode(bytes), width = width)
+}
+
+/// `bytes` as lowercase hex in at most `max_len` characters: the leading
+/// and trailing digits around a `…` when the full text is longer.
+pub fn truncated(bytes: &[u8], max_len: usize) -> String {
+    shorten(&::hex::encode(bytes), max_len)
+}
+
+fn shorten(digits: &str, max_len: usize) -> String {
+    if digits.len() <= max_len {
+        return digits.to_string();
+    }
+    let Some(kept) = max_len.checked_sub(1) else {
+        return String::new();
+    };
+    let head = kept.div_ceil(2);
+    format!(
+        "{}…{}",
+        &digits[..head],
+        &digits[digits.len() - (kept - head)..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Padding, truncation and the format flags give the documented text.
+    #[test]
+    fn formats_follow_the_flags() {
+        let bytes = [0xde, 0xad, 0xbe, 0xef, 0x01];
+        assert_eq!(encode_padded(&[0xab], 8), "000000ab");
+        assert_eq!(encode_padded(&bytes, 4), "deadbeef01");
+        assert_eq!(truncated(&bytes, 10), "deadbeef01");
+        assert_eq!(truncated(&bytes, 9), "dead…ef01");
+        assert_eq!(truncated(&bytes, 6), "dea…01");
+        assert_eq!(truncated(&bytes, 1), "…");
+        assert_eq!(truncated(&bytes, 0), "");
+        assert_eq!(truncated(&[], 3), "");
+
+        let hex = Hex(&bytes);
+        assert_eq!(format!("{}", hex), "deadbeef01");
+        assert_eq!(format!("{:x}", hex), "deadbeef01");
+        assert_eq!(format!("{:X}", hex), "DEADBEEF01");
+        assert_eq!(format!("{:#x}", hex), "0xdeadbeef01");
+        assert_eq!(format!("{:#014x}", hex), "0x00deadbeef01");
+        assert_eq!(format!("{:>12x}", hex), "  deadbeef01");
+        assert_eq!(format!("{:#.7x}", hex), "0xdea…f01");
+        assert_eq!(format!("{:?}", hex), "Hex(0xdeadbeef01)");
+    }
+
+    /// The crate's byte types format like [`Hex`] over their bytes.
+    #[test]
+    fn newtypes_share_the_format() {
+        use crate::ethereum::storage::Slot;
+        use crate::secp256k1::Scalar;
+        use crate::signature::Signature;
+
+        let one = format!("0x{}1", "0".repeat(63));
+        assert_eq!(format!("{:#x}", Scalar::ONE), one);
+        assert_eq!(format!("{:?}", Scalar::ONE), format!("Scalar({})", one));
+        assert_eq!(format!("{}", Slot::new(1)), one);
+        assert_eq!(format!("{:?}", Slot::new(1)), format!("Slot({})", one));
+        let signature =
+            Signature::new(Scalar::from_bytes(&[0xab; 32]).unwrap(), Scalar::ONE).unwrap();
+        assert_eq!(
+            format!("{:x}", signature),
+            Hex(&signature.to_compact()).to_string()
+        );
+        assert_eq!(format!("{:.9X}", signature), "ABAB…0001");
+    }
+}
@@ -0,0 +1,512 @@
+//! SHA-3 known-answer files and a runner for them.
+//!
+//! Reads the `.rsp` layout NIST uses for SHA3VS and SHAKEVS: the
+//! byte-oriented ShortMsg, LongMsg and Monte files, plus SHAKE
+//! VariableOut. None of NIST's own files are vendored, so passing the
+//! samples here is not CAVP coverage. The algorithm and test come from
+//! the file name as NIST spells it (`SHA3_256ShortMsg.rsp`,
+//! `SHAKE128Monte.rsp`), and Monte Carlo files re-run the full
+//! 1000-iteration chain for every checkpoint. Messages whose bit length is
+//! not a multiple of 8 are skipped.
+//!
+//! The Keccak team's XKCP known-answer files (`ShortMsgKAT_SHA3-256.txt`,
+//! `LongMsgKAT_SHAKE128.txt`) use the same record layout, with SHAKE
+//! output under `Squeezed`, and are run the same way.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::bench::backend::Backend;
+use crate::native;
+
+/// Locally generated samples in the NIST layout; the official NIST files
+/// can be dropped in beside them.
+pub const SAMPLE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../vectors/kat-samples");
+
+/// XKCP known-answer samples and Keccak-f[1600] states; the official
+/// XKCP files can be dropped in beside them.
+pub const XKCP_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../vectors/xkcp");
+
+/// Backends with a SHA-3 implementation to check.
+pub const BACKENDS: [Backend; 2] = [Backend::Native, Backend::Sha3];
+
+/// Iterations between Monte Carlo checkpoints.
+const MONTE_ITERATIONS: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// SHA3 with this digest size in bits.
+    Sha3(usize),
+    Shake128,
+    Shake256,
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Algorithm::Sha3(bits) => write!(f, "SHA3-{}", bits),
+            Algorithm::Shake128 => f.write_str("SHAKE128"),
+            Algorithm::Shake256 => f.write_str("SHAKE256"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Test {
+    ShortMsg,
+    LongMsg,
+    Monte,
+    VariableOut,
+}
+
+impl fmt::Display for Test {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Test::ShortMsg => "ShortMsg",
+            Test::LongMsg => "LongMsg",
+            Test::Monte => "Monte",
+            Test::VariableOut => "VariableOut",
+        })
+    }
+}
+
+/// The algorithm and test a NIST or XKCP file name describes.
+pub fn identify(file_name: &str) -> Option<(Algorithm, Test)> {
+    if let Some(stem) = file_name.strip_suffix(".txt") {
+        return identify_xkcp(stem);
+    }
+    let stem = file_name.strip_suffix(".rsp").unwrap_or(file_name);
+    let (algorithm, rest) = if let Some(rest) = stem.strip_prefix("SHAKE128") {
+        (Algorithm::Shake128, rest)
+    } else if let Some(rest) = stem.strip_prefix("SHAKE256") {
+        (Algorithm::Shake256, rest)
+    } else {
+        let rest = stem.strip_prefix("SHA3_")?;
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let bits = rest[..digits].parse().ok()?;
+        if ![224, 256, 384, 512].contains(&bits) {
+            return None;
+        }
+        (Algorithm::Sha3(bits), &rest[digits..])
+    };
+    let test = match rest {
+        "ShortMsg" => Test::ShortMsg,
+        "LongMsg" => Test::LongMsg,
+        "Monte" => Test::Monte,
+        "VariableOut" if !matches!(algorithm, Algorithm::Sha3(_)) => Test::VariableOut,
+        _ => return None,
+    };
+    Some((algorithm, test))
+}
+
+/// `ShortMsgKAT_SHA3-256`, `LongMsgKAT_SHAKE128` and the like.
+fn identify_xkcp(stem: &str) -> Option<(Algorithm, Test)> {
+    let (test, name) = stem.split_once("KAT_")?;
+    let test = match test {
+        "ShortMsg" => Test::ShortMsg,
+        "LongMsg" => Test::LongMsg,
+        _ => return None,
+    };
+    let algorithm = match name {
+        "SHAKE128" => Algorithm::Shake128,
+        "SHAKE256" => Algorithm::Shake256,
+        _ => match name.strip_prefix("SHA3-")?.parse().ok()? {
+            bits @ (224 | 256 | 384 | 512) => Algorithm::Sha3(bits),
+            _ => return None,
+        },
+    };
+    Some((algorithm, test))
+}
+
+/// One blank-line separated group of `key = value` lines, with the
+/// bracketed `[key = value]` headers in force where it appears.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Record {
+    /// 1-based line of the record's first field.
+    pub line: usize,
+    pub header: BTreeMap<String, String>,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Record {
+    fn field(&self, key: &str) -> Result<&str, String> {
+        self.fields
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| format!("line {}: no {}", self.line, key))
+    }
+
+    fn number(&self, key: &str) -> Result<usize, String> {
+        let value = self.field(key)?;
+        value
+            .parse()
+            .map_err(|_| format!("line {}: {} = {:?} is not a number", self.line, key, value))
+    }
+
+    fn bytes(&self, key: &str) -> Result<Vec<u8>, String> {
+        hex::decode(self.field(key)?)
+            .map_err(|err| format!("line {}: {} is not hex: {}", self.line, key, err))
+    }
+
+    fn header_number(&self, key: &str) -> Result<usize, String> {
+        self.header
+            .get(key)
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| format!("line {}: no [{}] header", self.line, key))
+    }
+}
+
+/// Split an `.rsp` file into records. `#` lines are comments, and headers
+/// without a value (`[Tested for ...]`) are ignored.
+pub fn parse(text: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut header = BTreeMap::new();
+    let mut current: Option<Record> = None;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            records.extend(current.take());
+            if let Some((key, value)) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+                .and_then(|line| line.split_once('='))
+            {
+                header.insert(key.trim().to_string(), value.trim().to_string());
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        current
+            .get_or_insert_with(|| Record {
+                line: index + 1,
+                header: header.clone(),
+                fields: BTreeMap::new(),
+            })
+            .fields
+            .insert(key.trim().to_string(), value.trim().to_string());
+    }
+    records.extend(current);
+    records
+}
+
+/// `length` bytes of `algorithm` over `message` from `backend`, or `None`
+/// when the backend does not implement it. SHA3 ignores `length`.
+pub fn hash(
+    backend: Backend,
+    algorithm: Algorithm,
+    message: &[u8],
+    length: usize,
+) -> Option<Vec<u8>> {
+    match (backend, algorithm) {
+        (Backend::Native, Algorithm::Shake128 | Algorithm::Shake256) => {
+            let mut shake = if algorithm == Algorithm::Shake128 {
+                native::Shake::shake128()
+            } else {
+                native::Shake::shake256()
+            };
+            shake.update(message);
+            let mut output = vec![0u8; length];
+            shake.finalize_xof().squeeze(&mut output);
+            Some(output)
+        }
+        #[cfg(feature = "sha3")]
+        (Backend::Sha3, _) => Some(sha3_hash(algorithm, message, length)),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "sha3")]
+fn sha3_hash(algorithm: Algorithm, message: &[u8], length: usize) -> Vec<u8> {
+    use sha3::digest::{Digest, ExtendableOutput, XofReader};
+
+    fn squeeze(mut shake: impl ExtendableOutput, message: &[u8], length: usize) -> Vec<u8> {
+        shake.update(message);
+        let mut output = vec![0u8; length];
+        shake.finalize_xof().read(&mut output);
+        output
+    }
+
+    match algorithm {
+        Algorithm::Sha3(224) => sha3::Sha3_224::digest(message).to_vec(),
+        Algorithm::Sha3(256) => sha3::Sha3_256::digest(message).to_vec(),
+        Algorithm::Sha3(384) => sha3::Sha3_384::digest(message).to_vec(),
+        Algorithm::Sha3(512) => sha3::Sha3_512::digest(message).to_vec(),
+        Algorithm::Sha3(bits) => unreachable!("identify rejects SHA3-{}", bits),
+        Algorithm::Shake128 => squeeze(sha3::Shake128::default(), message, length),
+        Algorithm::Shake256 => squeeze(sha3::Shake256::default(), message, length),
+    }
+}
+
+/// What checking one file against one backend found.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Outcome {
+    pub passed: usize,
+    pub skipped: usize,
+    /// One line per mismatching record.
+    pub failures: Vec<String>,
+}
+
+/// A parsed response file.
+#[derive(Clone, Debug)]
+pub struct RspFile {
+    pub algorithm: Algorithm,
+    pub test: Test,
+    pub records: Vec<Record>,
+}
+
+impl RspFile {
+    pub fn load(path: &Path) -> Result<RspFile, String> {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        let (algorithm, test) = identify(name).ok_or_else(|| {
+            format!(
+                "{}: not a NIST or XKCP SHA-3 file name (e.g. SHA3_256ShortMsg.rsp)",
+                path.display()
+            )
+        })?;
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        Ok(RspFile {
+            algorithm,
+            test,
+            records: parse(&text),
+        })
+    }
+
+    /// Check every record against `backend`, or `None` when the backend
+    /// does not implement the file's algorithm.
+    pub fn check(&self, backend: Backend) -> Option<Result<Outcome, String>> {
+        hash(backend, self.algorithm, &[], 1)?;
+        let hash = |message: &[u8], length| {
+            hash(backend, self.algorithm, message, length).expect("implemented")
+        };
+        Some(match (self.test, self.algorithm) {
+            (Test::Monte, Algorithm::Sha3(_)) => self.monte(hash),
+            (Test::Monte, _) => self.shake_monte(hash),
+            _ => self.known_answers(hash),
+        })
+    }
+
+    /// ShortMsg, LongMsg and VariableOut: one message and answer per record.
+    fn known_answers(&self, hash: impl Fn(&[u8], usize) -> Vec<u8>) -> Result<Outcome, String> {
+        let mut outcome = Outcome::default();
+        for record in self.records.iter().filter(|r| r.fields.contains_key("Msg")) {
+            let message = record.bytes("Msg")?;
+            let message = match record.fields.contains_key("Len") {
+                true => match record.number("Len")? {
+                    bits if bits % 8 != 0 => {
+                        outcome.skipped += 1;
+                        continue;
+                    }
+                    bits => message
+                        .get(..bits / 8)
+                        .ok_or_else(|| format!("line {}: Msg is shorter than Len", record.line))?,
+                },
+                false => &message[..],
+            };
+            let key = ["MD", "Output", "Squeezed"]
+                .into_iter()
+                .find(|key| record.fields.contains_key(*key))
+                .unwrap_or("MD");
+            let expected = record.bytes(key)?;
+            if record.fields.contains_key("Outputlen") && record.number("Outputlen")? % 8 != 0 {
+                outcome.skipped += 1;
+                continue;
+            }
+            compare(
+                &mut outcome,
+                record,
+                &expected,
+                &hash(message, expected.len()),
+            );
+        }
+        Ok(outcome)
+    }
+
+    /// SHA3VS Monte Carlo: each checkpoint is 1000 chained digests.
+    fn monte(&self, hash: impl Fn(&[u8], usize) -> Vec<u8>) -> Result<Outcome, String> {
+        let seed = self
+            .records
+            .iter()
+            .find(|record| record.fields.contains_key("Seed"))
+            .ok_or("no Seed record")?;
+        let mut digest = seed.bytes("Seed")?;
+        let mut outcome = Outcome::default();
+        for record in self.records.iter().filter(|r| r.fields.contains_key("MD")) {
+            for _ in 0..MONTE_ITERATIONS {
+                digest = hash(&digest, 0);
+            }
+            compare(&mut outcome, record, &record.bytes("MD")?, &digest);
+        }
+        Ok(outcome)
+    }
+
+    /// SHAKEVS Monte Carlo: each iteration hashes the leftmost 16 bytes
+    /// of the previous output, and the output's last two bytes pick the
+    /// next output length within the header's bounds.
+    fn shake_monte(&self, hash: impl Fn(&[u8], usize) -> Vec<u8>) -> Result<Outcome, String> {
+        let seed = self
+            .records
+            .iter()
+            .find(|record| record.fields.contains_key("Msg"))
+            .ok_or("no Msg record")?;
+        let min = seed.header_number("Minimum Output Length (bits)")? / 8;
+        let max = seed.header_number("Maximum Output Length (bits)")? / 8;
+        if min < 2 || min > max {
+            return Err(format!(
+                "line {}: output lengths {}..{} bytes are out of range",
+                seed.line, min, max
+            ));
+        }
+        let mut output = seed.bytes("Msg")?;
+        let mut length = max;
+        let mut outcome = Outcome::default();
+        for record in self
+            .records
+            .iter()
+            .filter(|r| r.fields.contains_key("Output"))
+        {
+            let mut used = length;
+            for _ in 0..MONTE_ITERATIONS {
+                let mut message = [0u8; 16];
+                let take = output.len().min(16);
+                message[..take].copy_from_slice(&output[..take]);
+                output = hash(&message, length);
+                used = length;
+                let tail = u16::from_be_bytes([output[length - 2], output[length - 1]]);
+                length = min + tail as usize % (max - min + 1);
+            }
+            if record.number("Outputlen")? != used * 8 {
+                outcome.failures.push(format!(
+                    "line {}: Outputlen {} expected, got {}",
+                    record.line,
+                    record.field("Outputlen")?,
+                    used * 8
+                ));
+                continue;
+            }
+            compare(&mut outcome, record, &record.bytes("Output")?, &output);
+        }
+        Ok(outcome)
+    }
+}
+
+fn compare(outcome: &mut Outcome, record: &Record, expected: &[u8], got: &[u8]) {
+    if expected == got {
+        outcome.passed += 1;
+    } else {
+        outcome.failures.push(format!(
+            "line {}: expected {}, got {}",
+            record.line,
+            hex::encode(expected),
+            hex::encode(got)
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// NIST file names map to their algorithm and test, and headers apply
+    /// to the records after them.
+    #[test]
+    fn identifies_and_parses_nist_files() {
+        assert_eq!(
+            identify("SHA3_256ShortMsg.rsp"),
+            Some((Algorithm::Sha3(256), Test::ShortMsg))
+        );
+        assert_eq!(
+            identify("SHAKE256VariableOut.rsp"),
+            Some((Algorithm::Shake256, Test::VariableOut))
+        );
+        assert_eq!(identify("SHA3_100Monte.rsp"), None);
+        assert_eq!(identify("SHA256ShortMsg.rsp"), None);
+        assert_eq!(
+            identify("ShortMsgKAT_SHA3-512.txt"),
+            Some((Algorithm::Sha3(512), Test::ShortMsg))
+        );
+        assert_eq!(
+            identify("LongMsgKAT_SHAKE128.txt"),
+            Some((Algorithm::Shake128, Test::LongMsg))
+        );
+        assert_eq!(identify("KeccakF-1600-states.txt"), None);
+
+        let records =
+            parse("# comment\n[L = 256]\n\nLen = 0\nMsg = 00\nMD = ab\n\n[L = 512]\nLen = 8\n");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].line, 4);
+        assert_eq!(records[0].fields["MD"], "ab");
+        assert_eq!(records[1].header["L"], "512");
+    }
+
+    /// Every sample file passes on each backend implementing its
+    /// algorithm, and a wrong answer is reported.
+    #[test]
+    fn sample_files_pass() {
+        check_dir(SAMPLE_DIR);
+    }
+
+    /// The XKCP known-answer samples pass the same way.
+    #[test]
+    fn xkcp_files_pass() {
+        check_dir(XKCP_DIR);
+    }
+
+    fn check_dir(dir: &str) {
+        let mut entries: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|path| identify(&path.file_name().unwrap().to_string_lossy()).is_some())
+            .collect();
+        entries.sort();
+        assert!(!entries.is_empty());
+        for path in entries {
+            let mut file = RspFile::load(&path).unwrap();
+            // Monte Carlo chains are hundreds of thousands of hashes, far
+            // too slow for Miri; every other file still runs there.
+            if cfg!(miri) && file.test == Test::Monte {
+                continue;
+            }
+            let mut checked = 0;
+            for backend in BACKENDS.into_iter().filter(|b| b.is_available()) {
+                let Some(outcome) = file.check(backend) else {
+                    continue;
+                };
+                let outcome = outcome.unwrap();
+                assert!(
+                    outcome.failures.is_empty(),
+                    "{} on {}: {:?}",
+                    path.display(),
+                    backend,
+                    outcome.failures
+                );
+                assert!(outcome.passed > 0, "{}", path.display());
+                checked += 1;
+            }
+            // Only the sha3 backend implements fixed-output SHA3.
+            let sha3 = matches!(file.algorithm, Algorithm::Sha3(_));
+            assert!(
+                checked > 0 || (sha3 && !cfg!(feature = "sha3")),
+                "{}",
+                path.display()
+            );
+            if checked > 0 && file.algorithm == Algorithm::Sha3(256) {
+                let record = file
+                    .records
+                    .iter_mut()
+                    .find(|r| r.fields.contains_key("MD"))
+                    .unwrap();
+                record.fields.insert("MD".into(), "00".repeat(32));
+                let outcome = file.check(Backend::Sha3).unwrap().unwrap();
+                assert_eq!(outcome.failures.len(), 1);
+            }
+        }
+    }
+}
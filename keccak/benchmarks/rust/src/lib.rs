@@ -1,24 +1,197 @@
-//! Minimal Keccak-256 helper used by benchmark smoke tests.
+//! The Rust baseline the Mojo secp256k1 and Keccak work is benchmarked and
+//! differentially tested against.
+//!
+//! The hashing core is Keccak-256 and SHAKE in `native`, with batch,
+//! multi-stream and SIMD paths, plus SHA-256 and SHA-512. On top of it sit
+//! secp256k1 arithmetic (`secp256k1`, `fixed_base`, `multiscalar`), ECDSA
+//! signature encodings and recovery-based verification (`signature`,
+//! `verifier`), and BIP-340 Schnorr verification, one signature at a time
+//! or in batches (`schnorr`). The Ethereum helpers in `ethereum` cover
+//! addresses, EIP-191 and EIP-155 signing, ABI encoding, RLP, tries,
+//! blooms, storage slots, ENS and blob hashes; the Bitcoin ones are
+//! `base58`, `bech32` and `taproot`. `bench` is the harness behind the
+//! `bench` binary. Other processes reach the crate through the C ABI in
+//! `ffi`, shared memory (`shm`), the hash oracle (`oracle`), the HTTP
+//! service (`service`), or the optional Python, wasm, Node and UniFFI
+//! bindings.
+//!
+//! With the `safe-only` feature the crate contains no `unsafe` code: the
+//! AVX2 permutation, TSC reads, CPU pinning, perf counters, memory-mapped
+//! inputs, shared memory and the C ABI are compiled out in favour of their
+//! portable fallbacks, so `cargo +nightly miri test --features safe-only`
+//! covers everything that is left.
+//!
+//! With `verify-only` the crate is for validators and light clients that
+//! hash and check signatures and nothing else: code that makes or
+//! handles secrets (the seedable RNGs in `rng`, key recovery in
+//! `nonce_reuse`, constant-time selection in `ct`, the wiping key type in
+//! `secret`, and the fixture signer and key derivation in `scenarios`)
+//! is compiled out.
+//!
+//! `test-utils`, off by default, adds the test fixtures: `scenarios`,
+//! whose signer runs in variable time and exists only to produce vectors,
+//! and the named keys in `test_keys`. The crate's own tests and benches
+//! turn it on through a dev-dependency on the crate itself, and it yields
+//! to `verify-only`.
+//!
+//! `serde` adds `Serialize` and `Deserialize` for points (compressed
+//! hex) and signatures (64-byte hex, or 65 through
+//! `signature::recoverable`), and `ethereum::eip55` for addresses, all
+//! strict about what they read.
+
+#![cfg_attr(feature = "safe-only", forbid(unsafe_code))]
+
+#[cfg(all(
+    feature = "safe-only",
+    any(
+        feature = "alloc-stats",
+        feature = "python",
+        feature = "wasm",
+        feature = "node",
+        feature = "uniffi"
+    )
+))]
+compile_error!("safe-only cannot be combined with alloc-stats or the language bindings");
+
+#[cfg(all(feature = "verify-only", any(feature = "rand", feature = "subtle")))]
+compile_error!("verify-only cannot be combined with rand or subtle");
 
 use tiny_keccak::{Hasher, Keccak};
 
+pub use error::Error;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+pub mod base58;
+pub mod bech32;
+pub mod bench;
+pub mod byte_order;
+pub mod commitment;
+#[cfg(feature = "subtle")]
+pub mod ct;
+pub mod digest;
+pub mod error;
+pub mod ethereum;
+#[cfg(not(feature = "safe-only"))]
+pub mod ffi;
+pub mod fixed_base;
+pub mod hex;
+pub mod kat;
+pub mod merkle;
+#[cfg(feature = "uniffi")]
+pub mod mobile;
+pub mod multiscalar;
+pub mod native;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(not(feature = "verify-only"))]
+pub mod nonce_reuse;
+pub mod oracle;
+#[cfg(feature = "parallel")]
+pub mod par;
+pub mod parallel;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(not(feature = "verify-only"))]
+pub mod rng;
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+pub mod scenarios;
+pub mod schnorr;
+pub mod secp256k1;
+#[cfg(not(feature = "verify-only"))]
+pub mod secret;
+pub mod service;
+pub mod sha256;
+pub mod sha512;
+pub mod shm;
+pub mod signature;
+pub mod taproot;
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+pub mod test_keys;
+pub mod trace;
+pub mod vectors;
+pub mod verifier;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// Hashers, batch contexts and shared tables can move to and be shared
+// between worker threads. Checked here so a field that would silently take
+// that away (an `Rc`, a raw pointer, a `Cell`) fails the build instead.
+const _: fn() = || {
+    fn send_sync<T: Send + Sync>() {}
+    send_sync::<native::Keccak256>();
+    send_sync::<native::Midstate>();
+    send_sync::<native::Shake>();
+    send_sync::<native::ShakeReader>();
+    send_sync::<native::Permutation>();
+    send_sync::<parallel::MultiHasher<4>>();
+    send_sync::<parallel::dispatch::Simd>();
+    send_sync::<bench::BenchConfig>();
+    send_sync::<bench::backend::Backend>();
+    send_sync::<secp256k1::AffinePoint>();
+    send_sync::<secp256k1::Scalar>();
+    send_sync::<verifier::VerifyContext>();
+    #[cfg(feature = "parallel")]
+    send_sync::<par::ParConfig>();
+    send_sync::<shm::Region>();
+};
+
 /// Compute the Keccak-256 digest of the provided message.
 pub fn keccak256(message: &[u8]) -> [u8; 32] {
-    let mut hasher = Keccak::v256();
-    hasher.update(message);
     let mut output = [0u8; 32];
-    hasher.finalize(&mut output);
+    keccak256_into(message, &mut output);
     output
 }
 
-/// Render a digest as a lowercase hexadecimal string.
+/// [`keccak256`] written straight into `out`, for callers hashing into
+/// storage they already own (an arena, a C buffer, a slot in a table).
+pub fn keccak256_into(message: &[u8], out: &mut [u8; 32]) {
+    let mut hasher = Keccak::v256();
+    hasher.update(message);
+    hasher.finalize(out);
+}
+
+/// Hash each message into the matching slot of `digests` with one call,
+/// e.g. the leaves or one level of a Merkle tree. Messages are grouped by
+/// padded block count and fed to the widest multi-message path the CPU has
+/// (see [`parallel::dispatch`]); without one they are hashed one by one.
+///
+/// Panics if the slices differ in length.
+pub fn keccak256_batch<M: AsRef<[u8]>>(messages: &[M], digests: &mut [[u8; 32]]) {
+    parallel::dispatch::keccak256_batch(messages, digests);
+}
+
+/// Render a digest as a lowercase hexadecimal string. The [`hex`] module
+/// has padded, truncated and `0x`-prefixed forms.
 pub fn to_hex_string(bytes: &[u8]) -> String {
-    hex::encode(bytes)
+    ::hex::encode(bytes)
+}
+
+/// [`to_hex_string`] without allocating: write the lowercase hex of `bytes`
+/// into `out`, which must hold exactly two bytes per input byte, and return
+/// it as a `str`.
+pub fn hex_into<'a>(bytes: &[u8], out: &'a mut [u8]) -> Result<&'a str, Error> {
+    ::hex::encode_to_slice(bytes, out).map_err(|_| Error::InvalidLength {
+        what: "a hex buffer",
+        expected: bytes.len() * 2,
+        actual: out.len(),
+    })?;
+    Ok(std::str::from_utf8(out).expect("hex digits are ASCII"))
+}
+
+/// The bytes `text` spells in hex, with an optional `0x` prefix.
+pub fn from_hex(text: &str) -> Result<Vec<u8>, Error> {
+    ::hex::decode(text.strip_prefix("0x").unwrap_or(text)).map_err(|source| Error::InvalidHex {
+        text: text.to_string(),
+        source,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     /// Check the canonical "abc" test vector.
     #[test]
@@ -39,4 +212,47 @@ mod tests {
             "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
         );
     }
+
+    /// The in-place variants write what the allocating ones return.
+    #[test]
+    fn into_variants_match() {
+        let mut digest = [0xffu8; 32];
+        keccak256_into(b"abc", &mut digest);
+        assert_eq!(digest, keccak256(b"abc"));
+        let mut text = [0u8; 64];
+        assert_eq!(
+            hex_into(&digest, &mut text).unwrap(),
+            to_hex_string(&digest)
+        );
+        assert!(hex_into(&digest, &mut [0u8; 63]).is_err());
+        assert_eq!(hex_into(&[], &mut []).unwrap(), "");
+    }
+
+    /// Batched hashing matches hashing each message on its own, with
+    /// enough mixed lengths to fill several groups of eight.
+    #[test]
+    fn keccak256_batch_matches_single_calls() {
+        let long = [7u8; 300];
+        let mut messages: Vec<&[u8]> = vec![b"", b"abc", &long];
+        messages.extend((0..20).map(|i| &long[..i * 15]));
+        let mut digests = vec![[0u8; 32]; messages.len()];
+        keccak256_batch(&messages, &mut digests);
+        for (message, digest) in messages.iter().zip(&digests) {
+            assert_eq!(*digest, keccak256(message));
+        }
+    }
+
+    proptest! {
+        /// Rendering then decoding gives the bytes back, and decoding any
+        /// hex renders it back lowercased.
+        #[test]
+        fn hex_round_trips(
+            bytes in prop::collection::vec(any::<u8>(), 0..100),
+            text in "([0-9a-fA-F]{2}){0,64}",
+        ) {
+            prop_assert_eq!(::hex::decode(to_hex_string(&bytes)).unwrap(), bytes);
+            let decoded = ::hex::decode(&text).unwrap();
+            prop_assert_eq!(to_hex_string(&decoded), text.to_lowercase());
+        }
+    }
 }
@@ -0,0 +1,350 @@
+//! Binary Keccak-256 Merkle roots.
+//!
+//! Each level pairs nodes left to right and hashes `left || right`; an odd
+//! node at the end of a level is carried up unchanged. A single leaf is its
+//! own root and an empty tree has none. Levels are hashed with
+//! [`crate::keccak256_batch`], whose 64-byte messages all fill the same
+//! number of blocks and so pack the multi-message paths fully.
+//!
+//! [`Accumulator`] is the other shape, the one the Ethereum deposit
+//! contract uses (with Keccak-256 for its SHA-256): a tree of fixed depth
+//! whose unfilled leaves are zero, grown one leaf at a time. The empty
+//! subtree of each height hashes to a precomputed zero hash, and the
+//! left siblings still waiting for a right one are kept as the branch,
+//! so an insertion costs at most `depth` hashes (two on average) and the
+//! root `depth`, however many leaves there are.
+//!
+//! To answer [`Accumulator::proof`] it also keeps every complete node it
+//! has hashed, about two per leaf, so its memory grows linearly with the
+//! leaf count (64 bytes a leaf) where the contract's branch alone is
+//! `depth` nodes. Callers that only need roots over very many leaves
+//! should keep that in mind.
+
+use crate::{keccak256, Error};
+
+/// The root over `leaves`, or `None` for no leaves.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    root_with(leaves, crate::keccak256_batch)
+}
+
+/// [`merkle_root`] with `hash_level` hashing each level's concatenated
+/// pairs into their parents.
+pub(crate) fn root_with(
+    leaves: &[[u8; 32]],
+    mut hash_level: impl FnMut(&[[u8; 64]], &mut [[u8; 32]]),
+) -> Option<[u8; 32]> {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let pairs: Vec<[u8; 64]> = level
+            .chunks_exact(2)
+            .map(|pair| {
+                let mut joined = [0u8; 64];
+                joined[..32].copy_from_slice(&pair[0]);
+                joined[32..].copy_from_slice(&pair[1]);
+                joined
+            })
+            .collect();
+        let mut parents = vec![[0u8; 32]; pairs.len()];
+        hash_level(&pairs, &mut parents);
+        if level.len() % 2 == 1 {
+            parents.push(level[level.len() - 1]);
+        }
+        level = parents;
+    }
+    level.first().copied()
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut joined = [0u8; 64];
+    joined[..32].copy_from_slice(left);
+    joined[32..].copy_from_slice(right);
+    keccak256(&joined)
+}
+
+/// The deepest [`Accumulator`], so the leaf count fits a `u64`.
+pub const MAX_DEPTH: usize = 63;
+
+/// An append-only Merkle tree of fixed depth, as the module describes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Accumulator {
+    /// `zero_hashes[h]` is the root of an empty subtree of height `h`.
+    zero_hashes: Vec<[u8; 32]>,
+    /// `branch[h]` is the last complete left node at height `h`, valid
+    /// when bit `h` of the leaf count is set.
+    branch: Vec<[u8; 32]>,
+    /// Every complete node by height, for proofs: about two per leaf.
+    levels: Vec<Vec<[u8; 32]>>,
+    count: u64,
+    hash: PairHash,
+}
+
+/// How an [`Accumulator`] hashes two nodes: Keccak-256 outside the tests,
+/// which also run the deposit contract's own SHA-256.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PairHash {
+    Keccak256,
+    #[cfg(test)]
+    Sha256,
+}
+
+impl PairHash {
+    fn hash(self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        match self {
+            PairHash::Keccak256 => hash_pair(left, right),
+            #[cfg(test)]
+            PairHash::Sha256 => crate::sha256::sha256(&[*left, *right].concat()),
+        }
+    }
+}
+
+impl Accumulator {
+    /// The deposit contract's depth.
+    pub const DEPOSIT_DEPTH: usize = 32;
+
+    /// An empty tree with room for `2^depth` leaves.
+    ///
+    /// # Panics
+    ///
+    /// If `depth` is zero or above [`MAX_DEPTH`].
+    pub fn new(depth: usize) -> Accumulator {
+        Accumulator::with_hash(depth, PairHash::Keccak256)
+    }
+
+    fn with_hash(depth: usize, hash: PairHash) -> Accumulator {
+        assert!(
+            (1..=MAX_DEPTH).contains(&depth),
+            "depth must be 1 to {}, not {}",
+            MAX_DEPTH,
+            depth
+        );
+        let mut zero_hashes = vec![[0u8; 32]];
+        for h in 0..depth {
+            zero_hashes.push(hash.hash(&zero_hashes[h], &zero_hashes[h]));
+        }
+        Accumulator {
+            zero_hashes,
+            branch: vec![[0u8; 32]; depth],
+            levels: vec![Vec::new(); depth + 1],
+            count: 0,
+            hash,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.branch.len()
+    }
+
+    /// Leaves inserted so far.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Append `leaf` and return its index, hashing the subtrees it
+    /// completes.
+    pub fn insert(&mut self, leaf: [u8; 32]) -> Result<u64, Error> {
+        if self.count >> self.depth() != 0 {
+            return Err(Error::AccumulatorFull {
+                depth: self.depth(),
+            });
+        }
+        let index = self.count;
+        self.count += 1;
+        self.levels[0].push(leaf);
+        let mut node = leaf;
+        let mut size = self.count;
+        for h in 0..self.depth() {
+            if size & 1 == 1 {
+                self.branch[h] = node;
+                break;
+            }
+            node = self.hash.hash(&self.branch[h], &node);
+            self.levels[h + 1].push(node);
+            size >>= 1;
+        }
+        Ok(index)
+    }
+
+    /// The root over every leaf, unfilled ones zero.
+    pub fn root(&self) -> [u8; 32] {
+        // A full tree's root completed on the last insertion; the deposit
+        // contract stops one leaf short of that.
+        if let Some(root) = self.levels[self.depth()].first() {
+            return *root;
+        }
+        let mut node = self.zero_hashes[0];
+        for h in 0..self.depth() {
+            node = if (self.count >> h) & 1 == 1 {
+                self.hash.hash(&self.branch[h], &node)
+            } else {
+                self.hash.hash(&node, &self.zero_hashes[h])
+            };
+        }
+        node
+    }
+
+    /// The root with the leaf count mixed in, as `get_deposit_root`
+    /// returns it: `keccak256(root || le64(count) || 24 zero bytes)`.
+    pub fn root_with_count(&self) -> [u8; 32] {
+        let mut count = [0u8; 32];
+        count[..8].copy_from_slice(&self.count.to_le_bytes());
+        self.hash.hash(&self.root(), &count)
+    }
+
+    /// The node at height `h` and position `index`, complete, empty, or
+    /// on the right edge of the filled leaves and hashed here.
+    fn node(&self, h: usize, index: u64) -> [u8; 32] {
+        if let Some(node) = self.levels[h].get(index as usize) {
+            return *node;
+        }
+        if index << h >= self.count {
+            return self.zero_hashes[h];
+        }
+        self.hash.hash(
+            &self.node(h - 1, 2 * index),
+            &self.node(h - 1, 2 * index + 1),
+        )
+    }
+
+    /// The siblings from leaf `index` up to the current root, bottom
+    /// first, or `None` if no such leaf has been inserted. Only the
+    /// partly filled subtrees along the right edge are hashed.
+    pub fn proof(&self, index: u64) -> Option<Vec<[u8; 32]>> {
+        (index < self.count).then(|| {
+            (0..self.depth())
+                .map(|h| self.node(h, (index >> h) ^ 1))
+                .collect()
+        })
+    }
+}
+
+/// Whether `proof` places `leaf` at `index` under `root`.
+pub fn verify_proof(root: &[u8; 32], leaf: &[u8; 32], index: u64, proof: &[[u8; 32]]) -> bool {
+    let node = proof.iter().enumerate().fold(*leaf, |node, (h, sibling)| {
+        if (index >> h) & 1 == 1 {
+            hash_pair(sibling, &node)
+        } else {
+            hash_pair(&node, sibling)
+        }
+    });
+    proof.len() < 64 && index >> proof.len() == 0 && node == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parent(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+        keccak256(&[left, right].concat())
+    }
+
+    /// Small trees match the definition worked by hand, the odd leaf
+    /// carried up rather than duplicated.
+    #[test]
+    fn roots_follow_the_definition() {
+        let leaves: Vec<[u8; 32]> = (0..5u8).map(|i| keccak256(&[i])).collect();
+        assert_eq!(merkle_root(&[]), None);
+        assert_eq!(merkle_root(&leaves[..1]), Some(leaves[0]));
+        assert_eq!(
+            merkle_root(&leaves[..3]),
+            Some(parent(parent(leaves[0], leaves[1]), leaves[2]))
+        );
+        let four = parent(parent(leaves[0], leaves[1]), parent(leaves[2], leaves[3]));
+        assert_eq!(merkle_root(&leaves[..4]), Some(four));
+        assert_eq!(merkle_root(&leaves), Some(parent(four, leaves[4])));
+    }
+
+    /// The accumulator's root after every insertion matches the full tree
+    /// padded with zero leaves, and every leaf's proof checks out against
+    /// it and fails for a different leaf or position.
+    #[test]
+    fn accumulator_matches_the_padded_tree() {
+        let depth = 4;
+        let leaves: Vec<[u8; 32]> = (0..16u8).map(|i| keccak256(&[i])).collect();
+        let mut accumulator = Accumulator::new(depth);
+        let padded_root = |count: usize| {
+            let mut level: Vec<[u8; 32]> = (0..16)
+                .map(|i| if i < count { leaves[i] } else { [0; 32] })
+                .collect();
+            while level.len() > 1 {
+                level = level
+                    .chunks(2)
+                    .map(|pair| parent(pair[0], pair[1]))
+                    .collect();
+            }
+            level[0]
+        };
+        assert_eq!(accumulator.root(), padded_root(0));
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert_eq!(accumulator.insert(*leaf).unwrap(), i as u64);
+            let root = accumulator.root();
+            assert_eq!(root, padded_root(i + 1), "{} leaves", i + 1);
+            for (j, leaf) in leaves[..=i].iter().enumerate() {
+                let proof = accumulator.proof(j as u64).unwrap();
+                assert_eq!(proof.len(), depth);
+                assert!(verify_proof(&root, leaf, j as u64, &proof));
+                assert!(!verify_proof(&root, &[0xff; 32], j as u64, &proof));
+                assert!(!verify_proof(&root, leaf, j as u64 ^ 1, &proof));
+            }
+        }
+        assert_eq!(accumulator.proof(16), None);
+        assert_eq!(
+            accumulator.insert([0; 32]),
+            Err(Error::AccumulatorFull { depth: 4 })
+        );
+        assert_eq!(accumulator.len(), 16);
+
+        let mut count = [0u8; 32];
+        count[0] = 16;
+        assert_eq!(
+            accumulator.root_with_count(),
+            parent(accumulator.root(), count)
+        );
+    }
+
+    /// Hashed with SHA-256, the empty tree at the deposit contract's depth
+    /// gives the `get_deposit_root()` the deployed contract (and every
+    /// consensus client's genesis tests) report before the first deposit.
+    #[test]
+    fn sha256_matches_the_deposit_contract() {
+        let accumulator = Accumulator::with_hash(Accumulator::DEPOSIT_DEPTH, PairHash::Sha256);
+        assert_eq!(
+            hex::encode(accumulator.root_with_count()),
+            "d70a234731285c6804c2a4f56711ddb8c82c99740f207854891028af34e27e5e"
+        );
+    }
+
+    /// At the deposit contract's depth the empty root is the chain of
+    /// zero hashes, and three leaves give 32-sibling proofs and the roots
+    /// below. No reference vectors exist for the Keccak variant; these
+    /// come from a separate Python script that evaluates the padded tree
+    /// recursively, with a small Python Keccak, rather than incrementally.
+    #[test]
+    fn deposit_depth_matches_python() {
+        let mut accumulator = Accumulator::new(Accumulator::DEPOSIT_DEPTH);
+        let mut zero = [0u8; 32];
+        for _ in 0..32 {
+            zero = parent(zero, zero);
+        }
+        assert_eq!(accumulator.root(), zero);
+        assert!(accumulator.is_empty());
+        let leaves: Vec<[u8; 32]> = (0..3u8).map(|i| keccak256(&[i])).collect();
+        for leaf in &leaves {
+            accumulator.insert(*leaf).unwrap();
+        }
+        assert_eq!(
+            hex::encode(accumulator.root()),
+            "73cddf35ac38a4a8b09dae52c9ffc224cfd9b30d5a1d85179476d8c2063e4211"
+        );
+        assert_eq!(
+            hex::encode(accumulator.root_with_count()),
+            "ca87af8b63ec3dc431e1435b024c82a6110a8e6de2e30ef1e97abd7f216b2092"
+        );
+        let proof = accumulator.proof(2).unwrap();
+        assert_eq!(proof.len(), 32);
+        assert!(verify_proof(&accumulator.root(), &leaves[2], 2, &proof));
+    }
+}
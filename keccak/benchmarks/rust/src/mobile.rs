@@ -0,0 +1,76 @@
+//! UniFFI bindings for Swift and Kotlin (`uniffi` feature), so mobile
+//! wallet prototypes consume this crate directly.
+//!
+//! Build the library with `--features uniffi`, then generate bindings from
+//! it with `cargo run --features uniffi,uniffi/cli --bin uniffi-bindgen --
+//! generate --library <path to the built library> --language swift` (or
+//! `kotlin`). Key management and signing join this interface once the
+//! Rust secp256k1 baseline implements them.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::native;
+
+/// Why hex input was rejected.
+#[derive(Debug, uniffi::Error)]
+pub enum HexError {
+    Invalid { reason: String },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::Invalid { reason } => write!(f, "invalid hex: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for HexError {}
+
+/// The Keccak-256 digest of `data`.
+#[uniffi::export]
+pub fn keccak256(data: Vec<u8>) -> Vec<u8> {
+    crate::keccak256(&data).to_vec()
+}
+
+/// The Keccak-256 digest of `data` as lowercase hex.
+#[uniffi::export]
+pub fn keccak256_hex(data: Vec<u8>) -> String {
+    crate::to_hex_string(&crate::keccak256(&data))
+}
+
+/// The bytes `text` spells in hex, with an optional `0x` prefix.
+#[uniffi::export]
+pub fn from_hex(text: String) -> Result<Vec<u8>, HexError> {
+    hex::decode(text.strip_prefix("0x").unwrap_or(&text)).map_err(|err| HexError::Invalid {
+        reason: err.to_string(),
+    })
+}
+
+/// Incremental Keccak-256. Foreign objects are shared, so the state sits
+/// behind a lock.
+#[derive(uniffi::Object)]
+pub struct Keccak256Hasher(Mutex<native::Keccak256>);
+
+#[uniffi::export]
+impl Keccak256Hasher {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Keccak256Hasher> {
+        Arc::new(Keccak256Hasher(Mutex::new(native::Keccak256::new())))
+    }
+
+    pub fn update(&self, data: Vec<u8>) {
+        self.0.lock().expect("hasher lock").update(&data);
+    }
+
+    /// The digest so far; the hasher keeps absorbing afterwards.
+    pub fn digest(&self) -> Vec<u8> {
+        self.0
+            .lock()
+            .expect("hasher lock")
+            .clone()
+            .finalize()
+            .to_vec()
+    }
+}
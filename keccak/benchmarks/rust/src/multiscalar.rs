@@ -0,0 +1,195 @@
+//! Multi-scalar multiplication: `k_1 * P_1 + ... + k_n * P_n` in one
+//! pass, for batch verification and commitment schemes.
+//!
+//! [`straus`] shares one run of 256 doublings between every term and adds
+//! a precomputed multiple `d * P_i` (`d` from 1 to 15) for each 4-bit
+//! window of each scalar: about `78 n` additions. [`pippenger`] keeps no
+//! tables. For each `c`-bit window it drops every point into the bucket
+//! of its digit and folds the `2^c - 1` buckets with a running sum, about
+//! `(256 / c) (n + 2^(c + 1))` additions, which wins once `n` is large
+//! enough to pay for the buckets. [`multiscalar_mul`] estimates both
+//! counts, with the cheapest `c`, and runs the smaller; the crossover
+//! lands near [`PIPPENGER_THRESHOLD`] terms.
+//!
+//! Like the rest of the Rust curve code this runs in variable time.
+
+use crate::secp256k1::{AffinePoint, Jacobian, Scalar};
+
+/// Where [`multiscalar_mul`] switches to [`pippenger`], as its cost
+/// estimates put it.
+pub const PIPPENGER_THRESHOLD: usize = 129;
+
+const STRAUS_BITS: usize = 4;
+
+/// The `width`-bit digit of `k` starting at bit `start`, bit 0 being the
+/// least significant.
+fn digit(k: &Scalar, start: usize, width: usize) -> usize {
+    (start..(start + width).min(256))
+        .filter(|&i| (k.to_bytes()[31 - i / 8] >> (i % 8)) & 1 == 1)
+        .fold(0, |digit, i| digit | 1 << (i - start))
+}
+
+/// Additions (doublings counted alike) [`straus`] needs for `n` terms.
+fn straus_cost(n: usize) -> usize {
+    256 + n * (14 + 256 / STRAUS_BITS)
+}
+
+/// The cheapest Pippenger window for `n` terms, and its cost in the
+/// units of [`straus_cost`].
+fn pippenger_window(n: usize) -> (usize, usize) {
+    (1..=16)
+        .map(|c| (c, 256 + 256_usize.div_ceil(c) * (n + (2 << c))))
+        .min_by_key(|&(_, cost)| cost)
+        .expect("a nonempty range")
+}
+
+fn check_lengths(scalars: &[Scalar], points: &[AffinePoint]) {
+    assert_eq!(
+        scalars.len(),
+        points.len(),
+        "one scalar per point: {} scalars, {} points",
+        scalars.len(),
+        points.len()
+    );
+}
+
+/// `sum scalars[i] * points[i]`, or `None` for the point at infinity
+/// (including the empty sum), by whichever of [`straus`] and
+/// [`pippenger`] is estimated cheaper for this many terms.
+///
+/// # Panics
+///
+/// If the slices differ in length.
+pub fn multiscalar_mul(scalars: &[Scalar], points: &[AffinePoint]) -> Option<AffinePoint> {
+    if pippenger_window(scalars.len()).1 < straus_cost(scalars.len()) {
+        pippenger(scalars, points)
+    } else {
+        straus(scalars, points)
+    }
+}
+
+/// [`multiscalar_mul`] by Straus's interleaved windows.
+///
+/// # Panics
+///
+/// If the slices differ in length.
+pub fn straus(scalars: &[Scalar], points: &[AffinePoint]) -> Option<AffinePoint> {
+    check_lengths(scalars, points);
+    let tables: Vec<Vec<Jacobian>> = points
+        .iter()
+        .map(|point| {
+            let base = Jacobian::from(point);
+            let mut multiples = vec![base];
+            for _ in 2..1 << STRAUS_BITS {
+                let last = multiples[multiples.len() - 1];
+                multiples.push(last.add(&base));
+            }
+            multiples
+        })
+        .collect();
+    let mut acc = Jacobian::INFINITY;
+    for window in (0..256 / STRAUS_BITS).rev() {
+        for _ in 0..STRAUS_BITS {
+            acc = acc.double();
+        }
+        for (k, table) in scalars.iter().zip(&tables) {
+            let d = digit(k, window * STRAUS_BITS, STRAUS_BITS);
+            if d != 0 {
+                acc = acc.add(&table[d - 1]);
+            }
+        }
+    }
+    acc.to_affine()
+}
+
+/// [`multiscalar_mul`] by Pippenger's buckets, with the window width
+/// that minimizes the estimated additions.
+///
+/// # Panics
+///
+/// If the slices differ in length.
+pub fn pippenger(scalars: &[Scalar], points: &[AffinePoint]) -> Option<AffinePoint> {
+    check_lengths(scalars, points);
+    let (c, _) = pippenger_window(scalars.len());
+    let points: Vec<Jacobian> = points.iter().map(Jacobian::from).collect();
+    let mut acc = Jacobian::INFINITY;
+    for window in (0..256_usize.div_ceil(c)).rev() {
+        for _ in 0..c {
+            acc = acc.double();
+        }
+        let mut buckets = vec![Jacobian::INFINITY; (1 << c) - 1];
+        for (k, point) in scalars.iter().zip(&points) {
+            let d = digit(k, window * c, c);
+            if d != 0 {
+                buckets[d - 1] = buckets[d - 1].add(point);
+            }
+        }
+        // Bucket d is counted d times: once per running sum from the top
+        // down to it.
+        let mut running = Jacobian::INFINITY;
+        let mut total = Jacobian::INFINITY;
+        for bucket in buckets.iter().rev() {
+            running = running.add(bucket);
+            total = total.add(&running);
+        }
+        acc = acc.add(&total);
+    }
+    acc.to_affine()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keccak256;
+    use crate::secp256k1::G;
+
+    fn naive(scalars: &[Scalar], points: &[AffinePoint]) -> Option<AffinePoint> {
+        scalars
+            .iter()
+            .zip(points)
+            .filter_map(|(k, point)| point.mul(k))
+            .fold(Jacobian::INFINITY, |acc, term| {
+                acc.add(&Jacobian::from(&term))
+            })
+            .to_affine()
+    }
+
+    /// Both methods agree with term-by-term multiplication, including
+    /// zero scalars, repeated points and terms that cancel.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn methods_match_naive_sums() {
+        let scalar = |i: u32| Scalar::reduce(&keccak256(&i.to_be_bytes()));
+        let mut scalars: Vec<Scalar> = (0..12).map(scalar).collect();
+        let mut points: Vec<AffinePoint> = (100..112).map(|i| G.mul(&scalar(i)).unwrap()).collect();
+        scalars[3] = Scalar::ZERO;
+        points[5] = points[4];
+        for n in [0, 1, 2, 12] {
+            let (scalars, points) = (&scalars[..n], &points[..n]);
+            let expected = naive(scalars, points);
+            assert_eq!(straus(scalars, points), expected, "straus, {} terms", n);
+            assert_eq!(
+                pippenger(scalars, points),
+                expected,
+                "pippenger, {} terms",
+                n
+            );
+            assert_eq!(multiscalar_mul(scalars, points), expected);
+        }
+        let k = scalar(7);
+        let p = points[0];
+        assert_eq!(multiscalar_mul(&[k, k], &[p, p.neg()]), None);
+        assert_eq!(pippenger(&[k, k.neg()], &[p, p]), None);
+        assert_eq!(digit(&Scalar::ONE.neg(), 252, 8), 0xf);
+    }
+
+    /// The estimates switch methods where the documented threshold says.
+    #[test]
+    fn crossover_matches_threshold() {
+        let pippenger_wins = |n: usize| pippenger_window(n).1 < straus_cost(n);
+        assert!(!pippenger_wins(PIPPENGER_THRESHOLD - 1));
+        assert!(pippenger_wins(PIPPENGER_THRESHOLD));
+        assert!(pippenger_wins(4096));
+        assert!(pippenger_window(4096).0 > pippenger_window(PIPPENGER_THRESHOLD).0);
+    }
+}
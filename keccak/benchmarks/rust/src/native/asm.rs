@@ -0,0 +1,347 @@
+//! Hand-written keccak-f[1600] kernels (`asm` feature).
+//!
+//! Both kernels run one round per loop iteration. Each round reads the state
+//! at `a`, writes the next one to the scratch state at `e`, and then the two
+//! pointers swap; after 24 rounds the result is back in the caller's state.
+//! Within a round, each output row gathers its five rotated input lanes
+//! (theta's `d` folded in, rho and pi) into registers and stores chi of
+//! them, in the order of `round!` in the parent module.
+//!
+//! - x86-64 keeps theta's `d` in a five-lane scratch array and uses BMI1
+//!   `andn` for chi's `!b & c`.
+//! - aarch64 keeps `d` in registers, folds theta's one-bit rotate into
+//!   `eor ..., ror #63`, and uses `bic` for chi.
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use std::arch::asm;
+
+    use crate::native::ROUND_CONSTANTS;
+
+    /// One theta column parity into `$c`, from the lanes at five byte
+    /// offsets of `a`.
+    #[rustfmt::skip]
+    macro_rules! parity {
+        ($c:literal, $o0:literal, $o1:literal, $o2:literal, $o3:literal, $o4:literal) => {
+            concat!(
+                "mov ", $c, ", [{a} + ", $o0, "]\n",
+                "xor ", $c, ", [{a} + ", $o1, "]\n",
+                "xor ", $c, ", [{a} + ", $o2, "]\n",
+                "xor ", $c, ", [{a} + ", $o3, "]\n",
+                "xor ", $c, ", [{a} + ", $o4, "]\n",
+            )
+        };
+    }
+
+    /// `d[$at] = $prev ^ rotl($next, 1)`.
+    #[rustfmt::skip]
+    macro_rules! theta_d {
+        ($at:literal, $prev:literal, $next:literal) => {
+            concat!(
+                "mov r13, ", $next, "\n",
+                "rol r13, 1\n",
+                "xor r13, ", $prev, "\n",
+                "mov [{d} + ", $at, "], r13\n",
+            )
+        };
+    }
+
+    /// `$b = rotl(a[$src] ^ d[$d], $rot)`.
+    #[rustfmt::skip]
+    macro_rules! gather {
+        ($b:literal, $src:literal, $d:literal, $rot:literal) => {
+            concat!(
+                "mov ", $b, ", [{a} + ", $src, "]\n",
+                "xor ", $b, ", [{d} + ", $d, "]\n",
+                "rol ", $b, ", ", $rot, "\n",
+            )
+        };
+    }
+
+    /// `e[$dst] = $b0 ^ (!$b1 & $b2)`.
+    #[rustfmt::skip]
+    macro_rules! chi {
+        ($dst:literal, $b0:literal, $b1:literal, $b2:literal) => {
+            concat!(
+                "andn r13, ", $b1, ", ", $b2, "\n",
+                "xor r13, ", $b0, "\n",
+                "mov [{e} + ", $dst, "], r13\n",
+            )
+        };
+    }
+
+    /// Chi over the gathered row in `r8`..`r12`, into the output row at
+    /// byte offset `$row` of `e`.
+    #[rustfmt::skip]
+    macro_rules! chi_row {
+        ($row:literal, $o1:literal, $o2:literal, $o3:literal, $o4:literal) => {
+            concat!(
+                chi!($row, "r8", "r9", "r10"),
+                chi!($o1, "r9", "r10", "r11"),
+                chi!($o2, "r10", "r11", "r12"),
+                chi!($o3, "r11", "r12", "r8"),
+                chi!($o4, "r12", "r8", "r9"),
+            )
+        };
+    }
+
+    /// keccak-f[1600] on x86-64.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support BMI1.
+    #[target_feature(enable = "bmi1")]
+    #[inline(never)]
+    pub unsafe fn keccak_f1600(state: &mut [u64; 25]) {
+        let mut scratch = [0u64; 25];
+        let mut d = [0u64; 5];
+        // SAFETY: `a` and `e` each point at 25 lanes, `d` at five and `rc`
+        // at the 24 round constants, and only those are read or written.
+        // The pointers swap every round, so after the even round count `a`
+        // is `state` again and nothing outlives the block.
+        unsafe {
+            asm!(
+                "2:",
+                // Theta: column parities, then `d`.
+                parity!("r8", 0, 40, 80, 120, 160),
+                parity!("r9", 8, 48, 88, 128, 168),
+                parity!("r10", 16, 56, 96, 136, 176),
+                parity!("r11", 24, 64, 104, 144, 184),
+                parity!("r12", 32, 72, 112, 152, 192),
+                theta_d!(0, "r12", "r9"),
+                theta_d!(8, "r8", "r10"),
+                theta_d!(16, "r9", "r11"),
+                theta_d!(24, "r10", "r12"),
+                theta_d!(32, "r11", "r8"),
+                // Row b, with iota on its first lane.
+                gather!("r8", 0, 0, 0),
+                gather!("r9", 48, 8, 44),
+                gather!("r10", 96, 16, 43),
+                gather!("r11", 144, 24, 21),
+                gather!("r12", 192, 32, 14),
+                "andn r13, r9, r10",
+                "xor r13, r8",
+                "xor r13, [{rc}]",
+                "mov [{e}], r13",
+                chi!(8, "r9", "r10", "r11"),
+                chi!(16, "r10", "r11", "r12"),
+                chi!(24, "r11", "r12", "r8"),
+                chi!(32, "r12", "r8", "r9"),
+                // Row g.
+                gather!("r8", 24, 24, 28),
+                gather!("r9", 72, 32, 20),
+                gather!("r10", 80, 0, 3),
+                gather!("r11", 128, 8, 45),
+                gather!("r12", 176, 16, 61),
+                chi_row!(40, 48, 56, 64, 72),
+                // Row k.
+                gather!("r8", 8, 8, 1),
+                gather!("r9", 56, 16, 6),
+                gather!("r10", 104, 24, 25),
+                gather!("r11", 152, 32, 8),
+                gather!("r12", 160, 0, 18),
+                chi_row!(80, 88, 96, 104, 112),
+                // Row m.
+                gather!("r8", 32, 32, 27),
+                gather!("r9", 40, 0, 36),
+                gather!("r10", 88, 8, 10),
+                gather!("r11", 136, 16, 15),
+                gather!("r12", 184, 24, 56),
+                chi_row!(120, 128, 136, 144, 152),
+                // Row s.
+                gather!("r8", 16, 16, 62),
+                gather!("r9", 64, 24, 55),
+                gather!("r10", 112, 32, 39),
+                gather!("r11", 120, 0, 41),
+                gather!("r12", 168, 8, 2),
+                chi_row!(160, 168, 176, 184, 192),
+                // Next round.
+                "add {rc}, 8",
+                "xchg {a}, {e}",
+                "dec {n:e}",
+                "jnz 2b",
+                a = inout(reg) state.as_mut_ptr() => _,
+                e = inout(reg) scratch.as_mut_ptr() => _,
+                d = in(reg) d.as_mut_ptr(),
+                rc = inout(reg) ROUND_CONSTANTS.as_ptr() => _,
+                n = inout(reg) 24u32 => _,
+                out("r8") _,
+                out("r9") _,
+                out("r10") _,
+                out("r11") _,
+                out("r12") _,
+                out("r13") _,
+                options(nostack),
+            );
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64 {
+    use std::arch::asm;
+
+    use crate::native::ROUND_CONSTANTS;
+
+    /// One theta column parity into `$c`, from the lanes at five byte
+    /// offsets of `a`.
+    #[rustfmt::skip]
+    macro_rules! parity {
+        ($c:literal, $o0:literal, $o1:literal, $o2:literal, $o3:literal, $o4:literal) => {
+            concat!(
+                "ldr ", $c, ", [{a}, #", $o0, "]\n",
+                "ldr x14, [{a}, #", $o1, "]\n",
+                "eor ", $c, ", ", $c, ", x14\n",
+                "ldr x14, [{a}, #", $o2, "]\n",
+                "eor ", $c, ", ", $c, ", x14\n",
+                "ldr x14, [{a}, #", $o3, "]\n",
+                "eor ", $c, ", ", $c, ", x14\n",
+                "ldr x14, [{a}, #", $o4, "]\n",
+                "eor ", $c, ", ", $c, ", x14\n",
+            )
+        };
+    }
+
+    /// `$b = rotr(a[$src] ^ $d, $ror)`, so `$ror` is 64 minus the rho
+    /// rotation.
+    #[rustfmt::skip]
+    macro_rules! gather {
+        ($b:literal, $src:literal, $d:literal, $ror:literal) => {
+            concat!(
+                "ldr ", $b, ", [{a}, #", $src, "]\n",
+                "eor ", $b, ", ", $b, ", ", $d, "\n",
+                "ror ", $b, ", ", $b, ", #", $ror, "\n",
+            )
+        };
+    }
+
+    /// `e[$dst] = $b0 ^ (!$b1 & $b2)`.
+    #[rustfmt::skip]
+    macro_rules! chi {
+        ($dst:literal, $b0:literal, $b1:literal, $b2:literal) => {
+            concat!(
+                "bic x14, ", $b2, ", ", $b1, "\n",
+                "eor x14, x14, ", $b0, "\n",
+                "str x14, [{e}, #", $dst, "]\n",
+            )
+        };
+    }
+
+    /// Chi over the gathered row in `x9`..`x13`, into the output row at
+    /// byte offset `$row` of `e`.
+    #[rustfmt::skip]
+    macro_rules! chi_row {
+        ($row:literal, $o1:literal, $o2:literal, $o3:literal, $o4:literal) => {
+            concat!(
+                chi!($row, "x9", "x10", "x11"),
+                chi!($o1, "x10", "x11", "x12"),
+                chi!($o2, "x11", "x12", "x13"),
+                chi!($o3, "x12", "x13", "x9"),
+                chi!($o4, "x13", "x9", "x10"),
+            )
+        };
+    }
+
+    /// keccak-f[1600] on aarch64.
+    ///
+    /// # Safety
+    ///
+    /// None beyond the borrow: the base instruction set suffices.
+    #[inline(never)]
+    pub unsafe fn keccak_f1600(state: &mut [u64; 25]) {
+        let mut scratch = [0u64; 25];
+        // SAFETY: `a` and `e` each point at 25 lanes and `rc` at the 24
+        // round constants, and only those are read or written. The
+        // pointers swap every round, so after the even round count `a` is
+        // `state` again and nothing outlives the block.
+        unsafe {
+            asm!(
+                "2:",
+                // Theta: column parities in x9..x13, then `d` in x15, x16,
+                // x17, x20 and x21.
+                parity!("x9", 0, 40, 80, 120, 160),
+                parity!("x10", 8, 48, 88, 128, 168),
+                parity!("x11", 16, 56, 96, 136, 176),
+                parity!("x12", 24, 64, 104, 144, 184),
+                parity!("x13", 32, 72, 112, 152, 192),
+                "eor x15, x13, x10, ror #63",
+                "eor x16, x9, x11, ror #63",
+                "eor x17, x10, x12, ror #63",
+                "eor x20, x11, x13, ror #63",
+                "eor x21, x12, x9, ror #63",
+                // Row b, with iota on its first lane.
+                "ldr x9, [{a}]",
+                "eor x9, x9, x15",
+                gather!("x10", 48, "x16", 20),
+                gather!("x11", 96, "x17", 21),
+                gather!("x12", 144, "x20", 43),
+                gather!("x13", 192, "x21", 50),
+                "bic x14, x11, x10",
+                "eor x14, x14, x9",
+                "ldr x22, [{rc}], #8",
+                "eor x14, x14, x22",
+                "str x14, [{e}]",
+                chi!(8, "x10", "x11", "x12"),
+                chi!(16, "x11", "x12", "x13"),
+                chi!(24, "x12", "x13", "x9"),
+                chi!(32, "x13", "x9", "x10"),
+                // Row g.
+                gather!("x9", 24, "x20", 36),
+                gather!("x10", 72, "x21", 44),
+                gather!("x11", 80, "x15", 61),
+                gather!("x12", 128, "x16", 19),
+                gather!("x13", 176, "x17", 3),
+                chi_row!(40, 48, 56, 64, 72),
+                // Row k.
+                gather!("x9", 8, "x16", 63),
+                gather!("x10", 56, "x17", 58),
+                gather!("x11", 104, "x20", 39),
+                gather!("x12", 152, "x21", 56),
+                gather!("x13", 160, "x15", 46),
+                chi_row!(80, 88, 96, 104, 112),
+                // Row m.
+                gather!("x9", 32, "x21", 37),
+                gather!("x10", 40, "x15", 28),
+                gather!("x11", 88, "x16", 54),
+                gather!("x12", 136, "x17", 49),
+                gather!("x13", 184, "x20", 8),
+                chi_row!(120, 128, 136, 144, 152),
+                // Row s.
+                gather!("x9", 16, "x17", 2),
+                gather!("x10", 64, "x20", 9),
+                gather!("x11", 112, "x21", 25),
+                gather!("x12", 120, "x15", 23),
+                gather!("x13", 168, "x16", 62),
+                chi_row!(160, 168, 176, 184, 192),
+                // Next round.
+                "mov x22, {a}",
+                "mov {a}, {e}",
+                "mov {e}, x22",
+                "subs {n:w}, {n:w}, #1",
+                "b.ne 2b",
+                a = inout(reg) state.as_mut_ptr() => _,
+                e = inout(reg) scratch.as_mut_ptr() => _,
+                rc = inout(reg) ROUND_CONSTANTS.as_ptr() => _,
+                n = inout(reg) 24u32 => _,
+                out("x9") _,
+                out("x10") _,
+                out("x11") _,
+                out("x12") _,
+                out("x13") _,
+                out("x14") _,
+                out("x15") _,
+                out("x16") _,
+                out("x17") _,
+                out("x20") _,
+                out("x21") _,
+                out("x22") _,
+                options(nostack),
+            );
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub use aarch64::keccak_f1600;
+#[cfg(target_arch = "x86_64")]
+pub use x86_64::keccak_f1600;
@@ -0,0 +1,163 @@
+//! keccak-f[1600] over bit-interleaved 32-bit halves, for targets without
+//! 64-bit registers or instructions.
+//!
+//! Each lane is split into its even bits and its odd bits. A 64-bit rotate
+//! then becomes two 32-bit rotates, instead of the shift-and-or pairs that
+//! a 32-bit target otherwise needs for every emulated `u64::rotate_left`.
+//! The rounds are [`super::unrolled_lanes`], so every rotation count is a
+//! constant and [`Interleaved::rotate_left`] picks its case at compile time.
+//! Lanes are converted on entry and back on exit, so callers keep the usual
+//! `[u64; 25]` state.
+
+use std::ops::{BitAnd, BitXor, Not};
+
+use super::{Lane, ROUND_CONSTANTS};
+
+/// One lane as its even bits and its odd bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Interleaved {
+    even: u32,
+    odd: u32,
+}
+
+/// Move the even bits of `x` into its low half and the odd bits into its
+/// high half, with the delta swaps from Hacker's Delight (7-2), all on
+/// 32-bit words.
+const fn unshuffle(mut x: u32) -> u32 {
+    let mut t;
+    t = (x ^ (x >> 1)) & 0x2222_2222;
+    x ^= t ^ (t << 1);
+    t = (x ^ (x >> 2)) & 0x0c0c_0c0c;
+    x ^= t ^ (t << 2);
+    t = (x ^ (x >> 4)) & 0x00f0_00f0;
+    x ^= t ^ (t << 4);
+    t = (x ^ (x >> 8)) & 0x0000_ff00;
+    x ^= t ^ (t << 8);
+    x
+}
+
+/// The inverse of [`unshuffle`]: the same swaps in reverse order.
+const fn shuffle(mut x: u32) -> u32 {
+    let mut t;
+    t = (x ^ (x >> 8)) & 0x0000_ff00;
+    x ^= t ^ (t << 8);
+    t = (x ^ (x >> 4)) & 0x00f0_00f0;
+    x ^= t ^ (t << 4);
+    t = (x ^ (x >> 2)) & 0x0c0c_0c0c;
+    x ^= t ^ (t << 2);
+    t = (x ^ (x >> 1)) & 0x2222_2222;
+    x ^= t ^ (t << 1);
+    x
+}
+
+impl Interleaved {
+    const fn new(lane: u64) -> Self {
+        let low = unshuffle(lane as u32);
+        let high = unshuffle((lane >> 32) as u32);
+        Interleaved {
+            even: (low & 0xffff) | (high << 16),
+            odd: (low >> 16) | (high & 0xffff_0000),
+        }
+    }
+
+    const fn lane(self) -> u64 {
+        let low = shuffle((self.even & 0xffff) | (self.odd << 16));
+        let high = shuffle((self.even >> 16) | (self.odd & 0xffff_0000));
+        low as u64 | (high as u64) << 32
+    }
+}
+
+impl BitXor for Interleaved {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitxor(self, other: Self) -> Self {
+        Interleaved {
+            even: self.even ^ other.even,
+            odd: self.odd ^ other.odd,
+        }
+    }
+}
+
+impl BitAnd for Interleaved {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitand(self, other: Self) -> Self {
+        Interleaved {
+            even: self.even & other.even,
+            odd: self.odd & other.odd,
+        }
+    }
+}
+
+impl Not for Interleaved {
+    type Output = Self;
+
+    #[inline(always)]
+    fn not(self) -> Self {
+        Interleaved {
+            even: !self.even,
+            odd: !self.odd,
+        }
+    }
+}
+
+impl Lane for Interleaved {
+    /// `lane.rotate_left(bits)` on the 64-bit lane. An even count rotates
+    /// both halves by half as much; an odd one also swaps them, since every
+    /// bit changes parity.
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        if bits.is_multiple_of(2) {
+            Interleaved {
+                even: self.even.rotate_left(bits / 2),
+                odd: self.odd.rotate_left(bits / 2),
+            }
+        } else {
+            Interleaved {
+                even: self.odd.rotate_left(bits / 2 + 1),
+                odd: self.even.rotate_left(bits / 2),
+            }
+        }
+    }
+}
+
+/// The round constants, interleaved once at compile time.
+const CONSTANTS: [Interleaved; 24] = {
+    let mut constants = [Interleaved { even: 0, odd: 0 }; 24];
+    let mut round = 0;
+    while round < 24 {
+        constants[round] = Interleaved::new(ROUND_CONSTANTS[round]);
+        round += 1;
+    }
+    constants
+};
+
+/// Apply the 24-round keccak-f[1600] permutation in place.
+pub fn keccak_f1600(state: &mut [u64; 25]) {
+    let mut lanes = state.map(Interleaved::new);
+    super::unrolled_lanes(&mut lanes, &CONSTANTS);
+    *state = lanes.map(Interleaved::lane);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Interleaving round-trips and rotating halves matches rotating the
+    /// lane, for every count.
+    #[test]
+    fn rotations_match_the_lane() {
+        let lane = 0x8000_0000_0000_0001 ^ 0x0123_4567_89ab_cdef;
+        assert_eq!(Interleaved::new(lane).lane(), lane);
+        for bits in 0..64 {
+            assert_eq!(
+                Lane::rotate_left(Interleaved::new(lane), bits).lane(),
+                lane.rotate_left(bits),
+                "{}",
+                bits
+            );
+        }
+    }
+}
@@ -0,0 +1,1015 @@
+//! Portable, dependency-free Keccak-256 and SHAKE.
+//!
+//! keccak-f[1600] over 25 `u64` lanes, fully unrolled with every lane in a
+//! local. The round-loop form it replaced, which follows the structure of
+//! the Mojo implementation so the two can be profiled against each other,
+//! is kept as [`keccak_f1600_compact`]. The unrolled permutation is also
+//! compiled a second time with AVX2 enabled. Hashers pick a copy with
+//! [`Permutation`], so the code paths can be timed side by side. On 32-bit
+//! targets [`keccak_f1600`] is the bit-interleaved form instead
+//! ([`keccak_f1600_interleaved`]), which needs no 64-bit rotates. wasm32 is
+//! the exception: its runtimes execute `i64` operations natively, where the
+//! interleaved form is several times slower.
+
+use std::cell::RefCell;
+use std::ops::{BitAnd, BitXor, Not};
+
+#[cfg(all(
+    feature = "asm",
+    not(feature = "safe-only"),
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod asm;
+mod interleaved;
+
+/// Sponge rate in bytes for a 256-bit capacity-512 Keccak.
+pub const RATE: usize = 136;
+
+pub(crate) const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+/// Rotation offsets for the rho step, in pi-step visiting order.
+pub(crate) const RHO: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+/// Lane visiting order for the pi step.
+pub(crate) const PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// Apply the 24-round keccak-f[1600] permutation in place.
+pub fn keccak_f1600(state: &mut [u64; 25]) {
+    #[cfg(all(target_pointer_width = "32", not(target_family = "wasm")))]
+    interleaved::keccak_f1600(state);
+    #[cfg(not(all(target_pointer_width = "32", not(target_family = "wasm"))))]
+    unrolled(state);
+}
+
+/// [`keccak_f1600`] on bit-interleaved 32-bit halves, what 32-bit targets
+/// other than wasm run; available everywhere so it can be tested and timed.
+pub fn keccak_f1600_interleaved(state: &mut [u64; 25]) {
+    interleaved::keccak_f1600(state);
+}
+
+/// [`keccak_f1600`] as a loop over rounds, each step a loop over lanes,
+/// as in the Mojo implementation.
+pub fn keccak_f1600_compact(state: &mut [u64; 25]) {
+    rounds(state);
+}
+
+/// [`keccak_f1600`] compiled with AVX2 enabled, leaving LLVM free to use
+/// the wider registers and instructions.
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+#[target_feature(enable = "avx2")]
+unsafe fn keccak_f1600_avx2(state: &mut [u64; 25]) {
+    unrolled(state);
+}
+
+/// Which compiled copy of the permutation a hasher calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Permutation {
+    /// Built for the baseline target, as [`keccak_f1600`].
+    #[default]
+    Generic,
+    /// Built with AVX2; only usable where [`Permutation::is_supported`].
+    Avx2,
+    /// The round loop, as [`keccak_f1600_compact`].
+    Compact,
+    /// The 32-bit form, as [`keccak_f1600_interleaved`].
+    Interleaved,
+    /// The hand-written kernel (`asm` feature): x86-64 with BMI1, or
+    /// aarch64.
+    Asm,
+}
+
+impl Permutation {
+    /// Whether this CPU can run the permutation.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Permutation::Generic | Permutation::Compact | Permutation::Interleaved => true,
+            #[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+            Permutation::Avx2 => std::arch::is_x86_feature_detected!("avx2"),
+            #[cfg(not(all(target_arch = "x86_64", not(feature = "safe-only"))))]
+            Permutation::Avx2 => false,
+            #[cfg(all(feature = "asm", not(feature = "safe-only"), target_arch = "x86_64"))]
+            Permutation::Asm => std::arch::is_x86_feature_detected!("bmi1"),
+            #[cfg(all(feature = "asm", not(feature = "safe-only"), target_arch = "aarch64"))]
+            Permutation::Asm => true,
+            #[cfg(not(all(
+                feature = "asm",
+                not(feature = "safe-only"),
+                any(target_arch = "x86_64", target_arch = "aarch64")
+            )))]
+            Permutation::Asm => false,
+        }
+    }
+
+    fn assert_supported(self) {
+        assert!(
+            self.is_supported(),
+            "{:?} permutation is not supported on this CPU",
+            self
+        );
+    }
+
+    /// Callers must have checked [`Permutation::is_supported`].
+    #[inline]
+    pub(crate) fn apply(self, state: &mut [u64; 25]) {
+        match self {
+            Permutation::Generic => keccak_f1600(state),
+            Permutation::Compact => keccak_f1600_compact(state),
+            Permutation::Interleaved => keccak_f1600_interleaved(state),
+            // SAFETY: hashers only hold `Avx2` after checking support.
+            #[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+            Permutation::Avx2 => unsafe { keccak_f1600_avx2(state) },
+            #[cfg(not(all(target_arch = "x86_64", not(feature = "safe-only"))))]
+            Permutation::Avx2 => unreachable!("AVX2 is never supported here"),
+            // SAFETY: hashers only hold `Asm` after checking support.
+            #[cfg(all(
+                feature = "asm",
+                not(feature = "safe-only"),
+                any(target_arch = "x86_64", target_arch = "aarch64")
+            ))]
+            Permutation::Asm => unsafe { asm::keccak_f1600(state) },
+            #[cfg(not(all(
+                feature = "asm",
+                not(feature = "safe-only"),
+                any(target_arch = "x86_64", target_arch = "aarch64")
+            )))]
+            Permutation::Asm => unreachable!("the asm kernel is not compiled in"),
+        }
+    }
+}
+
+/// One round from the 25 lane variables of `$a` into those of `$e`, in
+/// XKCP's lane naming (row `b g k m s`, column `a e i o u`). Theta's
+/// column parities come first; then each output row gathers its five
+/// rotated input lanes (rho and pi) and applies chi, leaving nothing in an
+/// array.
+macro_rules! round {
+    (
+        $rc:expr,
+        [$aba:ident, $abe:ident, $abi:ident, $abo:ident, $abu:ident,
+         $aga:ident, $age:ident, $agi:ident, $ago:ident, $agu:ident,
+         $aka:ident, $ake:ident, $aki:ident, $ako:ident, $aku:ident,
+         $ama:ident, $ame:ident, $ami:ident, $amo:ident, $amu:ident,
+         $asa:ident, $ase:ident, $asi:ident, $aso:ident, $asu:ident] =>
+        [$eba:ident, $ebe:ident, $ebi:ident, $ebo:ident, $ebu:ident,
+         $ega:ident, $ege:ident, $egi:ident, $ego:ident, $egu:ident,
+         $eka:ident, $eke:ident, $eki:ident, $eko:ident, $eku:ident,
+         $ema:ident, $eme:ident, $emi:ident, $emo:ident, $emu:ident,
+         $esa:ident, $ese:ident, $esi:ident, $eso:ident, $esu:ident]
+    ) => {{
+        let ca = $aba ^ $aga ^ $aka ^ $ama ^ $asa;
+        let ce = $abe ^ $age ^ $ake ^ $ame ^ $ase;
+        let ci = $abi ^ $agi ^ $aki ^ $ami ^ $asi;
+        let co = $abo ^ $ago ^ $ako ^ $amo ^ $aso;
+        let cu = $abu ^ $agu ^ $aku ^ $amu ^ $asu;
+        let da = cu ^ ce.rotate_left(1);
+        let de = ca ^ ci.rotate_left(1);
+        let di = ce ^ co.rotate_left(1);
+        let d_o = ci ^ cu.rotate_left(1);
+        let du = co ^ ca.rotate_left(1);
+
+        let b0 = $aba ^ da;
+        let b1 = ($age ^ de).rotate_left(44);
+        let b2 = ($aki ^ di).rotate_left(43);
+        let b3 = ($amo ^ d_o).rotate_left(21);
+        let b4 = ($asu ^ du).rotate_left(14);
+        $eba = b0 ^ (!b1 & b2) ^ $rc;
+        $ebe = b1 ^ (!b2 & b3);
+        $ebi = b2 ^ (!b3 & b4);
+        $ebo = b3 ^ (!b4 & b0);
+        $ebu = b4 ^ (!b0 & b1);
+
+        let b0 = ($abo ^ d_o).rotate_left(28);
+        let b1 = ($agu ^ du).rotate_left(20);
+        let b2 = ($aka ^ da).rotate_left(3);
+        let b3 = ($ame ^ de).rotate_left(45);
+        let b4 = ($asi ^ di).rotate_left(61);
+        $ega = b0 ^ (!b1 & b2);
+        $ege = b1 ^ (!b2 & b3);
+        $egi = b2 ^ (!b3 & b4);
+        $ego = b3 ^ (!b4 & b0);
+        $egu = b4 ^ (!b0 & b1);
+
+        let b0 = ($abe ^ de).rotate_left(1);
+        let b1 = ($agi ^ di).rotate_left(6);
+        let b2 = ($ako ^ d_o).rotate_left(25);
+        let b3 = ($amu ^ du).rotate_left(8);
+        let b4 = ($asa ^ da).rotate_left(18);
+        $eka = b0 ^ (!b1 & b2);
+        $eke = b1 ^ (!b2 & b3);
+        $eki = b2 ^ (!b3 & b4);
+        $eko = b3 ^ (!b4 & b0);
+        $eku = b4 ^ (!b0 & b1);
+
+        let b0 = ($abu ^ du).rotate_left(27);
+        let b1 = ($aga ^ da).rotate_left(36);
+        let b2 = ($ake ^ de).rotate_left(10);
+        let b3 = ($ami ^ di).rotate_left(15);
+        let b4 = ($aso ^ d_o).rotate_left(56);
+        $ema = b0 ^ (!b1 & b2);
+        $eme = b1 ^ (!b2 & b3);
+        $emi = b2 ^ (!b3 & b4);
+        $emo = b3 ^ (!b4 & b0);
+        $emu = b4 ^ (!b0 & b1);
+
+        let b0 = ($abi ^ di).rotate_left(62);
+        let b1 = ($ago ^ d_o).rotate_left(55);
+        let b2 = ($aku ^ du).rotate_left(39);
+        let b3 = ($ama ^ da).rotate_left(41);
+        let b4 = ($ase ^ de).rotate_left(2);
+        $esa = b0 ^ (!b1 & b2);
+        $ese = b1 ^ (!b2 & b3);
+        $esi = b2 ^ (!b3 & b4);
+        $eso = b3 ^ (!b4 & b0);
+        $esu = b4 ^ (!b0 & b1);
+    }};
+}
+
+/// What [`round!`] needs of a lane: `u64` itself, or the interleaved
+/// halves of one.
+trait Lane: Copy + BitXor<Output = Self> + BitAnd<Output = Self> + Not<Output = Self> {
+    fn rotate_left(self, bits: u32) -> Self;
+}
+
+impl Lane for u64 {
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        u64::rotate_left(self, bits)
+    }
+}
+
+/// keccak-f[1600] with all 24 rounds written out. Rounds alternate between
+/// two sets of lane variables, so no round copies its output back, and the
+/// round constants are immediates. 32-bit targets other than wasm only
+/// call it from tests.
+#[cfg_attr(
+    all(target_pointer_width = "32", not(target_family = "wasm")),
+    allow(dead_code)
+)]
+#[inline(always)]
+fn unrolled(state: &mut [u64; 25]) {
+    unrolled_lanes(state, &ROUND_CONSTANTS);
+}
+
+/// [`unrolled`] over any [`Lane`], with `constants` in the same form.
+#[inline(always)]
+fn unrolled_lanes<L: Lane>(state: &mut [L; 25], constants: &[L; 24]) {
+    #[rustfmt::skip]
+    let [
+        mut aba, mut abe, mut abi, mut abo, mut abu,
+        mut aga, mut age, mut agi, mut ago, mut agu,
+        mut aka, mut ake, mut aki, mut ako, mut aku,
+        mut ama, mut ame, mut ami, mut amo, mut amu,
+        mut asa, mut ase, mut asi, mut aso, mut asu,
+    ] = *state;
+    let (mut eba, mut ebe, mut ebi, mut ebo, mut ebu): (L, L, L, L, L);
+    let (mut ega, mut ege, mut egi, mut ego, mut egu): (L, L, L, L, L);
+    let (mut eka, mut eke, mut eki, mut eko, mut eku): (L, L, L, L, L);
+    let (mut ema, mut eme, mut emi, mut emo, mut emu): (L, L, L, L, L);
+    let (mut esa, mut ese, mut esi, mut eso, mut esu): (L, L, L, L, L);
+
+    macro_rules! two_rounds {
+        ($first:expr) => {
+            round!(
+                constants[$first],
+                [aba, abe, abi, abo, abu, aga, age, agi, ago, agu, aka, ake, aki, ako, aku,
+                 ama, ame, ami, amo, amu, asa, ase, asi, aso, asu] =>
+                [eba, ebe, ebi, ebo, ebu, ega, ege, egi, ego, egu, eka, eke, eki, eko, eku,
+                 ema, eme, emi, emo, emu, esa, ese, esi, eso, esu]
+            );
+            round!(
+                constants[$first + 1],
+                [eba, ebe, ebi, ebo, ebu, ega, ege, egi, ego, egu, eka, eke, eki, eko, eku,
+                 ema, eme, emi, emo, emu, esa, ese, esi, eso, esu] =>
+                [aba, abe, abi, abo, abu, aga, age, agi, ago, agu, aka, ake, aki, ako, aku,
+                 ama, ame, ami, amo, amu, asa, ase, asi, aso, asu]
+            );
+        };
+    }
+    two_rounds!(0);
+    two_rounds!(2);
+    two_rounds!(4);
+    two_rounds!(6);
+    two_rounds!(8);
+    two_rounds!(10);
+    two_rounds!(12);
+    two_rounds!(14);
+    two_rounds!(16);
+    two_rounds!(18);
+    two_rounds!(20);
+    two_rounds!(22);
+
+    *state = [
+        aba, abe, abi, abo, abu, aga, age, agi, ago, agu, aka, ake, aki, ako, aku, ama, ame, ami,
+        amo, amu, asa, ase, asi, aso, asu,
+    ];
+}
+
+#[inline(always)]
+fn rounds(state: &mut [u64; 25]) {
+    for &round_constant in &ROUND_CONSTANTS {
+        // Theta.
+        let mut columns = [0u64; 5];
+        for (x, column) in columns.iter_mut().enumerate() {
+            *column = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            for y in (0..25).step_by(5) {
+                state[y + x] ^= d;
+            }
+        }
+
+        // Rho and pi.
+        let mut carried = state[1];
+        for (&lane, &rotation) in PI.iter().zip(&RHO) {
+            let next = state[lane];
+            state[lane] = carried.rotate_left(rotation);
+            carried = next;
+        }
+
+        // Chi.
+        for y in (0..25).step_by(5) {
+            let row = [
+                state[y],
+                state[y + 1],
+                state[y + 2],
+                state[y + 3],
+                state[y + 4],
+            ];
+            for x in 0..5 {
+                state[y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // Iota.
+        state[0] ^= round_constant;
+    }
+}
+
+/// The 1600-bit sponge state, on a cache line of its own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C, align(64))]
+struct State([u64; 25]);
+
+impl State {
+    /// XOR `bytes` into the state from byte `offset` on, in the sponge's
+    /// little-endian lane order. Whole lanes go in a word at a time and
+    /// only the unaligned ends byte by byte, so input is never copied
+    /// into a block first.
+    #[inline]
+    fn xor_bytes(&mut self, mut offset: usize, mut bytes: &[u8]) {
+        while !offset.is_multiple_of(8) {
+            let Some((&byte, rest)) = bytes.split_first() else {
+                return;
+            };
+            self.0[offset / 8] ^= u64::from(byte) << (8 * (offset % 8));
+            offset += 1;
+            bytes = rest;
+        }
+        let mut words = bytes.chunks_exact(8);
+        for (lane, word) in self.0[offset / 8..].iter_mut().zip(&mut words) {
+            *lane ^= u64::from_le_bytes(word.try_into().expect("8-byte word"));
+        }
+        let lane = (offset + bytes.len()) / 8;
+        for (index, &byte) in words.remainder().iter().enumerate() {
+            self.0[lane] ^= u64::from(byte) << (8 * index);
+        }
+    }
+}
+
+/// Incremental Keccak-256 hasher.
+#[derive(Clone)]
+pub struct Keccak256 {
+    state: State,
+    /// Bytes of the current block already XORed into the state.
+    absorbed: usize,
+    permutation: Permutation,
+}
+
+impl Default for Keccak256 {
+    fn default() -> Self {
+        Keccak256::new()
+    }
+}
+
+impl Keccak256 {
+    pub fn new() -> Keccak256 {
+        Keccak256::with_permutation(Permutation::Generic)
+    }
+
+    /// A hasher calling `permutation`.
+    ///
+    /// # Panics
+    ///
+    /// If the CPU does not support `permutation`.
+    pub fn with_permutation(permutation: Permutation) -> Keccak256 {
+        permutation.assert_supported();
+        Keccak256 {
+            state: State::default(),
+            absorbed: 0,
+            permutation,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        if self.absorbed > 0 {
+            let take = data.len().min(RATE - self.absorbed);
+            self.state.xor_bytes(self.absorbed, &data[..take]);
+            self.absorbed += take;
+            data = &data[take..];
+            if self.absorbed < RATE {
+                return;
+            }
+            self.permutation.apply(&mut self.state.0);
+            self.absorbed = 0;
+        }
+        let mut blocks = data.chunks_exact(RATE);
+        for block in &mut blocks {
+            self.state.xor_bytes(0, block);
+            self.permutation.apply(&mut self.state.0);
+        }
+        let rest = blocks.remainder();
+        self.state.xor_bytes(0, rest);
+        self.absorbed = rest.len();
+    }
+
+    /// Apply the original Keccak `0x01 ... 0x80` padding and squeeze.
+    pub fn finalize(mut self) -> [u8; 32] {
+        self.finish()
+    }
+
+    /// [`Keccak256::finalize`] without giving up the hasher, which is left
+    /// [`reset`](Keccak256::reset) for the next message.
+    pub fn finalize_reset(&mut self) -> [u8; 32] {
+        let digest = self.finish();
+        self.reset();
+        digest
+    }
+
+    /// Forget everything absorbed so far, keeping the permutation, so one
+    /// hasher can serve many messages without being rebuilt.
+    pub fn reset(&mut self) {
+        self.state = State::default();
+        self.absorbed = 0;
+    }
+
+    fn finish(&mut self) -> [u8; 32] {
+        self.state.xor_bytes(self.absorbed, &[0x01]);
+        self.state.xor_bytes(RATE - 1, &[0x80]);
+        self.permutation.apply(&mut self.state.0);
+        digest(&self.state.0)
+    }
+}
+
+/// A [`Keccak256`] with a common prefix (a domain tag, a key, a fixed
+/// header) absorbed once. It is never mutated after construction, so one
+/// instance behind an `Arc` serves every thread of a benchmark or server:
+/// each message starts from a copy of the absorbed state rather than
+/// rehashing the prefix.
+#[derive(Clone)]
+pub struct Midstate(Keccak256);
+
+impl Midstate {
+    pub fn new(prefix: &[u8]) -> Midstate {
+        Midstate::with_permutation(Permutation::Generic, prefix)
+    }
+
+    /// A midstate whose hashers call `permutation`.
+    ///
+    /// # Panics
+    ///
+    /// If the CPU does not support `permutation`.
+    pub fn with_permutation(permutation: Permutation, prefix: &[u8]) -> Midstate {
+        let mut hasher = Keccak256::with_permutation(permutation);
+        hasher.update(prefix);
+        Midstate(hasher)
+    }
+
+    /// A hasher that has absorbed the prefix and nothing else.
+    pub fn hasher(&self) -> Keccak256 {
+        self.0.clone()
+    }
+
+    /// The digest of the prefix followed by `suffix`.
+    pub fn keccak256(&self, suffix: &[u8]) -> [u8; 32] {
+        let mut hasher = self.hasher();
+        hasher.update(suffix);
+        hasher.finalize()
+    }
+}
+
+/// Sponge rate in bytes for SHAKE128, the larger of the two SHAKE rates.
+pub const SHAKE128_RATE: usize = 168;
+
+/// Incremental SHAKE128 or SHAKE256, the FIPS 202 extendable-output
+/// functions. Kept separate from [`Keccak256`] so that type keeps its
+/// fixed-rate loop.
+#[derive(Clone)]
+pub struct Shake {
+    state: State,
+    rate: usize,
+    /// The padding's first byte, `0x1f` for SHAKE and `0x06` for SHA-3.
+    domain: u8,
+    /// Bytes of the current block already XORed into the state.
+    absorbed: usize,
+}
+
+impl Shake {
+    pub fn shake128() -> Shake {
+        Shake::sponge(SHAKE128_RATE, 0x1f)
+    }
+
+    pub fn shake256() -> Shake {
+        Shake::sponge(RATE, 0x1f)
+    }
+
+    fn sponge(rate: usize, domain: u8) -> Shake {
+        Shake {
+            state: State::default(),
+            rate,
+            domain,
+            absorbed: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        let rate = self.rate;
+        if self.absorbed > 0 {
+            let take = data.len().min(rate - self.absorbed);
+            self.state.xor_bytes(self.absorbed, &data[..take]);
+            self.absorbed += take;
+            data = &data[take..];
+            if self.absorbed < rate {
+                return;
+            }
+            keccak_f1600(&mut self.state.0);
+            self.absorbed = 0;
+        }
+        let mut blocks = data.chunks_exact(rate);
+        for block in &mut blocks {
+            self.state.xor_bytes(0, block);
+            keccak_f1600(&mut self.state.0);
+        }
+        let rest = blocks.remainder();
+        self.state.xor_bytes(0, rest);
+        self.absorbed = rest.len();
+    }
+
+    /// Apply the FIPS 202 `0x1f ... 0x80` padding and start squeezing.
+    pub fn finalize_xof(mut self) -> ShakeReader {
+        self.state.xor_bytes(self.absorbed, &[self.domain]);
+        self.state.xor_bytes(self.rate - 1, &[0x80]);
+        keccak_f1600(&mut self.state.0);
+        ShakeReader::new(self.state.0, self.rate)
+    }
+}
+
+/// Incremental SHA3-256, which differs from Keccak-256 only in its
+/// `0x06` padding byte. It shares [`Shake`]'s variable-rate loop rather
+/// than [`Keccak256`]'s permutation choice, as it is not benchmarked.
+#[derive(Clone)]
+pub struct Sha3_256(Shake);
+
+impl Default for Sha3_256 {
+    fn default() -> Self {
+        Sha3_256::new()
+    }
+}
+
+impl Sha3_256 {
+    pub fn new() -> Sha3_256 {
+        Sha3_256(Shake::sponge(RATE, 0x06))
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    pub fn finalize(self) -> [u8; 32] {
+        let mut digest = [0u8; 32];
+        self.0.finalize_xof().squeeze(&mut digest);
+        digest
+    }
+}
+
+/// The squeezing half of a [`Shake`]; yields output of any length.
+pub struct ShakeReader {
+    state: [u64; 25],
+    /// The current state serialized, refreshed after each permutation.
+    bytes: [u8; 200],
+    rate: usize,
+    offset: usize,
+}
+
+impl ShakeReader {
+    fn new(state: [u64; 25], rate: usize) -> ShakeReader {
+        let mut reader = ShakeReader {
+            state,
+            bytes: [0; 200],
+            rate,
+            offset: 0,
+        };
+        reader.serialize();
+        reader
+    }
+
+    fn serialize(&mut self) {
+        for (chunk, lane) in self.bytes.chunks_exact_mut(8).zip(&self.state) {
+            chunk.copy_from_slice(&lane.to_le_bytes());
+        }
+    }
+
+    /// Fill `output` with the next bytes of the stream.
+    pub fn squeeze(&mut self, mut output: &mut [u8]) {
+        while !output.is_empty() {
+            if self.offset == self.rate {
+                keccak_f1600(&mut self.state);
+                self.serialize();
+                self.offset = 0;
+            }
+            let take = output.len().min(self.rate - self.offset);
+            output[..take].copy_from_slice(&self.bytes[self.offset..self.offset + take]);
+            self.offset += take;
+            output = &mut output[take..];
+        }
+    }
+}
+
+/// One-shot Keccak-256 using the portable implementation.
+pub fn keccak256(message: &[u8]) -> [u8; 32] {
+    keccak256_with(Permutation::Generic, message)
+}
+
+/// One-shot Keccak-256 calling `permutation`; panics where it is
+/// unsupported.
+pub fn keccak256_with(permutation: Permutation, message: &[u8]) -> [u8; 32] {
+    if message.len() < RATE {
+        permutation.assert_supported();
+        return keccak256_short(permutation, message);
+    }
+    let mut hasher = Keccak256::with_permutation(permutation);
+    hasher.update(message);
+    hasher.finalize()
+}
+
+/// A message shorter than the rate, which is most Ethereum input: the
+/// message and its padding are written straight into a zero state and
+/// permuted once, with no block buffer or absorb loop.
+#[inline]
+fn keccak256_short(permutation: Permutation, message: &[u8]) -> [u8; 32] {
+    let mut state = State::default();
+    let mut words = message.chunks_exact(8);
+    for (lane, word) in state.0.iter_mut().zip(&mut words) {
+        *lane = u64::from_le_bytes(word.try_into().expect("8-byte word"));
+    }
+    let rest = words.remainder();
+    let mut last = [0u8; 8];
+    last[..rest.len()].copy_from_slice(rest);
+    last[rest.len()] = 0x01;
+    state.0[message.len() / 8] = u64::from_le_bytes(last);
+    state.0[RATE / 8 - 1] ^= 0x80 << 56;
+    permutation.apply(&mut state.0);
+    digest(&state.0)
+}
+
+/// The first 32 bytes of the state, little-endian lane by lane.
+fn digest(state: &[u64; 25]) -> [u8; 32] {
+    let mut output = [0u8; 32];
+    for (chunk, lane) in output.chunks_exact_mut(8).zip(state) {
+        chunk.copy_from_slice(&lane.to_le_bytes());
+    }
+    output
+}
+
+thread_local! {
+    static HASHER: RefCell<Keccak256> = RefCell::new(Keccak256::new());
+}
+
+/// Run `f` with this thread's reusable generic hasher, empty on entry, so
+/// loops over many small messages skip building a sponge per message. A
+/// nested call gets a fresh hasher instead.
+pub fn with_thread_hasher<R>(f: impl FnOnce(&mut Keccak256) -> R) -> R {
+    HASHER.with(|cell| match cell.try_borrow_mut() {
+        Ok(mut hasher) => {
+            hasher.reset();
+            f(&mut hasher)
+        }
+        Err(_) => f(&mut Keccak256::new()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+
+    /// `data` split at `cuts`, each taken modulo the possible positions.
+    fn split<'a>(data: &'a [u8], cuts: &[Index]) -> Vec<&'a [u8]> {
+        let mut points: Vec<usize> = cuts.iter().map(|cut| cut.index(data.len() + 1)).collect();
+        points.push(0);
+        points.push(data.len());
+        points.sort_unstable();
+        points.windows(2).map(|w| &data[w[0]..w[1]]).collect()
+    }
+
+    /// Every length around the rate boundary matches tiny-keccak.
+    #[test]
+    fn native_matches_tiny_keccak() {
+        let data: Vec<u8> = (0..=600u32).map(|i| (i * 7 + 3) as u8).collect();
+        for len in 0..data.len() {
+            assert_eq!(
+                keccak256(&data[..len]),
+                crate::keccak256(&data[..len]),
+                "{}",
+                len
+            );
+        }
+    }
+
+    /// XORing a slice in lands every byte where the serialized state has
+    /// it, from any offset, and the state sits on its own cache line.
+    #[test]
+    fn state_xors_bytes_in_place() {
+        assert_eq!(std::mem::align_of::<State>(), 64);
+        let bytes: Vec<u8> = (1..=200u8).collect();
+        for offset in 0..20 {
+            for len in 0..=200 - offset {
+                let mut state = State::default();
+                state.xor_bytes(offset, &bytes[..len]);
+                let mut expected = [0u8; 200];
+                expected[offset..offset + len].copy_from_slice(&bytes[..len]);
+                let serialized: Vec<u8> =
+                    state.0.iter().flat_map(|lane| lane.to_le_bytes()).collect();
+                assert_eq!(serialized, expected, "{} {}", offset, len);
+            }
+        }
+    }
+
+    /// A reset or reused hasher forgets earlier input, buffered or
+    /// absorbed, and keeps its permutation.
+    #[test]
+    fn reused_hashers_start_clean() {
+        let long = [3u8; 300];
+        let mut hasher = Keccak256::new();
+        hasher.update(&long);
+        hasher.reset();
+        hasher.update(b"abc");
+        assert_eq!(hasher.finalize_reset(), keccak256(b"abc"));
+        hasher.update(&long[..5]);
+        assert_eq!(hasher.finalize_reset(), keccak256(&long[..5]));
+        assert_eq!(hasher.clone().finalize(), keccak256(b""));
+
+        let digest = with_thread_hasher(|outer| {
+            outer.update(&long);
+            let inner = with_thread_hasher(|inner| {
+                inner.update(b"abc");
+                inner.finalize_reset()
+            });
+            assert_eq!(inner, keccak256(b"abc"));
+            outer.finalize_reset()
+        });
+        assert_eq!(digest, keccak256(&long));
+        let empty = with_thread_hasher(|hasher| hasher.finalize_reset());
+        assert_eq!(empty, keccak256(b""));
+    }
+
+    /// One midstate shared by several threads gives each of them the
+    /// digest of the prefix and its own suffix, for prefixes on both sides
+    /// of a block.
+    #[test]
+    fn shared_midstate_hashes_suffixes() {
+        let data: Vec<u8> = (0..400u32).map(|i| (i * 11 + 1) as u8).collect();
+        for prefix_len in [0, 26, RATE, 300] {
+            let (prefix, rest) = data.split_at(prefix_len);
+            let midstate = std::sync::Arc::new(Midstate::new(prefix));
+            std::thread::scope(|scope| {
+                for worker in 0..4 {
+                    let midstate = std::sync::Arc::clone(&midstate);
+                    scope.spawn(move || {
+                        for len in (worker..rest.len().min(100)).step_by(9) {
+                            let suffix = &rest[..len];
+                            assert_eq!(
+                                midstate.keccak256(suffix),
+                                keccak256(&[prefix, suffix].concat()),
+                                "{} {}",
+                                prefix_len,
+                                len
+                            );
+                        }
+                    });
+                }
+            });
+        }
+    }
+
+    /// The unrolled, compact and interleaved permutations agree on
+    /// arbitrary states.
+    #[test]
+    fn unrolled_matches_compact() {
+        let mut seed = 0x0123_4567_89ab_cdefu64;
+        for _ in 0..50 {
+            let state: [u64; 25] = std::array::from_fn(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                seed
+            });
+            let (mut unrolled, mut compact, mut interleaved) = (state, state, state);
+            super::unrolled(&mut unrolled);
+            keccak_f1600_compact(&mut compact);
+            keccak_f1600_interleaved(&mut interleaved);
+            assert_eq!(unrolled, compact);
+            assert_eq!(interleaved, compact);
+        }
+    }
+
+    /// The vectorized build computes the same digests as the generic one.
+    #[test]
+    fn permutations_agree() {
+        if !Permutation::Avx2.is_supported() {
+            return;
+        }
+        let data: Vec<u8> = (0..600u32).map(|i| (i * 11) as u8).collect();
+        for len in [0, 1, 135, 136, 137, 599] {
+            assert_eq!(
+                keccak256_with(Permutation::Avx2, &data[..len]),
+                keccak256(&data[..len]),
+                "{}",
+                len
+            );
+        }
+    }
+
+    /// Every permutation reproduces the XKCP Keccak-f[1600]
+    /// states for the zero state permuted once and twice.
+    #[test]
+    fn permutation_matches_xkcp_states() {
+        let path = format!("{}/KeccakF-1600-states.txt", crate::kat::XKCP_DIR);
+        let records = crate::kat::parse(&std::fs::read_to_string(path).unwrap());
+        assert_eq!(records.len(), 2);
+        for permutation in [
+            Permutation::Generic,
+            Permutation::Avx2,
+            Permutation::Compact,
+            Permutation::Interleaved,
+            Permutation::Asm,
+        ] {
+            if !permutation.is_supported() {
+                continue;
+            }
+            let mut state = [0u64; 25];
+            let mut applied = 0;
+            for record in &records {
+                let expected: Vec<u64> = record.fields["State"]
+                    .split_whitespace()
+                    .map(|lane| u64::from_str_radix(lane, 16).unwrap())
+                    .collect();
+                while applied < record.fields["Permutations"].parse().unwrap() {
+                    permutation.apply(&mut state);
+                    applied += 1;
+                }
+                assert_eq!(state[..], expected[..], "{:?} x{}", permutation, applied);
+            }
+        }
+    }
+
+    /// Splitting the input across updates does not change the digest.
+    #[test]
+    fn streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        for chunk in [1, 13, 135, 136, 137, 999] {
+            let mut hasher = Keccak256::new();
+            for piece in data.chunks(chunk) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finalize(), keccak256(&data), "{}", chunk);
+        }
+    }
+
+    /// FIPS 202 empty-message vectors.
+    #[test]
+    fn shake_matches_reference_vectors() {
+        let mut output = [0u8; 32];
+        Shake::shake128().finalize_xof().squeeze(&mut output);
+        assert_eq!(
+            crate::to_hex_string(&output),
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+        );
+        Shake::shake256().finalize_xof().squeeze(&mut output);
+        assert_eq!(
+            crate::to_hex_string(&output),
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f"
+        );
+    }
+
+    /// Output does not depend on how the input or output is split.
+    #[test]
+    fn shake_streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 5) as u8).collect();
+        let squeeze_all = |chunk: usize, step: usize| {
+            let mut shake = Shake::shake128();
+            for piece in data.chunks(chunk) {
+                shake.update(piece);
+            }
+            let mut reader = shake.finalize_xof();
+            let mut output = vec![0u8; 500];
+            for piece in output.chunks_mut(step) {
+                reader.squeeze(piece);
+            }
+            output
+        };
+        let expected = squeeze_all(data.len(), 500);
+        for (chunk, step) in [(1, 1), (167, 168), (168, 169), (169, 7)] {
+            assert_eq!(squeeze_all(chunk, step), expected, "{} {}", chunk, step);
+        }
+    }
+
+    proptest! {
+        /// Any chunking of any message hashes like the whole message, and
+        /// the whole message hashes like tiny-keccak.
+        #[test]
+        fn arbitrary_chunkings_match_one_shot(
+            data in vec(any::<u8>(), 0..700),
+            cuts in vec(any::<Index>(), 0..8),
+        ) {
+            let mut hasher = Keccak256::new();
+            for piece in split(&data, &cuts) {
+                hasher.update(piece);
+            }
+            prop_assert_eq!(hasher.finalize(), crate::keccak256(&data));
+        }
+
+        /// The hand-written kernel, where it is compiled in and the CPU
+        /// runs it, hashes random input like the Rust permutation.
+        #[test]
+        fn asm_matches_rust(data in vec(any::<u8>(), 0..700)) {
+            if Permutation::Asm.is_supported() {
+                prop_assert_eq!(keccak256_with(Permutation::Asm, &data), keccak256(&data));
+            }
+        }
+
+        /// SHAKE output is the same however the input is absorbed and the
+        /// output squeezed.
+        #[test]
+        fn shake_arbitrary_chunkings_match_one_shot(
+            data in vec(any::<u8>(), 0..500),
+            cuts in vec(any::<Index>(), 0..8),
+            steps in vec(any::<Index>(), 0..8),
+            wide in any::<bool>(),
+        ) {
+            let new = || if wide { Shake::shake256() } else { Shake::shake128() };
+            let mut whole = new();
+            whole.update(&data);
+            let mut expected = [0u8; 400];
+            whole.finalize_xof().squeeze(&mut expected);
+
+            let mut shake = new();
+            for piece in split(&data, &cuts) {
+                shake.update(piece);
+            }
+            let mut reader = shake.finalize_xof();
+            let mut output = [0u8; 400];
+            let mut offset = 0;
+            for piece in split(&expected, &steps) {
+                reader.squeeze(&mut output[offset..offset + piece.len()]);
+                offset += piece.len();
+            }
+            prop_assert_eq!(output, expected);
+        }
+    }
+}
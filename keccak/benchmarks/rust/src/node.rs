@@ -0,0 +1,56 @@
+//! Node.js bindings (`node` feature), so TypeScript tooling uses the same
+//! baseline the benchmarks measure rather than an unrelated JS library.
+//!
+//! `cargo build --release --lib --features node`, then copy
+//! `target/release/libkeccak256_rust_baseline.so` (`.dylib` on macOS) to
+//! `keccak256_rust_baseline.node` and `require` it. Bytes are `Buffer`s.
+
+use napi::bindgen_prelude::Buffer;
+use napi::{Error, Result};
+use napi_derive::napi;
+
+use crate::native;
+
+/// The Keccak-256 digest of `data`.
+#[napi(js_name = "keccak256")]
+pub fn keccak256(data: Buffer) -> Buffer {
+    crate::keccak256(&data).to_vec().into()
+}
+
+/// The Keccak-256 digest of `data` as lowercase hex.
+#[napi(js_name = "keccak256Hex")]
+pub fn keccak256_hex(data: Buffer) -> String {
+    crate::to_hex_string(&crate::keccak256(&data))
+}
+
+/// The bytes `text` spells in hex, with an optional `0x` prefix.
+#[napi(js_name = "fromHex")]
+pub fn from_hex(text: String) -> Result<Buffer> {
+    crate::from_hex(&text)
+        .map(Buffer::from)
+        .map_err(|err| Error::from_reason(err.to_string()))
+}
+
+/// Incremental Keccak-256: `update` any number of times, then `digest`,
+/// which leaves the hasher usable.
+#[napi(js_name = "Keccak256")]
+#[derive(Default)]
+pub struct Hasher(native::Keccak256);
+
+#[napi]
+impl Hasher {
+    #[napi(constructor)]
+    pub fn new() -> Hasher {
+        Hasher::default()
+    }
+
+    #[napi]
+    pub fn update(&mut self, data: Buffer) {
+        self.0.update(&data);
+    }
+
+    #[napi]
+    pub fn digest(&self) -> Buffer {
+        self.0.clone().finalize().to_vec().into()
+    }
+}
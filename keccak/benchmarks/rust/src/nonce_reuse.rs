@@ -0,0 +1,185 @@
+//! Private keys leaked by reused ECDSA nonces.
+//!
+//! A signature `(r, s)` over hash `z` satisfies `s * k = z + r * d`, where
+//! `r` is the x coordinate of `k * G`. Two signatures by the same key with
+//! the same `r` used the nonce `k` or, after low-s normalization or an
+//! unlucky signer, its negation `n - k`, which has the same x coordinate.
+//! Either way two equations share their two unknowns and [`recover_key`]
+//! solves them, giving one candidate key per sign it has to guess.
+//! [`scan`] groups a set of signatures by `r` and keeps the candidates
+//! every pair in a group agrees on; from three signatures that is usually
+//! the key alone. A candidate is confirmed by comparing `G.mul(&candidate)`
+//! with the public key.
+
+use std::collections::HashMap;
+
+use crate::secp256k1::Scalar;
+use crate::signature::Signature;
+
+/// A signature and the 32-byte message hash it signs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignedHash {
+    pub hash: [u8; 32],
+    pub signature: Signature,
+}
+
+/// Signatures in a [`scan`]ned set that share an `r`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub r: Scalar,
+    /// Positions in the scanned set, in order.
+    pub indices: Vec<usize>,
+    /// Keys consistent with every pair of distinct hashes in the group.
+    /// Empty when they disagree, as when different keys drew the same
+    /// nonce.
+    pub candidates: Vec<Scalar>,
+}
+
+/// The keys that could have made both signatures with one nonce, up to
+/// sign. Empty when their `r` differ or they sign the same hash, which
+/// leaks nothing.
+pub fn recover_key(a: &SignedHash, b: &SignedHash) -> Vec<Scalar> {
+    let r = a.signature.r;
+    if b.signature.r != r || a.hash == b.hash {
+        return Vec::new();
+    }
+    let r_inverse = r.invert().expect("signature r is nonzero");
+    let (za, zb) = (Scalar::reduce(&a.hash), Scalar::reduce(&b.hash));
+    let (sa, sb) = (a.signature.s, b.signature.s);
+    let mut candidates = Vec::new();
+    // `b` used `k`, so `(sa - sb) k = za - zb`, or `n - k`, so
+    // `(sa + sb) k = za - zb`.
+    for denominator in [sa.sub(&sb), sa.add(&sb)] {
+        let Some(inverse) = denominator.invert() else {
+            continue;
+        };
+        let k = za.sub(&zb).mul(&inverse);
+        let d = sa.mul(&k).sub(&za).mul(&r_inverse);
+        if !d.is_zero() && !candidates.contains(&d) {
+            candidates.push(d);
+        }
+    }
+    candidates
+}
+
+/// Every `r` that two signatures in `set` with different hashes share,
+/// with the keys they point to, in order of first appearance.
+pub fn scan(set: &[SignedHash]) -> Vec<Finding> {
+    let mut groups: HashMap<Scalar, Vec<usize>> = HashMap::new();
+    let mut order = Vec::new();
+    for (index, signed) in set.iter().enumerate() {
+        let group = groups.entry(signed.signature.r).or_default();
+        if group.is_empty() {
+            order.push(signed.signature.r);
+        }
+        group.push(index);
+    }
+    let mut findings = Vec::new();
+    for r in order {
+        let indices = groups.remove(&r).expect("grouped above");
+        let first = &set[indices[0]];
+        let mut candidates: Option<Vec<Scalar>> = None;
+        for &index in &indices[1..] {
+            if set[index].hash == first.hash {
+                continue;
+            }
+            let pair = recover_key(first, &set[index]);
+            candidates = Some(match candidates {
+                None => pair,
+                Some(kept) => kept.into_iter().filter(|d| pair.contains(d)).collect(),
+            });
+        }
+        if let Some(candidates) = candidates {
+            findings.push(Finding {
+                r,
+                indices,
+                candidates,
+            });
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar(text: &str) -> Scalar {
+        Scalar::from_bytes(&hex::decode(text).unwrap().try_into().unwrap()).unwrap()
+    }
+
+    /// Signatures over SHA-256 of "first", "second" and "third", computed
+    /// with Python from a fixed key, nonce and `r`; "third" used `n - k`.
+    fn reused() -> Vec<SignedHash> {
+        let r = scalar("336f9b5b536a9f5325e074cacf7331170f87de1e9900d81aa18f9788f7b37815");
+        [
+            (
+                "a7937b64b8caa58f03721bb6bacf5c78cb235febe0e70b1b84cd99541461a08e",
+                "cb7af1f8920b393d9818b2e68d620049411e75defc8cd023e892e34929c61629",
+            ),
+            (
+                "16367aacb67a4a017c8da8ab95682ccb390863780f7114dda0a0e0c55644c7c4",
+                "ddcb23db1e2c98f63e27aa1abdb05ae453ac2848d3360a984560358c08fefafb",
+            ),
+            (
+                "b1e99324505bd32da0e1f85dcf5e19a09db0481e8a15f62c41eb320304a8e927",
+                "16c43929e3e908c88c53b2eff2d83dd7b32961482d1bec9b0ca4288493386182",
+            ),
+        ]
+        .into_iter()
+        .map(|(hash, s)| SignedHash {
+            hash: scalar(hash).to_bytes(),
+            signature: Signature::new(r, scalar(s)).unwrap(),
+        })
+        .collect()
+    }
+
+    /// Each pair of reused signatures yields the key among its candidates,
+    /// whichever sign the second nonce had.
+    #[test]
+    fn pairs_recover_the_key() {
+        let key = scalar("b7ff7dbb9821e7c6a7df9f1a8db18f9216f39a87304b1bfb8bb91310da578228");
+        let set = reused();
+        for (a, b) in [(0, 1), (0, 2), (1, 2), (2, 0)] {
+            let candidates = recover_key(&set[a], &set[b]);
+            assert!(candidates.contains(&key), "{} {}", a, b);
+            assert!(candidates.len() <= 2);
+        }
+        assert!(recover_key(&set[0], &set[0]).is_empty());
+        let mut other = set[1];
+        other.signature.r = Scalar::ONE;
+        assert!(recover_key(&set[0], &other).is_empty());
+    }
+
+    /// A scan finds the shared `r` among unrelated signatures and narrows
+    /// three signatures to the key; repeats of one hash alone are not a
+    /// finding.
+    #[test]
+    fn scan_narrows_groups_to_the_key() {
+        let key = scalar("b7ff7dbb9821e7c6a7df9f1a8db18f9216f39a87304b1bfb8bb91310da578228");
+        let reused = reused();
+        let unrelated = |byte: u8| SignedHash {
+            hash: [byte; 32],
+            signature: Signature::new(
+                Scalar::from_bytes(&[byte; 32]).unwrap(),
+                Scalar::from_bytes(&[0x33; 32]).unwrap(),
+            )
+            .unwrap(),
+        };
+        let set = [
+            unrelated(1),
+            reused[0],
+            unrelated(2),
+            reused[1],
+            unrelated(3),
+            unrelated(3),
+            reused[2],
+        ];
+        let findings = scan(&set);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].r, reused[0].signature.r);
+        assert_eq!(findings[0].indices, [1, 3, 6]);
+        assert_eq!(findings[0].candidates, [key]);
+        assert!(scan(&reused[..1]).is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+//! The hash oracle protocol served by the `oracle` binary.
+//!
+//! Fuzzers and the Mojo test harness keep one connection open and send any
+//! number of requests, each answered in order. All integers are big-endian.
+//!
+//! ```text
+//! request:  u8 name_len | name | u32 output_len | u32 message_len | message
+//! response: u8 status   | u32 payload_len | payload
+//! ```
+//!
+//! `name` is one of [`HashAlgorithm::NAMES`](crate::digest::HashAlgorithm::NAMES)
+//! (`keccak256`, `sha3-256`, `sha256`, `shake128`, `shake256`). `output_len` is the number of
+//! bytes to squeeze for SHAKE and is ignored by fixed-size digests. On
+//! [`STATUS_OK`] the payload is the output; on [`STATUS_ERROR`] it is a
+//! UTF-8 message and the connection stays usable. A request whose framing
+//! cannot be honoured (a message or output over [`MAX_LEN`]) is answered
+//! with an error and the connection closed, since the rest of the stream
+//! can no longer be trusted.
+
+use std::io::{self, ErrorKind, Read, Write};
+
+use crate::vectors;
+
+pub const STATUS_OK: u8 = 0;
+pub const STATUS_ERROR: u8 = 1;
+
+/// Largest message or output accepted, in bytes.
+pub const MAX_LEN: u32 = 64 << 20;
+
+/// One decoded request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Request {
+    pub algorithm: String,
+    pub output_len: u32,
+    pub message: Vec<u8>,
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+/// The next request, or `None` at a clean end of stream.
+pub fn read_request(reader: &mut impl Read) -> io::Result<Option<Request>> {
+    let mut name_len = [0u8; 1];
+    match reader.read_exact(&mut name_len) {
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        result => result?,
+    }
+    let mut name = vec![0u8; name_len[0] as usize];
+    reader.read_exact(&mut name)?;
+    let output_len = read_u32(reader)?;
+    let message_len = read_u32(reader)?;
+    if output_len > MAX_LEN || message_len > MAX_LEN {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "lengths {}/{} exceed {} bytes",
+                output_len, message_len, MAX_LEN
+            ),
+        ));
+    }
+    let mut message = vec![0u8; message_len as usize];
+    reader.read_exact(&mut message)?;
+    Ok(Some(Request {
+        algorithm: String::from_utf8_lossy(&name).into_owned(),
+        output_len,
+        message,
+    }))
+}
+
+pub fn write_request(writer: &mut impl Write, request: &Request) -> io::Result<()> {
+    let name = request.algorithm.as_bytes();
+    let name_len = u8::try_from(name.len())
+        .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "algorithm name too long"))?;
+    writer.write_all(&[name_len])?;
+    writer.write_all(name)?;
+    writer.write_all(&request.output_len.to_be_bytes())?;
+    writer.write_all(&(request.message.len() as u32).to_be_bytes())?;
+    writer.write_all(&request.message)?;
+    writer.flush()
+}
+
+pub fn write_response(
+    writer: &mut impl Write,
+    response: &Result<Vec<u8>, String>,
+) -> io::Result<()> {
+    let (status, payload) = match response {
+        Ok(output) => (STATUS_OK, output.as_slice()),
+        Err(message) => (STATUS_ERROR, message.as_bytes()),
+    };
+    writer.write_all(&[status])?;
+    writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// The response payload, or the oracle's error message.
+pub fn read_response(reader: &mut impl Read) -> io::Result<Result<Vec<u8>, String>> {
+    let mut status = [0u8; 1];
+    reader.read_exact(&mut status)?;
+    let len = read_u32(reader)?;
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload)?;
+    Ok(match status[0] {
+        STATUS_OK => Ok(payload),
+        _ => Err(String::from_utf8_lossy(&payload).into_owned()),
+    })
+}
+
+/// What the oracle answers to `request`.
+pub fn answer(request: &Request) -> Result<Vec<u8>, String> {
+    vectors::digest(
+        &request.algorithm,
+        &request.message,
+        request.output_len as usize,
+    )
+    .ok_or_else(|| format!("unknown algorithm {:?}", request.algorithm))
+}
+
+/// Answer requests from `reader` on `writer` until the peer closes the
+/// stream. Returns the number of requests served.
+pub fn serve(mut reader: impl Read, mut writer: impl Write) -> io::Result<usize> {
+    let mut served = 0;
+    loop {
+        let request = match read_request(&mut reader) {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(served),
+            Err(err) if err.kind() == ErrorKind::InvalidData => {
+                write_response(&mut writer, &Err(err.to_string()))?;
+                return Err(err);
+            }
+            Err(err) => return Err(err),
+        };
+        write_response(&mut writer, &answer(&request))?;
+        served += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn request(algorithm: &str, output_len: u32, message: &[u8]) -> Request {
+        Request {
+            algorithm: algorithm.to_string(),
+            output_len,
+            message: message.to_vec(),
+        }
+    }
+
+    /// Several requests on one stream are answered in order, unknown
+    /// algorithms get an error without closing the stream, and oversized
+    /// frames end it.
+    #[test]
+    fn serves_requests_in_order() {
+        let mut input = Vec::new();
+        for sent in [
+            request("keccak256", 0, b"abc"),
+            request("md5", 0, b"abc"),
+            request("shake128", 100, b""),
+        ] {
+            write_request(&mut input, &sent).unwrap();
+        }
+        let mut output = Vec::new();
+        assert_eq!(serve(Cursor::new(input), &mut output).unwrap(), 3);
+
+        let mut replies = Cursor::new(output);
+        let digest = read_response(&mut replies).unwrap().unwrap();
+        assert_eq!(digest, crate::keccak256(b"abc"));
+        let error = read_response(&mut replies).unwrap().unwrap_err();
+        assert!(error.contains("md5"), "{}", error);
+        let squeezed = read_response(&mut replies).unwrap().unwrap();
+        assert_eq!(squeezed, vectors::digest("shake128", b"", 100).unwrap());
+
+        let mut oversized = Vec::new();
+        oversized.extend_from_slice(&[9]);
+        oversized.extend_from_slice(b"keccak256");
+        oversized.extend_from_slice(&0u32.to_be_bytes());
+        oversized.extend_from_slice(&u32::MAX.to_be_bytes());
+        let mut output = Vec::new();
+        assert!(serve(Cursor::new(oversized), &mut output).is_err());
+        assert!(read_response(&mut Cursor::new(output)).unwrap().is_err());
+    }
+}
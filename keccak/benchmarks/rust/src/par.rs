@@ -0,0 +1,120 @@
+//! Multi-core hashing over a rayon pool (`parallel` feature).
+//!
+//! Work runs on rayon's global pool, or on whichever pool the caller is
+//! already inside via `ThreadPool::install`. Inputs at or below
+//! [`ParConfig::sequential_threshold`] messages stay on the calling thread,
+//! where the hand-off would cost more than it saves. Larger ones are split
+//! into one chunk per worker (never smaller than the threshold), and each
+//! chunk goes through [`crate::keccak256_batch`] so SIMD grouping still
+//! applies within it.
+
+use rayon::prelude::*;
+
+use crate::merkle;
+
+/// How work is split across the pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParConfig {
+    /// Batches (and Merkle levels) of at most this many messages are
+    /// hashed without the pool.
+    pub sequential_threshold: usize,
+}
+
+impl Default for ParConfig {
+    fn default() -> Self {
+        ParConfig {
+            sequential_threshold: 1024,
+        }
+    }
+}
+
+impl ParConfig {
+    /// Hash each message into the matching slot of `digests`.
+    ///
+    /// Panics if the slices differ in length.
+    pub fn keccak256_batch<M: AsRef<[u8]> + Sync>(&self, messages: &[M], digests: &mut [[u8; 32]]) {
+        assert_eq!(messages.len(), digests.len(), "one digest slot per message");
+        if messages.len() <= self.sequential_threshold {
+            return crate::keccak256_batch(messages, digests);
+        }
+        let chunk = self
+            .sequential_threshold
+            .max(messages.len().div_ceil(rayon::current_num_threads()));
+        messages
+            .par_chunks(chunk)
+            .zip(digests.par_chunks_mut(chunk))
+            .for_each(|(messages, digests)| crate::keccak256_batch(messages, digests));
+    }
+
+    /// Digests of `messages` in order, as a parallel iterator whose pieces
+    /// hold at least `sequential_threshold` messages.
+    pub fn keccak256_iter<'a, M: AsRef<[u8]> + Sync>(
+        &self,
+        messages: &'a [M],
+    ) -> impl IndexedParallelIterator<Item = [u8; 32]> + 'a {
+        messages
+            .par_iter()
+            .with_min_len(self.sequential_threshold.max(1))
+            .map(|message| crate::native::keccak256(message.as_ref()))
+    }
+
+    /// [`merkle::merkle_root`], hashing each level with
+    /// [`ParConfig::keccak256_batch`].
+    pub fn merkle_root(&self, leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+        merkle::root_with(leaves, |pairs, parents| {
+            self.keccak256_batch(pairs, parents)
+        })
+    }
+}
+
+/// [`ParConfig::keccak256_iter`] with the default threshold.
+pub fn keccak256_par_iter<M: AsRef<[u8]> + Sync>(
+    messages: &[M],
+) -> impl IndexedParallelIterator<Item = [u8; 32]> + '_ {
+    ParConfig::default().keccak256_iter(messages)
+}
+
+/// [`ParConfig::keccak256_batch`] with the default threshold.
+pub fn keccak256_par<M: AsRef<[u8]> + Sync>(messages: &[M], digests: &mut [[u8; 32]]) {
+    ParConfig::default().keccak256_batch(messages, digests);
+}
+
+/// [`ParConfig::merkle_root`] with the default threshold.
+pub fn merkle_root_par(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    ParConfig::default().merkle_root(leaves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every threshold, from all-parallel to all-sequential, gives the
+    /// serial digests and roots.
+    #[test]
+    fn parallel_results_match_serial() {
+        let messages: Vec<Vec<u8>> = (0..300u32)
+            .map(|i| vec![i as u8; (i % 150) as usize])
+            .collect();
+        let expected: Vec<[u8; 32]> = messages.iter().map(|m| crate::keccak256(m)).collect();
+        for sequential_threshold in [0, 1, 7, 1024] {
+            let config = ParConfig {
+                sequential_threshold,
+            };
+            let mut digests = vec![[0u8; 32]; messages.len()];
+            config.keccak256_batch(&messages, &mut digests);
+            assert_eq!(digests, expected);
+            let collected: Vec<[u8; 32]> = config.keccak256_iter(&messages).collect();
+            assert_eq!(collected, expected);
+            for leaves in [
+                &expected[..0],
+                &expected[..1],
+                &expected[..5],
+                &expected[..],
+            ] {
+                assert_eq!(config.merkle_root(leaves), merkle::merkle_root(leaves));
+            }
+        }
+        assert_eq!(merkle_root_par(&expected), merkle::merkle_root(&expected));
+        assert_eq!(keccak256_par_iter(&messages).collect::<Vec<_>>(), expected);
+    }
+}
@@ -0,0 +1,76 @@
+//! Four lane-sliced states in `__m256i` registers.
+
+use std::arch::x86_64::*;
+
+use super::Lanes;
+
+/// One lane of each of four states. Only built inside
+/// [`keccak256_x4`], whose caller has checked for AVX2, so the intrinsics
+/// below always run on a CPU that has them.
+#[derive(Clone, Copy)]
+struct Avx2(__m256i);
+
+// SAFETY (every method): see `Avx2`; AVX2 is available.
+impl Lanes<4> for Avx2 {
+    #[inline(always)]
+    fn splat(value: u64) -> Self {
+        Avx2(unsafe { _mm256_set1_epi64x(value as i64) })
+    }
+
+    #[inline(always)]
+    fn from_array(values: [u64; 4]) -> Self {
+        Avx2(unsafe { _mm256_loadu_si256(values.as_ptr().cast()) })
+    }
+
+    #[inline(always)]
+    fn to_array(self) -> [u64; 4] {
+        let mut values = [0u64; 4];
+        unsafe { _mm256_storeu_si256(values.as_mut_ptr().cast(), self.0) };
+        values
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        Avx2(unsafe { _mm256_xor_si256(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    fn andnot(self, other: Self) -> Self {
+        Avx2(unsafe { _mm256_andnot_si256(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        // AVX2 has no 64-bit rotate; shift both ways and combine.
+        unsafe {
+            let left = _mm_cvtsi32_si128(bits as i32);
+            let right = _mm_cvtsi32_si128(64 - bits as i32);
+            Avx2(_mm256_or_si256(
+                _mm256_sll_epi64(self.0, left),
+                _mm256_srl_epi64(self.0, right),
+            ))
+        }
+    }
+}
+
+/// [`super::keccak256_x4`] on AVX2.
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[target_feature(enable = "avx2")]
+pub unsafe fn keccak256_x4(messages: [&[u8]; 4]) -> [[u8; 32]; 4] {
+    super::hash_lanes::<4, Avx2>(messages)
+}
+
+/// [`super::permute`] on AVX2 over four states held as plain lanes.
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[target_feature(enable = "avx2")]
+pub unsafe fn permute_x4(state: &mut [[u64; 4]; 25]) {
+    let mut lanes = state.map(Avx2::from_array);
+    super::permute::<4, Avx2>(&mut lanes);
+    *state = lanes.map(Avx2::to_array);
+}
@@ -0,0 +1,69 @@
+//! Eight lane-sliced states in `__m512i` registers.
+
+use std::arch::x86_64::*;
+
+use super::Lanes;
+
+/// One lane of each of eight states. Only built inside
+/// [`keccak256_x8`], whose caller has checked for AVX-512F, so the
+/// intrinsics below always run on a CPU that has them.
+#[derive(Clone, Copy)]
+struct Avx512(__m512i);
+
+// SAFETY (every method): see `Avx512`; AVX-512F is available.
+impl Lanes<8> for Avx512 {
+    #[inline(always)]
+    fn splat(value: u64) -> Self {
+        Avx512(unsafe { _mm512_set1_epi64(value as i64) })
+    }
+
+    #[inline(always)]
+    fn from_array(values: [u64; 8]) -> Self {
+        Avx512(unsafe { _mm512_loadu_si512(values.as_ptr().cast()) })
+    }
+
+    #[inline(always)]
+    fn to_array(self) -> [u64; 8] {
+        let mut values = [0u64; 8];
+        unsafe { _mm512_storeu_si512(values.as_mut_ptr().cast(), self.0) };
+        values
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        Avx512(unsafe { _mm512_xor_si512(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    fn andnot(self, other: Self) -> Self {
+        Avx512(unsafe { _mm512_andnot_si512(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        // Unlike AVX2, AVX-512F rotates 64-bit lanes directly.
+        Avx512(unsafe { _mm512_rolv_epi64(self.0, _mm512_set1_epi64(i64::from(bits))) })
+    }
+}
+
+/// [`super::keccak256_x8`] on AVX-512F.
+///
+/// # Safety
+///
+/// The CPU must support AVX-512F.
+#[target_feature(enable = "avx512f")]
+pub unsafe fn keccak256_x8(messages: [&[u8]; 8]) -> [[u8; 32]; 8] {
+    super::hash_lanes::<8, Avx512>(messages)
+}
+
+/// [`super::permute`] on AVX-512F over eight states held as plain lanes.
+///
+/// # Safety
+///
+/// The CPU must support AVX-512F.
+#[target_feature(enable = "avx512f")]
+pub unsafe fn permute_x8(state: &mut [[u64; 8]; 25]) {
+    let mut lanes = state.map(Avx512::from_array);
+    super::permute::<8, Avx512>(&mut lanes);
+    *state = lanes.map(Avx512::to_array);
+}
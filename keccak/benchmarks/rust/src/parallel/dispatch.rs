@@ -0,0 +1,207 @@
+//! Runtime choice between the scalar and multi-message paths.
+//!
+//! [`keccak256_batch`] hashes through [`selected`]: the path passed to
+//! [`force`] if there was one, else the one named by the `KECCAK256_SIMD`
+//! environment variable, else the widest this CPU supports (AVX-512, then
+//! AVX2, then NEON, then scalar). Benchmarks force each path in turn to time
+//! them on one machine. Batches are grouped by padded block count before
+//! they are split across lanes, so messages of similar lengths share
+//! permutation passes.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::native;
+
+/// The environment variable [`selected`] reads: a [`Simd`] name, or `auto`.
+pub const ENV_VAR: &str = "KECCAK256_SIMD";
+
+/// A batch hashing path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Simd {
+    /// One message at a time on the scalar permutation.
+    Scalar,
+    /// Four messages per pass, [`super::keccak256_x4`].
+    Avx2,
+    /// Eight messages per pass, [`super::keccak256_x8`].
+    Avx512,
+    /// Two messages per pass, [`super::keccak256_x2`].
+    Neon,
+}
+
+impl Simd {
+    /// Every path, widest first.
+    pub const ALL: [Simd; 4] = [Simd::Avx512, Simd::Avx2, Simd::Neon, Simd::Scalar];
+
+    /// Whether this CPU runs the path's vector code, rather than its
+    /// portable fallback.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Simd::Scalar => true,
+            Simd::Avx2 => super::avx2_supported(),
+            Simd::Avx512 => super::avx512_supported(),
+            Simd::Neon => super::neon_supported(),
+        }
+    }
+
+    /// Messages hashed per permutation pass.
+    pub fn lanes(self) -> usize {
+        match self {
+            Simd::Scalar => 1,
+            Simd::Avx2 => 4,
+            Simd::Avx512 => 8,
+            Simd::Neon => 2,
+        }
+    }
+
+    /// The widest supported path.
+    pub fn detect() -> Simd {
+        Simd::ALL
+            .into_iter()
+            .find(|simd| simd.is_supported())
+            .unwrap_or(Simd::Scalar)
+    }
+
+    /// The path [`ENV_VAR`] asks for: `None` when it is unset or `auto`, an
+    /// error when it names an unknown or unsupported path.
+    pub fn from_env() -> Result<Option<Simd>, String> {
+        let Some(value) = std::env::var_os(ENV_VAR) else {
+            return Ok(None);
+        };
+        let value = value.to_string_lossy();
+        if value == "auto" {
+            return Ok(None);
+        }
+        let simd: Simd = value
+            .parse()
+            .map_err(|_| format!("{}={:?} is not a SIMD path", ENV_VAR, value))?;
+        simd.check().map(|_| Some(simd))
+    }
+
+    /// An error naming the path when this CPU does not support it.
+    pub fn check(self) -> Result<(), String> {
+        if self.is_supported() {
+            Ok(())
+        } else {
+            Err(format!("this CPU does not support the {} path", self))
+        }
+    }
+}
+
+impl FromStr for Simd {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "scalar" => Ok(Simd::Scalar),
+            "avx2" => Ok(Simd::Avx2),
+            "avx512" => Ok(Simd::Avx512),
+            "neon" => Ok(Simd::Neon),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Simd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Simd::Scalar => "scalar",
+            Simd::Avx2 => "avx2",
+            Simd::Avx512 => "avx512",
+            Simd::Neon => "neon",
+        })
+    }
+}
+
+/// The [`force`]d path's index in [`Simd::ALL`] plus one; zero for none.
+static FORCED: AtomicU8 = AtomicU8::new(0);
+
+/// Use `simd` for every later [`keccak256_batch`] call, whatever
+/// [`ENV_VAR`] says; `None` goes back to the environment and detection.
+pub fn force(simd: Option<Simd>) -> Result<(), String> {
+    let raw = match simd {
+        Some(simd) => {
+            simd.check()?;
+            Simd::ALL
+                .iter()
+                .position(|&other| other == simd)
+                .expect("listed") as u8
+                + 1
+        }
+        None => 0,
+    };
+    FORCED.store(raw, Ordering::Relaxed);
+    Ok(())
+}
+
+/// The path [`keccak256_batch`] takes. An unusable [`ENV_VAR`] value is
+/// ignored in favour of detection; binaries that want it reported call
+/// [`Simd::from_env`] themselves.
+pub fn selected() -> Simd {
+    match FORCED.load(Ordering::Relaxed) {
+        0 => {
+            static FROM_ENV: OnceLock<Simd> = OnceLock::new();
+            *FROM_ENV.get_or_init(|| match Simd::from_env() {
+                Ok(Some(simd)) => simd,
+                Ok(None) => Simd::detect(),
+                Err(_err) => {
+                    crate::trace::event!(warn, "ignoring {}: {}", ENV_VAR, _err);
+                    Simd::detect()
+                }
+            })
+        }
+        raw => Simd::ALL[usize::from(raw) - 1],
+    }
+}
+
+/// Hash each message into the matching slot of `digests` on the
+/// [`selected`] path, grouping messages by padded block count first.
+///
+/// Panics if the slices differ in length.
+pub fn keccak256_batch<M: AsRef<[u8]>>(messages: &[M], digests: &mut [[u8; 32]]) {
+    match selected() {
+        Simd::Scalar => {
+            assert_eq!(messages.len(), digests.len(), "one digest slot per message");
+            for (message, digest) in messages.iter().zip(digests) {
+                *digest = native::keccak256(message.as_ref());
+            }
+        }
+        Simd::Avx2 => super::grouped(messages, digests, super::keccak256_x4),
+        Simd::Avx512 => super::grouped(messages, digests, super::keccak256_x8),
+        Simd::Neon => super::grouped(messages, digests, super::keccak256_x2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Forcing each supported path gives the same digests, an unsupported
+    /// one is refused, and clearing the override returns to detection.
+    #[test]
+    fn forced_paths_agree() {
+        let messages: Vec<Vec<u8>> = (0..13u8).map(|i| vec![i; i as usize * 30]).collect();
+        let expected: Vec<[u8; 32]> = messages.iter().map(|m| native::keccak256(m)).collect();
+        for simd in Simd::ALL {
+            if !simd.is_supported() {
+                assert!(force(Some(simd)).is_err(), "{}", simd);
+                continue;
+            }
+            force(Some(simd)).unwrap();
+            assert_eq!(selected(), simd);
+            let mut digests = vec![[0u8; 32]; messages.len()];
+            keccak256_batch(&messages, &mut digests);
+            assert_eq!(digests, expected, "{}", simd);
+        }
+        force(None).unwrap();
+        assert!(Simd::detect().is_supported());
+        for simd in Simd::ALL {
+            assert_eq!(simd.to_string().parse(), Ok(simd));
+        }
+    }
+}
@@ -0,0 +1,439 @@
+//! Keccak-256 over several independent messages per permutation pass.
+//!
+//! The state is held lane-sliced: lane `i` of every SIMD register belongs
+//! to message `i`, so one keccak-f[1600] over 25 registers permutes all
+//! the states at once. Messages absorb in lockstep for as many blocks as
+//! the shortest of them needs, padding included; longer messages then
+//! finish on the scalar permutation. Batches of similar lengths therefore
+//! gain the most.
+//!
+//! [`keccak256_x4`] runs four messages on AVX2 where the CPU has it, and
+//! [`keccak256_x8`] eight on AVX-512F, and [`keccak256_x2`] two on
+//! aarch64 NEON; each falls back to a portable `[u64; N]` build of the same
+//! code. The AVX-512 path is checked against
+//! the scalar permutation the first time it is asked for and is only used
+//! if it agrees. [`dispatch`] picks between the paths at run time.
+//! [`MultiHasher`] runs the same lockstep permutation over streams that
+//! arrive a piece at a time.
+
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+mod avx2;
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+mod avx512;
+pub mod dispatch;
+mod multi;
+#[cfg(all(target_arch = "aarch64", not(feature = "safe-only")))]
+mod neon;
+
+pub use multi::MultiHasher;
+
+use std::array;
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+use std::sync::OnceLock;
+
+use crate::native::{self, PI, RATE, RHO, ROUND_CONSTANTS};
+
+/// `N` 64-bit lanes, one per message, and the operations the permutation
+/// needs on them.
+trait Lanes<const N: usize>: Copy {
+    fn splat(value: u64) -> Self;
+    fn from_array(values: [u64; N]) -> Self;
+    fn to_array(self) -> [u64; N];
+    fn xor(self, other: Self) -> Self;
+    /// `!self & other`.
+    fn andnot(self, other: Self) -> Self;
+    fn rotate_left(self, bits: u32) -> Self;
+}
+
+/// The portable build: plain arrays, left to LLVM to vectorize.
+impl<const N: usize> Lanes<N> for [u64; N] {
+    #[inline(always)]
+    fn splat(value: u64) -> Self {
+        [value; N]
+    }
+
+    #[inline(always)]
+    fn from_array(values: [u64; N]) -> Self {
+        values
+    }
+
+    #[inline(always)]
+    fn to_array(self) -> [u64; N] {
+        self
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        array::from_fn(|i| self[i] ^ other[i])
+    }
+
+    #[inline(always)]
+    fn andnot(self, other: Self) -> Self {
+        array::from_fn(|i| !self[i] & other[i])
+    }
+
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        self.map(|lane| lane.rotate_left(bits))
+    }
+}
+
+/// keccak-f[1600] over `N` lane-sliced states, step for step the scalar
+/// permutation in [`native`].
+#[inline(always)]
+fn permute<const N: usize, L: Lanes<N>>(state: &mut [L; 25]) {
+    for &round_constant in &ROUND_CONSTANTS {
+        // Theta.
+        let mut columns = [state[0]; 5];
+        for (x, column) in columns.iter_mut().enumerate() {
+            *column = state[x]
+                .xor(state[x + 5])
+                .xor(state[x + 10])
+                .xor(state[x + 15])
+                .xor(state[x + 20]);
+        }
+        for x in 0..5 {
+            let d = columns[(x + 4) % 5].xor(columns[(x + 1) % 5].rotate_left(1));
+            for y in (0..25).step_by(5) {
+                state[y + x] = state[y + x].xor(d);
+            }
+        }
+
+        // Rho and pi.
+        let mut carried = state[1];
+        for (&lane, &rotation) in PI.iter().zip(&RHO) {
+            let next = state[lane];
+            state[lane] = carried.rotate_left(rotation);
+            carried = next;
+        }
+
+        // Chi.
+        for y in (0..25).step_by(5) {
+            let row = [
+                state[y],
+                state[y + 1],
+                state[y + 2],
+                state[y + 3],
+                state[y + 4],
+            ];
+            for x in 0..5 {
+                state[y + x] = row[x].xor(row[(x + 1) % 5].andnot(row[(x + 2) % 5]));
+            }
+        }
+
+        // Iota.
+        state[0] = state[0].xor(L::splat(round_constant));
+    }
+}
+
+/// [`permute`] over `N` states held as plain lanes, on the SIMD build for
+/// `N` where the CPU has one and the portable build otherwise.
+fn permute_lanes<const N: usize>(state: &mut [[u64; N]; 25]) {
+    // SAFETY (each cast): `N` has just been compared with the target
+    // width, so the reference keeps its type.
+    #[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+    {
+        if N == 4 && avx2_supported() {
+            let state = unsafe { &mut *(state as *mut [[u64; N]; 25]).cast() };
+            // SAFETY: the CPU supports AVX2.
+            return unsafe { avx2::permute_x4(state) };
+        }
+        if N == 8 && avx512_supported() {
+            let state = unsafe { &mut *(state as *mut [[u64; N]; 25]).cast() };
+            // SAFETY: the CPU supports AVX-512F.
+            return unsafe { avx512::permute_x8(state) };
+        }
+    }
+    #[cfg(all(target_arch = "aarch64", not(feature = "safe-only")))]
+    if N == 2 && neon_supported() {
+        let state = unsafe { &mut *(state as *mut [[u64; N]; 25]).cast() };
+        // SAFETY: the CPU supports NEON.
+        return unsafe { neon::permute_x2(state) };
+    }
+    permute::<N, [u64; N]>(state);
+}
+
+/// A message's last block with the Keccak `0x01 ... 0x80` padding applied.
+fn final_block(message: &[u8]) -> [u8; RATE] {
+    let tail = &message[message.len() / RATE * RATE..];
+    let mut block = [0u8; RATE];
+    block[..tail.len()].copy_from_slice(tail);
+    block[tail.len()] ^= 0x01;
+    block[RATE - 1] ^= 0x80;
+    block
+}
+
+fn xor_block(state: &mut [u64; 25], block: &[u8]) {
+    for (lane, chunk) in state.iter_mut().zip(block.chunks_exact(8)) {
+        *lane ^= u64::from_le_bytes(chunk.try_into().expect("8-byte chunk"));
+    }
+}
+
+/// Keccak-256 of `N` messages with a lockstep sponge over `L`.
+#[inline(always)]
+fn hash_lanes<const N: usize, L: Lanes<N>>(messages: [&[u8]; N]) -> [[u8; 32]; N] {
+    let finals: [[u8; RATE]; N] = array::from_fn(|i| final_block(messages[i]));
+    let blocks: [usize; N] = array::from_fn(|i| messages[i].len() / RATE + 1);
+    let block = |i: usize, index: usize| -> &[u8] {
+        if index + 1 == blocks[i] {
+            &finals[i]
+        } else {
+            &messages[i][index * RATE..(index + 1) * RATE]
+        }
+    };
+
+    let common = blocks.iter().copied().min().unwrap_or(0);
+    let mut state = [L::splat(0); 25];
+    for index in 0..common {
+        for (word, lane) in state.iter_mut().take(RATE / 8).enumerate() {
+            let words = array::from_fn(|i| {
+                let bytes = &block(i, index)[word * 8..word * 8 + 8];
+                u64::from_le_bytes(bytes.try_into().expect("8-byte word"))
+            });
+            *lane = lane.xor(L::from_array(words));
+        }
+        permute(&mut state);
+    }
+
+    let lanes = state.map(L::to_array);
+    array::from_fn(|i| {
+        let mut scalar: [u64; 25] = array::from_fn(|lane| lanes[lane][i]);
+        for index in common..blocks[i] {
+            xor_block(&mut scalar, block(i, index));
+            native::keccak_f1600(&mut scalar);
+        }
+        let mut digest = [0u8; 32];
+        for (chunk, lane) in digest.chunks_exact_mut(8).zip(&scalar) {
+            chunk.copy_from_slice(&lane.to_le_bytes());
+        }
+        digest
+    })
+}
+
+/// Whether [`keccak256_x4`] runs on AVX2 on this CPU.
+pub fn avx2_supported() -> bool {
+    #[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+    {
+        std::arch::is_x86_feature_detected!("avx2")
+    }
+    #[cfg(not(all(target_arch = "x86_64", not(feature = "safe-only"))))]
+    {
+        false
+    }
+}
+
+/// Keccak-256 of four messages at once.
+pub fn keccak256_x4(messages: [&[u8]; 4]) -> [[u8; 32]; 4] {
+    #[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+    if avx2_supported() {
+        // SAFETY: the CPU supports AVX2.
+        return unsafe { avx2::keccak256_x4(messages) };
+    }
+    hash_lanes::<4, [u64; 4]>(messages)
+}
+
+/// Whether [`keccak256_x2`] runs on NEON on this CPU.
+pub fn neon_supported() -> bool {
+    #[cfg(all(target_arch = "aarch64", not(feature = "safe-only")))]
+    {
+        std::arch::is_aarch64_feature_detected!("neon")
+    }
+    #[cfg(not(all(target_arch = "aarch64", not(feature = "safe-only"))))]
+    {
+        false
+    }
+}
+
+/// Keccak-256 of two messages at once.
+pub fn keccak256_x2(messages: [&[u8]; 2]) -> [[u8; 32]; 2] {
+    #[cfg(all(target_arch = "aarch64", not(feature = "safe-only")))]
+    if neon_supported() {
+        // SAFETY: the CPU supports NEON.
+        return unsafe { neon::keccak256_x2(messages) };
+    }
+    hash_lanes::<2, [u64; 2]>(messages)
+}
+
+/// Whether [`keccak256_x8`] runs on AVX-512F on this CPU: the CPU reports
+/// it and, on the first call, eight messages around the rate hashed with
+/// it matched the scalar permutation.
+pub fn avx512_supported() -> bool {
+    #[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+    {
+        static VERIFIED: OnceLock<bool> = OnceLock::new();
+        *VERIFIED.get_or_init(|| {
+            if !std::arch::is_x86_feature_detected!("avx512f") {
+                return false;
+            }
+            let data = [0xa5u8; 3 * RATE];
+            let messages: [&[u8]; 8] = array::from_fn(|i| &data[..i * 50]);
+            // SAFETY: the CPU supports AVX-512F.
+            let agrees =
+                unsafe { avx512::keccak256_x8(messages) } == messages.map(native::keccak256);
+            if !agrees {
+                crate::trace::event!(warn, "AVX-512 Keccak disagrees with the scalar path");
+            }
+            agrees
+        })
+    }
+    #[cfg(not(all(target_arch = "x86_64", not(feature = "safe-only"))))]
+    {
+        false
+    }
+}
+
+/// Keccak-256 of eight messages at once.
+pub fn keccak256_x8(messages: [&[u8]; 8]) -> [[u8; 32]; 8] {
+    #[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+    if avx512_supported() {
+        // SAFETY: the CPU supports AVX-512F.
+        return unsafe { avx512::keccak256_x8(messages) };
+    }
+    hash_lanes::<8, [u64; 8]>(messages)
+}
+
+/// Hash each message into the matching slot of `digests`, four at a time
+/// through [`keccak256_x4`]; a final group of fewer than four uses the
+/// scalar permutation.
+///
+/// Panics if the slices differ in length.
+pub fn keccak256_batch<M: AsRef<[u8]>>(messages: &[M], digests: &mut [[u8; 32]]) {
+    batch(messages, digests, keccak256_x4);
+}
+
+/// [`keccak256_batch`] two at a time through [`keccak256_x2`].
+///
+/// Panics if the slices differ in length.
+pub fn keccak256_batch_x2<M: AsRef<[u8]>>(messages: &[M], digests: &mut [[u8; 32]]) {
+    batch(messages, digests, keccak256_x2);
+}
+
+/// [`keccak256_batch`] eight at a time through [`keccak256_x8`].
+///
+/// Panics if the slices differ in length.
+pub fn keccak256_batch_x8<M: AsRef<[u8]>>(messages: &[M], digests: &mut [[u8; 32]]) {
+    batch(messages, digests, keccak256_x8);
+}
+
+fn batch<M: AsRef<[u8]>, const N: usize>(
+    messages: &[M],
+    digests: &mut [[u8; 32]],
+    hash: fn([&[u8]; N]) -> [[u8; 32]; N],
+) {
+    assert_eq!(messages.len(), digests.len(), "one digest slot per message");
+    let mut groups = messages.chunks_exact(N);
+    let mut slots = digests.chunks_exact_mut(N);
+    for (group, slots) in (&mut groups).zip(&mut slots) {
+        let group = array::from_fn(|i| group[i].as_ref());
+        slots.copy_from_slice(&hash(group));
+    }
+    for (message, digest) in groups.remainder().iter().zip(slots.into_remainder()) {
+        *digest = native::keccak256(message.as_ref());
+    }
+}
+
+/// [`batch`] over the messages reordered by padded block count, so each
+/// group of `N` shares as many lockstep blocks as possible; digests still
+/// land in the caller's order.
+fn grouped<M: AsRef<[u8]>, const N: usize>(
+    messages: &[M],
+    digests: &mut [[u8; 32]],
+    hash: fn([&[u8]; N]) -> [[u8; 32]; N],
+) {
+    assert_eq!(messages.len(), digests.len(), "one digest slot per message");
+    let mut order: Vec<usize> = (0..messages.len()).collect();
+    order.sort_by_key(|&index| messages[index].as_ref().len() / RATE);
+    let mut groups = order.chunks_exact(N);
+    for group in &mut groups {
+        let group_digests = hash(array::from_fn(|i| messages[group[i]].as_ref()));
+        for (&index, digest) in group.iter().zip(group_digests) {
+            digests[index] = digest;
+        }
+    }
+    for &index in groups.remainder() {
+        digests[index] = native::keccak256(messages[index].as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Messages of every length pairing around the rate, so groups both
+    /// share all their blocks and finish unevenly, match the scalar path.
+    #[test]
+    fn x4_matches_scalar() {
+        let data: Vec<u8> = (0..700u32).map(|i| (i * 29 + 1) as u8).collect();
+        let lengths = [0, 1, 8, 135, 136, 137, 271, 272, 300, 699];
+        for &a in &lengths {
+            for &b in &[0, 136, 300] {
+                let messages = [&data[..a], &data[..b], &data[3..a.max(3)], &data[..a]];
+                let expected = messages.map(native::keccak256);
+                assert_eq!(keccak256_x4(messages), expected, "{} {}", a, b);
+                assert_eq!(hash_lanes::<4, [u64; 4]>(messages), expected);
+            }
+        }
+    }
+
+    /// The two-way path, on NEON where the CPU has it, matches the scalar
+    /// one.
+    #[test]
+    fn x2_matches_scalar() {
+        let data: Vec<u8> = (0..700u32).map(|i| (i * 37 + 3) as u8).collect();
+        for &a in &[0, 1, 135, 136, 137, 272, 699] {
+            for &b in &[0, 136, 500] {
+                let messages = [&data[..a], &data[1..b.max(1)]];
+                let expected = messages.map(native::keccak256);
+                assert_eq!(keccak256_x2(messages), expected, "{} {}", a, b);
+                assert_eq!(hash_lanes::<2, [u64; 2]>(messages), expected);
+            }
+        }
+    }
+
+    /// The eight-way path, on AVX-512 where the CPU passes the check,
+    /// matches the scalar one message for message.
+    #[test]
+    fn x8_matches_scalar() {
+        let data: Vec<u8> = (0..700u32).map(|i| (i * 31 + 7) as u8).collect();
+        for &a in &[0, 135, 136, 137, 300, 699] {
+            let messages: [&[u8]; 8] = array::from_fn(|i| &data[i..a.max(i)]);
+            let expected = messages.map(native::keccak256);
+            assert_eq!(keccak256_x8(messages), expected, "{}", a);
+            assert_eq!(hash_lanes::<8, [u64; 8]>(messages), expected);
+        }
+        #[cfg(all(target_arch = "x86_64", not(feature = "safe-only")))]
+        assert_eq!(
+            avx512_supported(),
+            std::arch::is_x86_feature_detected!("avx512f")
+        );
+    }
+
+    /// Grouping by block count reorders the work but not the digests.
+    #[test]
+    fn grouped_batches_keep_the_callers_order() {
+        let lengths = [700, 0, 136, 5, 300, 135, 699, 137, 1, 272, 40];
+        let messages: Vec<Vec<u8>> = lengths.iter().map(|&n| vec![n as u8; n]).collect();
+        let mut digests = vec![[0u8; 32]; messages.len()];
+        grouped(&messages, &mut digests, keccak256_x4);
+        for (message, digest) in messages.iter().zip(&digests) {
+            assert_eq!(*digest, native::keccak256(message));
+        }
+    }
+
+    /// Batches of any size fill every slot, down the wide and scalar paths.
+    #[test]
+    fn batch_matches_single_calls() {
+        let messages: Vec<Vec<u8>> = (0..19u8).map(|i| vec![i; i as usize * 40]).collect();
+        for batch in [
+            keccak256_batch_x2::<Vec<u8>>,
+            keccak256_batch::<Vec<u8>>,
+            keccak256_batch_x8::<Vec<u8>>,
+        ] {
+            let mut digests = vec![[0u8; 32]; messages.len()];
+            batch(&messages, &mut digests);
+            for (message, digest) in messages.iter().zip(&digests) {
+                assert_eq!(*digest, crate::keccak256(message));
+            }
+        }
+    }
+}
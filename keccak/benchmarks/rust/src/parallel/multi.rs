@@ -0,0 +1,191 @@
+//! Incremental Keccak-256 over `N` streams in lockstep.
+//!
+//! Each stream buffers up to one block. A full block is absorbed into that
+//! stream's lanes at once, but the permutation waits until every stream has
+//! one, so streams fed at similar rates share every pass on the SIMD build.
+//! A stream that fills a second block before the others are ready forces a
+//! pass early: everything pending is permuted together and the idle
+//! streams' lanes are put back as they were.
+
+use std::array;
+
+use super::{final_block, permute_lanes};
+use crate::native::RATE;
+
+/// `N` incremental Keccak-256 hashers sharing one lane-sliced state.
+/// Widths 4 and 8 use AVX2 and AVX-512F, and 2 uses NEON, where the CPU
+/// has them; any other width runs the portable build.
+#[derive(Clone)]
+pub struct MultiHasher<const N: usize> {
+    state: [[u64; N]; 25],
+    buffers: [[u8; RATE]; N],
+    buffered: [usize; N],
+    /// Streams whose latest block is absorbed but not yet permuted.
+    pending: [bool; N],
+}
+
+impl<const N: usize> Default for MultiHasher<N> {
+    fn default() -> Self {
+        MultiHasher::new()
+    }
+}
+
+impl<const N: usize> MultiHasher<N> {
+    pub fn new() -> MultiHasher<N> {
+        MultiHasher {
+            state: [[0; N]; 25],
+            buffers: [[0; RATE]; N],
+            buffered: [0; N],
+            pending: [false; N],
+        }
+    }
+
+    /// Feed `data` to `stream`.
+    ///
+    /// # Panics
+    ///
+    /// If `stream` is not below `N`.
+    pub fn update(&mut self, stream: usize, mut data: &[u8]) {
+        assert!(stream < N, "stream {} of {}", stream, N);
+        while !data.is_empty() {
+            self.fill(stream, &mut data);
+        }
+    }
+
+    /// Feed `data[i]` to stream `i`, the streams taking turns a block at a
+    /// time, so equal-length pieces keep every pass full.
+    pub fn update_all(&mut self, mut data: [&[u8]; N]) {
+        while data.iter().any(|piece| !piece.is_empty()) {
+            for (stream, piece) in data.iter_mut().enumerate() {
+                if !piece.is_empty() {
+                    self.fill(stream, piece);
+                }
+            }
+        }
+    }
+
+    /// Apply the original Keccak `0x01 ... 0x80` padding to every stream
+    /// and squeeze them all.
+    pub fn finalize(mut self) -> [[u8; 32]; N] {
+        self.finish()
+    }
+
+    /// [`MultiHasher::finalize`] without giving up the hasher, which is
+    /// left [`reset`](MultiHasher::reset) for the next set of streams.
+    pub fn finalize_reset(&mut self) -> [[u8; 32]; N] {
+        let digests = self.finish();
+        self.reset();
+        digests
+    }
+
+    /// Forget everything absorbed so far on every stream.
+    pub fn reset(&mut self) {
+        self.state = [[0; N]; 25];
+        self.buffered = [0; N];
+        self.pending = [false; N];
+    }
+
+    /// Buffer as much of `data` as completes `stream`'s block. A full
+    /// block is absorbed, after a forced pass if the stream's previous one
+    /// is still pending, and once every stream is pending they permute.
+    fn fill(&mut self, stream: usize, data: &mut &[u8]) {
+        let buffered = self.buffered[stream];
+        let take = data.len().min(RATE - buffered);
+        self.buffers[stream][buffered..buffered + take].copy_from_slice(&data[..take]);
+        *data = &data[take..];
+        self.buffered[stream] += take;
+        if self.buffered[stream] < RATE {
+            return;
+        }
+        if self.pending[stream] {
+            self.permute_pending();
+        }
+        let block = self.buffers[stream];
+        self.absorb(stream, &block);
+        self.buffered[stream] = 0;
+        if !self.pending.contains(&false) {
+            self.permute_pending();
+        }
+    }
+
+    fn absorb(&mut self, stream: usize, block: &[u8; RATE]) {
+        for (lane, chunk) in self.state.iter_mut().zip(block.chunks_exact(8)) {
+            lane[stream] ^= u64::from_le_bytes(chunk.try_into().expect("8-byte chunk"));
+        }
+        self.pending[stream] = true;
+    }
+
+    /// Permute every pending stream, leaving the others' lanes untouched.
+    fn permute_pending(&mut self) {
+        let saved = self.state;
+        permute_lanes(&mut self.state);
+        for (lane, saved) in self.state.iter_mut().zip(&saved) {
+            for stream in 0..N {
+                if !self.pending[stream] {
+                    lane[stream] = saved[stream];
+                }
+            }
+        }
+        self.pending = [false; N];
+    }
+
+    fn finish(&mut self) -> [[u8; 32]; N] {
+        if self.pending.contains(&true) {
+            self.permute_pending();
+        }
+        for stream in 0..N {
+            let block = final_block(&self.buffers[stream][..self.buffered[stream]]);
+            self.absorb(stream, &block);
+        }
+        self.permute_pending();
+        array::from_fn(|stream| {
+            let mut digest = [0u8; 32];
+            for (chunk, lane) in digest.chunks_exact_mut(8).zip(&self.state) {
+                chunk.copy_from_slice(&lane[stream].to_le_bytes());
+            }
+            digest
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native;
+
+    /// Streams fed unevenly, one at a time or all together, in pieces that
+    /// straddle block boundaries, hash as their concatenations would, at
+    /// the SIMD widths and a portable one.
+    #[test]
+    fn streams_match_one_shot_hashes() {
+        fn check<const N: usize>() {
+            let data: Vec<u8> = (0..6000u32).map(|i| (i * 13 + 5) as u8).collect();
+            let mut hasher = MultiHasher::<N>::new();
+            let mut fed = vec![Vec::new(); N];
+            for step in 0..40 {
+                for (stream, fed) in fed.iter_mut().enumerate() {
+                    let len = (step * 7 + stream * 31) % 150;
+                    let piece = &data[fed.len()..fed.len() + len];
+                    hasher.update(stream, piece);
+                    fed.extend_from_slice(piece);
+                }
+            }
+            let expected: Vec<[u8; 32]> = fed.iter().map(|m| native::keccak256(m)).collect();
+            assert_eq!(hasher.finalize_reset().to_vec(), expected, "{}", N);
+
+            let pieces: [&[u8]; N] = array::from_fn(|stream| &data[..stream * 140]);
+            hasher.update_all(pieces);
+            hasher.update(0, b"tail");
+            let mut first = pieces[0].to_vec();
+            first.extend_from_slice(b"tail");
+            let mut expected = pieces.map(native::keccak256);
+            expected[0] = native::keccak256(&first);
+            assert_eq!(hasher.finalize(), expected, "{}", N);
+        }
+        check::<1>();
+        check::<2>();
+        check::<3>();
+        check::<4>();
+        check::<8>();
+    }
+}
@@ -0,0 +1,74 @@
+//! Two lane-sliced states in `uint64x2_t` registers.
+
+use std::arch::aarch64::*;
+
+use super::Lanes;
+
+/// One lane of each of two states. Only built inside [`keccak256_x2`],
+/// whose caller has checked for NEON, so the intrinsics below always run on
+/// a CPU that has them.
+#[derive(Clone, Copy)]
+struct Neon(uint64x2_t);
+
+// SAFETY (every method): see `Neon`; NEON is available.
+impl Lanes<2> for Neon {
+    #[inline(always)]
+    fn splat(value: u64) -> Self {
+        Neon(unsafe { vdupq_n_u64(value) })
+    }
+
+    #[inline(always)]
+    fn from_array(values: [u64; 2]) -> Self {
+        Neon(unsafe { vld1q_u64(values.as_ptr()) })
+    }
+
+    #[inline(always)]
+    fn to_array(self) -> [u64; 2] {
+        let mut values = [0u64; 2];
+        unsafe { vst1q_u64(values.as_mut_ptr(), self.0) };
+        values
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        Neon(unsafe { veorq_u64(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    fn andnot(self, other: Self) -> Self {
+        // `vbicq_u64(a, b)` is `a & !b`.
+        Neon(unsafe { vbicq_u64(other.0, self.0) })
+    }
+
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        // A negative count makes `vshlq_u64` shift right.
+        unsafe {
+            let left = vdupq_n_s64(i64::from(bits));
+            let right = vdupq_n_s64(i64::from(bits) - 64);
+            Neon(vorrq_u64(vshlq_u64(self.0, left), vshlq_u64(self.0, right)))
+        }
+    }
+}
+
+/// [`super::keccak256_x2`] on NEON.
+///
+/// # Safety
+///
+/// The CPU must support NEON.
+#[target_feature(enable = "neon")]
+pub unsafe fn keccak256_x2(messages: [&[u8]; 2]) -> [[u8; 32]; 2] {
+    super::hash_lanes::<2, Neon>(messages)
+}
+
+/// [`super::permute`] on NEON over two states held as plain lanes.
+///
+/// # Safety
+///
+/// The CPU must support NEON.
+#[target_feature(enable = "neon")]
+pub unsafe fn permute_x2(state: &mut [[u64; 2]; 25]) {
+    let mut lanes = state.map(Neon::from_array);
+    super::permute::<2, Neon>(&mut lanes);
+    *state = lanes.map(Neon::to_array);
+}
@@ -0,0 +1,88 @@
+//! Python bindings (`python` feature), so analysis notebooks can hash and
+//! benchmark in-process instead of parsing the bench binary's stdout.
+//!
+//! `maturin develop --release` in this directory builds the extension
+//! module `keccak256_rust_baseline` (see `pyproject.toml`). Configs and
+//! results cross the boundary as the JSON the binary reads and writes, so
+//! a dict holds the same keys as a result's `config`.
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use serde_json::{json, Value};
+
+use crate::bench::checksum::ChecksumMode;
+use crate::bench::{self, isolate};
+use crate::native;
+
+/// The Keccak-256 digest of `data`.
+#[pyfunction]
+fn keccak256<'py>(py: Python<'py>, data: &[u8]) -> Bound<'py, PyBytes> {
+    PyBytes::new(py, &crate::keccak256(data))
+}
+
+/// Incremental Keccak-256, shaped like a `hashlib` object.
+#[pyclass(name = "Keccak256")]
+#[derive(Clone, Default)]
+struct Hasher(native::Keccak256);
+
+#[pymethods]
+impl Hasher {
+    #[new]
+    #[pyo3(signature = (data = None))]
+    fn new(data: Option<&[u8]>) -> Hasher {
+        let mut hasher = Hasher::default();
+        if let Some(data) = data {
+            hasher.0.update(data);
+        }
+        hasher
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// The digest of everything so far; the hasher can keep absorbing.
+    fn digest<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.clone().finalize())
+    }
+
+    fn hexdigest(&self) -> String {
+        crate::to_hex_string(&self.0.clone().finalize())
+    }
+
+    fn copy(&self) -> Hasher {
+        self.clone()
+    }
+}
+
+/// Benchmark `config` and return its result. Keys missing from `config`
+/// take the binary's defaults.
+#[pyfunction]
+fn run_benchmark(py: Python<'_>, config: &Bound<'_, PyDict>) -> PyResult<PyObject> {
+    let json = py.import("json")?;
+    let text: String = json.call_method1("dumps", (config,))?.extract()?;
+    let mut value: Value =
+        serde_json::from_str(&text).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    // Recorded configs without a mode mean XOR; a new one means the default.
+    if let Some(fields) = value.as_object_mut() {
+        fields
+            .entry("checksum_mode")
+            .or_insert_with(|| json!(ChecksumMode::default()));
+    }
+    let config = isolate::config(&value).map_err(PyValueError::new_err)?;
+    let report = py
+        .allow_threads(|| bench::run(config))
+        .map_err(PyRuntimeError::new_err)?;
+    let result = serde_json::to_string(&report.results[0])
+        .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+    Ok(json.call_method1("loads", (result,))?.unbind())
+}
+
+#[pymodule]
+fn keccak256_rust_baseline(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(keccak256, module)?)?;
+    module.add_function(wrap_pyfunction!(run_benchmark, module)?)?;
+    module.add_class::<Hasher>()?;
+    Ok(())
+}
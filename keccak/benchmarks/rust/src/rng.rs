@@ -0,0 +1,368 @@
+//! Seedable pseudorandom streams built from Keccak alone.
+//!
+//! [`KeccakRng`] is the simple one. Block `i` of its stream is
+//! `keccak256(seed || be64(i))` for a 32-byte seed, and the stream is those
+//! 32-byte blocks back to back; integers are read from it little-endian.
+//! Anything with a Keccak-256, the Mojo port included, reproduces it from
+//! the seed. [`KeccakRng::from_u64`] spreads a small seed to
+//! `keccak256(be64(seed))` first.
+//!
+//! [`ShakeDrbg`] is a SHAKE256 bit generator shaped like NIST SP 800-90A:
+//! instantiated from entropy, a nonce and a personalization string,
+//! reseeded explicitly, with optional additional input per request, and
+//! its state replaced after every request so earlier output cannot be
+//! recomputed from a later state. It expands one test seed into nonce
+//! extra data and test keys for separate purposes without them
+//! overlapping.
+//!
+//! Both are for reproducible tests and workloads: whoever has the seed has
+//! every output, so neither is a key generator for real funds.
+//!
+//! With the `rand` feature both implement `rand_core`'s `RngCore`, and
+//! [`KeccakRng`] `SeedableRng`, so `rand` distributions can draw from them.
+
+use crate::native::Shake;
+use crate::secret::SecretKey;
+
+/// Pseudorandom bytes from a seed, in the layout the module describes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeccakRng {
+    seed: [u8; 32],
+    /// Index of the next block to generate.
+    counter: u64,
+    block: [u8; 32],
+    /// Bytes of `block` already handed out.
+    used: usize,
+}
+
+impl KeccakRng {
+    pub fn new(seed: [u8; 32]) -> KeccakRng {
+        KeccakRng {
+            seed,
+            counter: 0,
+            block: [0; 32],
+            used: 32,
+        }
+    }
+
+    /// A stream seeded with `keccak256(be64(seed))`.
+    pub fn from_u64(seed: u64) -> KeccakRng {
+        KeccakRng::new(crate::keccak256(&seed.to_be_bytes()))
+    }
+
+    fn refill(&mut self) {
+        let mut preimage = [0u8; 40];
+        preimage[..32].copy_from_slice(&self.seed);
+        preimage[32..].copy_from_slice(&self.counter.to_be_bytes());
+        self.block = crate::keccak256(&preimage);
+        self.counter = self.counter.wrapping_add(1);
+        self.used = 0;
+    }
+
+    /// Fill `out` with the next bytes of the stream.
+    pub fn fill_bytes(&mut self, mut out: &mut [u8]) {
+        while !out.is_empty() {
+            if self.used == 32 {
+                self.refill();
+            }
+            let take = out.len().min(32 - self.used);
+            out[..take].copy_from_slice(&self.block[self.used..self.used + take]);
+            self.used += take;
+            out = &mut out[take..];
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand_core::RngCore for KeccakRng {
+    fn next_u32(&mut self) -> u32 {
+        KeccakRng::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        KeccakRng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, out: &mut [u8]) {
+        KeccakRng::fill_bytes(self, out)
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand_core::SeedableRng for KeccakRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: [u8; 32]) -> KeccakRng {
+        KeccakRng::new(seed)
+    }
+
+    /// [`KeccakRng::from_u64`], rather than `rand_core`'s PCG expansion,
+    /// so the stream stays reproducible from Keccak-256 alone.
+    fn seed_from_u64(seed: u64) -> KeccakRng {
+        KeccakRng::from_u64(seed)
+    }
+}
+
+/// The label every [`ShakeDrbg`] input starts with.
+pub const DRBG_TAG: &[u8] = b"shake256-drbg-v1";
+
+/// Bytes of [`ShakeDrbg`] state.
+const DRBG_STATE: usize = 64;
+
+/// A SHAKE256 deterministic random bit generator.
+///
+/// With `enc(x) = be64(len(x)) || x`, and the operation byte after
+/// [`DRBG_TAG`] telling operations apart:
+///
+/// ```text
+/// instantiate: V = SHAKE256(tag || 0 || enc(entropy) || enc(nonce) || enc(personalization))[..64]
+/// reseed:      V = SHAKE256(tag || 1 || V || enc(entropy) || enc(additional))[..64]
+/// generate:    S = SHAKE256(tag || 2 || V || enc(additional)); V = S[..64]; output S[64..]
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct ShakeDrbg {
+    state: [u8; DRBG_STATE],
+    /// Requests served since instantiation or the last reseed.
+    generated: u64,
+}
+
+impl ShakeDrbg {
+    /// A generator from `entropy` and `nonce`; `personalization` separates
+    /// generators sharing them, e.g. one per test or purpose.
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> ShakeDrbg {
+        let mut shake = ShakeDrbg::start(0);
+        for field in [entropy, nonce, personalization] {
+            absorb_field(&mut shake, field);
+        }
+        let mut state = [0u8; DRBG_STATE];
+        shake.finalize_xof().squeeze(&mut state);
+        ShakeDrbg {
+            state,
+            generated: 0,
+        }
+    }
+
+    fn start(operation: u8) -> Shake {
+        let mut shake = Shake::shake256();
+        shake.update(DRBG_TAG);
+        shake.update(&[operation]);
+        shake
+    }
+
+    /// Mix fresh `entropy` and `additional` input into the state.
+    pub fn reseed(&mut self, entropy: &[u8], additional: &[u8]) {
+        let mut shake = ShakeDrbg::start(1);
+        shake.update(&self.state);
+        absorb_field(&mut shake, entropy);
+        absorb_field(&mut shake, additional);
+        shake.finalize_xof().squeeze(&mut self.state);
+        self.generated = 0;
+    }
+
+    /// Fill `out` with one request's output, mixing in `additional`
+    /// (empty for none).
+    pub fn generate(&mut self, out: &mut [u8], additional: &[u8]) {
+        let mut shake = ShakeDrbg::start(2);
+        shake.update(&self.state);
+        absorb_field(&mut shake, additional);
+        let mut reader = shake.finalize_xof();
+        reader.squeeze(&mut self.state);
+        reader.squeeze(out);
+        self.generated += 1;
+    }
+
+    /// Requests served since instantiation or the last [`reseed`](Self::reseed).
+    pub fn generated(&self) -> u64 {
+        self.generated
+    }
+
+    /// 32 bytes for the extra-data input of deterministic nonce
+    /// generation, such as RFC 6979's `k` derivation.
+    pub fn nonce_extra_data(&mut self) -> [u8; 32] {
+        let mut extra = [0u8; 32];
+        self.generate(&mut extra, b"nonce-extra-data");
+        extra
+    }
+
+    /// A secp256k1 secret key, uniform in `[1, n)`: 32-byte draws, with
+    /// `secret-scalar` as the additional input, outside that range are
+    /// rejected and drawn again. Each draw is written into the key
+    /// itself, so a rejected one is wiped and none is copied.
+    pub fn secret_key(&mut self) -> SecretKey {
+        loop {
+            let drawn = SecretKey::fill(|bytes| {
+                self.generate(bytes, b"secret-scalar");
+                Ok(())
+            });
+            if let Ok(key) = drawn {
+                return key;
+            }
+        }
+    }
+}
+
+fn absorb_field(shake: &mut Shake, field: &[u8]) {
+    shake.update(&(field.len() as u64).to_be_bytes());
+    shake.update(field);
+}
+
+impl std::fmt::Debug for ShakeDrbg {
+    /// Leaves the state out, as anyone holding it can predict the output.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShakeDrbg")
+            .field("generated", &self.generated)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand_core::RngCore for ShakeDrbg {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.generate(&mut bytes, &[]);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.generate(&mut bytes, &[]);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, out: &mut [u8]) {
+        self.generate(out, &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keccak256;
+    use crate::secp256k1::N;
+
+    /// The stream is the documented blocks however it is read, integers
+    /// take its bytes little-endian, and seeds give distinct streams.
+    #[test]
+    fn stream_is_counter_mode_keccak() {
+        let seed = [7u8; 32];
+        let block = |i: u64| keccak256(&[&seed[..], &i.to_be_bytes()].concat());
+        let expected: Vec<u8> = (0..4).flat_map(block).collect();
+
+        let mut whole = vec![0u8; 128];
+        KeccakRng::new(seed).fill_bytes(&mut whole);
+        assert_eq!(whole, expected);
+
+        let mut rng = KeccakRng::new(seed);
+        let mut pieces = Vec::new();
+        for len in [1, 7, 24, 33, 0, 63] {
+            let mut piece = vec![0u8; len];
+            rng.fill_bytes(&mut piece);
+            pieces.extend(piece);
+        }
+        assert_eq!(pieces, expected);
+
+        let mut rng = KeccakRng::new(seed);
+        assert_eq!(
+            rng.next_u32(),
+            u32::from_le_bytes(expected[..4].try_into().unwrap())
+        );
+        assert_eq!(
+            rng.next_u64(),
+            u64::from_le_bytes(expected[4..12].try_into().unwrap())
+        );
+
+        let small = KeccakRng::from_u64(42);
+        assert_eq!(small, KeccakRng::new(keccak256(&42u64.to_be_bytes())));
+        assert_ne!(KeccakRng::from_u64(43).next_u64(), small.clone().next_u64());
+        // Worked out independently from the definition with keccak256sum.
+        assert_eq!(KeccakRng::from_u64(0).next_u64(), 0x976e_c155_8782_0aff);
+    }
+
+    /// The generator follows its documented construction, reseeding and
+    /// personalization change what follows, and derived keys are in range.
+    #[test]
+    fn drbg_follows_its_construction() {
+        let enc = |field: &[u8]| [&(field.len() as u64).to_be_bytes()[..], field].concat();
+        let shake = |input: &[u8], len: usize| {
+            let mut shake = Shake::shake256();
+            shake.update(input);
+            let mut out = vec![0u8; len];
+            shake.finalize_xof().squeeze(&mut out);
+            out
+        };
+        let init = [
+            DRBG_TAG,
+            &[0],
+            &enc(b"entropy"),
+            &enc(b"nonce"),
+            &enc(b"test"),
+        ]
+        .concat();
+        let state = shake(&init, 64);
+        let request = [DRBG_TAG, &[2], &state, &enc(b"")].concat();
+        let expected = shake(&request, 64 + 40);
+        let mut drbg = ShakeDrbg::new(b"entropy", b"nonce", b"test");
+        let mut out = [0u8; 40];
+        drbg.generate(&mut out, &[]);
+        assert_eq!(out[..], expected[64..]);
+        assert_eq!(drbg.state[..], expected[..64]);
+        assert_eq!(drbg.generated(), 1);
+
+        let fresh = || ShakeDrbg::new(b"entropy", b"nonce", b"test");
+        let draw = |mut drbg: ShakeDrbg| {
+            let mut out = [0u8; 32];
+            drbg.generate(&mut out, &[]);
+            out
+        };
+        assert_eq!(draw(fresh()), draw(fresh()));
+        assert_ne!(
+            draw(fresh()),
+            draw(ShakeDrbg::new(b"entropy", b"nonce", b"other"))
+        );
+        let mut reseeded = fresh();
+        reseeded.reseed(b"more entropy", &[]);
+        assert_eq!(reseeded.generated(), 0);
+        assert_ne!(draw(reseeded), draw(fresh()));
+        let mut with_input = fresh();
+        let mut out = [0u8; 32];
+        with_input.generate(&mut out, b"request 1");
+        assert_ne!(out, draw(fresh()));
+
+        let mut drbg = fresh();
+        assert_ne!(drbg.nonce_extra_data(), drbg.nonce_extra_data());
+        for _ in 0..8 {
+            let key = drbg.secret_key();
+            assert!(!key.expose().is_zero() && key.expose().to_bytes() < N);
+        }
+        assert_eq!(
+            format!("{:?}", ShakeDrbg::new(b"", b"", b"")),
+            "ShakeDrbg { generated: 0, .. }"
+        );
+    }
+
+    /// Through `rand_core`, the traits read the same stream.
+    #[cfg(feature = "rand")]
+    #[test]
+    fn rand_core_traits_read_the_stream() {
+        use rand_core::{RngCore, SeedableRng};
+        let mut direct = KeccakRng::from_u64(9);
+        let mut via = <KeccakRng as SeedableRng>::seed_from_u64(9);
+        assert_eq!(RngCore::next_u64(&mut via), direct.next_u64());
+        let (mut a, mut b) = ([0u8; 50], [0u8; 50]);
+        RngCore::fill_bytes(&mut via, &mut a);
+        direct.fill_bytes(&mut b);
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,500 @@
+//! End-to-end signing fixtures, so the Mojo port can check whole flows
+//! and not only the primitives they are built from.
+//!
+//! A scenario has several signers, each a key derived by BIP-32 from the
+//! scenario's seed at `m/44'/60'/0'/0/i`, which sign EIP-1559
+//! transactions to each other in turn: plain transfers, token-style calls
+//! with an access list, and contract creations. For every transaction
+//! the document gives the fields, the signing payload and its hash, the
+//! RFC 6979 signature, the signed encoding and its hash, and the address
+//! recovered from the signature. Everything follows from the [`Spec`], so
+//! regenerating gives the same bytes; `keccak/vectors/scenarios/` holds
+//! [`SPECS`] as the `scenarios` binary writes them, and a test keeps the
+//! files in step with this module.
+//!
+//! The signer is for fixtures only: it runs in variable time, and anyone
+//! with the seed has the keys. The module needs the `test-utils` feature,
+//! and `verify-only` compiles it out.
+
+use serde::Serialize;
+
+use crate::ethereum::{checksum_address, recover_address, Eip1559Transaction};
+use crate::keccak256;
+use crate::secp256k1::{AffinePoint, Scalar, G};
+use crate::sha256::hmac_sha256;
+use crate::sha512::hmac_sha512;
+use crate::signature::Signature;
+use crate::vectors::SCHEMA_VERSION;
+
+/// The committed scenario files, relative to this crate.
+pub const SCENARIO_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../vectors/scenarios");
+
+/// Added to a BIP-32 index for hardened derivation.
+pub const HARDENED: u32 = 1 << 31;
+
+/// `m/44'/60'/0'/0`, the BIP-44 Ethereum account whose children sign.
+pub const ETHEREUM_ACCOUNT: [u32; 4] = [44 | HARDENED, 60 | HARDENED, HARDENED, 0];
+
+/// A BIP-32 extended private key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExtendedKey {
+    pub key: Scalar,
+    pub chain_code: [u8; 32],
+}
+
+impl ExtendedKey {
+    /// `HMAC-SHA512(key, data)` split into a key and a chain code, or
+    /// `None` when the key half is not a valid scalar.
+    fn from_hmac(key: &[u8], data: &[u8], parent: &Scalar) -> Option<ExtendedKey> {
+        let output = hmac_sha512(key, data);
+        let tweak = Scalar::from_bytes(&output[..32].try_into().expect("32 bytes"))?;
+        let key = tweak.add(parent);
+        (!key.is_zero()).then(|| ExtendedKey {
+            key,
+            chain_code: output[32..].try_into().expect("32 bytes"),
+        })
+    }
+
+    /// The master key of `seed`, or `None` in the cases BIP-32 calls
+    /// invalid, which no seed is known to hit.
+    pub fn master(seed: &[u8]) -> Option<ExtendedKey> {
+        ExtendedKey::from_hmac(b"Bitcoin seed", seed, &Scalar::ZERO)
+    }
+
+    pub fn public_key(&self) -> AffinePoint {
+        G.mul(&self.key).expect("keys are nonzero")
+    }
+
+    /// Child `index`, hardened from [`HARDENED`] up, or `None` in the
+    /// invalid cases, where BIP-32 moves on to the next index.
+    pub fn child(&self, index: u32) -> Option<ExtendedKey> {
+        let mut data = Vec::with_capacity(37);
+        if index >= HARDENED {
+            data.push(0);
+            data.extend_from_slice(&self.key.to_bytes());
+        } else {
+            data.extend_from_slice(&self.public_key().to_compressed());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+        ExtendedKey::from_hmac(&self.chain_code, &data, &self.key)
+    }
+
+    pub fn derive(&self, path: &[u32]) -> Option<ExtendedKey> {
+        path.iter().try_fold(*self, |key, &index| key.child(index))
+    }
+}
+
+/// `path` in the usual notation, such as `m/44'/60'/0'/0/3`.
+pub fn path_string(path: &[u32]) -> String {
+    let mut out = String::from("m");
+    for &index in path {
+        if index >= HARDENED {
+            out += &format!("/{}'", index - HARDENED);
+        } else {
+            out += &format!("/{}", index);
+        }
+    }
+    out
+}
+
+/// An ECDSA signature of the 32-byte `hash` with the RFC 6979 nonce for
+/// `key` and HMAC-SHA-256, normalized to low `s`, and whether the nonce
+/// point's `y` is odd after normalizing, as `recover` and `y_parity` take
+/// it. An `r` that wrapped past `n` would need a bit this cannot carry;
+/// that happens with probability about 2^-128.
+///
+/// **Variable time, fixtures only.** The nonce multiplication and the
+/// scalar arithmetic take time that depends on the key and nonce, so a
+/// caller that can be timed leaks the key. Never sign with real keys.
+pub fn sign(key: &Scalar, hash: &[u8; 32]) -> (Signature, bool) {
+    let z = Scalar::reduce(hash);
+    let mut k = [0u8; 32];
+    let mut v = [0x01; 32];
+    for tag in [0x00, 0x01] {
+        k = hmac_sha256(
+            &k,
+            &[&v[..], &[tag], &key.to_bytes(), &z.to_bytes()].concat(),
+        );
+        v = hmac_sha256(&k, &v);
+    }
+    loop {
+        v = hmac_sha256(&k, &v);
+        if let Some(nonce) = Scalar::from_bytes(&v).filter(|nonce| !nonce.is_zero()) {
+            let point = G.mul(&nonce).expect("the nonce is nonzero");
+            let r = Scalar::reduce(&point.x.to_bytes());
+            let s = nonce
+                .invert()
+                .expect("the nonce is nonzero")
+                .mul(&z.add(&r.mul(key)));
+            if let Ok(signature) = Signature::new(r, s) {
+                let odd = point.y.is_odd() ^ !signature.is_low_s();
+                return (signature.normalize_s(), odd);
+            }
+        }
+        k = hmac_sha256(&k, &[&v[..], &[0x00]].concat());
+        v = hmac_sha256(&k, &v);
+    }
+}
+
+/// What a scenario is generated from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Spec {
+    /// Also the file name, without `.json`.
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The BIP-32 seed, 16 to 64 bytes.
+    pub seed: &'static [u8],
+    pub chain_id: u64,
+    pub signers: u32,
+    pub transactions: u32,
+}
+
+/// The scenarios in `keccak/vectors/scenarios/`.
+pub const SPECS: &[Spec] = &[
+    Spec {
+        name: "mainnet-ring",
+        description:
+            "Three mainnet accounts from the BIP-32 test vector 1 seed paying each other in turn",
+        seed: &[
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ],
+        chain_id: 1,
+        signers: 3,
+        transactions: 6,
+    },
+    Spec {
+        name: "sepolia-pair",
+        description: "Two Sepolia accounts trading transfers, calls and contract creations",
+        seed: b"mojo-secp256k1 sepolia scenario seed",
+        chain_id: 11_155_111,
+        signers: 2,
+        transactions: 6,
+    },
+    Spec {
+        name: "single-signer",
+        description: "One account on a chain id above 32 bits sending to itself",
+        seed: &[0xa5; 64],
+        chain_id: 0x1_0000_0001,
+        signers: 1,
+        transactions: 3,
+    },
+];
+
+/// A scenario as written to disk; every byte string is `0x` hex, every
+/// quantity a `0x` hex number.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Scenario {
+    pub schema_version: u64,
+    pub name: String,
+    pub description: String,
+    pub seed: String,
+    pub chain_id: u64,
+    pub signers: Vec<Signer>,
+    pub transactions: Vec<SignedTransaction>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Signer {
+    pub path: String,
+    pub private_key: String,
+    /// Uncompressed, `0x04`-prefixed.
+    pub public_key: String,
+    /// EIP-55 cased.
+    pub address: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SignedTransaction {
+    /// Index into the scenario's signers.
+    pub signer: usize,
+    /// `transfer`, `call` or `create`.
+    pub kind: String,
+    pub fields: Fields,
+    pub signing_payload: String,
+    pub signing_hash: String,
+    pub signature: SignatureFields,
+    /// The signed encoding.
+    pub raw: String,
+    /// Keccak-256 of `raw`.
+    pub hash: String,
+    pub recovered_address: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Fields {
+    pub chain_id: String,
+    pub nonce: String,
+    pub max_priority_fee_per_gas: String,
+    pub max_fee_per_gas: String,
+    pub gas_limit: String,
+    /// Empty (`0x`) for contract creation.
+    pub to: String,
+    pub value: String,
+    pub data: String,
+    pub access_list: Vec<AccessListEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AccessListEntry {
+    pub address: String,
+    pub storage_keys: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SignatureFields {
+    pub r: String,
+    pub s: String,
+    pub y_parity: u8,
+}
+
+const GWEI: u128 = 1_000_000_000;
+
+/// Init code for a contract whose every call returns 42.
+const INIT_CODE: [u8; 22] = [
+    0x60, 0x0a, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x0a, 0x60, 0x00, 0xf3, 0x60, 0x2a, 0x60, 0x00,
+    0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+];
+
+pub(crate) fn prefixed(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn quantity_bytes(value: u128) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    bytes[bytes.iter().position(|&b| b != 0).unwrap_or(16)..].to_vec()
+}
+
+fn quantity(bytes: &[u8]) -> String {
+    let digits = hex::encode(bytes);
+    match digits.trim_start_matches('0') {
+        "" => String::from("0x0"),
+        digits => format!("0x{}", digits),
+    }
+}
+
+fn word(value: u128) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[16..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+/// Transaction `index` of a scenario, sent to `to` with `nonce`: a
+/// transfer, a token-style `transfer(to, amount)` call with an access
+/// list, or a contract creation, in that rotation.
+fn transaction(
+    spec: &Spec,
+    index: u32,
+    nonce: u32,
+    to: &[u8; 20],
+) -> (&'static str, Eip1559Transaction) {
+    let t = u128::from(index);
+    let mut tx = Eip1559Transaction {
+        chain_id: quantity_bytes(u128::from(spec.chain_id)),
+        nonce: quantity_bytes(u128::from(nonce)),
+        max_priority_fee_per_gas: quantity_bytes((1 + t % 3) * GWEI),
+        max_fee_per_gas: quantity_bytes((30 + t) * GWEI),
+        gas_limit: quantity_bytes(21_000),
+        to: to.to_vec(),
+        value: quantity_bytes((t + 1) * 1_000_000_000_000_000),
+        data: Vec::new(),
+        access_list: Vec::new(),
+    };
+    match index % 3 {
+        0 => ("transfer", tx),
+        1 => {
+            tx.gas_limit = quantity_bytes(60_000);
+            tx.value = Vec::new();
+            tx.data = [
+                &[0xa9, 0x05, 0x9c, 0xbb][..],
+                &word(0)[..12],
+                to,
+                &word((t + 1) * 1_000_000_000_000_000_000),
+            ]
+            .concat();
+            tx.access_list = vec![(*to, vec![word(0), word(t)])];
+            ("call", tx)
+        }
+        _ => {
+            tx.gas_limit = quantity_bytes(100_000);
+            tx.to = Vec::new();
+            tx.value = Vec::new();
+            tx.data = INIT_CODE.to_vec();
+            ("create", tx)
+        }
+    }
+}
+
+/// The scenario `spec` describes. Transaction `i` is sent by signer `i
+/// mod signers` to the next signer, with that sender's next nonce.
+///
+/// # Panics
+///
+/// If `spec` has no signers, or its seed derives an invalid key.
+pub fn generate(spec: &Spec) -> Scenario {
+    assert!(spec.signers > 0, "a scenario needs a signer");
+    let master = ExtendedKey::master(spec.seed).expect("a valid master key");
+    let keys: Vec<(String, ExtendedKey)> = (0..spec.signers)
+        .map(|i| {
+            let path = [&ETHEREUM_ACCOUNT[..], &[i]].concat();
+            let key = master.derive(&path).expect("a valid child key");
+            (path_string(&path), key)
+        })
+        .collect();
+    let addresses: Vec<[u8; 20]> = keys
+        .iter()
+        .map(|(_, key)| {
+            crate::ethereum::address(&key.public_key().to_uncompressed()).expect("65 bytes")
+        })
+        .collect();
+    let signers = keys
+        .iter()
+        .zip(&addresses)
+        .map(|((path, key), address)| Signer {
+            path: path.clone(),
+            private_key: prefixed(&key.key.to_bytes()),
+            public_key: prefixed(&key.public_key().to_uncompressed()),
+            address: checksum_address(address),
+        })
+        .collect();
+
+    let transactions = (0..spec.transactions)
+        .map(|index| {
+            let signer = (index % spec.signers) as usize;
+            let to = &addresses[(signer + 1) % addresses.len()];
+            let (kind, tx) = transaction(spec, index, index / spec.signers, to);
+            let signing_hash = tx.signing_hash();
+            let (signature, odd) = sign(&keys[signer].1.key, &signing_hash);
+            let raw = tx.encode_signed(&signature, odd);
+            let mut compact = [0u8; 65];
+            compact[..64].copy_from_slice(&signature.to_compact());
+            compact[64] = u8::from(odd);
+            let recovered = recover_address(&signing_hash, &compact).expect("a fresh signature");
+            SignedTransaction {
+                signer,
+                kind: kind.to_string(),
+                fields: Fields {
+                    chain_id: quantity(&tx.chain_id),
+                    nonce: quantity(&tx.nonce),
+                    max_priority_fee_per_gas: quantity(&tx.max_priority_fee_per_gas),
+                    max_fee_per_gas: quantity(&tx.max_fee_per_gas),
+                    gas_limit: quantity(&tx.gas_limit),
+                    to: prefixed(&tx.to),
+                    value: quantity(&tx.value),
+                    data: prefixed(&tx.data),
+                    access_list: tx
+                        .access_list
+                        .iter()
+                        .map(|(address, keys)| AccessListEntry {
+                            address: prefixed(address),
+                            storage_keys: keys.iter().map(|key| prefixed(key)).collect(),
+                        })
+                        .collect(),
+                },
+                signing_payload: prefixed(&tx.signing_payload()),
+                signing_hash: prefixed(&signing_hash),
+                signature: SignatureFields {
+                    r: prefixed(&signature.r.to_bytes()),
+                    s: prefixed(&signature.s.to_bytes()),
+                    y_parity: u8::from(odd),
+                },
+                hash: prefixed(&keccak256(&raw)),
+                raw: prefixed(&raw),
+                recovered_address: checksum_address(&recovered),
+            }
+        })
+        .collect();
+
+    Scenario {
+        schema_version: SCHEMA_VERSION,
+        name: spec.name.to_string(),
+        description: spec.description.to_string(),
+        seed: prefixed(spec.seed),
+        chain_id: spec.chain_id,
+        signers,
+        transactions,
+    }
+}
+
+/// `scenario` as its file holds it: pretty-printed, with a final newline.
+pub fn to_json(scenario: &Scenario) -> String {
+    let mut text = serde_json::to_string_pretty(scenario).expect("scenarios serialize");
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sha256::sha256;
+
+    /// BIP-32 test vector 1: the master key and the deepest child, through
+    /// both hardened and normal steps.
+    #[test]
+    #[cfg_attr(miri, ignore = "several point multiplications")]
+    fn derivation_matches_bip32_test_vector_1() {
+        let seed: Vec<u8> = (0..16).collect();
+        let master = ExtendedKey::master(&seed).unwrap();
+        assert_eq!(
+            hex::encode(master.key.to_bytes()),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        assert_eq!(
+            hex::encode(master.chain_code),
+            "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508"
+        );
+        let path = [HARDENED, 1, 2 | HARDENED, 2, 1_000_000_000];
+        assert_eq!(path_string(&path), "m/0'/1/2'/2/1000000000");
+        assert_eq!(
+            hex::encode(master.derive(&path).unwrap().key.to_bytes()),
+            "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8"
+        );
+    }
+
+    /// The widely published RFC 6979 secp256k1 signatures of
+    /// `SHA-256("Satoshi Nakamoto")`, low-s, recovering to the signer.
+    #[test]
+    #[cfg_attr(miri, ignore = "several point multiplications")]
+    fn signing_matches_rfc_6979_vectors() {
+        let hash = sha256(b"Satoshi Nakamoto");
+        let cases = [
+            (
+                Scalar::ONE,
+                "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8\
+                 2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+            ),
+            (
+                Scalar::ONE.neg(),
+                "fd567d121db66e382991534ada77a6bd3106f0a1098c231e47993447cd6af2d0\
+                 6b39cd0eb1bc8603e159ef5c20a5c8ad685a45b06ce9bebed3f153d10d93bed5",
+            ),
+        ];
+        for (key, expected) in cases {
+            let (signature, odd) = sign(&key, &hash);
+            assert_eq!(hex::encode(signature.to_compact()), expected);
+            assert!(signature.is_low_s());
+            assert_eq!(signature.recover(&hash, odd).ok(), G.mul(&key));
+        }
+    }
+
+    /// The committed files are what this module generates, and every
+    /// transaction recovers to its signer. The answers were also checked
+    /// against an independent Python implementation.
+    #[test]
+    #[cfg_attr(miri, ignore = "reads files and signs")]
+    fn committed_scenarios_are_current() {
+        for spec in SPECS {
+            let scenario = generate(spec);
+            for tx in &scenario.transactions {
+                assert_eq!(tx.recovered_address, scenario.signers[tx.signer].address);
+            }
+            let path = std::path::Path::new(SCENARIO_DIR).join(format!("{}.json", spec.name));
+            let committed = std::fs::read_to_string(&path).unwrap();
+            assert!(
+                committed == to_json(&scenario),
+                "{} is stale; rerun the scenarios binary",
+                path.display()
+            );
+        }
+    }
+}
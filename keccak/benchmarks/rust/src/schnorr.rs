@@ -0,0 +1,288 @@
+//! BIP-340 Schnorr signature verification, one signature at a time or a
+//! whole batch at once.
+//!
+//! A signature `r || s` over `message` by the x-only key `P` is valid when
+//! `R = s * G - e * P` has an even `y` and `x = r`, where `e` is the
+//! `BIP0340/challenge` hash of `r || P || message` modulo `n`.
+//! [`verify_schnorr`] checks exactly that. [`verify_schnorr_batch`] lifts
+//! every `r` to its even point `R_i` instead and checks the single
+//! equation
+//!
+//! `(a_1 s_1 + ... + a_u s_u) * G = a_1 R_1 + a_1 e_1 P_1 + ... + a_u R_u + a_u e_u P_u`
+//!
+//! with one [`multiscalar_mul`] of `2u + 1` terms, which costs well under
+//! `u` separate verifications. The randomizers `a_i` keep invalid
+//! signatures from cancelling each other out: `a_1` is 1 and the rest are
+//! tagged hashes of every signature and challenge in the batch, so they
+//! are reproducible, yet change unpredictably with any input a forger
+//! could pick. Like the rest of the Rust curve code this runs in variable
+//! time, which is fine for public signatures.
+
+use crate::multiscalar::multiscalar_mul;
+use crate::secp256k1::{double_mul, AffinePoint, FieldElement, Scalar, G, P};
+use crate::sha256::tagged_hash;
+
+/// The tag [`verify_schnorr_batch`] derives its randomizers under.
+const BATCH_TAG: &[u8] = b"keccak256-baseline/schnorr-batch";
+
+/// One signature for [`verify_schnorr_batch`]: an x-only public key, the
+/// message of any length, and the signature `r || s`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchnorrInput<'a> {
+    pub public_key: &'a [u8; 32],
+    pub message: &'a [u8],
+    pub signature: &'a [u8; 64],
+}
+
+/// An input with its encodings checked: the key as its even point, `r`
+/// below `p`, `s` below `n`, and the challenge.
+struct Parsed {
+    key: AffinePoint,
+    r: [u8; 32],
+    s: Scalar,
+    e: Scalar,
+}
+
+/// The point with x coordinate `x` and even `y`, or `None` when `x` is not
+/// below `p` or no such point exists: an x-only key or `r` as a point.
+pub(crate) fn lift_even(x: &[u8; 32]) -> Option<AffinePoint> {
+    AffinePoint::lift_x(&FieldElement::from_bytes(x)?, false)
+}
+
+/// `input` with `key`, its public key already lifted.
+fn parse(input: &SchnorrInput, key: AffinePoint) -> Option<Parsed> {
+    let r: [u8; 32] = input.signature[..32].try_into().expect("32 bytes");
+    let s: [u8; 32] = input.signature[32..].try_into().expect("32 bytes");
+    if r >= P {
+        return None;
+    }
+    let s = Scalar::from_bytes(&s)?;
+    let challenge = tagged_hash(
+        b"BIP0340/challenge",
+        &[&r[..], &input.public_key[..], input.message].concat(),
+    );
+    Some(Parsed {
+        key,
+        r,
+        s,
+        e: Scalar::reduce(&challenge),
+    })
+}
+
+/// Whether `signature` is a valid BIP-340 signature of `message` by the
+/// x-only `public_key`.
+pub fn verify_schnorr(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    let input = SchnorrInput {
+        public_key,
+        message,
+        signature,
+    };
+    lift_even(public_key).is_some_and(|key| verify_lifted(&input, key))
+}
+
+/// [`verify_schnorr`] with the key lifted already, as
+/// [`crate::verifier::VerifyContext`] caches it.
+pub(crate) fn verify_lifted(input: &SchnorrInput, key: AffinePoint) -> bool {
+    let Some(parsed) = parse(input, key) else {
+        return false;
+    };
+    double_mul(&parsed.s, &G, &parsed.e.neg(), &parsed.key)
+        .is_some_and(|point| !point.y.is_odd() && point.x.to_bytes() == parsed.r)
+}
+
+/// Whether every signature in `inputs` is valid, by one randomized
+/// equation over the whole batch. An empty batch is valid.
+///
+/// `true` means all of them are valid, short of a false accept with
+/// probability about `1 / n` per call. `false` means at least one is
+/// invalid and says nothing about which: the equation fails as a whole.
+/// A caller that needs to know which signatures to reject checks each
+/// with [`verify_schnorr`] after a failed batch.
+pub fn verify_schnorr_batch(inputs: &[SchnorrInput]) -> bool {
+    let Some(keys) = inputs
+        .iter()
+        .map(|input| lift_even(input.public_key))
+        .collect::<Option<Vec<AffinePoint>>>()
+    else {
+        return false;
+    };
+    verify_batch_lifted(inputs, &keys)
+}
+
+/// [`verify_schnorr_batch`] with `keys[i]` the lifted key of `inputs[i]`.
+pub(crate) fn verify_batch_lifted(inputs: &[SchnorrInput], keys: &[AffinePoint]) -> bool {
+    let Some(parsed) = inputs
+        .iter()
+        .zip(keys)
+        .map(|(input, &key)| parse(input, key))
+        .collect::<Option<Vec<Parsed>>>()
+    else {
+        return false;
+    };
+    let Some(nonces) = parsed
+        .iter()
+        .map(|parsed| lift_even(&parsed.r))
+        .collect::<Option<Vec<AffinePoint>>>()
+    else {
+        return false;
+    };
+
+    // Each challenge commits to its key and message, so the signatures
+    // and challenges pin down the whole batch.
+    let mut transcript = Vec::with_capacity(inputs.len() * 96);
+    for (input, parsed) in inputs.iter().zip(&parsed) {
+        transcript.extend_from_slice(input.signature);
+        transcript.extend_from_slice(&parsed.e.to_bytes());
+    }
+    let seed = tagged_hash(BATCH_TAG, &transcript);
+
+    let mut scalars = Vec::with_capacity(2 * inputs.len() + 1);
+    let mut points = Vec::with_capacity(2 * inputs.len() + 1);
+    let mut s_sum = Scalar::ZERO;
+    for (i, (parsed, nonce)) in parsed.iter().zip(nonces).enumerate() {
+        let a = if i == 0 {
+            Scalar::ONE
+        } else {
+            let index = u32::try_from(i).expect("fewer than 2^32 signatures");
+            Scalar::reduce(&tagged_hash(
+                BATCH_TAG,
+                &[&seed[..], &index.to_be_bytes()].concat(),
+            ))
+        };
+        s_sum = s_sum.add(&a.mul(&parsed.s));
+        scalars.push(a);
+        points.push(nonce);
+        scalars.push(a.mul(&parsed.e));
+        points.push(parsed.key);
+    }
+    scalars.push(s_sum.neg());
+    points.push(G);
+    multiscalar_mul(&scalars, &points).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::N;
+
+    /// BIP-340 test vectors 0, 1, 2 and 15: key, message, signature.
+    const VECTORS: [(&str, &str, &str); 4] = [
+        (
+            "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca8215\
+             25f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0",
+        ),
+        (
+            "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+            "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de3341\
+             8906d11ac976abccb20b091292bff4ea897efcb639ea871cfa95f6de339e4b0a",
+        ),
+        (
+            "dd308afec5777e13121fa72b9cc1b7cc0139715309b086c960e18fd969774eb8",
+            "7e2d58d8b3bcdf1abadec7829054f90dda9805aab56c77333024b9d0a508b75c",
+            "5831aaeed7b44bb74e5eab94ba9d4294c49bcf2a60728d8b4c200f50dd313c1b\
+             ab745879a5ad954a72c45a91c3a51d3c7adea98d82f8481e0e1e03674a6f3fb7",
+        ),
+        (
+            "778caa53b4393ac467774d09497a87224bf9fab6f6e68b23086497324d6fd117",
+            "",
+            "71535db165ecd9fbbc046e5ffaea61186bb6ad436732fccc25291a55895464cf\
+             6069ce26bf03466228f19a3a62db8a649f2d560fac652827d1af0574e427ab63",
+        ),
+    ];
+
+    /// Vector 5's key, an x coordinate with no point on the curve.
+    const OFF_CURVE: [u8; 32] = [
+        0xee, 0xfd, 0xea, 0x4c, 0xdb, 0x67, 0x77, 0x50, 0xa4, 0x20, 0xfe, 0xe8, 0x07, 0xea, 0xcf,
+        0x21, 0xeb, 0x98, 0x98, 0xae, 0x79, 0xb9, 0x76, 0x87, 0x66, 0xe4, 0xfa, 0xa0, 0x4a, 0x2d,
+        0x4a, 0x34,
+    ];
+
+    type Vector = ([u8; 32], Vec<u8>, [u8; 64]);
+
+    fn vectors() -> Vec<Vector> {
+        VECTORS
+            .iter()
+            .map(|(key, message, signature)| {
+                (
+                    hex::decode(key).unwrap().try_into().unwrap(),
+                    hex::decode(message).unwrap(),
+                    hex::decode(signature).unwrap().try_into().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn inputs(vectors: &[Vector]) -> Vec<SchnorrInput<'_>> {
+        vectors
+            .iter()
+            .map(|(public_key, message, signature)| SchnorrInput {
+                public_key,
+                message,
+                signature,
+            })
+            .collect()
+    }
+
+    /// The published vectors verify, and changing any part of one, or
+    /// putting an encoding out of range, makes it fail.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn single_verification_matches_bip_340() {
+        for (key, message, signature) in vectors() {
+            assert!(verify_schnorr(&key, &message, &signature));
+            let mut tampered = signature;
+            tampered[63] ^= 1;
+            assert!(!verify_schnorr(&key, &message, &tampered));
+            assert!(!verify_schnorr(&key, b"other", &signature));
+        }
+        let (key, message, signature) = &vectors()[1];
+        assert!(!verify_schnorr(&OFF_CURVE, message, signature));
+        let mut high_s = *signature;
+        high_s[32..].copy_from_slice(&N);
+        assert!(!verify_schnorr(key, message, &high_s));
+        let mut high_r = *signature;
+        high_r[..32].copy_from_slice(&P);
+        assert!(!verify_schnorr(key, message, &high_r));
+        assert!(!verify_schnorr(&P, message, signature));
+    }
+
+    /// A batch passes when every signature is valid, including repeats,
+    /// and fails when any one is not, even when two errors would cancel
+    /// without the randomizers.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn batches_fail_as_a_whole() {
+        let vectors = vectors();
+        assert!(verify_schnorr_batch(&[]));
+        assert!(verify_schnorr_batch(&inputs(&vectors)));
+        let repeated: Vec<Vector> = vectors.iter().cycle().take(6).cloned().collect();
+        assert!(verify_schnorr_batch(&inputs(&repeated)));
+
+        let mut bad = vectors.clone();
+        bad[2].1[0] ^= 1;
+        assert!(!verify_schnorr_batch(&inputs(&bad)));
+
+        // s_1 + 1 and s_2 - 1 pass the unweighted sum of the equations.
+        let mut cancelling = vectors.clone();
+        for (vector, delta) in cancelling.iter_mut().zip([Scalar::ONE, Scalar::ONE.neg()]) {
+            let s = Scalar::from_bytes(&vector.2[32..].try_into().unwrap())
+                .unwrap()
+                .add(&delta);
+            vector.2[32..].copy_from_slice(&s.to_bytes());
+        }
+        assert!(!verify_schnorr(
+            &cancelling[0].0,
+            &cancelling[0].1,
+            &cancelling[0].2
+        ));
+        assert!(!verify_schnorr_batch(&inputs(&cancelling)));
+
+        // An `r` with no point fails when the nonces are lifted.
+        let mut unliftable = vectors;
+        unliftable[3].2[..32].copy_from_slice(&OFF_CURVE);
+        assert!(!verify_schnorr_batch(&inputs(&unliftable)));
+    }
+}
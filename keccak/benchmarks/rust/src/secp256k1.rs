@@ -0,0 +1,901 @@
+//! secp256k1 domain parameters shared by the Rust baseline and the Mojo port.
+//!
+//! All multi-byte values are 32-byte big-endian encodings, matching the
+//! `make_bigint` literals in `secp256k1/sign.mojo`.
+//!
+//! [`Scalar`] has arithmetic modulo [`N`] and [`FieldElement`] modulo
+//! [`P`], and [`AffinePoint`] has the point multiplication public key
+//! recovery needs. Products are 4x64-bit limb multiplications reduced by
+//! folding, as both moduli are `2^256 - c` for a small `c`, and inverses
+//! and square roots are exponentiations. All of it runs in variable
+//! time, so it suits checking and analysing public signatures. The only
+//! signer built on it is the fixture one in `scenarios`, behind the
+//! `test-utils` feature, which must never see a real key.
+//!
+//! Values are always reduced: outside this module they are only made by
+//! the checked [`Scalar::from_bytes`] and [`FieldElement::from_bytes`],
+//! by [`Scalar::reduce`], or by arithmetic, so the modular operations
+//! never see an input at or above their modulus.
+
+use std::fmt;
+
+use zeroize::Zeroize;
+
+use crate::hex::impl_hex;
+use crate::Error;
+
+/// A field element modulo [`P`], encoded big-endian.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldElement([u8; 32]);
+
+/// A scalar modulo [`N`], encoded big-endian.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Scalar([u8; 32]);
+
+impl_hex!(FieldElement, self => self.0);
+impl_hex!(Scalar, self => self.0);
+
+impl fmt::Debug for FieldElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FieldElement({:#x})", self)
+    }
+}
+
+impl fmt::Debug for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Scalar({:#x})", self)
+    }
+}
+
+/// `a < m` for big-endian encodings, usable in constants.
+const fn below(a: &[u8; 32], m: &[u8; 32]) -> bool {
+    let mut i = 0;
+    while i < 32 {
+        if a[i] != m[i] {
+            return a[i] < m[i];
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Little-endian 64-bit limbs of a big-endian encoding.
+fn limbs(bytes: &[u8; 32]) -> [u64; 4] {
+    let mut limbs = [0u64; 4];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let start = 24 - 8 * i;
+        *limb = u64::from_be_bytes(bytes[start..start + 8].try_into().expect("8 bytes"));
+    }
+    limbs
+}
+
+fn encode(limbs: [u64; 4]) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (i, limb) in limbs.iter().enumerate() {
+        let start = 24 - 8 * i;
+        bytes[start..start + 8].copy_from_slice(&limb.to_be_bytes());
+    }
+    bytes
+}
+
+/// `a + b` and the carry out.
+fn add_limbs(a: [u64; 4], b: [u64; 4]) -> ([u64; 4], bool) {
+    let mut out = [0u64; 4];
+    let mut carry = false;
+    for i in 0..4 {
+        let (sum, c1) = a[i].overflowing_add(b[i]);
+        let (sum, c2) = sum.overflowing_add(u64::from(carry));
+        out[i] = sum;
+        carry = c1 || c2;
+    }
+    (out, carry)
+}
+
+/// `a - b` and the borrow out.
+fn sub_limbs(a: [u64; 4], b: [u64; 4]) -> ([u64; 4], bool) {
+    let mut out = [0u64; 4];
+    let mut borrow = false;
+    for i in 0..4 {
+        let (diff, b1) = a[i].overflowing_sub(b[i]);
+        let (diff, b2) = diff.overflowing_sub(u64::from(borrow));
+        out[i] = diff;
+        borrow = b1 || b2;
+    }
+    (out, borrow)
+}
+
+/// `a + b` modulo `m`; both must already be below `m`.
+fn add_mod(a: [u64; 4], b: [u64; 4], m: [u64; 4]) -> [u64; 4] {
+    let (sum, carry) = add_limbs(a, b);
+    let (reduced, borrow) = sub_limbs(sum, m);
+    if carry || !borrow {
+        reduced
+    } else {
+        sum
+    }
+}
+
+fn neg_mod(a: [u64; 4], m: [u64; 4]) -> [u64; 4] {
+    if a == [0; 4] {
+        return a;
+    }
+    sub_limbs(m, a).0
+}
+
+/// The full 512-bit product `a * b`, schoolbook over 64-bit limbs.
+fn mul_wide(a: [u64; 4], b: [u64; 4]) -> [u64; 8] {
+    let mut out = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let t = u128::from(a[i]) * u128::from(b[j]) + u128::from(out[i + j]) + carry;
+            out[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        out[i + 4] = carry as u64;
+    }
+    out
+}
+
+/// `wide` modulo `m`. Both moduli are `2^256 - c` with a small `c`
+/// (33 bits for `p`, 129 for `n`), so the high half folds down as
+/// `high * c`: each fold shrinks it by at least 127 bits, and a final
+/// subtraction brings the result below `m`.
+fn reduce_wide(mut wide: [u64; 8], m: [u64; 4]) -> [u64; 4] {
+    let c = sub_limbs([0; 4], m).0;
+    loop {
+        let low: [u64; 4] = wide[..4].try_into().expect("4 limbs");
+        let high: [u64; 4] = wide[4..].try_into().expect("4 limbs");
+        if high == [0; 4] {
+            let (reduced, borrow) = sub_limbs(low, m);
+            return if borrow { low } else { reduced };
+        }
+        let folded = mul_wide(high, c);
+        let mut carry = false;
+        for (i, limb) in folded.iter().enumerate() {
+            let (sum, c1) = limb.overflowing_add(if i < 4 { low[i] } else { 0 });
+            let (sum, c2) = sum.overflowing_add(u64::from(carry));
+            wide[i] = sum;
+            carry = c1 || c2;
+        }
+    }
+}
+
+/// `a * b` modulo `m`; both must already be below `m`.
+fn mul_mod(a: [u64; 4], b: [u64; 4], m: [u64; 4]) -> [u64; 4] {
+    reduce_wide(mul_wide(a, b), m)
+}
+
+/// `a^e` modulo `m`, by square-and-multiply over `e`'s bits.
+fn pow_mod(a: [u64; 4], e: [u64; 4], m: [u64; 4]) -> [u64; 4] {
+    let mut acc = [1, 0, 0, 0];
+    for bit in (0..256).rev() {
+        acc = mul_mod(acc, acc, m);
+        if (e[bit / 64] >> (bit % 64)) & 1 == 1 {
+            acc = mul_mod(acc, a, m);
+        }
+    }
+    acc
+}
+
+impl Zeroize for Scalar {
+    /// Overwrites the scalar with zero, itself a valid scalar.
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Scalar {
+    pub const ZERO: Scalar = Scalar([0; 32]);
+
+    pub const ONE: Scalar = Scalar([
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 1,
+    ]);
+
+    /// `bytes` as a scalar, or `None` when they encode `n` or more.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<Scalar> {
+        (*bytes < N).then_some(Scalar(*bytes))
+    }
+
+    /// A scalar the caller already knows is below `n`, such as one
+    /// selected from two scalars.
+    pub(crate) const fn from_reduced(bytes: [u8; 32]) -> Scalar {
+        debug_assert!(below(&bytes, &N), "not reduced modulo n");
+        Scalar(bytes)
+    }
+
+    /// The big-endian encoding.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// The encoding, for [`crate::secret::SecretKey`] to fill in place so
+    /// no copy of a key is left behind. The caller checks
+    /// [`is_reduced`](Self::is_reduced) before the value is used.
+    #[cfg(not(feature = "verify-only"))]
+    pub(crate) fn bytes_mut(&mut self) -> &mut [u8; 32] {
+        &mut self.0
+    }
+
+    /// Whether the value is below `n`, which only a scalar filled through
+    /// [`bytes_mut`](Self::bytes_mut) can fail.
+    #[cfg(not(feature = "verify-only"))]
+    pub(crate) fn is_reduced(&self) -> bool {
+        self.0 < N
+    }
+
+    /// `bytes` read as a big-endian number modulo `n`, as ECDSA reads a
+    /// 32-byte message hash.
+    pub fn reduce(bytes: &[u8; 32]) -> Scalar {
+        let (reduced, borrow) = sub_limbs(limbs(bytes), limbs(&N));
+        Scalar(if borrow { *bytes } else { encode(reduced) })
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 32]
+    }
+
+    /// `self + other` modulo `n`; both must already be below `n`.
+    pub fn add(&self, other: &Scalar) -> Scalar {
+        Scalar(encode(add_mod(limbs(&self.0), limbs(&other.0), limbs(&N))))
+    }
+
+    pub fn neg(&self) -> Scalar {
+        Scalar(encode(neg_mod(limbs(&self.0), limbs(&N))))
+    }
+
+    pub fn sub(&self, other: &Scalar) -> Scalar {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Scalar) -> Scalar {
+        Scalar(encode(mul_mod(limbs(&self.0), limbs(&other.0), limbs(&N))))
+    }
+
+    /// The multiplicative inverse, `self^(n - 2)`, or `None` for zero.
+    pub fn invert(&self) -> Option<Scalar> {
+        if self.is_zero() {
+            return None;
+        }
+        let exponent = sub_limbs(limbs(&N), [2, 0, 0, 0]).0;
+        Some(Scalar(encode(pow_mod(limbs(&self.0), exponent, limbs(&N)))))
+    }
+}
+
+impl FieldElement {
+    pub const ZERO: FieldElement = FieldElement([0; 32]);
+
+    pub const ONE: FieldElement = FieldElement(Scalar::ONE.0);
+
+    /// `bytes` as a field element, or `None` when they encode `p` or more.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<FieldElement> {
+        (*bytes < P).then_some(FieldElement(*bytes))
+    }
+
+    /// An element the caller already knows is below `p`.
+    pub(crate) const fn from_reduced(bytes: [u8; 32]) -> FieldElement {
+        debug_assert!(below(&bytes, &P), "not reduced modulo p");
+        FieldElement(bytes)
+    }
+
+    /// The big-endian encoding.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 32]
+    }
+
+    /// Whether the element, as an integer below `p`, is odd: the parity
+    /// compressed keys and BIP-340 keys encode for `y`.
+    pub fn is_odd(&self) -> bool {
+        self.0[31] & 1 == 1
+    }
+
+    /// `self + other` modulo `p`; both must already be below `p`.
+    pub fn add(&self, other: &FieldElement) -> FieldElement {
+        FieldElement(encode(add_mod(limbs(&self.0), limbs(&other.0), limbs(&P))))
+    }
+
+    pub fn neg(&self) -> FieldElement {
+        FieldElement(encode(neg_mod(limbs(&self.0), limbs(&P))))
+    }
+
+    pub fn sub(&self, other: &FieldElement) -> FieldElement {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &FieldElement) -> FieldElement {
+        FieldElement(encode(mul_mod(limbs(&self.0), limbs(&other.0), limbs(&P))))
+    }
+
+    pub fn square(&self) -> FieldElement {
+        self.mul(self)
+    }
+
+    /// The multiplicative inverse, `self^(p - 2)`, or `None` for zero.
+    pub fn invert(&self) -> Option<FieldElement> {
+        if self.is_zero() {
+            return None;
+        }
+        let exponent = sub_limbs(limbs(&P), [2, 0, 0, 0]).0;
+        Some(FieldElement(encode(pow_mod(
+            limbs(&self.0),
+            exponent,
+            limbs(&P),
+        ))))
+    }
+
+    /// A square root, `self^((p + 1) / 4)` as `p = 3 mod 4` allows, or
+    /// `None` when there is none. The other root is its negation.
+    pub fn sqrt(&self) -> Option<FieldElement> {
+        let [a, b, c, d] = add_limbs(limbs(&P), [1, 0, 0, 0]).0;
+        let exponent = [a >> 2 | b << 62, b >> 2 | c << 62, c >> 2 | d << 62, d >> 2];
+        let root = FieldElement(encode(pow_mod(limbs(&self.0), exponent, limbs(&P))));
+        (root.square() == *self).then_some(root)
+    }
+}
+
+/// An affine curve point with big-endian coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AffinePoint {
+    pub x: FieldElement,
+    pub y: FieldElement,
+}
+
+/// Field prime `p = 2^256 - 2^32 - 977`.
+pub const P: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xfc, 0x2f,
+];
+
+/// Order of the generator.
+pub const N: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Curve coefficient `b` in `y^2 = x^3 + b`.
+pub const B: FieldElement = FieldElement([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07,
+]);
+
+/// Cofactor of the curve group.
+pub const H: u32 = 1;
+
+/// Standard generator point.
+pub const G: AffinePoint = AffinePoint {
+    x: FieldElement([
+        0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b,
+        0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8,
+        0x17, 0x98,
+    ]),
+    y: FieldElement([
+        0x48, 0x3a, 0xda, 0x77, 0x26, 0xa3, 0xc4, 0x65, 0x5d, 0xa4, 0xfb, 0xfc, 0x0e, 0x11, 0x08,
+        0xa8, 0xfd, 0x17, 0xb4, 0x48, 0xa6, 0x85, 0x54, 0x19, 0x9c, 0x47, 0xd0, 0x8f, 0xfb, 0x10,
+        0xd4, 0xb8,
+    ]),
+};
+
+/// Cube root of unity modulo `p` used by the GLV endomorphism
+/// `(x, y) -> (beta * x, y)`.
+pub const BETA: FieldElement = FieldElement([
+    0x7a, 0xe9, 0x6a, 0x2b, 0x65, 0x7c, 0x07, 0x10, 0x6e, 0x64, 0x47, 0x9e, 0xac, 0x34, 0x34, 0xe9,
+    0x9c, 0xf0, 0x49, 0x75, 0x12, 0xf5, 0x89, 0x95, 0xc1, 0x39, 0x6c, 0x28, 0x71, 0x95, 0x01, 0xee,
+]);
+
+/// Cube root of unity modulo `n` paired with [`BETA`]: `lambda * P` equals
+/// the endomorphism applied to `P`.
+pub const LAMBDA: Scalar = Scalar([
+    0x53, 0x63, 0xad, 0x4c, 0xc0, 0x5c, 0x30, 0xe0, 0xa5, 0x26, 0x1c, 0x02, 0x88, 0x12, 0x64, 0x5a,
+    0x12, 0x2e, 0x22, 0xea, 0x20, 0x81, 0x66, 0x78, 0xdf, 0x02, 0x96, 0x7c, 0x1b, 0x23, 0xbd, 0x72,
+]);
+
+impl AffinePoint {
+    /// Whether the point satisfies `y^2 = x^3 + b`.
+    pub fn is_on_curve(&self) -> bool {
+        self.y.square() == self.x.square().mul(&self.x).add(&B)
+    }
+
+    /// The point with x coordinate `x` and a `y` of the given parity, or
+    /// `None` when `x^3 + b` has no square root.
+    pub fn lift_x(x: &FieldElement, odd: bool) -> Option<AffinePoint> {
+        let y = x.square().mul(x).add(&B).sqrt()?;
+        let y = if y.is_odd() == odd { y } else { y.neg() };
+        Some(AffinePoint { x: *x, y })
+    }
+
+    pub fn neg(&self) -> AffinePoint {
+        AffinePoint {
+            x: self.x,
+            y: self.y.neg(),
+        }
+    }
+
+    /// `k * self`, or `None` for the point at infinity. Multiples of [`G`]
+    /// use the shared [`crate::fixed_base::generator_table`].
+    pub fn mul(&self, k: &Scalar) -> Option<AffinePoint> {
+        if *self == G {
+            return crate::fixed_base::generator_table().mul(k);
+        }
+        double_mul(k, self, &Scalar::ZERO, self)
+    }
+
+    /// The SEC1 uncompressed encoding, `0x04 || x || y`.
+    pub fn to_uncompressed(&self) -> [u8; 65] {
+        let mut out = [0u8; 65];
+        out[0] = 0x04;
+        out[1..33].copy_from_slice(&self.x.0);
+        out[33..].copy_from_slice(&self.y.0);
+        out
+    }
+
+    /// The SEC1 compressed encoding, `0x02` or `0x03` for the parity of
+    /// `y`, then `x`.
+    pub fn to_compressed(&self) -> [u8; 33] {
+        let mut out = [0u8; 33];
+        out[0] = 0x02 | u8::from(self.y.is_odd());
+        out[1..].copy_from_slice(&self.x.0);
+        out
+    }
+
+    /// A point from its SEC1 encoding: 33 bytes compressed (`0x02` or
+    /// `0x03` and `x`) or 65 uncompressed (`0x04`, `x` and `y`), with the
+    /// coordinates below `p` and the point on the curve.
+    pub fn from_sec1(bytes: &[u8]) -> Result<AffinePoint, Error> {
+        let invalid = |reason| Error::InvalidPoint { reason };
+        let coordinate = |range: std::ops::Range<usize>| {
+            FieldElement::from_bytes(&bytes[range].try_into().expect("32 bytes"))
+                .ok_or(invalid("a coordinate is not below p"))
+        };
+        match (bytes.first(), bytes.len()) {
+            (Some(&prefix @ (0x02 | 0x03)), 33) => {
+                AffinePoint::lift_x(&coordinate(1..33)?, prefix == 0x03)
+                    .ok_or(invalid("no point has this x"))
+            }
+            (Some(0x04), 65) => {
+                let point = AffinePoint {
+                    x: coordinate(1..33)?,
+                    y: coordinate(33..65)?,
+                };
+                point
+                    .is_on_curve()
+                    .then_some(point)
+                    .ok_or(invalid("not on the curve"))
+            }
+            _ => Err(invalid("not 33 or 65 bytes with a matching prefix")),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for AffinePoint {
+    /// The compressed encoding as `0x`-prefixed hex.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::hex::serialize_hex(&self.to_compressed(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AffinePoint {
+    /// Only the 33-byte compressed encoding, of a point on the curve.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes: [u8; 33] = crate::hex::deserialize_hex(deserializer, "a compressed point")?;
+        AffinePoint::from_sec1(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+/// `a * p + b * q`, or `None` for the point at infinity, sharing one run
+/// of doublings between the two products.
+pub fn double_mul(a: &Scalar, p: &AffinePoint, b: &Scalar, q: &AffinePoint) -> Option<AffinePoint> {
+    let (p, q) = (Jacobian::from(p), Jacobian::from(q));
+    let sum = p.add(&q);
+    let mut acc = Jacobian::INFINITY;
+    for (&x, &y) in a.0.iter().zip(&b.0) {
+        for bit in (0..8).rev() {
+            acc = acc.double();
+            match ((x >> bit) & 1, (y >> bit) & 1) {
+                (1, 1) => acc = acc.add(&sum),
+                (1, 0) => acc = acc.add(&p),
+                (0, 1) => acc = acc.add(&q),
+                _ => {}
+            }
+        }
+    }
+    acc.to_affine()
+}
+
+/// A point in Jacobian coordinates, `(X / Z^2, Y / Z^3)`, so additions
+/// need no inversion. `Z = 0` is the point at infinity.
+#[derive(Clone, Copy)]
+pub(crate) struct Jacobian {
+    pub(crate) x: FieldElement,
+    pub(crate) y: FieldElement,
+    pub(crate) z: FieldElement,
+}
+
+impl From<&AffinePoint> for Jacobian {
+    fn from(point: &AffinePoint) -> Jacobian {
+        Jacobian {
+            x: point.x,
+            y: point.y,
+            z: FieldElement::ONE,
+        }
+    }
+}
+
+impl Jacobian {
+    pub(crate) const INFINITY: Jacobian = Jacobian {
+        x: FieldElement::ONE,
+        y: FieldElement::ONE,
+        z: FieldElement::ZERO,
+    };
+
+    pub(crate) fn double(&self) -> Jacobian {
+        if self.z.is_zero() || self.y.is_zero() {
+            return Jacobian::INFINITY;
+        }
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = b.square();
+        let d = self.x.add(&b).square().sub(&a).sub(&c);
+        let d = d.add(&d);
+        let e = a.add(&a).add(&a);
+        let x = e.square().sub(&d).sub(&d);
+        let c8 = c.add(&c);
+        let c8 = c8.add(&c8);
+        let c8 = c8.add(&c8);
+        let y = e.mul(&d.sub(&x)).sub(&c8);
+        let z = self.y.mul(&self.z);
+        Jacobian { x, y, z: z.add(&z) }
+    }
+
+    pub(crate) fn add(&self, other: &Jacobian) -> Jacobian {
+        if self.z.is_zero() {
+            return *other;
+        }
+        if other.z.is_zero() {
+            return *self;
+        }
+        let (z1z1, z2z2) = (self.z.square(), other.z.square());
+        let u1 = self.x.mul(&z2z2);
+        let u2 = other.x.mul(&z1z1);
+        let s1 = self.y.mul(&other.z).mul(&z2z2);
+        let s2 = other.y.mul(&self.z).mul(&z1z1);
+        if u1 == u2 {
+            return if s1 == s2 {
+                self.double()
+            } else {
+                Jacobian::INFINITY
+            };
+        }
+        let h = u2.sub(&u1);
+        let r = s2.sub(&s1);
+        let hh = h.square();
+        let hhh = hh.mul(&h);
+        let v = u1.mul(&hh);
+        let x = r.square().sub(&hhh).sub(&v).sub(&v);
+        let y = r.mul(&v.sub(&x)).sub(&s1.mul(&hhh));
+        Jacobian {
+            x,
+            y,
+            z: h.mul(&self.z).mul(&other.z),
+        }
+    }
+
+    pub(crate) fn to_affine(self) -> Option<AffinePoint> {
+        let z_inverse = self.z.invert()?;
+        let z2 = z_inverse.square();
+        Some(AffinePoint {
+            x: self.x.mul(&z2),
+            y: self.y.mul(&z2).mul(&z_inverse),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+
+    fn int(bytes: &[u8; 32]) -> BigUint {
+        BigUint::from_bytes_be(bytes)
+    }
+
+    /// Affine point arithmetic over `p`; `None` is the point at infinity.
+    type Point = Option<(BigUint, BigUint)>;
+
+    fn inv(value: &BigUint, p: &BigUint) -> BigUint {
+        value.modpow(&(p - 2u32), p)
+    }
+
+    fn add(a: &Point, b: &Point, p: &BigUint) -> Point {
+        let (x1, y1) = match a {
+            Some(point) => point,
+            None => return b.clone(),
+        };
+        let (x2, y2) = match b {
+            Some(point) => point,
+            None => return a.clone(),
+        };
+        let slope = if x1 == x2 {
+            if (y1 + y2) % p == BigUint::ZERO {
+                return None;
+            }
+            let num = BigUint::from(3u32) * x1 * x1 % p;
+            num * inv(&(BigUint::from(2u32) * y1 % p), p) % p
+        } else {
+            let num = (y2 + p - y1) % p;
+            num * inv(&((x2 + p - x1) % p), p) % p
+        };
+        let x3 = (&slope * &slope + p * 2u32 - x1 - x2) % p;
+        let y3 = (slope * ((x1 + p - &x3) % p) + p - y1) % p;
+        Some((x3, y3))
+    }
+
+    fn mul(k: &BigUint, point: &Point, p: &BigUint) -> Point {
+        let mut acc: Point = None;
+        for bit in (0..k.bits()).rev() {
+            acc = add(&acc, &acc, p);
+            if k.bit(bit) {
+                acc = add(&acc, point, p);
+            }
+        }
+        acc
+    }
+
+    fn generator() -> Point {
+        Some((int(&G.x.0), int(&G.y.0)))
+    }
+
+    /// `p` must match its documented special form.
+    #[test]
+    fn field_prime_has_expected_form() {
+        let expected = (BigUint::from(1u32) << 256u32) - (BigUint::from(1u32) << 32u32) - 977u32;
+        assert_eq!(int(&P), expected);
+        assert!(int(&N) < int(&P));
+    }
+
+    /// The generator must satisfy `y^2 = x^3 + 7`.
+    #[test]
+    fn generator_is_on_curve() {
+        let p = int(&P);
+        let x = int(&G.x.0);
+        let y = int(&G.y.0);
+        assert_eq!(&y * &y % &p, (&x * &x * &x + int(&B.0)) % &p);
+    }
+
+    /// `beta` is a non-trivial cube root of unity modulo `p`.
+    #[test]
+    fn beta_is_cube_root_of_unity() {
+        let p = int(&P);
+        let beta = int(&BETA.0);
+        assert_ne!(beta, BigUint::from(1u32));
+        assert_eq!(beta.modpow(&BigUint::from(3u32), &p), BigUint::from(1u32));
+    }
+
+    /// `lambda` is a non-trivial cube root of unity modulo `n`.
+    #[test]
+    fn lambda_is_cube_root_of_unity() {
+        let n = int(&N);
+        let lambda = int(&LAMBDA.0);
+        assert_ne!(lambda, BigUint::from(1u32));
+        assert_eq!(lambda.modpow(&BigUint::from(3u32), &n), BigUint::from(1u32));
+    }
+
+    /// Scalar arithmetic agrees with big integers modulo `n`, including
+    /// at the edges of the range.
+    #[test]
+    fn scalar_arithmetic_matches_big_integers() {
+        let n = int(&N);
+        let scalar = |value: &BigUint| {
+            let bytes = value.to_bytes_be();
+            let mut out = [0u8; 32];
+            out[32 - bytes.len()..].copy_from_slice(&bytes);
+            Scalar(out)
+        };
+        let mut values = vec![
+            BigUint::ZERO,
+            BigUint::from(1u32),
+            BigUint::from(2u32),
+            &n - 1u32,
+            &n - 2u32,
+            &n >> 1u32,
+            int(&LAMBDA.0),
+        ];
+        let mut state = BigUint::from(0x1234_5678u32);
+        for _ in 0..8 {
+            state = (&state * &state + 0x9e37_79b9u32) % &n;
+            values.push(state.clone());
+        }
+        for a in &values {
+            let x = scalar(a);
+            assert_eq!(int(&x.neg().0), (&n - a) % &n);
+            match x.invert() {
+                Some(inverse) => assert_eq!(int(&x.mul(&inverse).0), BigUint::from(1u32)),
+                None => assert!(x.is_zero()),
+            }
+            for b in &values {
+                let y = scalar(b);
+                assert_eq!(int(&x.add(&y).0), (a + b) % &n);
+                assert_eq!(int(&x.sub(&y).0), (a + &n - b) % &n);
+                assert_eq!(int(&x.mul(&y).0), a * b % &n);
+            }
+        }
+        assert_eq!(Scalar::reduce(&N), Scalar::ZERO);
+        assert_eq!(
+            Scalar::reduce(&[0xff; 32]),
+            scalar(&(int(&[0xff; 32]) - &n))
+        );
+        assert_eq!(Scalar::reduce(&LAMBDA.0), LAMBDA);
+        assert_eq!(Scalar::from_bytes(&LAMBDA.0), Some(LAMBDA));
+        assert_eq!(Scalar::from_bytes(&N), None);
+        assert_eq!(FieldElement::from_bytes(&BETA.0), Some(BETA));
+        assert_eq!(FieldElement::from_bytes(&P), None);
+        assert_eq!(FieldElement::from_bytes(&[0xff; 32]), None);
+    }
+
+    /// Field products and inverses agree with big integers modulo `p`,
+    /// including operands whose product needs more than one fold.
+    #[test]
+    fn field_multiplication_matches_big_integers() {
+        let p = int(&P);
+        let element = |value: &BigUint| {
+            let bytes = value.to_bytes_be();
+            let mut out = [0u8; 32];
+            out[32 - bytes.len()..].copy_from_slice(&bytes);
+            FieldElement(out)
+        };
+        let mut values = vec![
+            BigUint::from(1u32),
+            &p - 1u32,
+            &p - 2u32,
+            BigUint::from(1u32) << 255u32,
+            (BigUint::from(1u32) << 256u32) - &p,
+            int(&BETA.0),
+        ];
+        let mut state = BigUint::from(0x8765_4321u32);
+        for _ in 0..8 {
+            state = (&state * &state + 0x7f4a_7c15u32) % &p;
+            values.push(state.clone());
+        }
+        for a in &values {
+            let x = element(a);
+            assert_eq!(int(&x.invert().unwrap().mul(&x).0), BigUint::from(1u32));
+            for b in &values {
+                assert_eq!(int(&x.mul(&element(b)).0), a * b % &p);
+            }
+        }
+    }
+
+    /// Field arithmetic and point multiplication agree with the big
+    /// integer reference, and lifting an x coordinate finds the point.
+    #[test]
+    #[cfg_attr(miri, ignore = "thousands of field inversions")]
+    fn points_match_big_integers() {
+        let p = int(&P);
+        let affine = |point: &Point| {
+            point.as_ref().map(|(x, y)| {
+                let coordinate = |value: &BigUint| {
+                    let bytes = value.to_bytes_be();
+                    let mut out = [0u8; 32];
+                    out[32 - bytes.len()..].copy_from_slice(&bytes);
+                    FieldElement(out)
+                };
+                AffinePoint {
+                    x: coordinate(x),
+                    y: coordinate(y),
+                }
+            })
+        };
+        let x = G.x;
+        let inverse = x.invert().unwrap();
+        assert_eq!(x.mul(&inverse), FieldElement::ONE);
+        assert_eq!(int(&x.sub(&G.y).0), (int(&x.0) + &p - int(&G.y.0)) % &p);
+        assert_eq!(
+            FieldElement::ONE.neg().add(&FieldElement::ONE),
+            FieldElement::ZERO
+        );
+        assert!(G.is_on_curve() && !AffinePoint { x, y: x }.is_on_curve());
+        assert_eq!(AffinePoint::lift_x(&G.x, G.y.is_odd()), Some(G));
+        assert_eq!(AffinePoint::lift_x(&G.x, !G.y.is_odd()), Some(G.neg()));
+        // 5^3 + 7 = 132 is not a square modulo p.
+        let mut five = [0u8; 32];
+        five[31] = 5;
+        assert_eq!(AffinePoint::lift_x(&FieldElement(five), false), None);
+        for encoded in [
+            &G.to_compressed()[..],
+            &G.neg().to_compressed(),
+            &G.to_uncompressed(),
+        ] {
+            let point = AffinePoint::from_sec1(encoded).unwrap();
+            assert!(point == G || point == G.neg());
+        }
+        let mut off_curve = G.to_uncompressed();
+        off_curve[64] ^= 1;
+        let mut no_x = [0x02; 33];
+        no_x[1..].copy_from_slice(&five);
+        for bad in [&off_curve[..], &no_x, &G.to_uncompressed()[..33], &[]] {
+            assert!(matches!(
+                AffinePoint::from_sec1(bad),
+                Err(Error::InvalidPoint { .. })
+            ));
+        }
+
+        let scalars = [
+            Scalar::ONE,
+            Scalar::ONE.add(&Scalar::ONE),
+            LAMBDA,
+            Scalar::ONE.neg(),
+            Scalar([0x5a; 32]),
+        ];
+        for k in &scalars {
+            let expected = mul(&int(&k.0), &generator(), &p);
+            assert_eq!(G.mul(k), affine(&expected), "{:?}", k);
+            let point = G.mul(k).unwrap();
+            assert!(point.is_on_curve());
+            let other = Scalar([0x33; 32]);
+            assert_eq!(
+                double_mul(&other, &G, k, &point),
+                affine(&mul(
+                    &(int(&other.0) + int(&k.0) * int(&k.0)),
+                    &generator(),
+                    &p
+                ))
+            );
+        }
+        assert_eq!(G.mul(&Scalar::ZERO), None);
+        assert_eq!(double_mul(&Scalar::ONE, &G, &Scalar::ONE, &G.neg()), None);
+        assert_eq!(G.to_uncompressed()[1..33], G.x.0);
+        assert_eq!(G.to_compressed()[..], [&[0x02], &G.x.0[..]].concat());
+        assert_eq!(G.neg().to_compressed()[0], 0x03);
+    }
+
+    /// `n * G` is the point at infinity, so `n` is the generator's order.
+    #[test]
+    #[cfg_attr(miri, ignore = "hundreds of big-integer inversions; hours under Miri")]
+    fn generator_has_order_n() {
+        let p = int(&P);
+        assert_eq!(H, 1);
+        assert_eq!(mul(&int(&N), &generator(), &p), None);
+        assert!(mul(&(int(&N) - 1u32), &generator(), &p).is_some());
+    }
+
+    /// `lambda * G` equals `(beta * Gx, Gy)`, tying the two constants together.
+    #[test]
+    #[cfg_attr(miri, ignore = "hundreds of big-integer inversions; hours under Miri")]
+    fn lambda_and_beta_describe_the_same_endomorphism() {
+        let p = int(&P);
+        let expected = Some((int(&BETA.0) * int(&G.x.0) % &p, int(&G.y.0)));
+        assert_eq!(mul(&int(&LAMBDA.0), &generator(), &p), expected);
+    }
+
+    /// Points serialize as compressed hex and read back only from that
+    /// form, and only when on the curve.
+    #[test]
+    #[cfg(feature = "serde")]
+    #[cfg_attr(miri, ignore = "square roots modulo p")]
+    fn points_serialize_as_compressed_hex() {
+        let json = serde_json::to_string(&G).unwrap();
+        assert_eq!(json, format!("\"0x{}\"", hex::encode(G.to_compressed())));
+        assert_eq!(serde_json::from_str::<AffinePoint>(&json).unwrap(), G);
+        let read = |text: String| serde_json::from_value::<AffinePoint>(text.into());
+        assert_eq!(read(hex::encode(G.neg().to_compressed())).unwrap(), G.neg());
+        let mut no_x = [0u8; 33];
+        no_x[0] = 0x02;
+        no_x[32] = 5;
+        for bad in [
+            hex::encode(G.to_uncompressed()),
+            hex::encode(&G.to_compressed()[..32]),
+            hex::encode(no_x),
+            format!("04{}", &hex::encode(G.to_compressed())[2..]),
+            String::from("0xzz"),
+        ] {
+            assert!(read(bad.clone()).is_err(), "{}", bad);
+        }
+        assert!(serde_json::from_str::<AffinePoint>("2").is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! A secp256k1 secret key that wipes itself.
+//!
+//! [`SecretKey`] holds its scalar where a drop overwrites it with zeros,
+//! through `zeroize` so the compiler cannot skip the dead store. It is
+//! not `Clone` or `Copy`, its `Debug` prints no key material, and hex
+//! that fails to parse is not echoed into the error. Keys parsed from hex
+//! or drawn by `rng::ShakeDrbg::secret_key` are written straight into
+//! the key's own bytes and checked there. What it cannot do is
+//! vouch for copies made before it took the key, or for the `Scalar`
+//! temporaries of the variable-time arithmetic it is handed to: it
+//! shortens the key's life in memory, no more.
+
+use std::fmt;
+
+use zeroize::Zeroize;
+
+use crate::secp256k1::{AffinePoint, Scalar, G};
+use crate::Error;
+
+/// A secret key in `[1, n)`, zeroed on drop.
+pub struct SecretKey(Scalar);
+
+impl SecretKey {
+    /// Takes `scalar` as the key if it is in `[1, n)`. The caller's copy
+    /// is its own to wipe.
+    pub fn new(scalar: Scalar) -> Result<SecretKey, Error> {
+        if scalar.is_zero() {
+            return Err(Error::InvalidSecretKey {
+                reason: "not in [1, n)",
+            });
+        }
+        Ok(SecretKey(scalar))
+    }
+
+    /// A key whose bytes `fill` writes in place, then checked there. On
+    /// any error the key is dropped, which wipes whatever was written.
+    pub(crate) fn fill(
+        fill: impl FnOnce(&mut [u8; 32]) -> Result<(), Error>,
+    ) -> Result<SecretKey, Error> {
+        let mut key = SecretKey(Scalar::ZERO);
+        fill(key.0.bytes_mut())?;
+        if key.0.is_zero() || !key.0.is_reduced() {
+            return Err(Error::InvalidSecretKey {
+                reason: "not in [1, n)",
+            });
+        }
+        Ok(key)
+    }
+
+    /// A key from 64 hex digits, with an optional `0x` and surrounding
+    /// whitespace, as key files and environment variables hold it.
+    pub fn from_hex(text: &str) -> Result<SecretKey, Error> {
+        let text = text.trim();
+        let digits = text.strip_prefix("0x").unwrap_or(text);
+        SecretKey::fill(|bytes| {
+            hex::decode_to_slice(digits, bytes).map_err(|_| Error::InvalidSecretKey {
+                reason: "not 64 hex digits",
+            })
+        })
+    }
+
+    /// The key, for the signing and derivation functions that take a
+    /// `&Scalar`. Copying it out defeats the wiping.
+    pub fn expose(&self) -> &Scalar {
+        &self.0
+    }
+
+    pub fn public_key(&self) -> AffinePoint {
+        G.mul(&self.0).expect("keys are nonzero")
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::N;
+
+    /// Keys parse with or without `0x` and whitespace, out-of-range keys
+    /// are refused, and neither errors nor `Debug` show the digits.
+    #[test]
+    fn parses_without_echoing_the_key() {
+        let hex = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+        let key = SecretKey::from_hex(&format!(" 0x{}\n", hex)).unwrap();
+        assert_eq!(hex::encode(key.expose().to_bytes()), hex);
+        assert_eq!(format!("{:?}", key), "SecretKey(..)");
+
+        let bad = SecretKey::from_hex(&hex[..63]).unwrap_err();
+        assert!(!bad.to_string().contains(&hex[..8]));
+        assert_eq!(
+            SecretKey::from_hex(&"0".repeat(64)).unwrap_err(),
+            Error::InvalidSecretKey {
+                reason: "not in [1, n)"
+            }
+        );
+        assert!(SecretKey::new(Scalar::ZERO).is_err());
+        assert!(SecretKey::from_hex(&hex::encode(N)).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+//! The HTTP differential-testing service behind the `service` binary.
+//!
+//! Implementations running on other machines POST hex JSON to the Rust
+//! baseline and compare answers. Routes:
+//!
+//! - `POST /keccak256` with `{"message": "<hex>"}` answers
+//!   `{"digest": "<hex>"}`; `{"messages": [...]}` answers `{"digests": [...]}`.
+//! - `POST /verify` with `{"hash": "<32 bytes>", "signature": "<r || s ||
+//!   v, 65 bytes>", "address": "<20 bytes>"}` recovers the signer, as
+//!   `ecrecover` does, and answers `{"valid": bool, "recovered":
+//!   "<checksummed address>"}`, or `{"valid": false, "reason": "..."}`
+//!   when no key recovers.
+//! - `POST /sign` with `{"hash": "<32 bytes>", "key": "<test key name>"}`
+//!   or `{"hash": ..., "private_key": "<32 bytes>"}` signs with the
+//!   fixture signer and answers `{"signature": "<r || s || v>",
+//!   "address": "<checksummed address>"}`, `v` being 27 or 28. Names are
+//!   those of `test_keys`. The signer runs in variable time and is only
+//!   built with `test-utils`; without it the route answers 501. Fixture
+//!   keys only: a key sent here is as good as published.
+//! - `GET /health` answers `{"status": "ok"}`.
+//!
+//! Hex may carry a `0x` prefix. Errors are `{"error": "..."}` with a 4xx or
+//! 5xx status. Connections are HTTP/1.1 and stay open until the client
+//! sends `Connection: close` or hangs up.
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+
+use serde_json::{json, Value};
+
+use crate::ethereum::{checksum_address, recover_address};
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+use crate::secp256k1::{Scalar, G};
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+use crate::{ethereum::address, scenarios, test_keys};
+use crate::{keccak256, to_hex_string};
+
+/// Largest request body accepted, in bytes.
+pub const MAX_BODY: usize = 16 << 20;
+
+/// A response: status code and JSON body.
+pub type Response = (u16, Value);
+
+fn error(status: u16, message: impl Into<String>) -> Response {
+    (status, json!({ "error": message.into() }))
+}
+
+fn decode(field: &str, value: &Value) -> Result<Vec<u8>, Response> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| error(400, format!("{} must be a hex string", field)))?;
+    hex::decode(text.strip_prefix("0x").unwrap_or(text))
+        .map_err(|err| error(400, format!("{} is not hex: {}", field, err)))
+}
+
+/// `body[field]` decoded to exactly `N` bytes.
+fn decode_fixed<const N: usize>(body: &Value, field: &str) -> Result<[u8; N], Response> {
+    let value = body
+        .get(field)
+        .ok_or_else(|| error(400, format!("missing field {}", field)))?;
+    let bytes = decode(field, value)?;
+    bytes.as_slice().try_into().map_err(|_| {
+        error(
+            400,
+            format!("{} must be {} bytes, not {}", field, N, bytes.len()),
+        )
+    })
+}
+
+fn verify(body: &Value) -> Result<Value, Response> {
+    let hash = decode_fixed::<32>(body, "hash")?;
+    let signature = decode_fixed::<65>(body, "signature")?;
+    let address = decode_fixed::<20>(body, "address")?;
+    Ok(match recover_address(&hash, &signature) {
+        Ok(recovered) => json!({
+            "valid": recovered == address,
+            "recovered": checksum_address(&recovered),
+        }),
+        Err(err) => json!({ "valid": false, "reason": err.to_string() }),
+    })
+}
+
+/// The fixture signer over `hash`, as a named test key or with a
+/// 32-byte `private_key`.
+#[cfg(all(feature = "test-utils", not(feature = "verify-only")))]
+fn sign(body: &Value) -> Result<Value, Response> {
+    let hash = decode_fixed::<32>(body, "hash")?;
+    let key = match (body.get("key"), body.get("private_key")) {
+        (Some(name), None) => {
+            let name = name
+                .as_str()
+                .ok_or_else(|| error(400, "key must be a test key name"))?;
+            test_keys::get(name)
+                .ok_or_else(|| {
+                    let names = test_keys::NAMES.join(", ");
+                    error(400, format!("no test key {:?}; try one of {}", name, names))
+                })?
+                .key
+        }
+        (None, Some(_)) => Scalar::from_bytes(&decode_fixed::<32>(body, "private_key")?)
+            .filter(|key| !key.is_zero())
+            .ok_or_else(|| error(400, "private_key must be in [1, n)"))?,
+        _ => return Err(error(400, "expected one of key or private_key")),
+    };
+    let (signature, odd) = scenarios::sign(&key, &hash);
+    let mut signed = [0u8; 65];
+    signed[..64].copy_from_slice(&signature.to_compact());
+    signed[64] = 27 + u8::from(odd);
+    let public_key = G.mul(&key).expect("keys are nonzero").to_uncompressed();
+    Ok(json!({
+        "signature": to_hex_string(&signed),
+        "address": checksum_address(&address(&public_key).expect("65 bytes")),
+    }))
+}
+
+#[cfg(not(all(feature = "test-utils", not(feature = "verify-only"))))]
+fn sign(_: &Value) -> Result<Value, Response> {
+    Err(error(
+        501,
+        "this build has no signer; run the service with --features test-utils",
+    ))
+}
+
+/// Parse `body` as JSON and answer it with `route`.
+fn json_route(body: &[u8], route: fn(&Value) -> Result<Value, Response>) -> Response {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(body) => route(&body).map_or_else(|err| err, |value| (200, value)),
+        Err(err) => error(400, format!("body is not JSON: {}", err)),
+    }
+}
+
+fn keccak(body: &Value) -> Result<Value, Response> {
+    if let Some(message) = body.get("message") {
+        let digest = keccak256(&decode("message", message)?);
+        return Ok(json!({ "digest": to_hex_string(&digest) }));
+    }
+    let messages = body
+        .get("messages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| error(400, "expected {\"message\": hex} or {\"messages\": [hex]}"))?;
+    let digests = messages
+        .iter()
+        .enumerate()
+        .map(|(index, message)| {
+            decode(&format!("messages[{}]", index), message)
+                .map(|message| Value::from(to_hex_string(&keccak256(&message))))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(json!({ "digests": digests }))
+}
+
+/// Route one request.
+pub fn handle(method: &str, path: &str, body: &[u8]) -> Response {
+    let path = path.split('?').next().unwrap_or(path);
+    match (method, path) {
+        ("GET", "/health") => (200, json!({ "status": "ok" })),
+        ("POST", "/keccak256") => json_route(body, keccak),
+        ("POST", "/verify") => json_route(body, verify),
+        ("POST", "/sign") => json_route(body, sign),
+        (_, "/health" | "/keccak256" | "/sign" | "/verify") => {
+            error(405, format!("{} is not allowed on {}", method, path))
+        }
+        _ => error(404, format!("no route for {}", path)),
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        501 => "Not Implemented",
+        _ => "Error",
+    }
+}
+
+fn write_response(
+    writer: &mut impl Write,
+    (status, body): &Response,
+    close: bool,
+) -> io::Result<()> {
+    let body = body.to_string();
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\r\n{}",
+        status,
+        reason(*status),
+        body.len(),
+        if close { "Connection: close\r\n" } else { "" },
+        body
+    )?;
+    writer.flush()
+}
+
+/// Serve HTTP/1.1 requests from `reader` on `writer` until the client
+/// closes the connection or asks to. Returns the number of requests served.
+pub fn serve(reader: impl Read, mut writer: impl Write) -> io::Result<usize> {
+    let mut reader = BufReader::new(reader);
+    let mut served = 0;
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line)? == 0 {
+            return Ok(served);
+        }
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(path), Some(version)) = (parts.next(), parts.next(), parts.next())
+        else {
+            write_response(&mut writer, &error(400, "malformed request line"), true)?;
+            return Ok(served);
+        };
+        let (method, path) = (method.to_string(), path.to_string());
+        let mut close = version == "HTTP/1.0";
+        let mut length: Option<usize> = None;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "headers cut short",
+                ));
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.parse().ok();
+            } else if name.eq_ignore_ascii_case("connection") {
+                close = value.eq_ignore_ascii_case("close");
+            }
+        }
+        let length = match (length, method.as_str()) {
+            (Some(length), _) if length > MAX_BODY => {
+                let limit = format!("bodies are limited to {} bytes", MAX_BODY);
+                write_response(&mut writer, &error(413, limit), true)?;
+                return Ok(served);
+            }
+            (Some(length), _) => length,
+            (None, "POST") => {
+                write_response(
+                    &mut writer,
+                    &error(411, "POST needs a Content-Length"),
+                    true,
+                )?;
+                return Ok(served);
+            }
+            (None, _) => 0,
+        };
+        let mut body = vec![0u8; length];
+        reader.read_exact(&mut body)?;
+        write_response(&mut writer, &handle(&method, &path, &body), close)?;
+        served += 1;
+        if close {
+            return Ok(served);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Routes answer hex JSON, and bad input is a client error.
+    #[test]
+    fn routes_requests() {
+        let abc = "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45";
+        assert_eq!(
+            handle("POST", "/keccak256", br#"{"message": "0x616263"}"#),
+            (200, json!({ "digest": abc }))
+        );
+        let (status, body) = handle("POST", "/keccak256", br#"{"messages": ["616263", ""]}"#);
+        assert_eq!(status, 200);
+        assert_eq!(body["digests"][0], abc);
+        assert_eq!(handle("POST", "/keccak256", br#"{"message": "zz"}"#).0, 400);
+        assert_eq!(handle("POST", "/keccak256", b"not json").0, 400);
+        assert_eq!(handle("GET", "/keccak256", b"").0, 405);
+        assert_eq!(handle("GET", "/health?probe=1", b"").0, 200);
+        assert_eq!(handle("GET", "/nope", b"").0, 404);
+    }
+
+    /// `/verify` recovers a scenario signature's signer, and reports a
+    /// wrong address or an unrecoverable signature as invalid.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn verifies_by_recovery() {
+        let hash = "1e65933ff0f459c1bc85e75b2501a6a6ea48508b044871f4c61302b4fe858141";
+        let signature = "192d4e05ce9dda59fde77b1a858fa69f53d8fac34b9de9f16e037a0e9a58267c\
+                         5f15d4c063799c07840960d9c5e19f2ddaf9510f06795876af3b1b8225d58c94\
+                         01";
+        let signer = "0x05A4D2f180D02BDfB1ac52E4017f607617DbB245";
+        let request = |address: &str, signature: &str| {
+            let body = json!({ "hash": hash, "signature": signature, "address": address });
+            handle("POST", "/verify", body.to_string().as_bytes())
+        };
+        assert_eq!(
+            request(signer, signature),
+            (200, json!({ "valid": true, "recovered": signer }))
+        );
+        let other = "0x0000000000000000000000000000000000000001";
+        assert_eq!(request(other, signature).1["valid"], false);
+        let (status, body) = request(signer, &format!("{}05", &signature[..128]));
+        assert_eq!((status, &body["valid"]), (200, &json!(false)));
+        assert!(body["reason"].is_string());
+        assert_eq!(request(signer, &signature[..128]).0, 400);
+        assert_eq!(handle("POST", "/verify", b"{}").0, 400);
+    }
+
+    /// `/sign` reproduces a scenario signature from its key, signs as a
+    /// named test key in a way `/verify` accepts, and refuses unknown
+    /// names and ambiguous requests.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn signs_with_fixture_keys() {
+        let hash = "1e65933ff0f459c1bc85e75b2501a6a6ea48508b044871f4c61302b4fe858141";
+        let sign = |body: Value| handle("POST", "/sign", body.to_string().as_bytes());
+        // `m/44'/60'/0'/0/0` of the `single-signer` scenario.
+        let private_key = "5d14bc98f6b22117d468c32b7f0d6a5e8acdebd95c4ee5e78b5ae28277597178";
+        assert_eq!(
+            sign(json!({ "hash": hash, "private_key": private_key })),
+            (
+                200,
+                json!({
+                    "signature": "192d4e05ce9dda59fde77b1a858fa69f53d8fac34b9de9f16e037a0e9a58267c\
+                                  5f15d4c063799c07840960d9c5e19f2ddaf9510f06795876af3b1b8225d58c94\
+                                  1c",
+                    "address": "0x05A4D2f180D02BDfB1ac52E4017f607617DbB245",
+                })
+            )
+        );
+
+        let (status, signed) = sign(json!({ "hash": hash, "key": "bob" }));
+        assert_eq!(status, 200);
+        assert_eq!(
+            signed["address"],
+            "0xAF12B3d3764D192294E7347661dDBC1D06307f83"
+        );
+        let body = json!({
+            "hash": hash,
+            "signature": signed["signature"],
+            "address": signed["address"],
+        });
+        assert_eq!(
+            handle("POST", "/verify", body.to_string().as_bytes()).1["valid"],
+            true
+        );
+
+        for bad in [
+            json!({ "hash": hash, "key": "trent" }),
+            json!({ "hash": hash, "key": "bob", "private_key": private_key }),
+            json!({ "hash": hash, "private_key": "00".repeat(32) }),
+            json!({ "hash": hash }),
+            json!({ "key": "bob" }),
+        ] {
+            assert_eq!(sign(bad.clone()).0, 400, "{}", bad);
+        }
+    }
+
+    /// Keep-alive requests share a connection until one asks to close.
+    #[test]
+    fn serves_http_connections() {
+        let body = r#"{"message": "616263"}"#;
+        let input = format!(
+            "POST /keccak256 HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}\
+             GET /health HTTP/1.1\r\nConnection: close\r\n\r\n\
+             GET /health HTTP/1.1\r\n\r\n",
+            body.len(),
+            body
+        );
+        let mut output = Vec::new();
+        assert_eq!(serve(input.as_bytes(), &mut output).unwrap(), 2);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("HTTP/1.1 200 OK\r\n"), "{}", output);
+        assert!(output.contains("4e03657a"));
+        assert!(output.ends_with("Connection: close\r\n\r\n{\"status\":\"ok\"}"));
+
+        let mut output = Vec::new();
+        serve(&b"POST /keccak256 HTTP/1.1\r\n\r\n"[..], &mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("HTTP/1.1 411"));
+    }
+}
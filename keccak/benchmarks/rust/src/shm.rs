@@ -0,0 +1,522 @@
+//! The shared-memory digest protocol served by `bench shm` (Linux).
+//!
+//! The Mojo harness creates a POSIX shared-memory object, writes a batch of
+//! messages into it, and flips the header's state; this side hashes the
+//! messages in place and writes the digests back into the same region, so
+//! a correctness run moves no bytes through pipes or encoders. All
+//! integers are little-endian.
+//!
+//! ```text
+//! header (64 bytes):
+//!    0 u32 magic "KSHM"        4 u32 version
+//!    8 u32 state               12 u32 count
+//!   16 u64 data_len            24 u32 error
+//!   28..64 reserved
+//! body, from offset 64:
+//!   count × u64 message lengths | data_len message bytes | count × 32-byte digests
+//! ```
+//!
+//! The writer fills the body, sets `count` and `data_len`, then stores
+//! [`REQUEST`] into `state` with release ordering. The server answers
+//! with [`DONE`], or [`FAILED`] and an [`ERROR_LAYOUT`]-style code in
+//! `error`, and the writer may then reuse the region. [`SHUTDOWN`] stops
+//! the server.
+//!
+//! Builds without the mapping (off Linux, or with `safe-only`) keep the
+//! body layout half of the protocol, which needs no `unsafe`.
+
+#![cfg_attr(
+    not(all(target_os = "linux", not(feature = "safe-only"))),
+    allow(dead_code, unused_imports)
+)]
+
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use crate::bench::backend::Backend;
+
+pub const MAGIC: u32 = u32::from_le_bytes(*b"KSHM");
+pub const VERSION: u32 = 1;
+pub const HEADER_LEN: usize = 64;
+
+pub const IDLE: u32 = 0;
+pub const REQUEST: u32 = 1;
+pub const DONE: u32 = 2;
+pub const FAILED: u32 = 3;
+pub const SHUTDOWN: u32 = 4;
+
+/// The lengths, messages, and digests do not fit in the region.
+pub const ERROR_LAYOUT: u32 = 1;
+
+const STATE: usize = 8;
+const COUNT: usize = 12;
+const DATA_LEN: usize = 16;
+const ERROR: usize = 24;
+
+/// Where each part of a request's body sits, relative to the body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub count: usize,
+    pub data: usize,
+    pub digests: usize,
+    pub end: usize,
+}
+
+impl Layout {
+    /// The layout of `count` messages totalling `data_len` bytes, or `None`
+    /// when it needs more than `body_len` bytes.
+    pub fn new(count: u32, data_len: u64, body_len: usize) -> Option<Layout> {
+        let count = count as usize;
+        let data = count.checked_mul(8)?;
+        let digests = data.checked_add(usize::try_from(data_len).ok()?)?;
+        let end = digests.checked_add(count.checked_mul(32)?)?;
+        (end <= body_len).then_some(Layout {
+            count,
+            data,
+            digests,
+            end,
+        })
+    }
+}
+
+/// Hash the request in `body` and write its digests, or return the error
+/// code to report. `body` is the region after the header.
+pub fn answer(body: &mut [u8], layout: Layout, backend: Backend) -> Result<(), u32> {
+    let (head, digests) = body[..layout.end].split_at_mut(layout.digests);
+    let (lengths, data) = head.split_at(layout.data);
+    let mut offset = 0usize;
+    for (length, digest) in lengths.chunks_exact(8).zip(digests.chunks_exact_mut(32)) {
+        let length = u64::from_le_bytes(length.try_into().expect("8-byte chunk"));
+        let end = usize::try_from(length)
+            .ok()
+            .and_then(|length| offset.checked_add(length))
+            .filter(|&end| end <= data.len())
+            .ok_or(ERROR_LAYOUT)?;
+        digest.copy_from_slice(&backend.hash(&data[offset..end]));
+        offset = end;
+    }
+    if offset != data.len() {
+        return Err(ERROR_LAYOUT);
+    }
+    Ok(())
+}
+
+/// Lay out `messages` for a request in `body`, returning its count and
+/// data length. Used by tests and Rust-side writers.
+pub fn write_request(body: &mut [u8], messages: &[&[u8]]) -> Result<(u32, u64), String> {
+    let count = u32::try_from(messages.len()).map_err(|_| "too many messages")?;
+    let data_len: usize = messages.iter().map(|message| message.len()).sum();
+    let layout = Layout::new(count, data_len as u64, body.len())
+        .ok_or_else(|| format!("{} messages of {} bytes do not fit", count, data_len))?;
+    let (lengths, data) = body[..layout.digests].split_at_mut(layout.data);
+    let mut offset = 0;
+    for (message, length) in messages.iter().zip(lengths.chunks_exact_mut(8)) {
+        length.copy_from_slice(&(message.len() as u64).to_le_bytes());
+        data[offset..offset + message.len()].copy_from_slice(message);
+        offset += message.len();
+    }
+    Ok((count, data_len as u64))
+}
+
+#[cfg(all(target_os = "linux", not(feature = "safe-only")))]
+mod imp {
+    use std::ffi::CString;
+    use std::ptr;
+    use std::slice;
+    use std::sync::atomic::AtomicU32;
+
+    use super::{HEADER_LEN, MAGIC, VERSION};
+
+    /// A read-write shared mapping of a whole POSIX shared-memory object.
+    pub struct Region {
+        pub name: String,
+        data: *mut u8,
+        len: usize,
+    }
+
+    // Access to the mapping goes through the header's atomic state, which
+    // hands the body back and forth between the two sides.
+    unsafe impl Send for Region {}
+    unsafe impl Sync for Region {}
+
+    fn object_name(name: &str) -> Result<CString, String> {
+        let name = if name.starts_with('/') {
+            name.to_string()
+        } else {
+            format!("/{}", name)
+        };
+        CString::new(name).map_err(|_| String::from("shared-memory name contains a NUL byte"))
+    }
+
+    fn os_error(name: &str, call: &str) -> String {
+        format!(
+            "{}: {} failed: {}",
+            name,
+            call,
+            std::io::Error::last_os_error()
+        )
+    }
+
+    impl Region {
+        /// Map the existing object `name` and check its header.
+        pub fn open(name: &str) -> Result<Region, String> {
+            let region = Region::map(name, None)?;
+            if region.read_u32(0) != MAGIC {
+                return Err(format!("{}: not a KSHM region", name));
+            }
+            if region.read_u32(4) != VERSION {
+                return Err(format!(
+                    "{}: unsupported KSHM version {}",
+                    name,
+                    region.read_u32(4)
+                ));
+            }
+            Ok(region)
+        }
+
+        /// Create the object `name` with `len` bytes and an idle header.
+        /// The object stays until [`Region::unlink`].
+        pub fn create(name: &str, len: usize) -> Result<Region, String> {
+            if len < HEADER_LEN {
+                return Err(format!(
+                    "{}: a region needs at least {} bytes",
+                    name, HEADER_LEN
+                ));
+            }
+            let region = Region::map(name, Some(len))?;
+            region.write_u32(0, MAGIC);
+            region.write_u32(4, VERSION);
+            Ok(region)
+        }
+
+        /// Remove the object `name`; existing mappings stay valid.
+        pub fn unlink(name: &str) -> Result<(), String> {
+            let object = object_name(name)?;
+            // SAFETY: shm_unlink only reads the NUL-terminated name.
+            if unsafe { libc::shm_unlink(object.as_ptr()) } != 0 {
+                return Err(os_error(name, "shm_unlink"));
+            }
+            Ok(())
+        }
+
+        fn map(name: &str, create: Option<usize>) -> Result<Region, String> {
+            let object = object_name(name)?;
+            let flags = match create {
+                Some(_) => libc::O_RDWR | libc::O_CREAT | libc::O_EXCL,
+                None => libc::O_RDWR,
+            };
+            // SAFETY: shm_open only reads the NUL-terminated name.
+            let fd = unsafe { libc::shm_open(object.as_ptr(), flags, 0o600) };
+            if fd < 0 {
+                return Err(os_error(name, "shm_open"));
+            }
+            let len = match create {
+                // SAFETY: sizing the object we just created.
+                Some(len) if unsafe { libc::ftruncate(fd, len as libc::off_t) } != 0 => {
+                    let err = os_error(name, "ftruncate");
+                    unsafe { libc::close(fd) };
+                    return Err(err);
+                }
+                Some(len) => len,
+                None => {
+                    // SAFETY: fstat fills the zeroed struct it is given.
+                    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+                    if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+                        let err = os_error(name, "fstat");
+                        unsafe { libc::close(fd) };
+                        return Err(err);
+                    }
+                    stat.st_size as usize
+                }
+            };
+            if len < HEADER_LEN {
+                unsafe { libc::close(fd) };
+                return Err(format!("{}: {} bytes is too small for a header", name, len));
+            }
+            // SAFETY: a fresh shared mapping of an open object; the fd may
+            // be closed once the mapping exists.
+            let data = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    fd,
+                    0,
+                )
+            };
+            unsafe { libc::close(fd) };
+            if data == libc::MAP_FAILED {
+                return Err(os_error(name, "mmap"));
+            }
+            Ok(Region {
+                name: name.to_string(),
+                data: data as *mut u8,
+                len,
+            })
+        }
+
+        pub fn read_u32(&self, offset: usize) -> u32 {
+            assert!(offset + 4 <= HEADER_LEN);
+            // SAFETY: inside the mapped header.
+            u32::from_le(unsafe { ptr::read_unaligned(self.data.add(offset) as *const u32) })
+        }
+
+        pub fn read_u64(&self, offset: usize) -> u64 {
+            assert!(offset + 8 <= HEADER_LEN);
+            // SAFETY: inside the mapped header.
+            u64::from_le(unsafe { ptr::read_unaligned(self.data.add(offset) as *const u64) })
+        }
+
+        pub fn write_u32(&self, offset: usize, value: u32) {
+            assert!(offset + 4 <= HEADER_LEN);
+            // SAFETY: inside the mapped header.
+            unsafe { ptr::write_unaligned(self.data.add(offset) as *mut u32, value.to_le()) }
+        }
+
+        pub fn write_u64(&self, offset: usize, value: u64) {
+            assert!(offset + 8 <= HEADER_LEN);
+            // SAFETY: inside the mapped header.
+            unsafe { ptr::write_unaligned(self.data.add(offset) as *mut u64, value.to_le()) }
+        }
+
+        /// The header's state word.
+        pub fn state(&self) -> &AtomicU32 {
+            // SAFETY: the mapping is page-aligned, so offset 8 is aligned
+            // for a u32, and it lives as long as `self`.
+            unsafe { AtomicU32::from_ptr(self.data.add(super::STATE) as *mut u32) }
+        }
+
+        /// The region after the header.
+        ///
+        /// # Safety
+        ///
+        /// The caller must own the body under the state protocol: no other
+        /// reference to it may be live in this process, and the other side
+        /// must not be writing it.
+        #[allow(clippy::mut_from_ref)]
+        pub unsafe fn body(&self) -> &mut [u8] {
+            slice::from_raw_parts_mut(self.data.add(HEADER_LEN), self.len - HEADER_LEN)
+        }
+    }
+
+    impl Drop for Region {
+        fn drop(&mut self) {
+            // SAFETY: unmapping the region mapped in `map`.
+            unsafe {
+                libc::munmap(self.data as *mut libc::c_void, self.len);
+            }
+        }
+    }
+}
+
+#[cfg(not(all(target_os = "linux", not(feature = "safe-only"))))]
+mod imp {
+    use std::sync::atomic::AtomicU32;
+
+    pub struct Region {
+        pub name: String,
+    }
+
+    impl Region {
+        pub fn open(_name: &str) -> Result<Region, String> {
+            Err(String::from(
+                "shared-memory runs require Linux and a build without safe-only",
+            ))
+        }
+
+        pub fn create(_name: &str, _len: usize) -> Result<Region, String> {
+            Err(String::from(
+                "shared-memory runs require Linux and a build without safe-only",
+            ))
+        }
+
+        pub fn unlink(_name: &str) -> Result<(), String> {
+            Err(String::from(
+                "shared-memory runs require Linux and a build without safe-only",
+            ))
+        }
+
+        pub fn read_u32(&self, _offset: usize) -> u32 {
+            unreachable!("regions are never opened in this build")
+        }
+
+        pub fn read_u64(&self, _offset: usize) -> u64 {
+            unreachable!("regions are never opened in this build")
+        }
+
+        pub fn write_u32(&self, _offset: usize, _value: u32) {
+            unreachable!("regions are never opened in this build")
+        }
+
+        pub fn write_u64(&self, _offset: usize, _value: u64) {
+            unreachable!("regions are never opened in this build")
+        }
+
+        pub fn state(&self) -> &AtomicU32 {
+            unreachable!("regions are never opened in this build")
+        }
+    }
+}
+
+/// A mapped shared-memory region; see [`Region::open`].
+pub use imp::Region;
+
+/// Wait for the state to leave [`IDLE`], [`DONE`], and [`FAILED`]:
+/// spinning at first, then yielding, then sleeping briefly.
+#[cfg(all(target_os = "linux", not(feature = "safe-only")))]
+fn wait_for_work(region: &Region) -> u32 {
+    let mut polls = 0u32;
+    loop {
+        let state = region.state().load(Ordering::Acquire);
+        if !matches!(state, IDLE | DONE | FAILED) {
+            return state;
+        }
+        polls = polls.saturating_add(1);
+        match polls {
+            0..=1_000 => std::hint::spin_loop(),
+            1_001..=10_000 => thread::yield_now(),
+            _ => thread::sleep(Duration::from_micros(50)),
+        }
+    }
+}
+
+/// Answer requests in `region` with `backend` until the writer asks for
+/// [`SHUTDOWN`], returning the number of requests served.
+#[cfg(all(target_os = "linux", not(feature = "safe-only")))]
+pub fn serve(region: &Region, backend: Backend) -> Result<u64, String> {
+    let mut served = 0;
+    loop {
+        match wait_for_work(region) {
+            REQUEST => {
+                let count = region.read_u32(COUNT);
+                let data_len = region.read_u64(DATA_LEN);
+                let result = {
+                    // SAFETY: in the REQUEST state the writer has handed the
+                    // body over until we store DONE or FAILED, and the slice
+                    // is dropped before we do.
+                    let body = unsafe { region.body() };
+                    Layout::new(count, data_len, body.len())
+                        .ok_or(ERROR_LAYOUT)
+                        .and_then(|layout| answer(body, layout, backend))
+                };
+                let state = match result {
+                    Ok(()) => {
+                        region.write_u32(ERROR, 0);
+                        DONE
+                    }
+                    Err(code) => {
+                        region.write_u32(ERROR, code);
+                        FAILED
+                    }
+                };
+                region.state().store(state, Ordering::Release);
+                served += 1;
+            }
+            SHUTDOWN => return Ok(served),
+            state => return Err(format!("{}: unknown state {}", region.name, state)),
+        }
+    }
+}
+
+/// Submit `messages` through `region` and wait for their digests: the
+/// writer's side of the protocol.
+#[cfg(all(target_os = "linux", not(feature = "safe-only")))]
+pub fn request(region: &Region, messages: &[&[u8]]) -> Result<Vec<[u8; 32]>, String> {
+    let (count, data_len) = {
+        // SAFETY: outside REQUEST the writer owns the body. The slice is
+        // dropped before the body is handed to the server.
+        let body = unsafe { region.body() };
+        write_request(body, messages)?
+    };
+    region.write_u32(COUNT, count);
+    region.write_u64(DATA_LEN, data_len);
+    region.state().store(REQUEST, Ordering::Release);
+    loop {
+        match region.state().load(Ordering::Acquire) {
+            REQUEST => thread::yield_now(),
+            DONE => break,
+            FAILED => {
+                return Err(format!(
+                    "server failed with error {}",
+                    region.read_u32(ERROR)
+                ))
+            }
+            state => return Err(format!("unexpected state {}", state)),
+        }
+    }
+    // SAFETY: after DONE the server has dropped its slice and handed the
+    // body back.
+    let body = unsafe { region.body() };
+    let layout = Layout::new(count, data_len, body.len()).expect("written layout fits");
+    Ok(body[layout.digests..layout.end]
+        .chunks_exact(32)
+        .map(|digest| digest.try_into().expect("32-byte chunk"))
+        .collect())
+}
+
+/// Regions never open in this build, so there is nothing to serve.
+#[cfg(not(all(target_os = "linux", not(feature = "safe-only"))))]
+pub fn serve(_region: &Region, _backend: Backend) -> Result<u64, String> {
+    unreachable!("regions are never opened in this build")
+}
+
+#[cfg(not(all(target_os = "linux", not(feature = "safe-only"))))]
+pub fn request(_region: &Region, _messages: &[&[u8]]) -> Result<Vec<[u8; 32]>, String> {
+    unreachable!("regions are never opened in this build")
+}
+
+/// Tell the server behind `region` to stop.
+pub fn shutdown(region: &Region) {
+    region.state().store(SHUTDOWN, Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Digests land after the messages, and lengths that disagree with the
+    /// data are rejected instead of read past.
+    #[test]
+    fn answers_requests_in_place() {
+        let mut body = vec![0u8; 256];
+        let messages: [&[u8]; 3] = [b"abc", b"", b"hello"];
+        let (count, data_len) = write_request(&mut body, &messages).unwrap();
+        let layout = Layout::new(count, data_len, body.len()).unwrap();
+        answer(&mut body, layout, Backend::Native).unwrap();
+        for (index, message) in messages.iter().enumerate() {
+            let at = layout.digests + index * 32;
+            assert_eq!(body[at..at + 32], crate::keccak256(message));
+        }
+        let short = Layout::new(count, data_len - 1, body.len()).unwrap();
+        assert_eq!(answer(&mut body, short, Backend::Native), Err(ERROR_LAYOUT));
+        assert_eq!(Layout::new(4, 200, 256), None);
+        assert!(write_request(&mut body[..40], &messages).is_err());
+    }
+
+    /// A server thread answers requests through a real shared-memory
+    /// object until shut down.
+    #[cfg(all(target_os = "linux", not(feature = "safe-only")))]
+    #[test]
+    fn serves_through_shared_memory() {
+        let name = format!("keccak-bench-shm-{}", std::process::id());
+        let writer = Region::create(&name, 4096).unwrap();
+        let server = thread::spawn({
+            let name = name.clone();
+            move || serve(&Region::open(&name).unwrap(), Backend::Native)
+        });
+        for round in 0..3u8 {
+            let message = vec![round; 100 * round as usize];
+            let digests = request(&writer, &[&message, b"abc"]).unwrap();
+            assert_eq!(
+                digests,
+                [crate::keccak256(&message), crate::keccak256(b"abc")]
+            );
+        }
+        Region::unlink(&name).unwrap();
+        assert!(request(&writer, &[&[0u8; 5000]]).is_err());
+        shutdown(&writer);
+        assert_eq!(server.join().unwrap(), Ok(3));
+    }
+}
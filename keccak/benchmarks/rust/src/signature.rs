@@ -0,0 +1,480 @@
+//! ECDSA signature encodings and how strictly to parse them.
+//!
+//! A DER signature is `30 len 02 len r 02 len s`, with `r` and `s` as
+//! minimal big-endian two's-complement integers. Chains disagree on what
+//! else to accept, so [`Signature::from_der`] takes a [`Strictness`]:
+//! Bitcoin's BIP-66 consensus rules accept only that exact form, while
+//! signatures from before it (and OpenSSL) also used long-form lengths,
+//! padded or "negative" integers and a trailing sighash byte. Either way
+//! `r` and `s` must be in `[1, n)`. Whether `s` must also be low (BIP-146,
+//! EIP-2) is policy on top of both, checked with [`Signature::is_low_s`].
+//!
+//! [`Signature::recover`] finds the public key that made a signature and
+//! [`Signature::verify`] checks one against a known key, both in variable
+//! time. The corpus in `keccak/vectors/signatures/der.json` pins
+//! what each DER level accepts.
+//!
+//! With the `serde` feature a [`Signature`] is 64 bytes of `r || s` hex,
+//! and `recoverable` gives Ethereum's 65-byte `r || s || v` for a
+//! signature and its parity.
+
+use crate::hex::impl_hex;
+use crate::secp256k1::{double_mul, AffinePoint, FieldElement, Scalar, G};
+use crate::Error;
+
+/// `n / 2`, the largest low `s`.
+const HALF_N: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Which encodings [`Signature::from_der`] accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Strictness {
+    /// Strict DER as BIP-66 defines it: short-form lengths, minimal
+    /// non-negative integers, 8 to 72 bytes and nothing after.
+    Consensus,
+    /// Also long-form lengths, integers with excess zero bytes or the top
+    /// bit set (read as unsigned), and bytes after the sequence.
+    Lenient,
+}
+
+/// An ECDSA signature with `r` and `s` in `[1, n)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Signature {
+    pub r: Scalar,
+    pub s: Scalar,
+}
+
+impl_hex!(Signature, self => self.to_compact());
+
+impl Signature {
+    /// A signature from its scalars, which must be in `[1, n)`.
+    pub fn new(r: Scalar, s: Scalar) -> Result<Signature, Error> {
+        for (what, value) in [("r", &r), ("s", &s)] {
+            if value.is_zero() {
+                return Err(Error::ScalarOutOfRange { what });
+            }
+        }
+        Ok(Signature { r, s })
+    }
+
+    /// A signature from `r || s`, 32 bytes each, as Ethereum and BIP-340
+    /// style APIs pass them.
+    pub fn from_compact(bytes: &[u8; 64]) -> Result<Signature, Error> {
+        let half = |what, range: std::ops::Range<usize>| {
+            Scalar::from_bytes(&bytes[range].try_into().expect("32"))
+                .ok_or(Error::ScalarOutOfRange { what })
+        };
+        Signature::new(half("r", 0..32)?, half("s", 32..64)?)
+    }
+
+    pub fn to_compact(&self) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        out[..32].copy_from_slice(&self.r.to_bytes());
+        out[32..].copy_from_slice(&self.s.to_bytes());
+        out
+    }
+
+    /// A signature from its DER encoding, accepted at `strictness`.
+    pub fn from_der(der: &[u8], strictness: Strictness) -> Result<Signature, Error> {
+        let strict = strictness == Strictness::Consensus;
+        if strict && !(8..=72).contains(&der.len()) {
+            return Err(invalid("strict DER signatures are 8 to 72 bytes"));
+        }
+        if der.first() != Some(&0x30) {
+            return Err(invalid("expected a SEQUENCE"));
+        }
+        let (len, start) = length(der, 1, strict)?;
+        let end = start
+            .checked_add(len)
+            .filter(|&end| end <= der.len())
+            .ok_or_else(|| invalid("the sequence overruns the input"))?;
+        if strict && end < der.len() {
+            return Err(invalid("bytes after the sequence"));
+        }
+        let body = &der[..end];
+        let (r, next) = integer(body, start, strict, "r")?;
+        let (s, next) = integer(body, next, strict, "s")?;
+        if next != end {
+            return Err(invalid("bytes after s inside the sequence"));
+        }
+        Signature::new(r, s)
+    }
+
+    /// The strict DER encoding, which every [`Strictness`] accepts.
+    pub fn to_der(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(70);
+        for value in [&self.r, &self.s] {
+            let zeros = value
+                .to_bytes()
+                .iter()
+                .take_while(|&&b| b == 0)
+                .count()
+                .min(31);
+            let digits = &value.to_bytes()[zeros..];
+            let pad = digits[0] & 0x80 != 0;
+            body.push(0x02);
+            body.push((digits.len() + usize::from(pad)) as u8);
+            if pad {
+                body.push(0);
+            }
+            body.extend_from_slice(digits);
+        }
+        let mut out = vec![0x30, body.len() as u8];
+        out.extend_from_slice(&body);
+        out
+    }
+
+    /// Whether `s` is at most `n / 2`, as BIP-146 and EIP-2 require.
+    pub fn is_low_s(&self) -> bool {
+        self.s.to_bytes() <= HALF_N
+    }
+
+    /// The public key that signed the 32-byte `hash`, given the parity of
+    /// the nonce point's `y`, as Ethereum's `v` and Bitcoin's recovery
+    /// byte carry it. Only `r` itself is tried as the nonce point's `x`;
+    /// `r + n` is a valid field element with negligible probability.
+    pub fn recover(&self, hash: &[u8; 32], odd: bool) -> Result<AffinePoint, Error> {
+        let nonce_point = AffinePoint::lift_x(&FieldElement::from_reduced(self.r.to_bytes()), odd)
+            .ok_or(Error::Unrecoverable)?;
+        let r_inverse = self.r.invert().expect("r is nonzero");
+        let z = Scalar::reduce(hash);
+        double_mul(
+            &z.neg().mul(&r_inverse),
+            &G,
+            &self.s.mul(&r_inverse),
+            &nonce_point,
+        )
+        .ok_or(Error::Unrecoverable)
+    }
+
+    /// Whether this signs the 32-byte `hash` for `public_key`: the x
+    /// coordinate of `(z / s) * G + (r / s) * public_key`, reduced modulo
+    /// `n`, equals `r`. Both `s` and `n - s` pass, so low-s policy is
+    /// [`Signature::is_low_s`]'s job.
+    pub fn verify(&self, hash: &[u8; 32], public_key: &AffinePoint) -> bool {
+        let s_inverse = self.s.invert().expect("s is nonzero");
+        let z = Scalar::reduce(hash);
+        double_mul(&z.mul(&s_inverse), &G, &self.r.mul(&s_inverse), public_key)
+            .is_some_and(|point| Scalar::reduce(&point.x.to_bytes()) == self.r)
+    }
+
+    /// The same signature with `s` replaced by `n - s` when it is high;
+    /// both verify, and this is the one low-s rules accept.
+    pub fn normalize_s(&self) -> Signature {
+        if self.is_low_s() {
+            return *self;
+        }
+        Signature {
+            r: self.r,
+            s: self.s.neg(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Signature {
+    /// `r || s` as `0x`-prefixed hex.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::hex::serialize_hex(&self.to_compact(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Signature {
+    /// 64 bytes of `r || s`, both in `[1, n)`.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = crate::hex::deserialize_hex(deserializer, "an r || s signature")?;
+        Signature::from_compact(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serde functions for a `(Signature, bool)` field, the signature and the
+/// parity of its nonce point's `y`, as Ethereum's 65-byte `r || s || v`
+/// hex: `#[serde(with = "keccak256_rust_baseline::signature::recoverable")]`.
+/// `v` is written as 27 or 28 and read as 0, 1, 27 or 28, as
+/// [`crate::ethereum::recover_address`] reads it, and `s` must be low.
+#[cfg(feature = "serde")]
+pub mod recoverable {
+    use super::Signature;
+    use crate::Error;
+
+    pub fn serialize<S: serde::Serializer>(
+        signature: &(Signature, bool),
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut bytes = [0u8; 65];
+        bytes[..64].copy_from_slice(&signature.0.to_compact());
+        bytes[64] = 27 + u8::from(signature.1);
+        crate::hex::serialize_hex(&bytes, serializer)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<(Signature, bool), D::Error> {
+        use serde::de::Error as _;
+
+        let bytes: [u8; 65] =
+            crate::hex::deserialize_hex(deserializer, "an r || s || v signature")?;
+        let odd = match bytes[64] {
+            v @ (0 | 1) => v == 1,
+            v @ (27 | 28) => v == 28,
+            v => return Err(D::Error::custom(Error::InvalidRecoveryId { v })),
+        };
+        let signature = Signature::from_compact(bytes[..64].try_into().expect("64 bytes"))
+            .map_err(D::Error::custom)?;
+        if !signature.is_low_s() {
+            return Err(D::Error::custom(Error::HighS));
+        }
+        Ok((signature, odd))
+    }
+}
+
+fn invalid(reason: &'static str) -> Error {
+    Error::InvalidDer { reason }
+}
+
+/// The DER length at `pos` and the index after it. Strict parsing takes
+/// only the short form; lenient parsing also takes up to four length
+/// bytes, non-minimal or not, but not BER's indefinite form.
+fn length(der: &[u8], pos: usize, strict: bool) -> Result<(usize, usize), Error> {
+    let first = *der.get(pos).ok_or_else(|| invalid("truncated length"))?;
+    if first < 0x80 {
+        return Ok((usize::from(first), pos + 1));
+    }
+    if strict {
+        return Err(invalid("long-form lengths are not strict DER"));
+    }
+    let count = usize::from(first & 0x7f);
+    if count == 0 || count > 4 {
+        return Err(invalid("unsupported length form"));
+    }
+    let bytes = der
+        .get(pos + 1..pos + 1 + count)
+        .ok_or_else(|| invalid("truncated length"))?;
+    let len = bytes
+        .iter()
+        .fold(0usize, |len, &byte| len << 8 | usize::from(byte));
+    Ok((len, pos + 1 + count))
+}
+
+/// The INTEGER at `pos` as a 32-byte scalar encoding, and the index after
+/// it. `what` names it in errors.
+fn integer(
+    der: &[u8],
+    pos: usize,
+    strict: bool,
+    what: &'static str,
+) -> Result<(Scalar, usize), Error> {
+    if der.get(pos) != Some(&0x02) {
+        return Err(invalid("expected an INTEGER"));
+    }
+    let (len, start) = length(der, pos + 1, strict)?;
+    let content = start
+        .checked_add(len)
+        .and_then(|end| der.get(start..end))
+        .ok_or_else(|| invalid("an integer overruns the sequence"))?;
+    match content {
+        [] => return Err(invalid("empty integer")),
+        [first, ..] if strict && first & 0x80 != 0 => {
+            return Err(invalid("negative integer"));
+        }
+        [0, second, ..] if strict && second & 0x80 == 0 => {
+            return Err(invalid("excess zero padding"));
+        }
+        _ => {}
+    }
+    let digits = &content[content.iter().take_while(|&&b| b == 0).count()..];
+    if digits.len() > 32 {
+        return Err(Error::ScalarOutOfRange { what });
+    }
+    let mut scalar = [0u8; 32];
+    scalar[32 - digits.len()..].copy_from_slice(digits);
+    let scalar = Scalar::from_bytes(&scalar).ok_or(Error::ScalarOutOfRange { what })?;
+    Ok((scalar, start + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::N;
+    use serde_json::Value;
+
+    fn bytes(text: &str) -> Vec<u8> {
+        crate::from_hex(text).unwrap()
+    }
+
+    /// Every corpus encoding is accepted exactly at the levels it lists,
+    /// with the listed scalars, and strict ones re-encode byte for byte.
+    #[test]
+    fn corpus_strictness_matches() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../vectors/signatures/der.json"
+        );
+        let corpus: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(corpus["schema_version"], 1);
+        for case in corpus["vectors"].as_array().unwrap() {
+            let name = case["name"].as_str().unwrap();
+            let der = bytes(case["der"].as_str().unwrap());
+            for (level, key) in [
+                (Strictness::Consensus, "consensus"),
+                (Strictness::Lenient, "lenient"),
+            ] {
+                let parsed = Signature::from_der(&der, level);
+                assert_eq!(
+                    parsed.is_ok(),
+                    case[key].as_bool().unwrap(),
+                    "{} at {:?}: {:?}",
+                    name,
+                    level,
+                    parsed
+                );
+                let Ok(signature) = parsed else { continue };
+                assert_eq!(
+                    signature.r.to_bytes()[..],
+                    bytes(case["r"].as_str().unwrap()),
+                    "{}",
+                    name
+                );
+                assert_eq!(
+                    signature.s.to_bytes()[..],
+                    bytes(case["s"].as_str().unwrap()),
+                    "{}",
+                    name
+                );
+                assert_eq!(
+                    signature.is_low_s(),
+                    case["low_s"].as_bool().unwrap(),
+                    "{}",
+                    name
+                );
+                if level == Strictness::Consensus {
+                    assert_eq!(signature.to_der(), der, "{}", name);
+                }
+            }
+        }
+    }
+
+    /// Compact form round-trips and range-checks, and normalizing a high
+    /// `s` gives `n - s`, which is low.
+    #[test]
+    fn compact_and_low_s() {
+        let mut compact = [0x11u8; 64];
+        let signature = Signature::from_compact(&compact).unwrap();
+        assert_eq!(signature.to_compact(), compact);
+        assert_eq!(
+            Signature::from_der(&signature.to_der(), Strictness::Consensus),
+            Ok(signature)
+        );
+        compact[32..].copy_from_slice(&N);
+        assert_eq!(
+            Signature::from_compact(&compact),
+            Err(Error::ScalarOutOfRange { what: "s" })
+        );
+        assert!(Signature::from_compact(&[0; 64]).is_err());
+
+        let high = Signature::new(
+            Scalar::from_bytes(&[0x22; 32]).unwrap(),
+            Scalar::from_bytes(&[0xee; 32]).unwrap(),
+        )
+        .unwrap();
+        assert!(!high.is_low_s());
+        let low = high.normalize_s();
+        assert!(low.is_low_s() && low.r == high.r);
+        assert_eq!(low.normalize_s(), low);
+        let n = num_bigint::BigUint::from_bytes_be(&N);
+        let sum = num_bigint::BigUint::from_bytes_be(&low.s.to_bytes())
+            + num_bigint::BigUint::from_bytes_be(&high.s.to_bytes());
+        assert_eq!(sum, n);
+        let half = Signature::new(
+            Scalar::from_bytes(&[0x22; 32]).unwrap(),
+            Scalar::from_bytes(&HALF_N).unwrap(),
+        )
+        .unwrap();
+        assert!(half.is_low_s());
+    }
+
+    /// A scenario transaction's signature verifies for its signer, with
+    /// either `s`, and not for another hash or key.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn verifies_against_the_signer() {
+        let hash: [u8; 32] =
+            bytes("1e65933ff0f459c1bc85e75b2501a6a6ea48508b044871f4c61302b4fe858141")
+                .try_into()
+                .unwrap();
+        let signature = Signature::from_compact(
+            &bytes(
+                "192d4e05ce9dda59fde77b1a858fa69f53d8fac34b9de9f16e037a0e9a58267c\
+                 5f15d4c063799c07840960d9c5e19f2ddaf9510f06795876af3b1b8225d58c94",
+            )
+            .try_into()
+            .unwrap(),
+        )
+        .unwrap();
+        let key = bytes(
+            "3c2ec18bacfbaa5d98919c99139b864f68524fe6bcf50df9897eca97c5c92c8f\
+             f5320f0bc510a2b4db5d60f4aaa6403a5f42112a5bdd3c94007fe82bddf5f776",
+        );
+        let key = AffinePoint {
+            x: FieldElement::from_bytes(&key[..32].try_into().unwrap()).unwrap(),
+            y: FieldElement::from_bytes(&key[32..].try_into().unwrap()).unwrap(),
+        };
+        assert_eq!(signature.recover(&hash, true), Ok(key));
+        assert!(signature.verify(&hash, &key));
+        let high = Signature::new(signature.r, signature.s.neg()).unwrap();
+        assert!(high.verify(&hash, &key));
+        let mut other = hash;
+        other[0] ^= 1;
+        assert!(!signature.verify(&other, &key));
+        assert!(!signature.verify(&hash, &G));
+    }
+
+    /// Signatures serialize as 64 bytes of hex, or 65 with `v` through
+    /// [`recoverable`], and reading refuses out-of-range scalars, bad
+    /// recovery ids and, for the 65-byte form, high `s`.
+    #[test]
+    #[cfg(feature = "serde")]
+    fn signatures_serialize_as_hex() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Signed {
+            #[serde(with = "recoverable")]
+            signature: (Signature, bool),
+        }
+
+        let compact = "192d4e05ce9dda59fde77b1a858fa69f53d8fac34b9de9f16e037a0e9a58267c\
+                       5f15d4c063799c07840960d9c5e19f2ddaf9510f06795876af3b1b8225d58c94";
+        let signature =
+            Signature::from_compact(&hex::decode(compact).unwrap().try_into().unwrap()).unwrap();
+        let json = serde_json::to_value(signature).unwrap();
+        assert_eq!(json, format!("0x{}", compact));
+        assert_eq!(
+            serde_json::from_value::<Signature>(json).unwrap(),
+            signature
+        );
+        let zero_r = format!("{}{}", "00".repeat(32), &compact[64..]);
+        assert!(serde_json::from_value::<Signature>(zero_r.into()).is_err());
+        assert!(serde_json::from_value::<Signature>(compact[..126].into()).is_err());
+
+        let signed = Signed {
+            signature: (signature, true),
+        };
+        let json = serde_json::to_value(&signed).unwrap();
+        assert_eq!(json["signature"], format!("0x{}1c", compact));
+        assert_eq!(serde_json::from_value::<Signed>(json).unwrap(), signed);
+        let read = |v: &str, compact: &str| {
+            let json = serde_json::json!({ "signature": format!("{}{}", compact, v) });
+            serde_json::from_value::<Signed>(json).map(|signed| signed.signature)
+        };
+        assert_eq!(read("00", compact).unwrap(), (signature, false));
+        assert_eq!(read("1b", compact).unwrap(), (signature, false));
+        assert!(read("02", compact).is_err());
+        let high_s = Signature {
+            r: signature.r,
+            s: signature.s.neg(),
+        };
+        assert!(read("1c", &hex::encode(high_s.to_compact())).is_err());
+    }
+}
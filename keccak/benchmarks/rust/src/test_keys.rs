@@ -0,0 +1,156 @@
+//! Named test identities, so tests, benchmarks and the Mojo conformance
+//! suite all sign as the same people.
+//!
+//! Name `i` of [`NAMES`] is the key BIP-32 derives from [`SEED`] at
+//! `m/44'/60'/0'/0/i`, through [`ExtendedKey`], with the public key and
+//! Ethereum address that follow from it. `test-keys.json`, beside the
+//! scenario files, lists them for other implementations; the `scenarios`
+//! binary writes it and a test keeps it in step with this module.
+//!
+//! Anyone can derive these keys, so they must never hold funds. Like
+//! `scenarios` the module needs the `test-utils` feature.
+
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::ethereum::{address, checksum_address};
+use crate::scenarios::{path_string, prefixed, ExtendedKey, ETHEREUM_ACCOUNT};
+use crate::secp256k1::{AffinePoint, Scalar};
+use crate::vectors::SCHEMA_VERSION;
+
+/// The BIP-32 seed every test key comes from.
+pub const SEED: &[u8] = b"mojo-secp256k1 test keys seed";
+
+/// The identities, in derivation order.
+pub const NAMES: [&str; 6] = ["alice", "bob", "carol", "dave", "eve", "mallory"];
+
+/// The file the keys are written to, in the scenario directory.
+pub const FILE_NAME: &str = "test-keys.json";
+
+/// One named identity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TestKey {
+    pub name: &'static str,
+    /// `m/44'/60'/0'/0/i`.
+    pub path: [u32; 5],
+    pub key: Scalar,
+    pub public_key: AffinePoint,
+    pub address: [u8; 20],
+}
+
+/// Every identity, in the order of [`NAMES`], derived on first use.
+pub fn all() -> &'static [TestKey] {
+    static KEYS: OnceLock<Vec<TestKey>> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let master = ExtendedKey::master(SEED).expect("a valid master key");
+        (0u32..)
+            .zip(NAMES)
+            .map(|(index, name)| {
+                let mut path = [0; 5];
+                path[..4].copy_from_slice(&ETHEREUM_ACCOUNT);
+                path[4] = index;
+                let derived = master.derive(&path).expect("a valid child key");
+                let public_key = derived.public_key();
+                TestKey {
+                    name,
+                    path,
+                    key: derived.key,
+                    public_key,
+                    address: address(&public_key.to_uncompressed()).expect("65 bytes"),
+                }
+            })
+            .collect()
+    })
+}
+
+/// The identity called `name`, if it is one of [`NAMES`].
+pub fn get(name: &str) -> Option<&'static TestKey> {
+    all().iter().find(|key| key.name == name)
+}
+
+/// The keys as [`FILE_NAME`] holds them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TestKeyFile {
+    pub schema_version: u64,
+    pub seed: String,
+    pub keys: Vec<TestKeyEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TestKeyEntry {
+    pub name: String,
+    pub path: String,
+    pub private_key: String,
+    /// Uncompressed, `0x04`-prefixed.
+    pub public_key: String,
+    /// EIP-55 cased.
+    pub address: String,
+}
+
+/// The file: pretty-printed, with a final newline.
+pub fn to_json() -> String {
+    let file = TestKeyFile {
+        schema_version: SCHEMA_VERSION,
+        seed: prefixed(SEED),
+        keys: all()
+            .iter()
+            .map(|key| TestKeyEntry {
+                name: key.name.to_string(),
+                path: path_string(&key.path),
+                private_key: prefixed(&key.key.to_bytes()),
+                public_key: prefixed(&key.public_key.to_uncompressed()),
+                address: checksum_address(&key.address),
+            })
+            .collect(),
+    };
+    let mut text = serde_json::to_string_pretty(&file).expect("test keys serialize");
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenarios::{sign, SCENARIO_DIR};
+    use crate::secp256k1::G;
+
+    /// Names are found, keys match their public halves, and the first
+    /// two match an independent Python BIP-32 derivation.
+    #[test]
+    #[cfg_attr(miri, ignore = "several point multiplications")]
+    fn keys_are_derived_from_the_seed() {
+        assert_eq!(all().len(), NAMES.len());
+        let alice = get("alice").unwrap();
+        assert_eq!(path_string(&alice.path), "m/44'/60'/0'/0/0");
+        assert_eq!(get("mallory").unwrap().path[4], 5);
+        assert_eq!(get("trent"), None);
+        for key in all() {
+            assert_eq!(G.mul(&key.key), Some(key.public_key));
+        }
+        assert_eq!(
+            hex::encode(alice.key.to_bytes()),
+            "5d9bc4aa2cff46f50aa6234c3cef82a5b6d997befecd7de8aabde717e565b417"
+        );
+        assert_eq!(
+            checksum_address(&get("bob").unwrap().address),
+            "0xAF12B3d3764D192294E7347661dDBC1D06307f83"
+        );
+        let hash = [7; 32];
+        let (signature, odd) = sign(&alice.key, &hash);
+        assert_eq!(signature.recover(&hash, odd), Ok(alice.public_key));
+    }
+
+    /// The committed file is what this module writes.
+    #[test]
+    #[cfg_attr(miri, ignore = "reads files and derives keys")]
+    fn committed_file_is_current() {
+        let path = std::path::Path::new(SCENARIO_DIR).join(FILE_NAME);
+        let committed = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            committed == to_json(),
+            "{} is stale; rerun the scenarios binary",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,43 @@
+//! Structured tracing of benchmark runs (`tracing` feature).
+//!
+//! The harness opens a span per backend run, per warmup and per trial, and
+//! emits events for outlier rounds, throttling, noisy runs and drift next
+//! to its stderr warnings. Binaries call [`init`], which installs a
+//! subscriber writing to stderr and filtered by `RUST_LOG` (e.g.
+//! `RUST_LOG=keccak256_rust_baseline=debug`); nothing is recorded when it is
+//! unset. Without the feature the macros below expand to nothing and
+//! [`init`] does nothing.
+
+/// `tracing::$level!` with the feature; nothing without it.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+/// Enter a `tracing::$level_span!` until the end of the enclosing block.
+macro_rules! span {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::span!(tracing::Level::$level, $($arg)+).entered();
+    };
+}
+
+pub(crate) use {event, span};
+
+/// Install the `RUST_LOG`-filtered stderr subscriber. Safe to call more
+/// than once; later calls keep the first subscriber.
+pub fn init() {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing_subscriber::EnvFilter;
+
+        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("off"));
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr)
+            .with_ansi(std::io::IsTerminal::is_terminal(&std::io::stderr()))
+            .try_init();
+    }
+}
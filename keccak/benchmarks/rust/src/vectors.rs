@@ -0,0 +1,173 @@
+//! Loader for the shared JSON test vectors in `keccak/vectors/`.
+//!
+//! The Mojo and Rust suites read the same files, so the two
+//! implementations are always tested against the same answers. The format
+//! is described in `vectors/README.md`: each file holds a
+//! `schema_version`, a `description`, and a list of vectors, each with a
+//! `name`, an `input`, the `expected` hex output per algorithm, and free-form
+//! `metadata`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::digest::HashAlgorithm;
+
+/// The schema version this loader reads.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// The shared vector directory, relative to this crate.
+pub const VECTOR_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../vectors");
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VectorFile {
+    pub schema_version: u64,
+    pub description: String,
+    pub vectors: Vec<Vector>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Vector {
+    pub name: String,
+    pub input: Input,
+    /// Expected output as lowercase hex, keyed by algorithm name.
+    pub expected: BTreeMap<String, String>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, Value>,
+}
+
+/// How a vector's message is spelled.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "encoding", rename_all = "kebab-case")]
+pub enum Input {
+    /// The UTF-8 bytes of `value`, repeated `repeat` times.
+    Utf8 {
+        value: String,
+        #[serde(default = "one")]
+        repeat: usize,
+    },
+    /// The bytes `value` spells in hex, repeated `repeat` times.
+    Hex {
+        value: String,
+        #[serde(default = "one")]
+        repeat: usize,
+    },
+    /// `length` bytes counting up from zero, wrapping at 256.
+    Counter { length: usize },
+}
+
+fn one() -> usize {
+    1
+}
+
+impl Input {
+    pub fn bytes(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Input::Utf8 { value, repeat } => Ok(value.as_bytes().repeat(*repeat)),
+            Input::Hex { value, repeat } => hex::decode(value)
+                .map(|bytes| bytes.repeat(*repeat))
+                .map_err(|err| format!("invalid hex input: {}", err)),
+            Input::Counter { length } => Ok((0..*length).map(|i| i as u8).collect()),
+        }
+    }
+}
+
+/// `message` under `algorithm`, producing `length` bytes for the
+/// extendable-output functions, or `None` for an unknown algorithm.
+pub fn digest(algorithm: &str, message: &[u8], length: usize) -> Option<Vec<u8>> {
+    HashAlgorithm::from_name(algorithm, length)
+        .map(|algorithm| crate::digest::hash(algorithm, message).into_vec())
+}
+
+impl VectorFile {
+    pub fn load(path: &Path) -> Result<VectorFile, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let file: VectorFile =
+            serde_json::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        if file.schema_version != SCHEMA_VERSION {
+            return Err(format!(
+                "{}: schema_version {} is not {}",
+                path.display(),
+                file.schema_version,
+                SCHEMA_VERSION
+            ));
+        }
+        Ok(file)
+    }
+
+    /// Every `*.json` file in `dir`, by file name.
+    pub fn load_dir(dir: &Path) -> Result<Vec<(PathBuf, VectorFile)>, String> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .map_err(|err| format!("cannot read {}: {}", dir.display(), err))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| VectorFile::load(&path).map(|file| (path, file)))
+            .collect()
+    }
+
+    /// One line per vector and algorithm that did not produce its expected
+    /// output, including algorithms this crate does not implement.
+    pub fn failures(&self) -> Vec<String> {
+        let mut failures = Vec::new();
+        for vector in &self.vectors {
+            let message = match vector.input.bytes() {
+                Ok(message) => message,
+                Err(err) => {
+                    failures.push(format!("{}: {}", vector.name, err));
+                    continue;
+                }
+            };
+            for (algorithm, expected) in &vector.expected {
+                match digest(algorithm, &message, expected.len() / 2) {
+                    None => failures.push(format!(
+                        "{}: unknown algorithm {:?}",
+                        vector.name, algorithm
+                    )),
+                    Some(got) if hex::encode(&got) != *expected => failures.push(format!(
+                        "{}/{}: expected {}, got {}",
+                        vector.name,
+                        algorithm,
+                        expected,
+                        hex::encode(&got)
+                    )),
+                    Some(_) => {}
+                }
+            }
+        }
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every shared vector file loads and passes.
+    #[test]
+    fn shared_vectors_pass() {
+        let files = VectorFile::load_dir(Path::new(VECTOR_DIR)).unwrap();
+        assert!(!files.is_empty(), "no vector files in {}", VECTOR_DIR);
+        for (path, file) in files {
+            assert!(
+                !file.vectors.is_empty(),
+                "{} has no vectors",
+                path.display()
+            );
+            let failures = file.failures();
+            assert!(
+                failures.is_empty(),
+                "{}:\n{}",
+                path.display(),
+                failures.join("\n")
+            );
+        }
+    }
+}
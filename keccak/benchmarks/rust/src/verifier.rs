@@ -0,0 +1,386 @@
+//! Signature verification with optional caches, for RPC-style servers
+//! that see the same payloads again and again.
+//!
+//! A [`VerifyContext`] runs the ECDSA and BIP-340 checks of
+//! [`crate::signature`] and [`crate::schnorr`], with two least-recently
+//! used caches in front. The result cache maps a `(public key, message or
+//! hash, signature)` triple, stored as the Keccak-256 of its framed
+//! bytes, to the answer, so a repeat costs a hash and a lookup. The key
+//! cache keeps parsed public keys by their encoding, which saves the
+//! square root behind every compressed or x-only key. Either can be
+//! turned off with a capacity of 0, and [`VerifyContext::stats`] counts
+//! the hits and misses of both.
+//!
+//! [`VerifyContext::verify_schnorr_batch`] answers what it can from the
+//! result cache and batches only the rest, so a batch that repeats
+//! earlier signatures costs less. A passing batch caches every member as
+//! valid. A failing one caches nothing, as a batch failure does not say
+//! which signature was bad.
+//!
+//! The caches sit behind mutexes, so one context can be shared between
+//! worker threads; the curve arithmetic runs outside the locks.
+
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use crate::keccak256;
+use crate::schnorr::{self, SchnorrInput};
+use crate::secp256k1::AffinePoint;
+use crate::signature::Signature;
+
+/// A least-recently used map with hit and miss counts.
+struct Lru<K, V> {
+    capacity: usize,
+    /// Bumped on every use, so the smallest tick is the oldest entry.
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    order: BTreeMap<u64, K>,
+    hits: u64,
+    misses: u64,
+}
+
+impl<K: Clone + Eq + Hash, V: Copy> Lru<K, V> {
+    fn new(capacity: usize) -> Lru<K, V> {
+        Lru {
+            capacity,
+            tick: 0,
+            entries: HashMap::with_capacity(capacity),
+            order: BTreeMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let Some((value, tick)) = self.entries.get_mut(key) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        self.tick += 1;
+        let key = self.order.remove(tick).expect("every entry has a tick");
+        *tick = self.tick;
+        self.order.insert(self.tick, key);
+        Some(*value)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((old_value, tick)) = self.entries.get_mut(&key) {
+            *old_value = value;
+            let key = self.order.remove(tick).expect("every entry has a tick");
+            *tick = self.tick;
+            self.order.insert(self.tick, key);
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            let (_, oldest) = self.order.pop_first().expect("a full cache has entries");
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert(key.clone(), (value, self.tick));
+        self.order.insert(self.tick, key);
+    }
+}
+
+/// Hit and miss counts since a [`VerifyContext`] was made. A disabled
+/// cache counts nothing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub result_hits: u64,
+    pub result_misses: u64,
+    pub key_hits: u64,
+    pub key_misses: u64,
+}
+
+/// Verification through optional result and public-key caches.
+pub struct VerifyContext {
+    results: Option<Mutex<Lru<[u8; 32], bool>>>,
+    keys: Option<Mutex<Lru<Vec<u8>, AffinePoint>>>,
+}
+
+/// The result cache entry for one check: the Keccak-256 of the scheme
+/// and each field with its length, so no two triples share an entry.
+fn entry(scheme: &[u8], public_key: &[u8], message: &[u8], signature: &[u8]) -> [u8; 32] {
+    let mut framed = Vec::with_capacity(40 + public_key.len() + message.len() + signature.len());
+    for field in [scheme, public_key, message, signature] {
+        framed.extend_from_slice(&(field.len() as u64).to_be_bytes());
+        framed.extend_from_slice(field);
+    }
+    keccak256(&framed)
+}
+
+impl VerifyContext {
+    /// A context remembering up to `results` answers and `keys` parsed
+    /// public keys; 0 turns that cache off.
+    pub fn new(results: usize, keys: usize) -> VerifyContext {
+        VerifyContext {
+            results: (results > 0).then(|| Mutex::new(Lru::new(results))),
+            keys: (keys > 0).then(|| Mutex::new(Lru::new(keys))),
+        }
+    }
+
+    /// A context with both caches off, which verifies like the free
+    /// functions.
+    pub fn uncached() -> VerifyContext {
+        VerifyContext::new(0, 0)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats::default();
+        if let Some(results) = &self.results {
+            let results = results.lock().expect("result cache lock");
+            (stats.result_hits, stats.result_misses) = (results.hits, results.misses);
+        }
+        if let Some(keys) = &self.keys {
+            let keys = keys.lock().expect("key cache lock");
+            (stats.key_hits, stats.key_misses) = (keys.hits, keys.misses);
+        }
+        stats
+    }
+
+    fn cached(&self, entry: &[u8; 32]) -> Option<bool> {
+        let results = self.results.as_ref()?;
+        results.lock().expect("result cache lock").get(entry)
+    }
+
+    fn remember(&self, entry: [u8; 32], valid: bool) {
+        if let Some(results) = &self.results {
+            results
+                .lock()
+                .expect("result cache lock")
+                .insert(entry, valid);
+        }
+    }
+
+    /// `encoded` parsed by `parse`, through the key cache. Encodings that
+    /// do not parse are not cached.
+    fn key(
+        &self,
+        encoded: &[u8],
+        parse: impl FnOnce() -> Option<AffinePoint>,
+    ) -> Option<AffinePoint> {
+        let Some(keys) = &self.keys else {
+            return parse();
+        };
+        if let Some(key) = keys.lock().expect("key cache lock").get(encoded) {
+            return Some(key);
+        }
+        let key = parse()?;
+        keys.lock()
+            .expect("key cache lock")
+            .insert(encoded.to_vec(), key);
+        Some(key)
+    }
+
+    /// [`Signature::verify`] for a SEC1-encoded `public_key`; a key that
+    /// does not parse fails.
+    pub fn verify_ecdsa(&self, public_key: &[u8], hash: &[u8; 32], signature: &Signature) -> bool {
+        let entry = entry(b"ecdsa", public_key, hash, &signature.to_compact());
+        if let Some(valid) = self.cached(&entry) {
+            return valid;
+        }
+        let valid = self
+            .key(public_key, || AffinePoint::from_sec1(public_key).ok())
+            .is_some_and(|key| signature.verify(hash, &key));
+        self.remember(entry, valid);
+        valid
+    }
+
+    /// [`schnorr::verify_schnorr`] through the caches.
+    pub fn verify_schnorr(
+        &self,
+        public_key: &[u8; 32],
+        message: &[u8],
+        signature: &[u8; 64],
+    ) -> bool {
+        let entry = entry(b"bip340", public_key, message, signature);
+        if let Some(valid) = self.cached(&entry) {
+            return valid;
+        }
+        let input = SchnorrInput {
+            public_key,
+            message,
+            signature,
+        };
+        let valid = self
+            .key(public_key, || schnorr::lift_even(public_key))
+            .is_some_and(|key| schnorr::verify_lifted(&input, key));
+        self.remember(entry, valid);
+        valid
+    }
+
+    /// [`schnorr::verify_schnorr_batch`] through the caches: cached
+    /// answers are used, and only the other signatures are batched. It
+    /// fails as a whole, with the same semantics.
+    pub fn verify_schnorr_batch(&self, inputs: &[SchnorrInput]) -> bool {
+        let mut pending = Vec::new();
+        let mut keys = Vec::new();
+        let mut entries = Vec::new();
+        for input in inputs {
+            let entry = entry(b"bip340", input.public_key, input.message, input.signature);
+            match self.cached(&entry) {
+                Some(true) => continue,
+                Some(false) => return false,
+                None => {}
+            }
+            let Some(key) = self.key(input.public_key, || schnorr::lift_even(input.public_key))
+            else {
+                self.remember(entry, false);
+                return false;
+            };
+            pending.push(*input);
+            keys.push(key);
+            entries.push(entry);
+        }
+        let valid = schnorr::verify_batch_lifted(&pending, &keys);
+        if valid {
+            for entry in entries {
+                self.remember(entry, true);
+            }
+        }
+        valid
+    }
+}
+
+impl Default for VerifyContext {
+    /// 4096 answers and 1024 keys, around 200 KiB when full.
+    fn default() -> VerifyContext {
+        VerifyContext::new(4096, 1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::{Scalar, G};
+
+    /// A signature from the `single-signer` scenario and its signer.
+    fn ecdsa_fixture() -> ([u8; 32], Signature, AffinePoint) {
+        let hash: [u8; 32] =
+            hex::decode("1e65933ff0f459c1bc85e75b2501a6a6ea48508b044871f4c61302b4fe858141")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let signature = Signature::from_compact(
+            &hex::decode(concat!(
+                "192d4e05ce9dda59fde77b1a858fa69f53d8fac34b9de9f16e037a0e9a58267c",
+                "5f15d4c063799c07840960d9c5e19f2ddaf9510f06795876af3b1b8225d58c94"
+            ))
+            .unwrap()
+            .try_into()
+            .unwrap(),
+        )
+        .unwrap();
+        let signer = signature.recover(&hash, true).unwrap();
+        (hash, signature, signer)
+    }
+
+    /// BIP-340 test vector 1.
+    const SCHNORR: (&str, &str, &str) = (
+        "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+        "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+        "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de3341\
+         8906d11ac976abccb20b091292bff4ea897efcb639ea871cfa95f6de339e4b0a",
+    );
+
+    fn schnorr_fixture() -> ([u8; 32], Vec<u8>, [u8; 64]) {
+        let (key, message, signature) = SCHNORR;
+        (
+            hex::decode(key).unwrap().try_into().unwrap(),
+            hex::decode(message).unwrap(),
+            hex::decode(signature).unwrap().try_into().unwrap(),
+        )
+    }
+
+    /// The least recently used entry goes first, and a lookup counts as
+    /// a use.
+    #[test]
+    fn lru_evicts_the_oldest() {
+        let mut lru = Lru::new(2);
+        lru.insert(1, 'a');
+        lru.insert(2, 'b');
+        assert_eq!(lru.get(&1), Some('a'));
+        lru.insert(3, 'c');
+        assert_eq!(
+            (lru.get(&2), lru.get(&1), lru.get(&3)),
+            (None, Some('a'), Some('c'))
+        );
+        lru.insert(1, 'd');
+        lru.insert(4, 'e');
+        assert_eq!((lru.get(&3), lru.get(&1)), (None, Some('d')));
+        assert_eq!((lru.hits, lru.misses), (4, 2));
+        let mut off = Lru::new(0);
+        off.insert(1, 'a');
+        assert_eq!(off.get(&1), None);
+    }
+
+    /// Cached answers match the uncached ones, repeats hit both caches,
+    /// and a compressed key shares the answer of no other encoding.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn ecdsa_answers_are_cached() {
+        let (hash, signature, signer) = ecdsa_fixture();
+        let context = VerifyContext::default();
+        let compressed = signer.to_compressed();
+        for _ in 0..2 {
+            assert!(context.verify_ecdsa(&compressed, &hash, &signature));
+        }
+        assert!(!context.verify_ecdsa(&compressed, &[0; 32], &signature));
+        assert!(context.verify_ecdsa(&signer.to_uncompressed(), &hash, &signature));
+        assert!(!context.verify_ecdsa(&[0x02; 33], &hash, &signature));
+        assert_eq!(
+            context.stats(),
+            CacheStats {
+                result_hits: 1,
+                result_misses: 4,
+                key_hits: 1,
+                key_misses: 3,
+            }
+        );
+        let uncached = VerifyContext::uncached();
+        assert!(uncached.verify_ecdsa(&compressed, &hash, &signature));
+        assert_eq!(uncached.stats(), CacheStats::default());
+    }
+
+    /// Batches skip signatures verified before, cache their members when
+    /// they pass, and fail on a signature cached as invalid.
+    #[test]
+    #[cfg_attr(miri, ignore = "secp256k1 point multiplications")]
+    fn batches_use_and_fill_the_cache() {
+        let (key, message, signature) = schnorr_fixture();
+        let mut forged = signature;
+        forged[63] ^= 1;
+        let other_key = G.mul(&Scalar::ONE.add(&Scalar::ONE)).unwrap().x.to_bytes();
+        let input = |public_key, signature| SchnorrInput {
+            public_key,
+            message: &message,
+            signature,
+        };
+        let context = VerifyContext::default();
+        assert!(context.verify_schnorr(&key, &message, &signature));
+        assert!(context.verify_schnorr_batch(&[input(&key, &signature)]));
+        assert_eq!(context.stats().result_hits, 1);
+
+        assert!(!context.verify_schnorr(&key, &message, &forged));
+        assert!(!context.verify_schnorr_batch(&[input(&key, &signature), input(&key, &forged)]));
+        assert_eq!(context.stats().result_hits, 3);
+
+        // A failed batch caches nothing, so each member is checked again.
+        assert!(!context.verify_schnorr_batch(&[input(&other_key, &signature)]));
+        let before = context.stats();
+        assert!(!context.verify_schnorr(&other_key, &message, &signature));
+        assert_eq!(context.stats().result_misses, before.result_misses + 1);
+        assert!(!context.verify_schnorr(&other_key, &message, &signature));
+        assert_eq!(context.stats().result_hits, before.result_hits + 1);
+        assert!(context.verify_schnorr_batch(&[]));
+    }
+}
@@ -0,0 +1,32 @@
+//! JavaScript bindings (`wasm` feature), so the browser signer can be
+//! checked against the same baseline as the native and Mojo builds.
+//!
+//! `wasm-pack build --target web -- --features wasm` in this directory
+//! produces the package. Byte arguments and results are `Uint8Array`s.
+
+use wasm_bindgen::prelude::*;
+
+/// The Keccak-256 digest of `data`.
+#[wasm_bindgen]
+pub fn keccak256(data: &[u8]) -> Vec<u8> {
+    crate::keccak256(data).to_vec()
+}
+
+/// The Keccak-256 digest of `data` as lowercase hex.
+#[wasm_bindgen(js_name = keccak256Hex)]
+pub fn keccak256_hex(data: &[u8]) -> String {
+    crate::to_hex_string(&crate::keccak256(data))
+}
+
+/// `bytes` as lowercase hex.
+#[wasm_bindgen(js_name = toHex)]
+pub fn to_hex(bytes: &[u8]) -> String {
+    crate::to_hex_string(bytes)
+}
+
+/// The bytes `text` encodes, with an optional `0x` prefix. Throws on
+/// malformed hex.
+#[wasm_bindgen(js_name = fromHex)]
+pub fn from_hex(text: &str) -> Result<Vec<u8>, JsError> {
+    crate::from_hex(text).map_err(|err| JsError::new(&err.to_string()))
+}
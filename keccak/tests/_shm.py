@@ -0,0 +1,89 @@
+"""The writer's side of the shared-memory protocol of `bench shm`.
+
+The layout and state machine are documented in
+benchmarks/rust/src/shm.rs. A Session creates the region, starts the Rust
+server on it, and exchanges batches of messages for their digests.
+"""
+
+import os
+import struct
+import subprocess
+import time
+from multiprocessing import shared_memory
+
+MAGIC = b"KSHM"
+VERSION = 1
+HEADER_LEN = 64
+IDLE, REQUEST, DONE, FAILED, SHUTDOWN = range(5)
+
+DEFAULT_BENCH = os.path.join(
+    os.path.dirname(os.path.abspath(__file__)),
+    os.pardir,
+    "benchmarks",
+    "rust",
+    "target",
+    "release",
+    "bench",
+)
+
+
+class Session:
+    def __init__(self, size=1 << 20, bench=None, backend="native"):
+        self.shm = shared_memory.SharedMemory(create=True, size=size)
+        self.buf = self.shm.buf
+        self.buf[0:8] = MAGIC + struct.pack("<I", VERSION)
+        self.buf[8:HEADER_LEN] = bytes(HEADER_LEN - 8)
+        bench = bench or os.environ.get("KECCAK_BENCH", DEFAULT_BENCH)
+        self.server = subprocess.Popen(
+            [bench, "shm", "--name", self.shm.name, "--backend", backend]
+        )
+
+    def _state(self):
+        return struct.unpack_from("<I", self.buf, 8)[0]
+
+    def digests(self, messages):
+        """The server's 32-byte digest of each message, as bytes."""
+        count = len(messages)
+        data_len = sum(len(m) for m in messages)
+        data = HEADER_LEN + 8 * count
+        end = data + data_len + 32 * count
+        if end > len(self.buf):
+            raise ValueError("batch does not fit in the region")
+        struct.pack_into("<%dQ" % count, self.buf, HEADER_LEN, *(len(m) for m in messages))
+        offset = data
+        for message in messages:
+            self.buf[offset : offset + len(message)] = message
+            offset += len(message)
+        struct.pack_into("<IIQ", self.buf, 8, IDLE, count, data_len)
+        struct.pack_into("<I", self.buf, 8, REQUEST)
+        while True:
+            state = self._state()
+            if state == DONE:
+                break
+            if state == FAILED:
+                code = struct.unpack_from("<I", self.buf, 24)[0]
+                raise RuntimeError("bench shm rejected the batch (error %d)" % code)
+            if self.server.poll() is not None:
+                raise RuntimeError("bench shm exited with %d" % self.server.returncode)
+            time.sleep(0)
+        start = data + data_len
+        return [bytes(self.buf[start + 32 * i : start + 32 * (i + 1)]) for i in range(count)]
+
+    def close(self):
+        struct.pack_into("<I", self.buf, 8, SHUTDOWN)
+        self.server.wait()
+        self.buf = None
+        self.shm.close()
+        self.shm.unlink()
+
+
+def counter_cases(max_len=300):
+    """[message hex, digest hex] for counter messages of every length up to
+    `max_len`, hashed by the Rust server in one batch."""
+    messages = [bytes(i % 256 for i in range(n)) for n in range(max_len + 1)]
+    session = Session()
+    try:
+        digests = session.digests(messages)
+    finally:
+        session.close()
+    return [[m.hex(), d.hex()] for m, d in zip(messages, digests)]
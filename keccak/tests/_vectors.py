@@ -0,0 +1,42 @@
+"""Flatten the shared JSON vectors in ../vectors/ for the Mojo tests.
+
+The format is described in vectors/README.md; Rust reads the same files
+through keccak256_rust_baseline::vectors.
+"""
+
+import glob
+import json
+import os
+
+VECTOR_DIR = os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "vectors")
+SCHEMA_VERSION = 1
+
+
+def message(spec):
+    """The bytes an `input` object describes."""
+    encoding = spec["encoding"]
+    if encoding == "utf8":
+        return spec["value"].encode("utf-8") * spec.get("repeat", 1)
+    if encoding == "hex":
+        return bytes.fromhex(spec["value"]) * spec.get("repeat", 1)
+    if encoding == "counter":
+        return bytes(i % 256 for i in range(spec["length"]))
+    raise ValueError("unknown input encoding: " + encoding)
+
+
+def cases(algorithm):
+    """[label, message hex, expected hex] for every vector with an answer
+    for `algorithm`, across all vector files."""
+    out = []
+    for path in sorted(glob.glob(os.path.join(VECTOR_DIR, "*.json"))):
+        with open(path) as handle:
+            data = json.load(handle)
+        if data["schema_version"] != SCHEMA_VERSION:
+            raise ValueError(path + ": unsupported schema_version")
+        name = os.path.basename(path)
+        for vector in data["vectors"]:
+            expected = vector["expected"].get(algorithm)
+            if expected is not None:
+                label = name + ":" + vector["name"]
+                out.append([label, message(vector["input"]).hex(), expected])
+    return out